@@ -0,0 +1,49 @@
+//! Renders the default scene without the editor and writes it to `headless.png`,
+//! run with `cargo run --example headless --no-default-features`
+
+use four_dimentions::{wgpu, Camera, RenderPath, Renderer, Scene};
+
+fn main() {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+    let adapter = pollster::block_on(instance.request_adapter(&Default::default()))
+        .expect("no GPU adapter is available");
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            limits: four_dimentions::device_limits(&adapter),
+            ..Default::default()
+        },
+        None,
+    ))
+    .unwrap();
+
+    let (width, height) = (640, 480);
+    let mut renderer = Renderer::new(&device, &queue, RenderPath::for_device(&device));
+    renderer.resize(&device, &queue, width, height);
+
+    let camera = Camera {
+        sample_count: 64,
+        ..Default::default()
+    };
+    renderer.upload_camera(&queue, &camera.to_gpu(0)).unwrap();
+    renderer
+        .upload_scene(&device, &queue, &Scene::default())
+        .unwrap();
+
+    let mut encoder = device.create_command_encoder(&Default::default());
+    renderer.render(&device, &mut encoder, camera.render_options());
+    queue.submit([encoder.finish()]);
+
+    let pixels = renderer.read_pixels(&device, &queue).unwrap();
+    let mut encoder = png::Encoder::new(
+        std::fs::File::create("headless.png").unwrap(),
+        width as _,
+        height as _,
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .unwrap()
+        .write_image_data(&pixels)
+        .unwrap();
+}
@@ -0,0 +1,106 @@
+use crate::{PrimitiveKind, RenderMode, EXAMPLES};
+use eframe::egui;
+
+/// Something the editor can do, shared by the menus, hotkeys and the command palette
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    OpenCommandPalette,
+    #[cfg(not(target_arch = "wasm32"))]
+    CopyImage,
+    AddObject(PrimitiveKind),
+    AddMaterial,
+    /// An index into [`EXAMPLES`]
+    LoadExample(usize),
+    RunScript,
+    #[cfg(not(target_arch = "wasm32"))]
+    SaveScene,
+    SetRenderMode(RenderMode),
+    ToggleFlyMode,
+    ToggleMinimap,
+}
+
+impl Action {
+    /// Every action, in the order the command palette lists them before anything is typed
+    pub fn all() -> Vec<Action> {
+        let mut actions = vec![Action::OpenCommandPalette];
+        actions.extend(PrimitiveKind::ALL.iter().copied().map(Action::AddObject));
+        actions.push(Action::AddMaterial);
+        actions.extend((0..EXAMPLES.len()).map(Action::LoadExample));
+        actions.push(Action::RunScript);
+        #[cfg(not(target_arch = "wasm32"))]
+        actions.extend([Action::SaveScene, Action::CopyImage]);
+        actions.extend(RenderMode::ALL.map(Action::SetRenderMode));
+        actions.extend([Action::ToggleFlyMode, Action::ToggleMinimap]);
+        actions
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Action::OpenCommandPalette => "Open Command Palette".into(),
+            Action::AddObject(kind) => format!("Add {}", kind.name()),
+            Action::AddMaterial => "Add Material".into(),
+            Action::LoadExample(i) => format!("Load Example: {}", EXAMPLES[i].name),
+            Action::RunScript => "Run Script".into(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Action::SaveScene => "Save Scene".into(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Action::CopyImage => "Copy Image to Clipboard".into(),
+            Action::SetRenderMode(render_mode) => {
+                format!("Render Mode: {}", render_mode.name())
+            }
+            Action::ToggleFlyMode => "Toggle Fly Mode".into(),
+            Action::ToggleMinimap => "Toggle Minimap".into(),
+        }
+    }
+
+    pub fn shortcut(self) -> Option<egui::KeyboardShortcut> {
+        let shortcut = |modifiers, key| Some(egui::KeyboardShortcut::new(modifiers, key));
+        match self {
+            Action::OpenCommandPalette => shortcut(egui::Modifiers::COMMAND, egui::Key::P),
+            Action::RunScript => shortcut(egui::Modifiers::COMMAND, egui::Key::Enter),
+            #[cfg(not(target_arch = "wasm32"))]
+            Action::SaveScene => shortcut(egui::Modifiers::COMMAND, egui::Key::S),
+            #[cfg(not(target_arch = "wasm32"))]
+            Action::CopyImage => shortcut(
+                egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                egui::Key::C,
+            ),
+            Action::ToggleFlyMode => shortcut(egui::Modifiers::NONE, egui::Key::C),
+            Action::ToggleMinimap => shortcut(egui::Modifiers::NONE, egui::Key::M),
+            _ => None,
+        }
+    }
+}
+
+/// How well `query` matches `text` when its characters appear in order but not necessarily
+/// next to each other, ignoring case, higher is better and `None` is no match.
+/// Consecutive characters and characters at the start of words score more
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let mut score = 0;
+    let mut text_chars = text.chars().flat_map(char::to_lowercase);
+    let mut previous = None;
+    let mut consecutive = false;
+    for query_char in query.chars().flat_map(char::to_lowercase) {
+        if query_char.is_whitespace() {
+            continue;
+        }
+        loop {
+            let c = text_chars.next()?;
+            let word_start = previous.is_none_or(|p: char| !p.is_alphanumeric());
+            previous = Some(c);
+            if c == query_char {
+                score += 1;
+                if consecutive {
+                    score += 2;
+                }
+                if word_start {
+                    score += 3;
+                }
+                consecutive = true;
+                break;
+            }
+            consecutive = false;
+        }
+    }
+    Some(score)
+}
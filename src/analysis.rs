@@ -0,0 +1,358 @@
+use crate::{ColorManagement, GpuAnalysisSettings, GpuError, RenderPath};
+use encase::{ShaderType, UniformBuffer};
+use std::sync::mpsc;
+
+/// How many pixels of the last analysed image fall in each luma bin
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Histogram {
+    /// [`Histogram::BIN_COUNT`] bins from black to white, by the luma of the stored values
+    pub bins: Vec<u32>,
+    /// Pixels with a channel at 1, which could have been brighter
+    pub clipped: u32,
+    /// Pixels that are completely black
+    pub crushed: u32,
+}
+
+impl Histogram {
+    pub const BIN_COUNT: usize = 64;
+
+    pub fn pixel_count(&self) -> u32 {
+        self.bins.iter().sum()
+    }
+}
+
+/// Helps with setting exposure and emission, from a ray tracer's (or [`crate::PostProcessor`]'s)
+/// output it computes a [`Histogram`] that is read back without stalling, and a false color
+/// view of which parts are clipped, crushed or around middle grey. Needs compute shaders
+pub struct ExposureAnalysis {
+    texture_width: usize,
+    texture_height: usize,
+    false_color_texture: wgpu::Texture,
+    false_color_texture_view: wgpu::TextureView,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    counts_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    settings_uniform_buffer: wgpu::Buffer,
+    histogram_pipeline: wgpu::ComputePipeline,
+    false_color_pipeline: wgpu::ComputePipeline,
+    /// Set while `readback_buffer` is being mapped
+    pending_readback: Option<mpsc::Receiver<Result<(), wgpu::BufferAsyncError>>>,
+    histogram: Option<Histogram>,
+}
+
+impl ExposureAnalysis {
+    /// the bins, then the clipped and crushed counts
+    const COUNTS_SIZE: u64 = (Histogram::BIN_COUNT as u64 + 2) * std::mem::size_of::<u32>() as u64;
+
+    pub fn is_supported(device: &wgpu::Device) -> bool {
+        RenderPath::for_device(device) == RenderPath::Compute
+    }
+
+    /// `input` is an `Rgba8Unorm` texture of `width` by `height` pixels
+    pub fn new(
+        device: &wgpu::Device,
+        input: &wgpu::TextureView,
+        width: usize,
+        height: usize,
+    ) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Exposure Analysis Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(Self::COUNTS_SIZE),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::Rgba8Unorm,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: Some(GpuAnalysisSettings::min_size()),
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Exposure Analysis Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Exposure Analysis Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("./shaders/analysis.wgsl").into()),
+        });
+        let create_pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("Exposure Analysis Pipeline"),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point,
+            })
+        };
+        let histogram_pipeline = create_pipeline("histogram");
+        let false_color_pipeline = create_pipeline("false_color");
+
+        let counts_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Histogram Storage Buffer"),
+            size: Self::COUNTS_SIZE,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Histogram Readback Buffer"),
+            size: Self::COUNTS_SIZE,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let settings_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Exposure Analysis Uniform Buffer"),
+            size: GpuAnalysisSettings::min_size().get(),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+            mapped_at_creation: false,
+        });
+
+        let false_color_texture = Self::create_texture(device, width, height);
+        let false_color_texture_view =
+            false_color_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = Self::create_bind_group(
+            device,
+            &bind_group_layout,
+            input,
+            &counts_buffer,
+            &false_color_texture_view,
+            &settings_uniform_buffer,
+        );
+        Self {
+            texture_width: width,
+            texture_height: height,
+            false_color_texture,
+            false_color_texture_view,
+            bind_group_layout,
+            bind_group,
+            counts_buffer,
+            readback_buffer,
+            settings_uniform_buffer,
+            histogram_pipeline,
+            false_color_pipeline,
+            pending_readback: None,
+            histogram: None,
+        }
+    }
+
+    fn create_texture(device: &wgpu::Device, width: usize, height: usize) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("False Color Texture"),
+            size: wgpu::Extent3d {
+                width: width as _,
+                height: height as _,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: RenderPath::Compute.texture_usages(),
+            view_formats: &[],
+        })
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        input: &wgpu::TextureView,
+        counts_buffer: &wgpu::Buffer,
+        false_color_texture_view: &wgpu::TextureView,
+        settings_uniform_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Exposure Analysis Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(input),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: counts_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(false_color_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: settings_uniform_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    /// Analyses `input` from now on, call it whenever the analysed texture is recreated.
+    /// The false color texture is only recreated if the size changed, returns whether it was
+    pub fn set_input(
+        &mut self,
+        device: &wgpu::Device,
+        input: &wgpu::TextureView,
+        width: usize,
+        height: usize,
+    ) -> bool {
+        let resized = (width, height) != (self.texture_width, self.texture_height);
+        if resized {
+            (self.texture_width, self.texture_height) = (width, height);
+            self.false_color_texture = Self::create_texture(device, width, height);
+            self.false_color_texture_view = self
+                .false_color_texture
+                .create_view(&wgpu::TextureViewDescriptor::default());
+        }
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            input,
+            &self.counts_buffer,
+            &self.false_color_texture_view,
+            &self.settings_uniform_buffer,
+        );
+        resized
+    }
+
+    fn dispatch(&self, encoder: &mut wgpu::CommandEncoder, pipeline: &wgpu::ComputePipeline) {
+        let workgroup_size = (16, 16);
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Exposure Analysis Pass"),
+        });
+        compute_pass.set_pipeline(pipeline);
+        compute_pass.set_bind_group(0, &self.bind_group, &[]);
+        compute_pass.dispatch_workgroups(
+            self.texture_width.div_ceil(workgroup_size.0) as _,
+            self.texture_height.div_ceil(workgroup_size.1) as _,
+            1,
+        );
+    }
+
+    /// Records counting the input into `encoder`, unless the previous histogram is still being
+    /// read back. Returns whether it recorded it, if so call [`ExposureAnalysis::start_readback`]
+    /// once `encoder` is submitted
+    pub fn record_histogram(&self, encoder: &mut wgpu::CommandEncoder) -> bool {
+        if self.pending_readback.is_some() {
+            return false;
+        }
+        encoder.clear_buffer(&self.counts_buffer, 0, None);
+        self.dispatch(encoder, &self.histogram_pipeline);
+        encoder.copy_buffer_to_buffer(
+            &self.counts_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            Self::COUNTS_SIZE,
+        );
+        true
+    }
+
+    /// Starts mapping the counts recorded by [`ExposureAnalysis::record_histogram`], they are
+    /// picked up by [`ExposureAnalysis::poll_readback`] once the device has been polled
+    pub fn start_readback(&mut self) {
+        let (sender, receiver) = mpsc::channel();
+        self.readback_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                _ = sender.send(result);
+            });
+        self.pending_readback = Some(receiver);
+    }
+
+    /// Updates [`ExposureAnalysis::histogram`] if the readback has finished, without waiting for it
+    pub fn poll_readback(&mut self) -> Result<(), GpuError> {
+        let Some(receiver) = &self.pending_readback else {
+            return Ok(());
+        };
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return Ok(()),
+            Err(mpsc::TryRecvError::Disconnected) => Err(wgpu::BufferAsyncError),
+        };
+        self.pending_readback = None;
+        result?;
+
+        let counts = {
+            let data = self.readback_buffer.slice(..).get_mapped_range();
+            data.chunks_exact(4)
+                .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+                .collect::<Vec<_>>()
+        };
+        self.readback_buffer.unmap();
+        self.histogram = Some(Histogram {
+            bins: counts[..Histogram::BIN_COUNT].to_vec(),
+            clipped: counts[Histogram::BIN_COUNT],
+            crushed: counts[Histogram::BIN_COUNT + 1],
+        });
+        Ok(())
+    }
+
+    /// The last histogram that was read back
+    pub fn histogram(&self) -> Option<&Histogram> {
+        self.histogram.as_ref()
+    }
+
+    /// Records drawing the false color view of the input into `encoder`, `color_management`
+    /// has to match how the input was encoded
+    pub fn record_false_color(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        color_management: ColorManagement,
+    ) -> Result<(), GpuError> {
+        let mut settings_buffer = UniformBuffer::new(vec![]);
+        settings_buffer.write(&GpuAnalysisSettings {
+            srgb: (color_management == ColorManagement::Srgb) as _,
+        })?;
+        queue.write_buffer(
+            &self.settings_uniform_buffer,
+            0,
+            &settings_buffer.into_inner(),
+        );
+        self.dispatch(encoder, &self.false_color_pipeline);
+        Ok(())
+    }
+
+    pub fn false_color_texture(&self) -> &wgpu::Texture {
+        &self.false_color_texture
+    }
+
+    /// The result of [`ExposureAnalysis::record_false_color`], it can be sampled once its
+    /// commands are submitted
+    pub fn false_color_texture_view(&self) -> &wgpu::TextureView {
+        &self.false_color_texture_view
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{BiVector4, Expression, ObjectRef, Rotor4, Scene};
+
+/// The source of an [`Expression`] of time as typed into the UI, empty to leave the value alone
+#[derive(Default)]
+pub struct AnimatedValue {
+    source: String,
+    compiled: Option<Result<Expression, String>>,
+}
+
+impl AnimatedValue {
+    pub fn new(source: impl Into<String>) -> Self {
+        let mut value = Self::default();
+        value.set_source(source);
+        value
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn set_source(&mut self, source: impl Into<String>) {
+        self.source = source.into();
+        self.compiled = (!self.source.trim().is_empty()).then(|| Expression::compile(&self.source));
+    }
+
+    /// The error from compiling the source, if it didn't compile
+    pub fn error(&self) -> Option<&str> {
+        match &self.compiled {
+            Some(Err(error)) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// `None` if the source is empty
+    pub fn evaluate(&self, t: f32) -> Result<Option<f32>, String> {
+        match &self.compiled {
+            None => Ok(None),
+            Some(Ok(expression)) => expression.evaluate(t).map(Some),
+            Some(Err(error)) => Err(error.clone()),
+        }
+    }
+}
+
+/// Moves one object of a [`Scene`] by expressions of the animation time `t`
+pub struct Animation {
+    pub object: ObjectRef,
+    /// The x, y, z and w of a hyper sphere's center or a hyper plane's point
+    pub position: [AnimatedValue; 4],
+    /// How far a hyper plane's normal is rotated away from `base_normal` in `plane`,
+    /// hyper spheres ignore it
+    pub angle: AnimatedValue,
+    pub plane: BiVector4,
+    pub base_normal: cgmath::Vector4<f32>,
+}
+
+impl Animation {
+    /// An animation that doesn't change anything yet, rotating from the object's current normal
+    pub fn new(scene: &Scene, object: ObjectRef) -> Self {
+        let base_normal = match object {
+            ObjectRef::HyperPlane(i) => scene.hyper_plane(i).map(|p| p.normal),
+            ObjectRef::HyperSphere(_) => None,
+        };
+        Self {
+            object,
+            position: Default::default(),
+            angle: AnimatedValue::default(),
+            plane: BiVector4::XY,
+            base_normal: base_normal.unwrap_or(cgmath::vec4(0.0, 1.0, 0.0, 0.0)),
+        }
+    }
+
+    /// Does nothing if the object doesn't exist anymore
+    pub fn apply(&self, scene: &mut Scene, t: f32) -> Result<(), String> {
+        let Some(mut position) = scene.object_position(self.object) else {
+            return Ok(());
+        };
+        let mut moved = false;
+        for (axis, value) in self.position.iter().enumerate() {
+            if let Some(value) = value.evaluate(t)? {
+                position[axis] = value;
+                moved = true;
+            }
+        }
+        let angle = self.angle.evaluate(t)?;
+
+        if !moved && angle.is_none() {
+            return Ok(());
+        }
+        let Some(object) = scene.object_mut(self.object) else {
+            return Ok(());
+        };
+        *object.position_mut() = position;
+        if let (Some(hyper_plane), Some(angle)) = (object.hyper_plane_mut(), angle) {
+            hyper_plane.normal =
+                Rotor4::from_angle_plane(angle, self.plane).rotate_vec(self.base_normal);
+        }
+        Ok(())
+    }
+}
+
+/// Plays the [`Animation`]s of a scene, they are applied before each upload while playing
+#[derive(Default)]
+pub struct Animator {
+    pub animations: Vec<Animation>,
+    /// The `t` the expressions see
+    pub time: f32,
+    pub playing: bool,
+}
+
+impl Animator {
+    pub fn animation(&self, object: ObjectRef) -> Option<&Animation> {
+        self.animations.iter().find(|a| a.object == object)
+    }
+
+    pub fn animation_mut(&mut self, object: ObjectRef) -> Option<&mut Animation> {
+        self.animations.iter_mut().find(|a| a.object == object)
+    }
+
+    pub fn remove_animation(&mut self, object: ObjectRef) {
+        self.animations.retain(|a| a.object != object);
+    }
+
+    /// Advances the time if playing, stopping at the first error
+    pub fn update(&mut self, scene: &mut Scene, ts: f32) -> Result<(), String> {
+        if !self.playing {
+            return Ok(());
+        }
+        self.time += ts;
+        self.apply(scene).inspect_err(|_| self.playing = false)
+    }
+
+    /// Puts every animated object where it is at the current time
+    pub fn apply(&self, scene: &mut Scene) -> Result<(), String> {
+        for animation in &self.animations {
+            animation.apply(scene, self.time).map_err(|error| {
+                let name = scene.object_name(animation.object).unwrap_or("?");
+                format!("Animation of '{name}': {error}")
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Keeps the animations pointing at the same objects after [`Scene::remove_objects`]
+    pub fn remove_objects(&mut self, removed: &[ObjectRef]) {
+        self.animations
+            .retain_mut(|animation| match animation.object.after_removal(removed) {
+                Some(object) => {
+                    animation.object = object;
+                    true
+                }
+                None => false,
+            });
+    }
+}
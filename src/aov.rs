@@ -0,0 +1,113 @@
+use crate::exr::{encode_exr, ExrChannel};
+
+/// The arbitrary output variables of a render, averaged like the colors are, from
+/// [`crate::Renderer::read_aovs`]. The albedo, normal and depths are of the first surface each
+/// sample hits, and the light is split into what reached the camera after at most one bounce
+/// and the rest
+#[derive(Clone, PartialEq, Debug)]
+pub struct Aovs {
+    pub width: usize,
+    pub height: usize,
+    /// [`Aovs::LAYER_COUNT`] layers of RGBA pixels, in the order the shader writes them
+    layers: Vec<[f32; 4]>,
+}
+
+impl Aovs {
+    /// How many RGBA32 layers the shader writes, the albedo and depth, the normal, the direct
+    /// light and w depth, then the indirect light
+    pub const LAYER_COUNT: usize = 4;
+
+    /// `layers` is [`Aovs::LAYER_COUNT`] tightly packed layers of `width` by `height` pixels
+    pub fn from_layers(width: usize, height: usize, layers: Vec<[f32; 4]>) -> Self {
+        assert_eq!(layers.len(), width * height * Self::LAYER_COUNT);
+        Self {
+            width,
+            height,
+            layers,
+        }
+    }
+
+    fn pixel(&self, layer: usize, x: usize, y: usize) -> [f32; 4] {
+        self.layers[(layer * self.height + y) * self.width + x]
+    }
+
+    /// The base color of the first hit, black where the sky was seen
+    pub fn albedo(&self, x: usize, y: usize) -> cgmath::Vector3<f32> {
+        let [r, g, b, _] = self.pixel(0, x, y);
+        cgmath::vec3(r, g, b)
+    }
+
+    /// Along the camera ray, the camera's max distance where the sky was seen
+    pub fn depth(&self, x: usize, y: usize) -> f32 {
+        self.pixel(0, x, y)[3]
+    }
+
+    /// Not normalized, the average of every sample's normal
+    pub fn normal(&self, x: usize, y: usize) -> cgmath::Vector4<f32> {
+        self.pixel(1, x, y).into()
+    }
+
+    pub fn direct(&self, x: usize, y: usize) -> cgmath::Vector3<f32> {
+        let [r, g, b, _] = self.pixel(2, x, y);
+        cgmath::vec3(r, g, b)
+    }
+
+    /// How far the first hit is from the camera along w, ana is positive
+    pub fn w_depth(&self, x: usize, y: usize) -> f32 {
+        self.pixel(2, x, y)[3]
+    }
+
+    pub fn indirect(&self, x: usize, y: usize) -> cgmath::Vector3<f32> {
+        let [r, g, b, _] = self.pixel(3, x, y);
+        cgmath::vec3(r, g, b)
+    }
+
+    /// The direct and indirect light together, before it is clamped for display
+    pub fn beauty(&self, x: usize, y: usize) -> cgmath::Vector3<f32> {
+        self.direct(x, y) + self.indirect(x, y)
+    }
+
+    /// Every AOV as an EXR layer, the beauty is the default layer. The 4D normal is split over
+    /// two RGB layers, `normal_xyz` and `normal_w` with w in red
+    pub fn to_exr(&self) -> Vec<u8> {
+        let pixels = || (0..self.height).flat_map(|y| (0..self.width).map(move |x| (x, y)));
+        let rgb = |layer: &str, value: &dyn Fn(usize, usize) -> [f32; 3]| {
+            ["R", "G", "B"]
+                .into_iter()
+                .enumerate()
+                .map(|(i, channel)| ExrChannel {
+                    name: if layer.is_empty() {
+                        channel.into()
+                    } else {
+                        format!("{layer}.{channel}")
+                    },
+                    values: pixels().map(|(x, y)| value(x, y)[i]).collect(),
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut channels = vec![];
+        channels.extend(rgb("", &|x, y| self.beauty(x, y).into()));
+        channels.extend(rgb("albedo", &|x, y| self.albedo(x, y).into()));
+        channels.extend(rgb("normal_xyz", &|x, y| {
+            self.normal(x, y).truncate().into()
+        }));
+        channels.extend(rgb("normal_w", &|x, y| [self.normal(x, y).w, 0.0, 0.0]));
+        channels.extend(rgb("direct", &|x, y| self.direct(x, y).into()));
+        channels.extend(rgb("indirect", &|x, y| self.indirect(x, y).into()));
+        channels.push(ExrChannel {
+            name: "depth.Z".into(),
+            values: pixels().map(|(x, y)| self.depth(x, y)).collect(),
+        });
+        channels.push(ExrChannel {
+            name: "w_depth.Z".into(),
+            values: pixels().map(|(x, y)| self.w_depth(x, y)).collect(),
+        });
+        encode_exr(self.width, self.height, &channels)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write_exr(&self, path: &std::path::Path) -> Result<(), String> {
+        std::fs::write(path, self.to_exr())
+            .map_err(|error| format!("Failed to write {}: {error}", path.display()))
+    }
+}
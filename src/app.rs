@@ -0,0 +1,4233 @@
+use crate::{
+    adapter_power_preference, available_adapters, backend_to_backends, camera_ray, edit_material,
+    edit_node_graph, edit_orientation, edit_value, edit_vec4, fuzzy_score, move_with_collisions,
+    pick, power_preference_to_str, present_mode_to_str, scene_script, stereo_camera_ray, Action,
+    AnimatedValue, Animation, Animator, ArrayKind, ArrayModifier, Backend, BiVector4, BlendMask,
+    BvhMode, Camera, CameraMotion, ColorManagement, Config, ConsoleLogger, CornellBox,
+    CrossSection, DisplayTexture, Dock, EditContext, EmissionPattern, ExposureAnalysis, Gamepad,
+    GamepadBinding, GamepadBindings, GamepadButton, GamepadControl, GamepadInput, GpuCamera,
+    GpuError, GpuMaterial, Histogram, Jitter, LightGroup, LoadedScene, MaterialId, MaterialUser,
+    MeasurePoint, Measurement, MotionPreview, ObjectData, ObjectFormat, ObjectId, ObjectProblem,
+    ObjectRef, Orbit, Palette, Panel, Physics, PhysicsMode, PixelPath, Placement, PostEffect,
+    PostProcessor, Prefab, PrimitiveKind, Projection, RandomSphereField, RayTracer, Recovery,
+    RenderMode, RenderPath, Room, SamplerKind, Scene, SceneChanges, SceneLoad, SceneObject, Script,
+    Selection, SkyKind, Snapping, SphereLattice, Spiral, Stereo, SwatchRenderer, Theme, Tile,
+    Value, WRamp, EXAMPLES, EXAMPLE_SCRIPT, PRESENT_MODES, ROTATION_PLANES,
+};
+use cgmath::prelude::*;
+use eframe::egui;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Radians per point of mouse movement in fly mode
+const MOUSE_SENSITIVITY: f32 = 0.003;
+/// Frames taking longer than this many seconds are logged, at most every 10 seconds
+const SLOW_FRAME_TIME: f32 = 0.25;
+/// How much faster the camera moves while alt is held, shift turns the arrow keys into w
+/// rotations and ctrl is taken by shortcuts like saving
+const SPRINT_MULTIPLIER: f32 = 4.0;
+
+/// A scene that replaces the current one, once any unsaved changes may be lost
+enum NewScene {
+    /// An example or a dropped file, run like the script editor's script, along with the path
+    /// of the file that becomes the `scene_path`
+    Script(String, Option<String>),
+    /// Made by one of the generators
+    Generated(Box<Scene>),
+}
+
+pub struct App {
+    config: Config,
+    adapters: Vec<wgpu::AdapterInfo>,
+    previous_time: instant::Instant,
+    renderer: Box<dyn RayTracer>,
+    backend: Backend,
+    backends: Vec<Backend>,
+    new_backend: Option<Backend>,
+    /// The sRGB copy of the renderer's output that egui shows
+    display: DisplayTexture,
+    /// Whether `display` is showing `post_processor`'s output instead of the renderer's
+    displaying_post: bool,
+    /// `None` when the device can't run it
+    post_processor: Option<PostProcessor>,
+    post_processing: bool,
+    post_effects: Vec<PostEffect>,
+    /// `None` when the device can't run it
+    exposure_analysis: Option<ExposureAnalysis>,
+    show_histogram: bool,
+    false_color: bool,
+    /// Whether `display` is showing `exposure_analysis`'s false color view
+    displaying_false_color: bool,
+    /// Only this part of the viewport is rendered, in pixels
+    render_region: Option<Tile>,
+    /// Dragging on the viewport sets `render_region` instead of picking
+    drawing_region: bool,
+    region_drag_start: Option<egui::Pos2>,
+    /// Clicking the viewport inspects a pixel instead of picking
+    inspecting_pixels: bool,
+    inspected_path: Option<PixelPath>,
+    /// Draws `inspected_path` into the scene with small emissive hyper spheres
+    show_path_markers: bool,
+    path_marker_radius: f32,
+    /// The markers have to be uploaded again even if they are still shown
+    path_markers_changed: bool,
+    /// Whether the renderer's objects include the markers
+    showing_path_markers: bool,
+    texture_id: egui::TextureId,
+    /// The device the renderer was made with, everything is recreated if eframe's changes
+    device: Arc<wgpu::Device>,
+    /// Set by `device`'s error handler, nothing is submitted to a lost device
+    device_lost: Arc<AtomicBool>,
+    camera: Camera,
+    /// units per second, kept out of [`Camera`] so changing it doesn't restart accumulation
+    camera_speed: f32,
+    /// The size of the render compared to the viewport
+    resolution_scale: f32,
+    camera_motion: CameraMotion,
+    gamepad: Gamepad,
+    gamepad_bindings: GamepadBindings,
+    show_gamepad_bindings: bool,
+    fly_mode: bool,
+    /// Stops the camera from moving into objects
+    collide: bool,
+    collision_radius: f32,
+    show_crosshair: bool,
+    show_minimap: bool,
+    /// How far from the camera the edges of the minimap are
+    minimap_range: f32,
+    auto_orbit: bool,
+    frame_index: u32,
+    /// What was rendered last frame, with the motion preview applied
+    previous_camera: Camera,
+    previous_light_group_weights: cgmath::Vector4<f32>,
+    motion_preview: MotionPreview,
+    accumulate: bool,
+    reset_accumulation: bool,
+    dispatches_per_frame: u32,
+    pause_when_unfocused: bool,
+    tiled: bool,
+    tile_size: usize,
+    object_format: ObjectFormat,
+    bvh_mode: BvhMode,
+    wavefront: bool,
+    snapping: Snapping,
+    physics: Physics,
+    animator: Animator,
+    array_modifiers: Vec<ArrayModifier>,
+    animation_error: Option<String>,
+    selection: Selection,
+    palette: Palette,
+    /// One per material of `palette`, the missing ones are rendered at the start of the next frame
+    palette_swatches: Vec<egui::TextureHandle>,
+    swatch_renderer: Option<SwatchRenderer>,
+    /// One per scene material with the material it was rendered from, stale ones are re-rendered one per frame
+    material_previews: Vec<Option<(GpuMaterial, egui::TextureHandle)>>,
+    palette_path: String,
+    palette_message: Option<String>,
+    /// Placed into the scene by the prefab panel, made from the selection or loaded from a file
+    prefab: Option<Prefab>,
+    jitter: Jitter,
+    prefab_placement: Placement,
+    prefab_name: String,
+    /// Where prefabs are saved, and listed from for loading
+    #[cfg(not(target_arch = "wasm32"))]
+    prefab_directory: String,
+    #[cfg(not(target_arch = "wasm32"))]
+    prefab_files: Vec<std::path::PathBuf>,
+    prefab_message: Option<String>,
+    /// Set by [`Action::CopyImage`], the display texture is read back at the start of the next frame
+    copying_image: bool,
+    /// Burns `caption` into copied images and PNGs from the render queue
+    #[cfg(not(target_arch = "wasm32"))]
+    caption_enabled: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    caption: crate::Caption,
+    hdr_path: String,
+    hdr_message: Option<String>,
+    /// Set by the export button, the image is read back at the start of the next frame
+    exporting_hdr: bool,
+    aov_path: String,
+    aov_message: Option<String>,
+    /// Set by the export button, the AOVs are read back at the start of the next frame
+    exporting_aovs: bool,
+    /// Why the last material couldn't be deleted
+    material_message: Option<String>,
+    /// The material whose nodes are open in the node editor
+    node_editor_material: Option<MaterialId>,
+    batch_translation: cgmath::Vector4<f32>,
+    batch_material: u32,
+    measure_points: [Option<MeasurePoint>; 2],
+    /// Which of `measure_points` the next click on the view sets
+    measure_picking: Option<usize>,
+    script_source: String,
+    /// Set when a script or generator replaces the scene, so the changes it uploads aren't
+    /// taken for edits
+    scene_replaced: bool,
+    /// Whether the scene differs from the one last saved to or opened from `scene_path`
+    unsaved_changes: bool,
+    scene_path: String,
+    scene_message: Option<String>,
+    /// Asking whether to save before closing the window
+    close_prompt: bool,
+    allow_close: bool,
+    /// A scene waiting to replace the current one while there are unsaved changes, with its name
+    pending_scene: Option<(String, NewScene)>,
+    dropped_file_error: Option<String>,
+    command_palette_open: bool,
+    /// The least severe records shown in the log console
+    log_level: log::LevelFilter,
+    last_slow_frame_warning: Option<instant::Instant>,
+    command_query: String,
+    /// Index into the actions matching `command_query`
+    command_selected: usize,
+    script: Option<Script>,
+    /// A script running in the background, whether to animate it once it's done, and whether
+    /// it is the scene saved at `scene_path`
+    scene_load: Option<(SceneLoad, bool, bool)>,
+    /// Left behind by a session that crashed, waiting to be restored or discarded
+    recovery: Option<Result<Recovery, String>>,
+    last_recovery_snapshot: Option<instant::Instant>,
+    script_console: Vec<String>,
+    script_animate: bool,
+    script_time: f32,
+    scene: Scene,
+    sphere_lattice: SphereLattice,
+    random_sphere_field: RandomSphereField,
+    spiral: Spiral,
+    cornell_box: CornellBox,
+    room: Room,
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    shader_watcher: crate::hot_reload::ShaderWatcher,
+    #[cfg(not(target_arch = "wasm32"))]
+    render_queue: crate::RenderQueue,
+    show_render_queue: bool,
+    /// Where to write a screenshot once the scene has loaded, its size, and whether to close after
+    #[cfg(not(target_arch = "wasm32"))]
+    pending_screenshot: Option<(std::path::PathBuf, (usize, usize), bool)>,
+    #[cfg(not(target_arch = "wasm32"))]
+    exit_when_rendered: bool,
+    /// Renders the next queued job from `queue_scene_path` instead of a copy of the current scene
+    queue_from_file: bool,
+    queue_scene_path: String,
+    queue_size: (usize, usize),
+    queue_samples: u32,
+    queue_output: String,
+}
+
+impl App {
+    pub fn new(cc: &eframe::CreationContext, config: Config) -> Self {
+        let eframe::egui_wgpu::RenderState {
+            device,
+            queue,
+            renderer: egui_renderer,
+            ..
+        } = cc
+            .wgpu_render_state
+            .as_ref()
+            .expect("the app is always started with the wgpu renderer");
+        let device_lost = watch_device(device);
+
+        let backend = Backend::Gpu(RenderPath::for_device(device));
+        let renderer = backend.create(device, queue);
+        let (width, height) = renderer.size();
+        let display = DisplayTexture::new(device, renderer.texture_view(), width, height);
+        let post_processor = PostProcessor::is_supported(device)
+            .then(|| PostProcessor::new(device, renderer.texture_view(), width, height));
+        let exposure_analysis = ExposureAnalysis::is_supported(device)
+            .then(|| ExposureAnalysis::new(device, renderer.texture_view(), width, height));
+        let texture_id = egui_renderer.write().register_native_texture(
+            device,
+            display.texture_view(),
+            wgpu::FilterMode::Nearest,
+        );
+
+        // the panic hook only writes the recovery file on native
+        #[cfg(not(target_arch = "wasm32"))]
+        let recovery = Recovery::load();
+        #[cfg(target_arch = "wasm32")]
+        let recovery = None;
+
+        cc.egui_ctx.set_visuals(theme_visuals(config.theme));
+        let camera = Camera {
+            sample_count: config.sample_count,
+            bounce_count: config.bounce_count,
+            ..Default::default()
+        };
+        let camera_speed = config.movement_speed;
+        let resolution_scale = config.resolution_scale;
+        #[cfg(not(target_arch = "wasm32"))]
+        let startup_scene = config.startup_scene.clone();
+
+        let mut app = Self {
+            config,
+            adapters: available_adapters(),
+            previous_time: instant::Instant::now(),
+            renderer,
+            backend,
+            backends: Backend::available(device),
+            new_backend: None,
+            display,
+            displaying_post: false,
+            post_processor,
+            post_processing: true,
+            post_effects: vec![],
+            exposure_analysis,
+            show_histogram: false,
+            false_color: false,
+            displaying_false_color: false,
+            render_region: None,
+            drawing_region: false,
+            region_drag_start: None,
+            inspecting_pixels: false,
+            inspected_path: None,
+            show_path_markers: true,
+            path_marker_radius: 0.05,
+            path_markers_changed: false,
+            showing_path_markers: false,
+            texture_id,
+            device: device.clone(),
+            device_lost,
+            camera,
+            camera_speed,
+            resolution_scale,
+            camera_motion: CameraMotion::default(),
+            gamepad: Gamepad::new(),
+            gamepad_bindings: GamepadBindings::default(),
+            show_gamepad_bindings: false,
+            fly_mode: false,
+            collide: false,
+            collision_radius: 0.2,
+            show_crosshair: false,
+            show_minimap: false,
+            minimap_range: 10.0,
+            auto_orbit: true,
+            frame_index: 0,
+            previous_camera: Camera::default(),
+            previous_light_group_weights: cgmath::vec4(1.0, 1.0, 1.0, 1.0),
+            motion_preview: MotionPreview::default(),
+            accumulate: true,
+            reset_accumulation: true,
+            dispatches_per_frame: 1,
+            pause_when_unfocused: true,
+            tiled: false,
+            tile_size: 256,
+            object_format: ObjectFormat::Auto,
+            bvh_mode: BvhMode::Auto,
+            wavefront: false,
+            snapping: Snapping::default(),
+            physics: Physics::default(),
+            animator: Animator::default(),
+            array_modifiers: vec![],
+            animation_error: None,
+            selection: Selection::new(),
+            palette: Palette::default(),
+            palette_swatches: vec![],
+            swatch_renderer: None,
+            material_previews: vec![],
+            palette_path: "palette.json".into(),
+            palette_message: None,
+            prefab: None,
+            jitter: Jitter::default(),
+            prefab_placement: Placement::default(),
+            prefab_name: "prefab".into(),
+            #[cfg(not(target_arch = "wasm32"))]
+            prefab_directory: "prefabs".into(),
+            #[cfg(not(target_arch = "wasm32"))]
+            prefab_files: vec![],
+            prefab_message: None,
+            copying_image: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            caption_enabled: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            caption: crate::Caption {
+                title: "scene".into(),
+                ..Default::default()
+            },
+            hdr_path: "render.exr".into(),
+            hdr_message: None,
+            exporting_hdr: false,
+            aov_path: "aovs.exr".into(),
+            aov_message: None,
+            exporting_aovs: false,
+            material_message: None,
+            node_editor_material: None,
+            batch_translation: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            batch_material: 0,
+            measure_points: [None; 2],
+            measure_picking: None,
+            script_source: EXAMPLE_SCRIPT.into(),
+            scene_replaced: false,
+            unsaved_changes: false,
+            scene_path: "scene.script".into(),
+            scene_message: None,
+            close_prompt: false,
+            allow_close: false,
+            pending_scene: None,
+            dropped_file_error: None,
+            command_palette_open: false,
+            log_level: log::LevelFilter::Info,
+            last_slow_frame_warning: None,
+            command_query: String::new(),
+            command_selected: 0,
+            script: None,
+            scene_load: None,
+            recovery,
+            last_recovery_snapshot: None,
+            script_console: vec![],
+            script_animate: false,
+            script_time: 0.0,
+            scene: Scene::default(),
+            sphere_lattice: SphereLattice::default(),
+            random_sphere_field: RandomSphereField::default(),
+            spiral: Spiral::default(),
+            cornell_box: CornellBox::default(),
+            room: Room::default(),
+            #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+            shader_watcher: crate::hot_reload::ShaderWatcher::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            render_queue: crate::RenderQueue::default(),
+            show_render_queue: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            pending_screenshot: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            exit_when_rendered: false,
+            queue_from_file: false,
+            queue_scene_path: "scene.script".into(),
+            queue_size: (1920, 1080),
+            queue_samples: 256,
+            queue_output: "render.png".into(),
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = startup_scene {
+            match std::fs::read_to_string(&path) {
+                Ok(source) => {
+                    app.scene_path = path.display().to_string();
+                    app.load_script(source, true);
+                }
+                Err(error) => log::error!("Failed to read {}: {error}", path.display()),
+            }
+        }
+        app
+    }
+
+    /// Queues a render of the scene with the camera's sample count once the startup scene has
+    /// loaded, closing the editor after it is written if `exit` is set
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn take_screenshot(&mut self, path: std::path::PathBuf, size: (usize, usize), exit: bool) {
+        self.pending_screenshot = Some((path, size, exit));
+    }
+
+    /// Removes the objects from the scene, its animations, and the selection all at once
+    fn remove_objects(&mut self, objects: &[ObjectRef]) {
+        if objects.is_empty() {
+            return;
+        }
+        self.animator.remove_objects(objects);
+        self.scene.remove_objects(objects);
+        self.selection.retain_existing(&self.scene);
+    }
+
+    /// Starts running the script on a copy of the scene in the background, the copy replaces
+    /// the scene once it's done, see [`Self::finish_script`]
+    fn run_script(&mut self) {
+        self.scene_load = Some((
+            SceneLoad::start(self.script_source.clone(), self.scene.clone()),
+            false,
+            false,
+        ));
+    }
+
+    fn finish_script(&mut self, loaded: LoadedScene, animate_if_possible: bool, saved: bool) {
+        self.script_console.extend(loaded.output);
+        let Some(scene) = loaded.scene else {
+            if let Err(error) = loaded.script {
+                self.script_console.push(error);
+            }
+            return;
+        };
+        self.scene = scene;
+        self.scene_replaced = true;
+        self.unsaved_changes = !saved;
+        self.script_time = 0.0;
+        self.selection.clear();
+        self.animator = Animator::default();
+        self.array_modifiers.clear();
+        self.script = match loaded.script {
+            Ok(script) => Some(script),
+            Err(error) => {
+                self.script_console.push(error);
+                None
+            }
+        };
+        if animate_if_possible {
+            self.script_animate = self
+                .script
+                .as_ref()
+                .is_some_and(|script| script.has_function("update"));
+        }
+    }
+
+    /// Writes the scene to `scene_path` as a script, returns whether it worked
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_scene(&mut self) -> bool {
+        match std::fs::write(&self.scene_path, scene_script(&self.scene)) {
+            Ok(()) => {
+                self.unsaved_changes = false;
+                self.scene_message = Some(format!("Saved to {}", self.scene_path));
+                true
+            }
+            Err(error) => {
+                log::error!("Failed to save {}: {error}", self.scene_path);
+                self.scene_message = Some(format!("Failed to save: {error}"));
+                false
+            }
+        }
+    }
+
+    fn perform(&mut self, action: Action) {
+        match action {
+            Action::OpenCommandPalette => {
+                self.command_palette_open = true;
+                self.command_query.clear();
+                self.command_selected = 0;
+            }
+            Action::AddObject(kind) => {
+                let material = self
+                    .scene
+                    .add_material("Default Material", GpuMaterial::default());
+
+                self.scene.add_object(
+                    format!("Default {}", kind.name()),
+                    ObjectData::new(kind, material),
+                );
+            }
+            Action::AddMaterial => {
+                self.scene
+                    .add_material("Default Material", GpuMaterial::default());
+            }
+            Action::LoadExample(i) => {
+                let example = &EXAMPLES[i];
+                self.pending_scene = Some((
+                    example.name.into(),
+                    NewScene::Script(example.source.into(), None),
+                ));
+            }
+            Action::RunScript => self.run_script(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Action::SaveScene => {
+                self.save_scene();
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Action::CopyImage => self.copying_image = true,
+            Action::SetRenderMode(render_mode) => self.camera.render_mode = render_mode,
+            Action::ToggleFlyMode => self.fly_mode = !self.fly_mode,
+            Action::ToggleMinimap => self.show_minimap = !self.show_minimap,
+        }
+    }
+
+    /// Remakes everything that lives on the GPU from the CPU-side state, after the device was lost or replaced
+    fn recreate_gpu_resources(&mut self, render_state: &eframe::egui_wgpu::RenderState) {
+        let eframe::egui_wgpu::RenderState {
+            device,
+            queue,
+            renderer: egui_renderer,
+            ..
+        } = render_state;
+        self.backends = Backend::available(device);
+        if !self.backends.contains(&self.backend) {
+            self.backend = Backend::Gpu(RenderPath::for_device(device));
+        }
+        self.renderer = self.backend.create(device, queue);
+        self.new_backend = None;
+        #[cfg(not(target_arch = "wasm32"))]
+        self.render_queue.restart();
+        let (width, height) = self.renderer.size();
+        self.display = DisplayTexture::new(device, self.renderer.texture_view(), width, height);
+        self.displaying_post = false;
+        self.post_processor = PostProcessor::is_supported(device)
+            .then(|| PostProcessor::new(device, self.renderer.texture_view(), width, height));
+        self.displaying_false_color = false;
+        self.exposure_analysis = ExposureAnalysis::is_supported(device)
+            .then(|| ExposureAnalysis::new(device, self.renderer.texture_view(), width, height));
+        let mut egui_renderer = egui_renderer.write();
+        egui_renderer.free_texture(&self.texture_id);
+        self.texture_id = egui_renderer.register_native_texture(
+            device,
+            self.display.texture_view(),
+            wgpu::FilterMode::Nearest,
+        );
+        self.scene.changes = SceneChanges::ALL;
+        self.reset_accumulation = true;
+        self.swatch_renderer = None;
+        self.palette_swatches.clear();
+        self.material_previews.clear();
+    }
+
+    /// Runs `source` in place of the scene and animates it, `saved` if it was just read from
+    /// `scene_path`
+    fn load_script(&mut self, source: String, saved: bool) {
+        self.script_source = source;
+        self.run_script();
+        if let Some((_, animate_if_possible, load_saved)) = &mut self.scene_load {
+            *animate_if_possible = true;
+            *load_saved = saved;
+        }
+    }
+
+    fn replace_scene(&mut self, new_scene: NewScene) {
+        match new_scene {
+            NewScene::Script(source, path) => {
+                let saved = path.is_some();
+                if let Some(path) = path {
+                    self.scene_path = path;
+                }
+                self.load_script(source, saved);
+            }
+            NewScene::Generated(scene) => {
+                self.scene = *scene;
+                self.scene_replaced = true;
+                self.unsaved_changes = true;
+                self.selection.clear();
+                self.animator = Animator::default();
+                self.array_modifiers.clear();
+                // the script would be editing objects that don't exist anymore
+                self.script_animate = false;
+            }
+        }
+    }
+}
+
+impl eframe::App for App {
+    fn on_close_event(&mut self) -> bool {
+        if self.unsaved_changes && !self.allow_close {
+            self.close_prompt = true;
+            return false;
+        }
+        true
+    }
+
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let Some(render_state) = frame.wgpu_render_state().cloned() else {
+            log::error!("The wgpu renderer isn't available, nothing can be drawn");
+            return;
+        };
+        if !Arc::ptr_eq(&self.device, &render_state.device) {
+            log::warn!("The GPU device changed, recreating the renderer");
+            self.device = render_state.device.clone();
+            self.device_lost = watch_device(&self.device);
+            self.recreate_gpu_resources(&render_state);
+        }
+        let device_lost = self.device_lost.load(Ordering::Relaxed);
+
+        // there is no way to block on the web, the browser decides the frame rate there
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(fps_cap) = self.config.fps_cap {
+            let min_frame_time = std::time::Duration::from_secs_f32(1.0 / fps_cap);
+            let elapsed = self.previous_time.elapsed();
+            if elapsed < min_frame_time {
+                std::thread::sleep(min_frame_time - elapsed);
+            }
+        }
+
+        let time = instant::Instant::now();
+        let dt = time.duration_since(self.previous_time);
+
+        let ts = dt.as_secs_f32();
+
+        if ts > SLOW_FRAME_TIME
+            && self
+                .last_slow_frame_warning
+                .is_none_or(|last| time.duration_since(last).as_secs_f32() > 10.0)
+        {
+            log::warn!(
+                "Slow frame, {:.0}ms. Lowering the sample count or using tiles might help",
+                ts * 1000.0
+            );
+            self.last_slow_frame_warning = Some(time);
+        }
+
+        // cloning the scene every frame would be wasteful, a second of lost edits is fine
+        #[cfg(not(target_arch = "wasm32"))]
+        if self
+            .last_recovery_snapshot
+            .is_none_or(|last| time.duration_since(last).as_secs_f32() > 1.0)
+        {
+            Recovery::snapshot(&self.scene, &self.camera);
+            self.last_recovery_snapshot = Some(time);
+        }
+
+        if self.auto_orbit {
+            if let Some(orbit) = &mut self.camera.orbit {
+                orbit.advance(ts);
+            }
+        }
+
+        if !device_lost && self.palette_swatches.len() < self.palette.materials.len() {
+            let eframe::egui_wgpu::RenderState { device, queue, .. } = &render_state;
+            let swatch_renderer = self
+                .swatch_renderer
+                .get_or_insert_with(|| SwatchRenderer::new(device, queue));
+            for (name, material) in &self.palette.materials[self.palette_swatches.len()..] {
+                self.palette_swatches.push(swatch_texture(
+                    ctx,
+                    swatch_renderer,
+                    device,
+                    queue,
+                    format!("Swatch {name}"),
+                    material,
+                ));
+            }
+        }
+
+        self.material_previews
+            .resize(self.scene.materials.len(), None);
+        let stale_preview = self
+            .scene
+            .materials
+            .iter()
+            .zip(&self.material_previews)
+            .position(|(material, preview)| {
+                preview
+                    .as_ref()
+                    .is_none_or(|(rendered, _)| rendered != material)
+            });
+        if let Some(i) = stale_preview.filter(|_| !device_lost) {
+            let eframe::egui_wgpu::RenderState { device, queue, .. } = &render_state;
+            let swatch_renderer = self
+                .swatch_renderer
+                .get_or_insert_with(|| SwatchRenderer::new(device, queue));
+            let material = self.scene.materials[i];
+            let texture = swatch_texture(
+                ctx,
+                swatch_renderer,
+                device,
+                queue,
+                format!("Material Preview {i}"),
+                &material,
+            );
+            self.material_previews[i] = Some((material, texture));
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if std::mem::take(&mut self.copying_image) && !device_lost {
+            let eframe::egui_wgpu::RenderState { device, queue, .. } = &render_state;
+            let (width, height) = self.display.size();
+            let result = self
+                .display
+                .read_pixels(device, queue)
+                .map_err(|error| error.to_string())
+                .and_then(|mut pixels| {
+                    if self.caption_enabled {
+                        let samples =
+                            self.renderer.accumulated_frames().max(1) * self.camera.sample_count;
+                        self.caption.burn(
+                            &mut pixels,
+                            width,
+                            height,
+                            &self.camera,
+                            samples,
+                            std::time::SystemTime::now(),
+                        );
+                    }
+                    crate::encode_png(width, height, &pixels)
+                })
+                .and_then(|png| crate::copy_png_to_clipboard(&png));
+            match result {
+                Ok(()) => log::info!("Copied the image to the clipboard"),
+                Err(error) => log::error!("Failed to copy the image: {error}"),
+            }
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if std::mem::take(&mut self.exporting_hdr) && !device_lost {
+            let eframe::egui_wgpu::RenderState { device, queue, .. } = &render_state;
+            self.hdr_message = Some(match self.renderer.read_hdr(device, queue) {
+                Ok(Some(image)) => match image.write_exr(std::path::Path::new(&self.hdr_path)) {
+                    Ok(()) => format!("Exported to {}", self.hdr_path),
+                    Err(error) => {
+                        log::error!("{error}");
+                        error
+                    }
+                },
+                Ok(None) => format!("The {} backend doesn't accumulate", self.backend.name()),
+                Err(error) => {
+                    log::error!("Failed to read the image: {error}");
+                    format!("Failed to read the image: {error}")
+                }
+            });
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if std::mem::take(&mut self.exporting_aovs) && !device_lost {
+            let eframe::egui_wgpu::RenderState { device, queue, .. } = &render_state;
+            self.aov_message = Some(match self.renderer.read_aovs(device, queue) {
+                Ok(Some(aovs)) => match aovs.write_exr(std::path::Path::new(&self.aov_path)) {
+                    Ok(()) => format!("Exported to {}", self.aov_path),
+                    Err(error) => {
+                        log::error!("{error}");
+                        error
+                    }
+                },
+                Ok(None) => "Render with AOVs on to export them".into(),
+                Err(error) => {
+                    log::error!("Failed to read the AOVs: {error}");
+                    format!("Failed to read the AOVs: {error}")
+                }
+            });
+        }
+
+        let camera_rotation = self.camera.rotation();
+        let camera_forward = camera_rotation.rotate_vec(cgmath::vec4(0.0, 0.0, 1.0, 0.0));
+        let camera_right = camera_rotation.rotate_vec(cgmath::vec4(1.0, 0.0, 0.0, 0.0));
+        let camera_up = camera_rotation.rotate_vec(cgmath::vec4(0.0, 1.0, 0.0, 0.0));
+        let camera_ana = camera_rotation.rotate_vec(cgmath::vec4(0.0, 0.0, 0.0, 1.0));
+
+        egui::TopBottomPanel::top("Menu Bar").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                if ui.button("Commands").clicked() {
+                    self.perform(Action::OpenCommandPalette);
+                }
+                ui.menu_button("Layout", |ui| {
+                    let layout = &mut self.config.layout;
+                    let mut changed = false;
+                    egui::Grid::new("Layout").show(ui, |ui| {
+                        for (panel, dock) in layout.panels().to_vec() {
+                            ui.label(panel.name());
+                            egui::ComboBox::from_id_source(panel.name())
+                                .selected_text(dock.name())
+                                .show_ui(ui, |ui| {
+                                    for new_dock in Dock::ALL {
+                                        if ui
+                                            .selectable_label(dock == new_dock, new_dock.name())
+                                            .clicked()
+                                        {
+                                            layout.set_dock(panel, new_dock);
+                                            changed = true;
+                                        }
+                                    }
+                                });
+                            if ui.small_button("Up").clicked() {
+                                changed |= layout.move_up(panel);
+                            }
+                            if ui.small_button("Down").clicked() {
+                                changed |= layout.move_down(panel);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                    if ui.button("Reset Layout").clicked() {
+                        *layout = Default::default();
+                        changed = true;
+                    }
+                    if changed {
+                        if let Err(error) = self.config.save() {
+                            log::error!("Failed to save config: {error}");
+                        }
+                    }
+                });
+                ui.menu_button("Examples", |ui| {
+                    for (i, example) in EXAMPLES.iter().enumerate() {
+                        if ui.button(example.name).clicked() {
+                            self.perform(Action::LoadExample(i));
+                            ui.close_menu();
+                        }
+                    }
+                });
+                ui.menu_button("Generate", |ui| {
+                    fn drag_value(
+                        ui: &mut egui::Ui,
+                        label: &str,
+                        value: &mut impl egui::emath::Numeric,
+                        speed: f64,
+                    ) {
+                        ui.horizontal(|ui| {
+                            ui.label(label);
+                            ui.add(egui::DragValue::new(value).speed(speed));
+                        });
+                    }
+
+                    let mut generated = None;
+                    ui.menu_button("Sphere Lattice", |ui| {
+                        let lattice = &mut self.sphere_lattice;
+                        drag_value(ui, "Size: ", &mut lattice.size, 0.05);
+                        lattice.size = lattice.size.clamp(1, 8);
+                        drag_value(ui, "Spacing: ", &mut lattice.spacing, 0.01);
+                        drag_value(ui, "Radius: ", &mut lattice.radius, 0.01);
+                        if ui.button("Generate").clicked() {
+                            generated = Some(("Sphere Lattice", lattice.generate()));
+                        }
+                    });
+                    ui.menu_button("Random Sphere Field", |ui| {
+                        let field = &mut self.random_sphere_field;
+                        drag_value(ui, "Count: ", &mut field.count, 0.1);
+                        drag_value(ui, "Extent: ", &mut field.extent, 0.1);
+                        drag_value(ui, "Min Radius: ", &mut field.min_radius, 0.01);
+                        drag_value(ui, "Max Radius: ", &mut field.max_radius, 0.01);
+                        drag_value(ui, "Emissive Chance: ", &mut field.emissive_chance, 0.01);
+                        field.emissive_chance = field.emissive_chance.clamp(0.0, 1.0);
+                        drag_value(ui, "Seed: ", &mut field.seed, 1.0);
+                        if ui.button("Generate").clicked() {
+                            generated = Some(("Random Sphere Field", field.generate()));
+                        }
+                    });
+                    ui.menu_button("Spiral", |ui| {
+                        let spiral = &mut self.spiral;
+                        drag_value(ui, "Count: ", &mut spiral.count, 0.1);
+                        drag_value(ui, "Turns: ", &mut spiral.turns, 0.01);
+                        drag_value(ui, "Radius: ", &mut spiral.radius, 0.01);
+                        drag_value(ui, "Height: ", &mut spiral.height, 0.01);
+                        drag_value(ui, "W Length: ", &mut spiral.w_length, 0.01);
+                        drag_value(ui, "Sphere Radius: ", &mut spiral.sphere_radius, 0.01);
+                        if ui.button("Generate").clicked() {
+                            generated = Some(("Spiral", spiral.generate()));
+                        }
+                    });
+                    ui.menu_button("Cornell Box", |ui| {
+                        let cornell_box = &mut self.cornell_box;
+                        drag_value(ui, "Size: ", &mut cornell_box.size, 0.01);
+                        drag_value(
+                            ui,
+                            "Light Strength: ",
+                            &mut cornell_box.light_strength,
+                            0.01,
+                        );
+                        if ui.button("Generate").clicked() {
+                            generated = Some(("Cornell Box", cornell_box.generate()));
+                        }
+                    });
+                    ui.menu_button("Room", |ui| {
+                        let room = &mut self.room;
+                        drag_value(ui, "Size: ", &mut room.size, 0.01);
+                        room.size = room.size.max(0.1);
+                        drag_value(ui, "Height: ", &mut room.height, 0.01);
+                        room.height = room.height.max(0.1);
+                        drag_value(ui, "Light Size: ", &mut room.light_size, 0.01);
+                        room.light_size = room.light_size.max(0.0);
+                        drag_value(ui, "Light Strength: ", &mut room.light_strength, 0.01);
+                        if ui.button("Generate").clicked() {
+                            generated = Some(("Room", room.generate()));
+                        }
+                    });
+                    if let Some((name, scene)) = generated {
+                        self.pending_scene =
+                            Some((name.into(), NewScene::Generated(Box::new(scene))));
+                        ui.close_menu();
+                    }
+                });
+            });
+        });
+
+        if let Some((load, animate_if_possible, saved)) = &mut self.scene_load {
+            if let Some(loaded) = load.poll() {
+                let (animate_if_possible, saved) = (*animate_if_possible, *saved);
+                self.scene_load = None;
+                self.finish_script(loaded, animate_if_possible, saved);
+            } else {
+                let stage = load.stage();
+                egui::Window::new("Loading Scene")
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                    .show(ctx, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label(format!("{}...", stage.name()));
+                        });
+                        if ui.button("Cancel").clicked() {
+                            self.scene_load = None;
+                        }
+                    });
+            }
+        }
+
+        for file in ctx.input(|i| i.raw.dropped_files.clone()) {
+            match read_dropped_scene(&file) {
+                Ok((name, source)) => {
+                    let path = file.path.as_ref().map(|path| path.display().to_string());
+                    self.pending_scene = Some((name, NewScene::Script(source, path)));
+                }
+                Err(error) => {
+                    log::warn!("{error}");
+                    self.dropped_file_error = Some(error);
+                }
+            }
+        }
+        if !self.unsaved_changes {
+            if let Some((_, new_scene)) = self.pending_scene.take() {
+                self.replace_scene(new_scene);
+            }
+        }
+        if let Some((name, _)) = &self.pending_scene {
+            let mut load = false;
+            let mut cancel = false;
+            egui::Window::new("Unsaved Changes")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "The scene was edited since it was last saved, opening {name} will lose those changes"
+                    ));
+                    ui.horizontal(|ui| {
+                        load = ui.button("Open Anyway").clicked();
+                        cancel = ui.button("Cancel").clicked();
+                    });
+                });
+            if load {
+                let (_, new_scene) = self.pending_scene.take().unwrap();
+                self.replace_scene(new_scene);
+            } else if cancel {
+                self.pending_scene = None;
+            }
+        }
+        if let Some(recovery) = &self.recovery {
+            let mut restore = false;
+            let mut discard = false;
+            egui::Window::new("Restore Previous Session")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    match recovery {
+                        Ok(_) => {
+                            ui.label("The editor crashed last time, the scene and camera from then were saved");
+                        }
+                        Err(error) => {
+                            ui.label("The editor crashed last time, but the scene it saved can't be read");
+                            ui.colored_label(ui.visuals().error_fg_color, error);
+                        }
+                    }
+                    ui.horizontal(|ui| {
+                        restore = recovery.is_ok() && ui.button("Restore").clicked();
+                        discard = ui.button("Discard").clicked();
+                    });
+                });
+            if restore {
+                if let Some(Ok(recovery)) = self.recovery.take() {
+                    // it was never saved, that's why it had to be recovered
+                    self.load_script(recovery.scene_source, false);
+                    let camera = recovery.camera;
+                    self.camera.position = camera.position;
+                    self.camera.pitch = camera.pitch;
+                    self.camera.yaw = camera.yaw;
+                    self.camera.weird_pitch = camera.weird_pitch;
+                    self.camera.weird_yaw = camera.weird_yaw;
+                    self.camera.fov = camera.fov;
+                }
+                Recovery::discard();
+            } else if discard {
+                self.recovery = None;
+                Recovery::discard();
+            }
+        }
+        if let Some(error) = &self.dropped_file_error {
+            let mut close = false;
+            egui::Window::new("Couldn't Open File")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                    close = ui.button("Ok").clicked();
+                });
+            if close {
+                self.dropped_file_error = None;
+            }
+        }
+
+        // eframe owns the device and never requests another, so only a restart can render again
+        if device_lost {
+            egui::Window::new("GPU Device Lost")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(
+                        "The GPU device can't be used again, nothing can be rendered until \
+                         the editor is restarted.",
+                    );
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        ui.label(
+                            "The scene was saved and is offered to be restored on the next start.",
+                        );
+                        if ui.button("Quit").clicked() {
+                            frame.close();
+                        }
+                    }
+                    #[cfg(target_arch = "wasm32")]
+                    ui.label("Reload the page to start again, unsaved changes are lost.");
+                });
+        }
+
+        // shortcuts without modifiers would get in the way of typing
+        let typing = ctx.wants_keyboard_input();
+        let shortcut_action = ctx.input_mut(|i| {
+            Action::all().into_iter().find(|action| {
+                action.shortcut().is_some_and(|shortcut| {
+                    (!typing || shortcut.modifiers != egui::Modifiers::NONE)
+                        && i.consume_shortcut(&shortcut)
+                })
+            })
+        });
+        if let Some(action) = shortcut_action {
+            self.perform(action);
+        }
+
+        if self.command_palette_open {
+            let mut matches = Action::all()
+                .into_iter()
+                .filter_map(|action| {
+                    fuzzy_score(&self.command_query, &action.name()).map(|score| (score, action))
+                })
+                .collect::<Vec<_>>();
+            // stable, so equally good matches stay in the registry order
+            matches.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+
+            let (up, down, enter, escape) = ctx.input_mut(|i| {
+                (
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+                )
+            });
+            if up {
+                self.command_selected = self.command_selected.saturating_sub(1);
+            }
+            if down {
+                self.command_selected += 1;
+            }
+            self.command_selected = self.command_selected.min(matches.len().saturating_sub(1));
+
+            let mut chosen = None;
+            egui::Window::new("Command Palette")
+                .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 40.0))
+                .collapsible(false)
+                .resizable(false)
+                .title_bar(false)
+                .show(ctx, |ui| {
+                    let query = ui.text_edit_singleline(&mut self.command_query);
+                    query.request_focus();
+                    if query.changed() {
+                        self.command_selected = 0;
+                    }
+                    for (i, &(_, action)) in matches.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .selectable_label(i == self.command_selected, action.name())
+                                .clicked()
+                            {
+                                chosen = Some(action);
+                            }
+                            if let Some(shortcut) = action.shortcut() {
+                                ui.weak(ctx.format_shortcut(&shortcut));
+                            }
+                        });
+                    }
+                    if matches.is_empty() {
+                        ui.weak("No matching commands");
+                    }
+                });
+            if enter {
+                chosen = matches
+                    .get(self.command_selected)
+                    .map(|&(_, action)| action);
+            }
+            if chosen.is_some() || escape {
+                self.command_palette_open = false;
+            }
+            if let Some(action) = chosen {
+                self.perform(action);
+            }
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.close_prompt {
+            egui::Window::new("Save Before Closing?")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label("The scene was edited since it was last saved");
+                    ui.horizontal(|ui| {
+                        ui.label("File: ");
+                        ui.text_edit_singleline(&mut self.scene_path);
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("Save").clicked() && self.save_scene() {
+                            self.allow_close = true;
+                            frame.close();
+                        }
+                        if ui.button("Discard").clicked() {
+                            self.allow_close = true;
+                            frame.close();
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.close_prompt = false;
+                        }
+                    });
+                    if let Some(message) = &self.scene_message {
+                        ui.label(message);
+                    }
+                });
+        }
+
+        let changes_before_panel = self.scene.changes;
+
+        fn edit_expression(
+            ui: &mut egui::Ui,
+            label: impl Into<egui::WidgetText>,
+            value: &mut AnimatedValue,
+        ) {
+            ui.horizontal(|ui| {
+                ui.label(label);
+                let mut source = value.source().to_string();
+                if ui.text_edit_singleline(&mut source).changed() {
+                    value.set_source(source);
+                }
+            });
+            if let Some(error) = value.error() {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        }
+
+        /// Returns if the animation should be removed
+        fn edit_animation(ui: &mut egui::Ui, animation: &mut Animation) -> bool {
+            ui.label("Animation, expressions of t that are empty leave the value alone");
+            for (axis, label) in ["X: ", "Y: ", "Z: ", "W: "].into_iter().enumerate() {
+                edit_expression(ui, label, &mut animation.position[axis]);
+            }
+            if let ObjectRef::HyperPlane(_) = animation.object {
+                edit_expression(ui, "Angle: ", &mut animation.angle);
+                ui.horizontal(|ui| {
+                    ui.label("Plane: ");
+                    let selected = ROTATION_PLANES
+                        .iter()
+                        .find(|&&(_, plane)| plane == animation.plane)
+                        .map_or("Custom", |&(name, _)| name);
+                    egui::ComboBox::from_id_source(("Animation Plane", animation.object))
+                        .selected_text(selected)
+                        .show_ui(ui, |ui| {
+                            for (name, plane) in ROTATION_PLANES {
+                                ui.selectable_value(&mut animation.plane, plane, name);
+                            }
+                        });
+                });
+            }
+            ui.button("Remove Animation").clicked()
+        }
+
+        fn rotation_plane_combo_box(ui: &mut egui::Ui, id: &str, plane: &mut BiVector4) {
+            let selected = ROTATION_PLANES
+                .iter()
+                .find(|&&(_, other)| other == *plane)
+                .map_or("Custom", |&(name, _)| name);
+            egui::ComboBox::from_id_source(id)
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    for (name, other) in ROTATION_PLANES {
+                        ui.selectable_value(plane, other, name);
+                    }
+                });
+        }
+
+        fn selection_checkbox(ui: &mut egui::Ui, selection: &mut Selection, object: ObjectId) {
+            let mut selected = selection.contains(object);
+            if ui.checkbox(&mut selected, "").changed() {
+                selection.toggle(object);
+            }
+        }
+
+        #[inline(always)]
+        fn edit_angle(ui: &mut egui::Ui, label: impl Into<egui::WidgetText>, angle: &mut f32) {
+            ui.horizontal(|ui| {
+                ui.label(label);
+                ui.drag_angle(angle);
+            });
+            *angle %= std::f32::consts::TAU;
+            *angle += std::f32::consts::TAU;
+            *angle %= std::f32::consts::TAU;
+        }
+
+        #[inline(always)]
+        fn edit_color3(
+            ui: &mut egui::Ui,
+            label: impl Into<egui::WidgetText>,
+            color: &mut cgmath::Vector3<f32>,
+        ) -> bool {
+            ui.horizontal(|ui| {
+                ui.label(label);
+                let mut array = [color.x, color.y, color.z];
+                let changed = egui::color_picker::color_edit_button_rgb(ui, &mut array).changed();
+                *color = cgmath::vec3(array[0], array[1], array[2]);
+                changed
+            })
+            .inner
+        }
+
+        /// Returns whether the object should be replaced with its sanitized version
+        fn show_problems(ui: &mut egui::Ui, problems: &[ObjectProblem]) -> bool {
+            for problem in problems {
+                ui.colored_label(ui.visuals().warn_fg_color, problem.message());
+            }
+            !problems.is_empty()
+                && ui
+                    .button("Fix")
+                    .on_hover_text("Change the object to what is being rendered")
+                    .clicked()
+        }
+
+        let layout = self.config.layout.clone();
+        let mut show_panel = |ui: &mut egui::Ui, panel: Panel| match panel {
+            Panel::Scene => {
+                ui.collapsing("Environment", |ui| {
+                    let environment = &mut self.scene.environment;
+                    let mut changed = false;
+                    ui.horizontal(|ui| {
+                        ui.label("Sky: ");
+                        egui::ComboBox::from_id_source("Sky")
+                            .selected_text(environment.sky.name())
+                            .show_ui(ui, |ui| {
+                                for sky in SkyKind::ALL {
+                                    changed |= ui
+                                        .selectable_value(&mut environment.sky, sky, sky.name())
+                                        .changed();
+                                }
+                            });
+                    });
+                    ui.add_enabled_ui(environment.sky == SkyKind::Physical, |ui| {
+                        for (label, angle) in [
+                            ("Sun Elevation: ", &mut environment.sun_elevation),
+                            ("Sun Azimuth: ", &mut environment.sun_azimuth),
+                            ("Sun 4D Angle: ", &mut environment.sun_w_angle),
+                            ("Sun Radius: ", &mut environment.sun_radius),
+                        ] {
+                            ui.horizontal(|ui| {
+                                ui.label(label);
+                                changed |= ui.drag_angle(angle).changed();
+                            });
+                        }
+                        environment.sun_elevation = environment
+                            .sun_elevation
+                            .clamp(-std::f32::consts::FRAC_PI_2, std::f32::consts::FRAC_PI_2);
+                        environment.sun_radius = environment.sun_radius.clamp(0.0, 0.5);
+                        changed |= edit_value(ui, "Turbidity: ", &mut environment.turbidity, 0.01);
+                        environment.turbidity = environment.turbidity.clamp(1.7, 10.0);
+                        changed |= edit_value(ui, "Exposure: ", &mut environment.exposure, 0.001);
+                        environment.exposure = environment.exposure.max(0.0);
+                        changed |=
+                            edit_value(ui, "Sun Strength: ", &mut environment.sun_strength, 0.1);
+                        environment.sun_strength = environment.sun_strength.max(0.0);
+                    });
+                    self.scene.changes.environment |= changed;
+                });
+                ui.collapsing("Prefabs", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Name: ");
+                        ui.text_edit_singleline(&mut self.prefab_name);
+                    });
+                    if ui
+                        .add_enabled(
+                            !self.selection.is_empty(),
+                            egui::Button::new("Make Prefab From Selection"),
+                        )
+                        .clicked()
+                    {
+                        let objects = self.selection.objects(&self.scene);
+                        self.prefab = Some(Prefab::from_objects(
+                            self.prefab_name.clone(),
+                            &self.scene,
+                            &objects,
+                        ));
+                        self.prefab_message = None;
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        ui.horizontal(|ui| {
+                            ui.label("Library: ");
+                            ui.text_edit_singleline(&mut self.prefab_directory);
+                            if ui.button("Refresh").clicked() {
+                                self.prefab_files = crate::list_prefabs(std::path::Path::new(
+                                    &self.prefab_directory,
+                                ));
+                            }
+                        });
+                        if let Some(prefab) = &self.prefab {
+                            if ui.button("Save Prefab To Library").clicked() {
+                                let directory = std::path::Path::new(&self.prefab_directory);
+                                let path = directory
+                                    .join(&prefab.name)
+                                    .with_extension(Prefab::EXTENSION);
+                                let result = std::fs::create_dir_all(directory)
+                                    .map_err(|error| {
+                                        format!("Failed to create {}: {error}", directory.display())
+                                    })
+                                    .and_then(|()| prefab.save(&path));
+                                self.prefab_message = Some(match result {
+                                    Ok(()) => {
+                                        self.prefab_files = crate::list_prefabs(directory);
+                                        format!("Saved to {}", path.display())
+                                    }
+                                    Err(error) => {
+                                        log::error!("{error}");
+                                        error
+                                    }
+                                });
+                            }
+                        }
+                        let mut loaded = None;
+                        for path in &self.prefab_files {
+                            ui.horizontal(|ui| {
+                                if ui.button("Load").clicked() {
+                                    loaded = Some(Prefab::load(path));
+                                }
+                                ui.label(path.file_stem().unwrap_or_default().to_string_lossy());
+                            });
+                        }
+                        match loaded {
+                            Some(Ok(prefab)) => {
+                                self.prefab_name = prefab.name.clone();
+                                self.prefab = Some(prefab);
+                                self.prefab_message = None;
+                            }
+                            Some(Err(error)) => {
+                                log::warn!("Failed to load the prefab: {error}");
+                                self.prefab_message = Some(error);
+                            }
+                            None => {}
+                        }
+                    }
+                    ui.separator();
+                    if let Some(prefab) = &self.prefab {
+                        ui.label(format!(
+                            "{}: {} objects, {} materials",
+                            prefab.name,
+                            prefab.scene.objects.len(),
+                            prefab.scene.materials.len()
+                        ));
+                        let placement = &mut self.prefab_placement;
+                        edit_vec4(ui, "Position: ", &mut placement.position);
+                        ui.horizontal(|ui| {
+                            ui.label("Rotation: ");
+                            ui.drag_angle(&mut placement.angle);
+                            rotation_plane_combo_box(
+                                ui,
+                                "Prefab Rotation Plane",
+                                &mut placement.plane,
+                            );
+                        });
+                        edit_value(ui, "Scale: ", &mut placement.scale, 0.01);
+                        placement.scale = placement.scale.max(0.001);
+                        if ui.button("Place").clicked() {
+                            let ids = prefab.instantiate(&mut self.scene, placement);
+                            self.selection.clear();
+                            for id in ids {
+                                self.selection.add(id);
+                            }
+                        }
+                    } else {
+                        ui.label("No prefab, make one from the selection or load one");
+                    }
+                    if let Some(message) = &self.prefab_message {
+                        ui.label(message);
+                    }
+                });
+                ui.collapsing("Arrays", |ui| {
+                    if self.array_modifiers.is_empty() {
+                        ui.label("Add one from an object with its Add Array button");
+                    }
+                    let mut removed = None;
+                    for (index, modifier) in self.array_modifiers.iter_mut().enumerate() {
+                        let source = self
+                            .scene
+                            .object_ref(modifier.source)
+                            .and_then(|source| self.scene.object_name(source))
+                            .unwrap_or("?");
+                        ui.push_id(index, |ui| {
+                            ui.label(format!("Array of {source}"));
+                            let settings = &mut modifier.settings;
+                            ui.horizontal(|ui| {
+                                ui.label("Copies: ");
+                                ui.add(
+                                    egui::DragValue::new(&mut settings.count).clamp_range(0..=256),
+                                );
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Kind: ");
+                                egui::ComboBox::from_id_source("Array Kind")
+                                    .selected_text(settings.kind.name())
+                                    .show_ui(ui, |ui| {
+                                        for kind in [
+                                            ArrayKind::Linear {
+                                                offset: cgmath::vec4(2.0, 0.0, 0.0, 0.0),
+                                            },
+                                            ArrayKind::Radial {
+                                                center: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                                                plane: BiVector4::XW,
+                                                angle: std::f32::consts::TAU,
+                                            },
+                                        ] {
+                                            if ui
+                                                .selectable_label(
+                                                    settings.kind.name() == kind.name(),
+                                                    kind.name(),
+                                                )
+                                                .clicked()
+                                                && settings.kind.name() != kind.name()
+                                            {
+                                                settings.kind = kind;
+                                            }
+                                        }
+                                    });
+                            });
+                            match &mut settings.kind {
+                                ArrayKind::Linear { offset } => {
+                                    edit_vec4(ui, "Offset: ", offset);
+                                }
+                                ArrayKind::Radial {
+                                    center,
+                                    plane,
+                                    angle,
+                                } => {
+                                    edit_vec4(ui, "Center: ", center);
+                                    ui.horizontal(|ui| {
+                                        ui.label("Sweep: ");
+                                        ui.drag_angle(angle);
+                                        rotation_plane_combo_box(ui, "Array Plane", plane);
+                                    });
+                                }
+                            }
+                            ui.horizontal(|ui| {
+                                ui.label("Rotation Per Copy: ");
+                                ui.drag_angle(&mut settings.rotation);
+                                rotation_plane_combo_box(
+                                    ui,
+                                    "Array Rotation Plane",
+                                    &mut settings.rotation_plane,
+                                );
+                            });
+                            ui.horizontal(|ui| {
+                                if ui
+                                    .button("Apply")
+                                    .on_hover_text("Keeps the copies as normal objects")
+                                    .clicked()
+                                {
+                                    removed = Some((index, false));
+                                }
+                                if ui.button("Remove").clicked() {
+                                    removed = Some((index, true));
+                                }
+                            });
+                        });
+                        ui.separator();
+                    }
+                    if let Some((index, remove_clones)) = removed {
+                        let mut modifier = self.array_modifiers.remove(index);
+                        let clones = modifier.detach_clones();
+                        if remove_clones {
+                            let clones = clones
+                                .into_iter()
+                                .filter_map(|clone| self.scene.object_ref(clone))
+                                .collect::<Vec<_>>();
+                            self.remove_objects(&clones);
+                        }
+                    }
+                });
+                ui.collapsing("Jitter", |ui| {
+                    ui.label("The most the selected objects change by in either direction");
+                    edit_vec4(ui, "Position: ", &mut self.jitter.position);
+                    self.jitter.position = self.jitter.position.map(|range| range.max(0.0));
+                    ui.horizontal(|ui| {
+                        ui.label("Radius: ");
+                        ui.add(
+                            egui::Slider::new(&mut self.jitter.radius, 0.0..=0.9).custom_formatter(
+                                |fraction, _| format!("{:.0}%", fraction * 100.0),
+                            ),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Hue: ");
+                        ui.add(egui::Slider::new(&mut self.jitter.hue, 0.0..=0.5).suffix(" turns"))
+                            .on_hover_text(
+                                "Gives every jittered object its own copy of its material",
+                            );
+                    });
+                    edit_value(ui, "Seed: ", &mut self.jitter.seed, 1.0);
+                    if ui
+                        .add_enabled(
+                            !self.selection.is_empty(),
+                            egui::Button::new("Jitter Selection"),
+                        )
+                        .clicked()
+                    {
+                        let objects = self.selection.objects(&self.scene);
+                        self.jitter.apply(&mut self.scene, &objects);
+                        // so clicking again gives different values
+                        self.jitter.seed = self.jitter.seed.wrapping_add(1);
+                    }
+                });
+                ui.collapsing("Snapping", |ui| {
+                    ui.checkbox(&mut self.snapping.enabled, "Snap To Grid");
+                    edit_vec4(ui, "Step: ", &mut self.snapping.step);
+                    self.snapping.step = self.snapping.step.map(|step| step.max(0.0));
+                    ui.horizontal(|ui| {
+                        ui.label("Lock: ");
+                        for (locked, axis) in
+                            self.snapping.locked.iter_mut().zip(["x", "y", "z", "w"])
+                        {
+                            ui.checkbox(locked, axis);
+                        }
+                    });
+                    if ui.button("Snap Objects To Grid").clicked() {
+                        for object in self.scene.objects_mut() {
+                            let position = object.position_mut();
+                            *position = self.snapping.snap_position(*position);
+                        }
+                    }
+                });
+                for &kind in PrimitiveKind::ALL {
+                    ui.collapsing(kind.plural_name(), |ui| {
+                        if ui.button(format!("Add {}", kind.name())).clicked() {
+                            self.perform(Action::AddObject(kind));
+                        }
+
+                        let context = EditContext {
+                            snapping: &self.snapping,
+                            material_ids: &self.scene.material_ids,
+                            material_names: &self.scene.material_names,
+                        };
+                        let mut changed = false;
+                        let mut to_delete = vec![];
+                        let mut to_animate = vec![];
+                        let mut to_array = vec![];
+                        let object_refs = self.scene.object_refs().collect::<Vec<_>>();
+                        for (
+                            object,
+                            SceneObject {
+                                id,
+                                name,
+                                notes,
+                                data,
+                            },
+                        ) in object_refs.into_iter().zip(&mut self.scene.objects)
+                        {
+                            if data.kind() != kind {
+                                continue;
+                            }
+                            let object_id = *id;
+                            let problems = data.problems(context.material_ids);
+                            let id = ui.make_persistent_id(object_id);
+                            egui::collapsing_header::CollapsingState::load_with_default_open(
+                                ui.ctx(),
+                                id,
+                                false,
+                            )
+                            .show_header(ui, |ui| {
+                                selection_checkbox(ui, &mut self.selection, object_id);
+                                let label = ui.label(name.as_str());
+                                if !notes.is_empty() {
+                                    label.on_hover_text(notes.as_str());
+                                }
+                                if !problems.is_empty() {
+                                    ui.colored_label(ui.visuals().warn_fg_color, "⚠");
+                                }
+                            })
+                            .body(|ui| {
+                                ui.horizontal(|ui| {
+                                    ui.label("Name: ");
+                                    ui.text_edit_singleline(name);
+                                });
+                                ui.label("Notes: ");
+                                ui.add(
+                                    egui::TextEdit::multiline(notes)
+                                        .desired_rows(2)
+                                        .hint_text("What this object demonstrates"),
+                                );
+                                changed |= data.edit(ui, &context);
+                                if show_problems(ui, &problems) {
+                                    *data = data.sanitized(context.material_ids);
+                                    changed = true;
+                                }
+                                match self.animator.animation_mut(object) {
+                                    Some(animation) => {
+                                        if edit_animation(ui, animation) {
+                                            self.animator.remove_animation(object);
+                                        }
+                                    }
+                                    None => {
+                                        if ui.button("Animate").clicked() {
+                                            to_animate.push(object);
+                                        }
+                                    }
+                                }
+                                if ui
+                                    .button("Add Array")
+                                    .on_hover_text(
+                                        "Copies that follow this object, set up under Arrays",
+                                    )
+                                    .clicked()
+                                {
+                                    to_array.push(object_id);
+                                }
+                                if ui.button("Delete").clicked() {
+                                    to_delete.push(object);
+                                }
+                            });
+                        }
+                        for object in to_animate {
+                            let animation = Animation::new(&self.scene, object);
+                            self.animator.animations.push(animation);
+                        }
+                        self.array_modifiers
+                            .extend(to_array.into_iter().map(ArrayModifier::new));
+                        if changed {
+                            self.scene.changes.mark(kind);
+                        }
+                        self.remove_objects(&to_delete);
+                    });
+                }
+                ui.collapsing("Physics", |ui| {
+                    ui.horizontal(|ui| {
+                        if self.physics.running {
+                            if ui.button("Pause").clicked() {
+                                self.physics.pause();
+                            }
+                        } else if ui.button("Play").clicked() {
+                            self.physics.play(&self.scene);
+                        }
+                        if ui.button("Reset").clicked() {
+                            self.physics.reset(&mut self.scene);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Mode: ");
+                        egui::ComboBox::from_id_source("Physics Mode")
+                            .selected_text(self.physics.mode.name())
+                            .show_ui(ui, |ui| {
+                                for mode in PhysicsMode::ALL {
+                                    ui.selectable_value(&mut self.physics.mode, mode, mode.name());
+                                }
+                            });
+                    });
+                    match self.physics.mode {
+                        PhysicsMode::Sandbox => {
+                            edit_vec4(ui, "Gravity: ", &mut self.physics.gravity);
+                            edit_value(ui, "Restitution: ", &mut self.physics.restitution, 0.01);
+                            self.physics.restitution = self.physics.restitution.clamp(0.0, 1.0);
+                        }
+                        PhysicsMode::Orbital => {
+                            edit_value(
+                                ui,
+                                "Gravitational Constant: ",
+                                &mut self.physics.gravitational_constant,
+                                0.1,
+                            );
+                            ui.label("Emissive hyper spheres are suns that pull on the rest");
+                        }
+                    }
+                    edit_value(ui, "Trail Interval: ", &mut self.physics.trail_interval, 0.01);
+                    self.physics.trail_interval = self.physics.trail_interval.max(0.0);
+                    edit_value(ui, "Trail Length: ", &mut self.physics.trail_length, 1);
+                    ui.label(
+                        "A hyper sphere's velocity is its physics velocity, trails are removed by Reset",
+                    );
+                });
+                ui.collapsing("Animation", |ui| {
+                    ui.horizontal(|ui| {
+                        if self.animator.playing {
+                            if ui.button("Pause").clicked() {
+                                self.animator.playing = false;
+                            }
+                        } else if ui.button("Play").clicked() {
+                            self.animator.playing = true;
+                            self.animation_error = None;
+                        }
+                        if ui.button("Reset").clicked() {
+                            self.animator.time = 0.0;
+                            self.animation_error = self.animator.apply(&mut self.scene).err();
+                        }
+                    });
+                    if edit_value(ui, "Time: ", &mut self.animator.time, 0.01) {
+                        self.animation_error = self.animator.apply(&mut self.scene).err();
+                    }
+                    ui.label(format!(
+                        "{} animated objects, animate them from the object lists",
+                        self.animator.animations.len()
+                    ));
+                    if let Some(error) = &self.animation_error {
+                        ui.colored_label(ui.visuals().error_fg_color, error);
+                    }
+                });
+                ui.collapsing("Selection", |ui| {
+                    ui.label(format!(
+                        "{} selected, tick objects in the lists or ctrl-click them in the view",
+                        self.selection.len()
+                    ));
+                    ui.horizontal(|ui| {
+                        if ui.button("Select All").clicked() {
+                            self.selection.select_all(&self.scene);
+                        }
+                        if ui.button("Clear").clicked() {
+                            self.selection.clear();
+                        }
+                    });
+                    let selected = self.selection.objects(&self.scene);
+                    ui.add_enabled_ui(!selected.is_empty(), |ui| {
+                        ui.horizontal(|ui| {
+                            edit_vec4(ui, "Translate By: ", &mut self.batch_translation);
+                            if ui.button("Translate").clicked() {
+                                self.scene
+                                    .translate_objects(&selected, self.batch_translation);
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            edit_material(
+                                ui,
+                                "Material: ",
+                                &mut self.batch_material,
+                                &self.scene.material_ids,
+                                &self.scene.material_names,
+                            );
+                            if ui.button("Assign").clicked() {
+                                self.scene.set_objects_material(
+                                    &selected,
+                                    MaterialId(self.batch_material),
+                                );
+                            }
+                        });
+                        if ui.button("Delete Selected").clicked() {
+                            self.remove_objects(&selected);
+                        }
+                    });
+                });
+                ui.collapsing("Measure", |ui| {
+                    for (i, label) in ["A: ", "B: "].into_iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(label);
+                            let selected = match self.measure_points[i] {
+                                Some(MeasurePoint::Object(object)) => self
+                                    .scene
+                                    .object_name(object)
+                                    .unwrap_or("Removed")
+                                    .to_string(),
+                                Some(MeasurePoint::Point { position: p, .. }) => {
+                                    format!("({:.3}, {:.3}, {:.3}, {:.3})", p.x, p.y, p.z, p.w)
+                                }
+                                None => "None".into(),
+                            };
+                            egui::ComboBox::from_id_source(("Measure Point", i))
+                                .selected_text(selected)
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut self.measure_points[i], None, "None");
+                                    for object in self.scene.object_refs() {
+                                        ui.selectable_value(
+                                            &mut self.measure_points[i],
+                                            Some(MeasurePoint::Object(object)),
+                                            self.scene.object_name(object).unwrap_or_default(),
+                                        );
+                                    }
+                                });
+                            let picking = self.measure_picking == Some(i);
+                            if ui
+                                .selectable_label(
+                                    picking,
+                                    if picking {
+                                        "Click the View"
+                                    } else {
+                                        "Pick Point"
+                                    },
+                                )
+                                .clicked()
+                            {
+                                self.measure_picking = (!picking).then_some(i);
+                            }
+                        });
+                    }
+                    let [Some(a), Some(b)] = self.measure_points else {
+                        return;
+                    };
+                    let Some(measurement) =
+                        Measurement::between(&self.scene, a, b, self.camera.position)
+                    else {
+                        return;
+                    };
+                    let delta = measurement.delta;
+                    ui.label(format!("Distance: {:.4}", measurement.distance));
+                    ui.label(format!(
+                        "Delta: x: {:.4}, y: {:.4}, z: {:.4}, w: {:.4}",
+                        delta.x, delta.y, delta.z, delta.w
+                    ));
+                    ui.label(format!(
+                        "Angle From Camera: {:.2}°",
+                        measurement.view_angle.to_degrees()
+                    ));
+                    if let Some(angle) = measurement.direction_angle {
+                        ui.label(format!("Angle Between Normals: {:.2}°", angle.to_degrees()));
+                    }
+                });
+            }
+            Panel::Materials => {
+                if ui.button("Add Material").clicked() {
+                    self.perform(Action::AddMaterial);
+                }
+
+                if let Some(message) = &self.material_message {
+                    ui.colored_label(ui.visuals().warn_fg_color, message);
+                }
+
+                ui.collapsing("Light Groups", |ui| {
+                    let mut light_groups_changed = false;
+                    let mut removed = None;
+                    for (i, light_group) in self.scene.light_groups.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            light_groups_changed |=
+                                ui.checkbox(&mut light_group.enabled, "").changed();
+                            light_groups_changed |= ui
+                                .add(
+                                    egui::TextEdit::singleline(&mut light_group.name)
+                                        .desired_width(100.0),
+                                )
+                                .changed();
+                            light_groups_changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut light_group.intensity)
+                                        .speed(0.01)
+                                        .clamp_range(0.0..=f32::INFINITY)
+                                        .prefix("x"),
+                                )
+                                .changed();
+                            if ui.button("Remove").clicked() {
+                                removed = Some(i);
+                            }
+                        });
+                    }
+                    if let Some(i) = removed {
+                        self.scene.remove_light_group(i);
+                        light_groups_changed = true;
+                    }
+                    ui.add_enabled_ui(self.scene.light_groups.len() < LightGroup::MAX, |ui| {
+                        if ui.button("Add Light Group").clicked() {
+                            let name = format!("Light Group {}", self.scene.light_groups.len() + 1);
+                            self.scene.add_light_group(LightGroup::new(name));
+                            light_groups_changed = true;
+                        }
+                    });
+                    // light groups don't set `scene.changes`, so they have to be tracked here
+                    if light_groups_changed {
+                        self.unsaved_changes = true;
+                    }
+                });
+
+                let mut changed = false;
+                let mut to_delete = vec![];
+                let mut to_swap = None;
+                let mut edit_nodes = None;
+                let mut remove_nodes = None;
+                let material_count = self.scene.materials.len();
+                let material_names = self.scene.material_names.clone();
+                let material_problems = self.scene.material_problems();
+                let light_groups = &self.scene.light_groups;
+                for (i, ((material, name), &material_id)) in self
+                    .scene
+                    .materials
+                    .iter_mut()
+                    .zip(self.scene.material_names.iter_mut())
+                    .zip(&self.scene.material_ids)
+                    .enumerate()
+                {
+                    let id = ui.make_persistent_id(("Material", i));
+                    egui::collapsing_header::CollapsingState::load_with_default_open(
+                        ui.ctx(),
+                        id,
+                        false,
+                    )
+                    .show_header(ui, |ui| {
+                        if let Some(Some((_, preview))) = self.material_previews.get(i) {
+                            ui.image(preview, egui::vec2(24.0, 24.0));
+                        }
+                        ui.label(name.as_str());
+                    })
+                    .body(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Name: ");
+                            ui.text_edit_singleline(name);
+                        });
+                        changed |= edit_color3(ui, "Base Color: ", &mut material.base_color);
+                        changed |=
+                            edit_color3(ui, "Emissive Color: ", &mut material.emissive_color);
+                        changed |= edit_value(
+                            ui,
+                            "Emissive Strength: ",
+                            &mut material.emission_strength,
+                            0.01,
+                        );
+                        ui.horizontal(|ui| {
+                            ui.label("Emission Pattern: ");
+                            let selected = EmissionPattern::from_u32(material.emission_pattern)
+                                .map_or("Unknown", EmissionPattern::name);
+                            egui::ComboBox::from_id_source("Emission Pattern")
+                                .selected_text(selected)
+                                .show_ui(ui, |ui| {
+                                    for pattern in EmissionPattern::ALL {
+                                        changed |= ui
+                                            .selectable_value(
+                                                &mut material.emission_pattern,
+                                                pattern as _,
+                                                pattern.name(),
+                                            )
+                                            .changed();
+                                    }
+                                });
+                        });
+                        changed |= edit_vec4(
+                            ui,
+                            "Emission Pattern Scale: ",
+                            &mut material.emission_pattern_scale,
+                        );
+                        ui.horizontal(|ui| {
+                            ui.label("Blend: ");
+                            let selected = BlendMask::from_u32(material.blend_mask)
+                                .map_or("Unknown", BlendMask::name);
+                            egui::ComboBox::from_id_source("Blend Mask")
+                                .selected_text(selected)
+                                .show_ui(ui, |ui| {
+                                    for mask in BlendMask::ALL {
+                                        changed |= ui
+                                            .selectable_value(
+                                                &mut material.blend_mask,
+                                                mask as _,
+                                                mask.name(),
+                                            )
+                                            .changed();
+                                    }
+                                });
+                        });
+                        if material.blend_mask().is_some() {
+                            ui.label("Hits use these materials instead of the properties below");
+                            changed |= edit_material(
+                                ui,
+                                "Material A: ",
+                                &mut material.blend_a,
+                                &self.scene.material_ids,
+                                &material_names,
+                            );
+                            changed |= edit_material(
+                                ui,
+                                "Material B: ",
+                                &mut material.blend_b,
+                                &self.scene.material_ids,
+                                &material_names,
+                            );
+                            changed |= edit_vec4(ui, "Blend Scale: ", &mut material.blend_scale);
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.button("Edit Nodes").clicked() {
+                                edit_nodes = Some(material_id);
+                            }
+                            if self.scene.node_graphs.contains_key(&material_id)
+                                && ui.button("Remove Nodes").clicked()
+                            {
+                                remove_nodes = Some(material_id);
+                            }
+                        });
+                        let problems = material_problems
+                            .iter()
+                            .filter(|(id, _)| *id == material_id)
+                            .flat_map(|(_, problems)| problems);
+                        for problem in problems {
+                            ui.colored_label(ui.visuals().warn_fg_color, problem.message());
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label("Light Group: ");
+                            let group_name = |group: u32| {
+                                group
+                                    .checked_sub(1)
+                                    .and_then(|index| light_groups.get(index as usize))
+                                    .map_or("None", |light_group| light_group.name.as_str())
+                            };
+                            egui::ComboBox::from_id_source("Light Group")
+                                .selected_text(group_name(material.light_group))
+                                .show_ui(ui, |ui| {
+                                    for group in 0..=light_groups.len() as u32 {
+                                        changed |= ui
+                                            .selectable_value(
+                                                &mut material.light_group,
+                                                group,
+                                                group_name(group),
+                                            )
+                                            .changed();
+                                    }
+                                });
+                        });
+                        changed |=
+                            edit_value(ui, "Bump Strength: ", &mut material.bump_strength, 0.01);
+                        material.bump_strength = material.bump_strength.max(0.0);
+                        changed |= edit_value(ui, "Bump Scale: ", &mut material.bump_scale, 0.01);
+                        changed |=
+                            edit_value(ui, "Transmission: ", &mut material.transmission, 0.01);
+                        material.transmission = material.transmission.clamp(0.0, 1.0);
+                        changed |= edit_value(ui, "IOR: ", &mut material.ior, 0.01);
+                        material.ior = material.ior.max(1.0);
+                        changed |= edit_value(ui, "Dispersion: ", &mut material.dispersion, 0.001);
+                        material.dispersion = material.dispersion.max(0.0);
+                        changed |= edit_value(ui, "Priority: ", &mut material.priority, 0.05);
+                        changed |=
+                            edit_color3(ui, "Absorption Color: ", &mut material.absorption_color);
+                        changed |= edit_value(
+                            ui,
+                            "Absorption Density: ",
+                            &mut material.absorption_density,
+                            0.01,
+                        );
+                        material.absorption_density = material.absorption_density.max(0.0);
+                        changed |= edit_value(ui, "Clearcoat: ", &mut material.clearcoat, 0.01);
+                        material.clearcoat = material.clearcoat.clamp(0.0, 1.0);
+                        changed |= edit_value(
+                            ui,
+                            "Clearcoat Roughness: ",
+                            &mut material.clearcoat_roughness,
+                            0.01,
+                        );
+                        material.clearcoat_roughness = material.clearcoat_roughness.clamp(0.0, 1.0);
+                        changed |= edit_value(ui, "Sheen: ", &mut material.sheen, 0.01);
+                        material.sheen = material.sheen.max(0.0);
+                        changed |= edit_color3(ui, "Sheen Color: ", &mut material.sheen_color);
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(i > 0, egui::Button::new("Up")).clicked() {
+                                to_swap = Some((i, i - 1));
+                            }
+                            if ui
+                                .add_enabled(i + 1 < material_count, egui::Button::new("Down"))
+                                .clicked()
+                            {
+                                to_swap = Some((i, i + 1));
+                            }
+                            if ui.button("Delete").clicked() {
+                                to_delete.push(material_id);
+                            }
+                        });
+                    });
+                }
+                self.scene.changes.materials |= changed;
+                if edit_nodes.is_some() {
+                    self.node_editor_material = edit_nodes;
+                }
+                if let Some(id) = remove_nodes {
+                    self.scene.node_graphs.remove(&id);
+                    self.scene.changes.materials = true;
+                }
+                if let Some((a, b)) = to_swap {
+                    self.scene.swap_materials(a, b);
+                }
+                for id in to_delete {
+                    let name = self.scene.material_name(id).unwrap_or_default().to_owned();
+                    self.material_message = match self.scene.remove_material(id) {
+                        Ok(()) => None,
+                        Err(users) => {
+                            let mut names = users
+                                .iter()
+                                .take(3)
+                                .filter_map(|&user| match user {
+                                    MaterialUser::Object(object) => self.scene.object_name(object),
+                                    MaterialUser::Blend(id) => self.scene.material_name(id),
+                                })
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            if users.len() > 3 {
+                                names += &format!(" and {} more", users.len() - 3);
+                            }
+                            Some(format!("Can't delete '{name}', it is used by {names}"))
+                        }
+                    };
+                }
+                ui.collapsing("Material Palette", |ui| {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        ui.horizontal(|ui| {
+                            ui.label("File: ");
+                            ui.text_edit_singleline(&mut self.palette_path);
+                        });
+                        ui.horizontal(|ui| {
+                            if ui.button("Import").clicked() {
+                                let result = std::fs::read_to_string(&self.palette_path)
+                                    .map_err(|error| error.to_string())
+                                    .and_then(|source| Palette::from_json(&source));
+                                self.palette_message = match result {
+                                    Ok(palette) => {
+                                        self.palette = palette;
+                                        self.palette_swatches.clear();
+                                        None
+                                    }
+                                    Err(error) => {
+                                        log::warn!(
+                                            "Failed to import {}: {error}",
+                                            self.palette_path
+                                        );
+                                        Some(format!("Failed to import: {error}"))
+                                    }
+                                };
+                            }
+                            if ui.button("Export Scene Materials").clicked() {
+                                let json = Palette::from_scene(&self.scene).to_json();
+                                self.palette_message =
+                                    Some(match std::fs::write(&self.palette_path, json) {
+                                        Ok(()) => format!("Exported to {}", self.palette_path),
+                                        Err(error) => {
+                                            log::error!(
+                                                "Failed to export {}: {error}",
+                                                self.palette_path
+                                            );
+                                            format!("Failed to export: {error}")
+                                        }
+                                    });
+                            }
+                        });
+                    }
+                    if ui.button("Use Scene Materials").clicked() {
+                        self.palette = Palette::from_scene(&self.scene);
+                        self.palette_swatches.clear();
+                    }
+                    if let Some(message) = &self.palette_message {
+                        ui.label(message);
+                    }
+                    for ((name, material), swatch) in
+                        self.palette.materials.iter().zip(&self.palette_swatches)
+                    {
+                        ui.horizontal(|ui| {
+                            ui.image(
+                                swatch,
+                                egui::vec2(SwatchRenderer::SIZE as _, SwatchRenderer::SIZE as _),
+                            );
+                            ui.label(name);
+                            if ui.button("Add To Scene").clicked() {
+                                self.scene.add_material(name.clone(), *material);
+                            }
+                        });
+                    }
+                });
+            }
+            Panel::Camera => {
+                edit_vec4(ui, "Position: ", &mut self.camera.position);
+                ui.horizontal(|ui| {
+                    ui.label("Speed: ");
+                    ui.add(
+                        egui::DragValue::new(&mut self.camera_speed)
+                            .speed(0.05)
+                            .clamp_range(0.001..=10000.0),
+                    )
+                    .on_hover_text(format!(
+                        "Hold alt to move {SPRINT_MULTIPLIER} times faster, \
+                         shift makes the arrow keys rotate through w"
+                    ));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Acceleration Time: ");
+                    ui.add(
+                        egui::DragValue::new(&mut self.camera_motion.acceleration_time)
+                            .speed(0.01)
+                            .clamp_range(0.0..=5.0)
+                            .suffix("s"),
+                    );
+                });
+                ui.add(
+                    egui::Slider::new(&mut self.camera_motion.damping, 0.0..=2.0)
+                        .text("Cinematic Damping")
+                        .suffix("s"),
+                )
+                .on_hover_text(
+                    "Smooths turning, higher values look better in recorded fly-throughs",
+                );
+                ui.checkbox(&mut self.show_gamepad_bindings, "Gamepad Bindings")
+                    .on_hover_text("Controllers are read from /dev/input/js*, so only on Linux");
+                let fly_mode_shortcut = Action::ToggleFlyMode.shortcut().unwrap();
+                ui.checkbox(
+                    &mut self.fly_mode,
+                    format!("Fly Mode ({})", ctx.format_shortcut(&fly_mode_shortcut)),
+                );
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.collide, "Collide");
+                    ui.add_enabled(
+                        self.collide,
+                        egui::DragValue::new(&mut self.collision_radius)
+                            .prefix("radius: ")
+                            .speed(0.01)
+                            .clamp_range(0.0..=10.0),
+                    );
+                });
+                ui.collapsing("Cross Section", |ui| {
+                    let mut cutting = self.camera.cross_section.is_some();
+                    if ui.checkbox(&mut cutting, "Cross Section").changed() {
+                        self.camera.cross_section = cutting.then(|| CrossSection {
+                            point: self.camera.position + camera_forward * 3.0,
+                            normal: -camera_forward,
+                            ..Default::default()
+                        });
+                    }
+                    if let Some(cross_section) = &mut self.camera.cross_section {
+                        edit_vec4(ui, "Point: ", &mut cross_section.point);
+                        if edit_vec4(ui, "Normal: ", &mut cross_section.normal) {
+                            cross_section.normal = cross_section.normal.normalize();
+                        }
+                        edit_orientation(ui, &mut cross_section.normal, -camera_forward);
+                        edit_color3(ui, "Color: ", &mut cross_section.color);
+                    }
+                });
+                ui.collapsing("Orbit", |ui| {
+                    let mut orbiting = self.camera.orbit.is_some();
+                    if ui.checkbox(&mut orbiting, "Orbit").changed() {
+                        self.camera.orbit = orbiting.then(Orbit::default);
+                    }
+                    if let Some(orbit) = &mut self.camera.orbit {
+                        edit_vec4(ui, "Target: ", &mut orbit.target);
+                        ui.menu_button("Target Hyper Sphere", |ui| {
+                            for (name, hyper_sphere) in
+                                self.scene.objects.iter().filter_map(|object| {
+                                    Some((&object.name, object.hyper_sphere()?))
+                                })
+                            {
+                                if ui.button(name).clicked() {
+                                    orbit.target = hyper_sphere.center;
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Plane: ");
+                            let selected = ROTATION_PLANES
+                                .iter()
+                                .find(|&&(_, plane)| plane == orbit.plane)
+                                .map_or("Custom", |&(name, _)| name);
+                            egui::ComboBox::from_id_source("Orbit Plane")
+                                .selected_text(selected)
+                                .show_ui(ui, |ui| {
+                                    for (name, plane) in ROTATION_PLANES {
+                                        ui.selectable_value(&mut orbit.plane, plane, name);
+                                    }
+                                });
+                        });
+                        edit_angle(ui, "Angle: ", &mut orbit.angle);
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut self.auto_orbit, "Auto Rotate");
+                            ui.add_enabled_ui(self.auto_orbit, |ui| {
+                                ui.drag_angle(&mut orbit.speed);
+                                ui.label("per second");
+                            });
+                        });
+                    }
+                });
+                edit_angle(ui, "Vertical Fov: ", &mut self.camera.fov);
+                let mut anamorphic = self.camera.aspect_override.is_some();
+                if ui
+                    .checkbox(&mut anamorphic, "Aspect Override")
+                    .on_hover_text("Shapes the rays for this aspect ratio instead of the image's")
+                    .changed()
+                {
+                    self.camera.aspect_override = anamorphic.then_some(2.39);
+                }
+                if let Some(aspect) = &mut self.camera.aspect_override {
+                    edit_value(ui, "Aspect: ", aspect, 0.01);
+                    *aspect = aspect.max(0.01);
+                    let theta = (self.camera.fov / 2.0).tan();
+                    let mut horizontal_fov = 2.0 * (*aspect * theta).atan();
+                    ui.horizontal(|ui| {
+                        ui.label("Horizontal Fov: ");
+                        if ui.drag_angle(&mut horizontal_fov).changed() {
+                            let horizontal_fov = horizontal_fov.clamp(0.01, 3.13);
+                            *aspect = (horizontal_fov / 2.0).tan() / theta;
+                        }
+                    });
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Lens Shift: ");
+                    ui.add(
+                        egui::DragValue::new(&mut self.camera.lens_shift.x)
+                            .prefix("x: ")
+                            .speed(0.01),
+                    );
+                    ui.add(
+                        egui::DragValue::new(&mut self.camera.lens_shift.y)
+                            .prefix("y: ")
+                            .speed(0.01),
+                    );
+                });
+                edit_value(ui, "Min Distance: ", &mut self.camera.min_distance, 0.01);
+                self.camera.min_distance = self.camera.min_distance.max(0.0);
+                edit_value(ui, "Max Distance: ", &mut self.camera.max_distance, 0.01);
+                self.camera.max_distance = self.camera.max_distance.max(self.camera.min_distance);
+                edit_angle(ui, "Pitch: ", &mut self.camera.pitch);
+                edit_angle(ui, "Yaw: ", &mut self.camera.yaw);
+                edit_angle(ui, "4D Pitch: ", &mut self.camera.weird_pitch);
+                edit_angle(ui, "4D Yaw: ", &mut self.camera.weird_yaw);
+                ui.horizontal(|ui| {
+                    ui.label("Projection: ");
+                    egui::ComboBox::from_id_source("Projection")
+                        .selected_text(self.camera.projection.name())
+                        .show_ui(ui, |ui| {
+                            for projection in Projection::ALL {
+                                ui.selectable_value(
+                                    &mut self.camera.projection,
+                                    projection,
+                                    projection.name(),
+                                );
+                            }
+                        });
+                });
+                ui.add_enabled_ui(self.camera.projection == Projection::Stereographic, |ui| {
+                    edit_value(
+                        ui,
+                        "3-Sphere Radius: ",
+                        &mut self.camera.stereographic_radius,
+                        0.01,
+                    );
+                });
+                self.camera.stereographic_radius = self.camera.stereographic_radius.max(0.01);
+                ui.add_enabled_ui(self.camera.projection == Projection::Fisheye, |ui| {
+                    edit_angle(ui, "Fisheye Angle: ", &mut self.camera.fisheye_angle);
+                });
+                self.camera.fisheye_angle = self.camera.fisheye_angle.max(1.0f32.to_radians());
+                let mut stereo = self.camera.stereo.is_some();
+                if ui
+                    .checkbox(&mut stereo, "Side-By-Side Stereo")
+                    .on_hover_text("Renders the left and right eye into each half of the image")
+                    .changed()
+                {
+                    self.camera.stereo = stereo.then(Stereo::default);
+                }
+                if let Some(stereo) = &mut self.camera.stereo {
+                    edit_value(ui, "Eye Separation: ", &mut stereo.eye_separation, 0.001);
+                    stereo.eye_separation = stereo.eye_separation.max(0.0);
+                    ui.checkbox(&mut stereo.cross_eyed, "Cross-Eyed");
+                }
+                ui.add_enabled_ui(false, |ui| {
+                    edit_vec4(ui, "Forward: ", &mut camera_forward.clone());
+                    edit_vec4(ui, "Right: ", &mut camera_right.clone());
+                    edit_vec4(ui, "Up: ", &mut camera_up.clone());
+                });
+            }
+            Panel::RenderSettings => {
+                ui.label(format!("FPS: {}", 1.0 / ts));
+                ui.label(format!("Frame Time: {}ms", 1000.0 * ts));
+                if let Some(stats) = self.renderer.bvh_stats() {
+                    ui.label(format!(
+                        "BVH: {} nodes, {} leaves, {} deep, {} dynamic hyper spheres",
+                        stats.nodes, stats.leaves, stats.depth, stats.dynamic
+                    ))
+                    .on_hover_text(
+                        "Hyper spheres that move or were recently edited are checked by every ray instead of being in the tree",
+                    );
+                    ui.label(format!(
+                        "BVH SAH Cost: {:.2}, built in {:.2}ms ({}), refit {} times",
+                        stats.sah_cost,
+                        stats.build_time.as_secs_f64() * 1000.0,
+                        stats.builder.name(),
+                        stats.refits
+                    ))
+                    .on_hover_text(
+                        "How many boxes and hyper spheres a ray is expected to check, lower is better",
+                    );
+                }
+                edit_value(ui, "Max Bounces: ", &mut self.camera.bounce_count, 1);
+                self.camera.bounce_count = self.camera.bounce_count.max(1);
+                edit_value(ui, "Sample Count: ", &mut self.camera.sample_count, 1);
+                self.camera.sample_count = self.camera.sample_count.max(1);
+                ui.horizontal(|ui| {
+                    ui.label("Resolution Scale: ");
+                    ui.add(
+                        egui::DragValue::new(&mut self.resolution_scale)
+                            .speed(0.01)
+                            .clamp_range(Config::RESOLUTION_SCALES),
+                    )
+                    .on_hover_text(
+                        "Renders fewer or more pixels than the viewport has, stretched to fill it",
+                    );
+                });
+                edit_value(ui, "Shutter Time: ", &mut self.camera.shutter_time, 0.01);
+                self.camera.shutter_time = self.camera.shutter_time.max(0.0);
+                ui.horizontal(|ui| {
+                    ui.label("Render Mode: ");
+                    egui::ComboBox::from_id_source("Render Mode")
+                        .selected_text(self.camera.render_mode.name())
+                        .show_ui(ui, |ui| {
+                            for render_mode in RenderMode::ALL {
+                                ui.selectable_value(
+                                    &mut self.camera.render_mode,
+                                    render_mode,
+                                    render_mode.name(),
+                                );
+                            }
+                        });
+                });
+                ui.add_enabled_ui(
+                    self.camera.render_mode == RenderMode::AmbientOcclusion,
+                    |ui| {
+                        edit_value(ui, "AO Distance: ", &mut self.camera.ao_distance, 0.01);
+                    },
+                );
+                self.camera.ao_distance = self.camera.ao_distance.max(0.0);
+                ui.add_enabled_ui(self.camera.render_mode == RenderMode::PathTrace, |ui| {
+                        ui.checkbox(&mut self.camera.spectral, "Spectral")
+                            .on_hover_text("Traces a wavelength per sample so glass with dispersion splits light into rainbows");
+                        ui.checkbox(&mut self.camera.light_group_aovs, "Light Group AOVs")
+                            .on_hover_text("Averages each light group apart so they can be changed without restarting the render, needs more GPU memory");
+                        ui.checkbox(&mut self.camera.aovs, "AOVs")
+                            .on_hover_text("Also averages the albedo, normal, depths and direct and indirect light of the first hit, for exporting as a multi-layer EXR");
+                        #[cfg(not(target_arch = "wasm32"))]
+                        ui.add_enabled_ui(self.camera.aovs, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("AOV File: ");
+                                ui.text_edit_singleline(&mut self.aov_path);
+                                if ui.button("Export EXR").clicked() {
+                                    self.exporting_aovs = true;
+                                }
+                            });
+                            if let Some(message) = &self.aov_message {
+                                ui.label(message);
+                            }
+                        });
+                    });
+                ui.add_enabled_ui(self.camera.render_mode == RenderMode::WCoordinate, |ui| {
+                    let w_ramp = &mut self.camera.w_ramp;
+                    edit_value(ui, "W Range: ", &mut w_ramp.range, 0.01);
+                    w_ramp.range = w_ramp.range.max(0.01);
+                    edit_color3(ui, "Kata Color: ", &mut w_ramp.kata);
+                    edit_color3(ui, "Center Color: ", &mut w_ramp.center);
+                    edit_color3(ui, "Ana Color: ", &mut w_ramp.ana);
+
+                    // the legend, in the same colors the output shows
+                    let (rect, _) = ui.allocate_exact_size(
+                        egui::vec2(ui.available_width().min(256.0), 16.0),
+                        egui::Sense::hover(),
+                    );
+                    const SEGMENTS: usize = 32;
+                    for i in 0..SEGMENTS {
+                        let t = (i as f32 + 0.5) / SEGMENTS as f32 * 2.0 - 1.0;
+                        let x = |i: usize| rect.left() + rect.width() * i as f32 / SEGMENTS as f32;
+                        ui.painter().rect_filled(
+                            egui::Rect::from_x_y_ranges(x(i)..=x(i + 1), rect.y_range()),
+                            0.0,
+                            output_color(
+                                w_ramp.color(t * w_ramp.range),
+                                self.camera.color_management,
+                            ),
+                        );
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Kata {:.2}", -w_ramp.range));
+                        ui.label("| Camera w |");
+                        ui.label(format!("Ana +{:.2}", w_ramp.range));
+                    });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Ray Tracer: ");
+                    egui::ComboBox::from_id_source("Ray Tracer")
+                        .selected_text(self.backend.name())
+                        .show_ui(ui, |ui| {
+                            for &backend in &self.backends {
+                                if ui
+                                    .selectable_label(self.backend == backend, backend.name())
+                                    .clicked()
+                                    && self.backend != backend
+                                {
+                                    self.new_backend = Some(backend);
+                                }
+                            }
+                        });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Color Management: ");
+                    egui::ComboBox::from_id_source("Color Management")
+                        .selected_text(self.camera.color_management.name())
+                        .show_ui(ui, |ui| {
+                            for color_management in ColorManagement::ALL {
+                                ui.selectable_value(
+                                    &mut self.camera.color_management,
+                                    color_management,
+                                    color_management.name(),
+                                );
+                            }
+                        });
+                })
+                .response
+                .on_hover_text("sRGB matches the color pickers, Linear shows the raw values");
+                ui.collapsing("Exposure Analysis", |ui| {
+                    let Some(exposure_analysis) = &self.exposure_analysis else {
+                        ui.label("Exposure analysis needs compute shaders, which this device doesn't support");
+                        return;
+                    };
+                    ui.checkbox(&mut self.false_color, "False Color").on_hover_text(
+                        "Clipped pixels are red, black ones blue and ones around middle grey green",
+                    );
+                    ui.checkbox(&mut self.show_histogram, "Histogram");
+                    if let Some(histogram) =
+                        exposure_analysis.histogram().filter(|_| self.show_histogram)
+                    {
+                        draw_histogram(ui, histogram);
+                    }
+                });
+                ui.collapsing("Post Processing", |ui| {
+                    if self.post_processor.is_none() {
+                        ui.label("Post processing needs compute shaders, which this device doesn't support");
+                        return;
+                    }
+                    ui.checkbox(&mut self.post_processing, "Enabled");
+                    let mut remove = None;
+                    let mut move_up = None;
+                    for (i, effect) in self.post_effects.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(effect.name());
+                            if ui.small_button("Up").clicked() {
+                                move_up = Some(i);
+                            }
+                            if ui.small_button("Down").clicked() {
+                                move_up = Some(i + 1);
+                            }
+                            if ui.small_button("Remove").clicked() {
+                                remove = Some(i);
+                            }
+                        });
+                        match effect {
+                            PostEffect::Vignette { strength, radius } => {
+                                ui.add(egui::Slider::new(strength, 0.0..=1.0).text("Strength"));
+                                ui.add(egui::Slider::new(radius, 0.0..=0.99).text("Radius"));
+                            }
+                            PostEffect::ChromaticAberration { strength } => {
+                                ui.add(egui::Slider::new(strength, 0.0..=0.05).text("Strength"));
+                            }
+                            PostEffect::FilmGrain { strength } => {
+                                ui.add(egui::Slider::new(strength, 0.0..=0.5).text("Strength"));
+                            }
+                        }
+                    }
+                    if let Some(i) = move_up.filter(|&i| i > 0 && i < self.post_effects.len()) {
+                        self.post_effects.swap(i - 1, i);
+                    }
+                    if let Some(i) = remove {
+                        self.post_effects.remove(i);
+                    }
+                    ui.add_enabled_ui(self.post_effects.len() < PostProcessor::MAX_EFFECTS, |ui| {
+                        ui.menu_button("Add Effect", |ui| {
+                            for effect in PostEffect::DEFAULTS {
+                                if ui.button(effect.name()).clicked() {
+                                    self.post_effects.push(effect);
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                    });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Sampler: ");
+                    egui::ComboBox::from_id_source("Sampler")
+                        .selected_text(self.camera.sampler.name())
+                        .show_ui(ui, |ui| {
+                            for sampler in SamplerKind::ALL {
+                                ui.selectable_value(
+                                    &mut self.camera.sampler,
+                                    sampler,
+                                    sampler.name(),
+                                );
+                            }
+                        });
+                });
+                ui.horizontal(|ui| {
+                    let mut deterministic = self.camera.deterministic_seed.is_some();
+                    if ui
+                        .checkbox(&mut deterministic, "Deterministic Seed")
+                        .changed()
+                    {
+                        self.camera.deterministic_seed = deterministic.then_some(0);
+                        self.frame_index = 0;
+                    }
+                    if let Some(seed) = &mut self.camera.deterministic_seed {
+                        if ui.add(egui::DragValue::new(seed)).changed() {
+                            self.frame_index = 0;
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label(format!("Frame Index: {}", self.frame_index));
+                    if ui.button("Reset").clicked() {
+                        self.frame_index = 0;
+                        self.reset_accumulation = true;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.accumulate, "Accumulate");
+                    ui.label(format!(
+                        "Accumulated Frames: {}",
+                        self.renderer.accumulated_frames()
+                    ));
+                });
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    ui.horizontal(|ui| {
+                        ui.label("HDR File: ");
+                        ui.text_edit_singleline(&mut self.hdr_path);
+                        if ui
+                            .button("Export EXR")
+                            .on_hover_text("Writes the accumulated image in linear HDR, before it is clamped for display")
+                            .clicked()
+                        {
+                            self.exporting_hdr = true;
+                        }
+                    });
+                    if ui.button("Copy Image to Clipboard").clicked() {
+                        self.perform(Action::CopyImage);
+                    }
+                    ui.checkbox(&mut self.caption_enabled, "Caption")
+                        .on_hover_text(
+                        "Burns a line of text into copied images and PNGs from the render queue",
+                    );
+                    ui.add_enabled_ui(self.caption_enabled, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Title: ");
+                            ui.text_edit_singleline(&mut self.caption.title);
+                            if ui.button("From Scene File").clicked() {
+                                self.caption.title = std::path::Path::new(&self.scene_path)
+                                    .file_stem()
+                                    .map_or_else(String::new, |stem| {
+                                        stem.to_string_lossy().into_owned()
+                                    });
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut self.caption.w_coordinate, "W Coordinate");
+                            ui.checkbox(&mut self.caption.sample_count, "Sample Count");
+                            ui.checkbox(&mut self.caption.timestamp, "Timestamp");
+                        });
+                    });
+                    if let Some(message) = &self.hdr_message {
+                        ui.label(message);
+                    }
+                }
+                ui.add_enabled_ui(self.accumulate, |ui| {
+                    edit_value(
+                        ui,
+                        "Dispatches Per Frame: ",
+                        &mut self.dispatches_per_frame,
+                        0.05,
+                    );
+                });
+                ui.checkbox(&mut self.motion_preview.enabled, "Preview While Moving")
+                    .on_hover_text("Renders with fewer samples and bounces while the camera moves");
+                ui.add_enabled_ui(self.motion_preview.enabled, |ui| {
+                    let preview = &mut self.motion_preview;
+                    edit_value(ui, "Preview Samples: ", &mut preview.sample_count, 1);
+                    preview.sample_count = preview.sample_count.max(1);
+                    edit_value(ui, "Preview Bounces: ", &mut preview.bounce_count, 1);
+                    edit_value(ui, "Settle Time: ", &mut preview.settle_time, 0.01);
+                    preview.settle_time = preview.settle_time.max(0.0);
+                });
+                self.dispatches_per_frame = self.dispatches_per_frame.clamp(1, 64);
+                ui.checkbox(&mut self.pause_when_unfocused, "Pause When Unfocused");
+                ui.checkbox(&mut self.show_crosshair, "Crosshair");
+                ui.checkbox(&mut self.show_minimap, "Minimap");
+                #[cfg(not(target_arch = "wasm32"))]
+                ui.checkbox(&mut self.show_render_queue, "Render Queue");
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.tiled, "Tiled");
+                    ui.add_enabled(
+                        self.tiled,
+                        egui::DragValue::new(&mut self.tile_size)
+                            .speed(1.0)
+                            .clamp_range(16..=4096),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.toggle_value(&mut self.drawing_region, "Draw Region")
+                        .on_hover_text("Drag on the viewport to only render that part of it");
+                    if ui
+                        .add_enabled(
+                            self.render_region.is_some(),
+                            egui::Button::new("Clear Region"),
+                        )
+                        .clicked()
+                    {
+                        self.render_region = None;
+                    }
+                    if let Some(region) = self.render_region {
+                        ui.label(format!("{}x{}", region.width, region.height));
+                    }
+                });
+                ui.toggle_value(&mut self.inspecting_pixels, "Inspect Pixel")
+                    .on_hover_text(
+                        "Click on the viewport to see every bounce of a ray through that pixel",
+                    );
+                ui.horizontal(|ui| {
+                    ui.label("Object Format: ");
+                    egui::ComboBox::from_id_source("Object Format")
+                        .selected_text(self.object_format.name())
+                        .show_ui(ui, |ui| {
+                            for object_format in ObjectFormat::ALL {
+                                ui.selectable_value(
+                                    &mut self.object_format,
+                                    object_format,
+                                    object_format.name(),
+                                );
+                            }
+                        });
+                })
+                .response
+                .on_hover_text(format!(
+                    "Packed stores positions as half floats, a third less memory for hyper spheres \
+                     and half for hyper planes, but less precise. \
+                     Auto packs scenes with at least {} objects",
+                    ObjectFormat::AUTO_PACK_OBJECTS
+                ));
+                ui.horizontal(|ui| {
+                    ui.label("BVH: ");
+                    egui::ComboBox::from_id_source("BVH")
+                        .selected_text(self.bvh_mode.name())
+                        .show_ui(ui, |ui| {
+                            for bvh_mode in BvhMode::ALL {
+                                ui.selectable_value(&mut self.bvh_mode, bvh_mode, bvh_mode.name());
+                            }
+                        });
+                })
+                .response
+                .on_hover_text(format!(
+                    "Groups the hyper spheres that don't move into boxes so rays skip most of them. \
+                     Median builds quickly for editing, Binned SAH takes longer but renders \
+                     faster. Auto uses Median with at least {} hyper spheres",
+                    BvhMode::AUTO_BVH_HYPER_SPHERES
+                ));
+                ui.checkbox(&mut self.wavefront, "Wavefront").on_hover_text(
+                    "Traces a wave of paths one bounce at a time in separate kernels, which keeps \
+                     the GPU busier with many samples, bounces or complex materials. Path \
+                     tracing without light groups, AOVs or the stereographic projection only",
+                );
+                let (tiles_rendered, tile_count) = self.renderer.tile_progress();
+                if tile_count > 1 {
+                    ui.add(
+                        egui::ProgressBar::new(tiles_rendered as f32 / tile_count as f32)
+                            .text(format!("Tile {tiles_rendered}/{tile_count}")),
+                    );
+                }
+                ui.collapsing("Preferences", |ui| {
+                    let mut changed = false;
+                    ui.horizontal(|ui| {
+                        ui.label("Adapter: ");
+                        egui::ComboBox::from_id_source("Adapter")
+                            .selected_text(self.config.adapter_name.as_deref().unwrap_or("Auto"))
+                            .show_ui(ui, |ui| {
+                                if ui
+                                    .selectable_label(self.config.adapter_name.is_none(), "Auto")
+                                    .clicked()
+                                {
+                                    self.config.adapter_name = None;
+                                    self.config.backends = Config::default().backends;
+                                    changed = true;
+                                }
+                                for adapter in &self.adapters {
+                                    let selected = self.config.adapter_name.as_deref()
+                                        == Some(adapter.name.as_str())
+                                        && self.config.backends
+                                            == backend_to_backends(adapter.backend);
+                                    if ui
+                                        .selectable_label(
+                                            selected,
+                                            format!("{} ({:?})", adapter.name, adapter.backend),
+                                        )
+                                        .clicked()
+                                    {
+                                        self.config.adapter_name = Some(adapter.name.clone());
+                                        self.config.backends = backend_to_backends(adapter.backend);
+                                        self.config.power_preference =
+                                            adapter_power_preference(adapter.device_type);
+                                        changed = true;
+                                    }
+                                }
+                            })
+                            .response
+                            .on_hover_text(
+                                "Picked by its backend and power preference, two of the same kind of GPU on one backend can't be told apart",
+                            );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Power Preference: ");
+                        egui::ComboBox::from_id_source("Power Preference")
+                            .selected_text(power_preference_to_str(self.config.power_preference))
+                            .show_ui(ui, |ui| {
+                                for power_preference in [
+                                    wgpu::PowerPreference::LowPower,
+                                    wgpu::PowerPreference::HighPerformance,
+                                ] {
+                                    changed |= ui
+                                        .selectable_value(
+                                            &mut self.config.power_preference,
+                                            power_preference,
+                                            power_preference_to_str(power_preference),
+                                        )
+                                        .changed();
+                                }
+                            });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Present Mode: ");
+                        egui::ComboBox::from_id_source("Present Mode")
+                            .selected_text(present_mode_to_str(self.config.present_mode))
+                            .show_ui(ui, |ui| {
+                                for present_mode in PRESENT_MODES {
+                                    changed |= ui
+                                        .selectable_value(
+                                            &mut self.config.present_mode,
+                                            present_mode,
+                                            present_mode_to_str(present_mode),
+                                        )
+                                        .changed();
+                                }
+                            })
+                            .response
+                            .on_hover_text(
+                                "Applies on restart. Auto no vsync uses mailbox or immediate \
+                                 when the display supports them",
+                            );
+                    });
+                    ui.horizontal(|ui| {
+                        let mut capped = self.config.fps_cap.is_some();
+                        if ui.checkbox(&mut capped, "FPS Cap: ").changed() {
+                            self.config.fps_cap = capped.then_some(60.0);
+                            changed = true;
+                        }
+                        if let Some(fps_cap) = &mut self.config.fps_cap {
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(fps_cap)
+                                        .speed(1)
+                                        .clamp_range(1.0..=1000.0),
+                                )
+                                .changed();
+                        }
+                    });
+                    ui.separator();
+                    ui.label("Defaults when the editor starts:");
+                    ui.horizontal(|ui| {
+                        ui.label("Resolution Scale: ");
+                        changed |= ui
+                            .add(
+                                egui::DragValue::new(&mut self.config.resolution_scale)
+                                    .speed(0.01)
+                                    .clamp_range(Config::RESOLUTION_SCALES),
+                            )
+                            .changed();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Sample Count: ");
+                        changed |= ui
+                            .add(
+                                egui::DragValue::new(&mut self.config.sample_count)
+                                    .clamp_range(1..=u32::MAX),
+                            )
+                            .changed();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Bounce Count: ");
+                        changed |= ui
+                            .add(
+                                egui::DragValue::new(&mut self.config.bounce_count)
+                                    .clamp_range(1..=u32::MAX),
+                            )
+                            .changed();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Movement Speed: ");
+                        changed |= ui
+                            .add(
+                                egui::DragValue::new(&mut self.config.movement_speed)
+                                    .speed(0.05)
+                                    .clamp_range(0.001..=10000.0),
+                            )
+                            .changed();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Theme: ");
+                        egui::ComboBox::from_id_source("Theme")
+                            .selected_text(self.config.theme.name())
+                            .show_ui(ui, |ui| {
+                                for theme in Theme::ALL {
+                                    if ui
+                                        .selectable_value(
+                                            &mut self.config.theme,
+                                            theme,
+                                            theme.name(),
+                                        )
+                                        .changed()
+                                    {
+                                        ui.ctx().set_visuals(theme_visuals(theme));
+                                        changed = true;
+                                    }
+                                }
+                            });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Startup Scene: ");
+                        let mut startup_scene = self
+                            .config
+                            .startup_scene
+                            .as_ref()
+                            .map(|path| path.display().to_string())
+                            .unwrap_or_default();
+                        if ui.text_edit_singleline(&mut startup_scene).changed() {
+                            self.config.startup_scene =
+                                (!startup_scene.is_empty()).then(|| startup_scene.into());
+                            changed = true;
+                        }
+                    });
+                    if changed {
+                        if let Err(error) = self.config.save() {
+                            log::error!("Failed to save config: {error}");
+                        }
+                    }
+                    ui.label("Adapter and present mode changes take effect after a restart");
+                });
+            }
+            Panel::Console => {
+                ui.collapsing("Script", |ui| {
+                    ui.add(
+                        egui::TextEdit::multiline(&mut self.script_source)
+                            .code_editor()
+                            .desired_rows(12)
+                            .desired_width(f32::INFINITY),
+                    );
+                    ui.horizontal(|ui| {
+                        if ui.button("Run").clicked() {
+                            self.perform(Action::RunScript);
+                        }
+                        ui.add_enabled(
+                            self.script
+                                .as_ref()
+                                .is_some_and(|script| script.has_function("update")),
+                            egui::Checkbox::new(&mut self.script_animate, "Animate"),
+                        );
+                        if ui.button("Clear Console").clicked() {
+                            self.script_console.clear();
+                        }
+                    });
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        ui.horizontal(|ui| {
+                            ui.label("Scene File: ");
+                            ui.text_edit_singleline(&mut self.scene_path);
+                        });
+                        ui.horizontal(|ui| {
+                            if ui.button("Save Scene").clicked() {
+                                self.perform(Action::SaveScene);
+                            }
+                            if self.unsaved_changes {
+                                ui.label("Unsaved changes");
+                            }
+                        });
+                        if let Some(message) = &self.scene_message {
+                            ui.label(message);
+                        }
+                    }
+                    for message in &self.script_console {
+                        ui.label(message);
+                    }
+                });
+                ui.collapsing("Log", |ui| {
+                    let logger = ConsoleLogger::global();
+                    ui.horizontal(|ui| {
+                        ui.label("Level: ");
+                        egui::ComboBox::from_id_source("Log Level")
+                            .selected_text(self.log_level.as_str())
+                            .show_ui(ui, |ui| {
+                                for level in log::LevelFilter::iter().skip(1) {
+                                    ui.selectable_value(&mut self.log_level, level, level.as_str());
+                                }
+                            });
+                        if ui.button("Clear").clicked() {
+                            logger.clear();
+                        }
+                    });
+                    egui::ScrollArea::vertical()
+                        .max_height(300.0)
+                        .stick_to_bottom(true)
+                        .show(ui, |ui| {
+                            for entry in logger.entries().iter() {
+                                if entry.level > self.log_level {
+                                    continue;
+                                }
+                                let text = format!("[{}] {}", entry.level, entry.message);
+                                let label = match entry.level {
+                                    log::Level::Error => {
+                                        ui.colored_label(ui.visuals().error_fg_color, text)
+                                    }
+                                    log::Level::Warn => {
+                                        ui.colored_label(ui.visuals().warn_fg_color, text)
+                                    }
+                                    _ => ui.label(text),
+                                };
+                                label.on_hover_text(&entry.target);
+                            }
+                        });
+                });
+            }
+        };
+        for (side, dock) in [
+            (egui::panel::Side::Left, Dock::Left),
+            (egui::panel::Side::Right, Dock::Right),
+        ] {
+            let panels = layout.docked(dock);
+            if panels.is_empty() {
+                continue;
+            }
+            egui::SidePanel::new(side, dock.name()).show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for panel in panels {
+                        egui::CollapsingHeader::new(egui::RichText::new(panel.name()).heading())
+                            .default_open(true)
+                            .show(ui, |ui| show_panel(ui, panel));
+                    }
+                    ui.allocate_space(ui.available_size());
+                });
+            });
+        }
+        for panel in layout.docked(Dock::Floating) {
+            egui::Window::new(panel.name())
+                .vscroll(true)
+                .show(ctx, |ui| show_panel(ui, panel));
+        }
+        if !std::mem::take(&mut self.scene_replaced) && self.scene.changes != changes_before_panel {
+            self.unsaved_changes = true;
+        }
+
+        self.physics.step(&mut self.scene, ts);
+        if let Err(error) = self.animator.update(&mut self.scene, ts) {
+            self.animation_error = Some(error);
+        }
+        let mut removed_clones = vec![];
+        for modifier in &mut self.array_modifiers {
+            removed_clones.extend(modifier.update(&mut self.scene));
+        }
+        self.array_modifiers
+            .retain(|modifier| self.scene.object_ref(modifier.source).is_some());
+        self.remove_objects(&removed_clones);
+
+        if self.script_animate {
+            if let Some(script) = &mut self.script {
+                self.script_time += ts;
+                if let Err(error) = script.call(
+                    "update",
+                    &[Value::Number(self.script_time as _), Value::Number(ts as _)],
+                    &mut self.scene,
+                    &mut self.script_console,
+                ) {
+                    self.script_console.push(error);
+                    self.script_animate = false;
+                }
+            }
+        }
+
+        egui::CentralPanel::default()
+            .frame(egui::Frame::default().fill(ctx.style().visuals.panel_fill))
+            .show(ctx, |ui| {
+                let eframe::egui_wgpu::RenderState {
+                    device,
+                    queue,
+                    renderer: egui_renderer,
+                    ..
+                } = &render_state;
+
+                let size = ui.available_size() * self.resolution_scale;
+                let size = (size.x.max(1.0) as usize, size.y.max(1.0) as usize);
+
+                let mut recreated = false;
+                if let Some(backend) = self.new_backend.take() {
+                    self.renderer = backend.create(device, queue);
+                    self.backend = backend;
+                    self.scene.changes = SceneChanges::ALL;
+                    recreated = true;
+                }
+
+                // long renders are split up so no single submission can hit a GPU timeout
+                self.renderer
+                    .set_tile_size(device, self.tiled.then_some(self.tile_size));
+                if self.renderer.set_object_format(self.object_format) {
+                    // the objects have to be uploaded again in the new format
+                    self.scene.changes.hyper_spheres = true;
+                }
+                if self.renderer.set_bvh_mode(self.bvh_mode) {
+                    // the BVH is built when the objects are uploaded
+                    self.scene.changes.hyper_spheres = true;
+                }
+                // both trace the same samples, so the accumulation carries on
+                self.renderer.set_wavefront(self.wavefront);
+
+                let resized = self.renderer.resize(device, queue, size.0, size.1);
+                if resized {
+                    // the region is in pixels, it doesn't cover the same part of a different size
+                    self.render_region = None;
+                }
+                self.renderer.set_region(device, self.render_region);
+                let output_changed = resized || recreated;
+                if let Some(post_processor) = &mut self.post_processor {
+                    if output_changed {
+                        post_processor.set_input(
+                            device,
+                            self.renderer.texture_view(),
+                            size.0,
+                            size.1,
+                        );
+                    }
+                }
+                let post_processor = self
+                    .post_processor
+                    .as_ref()
+                    .filter(|_| self.post_processing && !self.post_effects.is_empty());
+                let source_changed =
+                    output_changed || post_processor.is_some() != self.displaying_post;
+                self.displaying_post = post_processor.is_some();
+                let source = match post_processor {
+                    Some(post_processor) => post_processor.texture_view(),
+                    None => self.renderer.texture_view(),
+                };
+                if let Some(exposure_analysis) = &mut self.exposure_analysis {
+                    if source_changed {
+                        exposure_analysis.set_input(device, source, size.0, size.1);
+                    }
+                }
+                let false_color = self.false_color && self.exposure_analysis.is_some();
+                if source_changed || false_color != self.displaying_false_color {
+                    self.displaying_false_color = false_color;
+                    let output = match &self.exposure_analysis {
+                        Some(exposure_analysis) if false_color => {
+                            exposure_analysis.false_color_texture_view()
+                        }
+                        _ => source,
+                    };
+                    if self.display.set_output(device, output, size.0, size.1) {
+                        egui_renderer.write().update_egui_texture_from_wgpu_texture(
+                            device,
+                            self.display.texture_view(),
+                            wgpu::FilterMode::Nearest,
+                            self.texture_id,
+                        );
+                    }
+                }
+
+                let show_path_markers = self.show_path_markers && self.inspected_path.is_some();
+                if std::mem::take(&mut self.path_markers_changed)
+                    || show_path_markers != self.showing_path_markers
+                {
+                    self.showing_path_markers = show_path_markers;
+                    self.scene.changes.hyper_spheres = true;
+                    self.scene.changes.materials = true;
+                }
+
+                // the full settings coming back also changes the camera, so the average restarts
+                self.motion_preview.update(&self.camera, ts);
+                let camera = self.motion_preview.apply(&self.camera);
+
+                // light groups only need a new average when they aren't accumulated apart
+                let light_group_weights = self.scene.light_group_weights();
+                let light_groups_reweighted = light_group_weights
+                    != self.previous_light_group_weights
+                    && !(camera.render_options().light_groups
+                        && self.backend == Backend::Gpu(RenderPath::Compute));
+                self.previous_light_group_weights = light_group_weights;
+
+                // anything that changes the image means starting the average again
+                let reset = std::mem::take(&mut self.reset_accumulation)
+                    || !self.accumulate
+                    || camera != self.previous_camera
+                    || self.scene.changes != SceneChanges::NONE
+                    || light_groups_reweighted;
+                self.previous_camera = camera;
+                if reset {
+                    self.renderer.reset_accumulation();
+                }
+
+                // the changes are kept on failure so the upload is retried next frame
+                let uploaded = match &self.inspected_path {
+                    // the markers only exist in the renderer's copy so they can't be edited or saved
+                    Some(path)
+                        if self.showing_path_markers
+                            && self.scene.changes != SceneChanges::NONE =>
+                    {
+                        let mut scene = self.scene.clone();
+                        let [hit, segment] = PixelPath::marker_materials();
+                        let hit = scene.add_material("Path Hit", hit);
+                        let segment = scene.add_material("Path Segment", segment);
+                        for marker in path.markers(hit, segment, self.path_marker_radius) {
+                            scene.add_hyper_sphere("Path Marker", marker);
+                        }
+                        // the markers' material indices move with the scene's materials
+                        scene.changes.hyper_spheres |= self.scene.changes.materials;
+                        self.renderer.update_scene(device, queue, &scene)
+                    }
+                    _ => self.renderer.update_scene(device, queue, &self.scene),
+                };
+                match uploaded {
+                    Ok(()) => self.scene.changes = SceneChanges::NONE,
+                    Err(error) => log::error!("Failed to upload the scene: {error}"),
+                }
+
+                #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+                if self.shader_watcher.poll() {
+                    self.renderer.reload_shaders();
+                }
+
+                // while nothing is changing several renders can be done per frame so convergence
+                // isn't limited by the UI frame rate, but only one while interacting to stay responsive
+                let dispatches = if device_lost
+                    || self.pause_when_unfocused && !ctx.input(|i| i.raw.has_focus)
+                {
+                    0
+                } else if reset {
+                    1
+                } else {
+                    self.dispatches_per_frame
+                };
+                for _ in 0..dispatches {
+                    // each render needs its own submit so it sees its own camera seed
+                    if let Err(error) = self.renderer.update_camera(
+                        queue,
+                        &GpuCamera {
+                            light_group_weights,
+                            ..camera.to_gpu(self.frame_index)
+                        },
+                    ) {
+                        log::error!("Failed to upload the camera: {error}");
+                        break;
+                    }
+                    self.frame_index = self.frame_index.wrapping_add(1);
+
+                    let mut encoder =
+                        device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                            label: Some("Ray Tracing Command Encoder"),
+                        });
+                    self.renderer
+                        .render(device, queue, &mut encoder, camera.render_options());
+                    queue.submit([encoder.finish()]);
+                }
+                if let Some(exposure_analysis) = &mut self.exposure_analysis {
+                    // picks up the histogram from an earlier frame once it's ready
+                    device.poll(wgpu::Maintain::Poll);
+                    if let Err(error) = exposure_analysis.poll_readback() {
+                        log::error!("Failed to read back the histogram: {error}");
+                    }
+                }
+                if !device_lost {
+                    let mut encoder =
+                        device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                            label: Some("Display Command Encoder"),
+                        });
+                    if let Some(post_processor) = post_processor {
+                        post_processor.render(
+                            queue,
+                            &mut encoder,
+                            &self.post_effects,
+                            self.camera.color_management,
+                            self.frame_index,
+                        );
+                    }
+                    let mut histogram_recorded = false;
+                    if let Some(exposure_analysis) = &self.exposure_analysis {
+                        if self.displaying_false_color {
+                            if let Err(error) = exposure_analysis.record_false_color(
+                                queue,
+                                &mut encoder,
+                                self.camera.color_management,
+                            ) {
+                                log::error!("Failed to draw the false color view: {error}");
+                            }
+                        }
+                        histogram_recorded =
+                            self.show_histogram && exposure_analysis.record_histogram(&mut encoder);
+                    }
+                    self.display.render(&mut encoder);
+                    queue.submit([encoder.finish()]);
+                    if let (Some(exposure_analysis), true) =
+                        (&mut self.exposure_analysis, histogram_recorded)
+                    {
+                        exposure_analysis.start_readback();
+                    }
+                }
+
+                let (width, height) = self.renderer.size();
+                // viewport points per rendered pixel
+                let pixel_size = 1.0 / self.resolution_scale;
+                let response = ui.add(
+                    egui::Image::new(
+                        self.texture_id,
+                        egui::vec2(width as _, height as _) * pixel_size,
+                    )
+                    .sense(if self.drawing_region {
+                        egui::Sense::drag()
+                    } else {
+                        egui::Sense::click()
+                    }),
+                );
+                if self.drawing_region {
+                    if response.drag_started() {
+                        self.region_drag_start = response.interact_pointer_pos();
+                    }
+                    if let (Some(start), Some(end)) =
+                        (self.region_drag_start, response.interact_pointer_pos())
+                    {
+                        let rect = egui::Rect::from_two_pos(start, end)
+                            .intersect(response.rect)
+                            .translate(-response.rect.min.to_vec2());
+                        let rect = egui::Rect::from_min_max(
+                            (rect.min.to_vec2() / pixel_size).to_pos2(),
+                            (rect.max.to_vec2() / pixel_size).to_pos2(),
+                        );
+                        self.render_region = Some(Tile {
+                            x: rect.left() as _,
+                            y: rect.top() as _,
+                            width: (rect.width().round() as usize).max(1),
+                            height: (rect.height().round() as usize).max(1),
+                        });
+                    }
+                    if response.drag_released() {
+                        self.region_drag_start = None;
+                        self.drawing_region = false;
+                    }
+                }
+                if let Some(region) = self.render_region {
+                    let min =
+                        response.rect.min + egui::vec2(region.x as _, region.y as _) * pixel_size;
+                    ui.painter_at(response.rect).rect_stroke(
+                        egui::Rect::from_min_size(
+                            min,
+                            egui::vec2(region.width as _, region.height as _) * pixel_size,
+                        ),
+                        0.0,
+                        egui::Stroke::new(1.0, egui::Color32::YELLOW),
+                    );
+                }
+                let gpu_camera = self.camera.to_gpu(self.frame_index);
+                let aspect = width as f32 / height as f32;
+                if let Some(pointer) = response
+                    .interact_pointer_pos()
+                    .filter(|_| response.clicked())
+                {
+                    let offset = pointer - response.rect.min;
+                    let uv = offset / response.rect.size();
+                    let ray = if self.camera.stereo.is_some() {
+                        stereo_camera_ray
+                    } else {
+                        camera_ray
+                    };
+                    let (origin, direction) = ray(
+                        &gpu_camera,
+                        cgmath::vec2(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0),
+                        aspect,
+                    );
+                    let hit = pick(&self.scene, origin, direction);
+                    if self.inspecting_pixels {
+                        match self.renderer.inspect_pixel(
+                            device,
+                            queue,
+                            self.camera.render_options(),
+                            ((offset.x / pixel_size) as _, (offset.y / pixel_size) as _),
+                        ) {
+                            Ok(Some(path)) => {
+                                self.inspected_path = Some(path);
+                                self.path_markers_changed = true;
+                            }
+                            Ok(None) => log::warn!(
+                                "The {} backend can't inspect pixels",
+                                self.backend.name()
+                            ),
+                            Err(error) => log::error!("Failed to inspect the pixel: {error}"),
+                        }
+                    } else if let (Some(slot), Some(hit)) = (self.measure_picking, hit) {
+                        self.measure_points[slot] = Some(MeasurePoint::Point {
+                            position: hit.position,
+                            normal: hit.normal,
+                        });
+                        self.measure_picking = None;
+                    } else if let Some(hit) = hit.filter(|_| ui.input(|i| i.modifiers.command)) {
+                        if let Some(id) = self.scene.object_id(hit.object) {
+                            self.selection.toggle(id);
+                        }
+                    }
+                }
+
+                if let Some(path) = &self.inspected_path {
+                    let center = response.rect.min
+                        + egui::vec2(path.pixel.0 as f32 + 0.5, path.pixel.1 as f32 + 0.5)
+                            * pixel_size;
+                    ui.painter_at(response.rect).circle_stroke(
+                        center,
+                        4.0,
+                        egui::Stroke::new(1.0, egui::Color32::LIGHT_BLUE),
+                    );
+                }
+
+                if self.show_crosshair {
+                    let painter = ui.painter_at(response.rect);
+                    let center = response.rect.center();
+                    // one in the middle of each eye, both read out what is in front of the camera
+                    let eye_shift = egui::vec2(response.rect.width() * 0.25, 0.0);
+                    let crosshairs = if self.camera.stereo.is_some() {
+                        vec![center - eye_shift, center + eye_shift]
+                    } else {
+                        vec![center]
+                    };
+                    for crosshair in crosshairs {
+                        for stroke in [
+                            egui::Stroke::new(3.0, egui::Color32::BLACK),
+                            egui::Stroke::new(1.0, egui::Color32::WHITE),
+                        ] {
+                            painter.line_segment(
+                                [
+                                    crosshair - egui::vec2(8.0, 0.0),
+                                    crosshair + egui::vec2(8.0, 0.0),
+                                ],
+                                stroke,
+                            );
+                            painter.line_segment(
+                                [
+                                    crosshair - egui::vec2(0.0, 8.0),
+                                    crosshair + egui::vec2(0.0, 8.0),
+                                ],
+                                stroke,
+                            );
+                        }
+                    }
+
+                    let (origin, direction) =
+                        camera_ray(&gpu_camera, cgmath::vec2(0.0, 0.0), aspect);
+                    let readout = match pick(&self.scene, origin, direction) {
+                        Some(hit) => {
+                            let name = self.scene.object_name(hit.object).unwrap_or_default();
+                            let material = self
+                                .scene
+                                .object_material(hit.object)
+                                .and_then(|material| self.scene.material_name(material))
+                                .unwrap_or("Invalid");
+                            format!(
+                                "{name}\nDistance: {:.3}\nMaterial: {material}",
+                                hit.distance
+                            )
+                        }
+                        None => "Nothing".into(),
+                    };
+                    let position = center + egui::vec2(12.0, 12.0);
+                    let font = egui::FontId::monospace(12.0);
+                    painter.text(
+                        position + egui::vec2(1.0, 1.0),
+                        egui::Align2::LEFT_TOP,
+                        &readout,
+                        font.clone(),
+                        egui::Color32::BLACK,
+                    );
+                    painter.text(
+                        position,
+                        egui::Align2::LEFT_TOP,
+                        readout,
+                        font,
+                        egui::Color32::WHITE,
+                    );
+                }
+            });
+
+        let mut show_inspected_path = self.inspected_path.is_some();
+        if let Some(path) = &self.inspected_path {
+            egui::Window::new("Pixel Path")
+                .open(&mut show_inspected_path)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        self.path_markers_changed |= ui
+                            .checkbox(&mut self.show_path_markers, "Show In Scene")
+                            .changed();
+                        ui.label("Radius: ");
+                        self.path_markers_changed |= ui
+                            .add(
+                                egui::DragValue::new(&mut self.path_marker_radius)
+                                    .speed(0.005)
+                                    .clamp_range(0.005..=1.0),
+                            )
+                            .changed();
+                    });
+                    draw_pixel_path(ui, path, &self.scene.material_names);
+                });
+        }
+        if !show_inspected_path {
+            self.inspected_path = None;
+        }
+
+        // the material may have been removed since the editor was opened
+        self.node_editor_material = self
+            .node_editor_material
+            .filter(|&id| self.scene.material_index(id).is_some());
+        let mut show_node_editor = self.node_editor_material.is_some();
+        if let Some(id) = self.node_editor_material {
+            let name = self.scene.material_name(id).unwrap_or_default();
+            egui::Window::new(format!("{name} Nodes"))
+                .id(egui::Id::new("Material Nodes"))
+                .open(&mut show_node_editor)
+                .default_size([720.0, 460.0])
+                .show(ctx, |ui| {
+                    let graph = self.scene.node_graphs.entry(id).or_default();
+                    if let Err(error) = graph.compile() {
+                        ui.colored_label(ui.visuals().warn_fg_color, error.message());
+                    }
+                    // after the panels' edits are checked for, so it is marked unsaved here
+                    if edit_node_graph(ui, graph) {
+                        self.scene.changes.materials = true;
+                        self.unsaved_changes = true;
+                    }
+                });
+        }
+        if !show_node_editor {
+            self.node_editor_material = None;
+        }
+
+        egui::Window::new("Gamepad Bindings")
+            .open(&mut self.show_gamepad_bindings)
+            .show(ctx, |ui| {
+                if self.gamepad.connected() {
+                    ui.label("Controller connected");
+                } else if Gamepad::SUPPORTED {
+                    ui.colored_label(ui.visuals().warn_fg_color, "No controller found");
+                } else {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        "Controllers are only supported on Linux",
+                    );
+                }
+                ui.add(
+                    egui::Slider::new(&mut self.gamepad_bindings.dead_zone, 0.0..=0.9)
+                        .text("Dead Zone"),
+                );
+                let state = *self.gamepad.state();
+                let dead_zone = self.gamepad_bindings.dead_zone;
+                let mut removed = None;
+                egui::Grid::new("Gamepad Bindings Grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (index, binding) in
+                            self.gamepad_bindings.bindings.iter_mut().enumerate()
+                        {
+                            egui::ComboBox::from_id_source(("gamepad control", index))
+                                .selected_text(binding.control.name())
+                                .show_ui(ui, |ui| {
+                                    for control in GamepadControl::ALL {
+                                        ui.selectable_value(
+                                            &mut binding.control,
+                                            control,
+                                            control.name(),
+                                        );
+                                    }
+                                });
+                            egui::ComboBox::from_id_source(("gamepad input", index))
+                                .selected_text(binding.input.name())
+                                .show_ui(ui, |ui| {
+                                    for input in GamepadInput::all() {
+                                        ui.selectable_value(
+                                            &mut binding.input,
+                                            input,
+                                            input.name(),
+                                        );
+                                    }
+                                });
+                            ui.add(
+                                egui::DragValue::new(&mut binding.scale)
+                                    .prefix("scale: ")
+                                    .speed(0.01),
+                            );
+                            ui.add(
+                                egui::ProgressBar::new(state.value(binding.input, dead_zone).abs())
+                                    .desired_width(60.0),
+                            );
+                            if ui.button("Remove").clicked() {
+                                removed = Some(index);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(index) = removed {
+                    self.gamepad_bindings.bindings.remove(index);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Add Binding").clicked() {
+                        self.gamepad_bindings.bindings.push(GamepadBinding {
+                            input: GamepadInput::Button(GamepadButton::South),
+                            control: GamepadControl::MoveForward,
+                            scale: 1.0,
+                        });
+                    }
+                    if ui.button("Reset To Defaults").clicked() {
+                        self.gamepad_bindings = GamepadBindings::default();
+                    }
+                });
+            });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if self.scene_load.is_none() {
+                if let Some((output, (width, height), exit)) = self.pending_screenshot.take() {
+                    self.render_queue.push(crate::RenderJob {
+                        name: "Screenshot".into(),
+                        scene: crate::JobScene::Snapshot(Box::new(self.scene.clone())),
+                        camera: self.camera,
+                        width,
+                        height,
+                        samples: self.camera.sample_count,
+                        output,
+                        caption: self.caption_enabled.then(|| self.caption.clone()),
+                    });
+                    self.exit_when_rendered |= exit;
+                }
+            }
+            if !device_lost {
+                let eframe::egui_wgpu::RenderState { device, queue, .. } = &render_state;
+                self.render_queue.step(device, queue);
+            }
+            if self.exit_when_rendered && !self.render_queue.is_busy() {
+                self.allow_close = true;
+                frame.close();
+            }
+            egui::Window::new("Render Queue")
+                .open(&mut self.show_render_queue)
+                .vscroll(true)
+                .show(ctx, |ui| {
+                    ui.checkbox(&mut self.queue_from_file, "From Scene File");
+                    ui.add_enabled_ui(self.queue_from_file, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Scene File: ");
+                            ui.text_edit_singleline(&mut self.queue_scene_path);
+                        });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Size: ");
+                        ui.add(
+                            egui::DragValue::new(&mut self.queue_size.0)
+                                .clamp_range(1..=16384)
+                                .suffix("px"),
+                        );
+                        ui.label("x");
+                        ui.add(
+                            egui::DragValue::new(&mut self.queue_size.1)
+                                .clamp_range(1..=16384)
+                                .suffix("px"),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Samples: ");
+                        ui.add(
+                            egui::DragValue::new(&mut self.queue_samples).clamp_range(1..=1 << 20),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Output: ");
+                        ui.text_edit_singleline(&mut self.queue_output)
+                            .on_hover_text("A .png, or a .exr for the linear HDR image");
+                    });
+                    if ui
+                        .button("Add Job")
+                        .on_hover_text("Renders with the camera as it is now")
+                        .clicked()
+                    {
+                        let scene = if self.queue_from_file {
+                            crate::JobScene::File(self.queue_scene_path.clone().into())
+                        } else {
+                            crate::JobScene::Snapshot(Box::new(self.scene.clone()))
+                        };
+                        self.render_queue.push(crate::RenderJob {
+                            name: format!("Job {}", self.render_queue.jobs().len() + 1),
+                            scene,
+                            camera: self.camera,
+                            width: self.queue_size.0,
+                            height: self.queue_size.1,
+                            samples: self.queue_samples,
+                            output: self.queue_output.clone().into(),
+                            caption: self.caption_enabled.then(|| self.caption.clone()),
+                        });
+                    }
+                    ui.separator();
+
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.render_queue.paused, "Paused");
+                        if ui.button("Clear Finished").clicked() {
+                            self.render_queue.clear_finished();
+                        }
+                    });
+                    let mut removed = None;
+                    for (index, (job, status)) in self.render_queue.jobs().iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(format!(
+                                "{}: {}x{}, {} samples to {}",
+                                job.name,
+                                job.width,
+                                job.height,
+                                job.samples,
+                                job.output.display()
+                            ));
+                            match status {
+                                crate::JobStatus::Queued => {
+                                    ui.label("Queued");
+                                }
+                                crate::JobStatus::Rendering(progress) => {
+                                    ui.add(
+                                        egui::ProgressBar::new(*progress)
+                                            .desired_width(120.0)
+                                            .show_percentage(),
+                                    );
+                                }
+                                crate::JobStatus::Done => {
+                                    ui.label("Done");
+                                }
+                                crate::JobStatus::Failed(error) => {
+                                    ui.colored_label(ui.visuals().error_fg_color, error);
+                                }
+                            }
+                            if self.render_queue.active_index() != Some(index)
+                                && ui.button("Remove").clicked()
+                            {
+                                removed = Some(index);
+                            }
+                        });
+                    }
+                    if let Some(index) = removed {
+                        self.render_queue.remove(index);
+                    }
+                });
+        }
+
+        egui::Window::new("Minimap")
+            .open(&mut self.show_minimap)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Range: ");
+                    ui.add(
+                        egui::DragValue::new(&mut self.minimap_range)
+                            .speed(0.1)
+                            .clamp_range(1.0..=1000.0),
+                    );
+                });
+                let camera = self.camera.to_gpu(self.frame_index);
+                let mut teleport = None;
+                ui.horizontal(|ui| {
+                    for (name, vertical_axis) in [("XZ", 2), ("XW", 3)] {
+                        ui.vertical(|ui| {
+                            ui.label(name);
+                            teleport = teleport.or(draw_minimap(
+                                ui,
+                                &self.scene,
+                                &camera,
+                                &self.camera.w_ramp,
+                                self.camera.color_management,
+                                self.minimap_range,
+                                vertical_axis,
+                            ));
+                        });
+                    }
+                });
+                if let Some(delta) = teleport {
+                    self.camera.position += delta;
+                }
+                ui.label("Colored by w like the W Coordinate render mode, click to move there");
+            });
+
+        let camera_rotation_speed: f32 = 90.0f32.to_radians() * 1.5;
+        let mut target_velocity = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+        if !ctx.wants_keyboard_input() {
+            ctx.input(|i| {
+                if i.key_pressed(egui::Key::Escape) {
+                    self.fly_mode = false;
+                }
+
+                let camera_speed = if i.modifiers.alt {
+                    self.camera_speed * SPRINT_MULTIPLIER
+                } else {
+                    self.camera_speed
+                };
+                let mut direction = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+                if i.key_down(egui::Key::W) {
+                    direction += camera_forward;
+                }
+                if i.key_down(egui::Key::S) {
+                    direction -= camera_forward;
+                }
+                if i.key_down(egui::Key::A) {
+                    direction -= camera_right;
+                }
+                if i.key_down(egui::Key::D) {
+                    direction += camera_right;
+                }
+                if i.key_down(egui::Key::Q) {
+                    direction -= camera_up;
+                }
+                if i.key_down(egui::Key::E) {
+                    direction += camera_up;
+                }
+                if i.key_down(egui::Key::F) {
+                    direction -= camera_ana;
+                }
+                if i.key_down(egui::Key::R) {
+                    direction += camera_ana;
+                }
+                target_velocity = direction * camera_speed;
+
+                let turn = camera_rotation_speed * ts;
+                let mut rotation = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+                if i.key_down(egui::Key::ArrowUp) {
+                    rotation.x += turn;
+                }
+                if i.key_down(egui::Key::ArrowDown) {
+                    rotation.x -= turn;
+                }
+                if i.key_down(egui::Key::ArrowLeft) {
+                    rotation.y -= turn;
+                }
+                if i.key_down(egui::Key::ArrowRight) {
+                    rotation.y += turn;
+                }
+                if i.modifiers.shift {
+                    self.camera_motion
+                        .add_rotation(0.0, 0.0, rotation.x, rotation.y);
+                } else {
+                    self.camera_motion
+                        .add_rotation(rotation.x, rotation.y, 0.0, 0.0);
+                }
+            });
+        }
+
+        self.gamepad.poll();
+        if self.gamepad.connected() {
+            let controls = self.gamepad_bindings.controls(self.gamepad.state());
+            let movement = controls.movement;
+            target_velocity += (camera_right * movement.x
+                + camera_up * movement.y
+                + camera_forward * movement.z
+                + camera_ana * movement.w)
+                * self.camera_speed;
+            let turn = camera_rotation_speed * ts;
+            self.camera_motion.add_rotation(
+                controls.pitch * turn,
+                controls.yaw * turn,
+                controls.weird_pitch * turn,
+                controls.weird_yaw * turn,
+            );
+        }
+
+        // still stepped when the keyboard is taken so the camera slows down instead of drifting
+        let movement = self
+            .camera_motion
+            .movement(target_velocity, self.camera_speed, ts);
+        if self.collide {
+            self.camera.position = move_with_collisions(
+                &self.scene,
+                self.camera.position,
+                movement,
+                self.collision_radius,
+            );
+        } else {
+            self.camera.position += movement;
+        }
+
+        // eframe can't grab the cursor, so it is hidden and its movement inside the window is used instead
+        if self.fly_mode {
+            ctx.set_cursor_icon(egui::CursorIcon::None);
+            ctx.input(|i| {
+                let delta = i.pointer.delta() * MOUSE_SENSITIVITY;
+                if i.modifiers.ctrl {
+                    self.camera_motion.add_rotation(0.0, 0.0, -delta.y, delta.x);
+                } else {
+                    self.camera_motion.add_rotation(-delta.y, delta.x, 0.0, 0.0);
+                }
+                self.camera_speed *= (i.scroll_delta.y * 0.01).exp();
+            });
+        }
+        self.camera_motion.rotate(&mut self.camera, ts);
+
+        ctx.request_repaint();
+        self.previous_time = time;
+    }
+}
+
+/// The file name and source of a dropped `.script` file
+fn read_dropped_scene(file: &egui::DroppedFile) -> Result<(String, String), String> {
+    let name = file
+        .path
+        .as_ref()
+        .and_then(|path| path.file_name())
+        .map_or_else(|| file.name.clone(), |name| name.to_string_lossy().into());
+    let extension = name.rsplit_once('.').map_or("", |(_, extension)| extension);
+    if extension.eq_ignore_ascii_case("hdr") {
+        return Err(format!(
+            "{name} is an HDR environment map, but the sky can only be a gradient or the physical sky"
+        ));
+    }
+    if !extension.eq_ignore_ascii_case("script") {
+        return Err(format!("{name} isn't a .script scene file"));
+    }
+    let bytes = match (&file.bytes, &file.path) {
+        (Some(bytes), _) => bytes.to_vec(),
+        #[cfg(not(target_arch = "wasm32"))]
+        (None, Some(path)) => std::fs::read(path).map_err(|error| format!("{name}: {error}"))?,
+        _ => return Err(format!("{name} couldn't be read")),
+    };
+    String::from_utf8(bytes)
+        .map_err(|error| format!("{name}: {error}"))
+        .map(|source| (name, source))
+}
+
+fn theme_visuals(theme: Theme) -> egui::Visuals {
+    match theme {
+        Theme::Dark => egui::Visuals::dark(),
+        Theme::Light => egui::Visuals::light(),
+    }
+}
+
+/// Logs errors from `device` that nothing is waiting for, wgpu panics on them otherwise.
+/// The returned flag is set once the device is lost
+fn watch_device(device: &wgpu::Device) -> Arc<AtomicBool> {
+    let device_lost = Arc::new(AtomicBool::new(false));
+    device.on_uncaptured_error(Box::new({
+        let device_lost = device_lost.clone();
+        move |error| {
+            let error = GpuError::from(error);
+            if error.is_device_lost() {
+                if !device_lost.swap(true, Ordering::Relaxed) {
+                    log::error!("The GPU device was lost, the editor has to be restarted: {error}");
+                    #[cfg(not(target_arch = "wasm32"))]
+                    Recovery::save_snapshot();
+                }
+            } else {
+                log::error!("GPU error: {error}");
+            }
+        }
+    }));
+    device_lost
+}
+
+fn swatch_texture(
+    ctx: &egui::Context,
+    swatch_renderer: &mut SwatchRenderer,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    name: String,
+    material: &GpuMaterial,
+) -> egui::TextureHandle {
+    let image = match swatch_renderer.render(device, queue, material) {
+        Ok(pixels) => egui::ColorImage::from_rgba_unmultiplied(
+            [SwatchRenderer::SIZE, SwatchRenderer::SIZE],
+            &pixels,
+        ),
+        Err(error) => {
+            log::error!("Failed to render {name}: {error}");
+            egui::ColorImage::new(
+                [SwatchRenderer::SIZE, SwatchRenderer::SIZE],
+                egui::Color32::TRANSPARENT,
+            )
+        }
+    };
+    ctx.load_texture(name, image, egui::TextureOptions::LINEAR)
+}
+
+/// The color a pixel of the output would be shown in for the linear `color`, as the output holds
+/// bytes that are displayed as sRGB
+fn output_color(color: cgmath::Vector3<f32>, color_management: ColorManagement) -> egui::Color32 {
+    let color = color_management
+        .encode(color.map(|c| c.clamp(0.0, 1.0)))
+        .map(|c| (c * 255.0).round() as u8);
+    egui::Color32::from_rgb(color.x, color.y, color.z)
+}
+
+/// Bars for the luma bins from black on the left to white on the right, and how much is clipped
+fn draw_pixel_path(ui: &mut egui::Ui, path: &PixelPath, material_names: &[String]) {
+    let vector =
+        |v: cgmath::Vector4<f32>| format!("({:.3}, {:.3}, {:.3}, {:.3})", v.x, v.y, v.z, v.w);
+    let color = |c: cgmath::Vector3<f32>| format!("({:.3}, {:.3}, {:.3})", c.x, c.y, c.z);
+
+    ui.label(format!("Pixel: ({}, {})", path.pixel.0, path.pixel.1));
+    ui.label(format!("Color: {}", color(path.color)));
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for (i, bounce) in path.bounces.iter().enumerate() {
+            let material = material_names
+                .get(bounce.material as usize)
+                .map_or("Invalid", |name| name.as_str());
+            egui::CollapsingHeader::new(format!("Bounce {}: {material}", i + 1))
+                .default_open(true)
+                .show(ui, |ui| {
+                    ui.monospace(format!("Position:   {}", vector(bounce.position)));
+                    ui.monospace(format!("Normal:     {}", vector(bounce.normal)));
+                    ui.monospace(format!("Direction:  {}", vector(bounce.direction)));
+                    ui.monospace(format!("Throughput: {}", color(bounce.throughput)));
+                    ui.monospace(format!("Emitted:    {}", color(bounce.emitted)));
+                    let flags = [
+                        (bounce.front_face, "Front Face"),
+                        (!bounce.front_face, "Back Face"),
+                        (bounce.transmitted, "Transmitted"),
+                        (bounce.coated, "Clearcoat"),
+                        (bounce.cut, "Cross Section"),
+                    ];
+                    ui.label(
+                        flags
+                            .iter()
+                            .filter(|(set, _)| *set)
+                            .map(|(_, name)| *name)
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    );
+                });
+        }
+    });
+    match path.escape {
+        Some(escape) => ui.label(format!("Escaped to the sky: {}", color(escape.light))),
+        None => ui.label("Ran out of bounces"),
+    };
+}
+
+fn draw_histogram(ui: &mut egui::Ui, histogram: &Histogram) {
+    let (rect, _) = ui.allocate_exact_size(
+        egui::vec2(ui.available_width().min(256.0), 64.0),
+        egui::Sense::hover(),
+    );
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, egui::Color32::from_gray(20));
+    let largest = histogram.bins.iter().copied().max().unwrap_or(0).max(1);
+    let bar_width = rect.width() / histogram.bins.len() as f32;
+    for (i, &count) in histogram.bins.iter().enumerate() {
+        let height = rect.height() * count as f32 / largest as f32;
+        let left = rect.left() + bar_width * i as f32;
+        painter.rect_filled(
+            egui::Rect::from_min_max(
+                egui::pos2(left, rect.bottom() - height),
+                egui::pos2(left + bar_width, rect.bottom()),
+            ),
+            0.0,
+            egui::Color32::from_gray(200),
+        );
+    }
+    let percent = |count: u32| count as f32 / histogram.pixel_count().max(1) as f32 * 100.0;
+    ui.label(format!(
+        "Clipped: {:.1}% | Black: {:.1}%",
+        percent(histogram.clipped),
+        percent(histogram.crushed),
+    ));
+}
+
+/// Draws the objects projected onto x and `vertical_axis` (z or w), tinting them by their w
+/// offset from the camera, with the camera in the middle. Returns how far from the camera a
+/// click on the map was
+fn draw_minimap(
+    ui: &mut egui::Ui,
+    scene: &Scene,
+    camera: &GpuCamera,
+    w_ramp: &WRamp,
+    color_management: ColorManagement,
+    range: f32,
+    vertical_axis: usize,
+) -> Option<cgmath::Vector4<f32>> {
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(200.0, 200.0), egui::Sense::click());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, egui::Color32::from_gray(20));
+
+    let scale = rect.width() * 0.5 / range;
+    // +vertical_axis is up on the map
+    let to_map = |position: cgmath::Vector4<f32>| {
+        rect.center()
+            + egui::vec2(
+                (position.x - camera.position.x) * scale,
+                (camera.position[vertical_axis] - position[vertical_axis]) * scale,
+            )
+    };
+    let color = |position: cgmath::Vector4<f32>| {
+        output_color(
+            w_ramp.color(position.w - camera.position.w),
+            color_management,
+        )
+    };
+
+    for hyper_plane in scene.hyper_planes() {
+        // only planes that cut through the map show up, as the line where they cross the
+        // plane of the map through the camera
+        let normal = cgmath::vec2(hyper_plane.normal.x, hyper_plane.normal[vertical_axis]);
+        if normal.magnitude2() < 1e-6 {
+            continue;
+        }
+        let mut distance = hyper_plane.normal.dot(hyper_plane.point);
+        for axis in 1..4 {
+            if axis != vertical_axis {
+                distance -= hyper_plane.normal[axis] * camera.position[axis];
+            }
+        }
+        let offset = normal * (distance / normal.magnitude2());
+        let along = cgmath::vec2(-normal.y, normal.x).normalize() * range * 4.0;
+        let point = |p: cgmath::Vector2<f32>| {
+            let mut position = camera.position;
+            position.x = p.x;
+            position[vertical_axis] = p.y;
+            position
+        };
+
+        // finite planes only draw the part of the line inside their extents
+        let (start, end) = (point(offset - along), point(offset + along));
+        let (mut from, mut to) = (0.0f32, 1.0f32);
+        for (k, tangent) in hyper_plane.tangents().into_iter().enumerate() {
+            let extent = hyper_plane.extents[k];
+            if extent <= 0.0 {
+                continue;
+            }
+            let a = (start - hyper_plane.point).dot(tangent);
+            let b = (end - start).dot(tangent);
+            if b.abs() < 1e-6 {
+                if a.abs() > extent {
+                    to = from;
+                }
+                continue;
+            }
+            let (t0, t1) = ((-extent - a) / b, (extent - a) / b);
+            from = from.max(t0.min(t1));
+            to = to.min(t0.max(t1));
+        }
+        if from >= to {
+            continue;
+        }
+        painter.line_segment(
+            [
+                to_map(start + (end - start) * from),
+                to_map(start + (end - start) * to),
+            ],
+            egui::Stroke::new(2.0, color(hyper_plane.point)),
+        );
+    }
+    for hyper_sphere in scene.hyper_spheres() {
+        if hyper_sphere.inner_radius > 0.0 {
+            let thickness = hyper_sphere.radius - hyper_sphere.inner_radius;
+            painter.circle_stroke(
+                to_map(hyper_sphere.center),
+                (hyper_sphere.radius - thickness * 0.5) * scale,
+                egui::Stroke::new((thickness * scale).max(1.0), color(hyper_sphere.center)),
+            );
+            continue;
+        }
+        painter.circle_filled(
+            to_map(hyper_sphere.center),
+            (hyper_sphere.radius * scale).max(2.0),
+            color(hyper_sphere.center),
+        );
+    }
+
+    let heading = egui::vec2(camera.forward.x, -camera.forward[vertical_axis]);
+    let heading = if heading.length() > 1e-3 {
+        heading.normalized()
+    } else {
+        egui::vec2(0.0, -1.0)
+    };
+    let side = heading.rot90();
+    let center = rect.center();
+    painter.add(egui::Shape::convex_polygon(
+        vec![
+            center + heading * 10.0,
+            center - heading * 6.0 + side * 6.0,
+            center - heading * 6.0 - side * 6.0,
+        ],
+        egui::Color32::WHITE,
+        egui::Stroke::new(1.0, egui::Color32::BLACK),
+    ));
+
+    let pointer = response
+        .interact_pointer_pos()
+        .filter(|_| response.clicked())?;
+    let offset = (pointer - center) / scale;
+    let mut delta = cgmath::vec4(offset.x, 0.0, 0.0, 0.0);
+    delta[vertical_axis] = -offset.y;
+    Some(delta)
+}
@@ -0,0 +1,166 @@
+use crate::{BiVector4, ObjectData, ObjectId, ObjectRef, Placement, Scene};
+
+/// How an [`ArrayModifier`] spreads out its copies
+#[derive(Clone, Copy, PartialEq)]
+pub enum ArrayKind {
+    /// Each copy is `offset` further along than the last
+    Linear { offset: cgmath::Vector4<f32> },
+    /// Around `center` in `plane`, the source and its copies split `angle` evenly so a full
+    /// turn puts them all the same distance apart
+    Radial {
+        center: cgmath::Vector4<f32>,
+        plane: BiVector4,
+        angle: f32,
+    },
+}
+
+impl ArrayKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ArrayKind::Linear { .. } => "Linear",
+            ArrayKind::Radial { .. } => "Radial",
+        }
+    }
+}
+
+/// Everything that decides where the copies of an [`ArrayModifier`] go
+#[derive(Clone, Copy, PartialEq)]
+pub struct ArraySettings {
+    /// Not counting the source object
+    pub count: usize,
+    pub kind: ArrayKind,
+    /// How much more each copy is turned about its own position than the last, in `rotation_plane`
+    pub rotation: f32,
+    pub rotation_plane: BiVector4,
+}
+
+impl Default for ArraySettings {
+    fn default() -> Self {
+        Self {
+            count: 3,
+            kind: ArrayKind::Linear {
+                offset: cgmath::vec4(2.0, 0.0, 0.0, 0.0),
+            },
+            rotation: 0.0,
+            rotation_plane: BiVector4::XZ,
+        }
+    }
+}
+
+/// Copies of an object that follow it, they are regenerated whenever the settings or the source
+/// object change. The copies are normal objects of the scene, so saving the scene bakes them
+pub struct ArrayModifier {
+    pub source: ObjectId,
+    pub settings: ArraySettings,
+    clones: Vec<ObjectId>,
+    /// What the clones were last generated from, the settings, name, notes and data of the source
+    generated: Option<(ArraySettings, String, String, ObjectData)>,
+}
+
+impl ArrayModifier {
+    pub fn new(source: ObjectId) -> Self {
+        Self {
+            source,
+            settings: ArraySettings::default(),
+            clones: vec![],
+            generated: None,
+        }
+    }
+
+    /// The objects generated so far, some may have been removed since
+    pub fn clones(&self) -> &[ObjectId] {
+        &self.clones
+    }
+
+    /// Forgets the clones so they stay in the scene as they are, for applying the modifier
+    pub fn detach_clones(&mut self) -> Vec<ObjectId> {
+        self.generated = None;
+        std::mem::take(&mut self.clones)
+    }
+
+    /// The data of the `i`th copy, counting from 1, made from the source's data
+    pub fn copy(&self, source: ObjectData, i: usize) -> ObjectData {
+        let position = source.position();
+        let target = match self.settings.kind {
+            ArrayKind::Linear { offset } => position + offset * i as f32,
+            ArrayKind::Radial {
+                center,
+                plane,
+                angle,
+            } => Placement {
+                position: center,
+                angle: angle / (self.settings.count + 1) as f32 * i as f32,
+                plane,
+                scale: 1.0,
+            }
+            .point(position - center),
+        };
+        let mut data = source;
+        *data.position_mut() -= position;
+        data.placed(&Placement {
+            position: target,
+            angle: self.settings.rotation * i as f32,
+            plane: self.settings.rotation_plane,
+            scale: 1.0,
+        })
+    }
+
+    /// Regenerates the clones if anything they come from changed, or one of them was removed.
+    /// Clones that are no longer needed are returned instead of removed, so that the caller can
+    /// remove them along with anything else that refers to them. Every clone is returned when the
+    /// source object has been removed
+    pub fn update(&mut self, scene: &mut Scene) -> Vec<ObjectRef> {
+        let Some(source) = scene
+            .object_ref(self.source)
+            .and_then(|source| scene.object(source))
+        else {
+            self.generated = None;
+            return self
+                .clones
+                .drain(..)
+                .filter_map(|clone| scene.object_ref(clone))
+                .collect();
+        };
+        let generated = (
+            self.settings,
+            source.name.clone(),
+            source.notes.clone(),
+            source.data,
+        );
+        let clones_exist = self
+            .clones
+            .iter()
+            .all(|&clone| scene.object_ref(clone).is_some());
+        if clones_exist && self.generated.as_ref() == Some(&generated) {
+            return vec![];
+        }
+
+        let (_, name, notes, data) = &generated;
+        self.clones
+            .retain(|&clone| scene.object_ref(clone).is_some());
+        let surplus = self
+            .clones
+            .split_off(self.clones.len().min(self.settings.count));
+        for i in 1..=self.settings.count {
+            let copy = self.copy(*data, i);
+            let clone = match self.clones.get(i - 1) {
+                Some(&clone) => clone,
+                None => {
+                    let clone = scene.add_object(String::new(), copy);
+                    self.clones.push(clone);
+                    clone
+                }
+            };
+            let object = scene.object_ref(clone).unwrap();
+            let object = scene.object_mut(object).unwrap();
+            object.name = format!("{name} {i}");
+            object.notes.clone_from(notes);
+            object.data = copy;
+        }
+        self.generated = Some(generated);
+        surplus
+            .into_iter()
+            .filter_map(|clone| scene.object_ref(clone))
+            .collect()
+    }
+}
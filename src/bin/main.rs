@@ -1,22 +1,99 @@
-use eframe::wgpu;
-use four_dimentions::App;
+use four_dimentions::{device_limits, App, Config, ConsoleLogger, LaunchOptions, Recovery};
 
+#[cfg(not(target_arch = "wasm32"))]
 fn main() {
+    ConsoleLogger::install().expect("nothing else sets a logger");
+    Recovery::install_panic_hook();
+    let options = match LaunchOptions::parse(std::env::args().skip(1)) {
+        Ok(options) if options.help => {
+            println!("{}", LaunchOptions::USAGE);
+            return;
+        }
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{error}\n\n{}", LaunchOptions::USAGE);
+            std::process::exit(2);
+        }
+    };
+    let mut config = Config::load();
+    options.apply(&mut config);
+
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        // the chosen adapter can be on any backend
+        backends: if config.adapter_name.is_some() {
+            wgpu::Backends::all()
+        } else {
+            config.backends
+        },
+        ..Default::default()
+    });
+    let adapter = config.select_adapter(&instance).or_else(|| {
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: config.power_preference,
+            ..Default::default()
+        }))
+    });
+    let limits = adapter.map_or_else(wgpu::Limits::default, |adapter| device_limits(&adapter));
+    drop(instance);
+
     eframe::run_native(
         "4D Ray Tracing",
         eframe::NativeOptions {
             renderer: eframe::Renderer::Wgpu,
-            wgpu_options: eframe::egui_wgpu::WgpuConfiguration {
-                device_descriptor: wgpu::DeviceDescriptor {
-                    ..Default::default()
-                },
-                present_mode: wgpu::PresentMode::AutoNoVsync,
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                ..Default::default()
-            },
+            wgpu_options: wgpu_configuration(&config, limits),
+            initial_window_size: options
+                .size
+                .map(|(width, height)| eframe::egui::vec2(width as _, height as _)),
             ..Default::default()
         },
-        Box::new(|cc| Box::new(App::new(cc))),
+        Box::new(move |cc| {
+            let mut app = App::new(cc, config);
+            if let Some(path) = options.screenshot {
+                let size = options
+                    .size
+                    .unwrap_or(LaunchOptions::DEFAULT_SCREENSHOT_SIZE);
+                app.take_screenshot(path, size, options.exit);
+            }
+            Box::new(app)
+        }),
     )
     .unwrap()
 }
+
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    ConsoleLogger::install().expect("nothing else sets a logger");
+    let config = Config::load();
+    wasm_bindgen_futures::spawn_local(async move {
+        eframe::start_web(
+            "the_canvas_id",
+            eframe::WebOptions {
+                // WebGL2 has no compute shaders, so the fragment path is used
+                wgpu_options: wgpu_configuration(
+                    &config,
+                    wgpu::Limits::downlevel_webgl2_defaults(),
+                ),
+                ..Default::default()
+            },
+            Box::new(|cc| Box::new(App::new(cc, config))),
+        )
+        .await
+        .expect("failed to start eframe");
+    });
+}
+
+fn wgpu_configuration(
+    config: &Config,
+    limits: wgpu::Limits,
+) -> eframe::egui_wgpu::WgpuConfiguration {
+    eframe::egui_wgpu::WgpuConfiguration {
+        device_descriptor: wgpu::DeviceDescriptor {
+            limits,
+            ..Default::default()
+        },
+        backends: config.backends,
+        present_mode: config.present_mode,
+        power_preference: config.power_preference,
+        ..Default::default()
+    }
+}
@@ -1,4 +1,6 @@
-#[derive(Clone, Copy)]
+use encase::ShaderType;
+
+#[derive(Clone, Copy, PartialEq, ShaderType)]
 pub struct BiVector4 {
     pub xy: f32,
     pub xz: f32,
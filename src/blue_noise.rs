@@ -0,0 +1,98 @@
+use rand::{Rng, SeedableRng};
+use std::sync::OnceLock;
+
+pub const BLUE_NOISE_SIZE: usize = 64;
+
+/// The [`BLUE_NOISE_SIZE`] blue noise every [`crate::Renderer`] samples with. Generating it takes
+/// a while, so it is only done the first time it is needed and then shared
+pub fn blue_noise() -> &'static [f32] {
+    static BLUE_NOISE: OnceLock<Vec<f32>> = OnceLock::new();
+    BLUE_NOISE.get_or_init(|| generate_blue_noise(BLUE_NOISE_SIZE))
+}
+
+/// Generates a tileable `size`x`size` blue noise texture with the void-and-cluster method,
+/// each value is the pixel's rank in `[0, 1)`
+pub fn generate_blue_noise(size: usize) -> Vec<f32> {
+    const SIGMA: f32 = 1.5;
+
+    let count = size * size;
+    // gaussian falloff for every toroidal offset so energy updates are a single add
+    let falloff = (0..count)
+        .map(|i| {
+            let (x, y) = (i % size, i / size);
+            let dx = x.min(size - x) as f32;
+            let dy = y.min(size - y) as f32;
+            (-(dx * dx + dy * dy) / (2.0 * SIGMA * SIGMA)).exp()
+        })
+        .collect::<Vec<_>>();
+
+    let mut pattern = vec![false; count];
+    let mut energy = vec![0.0f32; count];
+    let set = |pattern: &mut [bool], energy: &mut [f32], index: usize, value: bool| {
+        pattern[index] = value;
+        let (x, y) = (index % size, index / size);
+        let sign = if value { 1.0 } else { -1.0 };
+        for (i, energy) in energy.iter_mut().enumerate() {
+            let (ox, oy) = ((i % size + size - x) % size, (i / size + size - y) % size);
+            *energy += sign * falloff[ox + oy * size];
+        }
+    };
+    let tightest_cluster = |pattern: &[bool], energy: &[f32]| {
+        (0..count)
+            .filter(|&i| pattern[i])
+            .max_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+            .unwrap()
+    };
+    let largest_void = |pattern: &[bool], energy: &[f32]| {
+        (0..count)
+            .filter(|&i| !pattern[i])
+            .min_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+            .unwrap()
+    };
+
+    // start from white noise and relax it into an evenly spread initial pattern
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    let initial_count = count / 10;
+    let mut placed = 0;
+    while placed < initial_count {
+        let index = rng.gen_range(0..count);
+        if !pattern[index] {
+            set(&mut pattern, &mut energy, index, true);
+            placed += 1;
+        }
+    }
+    for _ in 0..count {
+        let cluster = tightest_cluster(&pattern, &energy);
+        set(&mut pattern, &mut energy, cluster, false);
+        let void = largest_void(&pattern, &energy);
+        set(&mut pattern, &mut energy, void, true);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut ranks = vec![0; count];
+
+    // rank the initial points by repeatedly removing the tightest cluster
+    let initial_pattern = pattern.clone();
+    let initial_energy = energy.clone();
+    for rank in (0..initial_count).rev() {
+        let cluster = tightest_cluster(&pattern, &energy);
+        set(&mut pattern, &mut energy, cluster, false);
+        ranks[cluster] = rank;
+    }
+
+    // then fill the remaining pixels by repeatedly filling the largest void
+    pattern = initial_pattern;
+    energy = initial_energy;
+    for rank in initial_count..count {
+        let void = largest_void(&pattern, &energy);
+        set(&mut pattern, &mut energy, void, true);
+        ranks[void] = rank;
+    }
+
+    ranks
+        .into_iter()
+        .map(|rank| rank as f32 / count as f32)
+        .collect()
+}
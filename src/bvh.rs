@@ -0,0 +1,587 @@
+use crate::{GpuBvhNode, GpuHyperSphere};
+use std::ops::Range;
+
+/// Leaves hold at most this many hyper spheres, as many as a [`GpuBvhNode`] has room for
+pub const MAX_BVH_LEAF_SIZE: usize = 4;
+
+/// How many buckets [`BvhBuilder::BinnedSah`] sorts the hyper spheres into along each axis
+const SAH_BINS: usize = 16;
+
+/// Past this depth [`BvhBuilder::BinnedSah`] splits at the median, so lopsided splits can't
+/// make the tree deeper than about `SAH_MAX_DEPTH` plus the log of the hyper sphere count
+const SAH_MAX_DEPTH: usize = 24;
+
+/// [`Bvh::update`] rebuilds a refit tree once its SAH cost is this many times what it was
+/// built with, the boxes of hyper spheres that moved apart overlap too much by then
+pub const BVH_REBUILD_COST_RATIO: f32 = 1.5;
+
+/// When [`Bvh::update`] rebuilds, up to this many hyper spheres that were edited or animated
+/// since the last build are left out of the tree and checked by every ray instead, so moving
+/// them again doesn't touch the tree. More than that and the whole scene is animated, which
+/// refitting handles better
+pub const MAX_DYNAMIC_HYPER_SPHERES: usize = 32;
+
+/// A box in 4D aligned to the axes
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Aabb4 {
+    pub min: cgmath::Vector4<f32>,
+    pub max: cgmath::Vector4<f32>,
+}
+
+impl Aabb4 {
+    /// Contains nothing, its union with a box is that box
+    pub const EMPTY: Self = Self {
+        min: cgmath::Vector4::new(f32::INFINITY, f32::INFINITY, f32::INFINITY, f32::INFINITY),
+        max: cgmath::Vector4::new(
+            f32::NEG_INFINITY,
+            f32::NEG_INFINITY,
+            f32::NEG_INFINITY,
+            f32::NEG_INFINITY,
+        ),
+    };
+
+    /// Around the hyper sphere where it is at time 0, slightly larger so rounding in the
+    /// shader's box test can't make a ray miss it
+    pub fn around_hyper_sphere(hyper_sphere: &GpuHyperSphere) -> Self {
+        let center = hyper_sphere.center;
+        let largest = center
+            .x
+            .abs()
+            .max(center.y.abs())
+            .max(center.z.abs().max(center.w.abs()));
+        let radius = hyper_sphere.radius.abs() + (largest + hyper_sphere.radius.abs()) * 1e-3;
+        let radius = cgmath::vec4(radius, radius, radius, radius);
+        Self {
+            min: center - radius,
+            max: center + radius,
+        }
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            min: self.min.zip(other.min, f32::min),
+            max: self.max.zip(other.max, f32::max),
+        }
+    }
+
+    pub fn contains(self, other: Self) -> bool {
+        (0..4).all(|axis| self.min[axis] <= other.min[axis] && other.max[axis] <= self.max[axis])
+    }
+
+    pub fn centroid(self) -> cgmath::Vector4<f32> {
+        (self.min + self.max) * 0.5
+    }
+
+    /// The volume of the 3D boundary of the box, how likely a random ray through a larger
+    /// box is to also pass through this one is proportional to it
+    pub fn surface_area(self) -> f32 {
+        let extent = self.max - self.min;
+        if (0..4).any(|axis| extent[axis] < 0.0) {
+            return 0.0;
+        }
+        let product_without = |skipped: usize| -> f32 {
+            (0..4)
+                .filter(|&axis| axis != skipped)
+                .map(|axis| extent[axis])
+                .product()
+        };
+        2.0 * (0..4).map(product_without).sum::<f32>()
+    }
+}
+
+/// How a [`Bvh`] decides where to split the hyper spheres
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BvhBuilder {
+    /// Halves them along the longest axis, fast enough to rebuild while editing
+    Median,
+    /// Picks the split with the lowest surface area heuristic cost among a few buckets per
+    /// axis, slower to build but rays visit fewer nodes, for final renders
+    BinnedSah,
+}
+
+impl BvhBuilder {
+    pub fn name(self) -> &'static str {
+        match self {
+            BvhBuilder::Median => "Median",
+            BvhBuilder::BinnedSah => "Binned SAH",
+        }
+    }
+}
+
+/// Whether the [`crate::Renderer`] builds a [`Bvh`] over the hyper spheres, and how
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BvhMode {
+    /// [`BvhMode::Median`] once there are at least [`BvhMode::AUTO_BVH_HYPER_SPHERES`] hyper spheres
+    Auto,
+    /// Every ray checks every hyper sphere
+    Off,
+    Median,
+    BinnedSah,
+}
+
+impl BvhMode {
+    pub const ALL: [BvhMode; 4] = [
+        BvhMode::Auto,
+        BvhMode::Off,
+        BvhMode::Median,
+        BvhMode::BinnedSah,
+    ];
+    pub const AUTO_BVH_HYPER_SPHERES: usize = 256;
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BvhMode::Auto => "Auto",
+            BvhMode::Off => "Off",
+            BvhMode::Median => BvhBuilder::Median.name(),
+            BvhMode::BinnedSah => BvhBuilder::BinnedSah.name(),
+        }
+    }
+
+    /// How a scene with `hyper_sphere_count` hyper spheres is built, if it is
+    pub fn builder(self, hyper_sphere_count: usize) -> Option<BvhBuilder> {
+        match self {
+            BvhMode::Auto => {
+                (hyper_sphere_count >= Self::AUTO_BVH_HYPER_SPHERES).then_some(BvhBuilder::Median)
+            }
+            BvhMode::Off => None,
+            BvhMode::Median => Some(BvhBuilder::Median),
+            BvhMode::BinnedSah => Some(BvhBuilder::BinnedSah),
+        }
+    }
+}
+
+/// How good a [`Bvh`] is and what it took to build
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BvhStats {
+    pub builder: BvhBuilder,
+    /// The nodes of the tree, without the leaves of dynamic hyper spheres
+    pub nodes: usize,
+    pub leaves: usize,
+    pub depth: usize,
+    /// How many nodes and hyper spheres a random ray through the root is expected to check,
+    /// lower is better
+    pub sah_cost: f32,
+    pub build_time: std::time::Duration,
+    /// How many times the bounds were refit since it was built
+    pub refits: usize,
+    /// How many hyper spheres aren't in the tree because they move, or were edited or
+    /// animated since the last build
+    pub dynamic: usize,
+}
+
+/// What [`Bvh::update`] did
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BvhUpdate {
+    /// The nodes are the same, only dynamic hyper spheres changed if any
+    Unchanged,
+    /// The tree kept its shape and only the bounds changed
+    Refit,
+    Rebuilt,
+}
+
+/// A bounding volume hierarchy over the static hyper spheres, so a ray only checks the ones
+/// in boxes it passes through. The dynamic ones, that move or keep being edited or animated,
+/// are in leaves after the tree, which every ray checks
+pub struct Bvh {
+    /// The tree in depth first order starting at the root, followed by the dynamic leaves
+    pub nodes: Vec<GpuBvhNode>,
+    /// How many of `nodes` are in the tree
+    pub tree_nodes: usize,
+    pub stats: BvhStats,
+    /// What it was built or last refit over, so uploading the same hyper spheres again
+    /// doesn't change it
+    hyper_spheres: Vec<GpuHyperSphere>,
+    /// The SAH cost and root surface area when it was built, refits that make the cost
+    /// relative to that root much worse rebuild it
+    built_sah_cost: f32,
+    built_root_area: f32,
+    /// Which hyper spheres are in the tree rather than the dynamic leaves
+    in_tree: Vec<bool>,
+    /// Which hyper spheres changed since it was built, they become dynamic when it is rebuilt
+    edited: Vec<bool>,
+}
+
+impl Bvh {
+    /// Every hyper sphere that doesn't move is put in the tree
+    pub fn build(hyper_spheres: &[GpuHyperSphere], builder: BvhBuilder) -> Self {
+        Self::build_with_dynamic(hyper_spheres, builder, &vec![false; hyper_spheres.len()])
+    }
+
+    /// Like [`Bvh::build`], but the hyper spheres that are `dynamic` are left out of the tree too
+    pub fn build_with_dynamic(
+        hyper_spheres: &[GpuHyperSphere],
+        builder: BvhBuilder,
+        dynamic: &[bool],
+    ) -> Self {
+        let start = instant::Instant::now();
+        let in_tree = hyper_spheres
+            .iter()
+            .zip(dynamic)
+            .map(|(hyper_sphere, &dynamic)| !dynamic && !moves(hyper_sphere))
+            .collect::<Vec<_>>();
+        let bounds = hyper_spheres
+            .iter()
+            .map(Aabb4::around_hyper_sphere)
+            .collect::<Vec<_>>();
+        let mut state = BuildState {
+            builder,
+            centroids: bounds.iter().map(|bounds| bounds.centroid()).collect(),
+            bounds,
+            indices: (0..hyper_spheres.len() as u32)
+                .filter(|&i| in_tree[i as usize])
+                .collect(),
+            nodes: vec![],
+            depth: 0,
+        };
+        if !state.indices.is_empty() {
+            state.build_node(0..state.indices.len(), 1);
+        }
+        let tree_nodes = state.nodes.len();
+
+        let dynamic_hyper_spheres = (0..hyper_spheres.len() as u32)
+            .filter(|&i| !in_tree[i as usize])
+            .collect::<Vec<_>>();
+        for leaf in dynamic_hyper_spheres.chunks(MAX_BVH_LEAF_SIZE) {
+            let skip = state.nodes.len() as u32 + 1;
+            state.nodes.push(leaf_node(Aabb4::EMPTY, leaf, skip));
+        }
+
+        let tree = &state.nodes[..tree_nodes];
+        let sah_cost = sah_cost(tree);
+        let built_root_area = root_area(tree);
+        let stats = BvhStats {
+            builder,
+            nodes: tree_nodes,
+            leaves: tree.iter().filter(|node| node.count > 0).count(),
+            depth: state.depth,
+            sah_cost,
+            build_time: start.elapsed(),
+            refits: 0,
+            dynamic: dynamic_hyper_spheres.len(),
+        };
+        Self {
+            nodes: state.nodes,
+            tree_nodes,
+            stats,
+            hyper_spheres: hyper_spheres.to_vec(),
+            built_sah_cost: sah_cost,
+            built_root_area,
+            in_tree,
+            edited: dynamic.to_vec(),
+        }
+    }
+
+    /// Brings it up to date with `hyper_spheres` the cheapest way it can. Changes to only the
+    /// dynamic hyper spheres leave the nodes as they are. When there are as many as before and
+    /// none in the tree started moving, only the bounds are refit, unless that makes the SAH
+    /// cost more than [`BVH_REBUILD_COST_RATIO`] times what it was built with. A rebuild makes
+    /// the hyper spheres that changed since the last one dynamic, if there are few enough
+    pub fn update(&mut self, hyper_spheres: &[GpuHyperSphere]) -> BvhUpdate {
+        if self.hyper_spheres == hyper_spheres {
+            return BvhUpdate::Unchanged;
+        }
+        if self.hyper_spheres.len() != hyper_spheres.len() {
+            *self = Self::build(hyper_spheres, self.stats.builder);
+            return BvhUpdate::Rebuilt;
+        }
+
+        let mut tree_changed = false;
+        let mut left_tree = false;
+        for (i, (old, new)) in self.hyper_spheres.iter().zip(hyper_spheres).enumerate() {
+            if old != new {
+                self.edited[i] = true;
+                tree_changed |= self.in_tree[i];
+                left_tree |= self.in_tree[i] && moves(new);
+            }
+        }
+        if !tree_changed {
+            self.hyper_spheres.copy_from_slice(hyper_spheres);
+            return BvhUpdate::Unchanged;
+        }
+        if !left_tree {
+            self.refit(hyper_spheres);
+            // relative to the old root, so boxes stretched over hyper spheres that moved apart
+            // count for as much as the extra space a ray has to cross
+            let cost = sah_cost_relative_to(&self.nodes[..self.tree_nodes], self.built_root_area);
+            if cost <= self.built_sah_cost * BVH_REBUILD_COST_RATIO {
+                return BvhUpdate::Refit;
+            }
+        }
+
+        let edited = self.edited.iter().filter(|&&edited| edited).count();
+        *self = if edited <= MAX_DYNAMIC_HYPER_SPHERES {
+            Self::build_with_dynamic(hyper_spheres, self.stats.builder, &self.edited)
+        } else {
+            Self::build(hyper_spheres, self.stats.builder)
+        };
+        BvhUpdate::Rebuilt
+    }
+
+    /// Recomputes the bounds of the tree bottom up around `hyper_spheres`, which have to be as
+    /// many as it was built over without any in the tree moving. Animated hyper spheres that
+    /// stay near each other keep a good tree, see [`Bvh::update`] for when it should be rebuilt
+    pub fn refit(&mut self, hyper_spheres: &[GpuHyperSphere]) {
+        // children come after their parents, so going backwards they are always refit first
+        for index in (0..self.tree_nodes).rev() {
+            let node = self.nodes[index];
+            let bounds = if node.count > 0 {
+                leaf_hyper_spheres(&node)
+                    .iter()
+                    .fold(Aabb4::EMPTY, |bounds, &i| {
+                        bounds.union(Aabb4::around_hyper_sphere(&hyper_spheres[i as usize]))
+                    })
+            } else {
+                let first = &self.nodes[index + 1];
+                node_bounds(first).union(node_bounds(&self.nodes[first.skip as usize]))
+            };
+            self.nodes[index].min = bounds.min;
+            self.nodes[index].max = bounds.max;
+        }
+        self.hyper_spheres.clear();
+        self.hyper_spheres.extend_from_slice(hyper_spheres);
+        self.stats.sah_cost = sah_cost(&self.nodes[..self.tree_nodes]);
+        self.stats.refits += 1;
+    }
+
+    /// The indices of the hyper spheres that a ray starting at `origin` going in `direction`
+    /// could hit, the same nodes the shader visits
+    pub fn candidates(
+        &self,
+        origin: cgmath::Vector4<f32>,
+        direction: cgmath::Vector4<f32>,
+    ) -> Vec<u32> {
+        let mut candidates = vec![];
+        let mut index = 0;
+        while index < self.tree_nodes {
+            let node = &self.nodes[index];
+            if !ray_hits_box(origin, direction, node_bounds(node)) {
+                index = node.skip as usize;
+            } else if node.count > 0 {
+                candidates.extend(&leaf_hyper_spheres(node));
+                index = node.skip as usize;
+            } else {
+                index += 1;
+            }
+        }
+        for node in &self.nodes[self.tree_nodes..] {
+            candidates.extend(&leaf_hyper_spheres(node));
+        }
+        candidates
+    }
+}
+
+/// Moving hyper spheres are always dynamic
+fn moves(hyper_sphere: &GpuHyperSphere) -> bool {
+    hyper_sphere.velocity != cgmath::vec4(0.0, 0.0, 0.0, 0.0)
+}
+
+/// How many nodes and hyper spheres a random ray through the root of `tree` is expected to check
+fn sah_cost(tree: &[GpuBvhNode]) -> f32 {
+    sah_cost_relative_to(tree, root_area(tree))
+}
+
+/// Like [`sah_cost`] for rays through a box with a surface area of `root_area`, which doesn't
+/// have to be the root's
+fn sah_cost_relative_to(tree: &[GpuBvhNode], root_area: f32) -> f32 {
+    if root_area <= 0.0 {
+        return 0.0;
+    }
+    tree.iter()
+        .map(|node| {
+            let cost = if node.count == 0 {
+                1.0
+            } else {
+                node.count as f32
+            };
+            cost * node_bounds(node).surface_area() / root_area
+        })
+        .sum()
+}
+
+fn root_area(tree: &[GpuBvhNode]) -> f32 {
+    tree.first()
+        .map_or(0.0, |root| node_bounds(root).surface_area())
+}
+
+fn node_bounds(node: &GpuBvhNode) -> Aabb4 {
+    Aabb4 {
+        min: node.min,
+        max: node.max,
+    }
+}
+
+fn leaf_node(bounds: Aabb4, hyper_spheres: &[u32], skip: u32) -> GpuBvhNode {
+    let mut indices = cgmath::vec4(0, 0, 0, 0);
+    for (i, &index) in hyper_spheres.iter().enumerate() {
+        indices[i] = index;
+    }
+    GpuBvhNode {
+        min: bounds.min,
+        max: bounds.max,
+        hyper_spheres: indices,
+        count: hyper_spheres.len() as u32,
+        skip,
+    }
+}
+
+fn leaf_hyper_spheres(node: &GpuBvhNode) -> Vec<u32> {
+    (0..node.count as usize)
+        .map(|i| node.hyper_spheres[i])
+        .collect()
+}
+
+/// Like `ray_hits_box` in `hyper_sphere.wgsl`, without a maximum distance
+fn ray_hits_box(
+    origin: cgmath::Vector4<f32>,
+    direction: cgmath::Vector4<f32>,
+    bounds: Aabb4,
+) -> bool {
+    let mut enter = f32::NEG_INFINITY;
+    let mut exit = f32::INFINITY;
+    for axis in 0..4 {
+        let inverse = 1.0 / safe_direction(direction[axis]);
+        let t0 = (bounds.min[axis] - origin[axis]) * inverse;
+        let t1 = (bounds.max[axis] - origin[axis]) * inverse;
+        enter = enter.max(t0.min(t1));
+        exit = exit.min(t0.max(t1));
+    }
+    enter <= exit && exit >= 0.0
+}
+
+/// Directions parallel to an axis would divide by zero
+fn safe_direction(direction: f32) -> f32 {
+    if direction.abs() < 1e-20 {
+        1e-20
+    } else {
+        direction
+    }
+}
+
+struct BuildState {
+    builder: BvhBuilder,
+    bounds: Vec<Aabb4>,
+    centroids: Vec<cgmath::Vector4<f32>>,
+    /// The hyper spheres in the tree, reordered so every node's are next to each other
+    indices: Vec<u32>,
+    nodes: Vec<GpuBvhNode>,
+    depth: usize,
+}
+
+impl BuildState {
+    fn build_node(&mut self, range: Range<usize>, depth: usize) {
+        self.depth = self.depth.max(depth);
+        let bounds = self.indices[range.clone()]
+            .iter()
+            .fold(Aabb4::EMPTY, |bounds, &i| {
+                bounds.union(self.bounds[i as usize])
+            });
+        let node = self.nodes.len();
+        if range.len() <= MAX_BVH_LEAF_SIZE {
+            let skip = node as u32 + 1;
+            self.nodes
+                .push(leaf_node(bounds, &self.indices[range], skip));
+            return;
+        }
+
+        let sah_split = match self.builder {
+            BvhBuilder::BinnedSah if depth < SAH_MAX_DEPTH => self.sah_split(range.clone()),
+            _ => None,
+        };
+        let middle = sah_split.unwrap_or_else(|| self.median_split(range.clone()));
+        self.nodes.push(GpuBvhNode {
+            min: bounds.min,
+            max: bounds.max,
+            hyper_spheres: cgmath::vec4(0, 0, 0, 0),
+            count: 0,
+            skip: 0,
+        });
+        self.build_node(range.start..middle, depth + 1);
+        self.build_node(middle..range.end, depth + 1);
+        self.nodes[node].skip = self.nodes.len() as u32;
+    }
+
+    fn centroid_bounds(&self, range: Range<usize>) -> Aabb4 {
+        self.indices[range].iter().fold(Aabb4::EMPTY, |bounds, &i| {
+            let centroid = self.centroids[i as usize];
+            bounds.union(Aabb4 {
+                min: centroid,
+                max: centroid,
+            })
+        })
+    }
+
+    /// Splits in half along the axis the centroids are most spread out on, returns where the
+    /// second half starts
+    fn median_split(&mut self, range: Range<usize>) -> usize {
+        let extent = {
+            let bounds = self.centroid_bounds(range.clone());
+            bounds.max - bounds.min
+        };
+        let axis = (0..4)
+            .max_by(|&a, &b| extent[a].total_cmp(&extent[b]))
+            .unwrap();
+        let half = range.len() / 2;
+        let centroids = &self.centroids;
+        self.indices[range.clone()].select_nth_unstable_by(half, |&a, &b| {
+            centroids[a as usize][axis].total_cmp(&centroids[b as usize][axis])
+        });
+        range.start + half
+    }
+
+    /// The split between buckets with the lowest surface area heuristic cost, or `None` if
+    /// the centroids are all in the same place
+    fn sah_split(&mut self, range: Range<usize>) -> Option<usize> {
+        let centroid_bounds = self.centroid_bounds(range.clone());
+        let bucket = |axis: usize, centroid: cgmath::Vector4<f32>| {
+            let extent = centroid_bounds.max[axis] - centroid_bounds.min[axis];
+            let offset = (centroid[axis] - centroid_bounds.min[axis]) / extent;
+            ((offset * SAH_BINS as f32) as usize).min(SAH_BINS - 1)
+        };
+
+        // the cost, the axis and the first bucket on the right
+        let mut best: Option<(f32, usize, usize)> = None;
+        for axis in 0..4 {
+            if centroid_bounds.max[axis] <= centroid_bounds.min[axis] {
+                continue;
+            }
+            let mut bins = [(Aabb4::EMPTY, 0usize); SAH_BINS];
+            for &i in &self.indices[range.clone()] {
+                let bin = &mut bins[bucket(axis, self.centroids[i as usize])];
+                bin.0 = bin.0.union(self.bounds[i as usize]);
+                bin.1 += 1;
+            }
+
+            let mut right = [(0.0, 0usize); SAH_BINS];
+            let (mut bounds, mut count) = (Aabb4::EMPTY, 0);
+            for split in (1..SAH_BINS).rev() {
+                bounds = bounds.union(bins[split].0);
+                count += bins[split].1;
+                right[split] = (bounds.surface_area(), count);
+            }
+            let (mut bounds, mut count) = (Aabb4::EMPTY, 0);
+            for split in 1..SAH_BINS {
+                bounds = bounds.union(bins[split - 1].0);
+                count += bins[split - 1].1;
+                let (right_area, right_count) = right[split];
+                if count == 0 || right_count == 0 {
+                    continue;
+                }
+                let cost = bounds.surface_area() * count as f32 + right_area * right_count as f32;
+                if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                    best = Some((cost, axis, split));
+                }
+            }
+        }
+
+        let (_, axis, split) = best?;
+        let indices = &mut self.indices[range.clone()];
+        let mut middle = 0;
+        for i in 0..indices.len() {
+            if bucket(axis, self.centroids[indices[i] as usize]) < split {
+                indices.swap(i, middle);
+                middle += 1;
+            }
+        }
+        Some(range.start + middle)
+    }
+}
@@ -0,0 +1,408 @@
+use crate::{
+    BiVector4, ColorManagement, GpuCamera, RenderMode, RenderOptions, Rotor4, SamplerKind,
+};
+use cgmath::{InnerSpace, VectorSpace};
+
+/// Where the scene is viewed from and how it is sampled, see [`Camera::to_gpu`]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub position: cgmath::Vector4<f32>,
+    pub pitch: f32,
+    pub yaw: f32,
+    pub weird_pitch: f32,
+    pub weird_yaw: f32,
+    /// The vertical field of view, the horizontal one comes from the aspect ratio
+    pub fov: f32,
+    /// Used instead of the image's width over height, so the image can be squeezed or stretched
+    /// like it was shot through an anamorphic lens
+    pub aspect_override: Option<f32>,
+    /// Slides the view across the image plane without turning the camera, in half widths and
+    /// half heights of the image, for keeping vertical lines straight in architectural shots
+    pub lens_shift: cgmath::Vector2<f32>,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub bounce_count: u32,
+    pub sample_count: u32,
+    pub shutter_time: f32,
+    pub sampler: SamplerKind,
+    pub render_mode: RenderMode,
+    /// How far ambient occlusion rays look for something blocking them
+    pub ao_distance: f32,
+    pub w_ramp: WRamp,
+    /// When set the RNG only depends on this, the pixel and the frame index so renders are reproducible
+    pub deterministic_seed: Option<u32>,
+    /// Revolves the whole camera around a point, on top of its own position and rotation
+    pub orbit: Option<Orbit>,
+    pub cross_section: Option<CrossSection>,
+    pub projection: Projection,
+    /// The radius of the 3-sphere that [`Projection::Stereographic`] projects from
+    pub stereographic_radius: f32,
+    /// The angle across the circle that [`Projection::Fisheye`] fills
+    pub fisheye_angle: f32,
+    pub stereo: Option<Stereo>,
+    /// Traces one wavelength per sample so glass disperses light, only in [`RenderMode::PathTrace`]
+    pub spectral: bool,
+    /// Keeps a running average per light group, so changing [`crate::LightGroup`]s doesn't restart
+    /// the render. Uses more memory and only works on [`crate::RenderPath::Compute`]
+    pub light_group_aovs: bool,
+    /// Also averages the albedo, normal and depths of the first hit and the light split into
+    /// direct and indirect, for [`crate::Renderer::read_aovs`]. Only on [`crate::RenderPath::Compute`]
+    pub aovs: bool,
+    pub color_management: ColorManagement,
+}
+
+/// How the rays leaving the camera are shaped
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Projection {
+    Perspective,
+    /// The camera moves through the 3D space the 3-sphere of radius [`Camera::stereographic_radius`]
+    /// around the origin is stereographically projected into, so each straight ray becomes
+    /// a circle on the 3-sphere going to the pole. Only the part of the scene near the
+    /// 3-sphere is seen, and the 4D angles turn the 3-sphere under the camera
+    Stereographic,
+    /// An equidistant fisheye filling the largest circle that fits in the image, the distance
+    /// from the middle is proportional to the angle from forward up to half of
+    /// [`Camera::fisheye_angle`] at the edge of the circle
+    Fisheye,
+    /// The full sphere of directions in the camera's 3D hyperplane, longitude across the
+    /// image and latitude up it, for exporting environment maps
+    Equirectangular,
+}
+
+impl Projection {
+    pub const ALL: [Projection; 4] = [
+        Projection::Perspective,
+        Projection::Stereographic,
+        Projection::Fisheye,
+        Projection::Equirectangular,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Projection::Perspective => "Perspective",
+            Projection::Stereographic => "Stereographic",
+            Projection::Fisheye => "Fisheye",
+            Projection::Equirectangular => "Equirectangular",
+        }
+    }
+
+    pub fn shader_define(self) -> Option<&'static str> {
+        match self {
+            Projection::Perspective => None,
+            Projection::Stereographic => Some("STEREOGRAPHIC"),
+            Projection::Fisheye => Some("FISHEYE"),
+            Projection::Equirectangular => Some("EQUIRECTANGULAR"),
+        }
+    }
+
+    /// The direction the camera ray through `normalized_uv` leaves in, `None` outside the
+    /// fisheye circle. Matches the shader's `camera_direction`
+    pub fn ray_direction(
+        self,
+        camera: &GpuCamera,
+        normalized_uv: cgmath::Vector2<f32>,
+        aspect: f32,
+    ) -> Option<cgmath::Vector4<f32>> {
+        Some(match self {
+            Projection::Perspective | Projection::Stereographic => {
+                let theta = (camera.fov / 2.0).tan();
+                (camera.right * (normalized_uv.x * aspect * theta)
+                    + camera.up * (normalized_uv.y * theta)
+                    + camera.forward)
+                    .normalize()
+            }
+            Projection::Fisheye => {
+                // the circle touches the shorter sides of the image
+                let point = if aspect >= 1.0 {
+                    cgmath::vec2(normalized_uv.x * aspect, normalized_uv.y)
+                } else {
+                    cgmath::vec2(normalized_uv.x, normalized_uv.y / aspect)
+                };
+                let radius = point.magnitude();
+                if radius > 1.0 {
+                    return None;
+                }
+                let angle = radius * camera.fisheye_angle * 0.5;
+                let sideways = if radius > 0.0 {
+                    (camera.right * point.x + camera.up * point.y) / radius
+                } else {
+                    cgmath::vec4(0.0, 0.0, 0.0, 0.0)
+                };
+                camera.forward * angle.cos() + sideways * angle.sin()
+            }
+            Projection::Equirectangular => {
+                let longitude = normalized_uv.x * std::f32::consts::PI;
+                let latitude = normalized_uv.y * std::f32::consts::FRAC_PI_2;
+                (camera.forward * longitude.cos() + camera.right * longitude.sin()) * latitude.cos()
+                    + camera.up * latitude.sin()
+            }
+        })
+    }
+}
+
+/// The colors [`RenderMode::WCoordinate`] shows hits in, by how far they are along w from the camera
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct WRamp {
+    /// How far along w a hit has to be to get the full `kata` or `ana` color
+    pub range: f32,
+    /// For hits towards -w
+    pub kata: cgmath::Vector3<f32>,
+    /// For hits at the same w as the camera
+    pub center: cgmath::Vector3<f32>,
+    /// For hits towards +w
+    pub ana: cgmath::Vector3<f32>,
+}
+
+impl Default for WRamp {
+    fn default() -> Self {
+        Self {
+            range: 2.0,
+            kata: cgmath::vec3(0.1, 0.3, 1.0),
+            center: cgmath::vec3(0.9, 0.9, 0.9),
+            ana: cgmath::vec3(1.0, 0.2, 0.1),
+        }
+    }
+}
+
+impl WRamp {
+    /// The color for a hit `w_offset` along w from the camera
+    pub fn color(&self, w_offset: f32) -> cgmath::Vector3<f32> {
+        let t = (w_offset / self.range).clamp(-1.0, 1.0);
+        if t < 0.0 {
+            self.center.lerp(self.kata, -t)
+        } else {
+            self.center.lerp(self.ana, t)
+        }
+    }
+}
+
+/// Renders a side-by-side stereo pair, the left eye into the left half of the image and the
+/// right eye into the right half, each eye moved along the camera's right vector
+#[derive(Clone, Copy, PartialEq)]
+pub struct Stereo {
+    /// The distance between the eyes, the 4D version of the interpupillary distance
+    pub eye_separation: f32,
+    /// Swaps the halves so the pair can be viewed by crossing your eyes
+    pub cross_eyed: bool,
+}
+
+impl Default for Stereo {
+    fn default() -> Self {
+        Self {
+            eye_separation: 0.065,
+            cross_eyed: false,
+        }
+    }
+}
+
+/// Removes everything on the side of a hyperplane that `normal` points to, the surfaces
+/// where it cuts through hyper spheres are shown in `color`
+#[derive(Clone, Copy, PartialEq)]
+pub struct CrossSection {
+    pub point: cgmath::Vector4<f32>,
+    pub normal: cgmath::Vector4<f32>,
+    pub color: cgmath::Vector3<f32>,
+}
+
+impl Default for CrossSection {
+    fn default() -> Self {
+        Self {
+            point: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            normal: cgmath::vec4(0.0, 0.0, -1.0, 0.0),
+            color: cgmath::vec3(0.9, 0.1, 0.6),
+        }
+    }
+}
+
+/// Turntable motion around `target`, the camera keeps its view relative to the target as it moves
+#[derive(Clone, Copy, PartialEq)]
+pub struct Orbit {
+    pub target: cgmath::Vector4<f32>,
+    /// Any plane works, including the ones that rotate through w like [`BiVector4::XW`] and [`BiVector4::ZW`]
+    pub plane: BiVector4,
+    pub angle: f32,
+    /// Radians per second that [`Orbit::advance`] adds to `angle`
+    pub speed: f32,
+}
+
+impl Default for Orbit {
+    fn default() -> Self {
+        Self {
+            target: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            plane: BiVector4::ZX,
+            angle: 0.0,
+            speed: 30.0f32.to_radians(),
+        }
+    }
+}
+
+impl Orbit {
+    pub fn rotation(&self) -> Rotor4 {
+        Rotor4::from_angle_plane(self.angle, self.plane)
+    }
+
+    pub fn advance(&mut self, ts: f32) {
+        self.angle = (self.angle + self.speed * ts).rem_euclid(std::f32::consts::TAU);
+    }
+
+    pub fn apply_to_point(&self, point: cgmath::Vector4<f32>) -> cgmath::Vector4<f32> {
+        self.target + self.rotation().rotate_vec(point - self.target)
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            position: cgmath::vec4(0.0, 1.0, -3.0, 0.0),
+            pitch: 0.0,
+            yaw: 0.0,
+            weird_pitch: 0.0,
+            weird_yaw: 0.0,
+            fov: 90.0f32.to_radians(),
+            aspect_override: None,
+            lens_shift: cgmath::vec2(0.0, 0.0),
+            min_distance: 0.0001,
+            max_distance: 1000.0,
+            bounce_count: 10,
+            sample_count: 10,
+            shutter_time: 0.0,
+            sampler: SamplerKind::Pcg,
+            render_mode: RenderMode::PathTrace,
+            ao_distance: 1.0,
+            w_ramp: WRamp::default(),
+            deterministic_seed: None,
+            orbit: None,
+            cross_section: None,
+            projection: Projection::Perspective,
+            stereographic_radius: 1.0,
+            fisheye_angle: 180.0f32.to_radians(),
+            stereo: None,
+            spectral: false,
+            light_group_aovs: false,
+            aovs: false,
+            color_management: ColorManagement::Srgb,
+        }
+    }
+}
+
+impl Camera {
+    pub fn rotation(&self) -> Rotor4 {
+        Rotor4::from_angle_plane(self.yaw, BiVector4::ZX)
+            .rotate_by(Rotor4::from_angle_plane(self.pitch, BiVector4::ZY))
+            .rotate_by(Rotor4::from_angle_plane(self.weird_yaw, BiVector4::XW))
+            .rotate_by(Rotor4::from_angle_plane(self.weird_pitch, BiVector4::ZW))
+    }
+
+    /// Whether both cameras are in the same place looking the same way, ignoring how they sample
+    pub fn same_view(&self, other: &Camera) -> bool {
+        self.position == other.position
+            && self.pitch == other.pitch
+            && self.yaw == other.yaw
+            && self.weird_pitch == other.weird_pitch
+            && self.weird_yaw == other.weird_yaw
+            && self.fov == other.fov
+            && self.projection == other.projection
+            && self.fisheye_angle == other.fisheye_angle
+            && self.aspect_override == other.aspect_override
+            && self.lens_shift == other.lens_shift
+            && self.orbit == other.orbit
+            && self.stereo == other.stereo
+    }
+
+    /// The shader permutation needed to render with this camera
+    pub fn render_options(&self) -> RenderOptions {
+        RenderOptions {
+            motion_blur: self.shutter_time > 0.0,
+            sampler: self.sampler,
+            render_mode: self.render_mode,
+            cross_section: self.cross_section.is_some(),
+            projection: self.projection,
+            stereo: self.stereo.is_some(),
+            spectral: self.spectral && self.render_mode == RenderMode::PathTrace,
+            light_groups: self.light_group_aovs && self.render_mode == RenderMode::PathTrace,
+            aovs: self.aovs && self.render_mode == RenderMode::PathTrace,
+            color_management: self.color_management,
+            debug_path: false,
+            packed_objects: false,
+            material_blends: false,
+            material_nodes: false,
+            bvh: false,
+            wavefront: false,
+        }
+    }
+
+    /// `frame_index` should go up by one every frame so the noise changes between frames
+    pub fn to_gpu(&self, frame_index: u32) -> GpuCamera {
+        let rotation = self.rotation();
+        let mut position = self.position;
+        let mut forward = rotation.rotate_vec(cgmath::vec4(0.0, 0.0, 1.0, 0.0));
+        let mut right = rotation.rotate_vec(cgmath::vec4(1.0, 0.0, 0.0, 0.0));
+        let mut up = rotation.rotate_vec(cgmath::vec4(0.0, 1.0, 0.0, 0.0));
+        let mut pole = match self.projection {
+            Projection::Stereographic => {
+                rotation.rotate_vec(cgmath::vec4(0.0, 0.0, 0.0, self.stereographic_radius))
+            }
+            Projection::Perspective | Projection::Fisheye | Projection::Equirectangular => {
+                cgmath::vec4(0.0, 0.0, 0.0, 0.0)
+            }
+        };
+        if let Some(orbit) = self.orbit {
+            let orbit_rotation = orbit.rotation();
+            position = orbit.apply_to_point(position);
+            forward = orbit_rotation.rotate_vec(forward);
+            right = orbit_rotation.rotate_vec(right);
+            up = orbit_rotation.rotate_vec(up);
+            pole = orbit_rotation.rotate_vec(pole);
+        }
+        if self.projection == Projection::Stereographic {
+            // the camera has to be in the space that is projected into
+            position -= pole * (position.dot(pole) / pole.magnitude2());
+        }
+        GpuCamera {
+            position,
+            forward,
+            right,
+            up,
+            fov: self.fov,
+            aspect_override: self.aspect_override.unwrap_or(0.0),
+            lens_shift: self.lens_shift,
+            min_distance: self.min_distance,
+            max_distance: self.max_distance,
+            bounce_count: self.bounce_count,
+            sample_count: self.sample_count,
+            seed_offset: match self.deterministic_seed {
+                Some(seed) => seed ^ frame_index.wrapping_mul(0x9E3779B9),
+                None => rand::random(),
+            },
+            shutter_time: self.shutter_time,
+            // keeps the low discrepancy sequences progressing between frames
+            sample_index: frame_index.wrapping_mul(self.sample_count),
+            accumulated_frames: 0,
+            ao_distance: self.ao_distance,
+            w_range: self.w_ramp.range,
+            w_kata_color: self.w_ramp.kata,
+            w_center_color: self.w_ramp.center,
+            w_ana_color: self.w_ramp.ana,
+            cut_point: self
+                .cross_section
+                .map_or(cgmath::vec4(0.0, 0.0, 0.0, 0.0), |c| c.point),
+            cut_normal: self
+                .cross_section
+                .map_or(cgmath::vec4(0.0, 0.0, 0.0, 0.0), |c| c.normal),
+            cut_color: self
+                .cross_section
+                .map_or(cgmath::vec3(0.0, 0.0, 0.0), |c| c.color),
+            stereographic_pole: pole,
+            fisheye_angle: self.fisheye_angle,
+            eye_offset: self.stereo.map_or(0.0, |stereo| {
+                let offset = stereo.eye_separation * 0.5;
+                if stereo.cross_eyed {
+                    -offset
+                } else {
+                    offset
+                }
+            }),
+            light_group_weights: cgmath::vec4(1.0, 1.0, 1.0, 1.0),
+        }
+    }
+}
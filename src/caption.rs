@@ -0,0 +1,276 @@
+use crate::Camera;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Text burned into the bottom left corner of exported images, with a built in bitmap font
+/// so it doesn't depend on any fonts being installed
+#[derive(Clone, PartialEq, Debug)]
+pub struct Caption {
+    /// Usually the scene's name, left out when empty
+    pub title: String,
+    pub w_coordinate: bool,
+    pub sample_count: bool,
+    pub timestamp: bool,
+}
+
+impl Default for Caption {
+    fn default() -> Self {
+        Self {
+            title: String::new(),
+            w_coordinate: true,
+            sample_count: true,
+            timestamp: false,
+        }
+    }
+}
+
+impl Caption {
+    /// The line of text for an image rendered from `camera` with `samples` samples per pixel at `time`
+    pub fn text(&self, camera: &Camera, samples: u32, time: SystemTime) -> String {
+        let mut parts = vec![];
+        if !self.title.is_empty() {
+            parts.push(self.title.clone());
+        }
+        if self.w_coordinate {
+            parts.push(format!("w = {:.3}", camera.position.w));
+        }
+        if self.sample_count {
+            parts.push(format!("{samples} samples"));
+        }
+        if self.timestamp {
+            parts.push(format_utc(time));
+        }
+        parts.join("  |  ")
+    }
+
+    /// Draws the caption onto tightly packed RGBA8 rows like [`crate::Renderer::read_pixels`]
+    /// returns, scaled up with the image so it stays readable
+    pub fn burn(
+        &self,
+        pixels: &mut [u8],
+        width: usize,
+        height: usize,
+        camera: &Camera,
+        samples: u32,
+        time: SystemTime,
+    ) {
+        let text = self.text(camera, samples, time);
+        burn_text(pixels, width, height, &text, (height / 360).max(1));
+    }
+}
+
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+/// Around the text, in font pixels
+const PADDING: usize = 2;
+
+/// Draws one line of white text on a darkened box in the bottom left corner, each font pixel
+/// is `scale` image pixels wide. Whatever doesn't fit is cut off
+pub fn burn_text(pixels: &mut [u8], width: usize, height: usize, text: &str, scale: usize) {
+    assert_eq!(pixels.len(), width * height * 4);
+    if text.is_empty() {
+        return;
+    }
+    let advance = GLYPH_WIDTH + 1;
+    let box_width = ((text.chars().count() * advance - 1 + PADDING * 2) * scale).min(width);
+    let box_height = ((GLYPH_HEIGHT + PADDING * 2) * scale).min(height);
+    let top = height - box_height;
+
+    for y in top..height {
+        for pixel in pixels[y * width * 4..][..box_width * 4].chunks_exact_mut(4) {
+            for channel in &mut pixel[..3] {
+                *channel = (*channel as u32 * 2 / 5) as u8;
+            }
+        }
+    }
+
+    for (i, c) in text.chars().enumerate() {
+        let rows = glyph(c);
+        for (row, bits) in rows.into_iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                    continue;
+                }
+                let x = (PADDING + i * advance + column) * scale;
+                let y = top + (PADDING + row) * scale;
+                for y in y..(y + scale).min(height) {
+                    for x in x..(x + scale).min(box_width) {
+                        pixels[(y * width + x) * 4..][..3].fill(255);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Rows from the top, the lowest 5 bits of each from left to right. Lowercase letters are drawn
+/// as uppercase, and anything else without a glyph as `?`
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        'A' => [
+            0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+        ],
+        'B' => [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110,
+        ],
+        'C' => [
+            0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110,
+        ],
+        'D' => [
+            0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110,
+        ],
+        'E' => [
+            0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111,
+        ],
+        'F' => [
+            0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000,
+        ],
+        'G' => [
+            0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111,
+        ],
+        'H' => [
+            0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+        ],
+        'I' => [
+            0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+        'J' => [
+            0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100,
+        ],
+        'K' => [
+            0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001,
+        ],
+        'L' => [
+            0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111,
+        ],
+        'M' => [
+            0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001,
+        ],
+        'N' => [
+            0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001,
+        ],
+        'O' => [
+            0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+        ],
+        'P' => [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000,
+        ],
+        'Q' => [
+            0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101,
+        ],
+        'R' => [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001,
+        ],
+        'S' => [
+            0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110,
+        ],
+        'T' => [
+            0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100,
+        ],
+        'U' => [
+            0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+        ],
+        'V' => [
+            0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100,
+        ],
+        'W' => [
+            0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010,
+        ],
+        'X' => [
+            0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001,
+        ],
+        'Y' => [
+            0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00100,
+        ],
+        'Z' => [
+            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111,
+        ],
+        '0' => [
+            0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110,
+        ],
+        '1' => [
+            0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+        '2' => [
+            0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111,
+        ],
+        '3' => [
+            0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110,
+        ],
+        '4' => [
+            0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010,
+        ],
+        '5' => [
+            0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110,
+        ],
+        '6' => [
+            0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110,
+        ],
+        '7' => [
+            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000,
+        ],
+        '8' => [
+            0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110,
+        ],
+        '9' => [
+            0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100,
+        ],
+        ' ' => [0; GLYPH_HEIGHT],
+        '.' => [0, 0, 0, 0, 0, 0b01100, 0b01100],
+        ',' => [0, 0, 0, 0, 0b01100, 0b00100, 0b01000],
+        ':' => [0, 0b01100, 0b01100, 0, 0b01100, 0b01100, 0],
+        '-' => [0, 0, 0, 0b11111, 0, 0, 0],
+        '+' => [0, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0],
+        '=' => [0, 0, 0b11111, 0, 0b11111, 0, 0],
+        '/' => [
+            0b00001, 0b00010, 0b00010, 0b00100, 0b01000, 0b01000, 0b10000,
+        ],
+        '(' => [
+            0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010,
+        ],
+        ')' => [
+            0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000,
+        ],
+        '_' => [0, 0, 0, 0, 0, 0, 0b11111],
+        '|' => [0b00100; GLYPH_HEIGHT],
+        '#' => [
+            0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010,
+        ],
+        '%' => [
+            0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011,
+        ],
+        '!' => [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0, 0b00100],
+        '\'' => [0b00100, 0b00100, 0b01000, 0, 0, 0, 0],
+        _ => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0, 0b00100],
+    }
+}
+
+/// Like `2024-01-31 12:00:00 UTC`, times before 1970 are shown as 1970
+pub fn format_utc(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+
+    // the civil from days algorithm, with years starting in March so leap days come last
+    let days = days as i64 + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
@@ -0,0 +1,77 @@
+//! Puts images on the OS clipboard through the tools each platform ships with, arboard can
+//! only copy images with its `image-data` feature which needs the `image` crate
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Copies PNG encoded bytes to the clipboard as an image
+pub fn copy_png_to_clipboard(png: &[u8]) -> Result<(), String> {
+    if cfg!(target_os = "macos") {
+        let path = temporary_png(png)?;
+        let script = format!(
+            "set the clipboard to (read (POSIX file \"{}\") as «class PNGf»)",
+            path.display()
+        );
+        run(Command::new("osascript").args(["-e", &script]), None)
+    } else if cfg!(target_os = "windows") {
+        let path = temporary_png(png)?;
+        let script = format!(
+            "Add-Type -AssemblyName System.Windows.Forms, System.Drawing; \
+             [System.Windows.Forms.Clipboard]::SetImage([System.Drawing.Image]::FromFile('{}'))",
+            path.display()
+        );
+        run(
+            Command::new("powershell").args(["-NoProfile", "-STA", "-Command", &script]),
+            None,
+        )
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        run(
+            Command::new("wl-copy").args(["--type", "image/png"]),
+            Some(png),
+        )
+    } else {
+        run(
+            Command::new("xclip").args(["-selection", "clipboard", "-target", "image/png", "-i"]),
+            Some(png),
+        )
+    }
+}
+
+/// The tools read the image from a file, it is left for the OS to clean up since the
+/// clipboard may still be reading it
+fn temporary_png(png: &[u8]) -> Result<std::path::PathBuf, String> {
+    let path = std::env::temp_dir().join("four_dimentions_clipboard.png");
+    std::fs::write(&path, png)
+        .map_err(|error| format!("Failed to write {}: {error}", path.display()))?;
+    Ok(path)
+}
+
+fn run(command: &mut Command, input: Option<&[u8]>) -> Result<(), String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    // xclip and wl-copy keep running in the background to serve the clipboard, so their
+    // output isn't waited on
+    let mut child = command
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|error| format!("Failed to run {program}: {error}"))?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin
+            .write_all(input)
+            .map_err(|error| format!("Failed to send the image to {program}: {error}"))?;
+    }
+    let status = child
+        .wait()
+        .map_err(|error| format!("Failed to run {program}: {error}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{program} failed with {status}"))
+    }
+}
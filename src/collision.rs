@@ -0,0 +1,114 @@
+use crate::Scene;
+use cgmath::prelude::*;
+
+/// How far from surfaces a blocked move stops, so the next cast doesn't start touching them
+const SKIN: f32 = 1e-3;
+
+/// The distance along `direction`, which has to be normalized, that a hyper sphere of `radius`
+/// at `origin` can move before touching an object, and the normal of what it touches.
+/// Objects that already overlap it only block moving further into them
+pub fn sphere_cast(
+    scene: &Scene,
+    origin: cgmath::Vector4<f32>,
+    direction: cgmath::Vector4<f32>,
+    radius: f32,
+) -> Option<(f32, cgmath::Vector4<f32>)> {
+    let mut closest: Option<(f32, cgmath::Vector4<f32>)> = None;
+    let mut add_hit = |distance: f32, normal: cgmath::Vector4<f32>| {
+        if closest.is_none_or(|(closest, _)| distance < closest) {
+            closest = Some((distance, normal));
+        }
+    };
+
+    for hyper_sphere in scene.hyper_spheres() {
+        let oc = origin - hyper_sphere.center;
+        // inside a hollow hyper sphere only its inner surface is in the way
+        if oc.magnitude() < hyper_sphere.inner_radius {
+            let room = hyper_sphere.inner_radius - radius;
+            if oc.magnitude() >= room {
+                if oc.dot(direction) > 0.0 {
+                    add_hit(0.0, -oc.normalize());
+                }
+                continue;
+            }
+            let half_b = oc.dot(direction);
+            let c = oc.magnitude2() - room * room;
+            let distance = -half_b + (half_b * half_b - c).sqrt();
+            let position = origin + direction * distance;
+            add_hit(distance, (hyper_sphere.center - position) / room);
+            continue;
+        }
+
+        let combined_radius = hyper_sphere.radius + radius;
+        if oc.magnitude2() < combined_radius * combined_radius {
+            if oc.dot(direction) < 0.0 {
+                add_hit(0.0, oc.normalize());
+            }
+            continue;
+        }
+        let half_b = oc.dot(direction);
+        let c = oc.magnitude2() - combined_radius * combined_radius;
+        let discriminant = half_b * half_b - c;
+        if discriminant < 0.0 {
+            continue;
+        }
+        let distance = -half_b - discriminant.sqrt();
+        if distance >= 0.0 {
+            let position = origin + direction * distance;
+            add_hit(distance, (position - hyper_sphere.center) / combined_radius);
+        }
+    }
+
+    for hyper_plane in scene.hyper_planes() {
+        // hyper planes block from both sides, the normal faces whichever side the origin is on
+        let height = hyper_plane.normal.dot(origin - hyper_plane.point);
+        let normal = if height >= 0.0 {
+            hyper_plane.normal
+        } else {
+            -hyper_plane.normal
+        };
+        let approach = -normal.dot(direction);
+        if approach <= 0.0 {
+            continue;
+        }
+        let distance = (height.abs() - radius).max(0.0) / approach;
+        if !hyper_plane.contains(origin + direction * distance, radius) {
+            continue;
+        }
+        add_hit(distance, normal);
+    }
+
+    closest
+}
+
+/// Moves a hyper sphere of `radius` by `delta`, sliding along anything in the way instead of
+/// going through it, and returns where it ends up
+pub fn move_with_collisions(
+    scene: &Scene,
+    position: cgmath::Vector4<f32>,
+    delta: cgmath::Vector4<f32>,
+    radius: f32,
+) -> cgmath::Vector4<f32> {
+    let mut position = position;
+    let mut remaining = delta;
+    // each slide can only be blocked by something new, so a few are enough for corners
+    for _ in 0..4 {
+        let length = remaining.magnitude();
+        if length < 1e-6 {
+            break;
+        }
+        let direction = remaining / length;
+        match sphere_cast(scene, position, direction, radius) {
+            Some((distance, normal)) if distance < length => {
+                position += direction * (distance - SKIN).max(0.0);
+                let leftover = remaining * (1.0 - distance / length);
+                remaining = leftover - normal * leftover.dot(normal);
+            }
+            _ => {
+                position += remaining;
+                break;
+            }
+        }
+    }
+    position
+}
@@ -0,0 +1,352 @@
+use crate::{Camera, Dock, Layout, Panel};
+use std::path::PathBuf;
+
+#[derive(Clone)]
+pub struct Config {
+    pub power_preference: wgpu::PowerPreference,
+    pub backends: wgpu::Backends,
+    pub adapter_name: Option<String>,
+    pub present_mode: wgpu::PresentMode,
+    pub fps_cap: Option<f32>,
+    pub layout: Layout,
+    /// The size of the render compared to the viewport, the image is stretched to fill it
+    pub resolution_scale: f32,
+    pub sample_count: u32,
+    pub bounce_count: u32,
+    pub movement_speed: f32,
+    pub theme: Theme,
+    /// A `.script` scene file that is run when the editor starts
+    pub startup_scene: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 2] = [Theme::Dark, Theme::Light];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            backends: wgpu::Backends::PRIMARY | wgpu::Backends::GL,
+            adapter_name: None,
+            present_mode: wgpu::PresentMode::AutoNoVsync,
+            fps_cap: None,
+            layout: Layout::default(),
+            resolution_scale: 1.0,
+            sample_count: Camera::default().sample_count,
+            bounce_count: Camera::default().bounce_count,
+            movement_speed: 3.0,
+            theme: Theme::Dark,
+            startup_scene: None,
+        }
+    }
+}
+
+impl Config {
+    /// How small or large the render can be made compared to the viewport
+    pub const RESOLUTION_SCALES: std::ops::RangeInclusive<f32> = 0.125..=2.0;
+
+    /// Finds the adapter named [`Config::adapter_name`], preferring one on [`Config::backends`].
+    /// eframe requests the adapter itself, so it is chosen by narrowing `backends` to the one
+    /// it is on and setting `power_preference` for its kind of GPU. `None` if no adapter is
+    /// named that, eframe picks one by the power preference then
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn select_adapter(&mut self, instance: &wgpu::Instance) -> Option<wgpu::Adapter> {
+        let name = self.adapter_name.as_deref()?;
+        let mut adapters = instance
+            .enumerate_adapters(wgpu::Backends::all())
+            .filter(|adapter| adapter.get_info().name == name)
+            .collect::<Vec<_>>();
+        adapters.sort_by_key(|adapter| {
+            !self
+                .backends
+                .contains(backend_to_backends(adapter.get_info().backend))
+        });
+        let Some(adapter) = adapters.into_iter().next() else {
+            log::warn!("There is no adapter named {name:?}, picking one by the power preference");
+            return None;
+        };
+        let info = adapter.get_info();
+        self.backends = backend_to_backends(info.backend);
+        self.power_preference = adapter_power_preference(info.device_type);
+        Some(adapter)
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("4d-raytracing").join("config.toml"))
+    }
+
+    /// Loads the config file, falling back to the defaults for anything missing or invalid
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map_or_else(Self::default, |source| Self::parse(&source))
+    }
+
+    pub fn parse(source: &str) -> Self {
+        let mut config = Self::default();
+        let Ok(document) = source.parse::<toml_edit::Document>() else {
+            return config;
+        };
+
+        if let Some(power_preference) = document
+            .get("power_preference")
+            .and_then(|item| item.as_str())
+            .and_then(power_preference_from_str)
+        {
+            config.power_preference = power_preference;
+        }
+        if let Some(backend) = document
+            .get("backend")
+            .and_then(|item| item.as_str())
+            .and_then(backends_from_str)
+        {
+            config.backends = backend;
+        }
+        config.adapter_name = document
+            .get("adapter_name")
+            .and_then(|item| item.as_str())
+            .map(Into::into);
+        if let Some(present_mode) = document
+            .get("present_mode")
+            .and_then(|item| item.as_str())
+            .and_then(present_mode_from_str)
+        {
+            config.present_mode = supported_present_mode(present_mode);
+        }
+        config.fps_cap = document
+            .get("fps_cap")
+            .and_then(|item| item.as_float())
+            .map(|fps_cap| fps_cap as f32)
+            .filter(|&fps_cap| fps_cap > 0.0);
+        if let Some(layout) = document.get("layout").and_then(|item| item.as_table()) {
+            // the order of the keys is the order the panels are stacked in
+            config.layout = Layout::new(layout.iter().filter_map(|(panel, dock)| {
+                let panel = Panel::ALL.into_iter().find(|p| p.name() == panel)?;
+                let dock = Dock::ALL
+                    .into_iter()
+                    .find(|d| Some(d.name()) == dock.as_str())?;
+                Some((panel, dock))
+            }));
+        }
+        if let Some(resolution_scale) = document
+            .get("resolution_scale")
+            .and_then(|item| item.as_float())
+            .filter(|scale| Self::RESOLUTION_SCALES.contains(&(*scale as f32)))
+        {
+            config.resolution_scale = resolution_scale as f32;
+        }
+        if let Some(sample_count) = document
+            .get("sample_count")
+            .and_then(|item| item.as_integer())
+            .and_then(|count| u32::try_from(count).ok())
+            .filter(|&count| count > 0)
+        {
+            config.sample_count = sample_count;
+        }
+        if let Some(bounce_count) = document
+            .get("bounce_count")
+            .and_then(|item| item.as_integer())
+            .and_then(|count| u32::try_from(count).ok())
+        {
+            config.bounce_count = bounce_count;
+        }
+        if let Some(movement_speed) = document
+            .get("movement_speed")
+            .and_then(|item| item.as_float())
+            .filter(|&speed| speed > 0.0)
+        {
+            config.movement_speed = movement_speed as f32;
+        }
+        if let Some(theme) = document
+            .get("theme")
+            .and_then(|item| item.as_str())
+            .and_then(|name| Theme::ALL.into_iter().find(|t| t.name() == name))
+        {
+            config.theme = theme;
+        }
+        config.startup_scene = document
+            .get("startup_scene")
+            .and_then(|item| item.as_str())
+            .filter(|path| !path.is_empty())
+            .map(Into::into);
+
+        config
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(path) = Self::path() else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "could not find a config directory",
+            ));
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_toml())
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut document = toml_edit::Document::new();
+        document["power_preference"] =
+            toml_edit::value(power_preference_to_str(self.power_preference));
+        document["backend"] = toml_edit::value(backends_to_str(self.backends));
+        if let Some(adapter_name) = &self.adapter_name {
+            document["adapter_name"] = toml_edit::value(adapter_name.as_str());
+        }
+        document["present_mode"] = toml_edit::value(present_mode_to_str(self.present_mode));
+        if let Some(fps_cap) = self.fps_cap {
+            document["fps_cap"] = toml_edit::value(fps_cap as f64);
+        }
+        document["resolution_scale"] = toml_edit::value(self.resolution_scale as f64);
+        document["sample_count"] = toml_edit::value(self.sample_count as i64);
+        document["bounce_count"] = toml_edit::value(self.bounce_count as i64);
+        document["movement_speed"] = toml_edit::value(self.movement_speed as f64);
+        document["theme"] = toml_edit::value(self.theme.name());
+        if let Some(startup_scene) = &self.startup_scene {
+            document["startup_scene"] = toml_edit::value(startup_scene.to_string_lossy().as_ref());
+        }
+        let mut layout = toml_edit::Table::new();
+        for &(panel, dock) in self.layout.panels() {
+            layout[panel.name()] = toml_edit::value(dock.name());
+        }
+        document["layout"] = toml_edit::Item::Table(layout);
+        document.to_string()
+    }
+}
+
+pub fn power_preference_to_str(power_preference: wgpu::PowerPreference) -> &'static str {
+    match power_preference {
+        wgpu::PowerPreference::LowPower => "low-power",
+        wgpu::PowerPreference::HighPerformance => "high-performance",
+    }
+}
+
+pub fn power_preference_from_str(s: &str) -> Option<wgpu::PowerPreference> {
+    Some(match s {
+        "low-power" => wgpu::PowerPreference::LowPower,
+        "high-performance" => wgpu::PowerPreference::HighPerformance,
+        _ => return None,
+    })
+}
+
+/// The present modes every surface supports. eframe configures its surface with the mode
+/// without checking it, and doesn't give access to the surface to ask what else it supports
+pub const PRESENT_MODES: [wgpu::PresentMode; 3] = [
+    wgpu::PresentMode::AutoVsync,
+    wgpu::PresentMode::AutoNoVsync,
+    wgpu::PresentMode::Fifo,
+];
+
+/// `present_mode` if it is one of [`PRESENT_MODES`], otherwise the automatic mode that picks it
+/// when the surface supports it
+pub fn supported_present_mode(present_mode: wgpu::PresentMode) -> wgpu::PresentMode {
+    match present_mode {
+        wgpu::PresentMode::Mailbox | wgpu::PresentMode::Immediate => wgpu::PresentMode::AutoNoVsync,
+        wgpu::PresentMode::FifoRelaxed => wgpu::PresentMode::AutoVsync,
+        present_mode => present_mode,
+    }
+}
+
+pub fn present_mode_to_str(present_mode: wgpu::PresentMode) -> &'static str {
+    match present_mode {
+        wgpu::PresentMode::AutoVsync => "auto-vsync",
+        wgpu::PresentMode::AutoNoVsync => "auto-no-vsync",
+        wgpu::PresentMode::Fifo => "fifo",
+        wgpu::PresentMode::FifoRelaxed => "fifo-relaxed",
+        wgpu::PresentMode::Immediate => "immediate",
+        wgpu::PresentMode::Mailbox => "mailbox",
+    }
+}
+
+pub fn present_mode_from_str(s: &str) -> Option<wgpu::PresentMode> {
+    Some(match s {
+        "auto-vsync" => wgpu::PresentMode::AutoVsync,
+        "auto-no-vsync" => wgpu::PresentMode::AutoNoVsync,
+        "fifo" => wgpu::PresentMode::Fifo,
+        "fifo-relaxed" => wgpu::PresentMode::FifoRelaxed,
+        "immediate" => wgpu::PresentMode::Immediate,
+        "mailbox" => wgpu::PresentMode::Mailbox,
+        _ => return None,
+    })
+}
+
+pub fn backends_to_str(backends: wgpu::Backends) -> &'static str {
+    match backends {
+        wgpu::Backends::VULKAN => "vulkan",
+        wgpu::Backends::METAL => "metal",
+        wgpu::Backends::DX12 => "dx12",
+        wgpu::Backends::DX11 => "dx11",
+        wgpu::Backends::GL => "gl",
+        _ => "auto",
+    }
+}
+
+pub fn backends_from_str(s: &str) -> Option<wgpu::Backends> {
+    Some(match s {
+        "vulkan" => wgpu::Backends::VULKAN,
+        "metal" => wgpu::Backends::METAL,
+        "dx12" => wgpu::Backends::DX12,
+        "dx11" => wgpu::Backends::DX11,
+        "gl" => wgpu::Backends::GL,
+        "auto" => Config::default().backends,
+        _ => return None,
+    })
+}
+
+pub fn backend_to_backends(backend: wgpu::Backend) -> wgpu::Backends {
+    match backend {
+        wgpu::Backend::Vulkan => wgpu::Backends::VULKAN,
+        wgpu::Backend::Metal => wgpu::Backends::METAL,
+        wgpu::Backend::Dx12 => wgpu::Backends::DX12,
+        wgpu::Backend::Dx11 => wgpu::Backends::DX11,
+        wgpu::Backend::Gl => wgpu::Backends::GL,
+        wgpu::Backend::BrowserWebGpu => wgpu::Backends::BROWSER_WEBGPU,
+        wgpu::Backend::Empty => wgpu::Backends::empty(),
+    }
+}
+
+/// What [`Config::power_preference`] picks an adapter of `device_type` over the others of its backend
+pub fn adapter_power_preference(device_type: wgpu::DeviceType) -> wgpu::PowerPreference {
+    match device_type {
+        wgpu::DeviceType::DiscreteGpu => wgpu::PowerPreference::HighPerformance,
+        _ => wgpu::PowerPreference::LowPower,
+    }
+}
+
+/// Lists every adapter wgpu can find on this machine, across all backends
+#[cfg(not(target_arch = "wasm32"))]
+pub fn available_adapters() -> Vec<wgpu::AdapterInfo> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        ..Default::default()
+    });
+    instance
+        .enumerate_adapters(wgpu::Backends::all())
+        .map(|adapter| adapter.get_info())
+        .collect()
+}
+
+/// Browsers only hand out a single adapter, so there is nothing to choose from
+#[cfg(target_arch = "wasm32")]
+pub fn available_adapters() -> Vec<wgpu::AdapterInfo> {
+    vec![]
+}
@@ -0,0 +1,1046 @@
+//! A slow CPU port of the ray tracing shader, used to cross-validate the GPU output.
+//! Each function mirrors the WGSL function with the same name, only the PCG sampler is implemented.
+
+use crate::material_graph::lattice_value;
+use crate::{
+    physical_sky_radiance, Camera, EmissionPattern, Environment, GpuCamera, GpuEnvironment,
+    GpuError, GpuHyperPlane, GpuHyperSphere, GpuMaterial, GpuNodeInstruction, Projection,
+    RayTracer, RenderMode, RenderOptions, Rotor4, MAX_BLEND_DEPTH,
+};
+use cgmath::{prelude::*, Vector3, Vector4};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Ray {
+    pub origin: Vector4<f32>,
+    pub direction: Vector4<f32>,
+    pub time: f32,
+    /// In nanometers, [`REFERENCE_WAVELENGTH`] unless the camera is spectral
+    pub wavelength: f32,
+}
+
+/// The wavelength that [`GpuMaterial::ior`] is for, given to every ray when not rendering spectrally
+pub const REFERENCE_WAVELENGTH: f32 = 589.3;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Hit {
+    pub distance: f32,
+    pub position: Vector4<f32>,
+    pub normal: Vector4<f32>,
+    pub material: u32,
+    /// Where emission patterns are evaluated, the plane coordinates of hyper planes with their own tangents
+    pub pattern_position: Vector4<f32>,
+    pub cut: bool,
+    pub front_face: bool,
+}
+
+pub struct Scene<'a> {
+    pub camera: &'a GpuCamera,
+    pub hyper_spheres: &'a [GpuHyperSphere],
+    pub hyper_planes: &'a [GpuHyperPlane],
+    pub materials: &'a [GpuMaterial],
+    /// What the `node_program` of the materials point into
+    pub node_programs: &'a [GpuNodeInstruction],
+    pub environment: &'a GpuEnvironment,
+}
+
+pub fn random_value(state: &mut u32) -> f32 {
+    *state = state.wrapping_mul(747796405).wrapping_add(2891336453);
+    let mut result = ((*state >> ((*state >> 28) + 4)) ^ *state).wrapping_mul(277803737);
+    result ^= result >> 22;
+    result as f32 / 4294967295.0
+}
+
+fn hash(x: u32) -> u32 {
+    let mut state = x;
+    (random_value(&mut state) * 4294967295.0) as u32
+}
+
+/// The first distance past `min_distance` where the ray crosses a sphere of `radius`
+fn sphere_crossing(
+    oc: Vector4<f32>,
+    direction: Vector4<f32>,
+    radius: f32,
+    min_distance: f32,
+) -> Option<f32> {
+    let a = direction.dot(direction);
+    let half_b = oc.dot(direction);
+    let c = oc.dot(oc) - radius * radius;
+    let discriminant = half_b * half_b - a * c;
+
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let sqrt_discriminant = discriminant.sqrt();
+    let t0 = (-half_b - sqrt_discriminant) / a;
+    let t1 = (-half_b + sqrt_discriminant) / a;
+
+    [t0, t1].into_iter().find(|&t| t > min_distance)
+}
+
+pub fn intersect_hyper_sphere(
+    camera: &GpuCamera,
+    ray: Ray,
+    hyper_sphere: &GpuHyperSphere,
+) -> Option<Hit> {
+    intersect_hyper_sphere_after(camera, ray, hyper_sphere, camera.min_distance)
+}
+
+fn intersect_hyper_sphere_after(
+    camera: &GpuCamera,
+    ray: Ray,
+    hyper_sphere: &GpuHyperSphere,
+    min_distance: f32,
+) -> Option<Hit> {
+    let center = hyper_sphere.center + hyper_sphere.velocity * ray.time;
+    let oc = ray.origin - center;
+
+    let outer = sphere_crossing(oc, ray.direction, hyper_sphere.radius, min_distance);
+    let inner = if hyper_sphere.inner_radius > 0.0 {
+        sphere_crossing(oc, ray.direction, hyper_sphere.inner_radius, min_distance)
+    } else {
+        None
+    };
+    let (distance, inner) = match (outer, inner) {
+        (outer, Some(inner)) if outer.is_none_or(|outer| inner < outer) => (inner, true),
+        (outer, _) => (outer?, false),
+    };
+    if camera.max_distance < distance {
+        return None;
+    }
+
+    let position = ray.origin + ray.direction * distance;
+    let mut normal = (position - center).normalize();
+    if inner {
+        normal *= -1.0;
+    }
+    let front_face = normal.dot(ray.origin - position) >= 0.0;
+    if !front_face {
+        normal *= -1.0;
+    }
+
+    Some(Hit {
+        distance,
+        position,
+        normal,
+        material: hyper_sphere.material,
+        pattern_position: position,
+        cut: false,
+        front_face,
+    })
+}
+
+pub fn intersect_hyper_plane(
+    camera: &GpuCamera,
+    ray: Ray,
+    hyper_plane: &GpuHyperPlane,
+) -> Option<Hit> {
+    let point = hyper_plane.point + hyper_plane.velocity * ray.time;
+    let speed = hyper_plane.angular_velocity.length();
+    let rotate = |v: Vector4<f32>| {
+        if speed == 0.0 {
+            v
+        } else {
+            Rotor4::from_angle_plane(speed * ray.time, hyper_plane.angular_velocity.normalized())
+                .rotate_vec(v)
+        }
+    };
+    let normal = rotate(hyper_plane.normal);
+
+    let d = normal.dot(ray.direction);
+    if d == 0.0 {
+        return None;
+    }
+
+    let distance = (point - ray.origin).dot(normal) / d;
+    if distance < camera.min_distance || camera.max_distance < distance {
+        return None;
+    }
+
+    let position = ray.origin + ray.direction * distance;
+
+    let offset = position - point;
+    let coordinates = hyper_plane
+        .tangents()
+        .map(|tangent| offset.dot(rotate(tangent)));
+    for (k, coordinate) in coordinates.into_iter().enumerate() {
+        let extent = hyper_plane.extents[k];
+        if extent > 0.0 && coordinate.abs() > extent {
+            return None;
+        }
+    }
+    let pattern_position = if hyper_plane.custom_tangents().is_some() {
+        cgmath::vec4(coordinates[0], coordinates[1], coordinates[2], 0.0)
+    } else {
+        position
+    };
+
+    let mut normal = normal;
+    let front_face = normal.dot(ray.origin - position) >= 0.0;
+    if !front_face {
+        normal *= -1.0;
+    }
+
+    Some(Hit {
+        distance,
+        position,
+        normal,
+        material: hyper_plane.material,
+        pattern_position,
+        cut: false,
+        front_face,
+    })
+}
+
+fn is_cut_away(camera: &GpuCamera, position: Vector4<f32>) -> bool {
+    (position - camera.cut_point).dot(camera.cut_normal) > 0.0
+}
+
+fn cut_hyper_sphere(
+    camera: &GpuCamera,
+    ray: Ray,
+    hyper_sphere: &GpuHyperSphere,
+    hit: Hit,
+) -> Option<Hit> {
+    if !is_cut_away(camera, hit.position) {
+        return Some(hit);
+    }
+
+    let d = camera.cut_normal.dot(ray.direction);
+    if d == 0.0 {
+        return None;
+    }
+
+    let t = (camera.cut_point - ray.origin).dot(camera.cut_normal) / d;
+    if t < camera.min_distance || camera.max_distance < t {
+        return None;
+    }
+
+    let position = ray.origin + ray.direction * t;
+    let center = hyper_sphere.center + hyper_sphere.velocity * ray.time;
+    let from_center = position.distance(center);
+    if from_center > hyper_sphere.radius {
+        return None;
+    }
+    if from_center < hyper_sphere.inner_radius {
+        return intersect_hyper_sphere_after(camera, ray, hyper_sphere, t)
+            .filter(|next_hit| !is_cut_away(camera, next_hit.position));
+    }
+
+    Some(Hit {
+        distance: t,
+        position,
+        normal: camera.cut_normal * -d.signum(),
+        material: hit.material,
+        pattern_position: position,
+        cut: true,
+        front_face: true,
+    })
+}
+
+/// Only applies the cross section when the camera has one, the shader decides with the `CROSS_SECTION` define instead
+pub fn get_closest_hit(scene: &Scene, ray: Ray) -> Option<Hit> {
+    let camera = scene.camera;
+    let cross_section = camera.cut_normal != cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+    let sphere_hits = scene.hyper_spheres.iter().filter_map(|hyper_sphere| {
+        let hit = intersect_hyper_sphere(camera, ray, hyper_sphere)?;
+        if cross_section {
+            cut_hyper_sphere(camera, ray, hyper_sphere, hit)
+        } else {
+            Some(hit)
+        }
+    });
+    let plane_hits = scene
+        .hyper_planes
+        .iter()
+        .filter_map(|hyper_plane| intersect_hyper_plane(camera, ray, hyper_plane))
+        .filter(|hit| !(cross_section && is_cut_away(camera, hit.position)));
+    sphere_hits
+        .chain(plane_hits)
+        .filter(|hit| hit.distance < scene.camera.max_distance)
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+}
+
+// uses the same truncated pi as the shader
+#[allow(clippy::approx_constant)]
+fn sample_normal_distribution(rng: &mut u32) -> f32 {
+    let theta = 2.0 * 3.1415926 * random_value(rng);
+    let rho = (-2.0 * random_value(rng).max(1.0e-7).ln()).sqrt();
+    rho * theta.cos()
+}
+
+fn sample_direction(rng: &mut u32) -> Vector4<f32> {
+    let x = sample_normal_distribution(rng);
+    let y = sample_normal_distribution(rng);
+    let z = sample_normal_distribution(rng);
+    let w = sample_normal_distribution(rng);
+    cgmath::vec4(x, y, z, w).normalize()
+}
+
+fn diffuse_direction(normal: Vector4<f32>, rng: &mut u32) -> Vector4<f32> {
+    (normal + sample_direction(rng)).normalize()
+}
+
+fn clearcoat_reflectance(direction: Vector4<f32>, normal: Vector4<f32>) -> f32 {
+    let cos_theta = (-direction.dot(normal)).clamp(0.0, 1.0);
+    0.04 + 0.96 * (1.0 - cos_theta).powf(5.0)
+}
+
+fn glossy_direction(
+    direction: Vector4<f32>,
+    normal: Vector4<f32>,
+    roughness: f32,
+    rng: &mut u32,
+) -> Vector4<f32> {
+    let reflected = direction - 2.0 * direction.dot(normal) * normal;
+    let mut scattered = (reflected + sample_direction(rng) * roughness).normalize();
+    let below = scattered.dot(normal);
+    if below < 0.0 {
+        scattered -= 2.0 * below * normal;
+    }
+    scattered
+}
+
+fn sheen_color(
+    material: &GpuMaterial,
+    incoming: Vector4<f32>,
+    outgoing: Vector4<f32>,
+) -> Vector3<f32> {
+    let half_vector = (outgoing - incoming).normalize();
+    let cos_d = outgoing.dot(half_vector).clamp(0.0, 1.0);
+    material.sheen_color * (material.sheen * (1.0 - cos_d).powf(5.0))
+}
+
+fn value_noise_gradient(p: Vector4<f32>) -> Vector4<f32> {
+    let cell = p.map(f32::floor);
+    let f = p - cell;
+    let t = f.map(|f| f * f * (3.0 - 2.0 * f));
+    let dt = f.map(|f| 6.0 * f * (1.0 - f));
+
+    let mut gradient = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+    for i in 0..16 {
+        let corner = cgmath::vec4(i & 1, (i >> 1) & 1, (i >> 2) & 1, (i >> 3) & 1);
+        let pick = |axis: usize, zero: f32, one: f32| if corner[axis] == 1 { one } else { zero };
+        let w = Vector4::from([0, 1, 2, 3].map(|axis| pick(axis, 1.0 - t[axis], t[axis])));
+        let dw = Vector4::from([0, 1, 2, 3].map(|axis| pick(axis, -dt[axis], dt[axis])));
+        let value = lattice_value(cell.map(|c| c as i32) + corner);
+        gradient += value
+            * cgmath::vec4(
+                dw.x * w.y * w.z * w.w,
+                w.x * dw.y * w.z * w.w,
+                w.x * w.y * dw.z * w.w,
+                w.x * w.y * w.z * dw.w,
+            );
+    }
+    gradient
+}
+
+fn bump_normal(
+    normal: Vector4<f32>,
+    position: Vector4<f32>,
+    strength: f32,
+    scale: f32,
+) -> Vector4<f32> {
+    let gradient = value_noise_gradient(position * scale);
+    (normal - strength * (gradient - gradient.dot(normal) * normal)).normalize()
+}
+
+/// The shader's `light_group_weight`
+fn light_group_weight(camera: &GpuCamera, light_group: u32) -> f32 {
+    if light_group == 0 || light_group > 4 {
+        return 1.0;
+    }
+    camera.light_group_weights[light_group as usize - 1]
+}
+
+fn emission_pattern(material: &GpuMaterial, position: Vector4<f32>) -> f32 {
+    let scaled = position.mul_element_wise(material.emission_pattern_scale);
+    let sum = scaled.x + scaled.y + scaled.z + scaled.w;
+    match EmissionPattern::from_u32(material.emission_pattern) {
+        Some(EmissionPattern::Gradient) => (0.5 + sum).clamp(0.0, 1.0),
+        Some(EmissionPattern::Stripes) => {
+            if sum - sum.floor() < 0.5 {
+                0.0
+            } else {
+                1.0
+            }
+        }
+        Some(EmissionPattern::Checker) => {
+            let cell = scaled.map(|x| x.floor() as i32);
+            ((cell.x + cell.y + cell.z + cell.w) & 1) as f32
+        }
+        Some(EmissionPattern::Solid) | None => 1.0,
+    }
+}
+
+/// The shader's `resolve_material`, the masks are the ones [`crate::MaterialNode::resolve`] uses
+fn resolve_material(
+    materials: &[GpuMaterial],
+    index: u32,
+    position: Vector4<f32>,
+    sample: f32,
+) -> u32 {
+    let mut index = index;
+    let mut sample = sample;
+    for _ in 0..MAX_BLEND_DEPTH {
+        let material = &materials[index as usize];
+        let Some(mask) = material.blend_mask() else {
+            break;
+        };
+        let mask = mask.value(position.mul_element_wise(material.blend_scale));
+        if sample < mask {
+            sample /= mask;
+            index = material.blend_b;
+        } else {
+            sample = (sample - mask) / (1.0 - mask);
+            index = material.blend_a;
+        }
+    }
+    index
+}
+
+fn gradient_sky(direction: Vector4<f32>) -> Vector3<f32> {
+    let up_color = cgmath::vec3(0.3, 0.4, 0.8);
+    let down_color = cgmath::vec3(0.2, 0.2, 0.2);
+    down_color.lerp(up_color, direction.y * 0.5 + 0.5)
+}
+
+fn sky_color(environment: &GpuEnvironment, direction: Vector4<f32>) -> Vector3<f32> {
+    if environment.sky == 1 {
+        let mut color = physical_sky_radiance(environment, direction);
+        if direction.dot(environment.sun_direction) > environment.sun_cos_radius {
+            color += environment.sun_radiance;
+        }
+        color
+    } else {
+        gradient_sky(direction)
+    }
+}
+
+/// The `AMBIENT_OCCLUSION` version of `trace` in the shader
+pub fn ambient_occlusion(scene: &Scene, ray: Ray, rng: &mut u32) -> Vector3<f32> {
+    let Some(hit) = get_closest_hit(scene, ray) else {
+        return cgmath::vec3(1.0, 1.0, 1.0);
+    };
+    let occlusion_ray = Ray {
+        origin: hit.position + hit.normal * scene.camera.min_distance,
+        direction: diffuse_direction(hit.normal, rng),
+        time: ray.time,
+        wavelength: ray.wavelength,
+    };
+    match get_closest_hit(scene, occlusion_ray) {
+        Some(occluder) if occluder.distance < scene.camera.ao_distance => {
+            cgmath::vec3(0.0, 0.0, 0.0)
+        }
+        _ => cgmath::vec3(1.0, 1.0, 1.0),
+    }
+}
+
+pub fn index_of_refraction(material: &GpuMaterial, wavelength: f32) -> f32 {
+    let micrometers = wavelength * 0.001;
+    material.ior
+        + material.dispersion * (1.0 / (micrometers * micrometers) - 1.0 / (0.5893 * 0.5893))
+}
+
+/// The WGSL `refract`, zero for total internal reflection
+fn refract(incident: Vector4<f32>, normal: Vector4<f32>, eta: f32) -> Vector4<f32> {
+    let d = normal.dot(incident);
+    let k = 1.0 - eta * eta * (1.0 - d * d);
+    if k < 0.0 {
+        cgmath::vec4(0.0, 0.0, 0.0, 0.0)
+    } else {
+        eta * incident - (eta * d + k.sqrt()) * normal
+    }
+}
+
+/// The transmissive materials a ray is inside of, innermost last
+#[derive(Default)]
+struct MediumStack {
+    materials: [u32; 4],
+    count: usize,
+}
+
+struct Boundary {
+    false_hit: bool,
+    from_ior: f32,
+    to_ior: f32,
+    inside: Option<usize>,
+}
+
+impl MediumStack {
+    fn top(&self, materials: &[GpuMaterial], excluded: Option<usize>) -> Option<usize> {
+        let mut top: Option<usize> = None;
+        for i in 0..self.count {
+            let priority = materials[self.materials[i] as usize].priority;
+            if Some(i) != excluded
+                && top
+                    .is_none_or(|top| priority >= materials[self.materials[top] as usize].priority)
+            {
+                top = Some(i);
+            }
+        }
+        top
+    }
+
+    fn transmittance(&self, materials: &[GpuMaterial], distance: f32) -> Vector3<f32> {
+        let Some(top) = self.top(materials, None) else {
+            return cgmath::vec3(1.0, 1.0, 1.0);
+        };
+        let material = &materials[self.materials[top] as usize];
+        let depth = material.absorption_density * distance;
+        if depth <= 0.0 {
+            return cgmath::vec3(1.0, 1.0, 1.0);
+        }
+        material.absorption_color.map(|c| c.powf(depth))
+    }
+
+    fn ior(&self, materials: &[GpuMaterial], index: Option<usize>, wavelength: f32) -> f32 {
+        index.map_or(1.0, |index| {
+            index_of_refraction(&materials[self.materials[index] as usize], wavelength)
+        })
+    }
+
+    fn boundary(
+        &self,
+        materials: &[GpuMaterial],
+        hit: &Hit,
+        material: &GpuMaterial,
+        wavelength: f32,
+    ) -> Boundary {
+        let inside = (0..self.count)
+            .rev()
+            .find(|&i| self.materials[i] == hit.material);
+        let outside = self.top(materials, inside);
+        let ior = index_of_refraction(material, wavelength);
+        let outside_ior = self.ior(materials, outside, wavelength);
+        let (from_ior, to_ior) = if hit.front_face {
+            (outside_ior, ior)
+        } else {
+            (ior, outside_ior)
+        };
+        Boundary {
+            false_hit: outside.is_some_and(|outside| {
+                materials[self.materials[outside] as usize].priority > material.priority
+            }),
+            from_ior,
+            to_ior,
+            inside,
+        }
+    }
+
+    fn cross(&mut self, hit: &Hit, boundary: &Boundary) {
+        if hit.front_face {
+            if self.count < self.materials.len() {
+                self.materials[self.count] = hit.material;
+                self.count += 1;
+            }
+        } else if let Some(inside) = boundary.inside {
+            self.materials.copy_within(inside + 1..self.count, inside);
+            self.count -= 1;
+        }
+    }
+}
+
+fn dielectric_scatter(
+    camera: &GpuCamera,
+    ray: Ray,
+    hit: &Hit,
+    from_ior: f32,
+    to_ior: f32,
+    rng: &mut u32,
+) -> Ray {
+    let eta = from_ior / to_ior;
+    let cos_theta = (-ray.direction.dot(hit.normal)).min(1.0);
+    let r0 = ((from_ior - to_ior) / (from_ior + to_ior)).powi(2);
+    let reflectance = r0 + (1.0 - r0) * (1.0 - cos_theta).powf(5.0);
+
+    let refracted = refract(ray.direction, hit.normal, eta);
+    if refracted == cgmath::vec4(0.0, 0.0, 0.0, 0.0) || random_value(rng) < reflectance {
+        Ray {
+            origin: hit.position + hit.normal * camera.min_distance,
+            direction: ray.direction - 2.0 * ray.direction.dot(hit.normal) * hit.normal,
+            ..ray
+        }
+    } else {
+        Ray {
+            origin: hit.position - hit.normal * camera.min_distance,
+            direction: refracted.normalize(),
+            ..ray
+        }
+    }
+}
+
+fn cie_lobe(wavelength: f32, peak: f32, width_below: f32, width_above: f32) -> f32 {
+    let width = if wavelength < peak {
+        width_below
+    } else {
+        width_above
+    };
+    let t = (wavelength - peak) / width;
+    (-0.5 * t * t).exp()
+}
+
+pub fn cie_xyz(wavelength: f32) -> Vector3<f32> {
+    cgmath::vec3(
+        1.056 * cie_lobe(wavelength, 599.8, 37.9, 31.0)
+            + 0.362 * cie_lobe(wavelength, 442.0, 16.0, 26.7)
+            - 0.065 * cie_lobe(wavelength, 501.1, 20.4, 26.2),
+        0.821 * cie_lobe(wavelength, 568.8, 46.9, 40.5)
+            + 0.286 * cie_lobe(wavelength, 530.9, 16.3, 31.1),
+        1.217 * cie_lobe(wavelength, 437.0, 11.8, 36.0)
+            + 0.681 * cie_lobe(wavelength, 459.0, 26.0, 13.8),
+    )
+}
+
+pub fn wavelength_to_rgb(wavelength: f32) -> Vector3<f32> {
+    let xyz = cie_xyz(wavelength);
+    let rgb = cgmath::vec3(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    );
+    rgb.map(|c| c.max(0.0))
+        .div_element_wise(cgmath::vec3(0.4404567, 0.2884662, 0.2732404))
+}
+
+/// The `W_COORDINATE` version of `trace` in the shader
+pub fn w_coordinate(scene: &Scene, ray: Ray) -> Vector3<f32> {
+    let Some(hit) = get_closest_hit(scene, ray) else {
+        return sky_color(scene.environment, ray.direction);
+    };
+    let camera = scene.camera;
+    let t = ((hit.position.w - camera.position.w) / camera.w_range).clamp(-1.0, 1.0);
+    let color = if t < 0.0 {
+        camera.w_center_color.lerp(camera.w_kata_color, -t)
+    } else {
+        camera.w_center_color.lerp(camera.w_ana_color, t)
+    };
+    color * (0.25 + 0.75 * hit.normal.dot(ray.direction).abs())
+}
+
+/// Whichever `trace` the shader is built with for `render_mode`
+fn trace_render_mode(
+    scene: &Scene,
+    render_mode: RenderMode,
+    ray: Ray,
+    rng: &mut u32,
+) -> Vector3<f32> {
+    match render_mode {
+        RenderMode::PathTrace => trace(scene, ray, rng),
+        RenderMode::AmbientOcclusion => ambient_occlusion(scene, ray, rng),
+        RenderMode::WCoordinate => w_coordinate(scene, ray),
+    }
+}
+
+pub fn trace(scene: &Scene, ray: Ray, rng: &mut u32) -> Vector3<f32> {
+    let mut ray = ray;
+    let mut incoming_light = cgmath::vec3(0.0, 0.0, 0.0);
+    let mut ray_color = cgmath::vec3(1.0, 1.0, 1.0);
+    let mut media = MediumStack::default();
+
+    let mut bounces = 0;
+    for _ in 0..scene.camera.bounce_count + 8 {
+        if bounces >= scene.camera.bounce_count {
+            break;
+        }
+        match get_closest_hit(scene, ray) {
+            Some(mut hit) => {
+                ray_color
+                    .mul_assign_element_wise(media.transmittance(scene.materials, hit.distance));
+                if scene.materials[hit.material as usize]
+                    .blend_mask()
+                    .is_some()
+                {
+                    hit.material = resolve_material(
+                        scene.materials,
+                        hit.material,
+                        hit.pattern_position,
+                        random_value(rng),
+                    );
+                }
+                let mut material = scene.materials[hit.material as usize];
+                if material.node_program_length > 0 {
+                    let program = scene
+                        .node_programs
+                        .iter()
+                        .skip(material.node_program as usize)
+                        .take(material.node_program_length as usize)
+                        .copied()
+                        .collect::<Vec<_>>();
+                    GpuNodeInstruction::run(
+                        &program,
+                        &mut material,
+                        hit.pattern_position,
+                        hit.normal,
+                        ray.direction,
+                    );
+                }
+                if hit.cut {
+                    material.base_color = scene.camera.cut_color;
+                    material.emission_strength = 0.0;
+                    material.bump_strength = 0.0;
+                    material.clearcoat = 0.0;
+                    material.sheen = 0.0;
+                }
+
+                let mut surface_color = material.base_color;
+                let mut transmitted = None;
+                if material.transmission > 0.0 {
+                    let boundary = media.boundary(scene.materials, &hit, &material, ray.wavelength);
+                    if boundary.false_hit {
+                        ray.origin = hit.position - hit.normal * scene.camera.min_distance;
+                        continue;
+                    }
+                    if random_value(rng) < material.transmission {
+                        transmitted = Some(boundary);
+                    }
+                }
+                if let Some(boundary) = transmitted {
+                    ray = dielectric_scatter(
+                        scene.camera,
+                        ray,
+                        &hit,
+                        boundary.from_ior,
+                        boundary.to_ior,
+                        rng,
+                    );
+                    if ray.direction.dot(hit.normal) < 0.0 {
+                        media.cross(&hit, &boundary);
+                    }
+                } else {
+                    ray.origin = hit.position + hit.normal * scene.camera.min_distance;
+                    let coated = material.clearcoat > 0.0
+                        && random_value(rng)
+                            < material.clearcoat * clearcoat_reflectance(ray.direction, hit.normal);
+                    let incoming = ray.direction;
+                    if coated {
+                        ray.direction = glossy_direction(
+                            ray.direction,
+                            hit.normal,
+                            material.clearcoat_roughness,
+                            rng,
+                        );
+                        surface_color = cgmath::vec3(1.0, 1.0, 1.0);
+                    } else if material.bump_strength > 0.0 {
+                        let normal = bump_normal(
+                            hit.normal,
+                            hit.position,
+                            material.bump_strength,
+                            material.bump_scale,
+                        );
+                        ray.direction = diffuse_direction(normal, rng);
+                        let below = ray.direction.dot(hit.normal);
+                        if below < 0.0 {
+                            ray.direction -= 2.0 * below * hit.normal;
+                        }
+                    } else {
+                        ray.direction = diffuse_direction(hit.normal, rng);
+                    }
+                    if !coated && material.sheen > 0.0 {
+                        surface_color += sheen_color(&material, incoming, ray.direction);
+                    }
+                }
+
+                let emission = material.emission_strength
+                    * emission_pattern(&material, hit.pattern_position)
+                    * light_group_weight(scene.camera, material.light_group);
+                incoming_light += (material.emissive_color * emission).mul_element_wise(ray_color);
+                ray_color.mul_assign_element_wise(surface_color);
+                bounces += 1;
+            }
+            None => {
+                incoming_light +=
+                    sky_color(scene.environment, ray.direction).mul_element_wise(ray_color);
+                break;
+            }
+        }
+    }
+
+    incoming_light
+}
+
+fn inverse_stereographic(camera: &GpuCamera, point: Vector4<f32>) -> Vector4<f32> {
+    let radius_squared = camera.stereographic_pole.magnitude2();
+    let distance_squared = point.magnitude2();
+    (2.0 * radius_squared * point + (distance_squared - radius_squared) * camera.stereographic_pole)
+        / (distance_squared + radius_squared)
+}
+
+/// The `STEREOGRAPHIC` ray generation in the shader
+pub fn trace_stereographic(
+    scene: &Scene,
+    options: RenderOptions,
+    ray: Ray,
+    rng: &mut u32,
+) -> Vector3<f32> {
+    const STEPS: u32 = 64;
+    let camera = scene.camera;
+    let radius = camera.stereographic_pole.magnitude();
+    let mut previous = inverse_stereographic(camera, ray.origin);
+    for i in 1..=STEPS {
+        let point = if i < STEPS {
+            let s = radius * (i as f32 / STEPS as f32 * std::f32::consts::FRAC_PI_2).tan();
+            inverse_stereographic(camera, ray.origin + ray.direction * s)
+        } else {
+            camera.stereographic_pole
+        };
+
+        let step = Ray {
+            origin: previous,
+            direction: (point - previous).normalize(),
+            time: ray.time,
+            wavelength: ray.wavelength,
+        };
+        if let Some(hit) = get_closest_hit(scene, step) {
+            if hit.distance <= point.distance(previous) {
+                return trace_render_mode(scene, options.render_mode, step, rng);
+            }
+        }
+        previous = point;
+    }
+    sky_color(scene.environment, ray.direction)
+}
+
+pub fn ray_trace_pixel(
+    scene: &Scene,
+    options: RenderOptions,
+    coords: (usize, usize),
+    size: (usize, usize),
+) -> Vector3<f32> {
+    let camera = scene.camera;
+    let mut rng = hash(((coords.0 + coords.1 * size.0) as u32) ^ hash(camera.seed_offset));
+
+    let mut eye_coords = coords;
+    let mut eye_size = size;
+    let mut origin = camera.position;
+    if options.stereo {
+        // each eye gets its own half of the image
+        eye_size.0 = size.0 / 2;
+        if coords.0 < eye_size.0 {
+            origin -= camera.right * camera.eye_offset;
+        } else {
+            eye_coords.0 -= eye_size.0;
+            origin += camera.right * camera.eye_offset;
+        }
+    }
+
+    let aspect = if camera.aspect_override > 0.0 {
+        camera.aspect_override
+    } else {
+        eye_size.0 as f32 / eye_size.1 as f32
+    };
+    let mut color = cgmath::vec3(0.0, 0.0, 0.0);
+    for _ in 0..camera.sample_count {
+        let jitter = cgmath::vec2(random_value(&mut rng), random_value(&mut rng));
+        let uv = cgmath::vec2(
+            (eye_coords.0 as f32 + jitter.x * 2.0 - 1.0) / eye_size.0 as f32,
+            (eye_coords.1 as f32 + jitter.y * 2.0 - 1.0) / eye_size.1 as f32,
+        );
+        let normalized_uv =
+            cgmath::vec2(uv.x * 2.0 - 1.0, (1.0 - uv.y) * 2.0 - 1.0) + camera.lens_shift;
+
+        let time = if options.motion_blur {
+            random_value(&mut rng) * camera.shutter_time
+        } else {
+            0.0
+        };
+        let wavelength = if options.spectral {
+            380.0 + 400.0 * random_value(&mut rng)
+        } else {
+            REFERENCE_WAVELENGTH
+        };
+        // outside the fisheye circle is left black
+        let Some(direction) = options
+            .projection
+            .ray_direction(camera, normalized_uv, aspect)
+        else {
+            continue;
+        };
+        let ray = Ray {
+            origin,
+            direction,
+            time,
+            wavelength,
+        };
+
+        let mut sample_color = if options.projection == Projection::Stereographic {
+            trace_stereographic(scene, options, ray, &mut rng)
+        } else {
+            trace_render_mode(scene, options.render_mode, ray, &mut rng)
+        };
+        if options.spectral {
+            sample_color.mul_assign_element_wise(wavelength_to_rgb(wavelength));
+        }
+        color += sample_color;
+    }
+    color /= camera.sample_count as f32;
+    color.map(|c| c.clamp(0.0, 1.0))
+}
+
+/// Renders the whole image as tightly packed RGBA8 rows like `Renderer::read_pixels`,
+/// splitting the rows across every available thread
+pub fn render(scene: &Scene, options: RenderOptions, width: usize, height: usize) -> Vec<u8> {
+    let mut pixels = vec![0; width * height * 4];
+    let thread_count = std::thread::available_parallelism().map_or(1, |count| count.get());
+    let rows_per_thread = height.div_ceil(thread_count).max(1);
+    std::thread::scope(|scope| {
+        for (chunk_index, chunk) in pixels.chunks_mut(rows_per_thread * width * 4).enumerate() {
+            scope.spawn(move || {
+                for (i, pixel) in chunk.chunks_exact_mut(4).enumerate() {
+                    let index = chunk_index * rows_per_thread * width + i;
+                    let color = options.color_management.encode(ray_trace_pixel(
+                        scene,
+                        options,
+                        (index % width, index / width),
+                        (width, height),
+                    ));
+                    // matches the unorm conversion done when storing to the texture
+                    pixel[0] = (color.x * 255.0).round() as u8;
+                    pixel[1] = (color.y * 255.0).round() as u8;
+                    pixel[2] = (color.z * 255.0).round() as u8;
+                    pixel[3] = 255;
+                }
+            });
+        }
+    });
+    pixels
+}
+
+/// Runs [`render`] on the CPU every frame and uploads the result, selectable in the app to
+/// compare against the GPU backends
+pub struct CpuRayTracer {
+    texture_width: usize,
+    texture_height: usize,
+    texture: wgpu::Texture,
+    texture_view: wgpu::TextureView,
+    camera: GpuCamera,
+    hyper_spheres: Vec<GpuHyperSphere>,
+    hyper_planes: Vec<GpuHyperPlane>,
+    materials: Vec<GpuMaterial>,
+    node_programs: Vec<GpuNodeInstruction>,
+    environment: GpuEnvironment,
+}
+
+impl CpuRayTracer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let texture = Self::create_texture(device, 1, 1);
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            texture_width: 1,
+            texture_height: 1,
+            texture,
+            texture_view,
+            camera: Camera::default().to_gpu(0),
+            hyper_spheres: vec![],
+            hyper_planes: vec![],
+            materials: vec![],
+            node_programs: vec![],
+            environment: Environment::default().to_gpu(),
+        }
+    }
+
+    fn create_texture(device: &wgpu::Device, width: usize, height: usize) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("CPU Texture"),
+            size: wgpu::Extent3d {
+                width: width as _,
+                height: height as _,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    }
+}
+
+impl RayTracer for CpuRayTracer {
+    fn size(&self) -> (usize, usize) {
+        (self.texture_width, self.texture_height)
+    }
+
+    fn texture_view(&self) -> &wgpu::TextureView {
+        &self.texture_view
+    }
+
+    fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        width: usize,
+        height: usize,
+    ) -> bool {
+        if (width, height) == (self.texture_width, self.texture_height) {
+            return false;
+        }
+        (self.texture_width, self.texture_height) = (width, height);
+        self.texture = Self::create_texture(device, width, height);
+        self.texture_view = self
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        true
+    }
+
+    fn update_camera(&mut self, _queue: &wgpu::Queue, camera: &GpuCamera) -> Result<(), GpuError> {
+        self.camera = *camera;
+        Ok(())
+    }
+
+    fn update_scene(
+        &mut self,
+        _device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        scene: &crate::Scene,
+    ) -> Result<(), GpuError> {
+        if scene.changes.hyper_spheres {
+            self.hyper_spheres = scene.sanitized_hyper_spheres();
+        }
+        if scene.changes.hyper_planes {
+            self.hyper_planes = scene.sanitized_hyper_planes();
+        }
+        if scene.changes.materials {
+            (self.materials, self.node_programs) = scene.compiled_materials();
+        }
+        if scene.changes.environment {
+            self.environment = scene.environment.to_gpu();
+        }
+        Ok(())
+    }
+
+    fn render(
+        &mut self,
+        _device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        _encoder: &mut wgpu::CommandEncoder,
+        options: RenderOptions,
+    ) {
+        let scene = Scene {
+            camera: &self.camera,
+            hyper_spheres: &self.hyper_spheres,
+            hyper_planes: &self.hyper_planes,
+            materials: &self.materials,
+            node_programs: &self.node_programs,
+            environment: &self.environment,
+        };
+        let pixels = render(&scene, options, self.texture_width, self.texture_height);
+        queue.write_texture(
+            self.texture.as_image_copy(),
+            &pixels,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: std::num::NonZeroU32::new(self.texture_width as u32 * 4),
+                rows_per_image: None,
+            },
+            wgpu::Extent3d {
+                width: self.texture_width as _,
+                height: self.texture_height as _,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use crate::GpuError;
+
+/// Copies a ray tracer's `Rgba8Unorm` output into an `Rgba8UnormSrgb` texture with the same bytes.
+///
+/// The output is already encoded by [`crate::ColorManagement`], but anything sampling an
+/// `Rgba8Unorm` texture (like egui) treats it as linear and would encode it a second time.
+/// Sampling the copy decodes it instead. A plain texture copy or an sRGB view of the output
+/// would need matching formats or `VIEW_FORMATS`, which the GL backend doesn't have
+pub struct DisplayTexture {
+    texture_width: usize,
+    texture_height: usize,
+    texture: wgpu::Texture,
+    texture_view: wgpu::TextureView,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl DisplayTexture {
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+    /// `output` is the ray tracer's output texture, of `width` by `height` pixels
+    pub fn new(
+        device: &wgpu::Device,
+        output: &wgpu::TextureView,
+        width: usize,
+        height: usize,
+    ) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Display Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Display Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Display Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("./shaders/display.wgsl").into()),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Display Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vertex",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "display",
+                targets: &[Some(Self::FORMAT.into())],
+            }),
+            multiview: None,
+        });
+
+        let texture = Self::create_texture(device, width, height);
+        Self {
+            texture_width: width,
+            texture_height: height,
+            texture_view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            texture,
+            bind_group: Self::create_bind_group(device, &bind_group_layout, output),
+            bind_group_layout,
+            pipeline,
+        }
+    }
+
+    fn create_texture(device: &wgpu::Device, width: usize, height: usize) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Display Texture"),
+            size: wgpu::Extent3d {
+                width: width as _,
+                height: height as _,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        output: &wgpu::TextureView,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Display Bind Group"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(output),
+            }],
+        })
+    }
+
+    /// Copies from `output` from now on, call it whenever the ray tracer's texture is recreated.
+    /// The display texture is only recreated if the size changed, returns whether it was
+    pub fn set_output(
+        &mut self,
+        device: &wgpu::Device,
+        output: &wgpu::TextureView,
+        width: usize,
+        height: usize,
+    ) -> bool {
+        self.bind_group = Self::create_bind_group(device, &self.bind_group_layout, output);
+        if (width, height) == (self.texture_width, self.texture_height) {
+            return false;
+        }
+        (self.texture_width, self.texture_height) = (width, height);
+        self.texture = Self::create_texture(device, width, height);
+        self.texture_view = self
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        true
+    }
+
+    /// The width and height of the display texture
+    pub fn size(&self) -> (usize, usize) {
+        (self.texture_width, self.texture_height)
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    /// The sRGB copy, it can be sampled once the commands from [`DisplayTexture::render`] are submitted
+    pub fn texture_view(&self) -> &wgpu::TextureView {
+        &self.texture_view
+    }
+
+    /// Copies the display texture back to the CPU as tightly packed RGBA8 rows, the same bytes
+    /// as the output, blocking until it's done
+    pub fn read_pixels(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Vec<u8>, GpuError> {
+        let unpadded_bytes_per_row = self.texture_width * 4;
+        let padded_bytes_per_row =
+            unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize);
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Display Readback Buffer"),
+            size: (padded_bytes_per_row * self.texture_height) as _,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Display Readback Command Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(padded_bytes_per_row as _),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: self.texture_width as _,
+                height: self.texture_height as _,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit([encoder.finish()]);
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            _ = sender.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError))?;
+
+        let data = slice.get_mapped_range();
+        Ok(data
+            .chunks(padded_bytes_per_row)
+            .flat_map(|row| &row[..unpadded_bytes_per_row])
+            .copied()
+            .collect())
+    }
+
+    /// Records the copy of the output into `encoder`
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Display Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.texture_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,186 @@
+use crate::GpuEnvironment;
+use cgmath::prelude::*;
+use std::f32::consts::{FRAC_PI_2, PI};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SkyKind {
+    /// A fixed blend from grey below the horizon to blue above
+    Gradient,
+    /// The Preetham analytic daylight model, lit by a sun
+    Physical,
+}
+
+impl SkyKind {
+    pub const ALL: [SkyKind; 2] = [SkyKind::Gradient, SkyKind::Physical];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SkyKind::Gradient => "Gradient",
+            SkyKind::Physical => "Physical",
+        }
+    }
+}
+
+/// What rays that escape the scene see, converted with [`Environment::to_gpu`]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Environment {
+    pub sky: SkyKind,
+    /// Angle of the sun above the horizon
+    pub sun_elevation: f32,
+    /// Angle of the sun around y, starting at +z and going towards +x
+    pub sun_azimuth: f32,
+    /// Angle of the sun out of the xz plane towards +w
+    pub sun_w_angle: f32,
+    /// How hazy the air is, from about 2 for a clear sky to 10 for a murky one
+    pub turbidity: f32,
+    /// Scales the sky luminance, which is in kilocandela per square meter
+    pub exposure: f32,
+    /// Angular radius of the sun disk
+    pub sun_radius: f32,
+    /// How much brighter the sun disk is than the sky next to it
+    pub sun_strength: f32,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self {
+            sky: SkyKind::Gradient,
+            sun_elevation: 45.0f32.to_radians(),
+            sun_azimuth: 30.0f32.to_radians(),
+            sun_w_angle: 0.0,
+            turbidity: 3.0,
+            exposure: 0.05,
+            sun_radius: 2.5f32.to_radians(),
+            sun_strength: 20.0,
+        }
+    }
+}
+
+impl Environment {
+    pub fn sun_direction(&self) -> cgmath::Vector4<f32> {
+        let (elevation_sin, elevation_cos) = self.sun_elevation.sin_cos();
+        let (azimuth_sin, azimuth_cos) = self.sun_azimuth.sin_cos();
+        let (w_sin, w_cos) = self.sun_w_angle.sin_cos();
+        cgmath::vec4(
+            azimuth_sin * w_cos * elevation_cos,
+            elevation_sin,
+            azimuth_cos * w_cos * elevation_cos,
+            w_sin * elevation_cos,
+        )
+    }
+
+    /// Works out the sky model coefficients so the shader only has to evaluate them
+    pub fn to_gpu(&self) -> GpuEnvironment {
+        let t = self.turbidity;
+        // the model isn't defined for a sun below the horizon
+        let theta_sun = FRAC_PI_2 - self.sun_elevation.clamp(0.0, FRAC_PI_2);
+
+        // Perez coefficients for luminance and the two chromaticities
+        let perez_a = cgmath::vec3(
+            0.1787 * t - 1.4630,
+            -0.0193 * t - 0.2592,
+            -0.0167 * t - 0.2608,
+        );
+        let perez_b = cgmath::vec3(
+            -0.3554 * t + 0.4275,
+            -0.0665 * t + 0.0008,
+            -0.0950 * t + 0.0092,
+        );
+        let perez_c = cgmath::vec3(
+            -0.0227 * t + 5.3251,
+            -0.0004 * t + 0.2125,
+            -0.0079 * t + 0.2102,
+        );
+        let perez_d = cgmath::vec3(
+            0.1206 * t - 2.5771,
+            -0.0641 * t - 0.8989,
+            -0.0441 * t - 1.6537,
+        );
+        let perez_e = cgmath::vec3(
+            -0.0670 * t + 0.3703,
+            -0.0033 * t + 0.0452,
+            -0.0109 * t + 0.0529,
+        );
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_sun);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let theta = cgmath::vec3(theta_sun.powi(3), theta_sun.powi(2), theta_sun);
+        let chromaticity = |t2: [f32; 3], t1: [f32; 4], t0: [f32; 4]| {
+            t * t * (t2[0] * theta.x + t2[1] * theta.y + t2[2] * theta.z)
+                + t * (t1[0] * theta.x + t1[1] * theta.y + t1[2] * theta.z + t1[3])
+                + (t0[0] * theta.x + t0[1] * theta.y + t0[2] * theta.z + t0[3])
+        };
+        let zenith_x = chromaticity(
+            [0.00166, -0.00375, 0.00209],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        );
+        let zenith_y = chromaticity(
+            [0.00275, -0.00610, 0.00317],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        );
+
+        let mut environment = GpuEnvironment {
+            sun_direction: self.sun_direction(),
+            sky: self.sky as _,
+            sun_cos_radius: self.sun_radius.cos(),
+            exposure: self.exposure,
+            perez_a,
+            perez_b,
+            perez_c,
+            perez_d,
+            perez_e,
+            zenith: cgmath::vec3(0.0, 0.0, 0.0),
+            sun_radiance: cgmath::vec3(0.0, 0.0, 0.0),
+        };
+        // dividing by the distribution at the zenith here saves doing it for every ray
+        let zenith_distribution = perez(&environment, 1.0, theta_sun.cos());
+        environment.zenith = cgmath::vec3(zenith_luminance, zenith_x, zenith_y)
+            .div_element_wise(zenith_distribution);
+
+        // the sun takes on the color of the sky around it, so it reddens near the horizon
+        let around_sun = physical_sky_radiance(&environment, environment.sun_direction);
+        let brightest = around_sun.x.max(around_sun.y).max(around_sun.z);
+        if brightest > 0.0 {
+            environment.sun_radiance = around_sun * (self.sun_strength / brightest);
+        }
+        environment
+    }
+}
+
+fn perez(environment: &GpuEnvironment, cos_theta: f32, cos_gamma: f32) -> cgmath::Vector3<f32> {
+    let gamma = cos_gamma.clamp(-1.0, 1.0).acos();
+    let exp = |v: cgmath::Vector3<f32>| v.map(f32::exp);
+    (cgmath::vec3(1.0, 1.0, 1.0)
+        + environment
+            .perez_a
+            .mul_element_wise(exp(environment.perez_b / cos_theta)))
+    .mul_element_wise(
+        cgmath::vec3(1.0, 1.0, 1.0)
+            + environment
+                .perez_c
+                .mul_element_wise(exp(environment.perez_d * gamma))
+            + environment.perez_e * (cos_gamma * cos_gamma),
+    )
+}
+
+/// The sky without the sun disk in linear sRGB, mirrors `physical_sky` in the shader
+pub fn physical_sky_radiance(
+    environment: &GpuEnvironment,
+    direction: cgmath::Vector4<f32>,
+) -> cgmath::Vector3<f32> {
+    let cos_theta = direction.y.max(0.01);
+    let cos_gamma = direction.dot(environment.sun_direction);
+    let yxy = environment
+        .zenith
+        .mul_element_wise(perez(environment, cos_theta, cos_gamma));
+    let (luminance, x, y) = (yxy.x, yxy.y, yxy.z);
+    let xyz = cgmath::vec3(x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+    let rgb = cgmath::vec3(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    );
+    (rgb * environment.exposure).map(|c| c.max(0.0))
+}
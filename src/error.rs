@@ -0,0 +1,69 @@
+/// Something that went wrong talking to the GPU, these are logged instead of crashing
+/// so the scene isn't lost
+#[derive(Debug)]
+pub enum GpuError {
+    /// Data didn't fit in the buffer it was written into
+    Encode(encase::internal::Error),
+    /// Reading a buffer back to the CPU failed
+    Map(wgpu::BufferAsyncError),
+    /// An error wgpu reported for a call, like a shader that doesn't validate
+    Wgpu(wgpu::Error),
+}
+
+impl std::fmt::Display for GpuError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GpuError::Encode(error) => write!(f, "couldn't encode GPU data: {error}"),
+            GpuError::Map(error) => write!(f, "couldn't read back a GPU buffer: {error}"),
+            GpuError::Wgpu(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for GpuError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GpuError::Encode(error) => Some(error),
+            GpuError::Map(error) => Some(error),
+            GpuError::Wgpu(error) => Some(error),
+        }
+    }
+}
+
+impl From<encase::internal::Error> for GpuError {
+    fn from(error: encase::internal::Error) -> Self {
+        GpuError::Encode(error)
+    }
+}
+
+impl From<wgpu::BufferAsyncError> for GpuError {
+    fn from(error: wgpu::BufferAsyncError) -> Self {
+        GpuError::Map(error)
+    }
+}
+
+impl From<wgpu::Error> for GpuError {
+    fn from(error: wgpu::Error) -> Self {
+        GpuError::Wgpu(error)
+    }
+}
+
+impl GpuError {
+    /// Whether the device that reported this is gone, after a driver reset or the GPU being
+    /// removed. Nothing made with it can be used again. wgpu only says so in the message of
+    /// wgpu-core's `DeviceError::Lost` somewhere in the source chain, which browsers don't use
+    pub fn is_device_lost(&self) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let lost = wgpu_core::device::DeviceError::Lost.to_string();
+            let mut source: Option<&dyn std::error::Error> = Some(self);
+            while let Some(error) = source {
+                if error.to_string().eq_ignore_ascii_case(&lost) {
+                    return true;
+                }
+                source = error.source();
+            }
+        }
+        false
+    }
+}
@@ -0,0 +1,26 @@
+use crate::EXAMPLE_SCRIPT;
+
+/// A demo scene, made by running `source` as a script
+pub struct Example {
+    pub name: &'static str,
+    pub source: &'static str,
+}
+
+pub const EXAMPLES: &[Example] = &[
+    Example {
+        name: "Tesseract",
+        source: include_str!("examples/tesseract.script"),
+    },
+    Example {
+        name: "Sphere Fractal",
+        source: include_str!("examples/sphere_fractal.script"),
+    },
+    Example {
+        name: "Lighting",
+        source: include_str!("examples/lighting.script"),
+    },
+    Example {
+        name: "Bobbing Grid",
+        source: EXAMPLE_SCRIPT,
+    },
+];
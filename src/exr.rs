@@ -0,0 +1,89 @@
+//! Just enough OpenEXR to write uncompressed 32-bit float scanline images
+
+/// The layers of a multi-layer file are the part of a channel's name before the last `.`,
+/// like `albedo.R`. Every channel has one value per pixel, in rows from the top
+pub(crate) struct ExrChannel {
+    pub name: String,
+    pub values: Vec<f32>,
+}
+
+const MAGIC: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+const VERSION: u32 = 2;
+const PIXEL_TYPE_FLOAT: i32 = 2;
+
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    for string in [name, kind] {
+        header.extend_from_slice(string.as_bytes());
+        header.push(0);
+    }
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+/// A single part scanline file with one scanline per block, the channels are sorted by name
+/// because the format requires it
+pub(crate) fn encode_exr(width: usize, height: usize, channels: &[ExrChannel]) -> Vec<u8> {
+    let mut channels = channels.iter().collect::<Vec<_>>();
+    channels.sort_by(|a, b| a.name.cmp(&b.name));
+    assert!(channels
+        .iter()
+        .all(|channel| channel.values.len() == width * height));
+
+    let mut file = MAGIC.to_vec();
+    file.extend_from_slice(&VERSION.to_le_bytes());
+
+    let mut channel_list = vec![];
+    for channel in &channels {
+        channel_list.extend_from_slice(channel.name.as_bytes());
+        channel_list.push(0);
+        channel_list.extend_from_slice(&PIXEL_TYPE_FLOAT.to_le_bytes());
+        // not perceptually linear, then 3 reserved bytes
+        channel_list.extend_from_slice(&[0; 4]);
+        // no subsampling in x or y
+        channel_list.extend_from_slice(&1i32.to_le_bytes());
+        channel_list.extend_from_slice(&1i32.to_le_bytes());
+    }
+    channel_list.push(0);
+
+    let window = [0, 0, width as i32 - 1, height as i32 - 1]
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect::<Vec<_>>();
+    attribute(&mut file, "channels", "chlist", &channel_list);
+    attribute(&mut file, "compression", "compression", &[0]);
+    attribute(&mut file, "dataWindow", "box2i", &window);
+    attribute(&mut file, "displayWindow", "box2i", &window);
+    // increasing y
+    attribute(&mut file, "lineOrder", "lineOrder", &[0]);
+    attribute(
+        &mut file,
+        "pixelAspectRatio",
+        "float",
+        &1.0f32.to_le_bytes(),
+    );
+    attribute(&mut file, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(
+        &mut file,
+        "screenWindowWidth",
+        "float",
+        &1.0f32.to_le_bytes(),
+    );
+    file.push(0);
+
+    let block_size = channels.len() * width * std::mem::size_of::<f32>();
+    let first_block = file.len() + height * std::mem::size_of::<u64>();
+    for y in 0..height {
+        let offset = first_block + y * (2 * std::mem::size_of::<i32>() + block_size);
+        file.extend_from_slice(&(offset as u64).to_le_bytes());
+    }
+    for y in 0..height {
+        file.extend_from_slice(&(y as i32).to_le_bytes());
+        file.extend_from_slice(&(block_size as i32).to_le_bytes());
+        for channel in &channels {
+            for value in &channel.values[y * width..][..width] {
+                file.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+    file
+}
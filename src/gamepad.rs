@@ -0,0 +1,443 @@
+/// An analog input on a controller, sticks go from -1 to 1 with up and right positive and
+/// triggers go from 0 to 1
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+impl GamepadAxis {
+    pub const ALL: [GamepadAxis; 6] = [
+        GamepadAxis::LeftStickX,
+        GamepadAxis::LeftStickY,
+        GamepadAxis::RightStickX,
+        GamepadAxis::RightStickY,
+        GamepadAxis::LeftTrigger,
+        GamepadAxis::RightTrigger,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GamepadAxis::LeftStickX => "Left Stick X",
+            GamepadAxis::LeftStickY => "Left Stick Y",
+            GamepadAxis::RightStickX => "Right Stick X",
+            GamepadAxis::RightStickY => "Right Stick Y",
+            GamepadAxis::LeftTrigger => "Left Trigger",
+            GamepadAxis::RightTrigger => "Right Trigger",
+        }
+    }
+
+    fn is_stick(self) -> bool {
+        !matches!(self, GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftBumper,
+    RightBumper,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl GamepadButton {
+    pub const ALL: [GamepadButton; 10] = [
+        GamepadButton::South,
+        GamepadButton::East,
+        GamepadButton::West,
+        GamepadButton::North,
+        GamepadButton::LeftBumper,
+        GamepadButton::RightBumper,
+        GamepadButton::DPadUp,
+        GamepadButton::DPadDown,
+        GamepadButton::DPadLeft,
+        GamepadButton::DPadRight,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GamepadButton::South => "South (A)",
+            GamepadButton::East => "East (B)",
+            GamepadButton::West => "West (X)",
+            GamepadButton::North => "North (Y)",
+            GamepadButton::LeftBumper => "Left Bumper",
+            GamepadButton::RightBumper => "Right Bumper",
+            GamepadButton::DPadUp => "D-Pad Up",
+            GamepadButton::DPadDown => "D-Pad Down",
+            GamepadButton::DPadLeft => "D-Pad Left",
+            GamepadButton::DPadRight => "D-Pad Right",
+        }
+    }
+}
+
+/// Anything on a controller that can be bound, buttons read as 0 or 1
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GamepadInput {
+    Axis(GamepadAxis),
+    Button(GamepadButton),
+}
+
+impl GamepadInput {
+    pub fn name(self) -> &'static str {
+        match self {
+            GamepadInput::Axis(axis) => axis.name(),
+            GamepadInput::Button(button) => button.name(),
+        }
+    }
+
+    /// Every input, for picking one in the bindings window
+    pub fn all() -> impl Iterator<Item = GamepadInput> {
+        GamepadAxis::ALL
+            .into_iter()
+            .map(GamepadInput::Axis)
+            .chain(GamepadButton::ALL.into_iter().map(GamepadInput::Button))
+    }
+}
+
+/// What a binding does to the camera, movement is along the camera's own axes
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GamepadControl {
+    MoveRight,
+    MoveUp,
+    MoveForward,
+    MoveAna,
+    Pitch,
+    Yaw,
+    WeirdPitch,
+    WeirdYaw,
+}
+
+impl GamepadControl {
+    pub const ALL: [GamepadControl; 8] = [
+        GamepadControl::MoveRight,
+        GamepadControl::MoveUp,
+        GamepadControl::MoveForward,
+        GamepadControl::MoveAna,
+        GamepadControl::Pitch,
+        GamepadControl::Yaw,
+        GamepadControl::WeirdPitch,
+        GamepadControl::WeirdYaw,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GamepadControl::MoveRight => "Move Right",
+            GamepadControl::MoveUp => "Move Up",
+            GamepadControl::MoveForward => "Move Forward",
+            GamepadControl::MoveAna => "Move Ana (+W)",
+            GamepadControl::Pitch => "Pitch",
+            GamepadControl::Yaw => "Yaw",
+            GamepadControl::WeirdPitch => "Weird Pitch",
+            GamepadControl::WeirdYaw => "Weird Yaw",
+        }
+    }
+}
+
+/// Drives `control` with `input`, a negative scale pushes it the other way
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GamepadBinding {
+    pub input: GamepadInput,
+    pub control: GamepadControl,
+    pub scale: f32,
+}
+
+/// How much each control is pushed this frame, from -1 to 1 for a single full input
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GamepadControls {
+    /// Right, up, forward and ana
+    pub movement: cgmath::Vector4<f32>,
+    pub pitch: f32,
+    pub yaw: f32,
+    pub weird_pitch: f32,
+    pub weird_yaw: f32,
+}
+
+impl Default for GamepadControls {
+    fn default() -> Self {
+        Self {
+            movement: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            pitch: 0.0,
+            yaw: 0.0,
+            weird_pitch: 0.0,
+            weird_yaw: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct GamepadBindings {
+    pub bindings: Vec<GamepadBinding>,
+    /// Stick values closer to the center than this are ignored, the rest is rescaled to start at 0
+    pub dead_zone: f32,
+}
+
+impl Default for GamepadBindings {
+    fn default() -> Self {
+        use GamepadAxis::*;
+        use GamepadButton::*;
+        use GamepadControl::*;
+        let axis = |axis, control, scale| GamepadBinding {
+            input: GamepadInput::Axis(axis),
+            control,
+            scale,
+        };
+        let button = |button, control, scale| GamepadBinding {
+            input: GamepadInput::Button(button),
+            control,
+            scale,
+        };
+        Self {
+            bindings: vec![
+                axis(LeftStickX, MoveRight, 1.0),
+                axis(LeftStickY, MoveForward, 1.0),
+                axis(RightStickX, Yaw, 1.0),
+                axis(RightStickY, Pitch, 1.0),
+                axis(RightTrigger, MoveAna, 1.0),
+                axis(LeftTrigger, MoveAna, -1.0),
+                button(RightBumper, WeirdYaw, 1.0),
+                button(LeftBumper, WeirdYaw, -1.0),
+                button(DPadUp, WeirdPitch, 1.0),
+                button(DPadDown, WeirdPitch, -1.0),
+                button(North, MoveUp, 1.0),
+                button(South, MoveUp, -1.0),
+            ],
+            dead_zone: 0.15,
+        }
+    }
+}
+
+impl GamepadBindings {
+    /// Combines every binding for the current state of the controller
+    pub fn controls(&self, state: &GamepadState) -> GamepadControls {
+        let mut controls = GamepadControls::default();
+        for binding in &self.bindings {
+            let value = state.value(binding.input, self.dead_zone) * binding.scale;
+            match binding.control {
+                GamepadControl::MoveRight => controls.movement.x += value,
+                GamepadControl::MoveUp => controls.movement.y += value,
+                GamepadControl::MoveForward => controls.movement.z += value,
+                GamepadControl::MoveAna => controls.movement.w += value,
+                GamepadControl::Pitch => controls.pitch += value,
+                GamepadControl::Yaw => controls.yaw += value,
+                GamepadControl::WeirdPitch => controls.weird_pitch += value,
+                GamepadControl::WeirdYaw => controls.weird_yaw += value,
+            }
+        }
+        controls
+    }
+}
+
+/// The latest values of a controller's inputs
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct GamepadState {
+    pub axes: [f32; GamepadAxis::ALL.len()],
+    pub buttons: [bool; GamepadButton::ALL.len()],
+}
+
+impl GamepadState {
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes[axis as usize]
+    }
+
+    pub fn set_axis(&mut self, axis: GamepadAxis, value: f32) {
+        self.axes[axis as usize] = value;
+    }
+
+    pub fn button(&self, button: GamepadButton) -> bool {
+        self.buttons[button as usize]
+    }
+
+    pub fn set_button(&mut self, button: GamepadButton, pressed: bool) {
+        self.buttons[button as usize] = pressed;
+    }
+
+    /// The value of `input`, with stick values inside `dead_zone` treated as centered
+    pub fn value(&self, input: GamepadInput, dead_zone: f32) -> f32 {
+        match input {
+            GamepadInput::Axis(axis) => {
+                let value = self.axis(axis);
+                if !axis.is_stick() || dead_zone <= 0.0 {
+                    value
+                } else if value.abs() <= dead_zone {
+                    0.0
+                } else {
+                    value.signum() * (value.abs() - dead_zone) / (1.0 - dead_zone).max(1e-6)
+                }
+            }
+            GamepadInput::Button(button) => self.button(button) as u32 as f32,
+        }
+    }
+}
+
+/// Reads the first Linux joystick device, `/dev/input/js*`, without blocking. The axis and
+/// button numbers are the ones the kernel's xpad driver uses for Xbox style controllers
+#[cfg(target_os = "linux")]
+pub struct Gamepad {
+    device: Option<std::fs::File>,
+    state: GamepadState,
+    /// Frames until looking for a controller again, so a missing one isn't searched for constantly
+    retry_in: u32,
+}
+
+#[cfg(target_os = "linux")]
+impl Default for Gamepad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Gamepad {
+    /// Whether controllers can be read on this platform
+    pub const SUPPORTED: bool = true;
+
+    pub fn new() -> Self {
+        Self {
+            device: None,
+            state: GamepadState::default(),
+            retry_in: 0,
+        }
+    }
+
+    pub fn connected(&self) -> bool {
+        self.device.is_some()
+    }
+
+    pub fn state(&self) -> &GamepadState {
+        &self.state
+    }
+
+    fn open() -> Option<std::fs::File> {
+        use std::os::unix::fs::OpenOptionsExt;
+        (0..4).find_map(|index| {
+            std::fs::OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_NONBLOCK)
+                .open(format!("/dev/input/js{index}"))
+                .ok()
+        })
+    }
+
+    /// Reads every event that arrived since the last poll
+    pub fn poll(&mut self) {
+        use std::io::Read;
+
+        if self.device.is_none() {
+            if self.retry_in > 0 {
+                self.retry_in -= 1;
+                return;
+            }
+            self.device = Self::open();
+            self.state = GamepadState::default();
+            if self.device.is_none() {
+                self.retry_in = 120;
+                return;
+            }
+            log::info!("Gamepad connected");
+        }
+        let Some(mut device) = self.device.take() else {
+            return;
+        };
+        // struct js_event { u32 time; i16 value; u8 type; u8 number; }
+        let mut event = [0u8; 8];
+        loop {
+            match device.read_exact(&mut event) {
+                Ok(()) => {
+                    let value = i16::from_ne_bytes([event[4], event[5]]);
+                    let kind = event[6] & !0x80;
+                    let number = event[7];
+                    match kind {
+                        0x01 => self.button_event(number, value != 0),
+                        0x02 => self.axis_event(number, value as f32 / i16::MAX as f32),
+                        _ => {}
+                    }
+                }
+                Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => {
+                    self.device = Some(device);
+                    break;
+                }
+                Err(error) => {
+                    log::info!("Gamepad disconnected: {error}");
+                    self.state = GamepadState::default();
+                    break;
+                }
+            }
+        }
+    }
+
+    fn button_event(&mut self, number: u8, pressed: bool) {
+        let button = match number {
+            0 => GamepadButton::South,
+            1 => GamepadButton::East,
+            2 => GamepadButton::West,
+            3 => GamepadButton::North,
+            4 => GamepadButton::LeftBumper,
+            5 => GamepadButton::RightBumper,
+            _ => return,
+        };
+        self.state.set_button(button, pressed);
+    }
+
+    fn axis_event(&mut self, number: u8, value: f32) {
+        let value = value.clamp(-1.0, 1.0);
+        match number {
+            0 => self.state.set_axis(GamepadAxis::LeftStickX, value),
+            1 => self.state.set_axis(GamepadAxis::LeftStickY, -value),
+            // triggers rest at -1
+            2 => self
+                .state
+                .set_axis(GamepadAxis::LeftTrigger, (value + 1.0) * 0.5),
+            3 => self.state.set_axis(GamepadAxis::RightStickX, value),
+            4 => self.state.set_axis(GamepadAxis::RightStickY, -value),
+            5 => self
+                .state
+                .set_axis(GamepadAxis::RightTrigger, (value + 1.0) * 0.5),
+            // the d-pad is reported as a pair of axes
+            6 => {
+                self.state.set_button(GamepadButton::DPadLeft, value < -0.5);
+                self.state.set_button(GamepadButton::DPadRight, value > 0.5);
+            }
+            7 => {
+                self.state.set_button(GamepadButton::DPadUp, value < -0.5);
+                self.state.set_button(GamepadButton::DPadDown, value > 0.5);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Controllers are only read on Linux, so this never connects
+#[cfg(not(target_os = "linux"))]
+#[derive(Default)]
+pub struct Gamepad {
+    state: GamepadState,
+}
+
+#[cfg(not(target_os = "linux"))]
+impl Gamepad {
+    pub const SUPPORTED: bool = false;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn connected(&self) -> bool {
+        false
+    }
+
+    pub fn state(&self) -> &GamepadState {
+        &self.state
+    }
+
+    pub fn poll(&mut self) {}
+}
@@ -0,0 +1,467 @@
+use crate::{BiVector4, GpuHyperPlane, GpuHyperSphere, GpuMaterial, MaterialId, Scene};
+use cgmath::InnerSpace;
+use rand::{Rng, SeedableRng};
+
+fn diffuse(r: f32, g: f32, b: f32) -> GpuMaterial {
+    GpuMaterial {
+        base_color: cgmath::vec3(r, g, b),
+        emissive_color: cgmath::vec3(0.0, 0.0, 0.0),
+        ..Default::default()
+    }
+}
+
+fn hyper_sphere(center: cgmath::Vector4<f32>, radius: f32, material: MaterialId) -> GpuHyperSphere {
+    GpuHyperSphere {
+        center,
+        velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        radius,
+        material: material.0,
+        inner_radius: 0.0,
+    }
+}
+
+fn hyper_plane(
+    point: cgmath::Vector4<f32>,
+    normal: cgmath::Vector4<f32>,
+    material: MaterialId,
+) -> GpuHyperPlane {
+    GpuHyperPlane {
+        point,
+        normal,
+        velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        angular_velocity: BiVector4::ZERO,
+        material: material.0,
+        extents: cgmath::vec3(0.0, 0.0, 0.0),
+        tangent: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        bitangent: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+    }
+}
+
+fn add_ground(scene: &mut Scene) {
+    let ground = scene.add_material("Ground", diffuse(0.5, 0.5, 0.5));
+    scene.add_hyper_plane(
+        "Ground",
+        hyper_plane(
+            cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+            ground,
+        ),
+    );
+}
+
+/// A color that goes around the hue wheel as `t` goes from 0 to 1
+fn hue(t: f32) -> cgmath::Vector3<f32> {
+    let channel = |offset: f32| 0.5 + 0.5 * (std::f32::consts::TAU * (t + offset)).cos();
+    cgmath::vec3(channel(0.0), channel(2.0 / 3.0), channel(1.0 / 3.0))
+}
+
+/// A `size`^4 grid of spheres spread over x, y, z and w
+#[derive(Clone, Copy)]
+pub struct SphereLattice {
+    pub size: u32,
+    pub spacing: f32,
+    pub radius: f32,
+}
+
+impl Default for SphereLattice {
+    fn default() -> Self {
+        Self {
+            size: 3,
+            spacing: 1.5,
+            radius: 0.4,
+        }
+    }
+}
+
+impl SphereLattice {
+    pub fn generate(&self) -> Scene {
+        let mut scene = Scene::empty();
+        add_ground(&mut scene);
+        let offset = (self.size as f32 - 1.0) * self.spacing * 0.5;
+        // one material per w slice so moving through w is easy to see
+        let materials = (0..self.size)
+            .map(|w| {
+                let color = hue(w as f32 / self.size as f32);
+                scene.add_material(format!("Lattice W {w}"), diffuse(color.x, color.y, color.z))
+            })
+            .collect::<Vec<_>>();
+        for x in 0..self.size {
+            for y in 0..self.size {
+                for z in 0..self.size {
+                    for w in 0..self.size {
+                        let center = cgmath::vec4(
+                            x as f32 * self.spacing - offset,
+                            y as f32 * self.spacing + self.radius,
+                            z as f32 * self.spacing - offset,
+                            w as f32 * self.spacing - offset,
+                        );
+                        scene.add_hyper_sphere(
+                            format!("Lattice {x} {y} {z} {w}"),
+                            hyper_sphere(center, self.radius, materials[w as usize]),
+                        );
+                    }
+                }
+            }
+        }
+        scene
+    }
+}
+
+/// Randomly placed spheres resting on the ground, colored from a small palette
+#[derive(Clone, Copy)]
+pub struct RandomSphereField {
+    pub count: u32,
+    pub extent: f32,
+    pub min_radius: f32,
+    pub max_radius: f32,
+    pub emissive_chance: f32,
+    pub seed: u64,
+}
+
+impl Default for RandomSphereField {
+    fn default() -> Self {
+        Self {
+            count: 50,
+            extent: 10.0,
+            min_radius: 0.2,
+            max_radius: 0.8,
+            emissive_chance: 0.1,
+            seed: 0,
+        }
+    }
+}
+
+impl RandomSphereField {
+    const PALETTE: [(&'static str, [f32; 3]); 5] = [
+        ("Red", [0.8, 0.2, 0.2]),
+        ("Yellow", [0.9, 0.8, 0.2]),
+        ("Teal", [0.2, 0.7, 0.7]),
+        ("Purple", [0.5, 0.3, 0.8]),
+        ("White", [0.9, 0.9, 0.9]),
+    ];
+
+    pub fn generate(&self) -> Scene {
+        let mut scene = Scene::empty();
+        add_ground(&mut scene);
+        let materials =
+            Self::PALETTE.map(|(name, [r, g, b])| scene.add_material(name, diffuse(r, g, b)));
+        let light = scene.add_material(
+            "Light",
+            GpuMaterial {
+                base_color: cgmath::vec3(1.0, 1.0, 1.0),
+                emissive_color: cgmath::vec3(1.0, 0.9, 0.7),
+                emission_strength: 4.0,
+                ..Default::default()
+            },
+        );
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(self.seed);
+        let max_radius = self.max_radius.max(self.min_radius);
+        for i in 0..self.count {
+            let radius = rng.gen_range(self.min_radius..=max_radius);
+            let mut coordinate = || rng.gen_range(-self.extent..=self.extent);
+            let center = cgmath::vec4(coordinate(), radius, coordinate(), coordinate());
+            let material = if rng.gen::<f32>() < self.emissive_chance {
+                light
+            } else {
+                materials[rng.gen_range(0..materials.len())]
+            };
+            scene.add_hyper_sphere(
+                format!("Sphere {i}"),
+                hyper_sphere(center, radius, material),
+            );
+        }
+        scene
+    }
+}
+
+/// Spheres along a helix in the xz plane that also advances through w
+#[derive(Clone, Copy)]
+pub struct Spiral {
+    pub count: u32,
+    pub turns: f32,
+    pub radius: f32,
+    pub height: f32,
+    pub w_length: f32,
+    pub sphere_radius: f32,
+}
+
+impl Default for Spiral {
+    fn default() -> Self {
+        Self {
+            count: 40,
+            turns: 2.0,
+            radius: 3.0,
+            height: 4.0,
+            w_length: 4.0,
+            sphere_radius: 0.3,
+        }
+    }
+}
+
+impl Spiral {
+    pub fn generate(&self) -> Scene {
+        let mut scene = Scene::empty();
+        add_ground(&mut scene);
+        let material_count = 8;
+        let materials = (0..material_count)
+            .map(|i| {
+                let color = hue(i as f32 / material_count as f32);
+                scene.add_material(format!("Spiral {i}"), diffuse(color.x, color.y, color.z))
+            })
+            .collect::<Vec<_>>();
+        for i in 0..self.count {
+            let t = i as f32 / (self.count.max(2) - 1) as f32;
+            let angle = t * self.turns * std::f32::consts::TAU;
+            let center = cgmath::vec4(
+                angle.cos() * self.radius,
+                self.sphere_radius + t * self.height,
+                angle.sin() * self.radius,
+                (t - 0.5) * self.w_length,
+            );
+            let material = materials[(t * (material_count - 1) as f32).round() as usize];
+            scene.add_hyper_sphere(
+                format!("Spiral {i}"),
+                hyper_sphere(center, self.sphere_radius, material),
+            );
+        }
+        scene
+    }
+}
+
+/// A hypercube room with a red and a green wall, white everywhere else and a light on the ceiling,
+/// the camera starts inside it at its default position
+#[derive(Clone, Copy)]
+pub struct CornellBox {
+    pub size: f32,
+    pub light_strength: f32,
+}
+
+impl Default for CornellBox {
+    fn default() -> Self {
+        Self {
+            size: 4.0,
+            light_strength: 5.0,
+        }
+    }
+}
+
+impl CornellBox {
+    pub fn generate(&self) -> Scene {
+        let mut scene = Scene::empty();
+        let half = self.size * 0.5;
+        let white = scene.add_material("White", diffuse(0.8, 0.8, 0.8));
+        let red = scene.add_material("Red", diffuse(0.8, 0.1, 0.1));
+        let green = scene.add_material("Green", diffuse(0.1, 0.8, 0.1));
+        let blue = scene.add_material("Blue", diffuse(0.1, 0.2, 0.8));
+        let light = scene.add_material(
+            "Light",
+            GpuMaterial {
+                base_color: cgmath::vec3(1.0, 1.0, 1.0),
+                emissive_color: cgmath::vec3(1.0, 0.9, 0.8),
+                emission_strength: self.light_strength,
+                ..Default::default()
+            },
+        );
+
+        let walls = [
+            (
+                "Floor",
+                cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+                white,
+            ),
+            (
+                "Ceiling",
+                cgmath::vec4(0.0, self.size, 0.0, 0.0),
+                cgmath::vec4(0.0, -1.0, 0.0, 0.0),
+                white,
+            ),
+            (
+                "Left Wall",
+                cgmath::vec4(-half, 0.0, 0.0, 0.0),
+                cgmath::vec4(1.0, 0.0, 0.0, 0.0),
+                red,
+            ),
+            (
+                "Right Wall",
+                cgmath::vec4(half, 0.0, 0.0, 0.0),
+                cgmath::vec4(-1.0, 0.0, 0.0, 0.0),
+                green,
+            ),
+            (
+                "Back Wall",
+                cgmath::vec4(0.0, 0.0, half, 0.0),
+                cgmath::vec4(0.0, 0.0, -1.0, 0.0),
+                white,
+            ),
+            (
+                "Front Wall",
+                cgmath::vec4(0.0, 0.0, -self.size, 0.0),
+                cgmath::vec4(0.0, 0.0, 1.0, 0.0),
+                white,
+            ),
+            (
+                "Ana Wall",
+                cgmath::vec4(0.0, 0.0, 0.0, half),
+                cgmath::vec4(0.0, 0.0, 0.0, -1.0),
+                blue,
+            ),
+            (
+                "Kata Wall",
+                cgmath::vec4(0.0, 0.0, 0.0, -half),
+                cgmath::vec4(0.0, 0.0, 0.0, 1.0),
+                white,
+            ),
+        ];
+        for (name, point, normal, material) in walls {
+            scene.add_hyper_plane(name, hyper_plane(point, normal, material));
+        }
+
+        // a big sphere poking through the ceiling acts as an area light
+        scene.add_hyper_sphere(
+            "Light",
+            hyper_sphere(
+                cgmath::vec4(0.0, self.size + half * 0.9, 0.0, 0.0),
+                half,
+                light,
+            ),
+        );
+        scene.add_hyper_sphere(
+            "Sphere",
+            hyper_sphere(
+                cgmath::vec4(-half * 0.4, half * 0.35, half * 0.3, 0.0),
+                half * 0.35,
+                white,
+            ),
+        );
+        scene.add_hyper_sphere(
+            "Sphere W",
+            hyper_sphere(
+                cgmath::vec4(half * 0.4, half * 0.25, -half * 0.2, half * 0.3),
+                half * 0.25,
+                white,
+            ),
+        );
+        scene
+    }
+}
+
+/// A closed hypercube room built from finite hyper planes, so it can be seen from outside too,
+/// lit by a square panel under the ceiling with a glass and a diffuse sphere inside.
+/// The camera starts inside it at its default position
+#[derive(Clone, Copy)]
+pub struct Room {
+    pub size: f32,
+    pub height: f32,
+    pub light_size: f32,
+    pub light_strength: f32,
+}
+
+impl Default for Room {
+    fn default() -> Self {
+        Self {
+            size: 4.0,
+            height: 3.0,
+            light_size: 1.5,
+            light_strength: 8.0,
+        }
+    }
+}
+
+/// A hyper plane cut down to the box between `center - half_size` and `center + half_size`,
+/// `normal` has to be along one of the axes
+fn hyper_slab(
+    center: cgmath::Vector4<f32>,
+    normal: cgmath::Vector4<f32>,
+    half_size: cgmath::Vector4<f32>,
+    material: MaterialId,
+) -> GpuHyperPlane {
+    let mut slab = hyper_plane(center, normal, material);
+    for (k, tangent) in slab.tangents().into_iter().enumerate() {
+        slab.extents[k] = tangent.map(f32::abs).dot(half_size);
+    }
+    slab
+}
+
+impl Room {
+    pub fn generate(&self) -> Scene {
+        let mut scene = Scene::empty();
+        let half = self.size * 0.5;
+        let white = scene.add_material("White", diffuse(0.8, 0.8, 0.8));
+        let red = scene.add_material("Red", diffuse(0.8, 0.1, 0.1));
+        let green = scene.add_material("Green", diffuse(0.1, 0.8, 0.1));
+        let blue = scene.add_material("Blue", diffuse(0.1, 0.2, 0.8));
+        let glass = scene.add_material(
+            "Glass",
+            GpuMaterial {
+                base_color: cgmath::vec3(1.0, 1.0, 1.0),
+                emissive_color: cgmath::vec3(0.0, 0.0, 0.0),
+                transmission: 1.0,
+                ior: 1.5,
+                ..Default::default()
+            },
+        );
+        let light = scene.add_material(
+            "Light",
+            GpuMaterial {
+                base_color: cgmath::vec3(1.0, 1.0, 1.0),
+                emissive_color: cgmath::vec3(1.0, 0.9, 0.8),
+                emission_strength: self.light_strength,
+                ..Default::default()
+            },
+        );
+
+        // the room goes from -size to half along z so the default camera is inside it
+        let min = cgmath::vec4(-half, 0.0, -self.size, -half);
+        let max = cgmath::vec4(half, self.height, half, half);
+        let center = (min + max) * 0.5;
+        let half_size = (max - min) * 0.5;
+        let walls = [
+            ("Floor", 1, min.y, 1.0, white),
+            ("Ceiling", 1, max.y, -1.0, white),
+            ("Left Wall", 0, min.x, 1.0, red),
+            ("Right Wall", 0, max.x, -1.0, green),
+            ("Front Wall", 2, min.z, 1.0, white),
+            ("Back Wall", 2, max.z, -1.0, white),
+            ("Kata Wall", 3, min.w, 1.0, white),
+            ("Ana Wall", 3, max.w, -1.0, blue),
+        ];
+        for (name, axis, position, facing, material) in walls {
+            let mut point = center;
+            point[axis] = position;
+            let mut normal = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+            normal[axis] = facing;
+            scene.add_hyper_plane(name, hyper_slab(point, normal, half_size, material));
+        }
+
+        // just under the ceiling so it doesn't flicker against it
+        let light_half_size = self.light_size.min(self.size) * 0.5;
+        scene.add_hyper_plane(
+            "Light",
+            hyper_slab(
+                cgmath::vec4(0.0, max.y - 0.01, 0.0, 0.0),
+                cgmath::vec4(0.0, -1.0, 0.0, 0.0),
+                cgmath::vec4(light_half_size, 0.0, light_half_size, light_half_size),
+                light,
+            ),
+        );
+        scene.add_hyper_sphere(
+            "Glass Sphere",
+            hyper_sphere(
+                cgmath::vec4(-half * 0.4, half * 0.35, half * 0.2, 0.0),
+                half * 0.35,
+                glass,
+            ),
+        );
+        scene.add_hyper_sphere(
+            "Sphere W",
+            hyper_sphere(
+                cgmath::vec4(half * 0.4, half * 0.25, -half * 0.3, half * 0.3),
+                half * 0.25,
+                white,
+            ),
+        );
+        scene
+    }
+}
@@ -0,0 +1,639 @@
+use crate::BiVector4;
+use cgmath::prelude::*;
+use encase::{ArrayLength, ShaderType};
+
+#[derive(Clone, Copy, ShaderType)]
+pub struct GpuCamera {
+    pub position: cgmath::Vector4<f32>,
+    pub forward: cgmath::Vector4<f32>,
+    pub right: cgmath::Vector4<f32>,
+    pub up: cgmath::Vector4<f32>,
+    pub fov: f32,
+    /// Zero to use the aspect ratio of the image
+    pub aspect_override: f32,
+    pub lens_shift: cgmath::Vector2<f32>,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub bounce_count: u32,
+    pub sample_count: u32,
+    pub seed_offset: u32,
+    pub shutter_time: f32,
+    pub sample_index: u32,
+    /// How many frames are already summed in the accumulation buffer, set by the renderer
+    pub accumulated_frames: u32,
+    pub ao_distance: f32,
+    pub w_range: f32,
+    pub w_kata_color: cgmath::Vector3<f32>,
+    pub w_center_color: cgmath::Vector3<f32>,
+    pub w_ana_color: cgmath::Vector3<f32>,
+    pub cut_point: cgmath::Vector4<f32>,
+    /// Zero when there is no cross section
+    pub cut_normal: cgmath::Vector4<f32>,
+    pub cut_color: cgmath::Vector3<f32>,
+    /// Zero unless the camera uses [`crate::Projection::Stereographic`]
+    pub stereographic_pole: cgmath::Vector4<f32>,
+    /// How far the left half of the image is moved along `-right` and the right half along
+    /// `right`, negative swaps the eyes. Only used with [`crate::Camera::stereo`]
+    pub eye_offset: f32,
+    /// Only used with [`crate::Projection::Fisheye`]
+    pub fisheye_angle: f32,
+    /// What the emission of each light group is multiplied by, from
+    /// [`crate::Scene::light_group_weights`]
+    pub light_group_weights: cgmath::Vector4<f32>,
+}
+
+/// The precomputed sky model, see [`crate::Environment::to_gpu`]
+#[derive(Clone, Copy, ShaderType)]
+pub struct GpuEnvironment {
+    pub sun_direction: cgmath::Vector4<f32>,
+    /// A [`crate::SkyKind`] as a `u32`
+    pub sky: u32,
+    pub sun_cos_radius: f32,
+    pub exposure: f32,
+    pub perez_a: cgmath::Vector3<f32>,
+    pub perez_b: cgmath::Vector3<f32>,
+    pub perez_c: cgmath::Vector3<f32>,
+    pub perez_d: cgmath::Vector3<f32>,
+    pub perez_e: cgmath::Vector3<f32>,
+    /// Zenith luminance and chromaticity divided by the sky distribution at the zenith
+    pub zenith: cgmath::Vector3<f32>,
+    pub sun_radiance: cgmath::Vector3<f32>,
+}
+
+#[derive(Clone, Copy, ShaderType)]
+pub(crate) struct GpuTile {
+    pub offset: cgmath::Vector2<u32>,
+    pub size: cgmath::Vector2<u32>,
+}
+
+#[derive(Clone, Copy, ShaderType)]
+pub(crate) struct GpuPostEffect {
+    pub kind: u32,
+    pub strength: f32,
+    pub radius: f32,
+    pub seed: u32,
+    pub srgb: u32,
+}
+
+#[derive(Clone, Copy, ShaderType)]
+pub(crate) struct GpuDebugBounce {
+    pub position: cgmath::Vector4<f32>,
+    pub normal: cgmath::Vector4<f32>,
+    pub direction: cgmath::Vector4<f32>,
+    pub throughput: cgmath::Vector3<f32>,
+    pub material: u32,
+    pub emitted: cgmath::Vector3<f32>,
+    pub flags: u32,
+}
+
+/// Written by the debug dispatch of [`crate::Renderer::inspect_pixel`]
+#[derive(Clone, Copy, ShaderType)]
+pub(crate) struct GpuDebugPath {
+    pub pixel: cgmath::Vector2<u32>,
+    pub traced_samples: u32,
+    pub bounce_count: u32,
+    pub color: cgmath::Vector3<f32>,
+    pub escaped: u32,
+    pub sky: cgmath::Vector3<f32>,
+    pub origin: cgmath::Vector4<f32>,
+    pub escape_direction: cgmath::Vector4<f32>,
+    pub bounces: [GpuDebugBounce; crate::PixelPath::MAX_BOUNCES],
+}
+
+#[derive(Clone, Copy, ShaderType)]
+pub(crate) struct GpuAnalysisSettings {
+    pub srgb: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, ShaderType)]
+pub struct GpuHyperSphere {
+    pub center: cgmath::Vector4<f32>,
+    pub velocity: cgmath::Vector4<f32>,
+    pub radius: f32,
+    /// The raw [`crate::MaterialId`] in a [`crate::Scene`], the material's index once uploaded
+    pub material: u32,
+    /// Radius of the hollow inside, 0 makes the hyper sphere solid
+    pub inner_radius: f32,
+}
+
+#[derive(Clone, Copy, ShaderType)]
+pub(crate) struct GpuHyperSpheres<'a> {
+    pub count: ArrayLength,
+    #[size(runtime)]
+    pub data: &'a [GpuHyperSphere],
+}
+
+/// A [`GpuHyperSphere`] with the vectors as half floats, see [`ObjectFormat::Packed`]
+#[derive(Clone, Copy, ShaderType)]
+pub(crate) struct GpuPackedHyperSphere {
+    pub center: cgmath::Vector2<u32>,
+    pub velocity: cgmath::Vector2<u32>,
+    pub radius: f32,
+    pub material: u32,
+    pub inner_radius: f32,
+}
+
+#[derive(Clone, Copy, ShaderType)]
+pub(crate) struct GpuPackedHyperSpheres<'a> {
+    pub count: ArrayLength,
+    /// A texel in like the other buffers, see [`crate::SceneBuffer`]
+    #[align(16)]
+    #[size(runtime)]
+    pub data: &'a [GpuPackedHyperSphere],
+}
+
+impl GpuHyperSphere {
+    pub(crate) fn pack(&self) -> GpuPackedHyperSphere {
+        GpuPackedHyperSphere {
+            center: pack_vector(self.center),
+            velocity: pack_vector(self.velocity),
+            radius: self.radius,
+            material: self.material,
+            inner_radius: self.inner_radius,
+        }
+    }
+}
+
+impl GpuPackedHyperSphere {
+    /// The hyper sphere the shader reads back, with the vectors rounded to halves
+    pub(crate) fn unpack(&self) -> GpuHyperSphere {
+        GpuHyperSphere {
+            center: unpack_vector(self.center),
+            velocity: unpack_vector(self.velocity),
+            radius: self.radius,
+            material: self.material,
+            inner_radius: self.inner_radius,
+        }
+    }
+}
+
+/// A node of a [`crate::Bvh`], in depth first order so an interior node's first child comes
+/// right after it
+#[derive(Clone, Copy, PartialEq, Debug, ShaderType)]
+pub struct GpuBvhNode {
+    pub min: cgmath::Vector4<f32>,
+    pub max: cgmath::Vector4<f32>,
+    /// The indices of a leaf's hyper spheres, only the first `count` are used
+    pub hyper_spheres: cgmath::Vector4<u32>,
+    /// 0 for interior nodes
+    pub count: u32,
+    /// The node after everything below this one, where the search goes when the ray misses it
+    pub skip: u32,
+}
+
+#[derive(Clone, Copy, ShaderType)]
+pub(crate) struct GpuBvh<'a> {
+    /// The nodes after these are leaves with the dynamic hyper spheres, which aren't in the tree
+    pub tree_nodes: u32,
+    pub count: ArrayLength,
+    #[size(runtime)]
+    pub data: &'a [GpuBvhNode],
+}
+
+#[derive(Clone, Copy, PartialEq, ShaderType)]
+pub struct GpuHyperPlane {
+    pub point: cgmath::Vector4<f32>,
+    pub normal: cgmath::Vector4<f32>,
+    pub velocity: cgmath::Vector4<f32>,
+    pub angular_velocity: BiVector4,
+    /// The raw [`crate::MaterialId`] in a [`crate::Scene`], the material's index once uploaded
+    pub material: u32,
+    /// Half the size of the plane along each of [`GpuHyperPlane::tangents`], 0 leaves it unbounded that way
+    pub extents: cgmath::Vector3<f32>,
+    /// The first two of [`GpuHyperPlane::tangents`] before they are made perpendicular to the normal,
+    /// both zero picks them automatically
+    pub tangent: cgmath::Vector4<f32>,
+    pub bitangent: cgmath::Vector4<f32>,
+}
+
+#[derive(Clone, Copy, ShaderType)]
+pub(crate) struct GpuHyperPlanes<'a> {
+    pub count: ArrayLength,
+    #[size(runtime)]
+    pub data: &'a [GpuHyperPlane],
+}
+
+/// A [`GpuHyperPlane`] with the vectors and angular velocity as half floats, see [`ObjectFormat::Packed`]
+#[derive(Clone, Copy, ShaderType)]
+pub(crate) struct GpuPackedHyperPlane {
+    pub point: cgmath::Vector2<u32>,
+    pub normal: cgmath::Vector2<u32>,
+    pub velocity: cgmath::Vector2<u32>,
+    /// xy and xz, xw and yz, yw and zw
+    pub angular_velocity: [u32; 3],
+    pub material: u32,
+    /// x and y, z and nothing
+    pub extents: [u32; 2],
+    pub tangent: cgmath::Vector2<u32>,
+    pub bitangent: cgmath::Vector2<u32>,
+}
+
+#[derive(Clone, Copy, ShaderType)]
+pub(crate) struct GpuPackedHyperPlanes<'a> {
+    pub count: ArrayLength,
+    /// A texel in like the other buffers, see [`crate::SceneBuffer`]
+    #[align(16)]
+    #[size(runtime)]
+    pub data: &'a [GpuPackedHyperPlane],
+}
+
+impl GpuHyperPlane {
+    pub(crate) fn pack(&self) -> GpuPackedHyperPlane {
+        let BiVector4 {
+            xy,
+            xz,
+            xw,
+            yz,
+            yw,
+            zw,
+        } = self.angular_velocity;
+        GpuPackedHyperPlane {
+            point: pack_vector(self.point),
+            normal: pack_vector(self.normal),
+            velocity: pack_vector(self.velocity),
+            angular_velocity: [
+                pack_halves(xy, xz),
+                pack_halves(xw, yz),
+                pack_halves(yw, zw),
+            ],
+            material: self.material,
+            extents: [
+                pack_halves(self.extents.x, self.extents.y),
+                pack_halves(self.extents.z, 0.0),
+            ],
+            tangent: pack_vector(self.tangent),
+            bitangent: pack_vector(self.bitangent),
+        }
+    }
+
+    /// [`GpuHyperPlane::tangent`] and [`GpuHyperPlane::bitangent`] made perpendicular to the normal
+    /// and each other, with the third direction perpendicular to all of them,
+    /// or `None` when they don't pick out a basis
+    pub fn custom_tangents(&self) -> Option<[cgmath::Vector4<f32>; 3]> {
+        let normal = self.normal;
+        let tangent = self.tangent - normal * self.tangent.dot(normal);
+        if tangent.magnitude2() <= 1e-6 {
+            return None;
+        }
+        let tangent = tangent.normalize();
+        let bitangent = self.bitangent
+            - normal * self.bitangent.dot(normal)
+            - tangent * self.bitangent.dot(tangent);
+        if bitangent.magnitude2() <= 1e-6 {
+            return None;
+        }
+        let bitangent = bitangent.normalize();
+        Some([
+            tangent,
+            bitangent,
+            cross4(normal, tangent, bitangent).normalize(),
+        ])
+    }
+
+    /// Replaces [`GpuHyperPlane::tangent`] and [`GpuHyperPlane::bitangent`] with the
+    /// perpendicular unit vectors they are used as, leaving them alone if they aren't a basis
+    pub fn orthonormalize_tangents(&mut self) {
+        if let Some([tangent, bitangent, _]) = self.custom_tangents() {
+            self.tangent = tangent;
+            self.bitangent = bitangent;
+        }
+    }
+
+    /// The directions [`GpuHyperPlane::extents`] and plane coordinates are measured along,
+    /// [`GpuHyperPlane::custom_tangents`] when there are some, otherwise the three axes least
+    /// aligned with the normal, made perpendicular to it and each other. Mirrors `plane_basis` in the shader
+    pub fn tangents(&self) -> [cgmath::Vector4<f32>; 3] {
+        if let Some(tangents) = self.custom_tangents() {
+            return tangents;
+        }
+        let normal = self.normal;
+        let mut largest = 0;
+        for axis in 1..4 {
+            if normal[axis].abs() > normal[largest].abs() {
+                largest = axis;
+            }
+        }
+        let mut tangents = [cgmath::Vector4::zero(); 3];
+        for (k, axis) in (0..4).filter(|&axis| axis != largest).enumerate() {
+            let mut tangent = cgmath::Vector4::zero();
+            tangent[axis] = 1.0;
+            tangent -= normal * tangent.dot(normal);
+            for &previous in &tangents[..k] {
+                tangent -= previous * tangent.dot(previous);
+            }
+            tangents[k] = tangent.normalize();
+        }
+        tangents
+    }
+
+    /// Whether the projection of `position` onto the plane is within the extents grown by `margin`,
+    /// ignoring the plane's motion
+    pub fn contains(&self, position: cgmath::Vector4<f32>, margin: f32) -> bool {
+        if self.extents == cgmath::Vector3::zero() {
+            return true;
+        }
+        let offset = position - self.point;
+        self.tangents().iter().enumerate().all(|(k, tangent)| {
+            self.extents[k] <= 0.0 || offset.dot(*tangent).abs() <= self.extents[k] + margin
+        })
+    }
+}
+
+/// The vector perpendicular to `a`, `b` and `c`, the 4D version of the cross product
+pub fn cross4(
+    a: cgmath::Vector4<f32>,
+    b: cgmath::Vector4<f32>,
+    c: cgmath::Vector4<f32>,
+) -> cgmath::Vector4<f32> {
+    let det3 = |i: usize, j: usize, k: usize| {
+        cgmath::Matrix3::new(a[i], b[i], c[i], a[j], b[j], c[j], a[k], b[k], c[k]).determinant()
+    };
+    cgmath::vec4(det3(1, 2, 3), -det3(0, 2, 3), det3(0, 1, 3), -det3(0, 1, 2))
+}
+
+/// How hyper spheres and hyper planes are stored on the GPU
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ObjectFormat {
+    /// [`ObjectFormat::Packed`] once there are at least [`ObjectFormat::AUTO_PACK_OBJECTS`] objects
+    Auto,
+    Full,
+    /// Vectors as half floats, 32 instead of 48 bytes a hyper sphere and 64 instead of 128 a
+    /// hyper plane, but only about 3 significant digits, so far away objects are placed less
+    /// precisely
+    Packed,
+}
+
+impl ObjectFormat {
+    pub const ALL: [ObjectFormat; 3] =
+        [ObjectFormat::Auto, ObjectFormat::Full, ObjectFormat::Packed];
+    pub const AUTO_PACK_OBJECTS: usize = 10_000;
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ObjectFormat::Auto => "Auto",
+            ObjectFormat::Full => "Full",
+            ObjectFormat::Packed => "Packed",
+        }
+    }
+
+    /// Whether a scene with `object_count` hyper spheres and hyper planes is packed
+    pub fn packs(self, object_count: usize) -> bool {
+        match self {
+            ObjectFormat::Auto => object_count >= Self::AUTO_PACK_OBJECTS,
+            ObjectFormat::Full => false,
+            ObjectFormat::Packed => true,
+        }
+    }
+}
+
+/// Two floats as halves in one `u32` with `a` in the low bits, like WGSL's `pack2x16float`
+pub fn pack_halves(a: f32, b: f32) -> u32 {
+    f32_to_f16_bits(a) as u32 | (f32_to_f16_bits(b) as u32) << 16
+}
+
+/// The two floats of [`pack_halves`], like WGSL's `unpack2x16float`
+pub fn unpack_halves(packed: u32) -> (f32, f32) {
+    (
+        f16_bits_to_f32(packed as u16),
+        f16_bits_to_f32((packed >> 16) as u16),
+    )
+}
+
+fn pack_vector(v: cgmath::Vector4<f32>) -> cgmath::Vector2<u32> {
+    cgmath::vec2(pack_halves(v.x, v.y), pack_halves(v.z, v.w))
+}
+
+fn unpack_vector(v: cgmath::Vector2<u32>) -> cgmath::Vector4<f32> {
+    let (x, y) = unpack_halves(v.x);
+    let (z, w) = unpack_halves(v.y);
+    cgmath::vec4(x, y, z, w)
+}
+
+/// Rounds to the nearest half float, values too large for one become infinity
+fn f32_to_f16_bits(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = (bits >> 16) as u16 & 0x8000;
+    let exponent = (bits >> 23 & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if exponent <= 0 {
+        // too small for a normal half, the implicit leading one becomes part of the mantissa
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        let rounding = mantissa >> (shift - 1) & 1;
+        return sign | ((mantissa >> shift) + rounding) as u16;
+    }
+    // a carry out of the mantissa correctly bumps the exponent
+    let rounding = mantissa >> 12 & 1;
+    sign | (((exponent as u32) << 10 | mantissa >> 13) + rounding) as u16
+}
+
+/// Every half float is exactly representable as a float
+fn f16_bits_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = (bits >> 10 & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+    sign * match exponent {
+        0 => mantissa * 2.0f32.powi(-24),
+        0x1f if mantissa == 0.0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2.0f32.powi(exponent - 15),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ShaderType)]
+pub struct GpuMaterial {
+    pub base_color: cgmath::Vector3<f32>,
+    pub emissive_color: cgmath::Vector3<f32>,
+    pub emission_strength: f32,
+    /// How far the shading normal is tilted by procedural noise, 0 disables it
+    pub bump_strength: f32,
+    /// The frequency of the bump noise, higher is finer
+    pub bump_scale: f32,
+    /// An [`EmissionPattern`] as a `u32`
+    pub emission_pattern: u32,
+    /// Scales the hit position before the pattern is evaluated, a zero component ignores that axis
+    pub emission_pattern_scale: cgmath::Vector4<f32>,
+    /// The chance of a hit being glass-like instead of diffuse, glass refracts and reflects
+    /// with the fresnel term and is tinted by `base_color`
+    pub transmission: f32,
+    /// The index of refraction at the sodium D line, 589.3nm
+    pub ior: f32,
+    /// The `B` of Cauchy's equation in micrometers squared, how much `ior` rises for shorter
+    /// wavelengths, only visible with [`crate::Camera::spectral`]
+    pub dispersion: f32,
+    /// Which transmissive material fills the space where objects overlap, the highest wins,
+    /// so a glass object in water can have a higher priority than the water
+    pub priority: u32,
+    /// How much light is left after travelling one unit through the inside of a transmissive
+    /// object, before `absorption_density` is applied
+    pub absorption_color: cgmath::Vector3<f32>,
+    /// How many times `absorption_color` is applied per unit, 0 for clear
+    pub absorption_density: f32,
+    /// How much of a clear varnish is layered over the base, it reflects like a dielectric with an IOR of 1.5
+    pub clearcoat: f32,
+    pub clearcoat_roughness: f32,
+    /// How strongly fabric like grazing reflections in `sheen_color` are added to the base
+    pub sheen: f32,
+    pub sheen_color: cgmath::Vector3<f32>,
+    /// 0 for none, otherwise 1 plus the index into [`crate::Scene::light_groups`] that scales
+    /// this material's emission
+    pub light_group: u32,
+    /// A [`BlendMask`] as a `u32`, anything but [`BlendMask::None`] makes hits use `blend_a` or
+    /// `blend_b` instead of this material, chosen randomly with the mask as the chance of `blend_b`
+    pub blend_mask: u32,
+    /// Like the `material` of objects these hold a [`crate::MaterialId`] on the CPU, and are
+    /// replaced with indices when the materials are uploaded
+    pub blend_a: u32,
+    pub blend_b: u32,
+    /// Scales the hit position before the mask is evaluated, a zero component ignores that axis
+    pub blend_scale: cgmath::Vector4<f32>,
+    /// Where the compiled [`crate::NodeGraph`] of the material starts in the node programs and
+    /// how many instructions it has, these are filled in when the materials are uploaded
+    pub node_program: u32,
+    pub node_program_length: u32,
+}
+
+impl Default for GpuMaterial {
+    fn default() -> Self {
+        Self {
+            base_color: cgmath::vec3(0.9, 0.9, 0.9),
+            emissive_color: cgmath::vec3(0.0, 0.0, 0.0),
+            emission_strength: 0.0,
+            bump_strength: 0.0,
+            bump_scale: 4.0,
+            emission_pattern: EmissionPattern::Solid as _,
+            emission_pattern_scale: cgmath::vec4(1.0, 0.0, 0.0, 0.0),
+            transmission: 0.0,
+            ior: 1.5,
+            dispersion: 0.0,
+            priority: 0,
+            absorption_color: cgmath::vec3(1.0, 1.0, 1.0),
+            absorption_density: 0.0,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.1,
+            sheen: 0.0,
+            sheen_color: cgmath::vec3(1.0, 1.0, 1.0),
+            light_group: 0,
+            blend_mask: BlendMask::None as _,
+            blend_a: 0,
+            blend_b: 0,
+            blend_scale: cgmath::vec4(1.0, 1.0, 1.0, 1.0),
+            node_program: 0,
+            node_program_length: 0,
+        }
+    }
+}
+
+/// How emission varies across a surface, evaluated on the 4D hit position scaled by
+/// [`GpuMaterial::emission_pattern_scale`]. Hyper planes with [`GpuHyperPlane::custom_tangents`]
+/// use their coordinates along the tangents instead, with 0 for w
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EmissionPattern {
+    Solid,
+    /// Fades from nothing to full strength along the scale vector, centered on the origin
+    Gradient,
+    /// Alternating bands along the scale vector
+    Stripes,
+    /// Alternating cells of the lattice, only counting the axes with a non-zero scale
+    Checker,
+}
+
+impl EmissionPattern {
+    pub const ALL: [EmissionPattern; 4] = [
+        EmissionPattern::Solid,
+        EmissionPattern::Gradient,
+        EmissionPattern::Stripes,
+        EmissionPattern::Checker,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EmissionPattern::Solid => "Solid",
+            EmissionPattern::Gradient => "Gradient",
+            EmissionPattern::Stripes => "Stripes",
+            EmissionPattern::Checker => "Checker",
+        }
+    }
+
+    pub fn from_u32(value: u32) -> Option<Self> {
+        Self::ALL.get(value as usize).copied()
+    }
+}
+
+/// Where a blended material uses [`GpuMaterial::blend_b`] instead of [`GpuMaterial::blend_a`],
+/// evaluated on the same position as the [`EmissionPattern`] scaled by
+/// [`GpuMaterial::blend_scale`]. Values between 0 and 1 pick `blend_b` with that chance, so
+/// the materials are mixed over many samples
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BlendMask {
+    /// Not a blend, the material's own properties are used
+    None,
+    /// Smooth 4D value noise, with features about one unit apart before scaling
+    Noise,
+    /// Fades from `blend_a` to `blend_b` along w, centered on w = 0
+    Gradient,
+    /// Alternating cells of the lattice, only counting the axes with a non-zero scale
+    Checker,
+}
+
+impl BlendMask {
+    pub const ALL: [BlendMask; 4] = [
+        BlendMask::None,
+        BlendMask::Noise,
+        BlendMask::Gradient,
+        BlendMask::Checker,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BlendMask::None => "None",
+            BlendMask::Noise => "Noise",
+            BlendMask::Gradient => "Gradient",
+            BlendMask::Checker => "Checker",
+        }
+    }
+
+    pub fn from_u32(value: u32) -> Option<Self> {
+        Self::ALL.get(value as usize).copied()
+    }
+}
+
+#[derive(Clone, Copy, ShaderType)]
+pub(crate) struct GpuMaterials<'a> {
+    pub count: ArrayLength,
+    #[size(runtime)]
+    pub data: &'a [GpuMaterial],
+}
+
+/// An input that isn't connected, the instruction's value is used instead
+pub const NO_REGISTER: u32 = u32::MAX;
+
+/// One node of a compiled [`crate::NodeGraph`], its result goes in the register with the same
+/// index as the instruction and `a`, `b` and `c` are the registers of its inputs
+#[derive(Clone, Copy, PartialEq, Debug, ShaderType)]
+pub struct GpuNodeInstruction {
+    /// A [`crate::NodeKind`] as a `u32`
+    pub kind: u32,
+    pub a: u32,
+    pub b: u32,
+    pub c: u32,
+    pub value: cgmath::Vector4<f32>,
+}
+
+#[derive(Clone, Copy, ShaderType)]
+pub(crate) struct GpuNodePrograms<'a> {
+    pub count: ArrayLength,
+    #[size(runtime)]
+    pub data: &'a [GpuNodeInstruction],
+}
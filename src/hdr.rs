@@ -0,0 +1,31 @@
+use crate::exr::{encode_exr, ExrChannel};
+
+/// The linear average colors of a render before they are clamped and encoded for display,
+/// from [`crate::Renderer::read_hdr`]
+#[derive(Clone, PartialEq, Debug)]
+pub struct HdrImage {
+    pub width: usize,
+    pub height: usize,
+    /// In rows from the top
+    pub pixels: Vec<cgmath::Vector3<f32>>,
+}
+
+impl HdrImage {
+    pub fn to_exr(&self) -> Vec<u8> {
+        let channels = ["R", "G", "B"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| ExrChannel {
+                name: name.into(),
+                values: self.pixels.iter().map(|pixel| pixel[i]).collect(),
+            })
+            .collect::<Vec<_>>();
+        encode_exr(self.width, self.height, &channels)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write_exr(&self, path: &std::path::Path) -> Result<(), String> {
+        std::fs::write(path, self.to_exr())
+            .map_err(|error| format!("Failed to write {}: {error}", path.display()))
+    }
+}
@@ -0,0 +1,45 @@
+use crate::SHADER_MODULES;
+use std::{path::PathBuf, time::SystemTime};
+
+pub(crate) fn shader_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src")
+        .join("shaders")
+}
+
+/// Polls the shader sources on disk for modifications
+pub(crate) struct ShaderWatcher {
+    paths: Vec<PathBuf>,
+    last_modified: Option<SystemTime>,
+}
+
+impl ShaderWatcher {
+    pub fn new() -> Self {
+        let shader_dir = shader_dir();
+        let paths = SHADER_MODULES
+            .iter()
+            .map(|&(name, _)| shader_dir.join(name))
+            .collect::<Vec<_>>();
+        Self {
+            last_modified: Self::latest_modification(&paths),
+            paths,
+        }
+    }
+
+    fn latest_modification(paths: &[PathBuf]) -> Option<SystemTime> {
+        paths
+            .iter()
+            .filter_map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+            .max()
+    }
+
+    /// Returns whether any shader module has changed since the last poll
+    pub fn poll(&mut self) -> bool {
+        let last_modified = Self::latest_modification(&self.paths);
+        if last_modified == self.last_modified {
+            return false;
+        }
+        self.last_modified = last_modified;
+        true
+    }
+}
@@ -0,0 +1,219 @@
+#[cfg(feature = "editor")]
+use crate::widgets::{edit_bivector4, edit_material, edit_orientation, edit_position, edit_vec4};
+use crate::{
+    all_finite, bivector_components, existing_material, finite_or_zero, BiVector4, GpuHyperPlane,
+    MaterialId, ObjectProblem, Placement, Primitive, PrimitiveKind,
+};
+use cgmath::prelude::*;
+#[cfg(feature = "editor")]
+use eframe::egui;
+
+impl Primitive for GpuHyperPlane {
+    const KIND: PrimitiveKind = PrimitiveKind::HyperPlane;
+    const NAME: &'static str = "Hyper Plane";
+    const PLURAL_NAME: &'static str = "Hyper Planes";
+    const SHADER_NAME: &'static str = "hyper_plane";
+
+    fn new(material: MaterialId) -> Self {
+        Self {
+            point: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            normal: cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+            velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            angular_velocity: BiVector4::ZERO,
+            material: material.0,
+            extents: cgmath::vec3(0.0, 0.0, 0.0),
+            tangent: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            bitangent: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        }
+    }
+
+    fn material(&self) -> MaterialId {
+        MaterialId(self.material)
+    }
+
+    fn set_material(&mut self, material: MaterialId) {
+        self.material = material.0;
+    }
+
+    fn position(&self) -> cgmath::Vector4<f32> {
+        self.point
+    }
+
+    fn position_mut(&mut self) -> &mut cgmath::Vector4<f32> {
+        &mut self.point
+    }
+
+    fn placed(self, placement: &Placement) -> Self {
+        // the default tangents follow the axes, so finite planes need theirs rotated explicitly
+        let (tangent, bitangent) =
+            if self.custom_tangents().is_some() || self.extents != cgmath::Vector3::zero() {
+                let [tangent, bitangent, _] = self.tangents();
+                (placement.direction(tangent), placement.direction(bitangent))
+            } else {
+                (self.tangent, self.bitangent)
+            };
+        Self {
+            point: placement.point(self.point),
+            normal: placement.direction(self.normal),
+            velocity: placement.direction(self.velocity) * placement.scale,
+            angular_velocity: placement.bivector(self.angular_velocity),
+            extents: self.extents * placement.scale,
+            tangent,
+            bitangent,
+            ..self
+        }
+    }
+
+    fn problems(&self, material_ids: &[MaterialId]) -> Vec<ObjectProblem> {
+        let mut problems = vec![];
+        let vectors: [[f32; 4]; 5] = [
+            self.point.into(),
+            self.normal.into(),
+            self.velocity.into(),
+            self.tangent.into(),
+            self.bitangent.into(),
+        ];
+        let extents: [f32; 3] = self.extents.into();
+        if !vectors.iter().all(|vector| all_finite(vector))
+            || !all_finite(&extents)
+            || !all_finite(&bivector_components(self.angular_velocity))
+        {
+            problems.push(ObjectProblem::NonFinite);
+        }
+        if self.normal.map(finite_or_zero).magnitude2() < f32::EPSILON {
+            problems.push(ObjectProblem::ZeroNormal);
+        }
+        if extents.iter().any(|&extent| extent < 0.0) {
+            problems.push(ObjectProblem::NegativeExtents);
+        }
+        if !material_ids.contains(&MaterialId(self.material)) {
+            problems.push(ObjectProblem::MaterialOutOfRange);
+        }
+        problems
+    }
+
+    /// Every value that isn't finite set to 0, a unit normal that is +Y if it had no length,
+    /// positive extents, and a material that exists
+    fn sanitized(self, material_ids: &[MaterialId]) -> Self {
+        let normal = self.normal.map(finite_or_zero);
+        let [xy, xz, xw, yz, yw, zw] =
+            bivector_components(self.angular_velocity).map(finite_or_zero);
+        Self {
+            point: self.point.map(finite_or_zero),
+            normal: if normal.magnitude2() < f32::EPSILON {
+                cgmath::vec4(0.0, 1.0, 0.0, 0.0)
+            } else {
+                normal.normalize()
+            },
+            velocity: self.velocity.map(finite_or_zero),
+            angular_velocity: BiVector4 {
+                xy,
+                xz,
+                xw,
+                yz,
+                yw,
+                zw,
+            },
+            material: existing_material(self.material, material_ids),
+            extents: self.extents.map(|extent| finite_or_zero(extent).abs()),
+            tangent: self.tangent.map(finite_or_zero),
+            bitangent: self.bitangent.map(finite_or_zero),
+        }
+    }
+
+    fn intersect(
+        &self,
+        origin: cgmath::Vector4<f32>,
+        direction: cgmath::Vector4<f32>,
+    ) -> Option<(f32, cgmath::Vector4<f32>)> {
+        let d = self.normal.dot(direction);
+        if d == 0.0 {
+            return None;
+        }
+        let distance = (self.point - origin).dot(self.normal) / d;
+        (distance > 0.0 && self.contains(origin + direction * distance, 0.0))
+            .then_some((distance, self.normal))
+    }
+
+    #[cfg(feature = "editor")]
+    fn edit(&mut self, ui: &mut egui::Ui, context: &crate::EditContext) -> bool {
+        let mut changed = edit_position(ui, "Point: ", &mut self.point, context.snapping);
+        if edit_vec4(ui, "Normal: ", &mut self.normal) {
+            self.normal = self.normal.normalize();
+            changed = true;
+        }
+        changed |= edit_orientation(ui, &mut self.normal, cgmath::vec4(0.0, 1.0, 0.0, 0.0));
+        changed |= edit_extents(ui, &mut self.extents);
+        changed |= edit_tangents(ui, self);
+        changed |= edit_vec4(ui, "Velocity: ", &mut self.velocity);
+        changed |= edit_bivector4(ui, "Angular Velocity: ", &mut self.angular_velocity);
+        changed |= edit_material(
+            ui,
+            "Material: ",
+            &mut self.material,
+            context.material_ids,
+            context.material_names,
+        );
+        changed
+    }
+}
+
+#[cfg(feature = "editor")]
+/// Half sizes of a finite hyper plane along its tangents, 0 leaves that direction unbounded
+fn edit_extents(ui: &mut egui::Ui, extents: &mut cgmath::Vector3<f32>) -> bool {
+    ui.horizontal(|ui| {
+        ui.label("Extents: ").on_hover_text(
+            "Half sizes along the three directions in the hyper plane least aligned with \
+             its normal, 0 is unbounded",
+        );
+        let mut changed = false;
+        for axis in 0..3 {
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut extents[axis])
+                        .clamp_range(0.0..=f32::INFINITY)
+                        .speed(0.01),
+                )
+                .changed();
+        }
+        changed
+    })
+    .inner
+}
+
+#[cfg(feature = "editor")]
+/// The hyper plane's own tangents, kept perpendicular to the normal and each other
+fn edit_tangents(ui: &mut egui::Ui, hyper_plane: &mut GpuHyperPlane) -> bool {
+    let mut custom = hyper_plane.custom_tangents().is_some();
+    let mut changed = false;
+    if ui
+        .checkbox(&mut custom, "Custom Tangents")
+        .on_hover_text(
+            "Orients the extents, and emission patterns use coordinates along the \
+             tangents instead of the position",
+        )
+        .changed()
+    {
+        let [tangent, bitangent, _] = hyper_plane.tangents();
+        (hyper_plane.tangent, hyper_plane.bitangent) = if custom {
+            (tangent, bitangent)
+        } else {
+            (cgmath::Vector4::zero(), cgmath::Vector4::zero())
+        };
+        changed = true;
+    }
+    if custom {
+        if edit_vec4(ui, "Tangent: ", &mut hyper_plane.tangent)
+            | edit_vec4(ui, "Bitangent: ", &mut hyper_plane.bitangent)
+        {
+            hyper_plane.orthonormalize_tangents();
+            changed = true;
+        }
+        let third = hyper_plane.tangents()[2];
+        ui.label(format!(
+            "Third Tangent: x: {:.3} y: {:.3} z: {:.3} w: {:.3}",
+            third.x, third.y, third.z, third.w
+        ));
+    }
+    changed
+}
@@ -0,0 +1,129 @@
+#[cfg(feature = "editor")]
+use crate::widgets::{edit_material, edit_position, edit_value, edit_vec4};
+use crate::{
+    all_finite, existing_material, finite_or_zero, GpuHyperSphere, MaterialId, ObjectProblem,
+    Placement, Primitive, PrimitiveKind,
+};
+use cgmath::prelude::*;
+#[cfg(feature = "editor")]
+use eframe::egui;
+
+impl Primitive for GpuHyperSphere {
+    const KIND: PrimitiveKind = PrimitiveKind::HyperSphere;
+    const NAME: &'static str = "Hyper Sphere";
+    const PLURAL_NAME: &'static str = "Hyper Spheres";
+    const SHADER_NAME: &'static str = "hyper_sphere";
+    const CULLED: bool = true;
+    const BVH: bool = true;
+
+    fn new(material: MaterialId) -> Self {
+        Self {
+            center: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            radius: 1.0,
+            material: material.0,
+            inner_radius: 0.0,
+        }
+    }
+
+    fn material(&self) -> MaterialId {
+        MaterialId(self.material)
+    }
+
+    fn set_material(&mut self, material: MaterialId) {
+        self.material = material.0;
+    }
+
+    fn position(&self) -> cgmath::Vector4<f32> {
+        self.center
+    }
+
+    fn position_mut(&mut self) -> &mut cgmath::Vector4<f32> {
+        &mut self.center
+    }
+
+    fn placed(self, placement: &Placement) -> Self {
+        Self {
+            center: placement.point(self.center),
+            velocity: placement.direction(self.velocity) * placement.scale,
+            radius: self.radius * placement.scale,
+            inner_radius: self.inner_radius * placement.scale,
+            ..self
+        }
+    }
+
+    fn problems(&self, material_ids: &[MaterialId]) -> Vec<ObjectProblem> {
+        let mut problems = vec![];
+        let center: [f32; 4] = self.center.into();
+        let velocity: [f32; 4] = self.velocity.into();
+        if !all_finite(&center)
+            || !all_finite(&velocity)
+            || !all_finite(&[self.radius, self.inner_radius])
+        {
+            problems.push(ObjectProblem::NonFinite);
+        }
+        if self.radius < 0.0 {
+            problems.push(ObjectProblem::NegativeRadius);
+        }
+        if !material_ids.contains(&MaterialId(self.material)) {
+            problems.push(ObjectProblem::MaterialOutOfRange);
+        }
+        problems
+    }
+
+    /// Every value that isn't finite set to 0, a positive radius, the inner radius inside the
+    /// radius, and a material that exists
+    fn sanitized(self, material_ids: &[MaterialId]) -> Self {
+        let radius = finite_or_zero(self.radius).abs();
+        Self {
+            center: self.center.map(finite_or_zero),
+            velocity: self.velocity.map(finite_or_zero),
+            radius,
+            inner_radius: finite_or_zero(self.inner_radius).clamp(0.0, radius),
+            material: existing_material(self.material, material_ids),
+        }
+    }
+
+    fn intersect(
+        &self,
+        origin: cgmath::Vector4<f32>,
+        direction: cgmath::Vector4<f32>,
+    ) -> Option<(f32, cgmath::Vector4<f32>)> {
+        let oc = origin - self.center;
+        let a = direction.magnitude2();
+        let half_b = oc.dot(direction);
+        // hollow hyper spheres can also be hit on the inside surface
+        let distance = [self.radius, self.inner_radius]
+            .into_iter()
+            .filter(|&radius| radius > 0.0)
+            .filter_map(|radius| {
+                let c = oc.magnitude2() - radius * radius;
+                let discriminant = half_b * half_b - a * c;
+                (discriminant >= 0.0)
+                    .then(|| [-1.0, 1.0].map(|sign| (-half_b + sign * discriminant.sqrt()) / a))
+            })
+            .flatten()
+            .filter(|&t| t > 0.0)
+            .min_by(f32::total_cmp)?;
+        let normal = (origin + direction * distance - self.center).normalize();
+        Some((distance, normal))
+    }
+
+    #[cfg(feature = "editor")]
+    fn edit(&mut self, ui: &mut egui::Ui, context: &crate::EditContext) -> bool {
+        let mut changed = edit_position(ui, "Center: ", &mut self.center, context.snapping);
+        changed |= edit_vec4(ui, "Velocity: ", &mut self.velocity);
+        changed |= edit_value(ui, "Radius: ", &mut self.radius, 0.01);
+        // 0 is solid
+        changed |= edit_value(ui, "Inner Radius: ", &mut self.inner_radius, 0.01);
+        self.inner_radius = self.inner_radius.clamp(0.0, self.radius);
+        changed |= edit_material(
+            ui,
+            "Material: ",
+            &mut self.material,
+            context.material_ids,
+            context.material_names,
+        );
+        changed
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{GpuDebugPath, GpuHyperSphere, GpuMaterial, MaterialId};
+use cgmath::prelude::*;
+
+/// One bounce of a [`PixelPath`]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PathBounce {
+    pub position: cgmath::Vector4<f32>,
+    pub normal: cgmath::Vector4<f32>,
+    /// The direction the ray arrived from
+    pub direction: cgmath::Vector4<f32>,
+    pub material: u32,
+    /// What the light from further along the path is multiplied by after this bounce
+    pub throughput: cgmath::Vector3<f32>,
+    /// The light this bounce adds to the pixel
+    pub emitted: cgmath::Vector3<f32>,
+    /// Went through the surface instead of reflecting off it
+    pub transmitted: bool,
+    /// Reflected off the clearcoat
+    pub coated: bool,
+    /// Hit the surface left by the cross section
+    pub cut: bool,
+    pub front_face: bool,
+}
+
+/// How a [`PixelPath`] left the scene
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PathEscape {
+    pub direction: cgmath::Vector4<f32>,
+    /// The light from the sky, already multiplied by the path's throughput
+    pub light: cgmath::Vector3<f32>,
+}
+
+/// The path the first sample of a pixel took, from [`crate::Renderer::inspect_pixel`]
+#[derive(Clone, PartialEq, Debug)]
+pub struct PixelPath {
+    pub pixel: (usize, usize),
+    /// The pixel's color over every sample of this frame, linear and clamped
+    pub color: cgmath::Vector3<f32>,
+    /// Where the camera ray started
+    pub origin: cgmath::Vector4<f32>,
+    /// At most [`PixelPath::MAX_BOUNCES`], hits on the boundaries of overlapping media are skipped
+    pub bounces: Vec<PathBounce>,
+    /// `None` if the path ran out of bounces
+    pub escape: Option<PathEscape>,
+}
+
+impl PixelPath {
+    pub const MAX_BOUNCES: usize = 16;
+    /// How far past the last bounce the markers of an escaping path go
+    pub const ESCAPE_LENGTH: f32 = 4.0;
+    /// At most this many markers are put along one segment, the rest are spread further apart
+    pub const MAX_SEGMENT_MARKERS: usize = 64;
+
+    /// The materials for [`PixelPath::markers`], one for the hits and one for the segments between them
+    pub fn marker_materials() -> [GpuMaterial; 2] {
+        [
+            GpuMaterial {
+                base_color: cgmath::vec3(0.0, 0.0, 0.0),
+                emissive_color: cgmath::vec3(1.0, 0.5, 0.1),
+                emission_strength: 4.0,
+                ..Default::default()
+            },
+            GpuMaterial {
+                base_color: cgmath::vec3(0.0, 0.0, 0.0),
+                emissive_color: cgmath::vec3(0.1, 0.6, 1.0),
+                emission_strength: 2.0,
+                ..Default::default()
+            },
+        ]
+    }
+
+    /// Small hyper spheres tracing out the path so it can be drawn into the scene, one of
+    /// `radius` on every hit and smaller ones along the segments between them, see
+    /// [`PixelPath::marker_materials`]
+    pub fn markers(
+        &self,
+        hit_material: MaterialId,
+        segment_material: MaterialId,
+        radius: f32,
+    ) -> Vec<GpuHyperSphere> {
+        let marker = |center, radius, material: MaterialId| GpuHyperSphere {
+            center,
+            velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            radius,
+            material: material.0,
+            inner_radius: 0.0,
+        };
+
+        let mut points = vec![self.origin];
+        points.extend(self.bounces.iter().map(|bounce| bounce.position));
+        if let Some(escape) = self.escape {
+            let last = *points.last().unwrap();
+            points.push(last + escape.direction.normalize_to(Self::ESCAPE_LENGTH));
+        }
+
+        let mut markers = self
+            .bounces
+            .iter()
+            .map(|bounce| marker(bounce.position, radius, hit_material))
+            .collect::<Vec<_>>();
+        let spacing = radius * 3.0;
+        for segment in points.windows(2) {
+            let length = (segment[1] - segment[0]).magnitude();
+            let count = ((length / spacing) as usize).min(Self::MAX_SEGMENT_MARKERS);
+            // the ends are left to the hit markers
+            markers.extend((1..count).map(|i| {
+                let t = i as f32 / count as f32;
+                marker(
+                    segment[0].lerp(segment[1], t),
+                    radius * 0.4,
+                    segment_material,
+                )
+            }));
+        }
+        markers
+    }
+}
+
+impl From<GpuDebugPath> for PixelPath {
+    fn from(path: GpuDebugPath) -> Self {
+        let bounce_count = (path.bounce_count as usize).min(Self::MAX_BOUNCES);
+        Self {
+            pixel: (path.pixel.x as _, path.pixel.y as _),
+            color: path.color,
+            origin: path.origin,
+            bounces: path.bounces[..bounce_count]
+                .iter()
+                .map(|bounce| PathBounce {
+                    position: bounce.position,
+                    normal: bounce.normal,
+                    direction: bounce.direction,
+                    material: bounce.material,
+                    throughput: bounce.throughput,
+                    emitted: bounce.emitted,
+                    transmitted: bounce.flags & 1 != 0,
+                    coated: bounce.flags & 2 != 0,
+                    cut: bounce.flags & 4 != 0,
+                    front_face: bounce.flags & 8 != 0,
+                })
+                .collect(),
+            escape: (path.escaped != 0).then_some(PathEscape {
+                direction: path.escape_direction,
+                light: path.sky,
+            }),
+        }
+    }
+}
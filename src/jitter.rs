@@ -0,0 +1,89 @@
+use crate::{GpuHyperSphere, ObjectRef, Scene};
+use cgmath::InnerSpace;
+use rand::{Rng, SeedableRng};
+
+/// Random variation for the editor's selected objects, every amount is the most an object can
+/// change by in either direction and zero leaves that property alone
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Jitter {
+    /// Added to the position on each axis
+    pub position: cgmath::Vector4<f32>,
+    /// A fraction of the hyper sphere radius up to 0.9, the inner radius is scaled with it
+    pub radius: f32,
+    /// A fraction of a turn around the hue wheel
+    pub hue: f32,
+    pub seed: u64,
+}
+
+impl Default for Jitter {
+    fn default() -> Self {
+        Self {
+            position: cgmath::vec4(0.5, 0.0, 0.5, 0.5),
+            radius: 0.25,
+            hue: 0.0,
+            seed: 0,
+        }
+    }
+}
+
+impl Jitter {
+    /// Changes every listed object, skipping ones that have been removed.
+    /// Materials can be shared, so jittering the hue gives each object a copy of its material
+    pub fn apply(&self, scene: &mut Scene, objects: &[ObjectRef]) {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(self.seed);
+        let mut amount = |range: f32| {
+            if range > 0.0 {
+                rng.gen_range(-range..=range)
+            } else {
+                0.0
+            }
+        };
+        for &object in objects {
+            let offset = cgmath::vec4(
+                amount(self.position.x),
+                amount(self.position.y),
+                amount(self.position.z),
+                amount(self.position.w),
+            );
+            let radius = 1.0 + amount(self.radius.min(0.9));
+            let hue = amount(self.hue);
+
+            let Some(data) = scene.object_mut(object).map(|object| &mut object.data) else {
+                continue;
+            };
+            *data.position_mut() += offset;
+            if let Some(sphere) = data.get_mut::<GpuHyperSphere>() {
+                sphere.radius *= radius;
+                sphere.inner_radius *= radius;
+            }
+
+            if hue == 0.0 {
+                continue;
+            }
+            let material = data.material();
+            let Some(index) = scene.material_index(material) else {
+                continue;
+            };
+            let name = scene.material_names[index].clone();
+            let mut material = scene.materials[index];
+            material.base_color = rotate_hue(material.base_color, hue);
+            material.emissive_color = rotate_hue(material.emissive_color, hue);
+            material.sheen_color = rotate_hue(material.sheen_color, hue);
+            let graph = scene.node_graphs.get(&scene.material_ids[index]).cloned();
+            let material = scene.add_material(name, material);
+            if let Some(graph) = graph {
+                scene.node_graphs.insert(material, graph);
+            }
+            scene.object_mut(object).unwrap().set_material(material);
+        }
+    }
+}
+
+/// Turns `color` by `turns` around the gray axis, which keeps its brightness and saturation
+/// roughly the same, clamped so it can't go negative
+pub fn rotate_hue(color: cgmath::Vector3<f32>, turns: f32) -> cgmath::Vector3<f32> {
+    let axis = cgmath::vec3(1.0f32, 1.0, 1.0).normalize();
+    let (sin, cos) = (turns * std::f32::consts::TAU).sin_cos();
+    let rotated = color * cos + axis.cross(color) * sin + axis * axis.dot(color) * (1.0 - cos);
+    rotated.map(|channel| channel.max(0.0))
+}
@@ -0,0 +1,293 @@
+//! Just enough JSON for the files the editor reads and writes
+
+use std::fmt::Write;
+
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Keeps the order the keys were written in
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        match *self {
+            Json::Number(n) => Some(n as f32),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn parse(source: &str) -> Result<Json, String> {
+        let mut parser = Parser {
+            source: source.as_bytes(),
+            position: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.position < source.len() {
+            return Err(parser.error("expected the end of the file"));
+        }
+        Ok(value)
+    }
+
+    /// Pretty prints with two space indents, arrays of numbers stay on one line
+    pub fn to_string_pretty(&self) -> String {
+        let mut output = String::new();
+        self.write(&mut output, 0);
+        output.push('\n');
+        output
+    }
+
+    fn write(&self, output: &mut String, indent: usize) {
+        match self {
+            Json::Null => output.push_str("null"),
+            Json::Bool(b) => write!(output, "{b}").unwrap(),
+            Json::Number(n) if n.is_finite() => write!(output, "{n}").unwrap(),
+            Json::Number(_) => output.push_str("null"),
+            Json::String(s) => write_string(output, s),
+            Json::Array(values) if values.iter().all(|v| matches!(v, Json::Number(_))) => {
+                output.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        output.push_str(", ");
+                    }
+                    value.write(output, indent);
+                }
+                output.push(']');
+            }
+            Json::Array(values) => {
+                output.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    output.push_str(if i > 0 { ",\n" } else { "\n" });
+                    push_indent(output, indent + 1);
+                    value.write(output, indent + 1);
+                }
+                if !values.is_empty() {
+                    output.push('\n');
+                    push_indent(output, indent);
+                }
+                output.push(']');
+            }
+            Json::Object(entries) => {
+                output.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    output.push_str(if i > 0 { ",\n" } else { "\n" });
+                    push_indent(output, indent + 1);
+                    write_string(output, key);
+                    output.push_str(": ");
+                    value.write(output, indent + 1);
+                }
+                if !entries.is_empty() {
+                    output.push('\n');
+                    push_indent(output, indent);
+                }
+                output.push('}');
+            }
+        }
+    }
+}
+
+fn push_indent(output: &mut String, indent: usize) {
+    for _ in 0..indent {
+        output.push_str("  ");
+    }
+}
+
+fn write_string(output: &mut String, s: &str) {
+    output.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c.is_control() => write!(output, "\\u{:04x}", c as u32).unwrap(),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+struct Parser<'a> {
+    source: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        let line = 1 + self.source[..self.position.min(self.source.len())]
+            .iter()
+            .filter(|&&b| b == b'\n')
+            .count();
+        format!("line {line}: {message}")
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .source
+            .get(self.position)
+            .is_some_and(|b| b.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.source.get(self.position).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", byte as char)))
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json) -> Result<Json, String> {
+        if self.source[self.position..].starts_with(keyword.as_bytes()) {
+            self.position += keyword.len();
+            Ok(value)
+        } else {
+            Err(self.error("unexpected character"))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        match self.peek() {
+            None => Err(self.error("unexpected end of file")),
+            Some(b'n') => self.keyword("null", Json::Null),
+            Some(b't') => self.keyword("true", Json::Bool(true)),
+            Some(b'f') => self.keyword("false", Json::Bool(false)),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'[') => {
+                self.position += 1;
+                let mut values = vec![];
+                if self.peek() == Some(b']') {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    match self.peek() {
+                        Some(b',') => self.position += 1,
+                        _ => break,
+                    }
+                }
+                self.expect(b']')?;
+                Ok(Json::Array(values))
+            }
+            Some(b'{') => {
+                self.position += 1;
+                let mut entries = vec![];
+                if self.peek() == Some(b'}') {
+                    self.position += 1;
+                    return Ok(Json::Object(entries));
+                }
+                loop {
+                    if self.peek() != Some(b'"') {
+                        return Err(self.error("expected a key"));
+                    }
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    entries.push((key, self.value()?));
+                    match self.peek() {
+                        Some(b',') => self.position += 1,
+                        _ => break,
+                    }
+                }
+                self.expect(b'}')?;
+                Ok(Json::Object(entries))
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.position;
+                while self
+                    .source
+                    .get(self.position)
+                    .is_some_and(|b| matches!(b, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+                {
+                    self.position += 1;
+                }
+                std::str::from_utf8(&self.source[start..self.position])
+                    .ok()
+                    .and_then(|number| number.parse().ok())
+                    .map(Json::Number)
+                    .ok_or_else(|| self.error("invalid number"))
+            }
+            Some(_) => Err(self.error("unexpected character")),
+        }
+    }
+
+    /// Expects to be at the opening quote
+    fn string(&mut self) -> Result<String, String> {
+        self.position += 1;
+        let mut bytes = vec![];
+        loop {
+            let Some(&byte) = self.source.get(self.position) else {
+                return Err(self.error("unterminated string"));
+            };
+            self.position += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let Some(&escape) = self.source.get(self.position) else {
+                        return Err(self.error("unterminated string"));
+                    };
+                    self.position += 1;
+                    let c = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let code = self
+                                .source
+                                .get(self.position..self.position + 4)
+                                .and_then(|hex| std::str::from_utf8(hex).ok())
+                                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                                .ok_or_else(|| self.error("invalid unicode escape"))?;
+                            self.position += 4;
+                            char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                byte => bytes.push(byte),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| self.error("invalid utf-8"))
+    }
+}
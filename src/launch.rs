@@ -0,0 +1,88 @@
+use crate::Config;
+use std::path::PathBuf;
+
+/// Command line arguments for the editor binary, anything given overrides the config
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct LaunchOptions {
+    pub scene: Option<PathBuf>,
+    pub samples: Option<u32>,
+    pub bounces: Option<u32>,
+    /// The window size, and the screenshot size
+    pub size: Option<(usize, usize)>,
+    pub screenshot: Option<PathBuf>,
+    /// Closes the editor once the screenshot is written
+    pub exit: bool,
+    pub help: bool,
+}
+
+impl LaunchOptions {
+    pub const USAGE: &'static str = "\
+Usage: main [options]
+
+Options:
+  --scene <path>         run a .script scene file on startup
+  --samples <n>          samples per pixel each frame
+  --bounces <n>          the max number of bounces
+  --size <width>x<height>  the window size, and the screenshot size
+  --screenshot <path>    render a PNG, or an HDR .exr, once the scene has loaded
+  --exit                 close once the screenshot is written
+  --help                 print this message";
+
+    /// The size of screenshots when `size` isn't given
+    pub const DEFAULT_SCREENSHOT_SIZE: (usize, usize) = (1280, 720);
+
+    /// Parses the arguments after the program name
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("'{arg}' needs a value"));
+            match arg.as_str() {
+                "--scene" => options.scene = Some(value()?.into()),
+                "--samples" => options.samples = Some(parse_count(&arg, &value()?)?),
+                "--bounces" => options.bounces = Some(parse_count(&arg, &value()?)?),
+                "--size" => {
+                    let size = value()?;
+                    options.size = Some(
+                        size.split_once('x')
+                            .and_then(|(width, height)| {
+                                Some((width.parse().ok()?, height.parse().ok()?))
+                            })
+                            .filter(|&(width, height)| width > 0 && height > 0)
+                            .ok_or_else(|| {
+                                format!("'--size' should look like 1280x720, not '{size}'")
+                            })?,
+                    );
+                }
+                "--screenshot" => options.screenshot = Some(value()?.into()),
+                "--exit" => options.exit = true,
+                "--help" | "-h" => options.help = true,
+                _ => return Err(format!("unknown argument '{arg}'")),
+            }
+        }
+        if options.exit && options.screenshot.is_none() {
+            return Err("'--exit' only works with '--screenshot'".into());
+        }
+        Ok(options)
+    }
+
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(scene) = &self.scene {
+            config.startup_scene = Some(scene.clone());
+        }
+        if let Some(samples) = self.samples {
+            config.sample_count = samples;
+        }
+        if let Some(bounces) = self.bounces {
+            config.bounce_count = bounces;
+        }
+    }
+}
+
+fn parse_count(arg: &str, value: &str) -> Result<u32, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|&count| count > 0)
+        .ok_or_else(|| format!("'{arg}' should be a whole number above 0, not '{value}'"))
+}
@@ -0,0 +1,150 @@
+/// The groups of editor controls that can be docked, see [`Layout`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Panel {
+    Scene,
+    Materials,
+    Camera,
+    RenderSettings,
+    Console,
+}
+
+impl Panel {
+    pub const ALL: [Panel; 5] = [
+        Panel::Scene,
+        Panel::Materials,
+        Panel::Camera,
+        Panel::RenderSettings,
+        Panel::Console,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Panel::Scene => "Scene",
+            Panel::Materials => "Materials",
+            Panel::Camera => "Camera",
+            Panel::RenderSettings => "Render Settings",
+            Panel::Console => "Console",
+        }
+    }
+}
+
+/// Where a [`Panel`] is shown
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Dock {
+    Left,
+    Right,
+    /// In its own window
+    Floating,
+    Hidden,
+}
+
+impl Dock {
+    pub const ALL: [Dock; 4] = [Dock::Left, Dock::Right, Dock::Floating, Dock::Hidden];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Dock::Left => "Left",
+            Dock::Right => "Right",
+            Dock::Floating => "Floating",
+            Dock::Hidden => "Hidden",
+        }
+    }
+}
+
+/// Where each panel is docked and the order they are stacked in, saved in the [`crate::Config`]
+#[derive(Clone, PartialEq, Debug)]
+pub struct Layout {
+    /// Every panel exactly once, in stacking order
+    panels: Vec<(Panel, Dock)>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            panels: vec![
+                (Panel::Camera, Dock::Left),
+                (Panel::RenderSettings, Dock::Left),
+                (Panel::Scene, Dock::Left),
+                (Panel::Materials, Dock::Right),
+                (Panel::Console, Dock::Right),
+            ],
+        }
+    }
+}
+
+impl Layout {
+    /// Panels that are missing are added after the given ones with their default dock,
+    /// unknown or repeated ones are ignored
+    pub fn new(panels: impl IntoIterator<Item = (Panel, Dock)>) -> Self {
+        let mut layout = Self { panels: vec![] };
+        for (panel, dock) in panels {
+            if layout.dock(panel).is_none() {
+                layout.panels.push((panel, dock));
+            }
+        }
+        for (panel, dock) in Self::default().panels {
+            if layout.dock(panel).is_none() {
+                layout.panels.push((panel, dock));
+            }
+        }
+        layout
+    }
+
+    pub fn panels(&self) -> &[(Panel, Dock)] {
+        &self.panels
+    }
+
+    pub fn dock(&self, panel: Panel) -> Option<Dock> {
+        self.panels
+            .iter()
+            .find(|&&(p, _)| p == panel)
+            .map(|&(_, dock)| dock)
+    }
+
+    pub fn set_dock(&mut self, panel: Panel, dock: Dock) {
+        for (p, d) in &mut self.panels {
+            if *p == panel {
+                *d = dock;
+            }
+        }
+    }
+
+    /// The panels in `dock`, top to bottom
+    pub fn docked(&self, dock: Dock) -> Vec<Panel> {
+        self.panels
+            .iter()
+            .filter(|&&(_, d)| d == dock)
+            .map(|&(panel, _)| panel)
+            .collect()
+    }
+
+    /// Swaps `panel` with the previous panel in the same dock, returns whether it moved
+    pub fn move_up(&mut self, panel: Panel) -> bool {
+        let Some(i) = self.panels.iter().position(|&(p, _)| p == panel) else {
+            return false;
+        };
+        let dock = self.panels[i].1;
+        match self.panels[..i].iter().rposition(|&(_, d)| d == dock) {
+            Some(previous) => {
+                self.panels.swap(previous, i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Swaps `panel` with the next panel in the same dock, returns whether it moved
+    pub fn move_down(&mut self, panel: Panel) -> bool {
+        let Some(i) = self.panels.iter().position(|&(p, _)| p == panel) else {
+            return false;
+        };
+        let dock = self.panels[i].1;
+        match self.panels[i + 1..].iter().position(|&(_, d)| d == dock) {
+            Some(offset) => {
+                self.panels.swap(i, i + 1 + offset);
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,31 @@
+/// A named set of emissive materials that can be dimmed or turned off together, materials
+/// join one through [`crate::GpuMaterial::light_group`]
+#[derive(Clone, PartialEq, Debug)]
+pub struct LightGroup {
+    pub name: String,
+    pub enabled: bool,
+    /// Multiplies the emission strength of every material in the group
+    pub intensity: f32,
+}
+
+impl LightGroup {
+    /// How many light groups a scene can have, the shader has a weight and an accumulated sum for each
+    pub const MAX: usize = 4;
+
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            enabled: true,
+            intensity: 1.0,
+        }
+    }
+
+    /// What the group's emission is multiplied by
+    pub fn weight(&self) -> f32 {
+        if self.enabled {
+            self.intensity
+        } else {
+            0.0
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{Scene, SceneChanges, Script};
+use std::sync::mpsc;
+
+/// How far a [`SceneLoad`] has got
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LoadStage {
+    Compiling,
+    Running,
+}
+
+impl LoadStage {
+    pub fn name(self) -> &'static str {
+        match self {
+            LoadStage::Compiling => "Compiling",
+            LoadStage::Running => "Running",
+        }
+    }
+}
+
+/// What a finished [`SceneLoad`] made
+pub struct LoadedScene {
+    /// The scene the script ran on, partly edited if it failed while running and `None` if
+    /// the loading thread panicked
+    pub scene: Option<Scene>,
+    /// The compiled script, for calling its functions later, or why it failed
+    pub script: Result<Script, String>,
+    /// Everything the script printed
+    pub output: Vec<String>,
+}
+
+enum LoadMessage {
+    Stage(LoadStage),
+    Done(Box<LoadedScene>),
+}
+
+/// Compiles and runs a scene script on a background thread so large scenes don't freeze the
+/// editor, on the web there are no threads so it all happens in [`SceneLoad::start`]
+pub struct SceneLoad {
+    receiver: mpsc::Receiver<LoadMessage>,
+    stage: LoadStage,
+}
+
+impl SceneLoad {
+    /// Starts running `source` on `scene`, which is given back in the result
+    pub fn start(source: String, scene: Scene) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let load = move || {
+            let mut scene = scene;
+            let mut output = vec![];
+            // nobody is waiting for the result if the receiver was dropped
+            _ = sender.send(LoadMessage::Stage(LoadStage::Compiling));
+            let script = Script::compile(&source).and_then(|mut script| {
+                _ = sender.send(LoadMessage::Stage(LoadStage::Running));
+                script.run(&mut scene, &mut output).map(|()| script)
+            });
+            // the whole scene is handed over, so all of it has to be uploaded again
+            scene.changes = SceneChanges::ALL;
+            _ = sender.send(LoadMessage::Done(Box::new(LoadedScene {
+                scene: Some(scene),
+                script,
+                output,
+            })));
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::Builder::new()
+            .name("Scene Load".into())
+            .spawn(load)
+            .expect("failed to spawn the scene loading thread");
+        #[cfg(target_arch = "wasm32")]
+        load();
+        Self {
+            receiver,
+            stage: LoadStage::Compiling,
+        }
+    }
+
+    pub fn stage(&self) -> LoadStage {
+        self.stage
+    }
+
+    /// Takes the messages sent so far, returns the result once the load has finished
+    pub fn poll(&mut self) -> Option<LoadedScene> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(LoadMessage::Stage(stage)) => self.stage = stage,
+                Ok(LoadMessage::Done(loaded)) => return Some(*loaded),
+                Err(mpsc::TryRecvError::Empty) => return None,
+                Err(mpsc::TryRecvError::Disconnected) => return Some(Self::panicked()),
+            }
+        }
+    }
+
+    /// Blocks until the load has finished
+    pub fn wait(self) -> LoadedScene {
+        self.receiver
+            .iter()
+            .find_map(|message| match message {
+                LoadMessage::Stage(_) => None,
+                LoadMessage::Done(loaded) => Some(*loaded),
+            })
+            .unwrap_or_else(Self::panicked)
+    }
+
+    fn panicked() -> LoadedScene {
+        LoadedScene {
+            scene: None,
+            script: Err("The scene loading thread panicked".into()),
+            output: vec![],
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::{collections::VecDeque, sync::Mutex};
+
+/// A record kept by the [`ConsoleLogger`]
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    pub level: log::Level,
+    pub target: String,
+    pub message: String,
+}
+
+/// A [`log::Log`] that keeps the most recent records for the editor's log console,
+/// they are also printed to stderr
+pub struct ConsoleLogger {
+    entries: Mutex<VecDeque<LogEntry>>,
+}
+
+static LOGGER: ConsoleLogger = ConsoleLogger::new();
+
+impl ConsoleLogger {
+    /// How many records are kept, the oldest are dropped first
+    pub const CAPACITY: usize = 1000;
+
+    pub const fn new() -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// The logger [`ConsoleLogger::install`] installs
+    pub fn global() -> &'static Self {
+        &LOGGER
+    }
+
+    /// Makes [`ConsoleLogger::global`] the logger for the `log` crate, fails if another logger was set first
+    pub fn install() -> Result<(), log::SetLoggerError> {
+        log::set_logger(&LOGGER)?;
+        log::set_max_level(log::LevelFilter::Info);
+        Ok(())
+    }
+
+    /// Oldest first
+    pub fn entries(&self) -> std::sync::MutexGuard<'_, VecDeque<LogEntry>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+
+    pub fn clear(&self) {
+        self.entries().clear();
+    }
+}
+
+impl Default for ConsoleLogger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl log::Log for ConsoleLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        let entry = LogEntry {
+            level: record.level(),
+            target: record.target().into(),
+            message: record.args().to_string(),
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        eprintln!("[{}] {}: {}", entry.level, entry.target, entry.message);
+        let mut entries = self.entries();
+        if entries.len() >= Self::CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    fn flush(&self) {}
+}
@@ -0,0 +1,184 @@
+use crate::{BlendMask, GpuMaterial, MaterialId, Scene};
+use cgmath::ElementWise;
+
+/// How many blends the shader follows from a hit before it gives up and uses the material it
+/// got to as it is, which is also what stops blends that contain themselves
+pub const MAX_BLEND_DEPTH: usize = 8;
+
+/// A material of a [`Scene`] as the tree of blends that the shader walks for every hit,
+/// the leaves are the materials whose own properties get used
+#[derive(Clone, PartialEq, Debug)]
+pub enum MaterialNode {
+    Material(MaterialId),
+    Blend {
+        material: MaterialId,
+        mask: BlendMask,
+        scale: cgmath::Vector4<f32>,
+        a: Box<MaterialNode>,
+        b: Box<MaterialNode>,
+    },
+}
+
+impl MaterialNode {
+    /// Follows the blends of `id` like the shader does, materials that don't exist are replaced
+    /// with the first one like they are when uploading, and blends past [`MAX_BLEND_DEPTH`]
+    /// are left as they are
+    pub fn new(scene: &Scene, id: MaterialId) -> Self {
+        Self::build(scene, id, 0)
+    }
+
+    fn build(scene: &Scene, id: MaterialId, depth: usize) -> Self {
+        let existing = |id: u32| {
+            let id = MaterialId(id);
+            if scene.material_index(id).is_some() {
+                id
+            } else {
+                scene.material_ids.first().copied().unwrap_or(id)
+            }
+        };
+        let Some(material) = scene.material(id) else {
+            return MaterialNode::Material(id);
+        };
+        match material.blend_mask() {
+            None => MaterialNode::Material(id),
+            Some(_) if depth >= MAX_BLEND_DEPTH => MaterialNode::Material(id),
+            Some(mask) => MaterialNode::Blend {
+                material: id,
+                mask,
+                scale: material.blend_scale,
+                a: Box::new(Self::build(scene, existing(material.blend_a), depth + 1)),
+                b: Box::new(Self::build(scene, existing(material.blend_b), depth + 1)),
+            },
+        }
+    }
+
+    /// The material this node is for
+    pub fn id(&self) -> MaterialId {
+        match *self {
+            MaterialNode::Material(id) | MaterialNode::Blend { material: id, .. } => id,
+        }
+    }
+
+    /// Every material a hit can end up using, in the order they are first reached
+    pub fn leaves(&self) -> Vec<MaterialId> {
+        let mut leaves = vec![];
+        self.collect_leaves(&mut leaves);
+        leaves
+    }
+
+    fn collect_leaves(&self, leaves: &mut Vec<MaterialId>) {
+        match self {
+            MaterialNode::Material(id) => {
+                if !leaves.contains(id) {
+                    leaves.push(*id);
+                }
+            }
+            MaterialNode::Blend { a, b, .. } => {
+                a.collect_leaves(leaves);
+                b.collect_leaves(leaves);
+            }
+        }
+    }
+
+    /// If the tree was cut off at [`MAX_BLEND_DEPTH`], which happens when a blend contains itself
+    pub fn is_truncated(&self, scene: &Scene) -> bool {
+        match self {
+            MaterialNode::Material(id) => scene
+                .material(*id)
+                .is_some_and(|material| material.blend_mask().is_some()),
+            MaterialNode::Blend { a, b, .. } => a.is_truncated(scene) || b.is_truncated(scene),
+        }
+    }
+
+    /// The material a hit at `position` uses, `sample` is a random number in [0, 1) that is
+    /// rescaled at every blend so a single one is enough for the whole tree
+    pub fn resolve(&self, position: cgmath::Vector4<f32>, sample: f32) -> MaterialId {
+        let mut node = self;
+        let mut sample = sample;
+        loop {
+            match node {
+                MaterialNode::Material(id) => return *id,
+                MaterialNode::Blend {
+                    mask, scale, a, b, ..
+                } => {
+                    let mask = mask.value(position.mul_element_wise(*scale));
+                    if sample < mask {
+                        sample /= mask;
+                        node = b;
+                    } else {
+                        sample = (sample - mask) / (1.0 - mask);
+                        node = a;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl BlendMask {
+    /// The chance of using `blend_b` at an already scaled position, matching `blend_mask` in
+    /// `blend.wgsl`
+    pub fn value(self, scaled: cgmath::Vector4<f32>) -> f32 {
+        match self {
+            BlendMask::None => 0.0,
+            BlendMask::Noise => 0.5 + 0.5 * value_noise(scaled),
+            BlendMask::Gradient => (0.5 + scaled.w).clamp(0.0, 1.0),
+            BlendMask::Checker => {
+                let cell = scaled.map(|x| x.floor() as i32);
+                ((cell.x + cell.y + cell.z + cell.w) & 1) as f32
+            }
+        }
+    }
+}
+
+impl GpuMaterial {
+    pub fn blend_mask(&self) -> Option<BlendMask> {
+        BlendMask::from_u32(self.blend_mask).filter(|&mask| mask != BlendMask::None)
+    }
+
+    /// The materials this blends, `None` if it isn't a blend
+    pub fn blended(&self) -> Option<[MaterialId; 2]> {
+        self.blend_mask()
+            .map(|_| [MaterialId(self.blend_a), MaterialId(self.blend_b)])
+    }
+}
+
+fn lattice_hash(x: u32) -> u32 {
+    let state = x.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+pub(crate) fn lattice_value(point: cgmath::Vector4<i32>) -> f32 {
+    let h = lattice_hash(
+        point.x as u32
+            ^ lattice_hash(
+                point.y as u32 ^ lattice_hash(point.z as u32 ^ lattice_hash(point.w as u32)),
+            ),
+    );
+    (h >> 8) as f32 / 8388607.5 - 1.0
+}
+
+/// 4D value noise in [-1, 1], smoothstep interpolated between the 16 corners of each cell
+fn value_noise(p: cgmath::Vector4<f32>) -> f32 {
+    let cell = p.map(f32::floor);
+    let f = p - cell;
+    let t = f.map(|f| f * f * (3.0 - 2.0 * f));
+    let mut value = 0.0;
+    for i in 0..16 {
+        let corner = cgmath::vec4(i & 1, i >> 1 & 1, i >> 2 & 1, i >> 3 & 1);
+        let weight = |axis: usize| {
+            if corner[axis] == 1 {
+                t[axis]
+            } else {
+                1.0 - t[axis]
+            }
+        };
+        value += lattice_value(cell.map(|x| x as i32) + corner)
+            * weight(0)
+            * weight(1)
+            * weight(2)
+            * weight(3);
+    }
+    value
+}
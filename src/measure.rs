@@ -0,0 +1,74 @@
+use crate::{ObjectRef, Scene};
+use cgmath::prelude::*;
+
+/// One end of a measurement
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MeasurePoint {
+    /// Follows the object as it is edited, hyper planes also give their normal as a direction
+    Object(ObjectRef),
+    /// A fixed point, usually clicked on a surface, with the surface normal as its direction
+    Point {
+        position: cgmath::Vector4<f32>,
+        normal: cgmath::Vector4<f32>,
+    },
+}
+
+impl MeasurePoint {
+    /// The position and direction of the point, `None` if its object has been removed
+    pub fn resolve(
+        self,
+        scene: &Scene,
+    ) -> Option<(cgmath::Vector4<f32>, Option<cgmath::Vector4<f32>>)> {
+        match self {
+            MeasurePoint::Object(object) => {
+                let direction = match object {
+                    ObjectRef::HyperSphere(_) => None,
+                    ObjectRef::HyperPlane(i) => Some(scene.hyper_plane(i)?.normal),
+                };
+                Some((scene.object_position(object)?, direction))
+            }
+            MeasurePoint::Point { position, normal } => Some((position, Some(normal))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Measurement {
+    /// From the first point to the second
+    pub delta: cgmath::Vector4<f32>,
+    pub distance: f32,
+    /// Between the directions from `viewpoint` to each point
+    pub view_angle: f32,
+    /// Between the directions of the points, if both have one
+    pub direction_angle: Option<f32>,
+}
+
+impl Measurement {
+    pub fn between(
+        scene: &Scene,
+        a: MeasurePoint,
+        b: MeasurePoint,
+        viewpoint: cgmath::Vector4<f32>,
+    ) -> Option<Self> {
+        let (a, a_direction) = a.resolve(scene)?;
+        let (b, b_direction) = b.resolve(scene)?;
+        let delta = b - a;
+        Some(Self {
+            delta,
+            distance: delta.magnitude(),
+            view_angle: angle_between(a - viewpoint, b - viewpoint),
+            direction_angle: a_direction
+                .zip(b_direction)
+                .map(|(a, b)| angle_between(a, b)),
+        })
+    }
+}
+
+/// In radians, zero if either vector is zero
+pub fn angle_between(a: cgmath::Vector4<f32>, b: cgmath::Vector4<f32>) -> f32 {
+    let lengths = a.magnitude() * b.magnitude();
+    if lengths == 0.0 {
+        return 0.0;
+    }
+    (a.dot(b) / lengths).clamp(-1.0, 1.0).acos()
+}
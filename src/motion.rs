@@ -0,0 +1,83 @@
+use crate::Camera;
+use cgmath::InnerSpace;
+
+/// Smooths the fly controls so the camera speeds up, slows down and turns gradually, every
+/// step depends on the time passed rather than the number of frames
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CameraMotion {
+    /// Seconds it takes to go from standing still to full speed, and back, 0 is instant
+    pub acceleration_time: f32,
+    /// Seconds it takes for most of a turn to be applied, 0 turns instantly
+    pub damping: f32,
+    velocity: cgmath::Vector4<f32>,
+    /// Pitch, yaw, weird pitch and weird yaw that were asked for but haven't been applied yet
+    pending_rotation: cgmath::Vector4<f32>,
+}
+
+impl Default for CameraMotion {
+    fn default() -> Self {
+        Self {
+            acceleration_time: 0.15,
+            damping: 0.0,
+            velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            pending_rotation: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        }
+    }
+}
+
+impl CameraMotion {
+    /// The velocity the camera is currently moving with
+    pub fn velocity(&self) -> cgmath::Vector4<f32> {
+        self.velocity
+    }
+
+    /// Accelerates towards `target_velocity` and returns how far the camera moves in `ts`
+    /// seconds, `speed` is the full speed so slowing down takes as long as speeding up
+    pub fn movement(
+        &mut self,
+        target_velocity: cgmath::Vector4<f32>,
+        speed: f32,
+        ts: f32,
+    ) -> cgmath::Vector4<f32> {
+        if self.acceleration_time <= 0.0 {
+            self.velocity = target_velocity;
+            return target_velocity * ts;
+        }
+        let start = self.velocity;
+        let max_change = speed / self.acceleration_time * ts;
+        let change = target_velocity - self.velocity;
+        let length = change.magnitude();
+        self.velocity = if length <= max_change {
+            target_velocity
+        } else {
+            self.velocity + change * (max_change / length)
+        };
+        // the average velocity over the step, so the distance doesn't depend on the frame rate
+        (start + self.velocity) * (0.5 * ts)
+    }
+
+    /// Queues a turn, it is applied to the camera over the next frames by [`Self::rotate`]
+    pub fn add_rotation(&mut self, pitch: f32, yaw: f32, weird_pitch: f32, weird_yaw: f32) {
+        self.pending_rotation += cgmath::vec4(pitch, yaw, weird_pitch, weird_yaw);
+    }
+
+    /// Turns `camera` by the part of the queued rotation that decays in `ts` seconds
+    pub fn rotate(&mut self, camera: &mut Camera, ts: f32) {
+        let applied = if self.damping <= 0.0 {
+            self.pending_rotation
+        } else {
+            self.pending_rotation * (1.0 - (-ts / self.damping).exp())
+        };
+        self.pending_rotation -= applied;
+        camera.pitch += applied.x;
+        camera.yaw += applied.y;
+        camera.weird_pitch += applied.z;
+        camera.weird_yaw += applied.w;
+    }
+
+    /// Drops any velocity and queued rotation, for when the camera is moved some other way
+    pub fn stop(&mut self) {
+        self.velocity = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+        self.pending_rotation = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+    }
+}
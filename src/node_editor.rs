@@ -0,0 +1,250 @@
+use crate::{NodeGraph, NodeId, NodeKind};
+use eframe::egui;
+
+const NODE_WIDTH: f32 = 170.0;
+const HEADER_HEIGHT: f32 = 22.0;
+const ROW_HEIGHT: f32 = 20.0;
+const PORT_RADIUS: f32 = 5.0;
+
+/// What the editor remembers between frames, kept in egui's memory
+#[derive(Clone, Copy, Default)]
+struct CanvasState {
+    pan: egui::Vec2,
+    /// The node whose output is being dragged to an input
+    connecting: Option<NodeId>,
+    /// Where the canvas was right clicked, new nodes go there
+    menu_position: egui::Pos2,
+}
+
+fn node_height(kind: NodeKind) -> f32 {
+    let value_rows = match kind.value_label() {
+        Some(_) => 2,
+        None => 0,
+    };
+    HEADER_HEIGHT + ROW_HEIGHT * (kind.inputs().len() + value_rows) as f32 + 6.0
+}
+
+fn input_position(rect: egui::Rect, input: usize) -> egui::Pos2 {
+    egui::pos2(
+        rect.left(),
+        rect.top() + HEADER_HEIGHT + ROW_HEIGHT * (input as f32 + 0.5),
+    )
+}
+
+fn output_position(rect: egui::Rect) -> egui::Pos2 {
+    egui::pos2(rect.right(), rect.top() + HEADER_HEIGHT * 0.5)
+}
+
+fn connection(from: egui::Pos2, to: egui::Pos2, stroke: egui::Stroke) -> egui::Shape {
+    let bend = egui::vec2(((to.x - from.x).abs() * 0.5).max(30.0), 0.0);
+    egui::epaint::CubicBezierShape::from_points_stroke(
+        [from, from + bend, to - bend, to],
+        false,
+        egui::Color32::TRANSPARENT,
+        stroke,
+    )
+    .into()
+}
+
+/// A canvas for editing `graph`, nodes are moved by their header and connected by dragging from
+/// the output on the right of a node to an input on the left of another. Dragging an input that
+/// is already connected picks the connection back up, right clicking adds nodes and dragging the
+/// background pans. Returns whether anything that changes the compiled program was edited
+pub fn edit_node_graph(ui: &mut egui::Ui, graph: &mut NodeGraph) -> bool {
+    let id = ui.make_persistent_id("Node Graph");
+    let mut state = ui
+        .data_mut(|data| data.get_temp::<CanvasState>(id))
+        .unwrap_or_default();
+    let size = ui.available_size().max(egui::vec2(400.0, 300.0));
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let mut canvas = ui.child_ui(rect, egui::Layout::top_down(egui::Align::Min));
+    canvas.set_clip_rect(rect.intersect(ui.clip_rect()));
+    let painter = canvas.painter().clone();
+    painter.rect_filled(rect, 0.0, egui::Color32::from_gray(24));
+
+    let origin = rect.min + state.pan;
+    let node_rect = |kind: NodeKind, position: [f32; 2]| {
+        egui::Rect::from_min_size(
+            origin + egui::vec2(position[0], position[1]),
+            egui::vec2(NODE_WIDTH, node_height(kind)),
+        )
+    };
+    let stroke = canvas.visuals().widgets.active.fg_stroke;
+    let pointer = canvas.ctx().pointer_latest_pos();
+
+    let mut changed = false;
+    let mut removed = None;
+    // ports are interacted with before the headers and the background, so they get the drags
+    let mut dropped_on = None;
+    for node in &mut graph.nodes {
+        let node_rect = node_rect(node.kind, node.position);
+        for input in 0..node.kind.inputs().len() {
+            let port = egui::Rect::from_center_size(
+                input_position(node_rect, input),
+                egui::Vec2::splat(PORT_RADIUS * 3.0),
+            );
+            let response = canvas.interact(port, id.with((node.id, input)), egui::Sense::drag());
+            if response.drag_started() {
+                if let Some(from) = node.inputs[input].take() {
+                    state.connecting = Some(from);
+                    changed = true;
+                }
+            }
+            if pointer.is_some_and(|pointer| port.contains(pointer)) {
+                dropped_on = Some((node.id, input));
+            }
+        }
+        if node.kind != NodeKind::Output {
+            let port = egui::Rect::from_center_size(
+                output_position(node_rect),
+                egui::Vec2::splat(PORT_RADIUS * 3.0),
+            );
+            let response = canvas.interact(port, id.with((node.id, "Output")), egui::Sense::drag());
+            if response.drag_started() {
+                state.connecting = Some(node.id);
+            }
+        }
+    }
+    if let Some(from) = state.connecting {
+        if canvas.input(|input| input.pointer.any_released()) {
+            if let Some((to, input)) = dropped_on {
+                graph.connect(Some(from), to, input);
+                changed = true;
+            }
+            state.connecting = None;
+        }
+    }
+
+    for node in &graph.nodes {
+        let to = node_rect(node.kind, node.position);
+        for (input, from) in node
+            .inputs
+            .iter()
+            .take(node.kind.inputs().len())
+            .enumerate()
+        {
+            if let Some(from) = from.and_then(|from| graph.node(from)) {
+                let from = node_rect(from.kind, from.position);
+                painter.add(connection(
+                    output_position(from),
+                    input_position(to, input),
+                    stroke,
+                ));
+            }
+        }
+    }
+    if let (Some(from), Some(pointer)) =
+        (state.connecting.and_then(|from| graph.node(from)), pointer)
+    {
+        let from = node_rect(from.kind, from.position);
+        painter.add(connection(output_position(from), pointer, stroke));
+    }
+
+    for node in &mut graph.nodes {
+        let node_rect = node_rect(node.kind, node.position);
+        let header =
+            egui::Rect::from_min_size(node_rect.min, egui::vec2(NODE_WIDTH, HEADER_HEIGHT));
+        let response = canvas.interact(header, id.with(node.id), egui::Sense::drag());
+        if response.dragged() {
+            node.position[0] += response.drag_delta().x;
+            node.position[1] += response.drag_delta().y;
+        }
+
+        let visuals = canvas.visuals();
+        painter.rect(node_rect, 4.0, visuals.window_fill, visuals.window_stroke);
+        painter.rect_filled(header, 4.0, visuals.widgets.inactive.bg_fill);
+        painter.text(
+            header.left_center() + egui::vec2(6.0, 0.0),
+            egui::Align2::LEFT_CENTER,
+            node.kind.name(),
+            egui::FontId::proportional(14.0),
+            visuals.strong_text_color(),
+        );
+        if node.kind != NodeKind::Output {
+            painter.circle_filled(output_position(node_rect), PORT_RADIUS, stroke.color);
+        }
+
+        let mut body = canvas.child_ui(
+            node_rect.shrink2(egui::vec2(PORT_RADIUS * 2.0, 0.0)),
+            egui::Layout::top_down(egui::Align::Min),
+        );
+        body.horizontal(|ui| {
+            ui.set_height(HEADER_HEIGHT);
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if node.kind != NodeKind::Output && ui.small_button("x").clicked() {
+                    removed = Some(node.id);
+                }
+            });
+        });
+        body.spacing_mut().item_spacing.y = 0.0;
+        for (input, name) in node.kind.inputs().iter().enumerate() {
+            let position = input_position(node_rect, input);
+            if node.inputs[input].is_some() {
+                painter.circle_filled(position, PORT_RADIUS, stroke.color);
+            } else {
+                painter.circle_stroke(position, PORT_RADIUS, stroke);
+            }
+            body.horizontal(|ui| {
+                ui.set_height(ROW_HEIGHT);
+                ui.label(*name);
+            });
+        }
+        if let Some(label) = node.kind.value_label() {
+            body.horizontal(|ui| {
+                ui.set_height(ROW_HEIGHT);
+                ui.label(label);
+            });
+            body.horizontal(|ui| {
+                ui.set_height(ROW_HEIGHT);
+                ui.spacing_mut().interact_size.x = 30.0;
+                changed |= match node.kind {
+                    NodeKind::Value | NodeKind::Fresnel => ui
+                        .add(egui::DragValue::new(&mut node.value.x).speed(0.01))
+                        .changed(),
+                    NodeKind::Color => {
+                        let mut color = [node.value.x, node.value.y, node.value.z];
+                        let changed = ui.color_edit_button_rgb(&mut color).changed();
+                        [node.value.x, node.value.y, node.value.z] = color;
+                        changed
+                    }
+                    _ => {
+                        let mut changed = false;
+                        for i in 0..4 {
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut node.value[i]).speed(0.01))
+                                .changed();
+                        }
+                        changed
+                    }
+                };
+            });
+        }
+    }
+    if let Some(node) = removed {
+        graph.remove_node(node);
+        changed = true;
+    }
+
+    let background = canvas.interact(rect, id.with("Background"), egui::Sense::click_and_drag());
+    if background.dragged() {
+        state.pan += background.drag_delta();
+    }
+    if background.secondary_clicked() {
+        if let Some(position) = background.interact_pointer_pos() {
+            state.menu_position = position;
+        }
+    }
+    background.context_menu(|ui| {
+        ui.label("Add Node");
+        for kind in NodeKind::ALL {
+            if kind != NodeKind::Output && ui.button(kind.name()).clicked() {
+                let position = state.menu_position - origin;
+                graph.add_node(kind, [position.x, position.y]);
+                ui.close_menu();
+            }
+        }
+    });
+
+    ui.data_mut(|data| data.insert_temp(id, state));
+    changed
+}
@@ -0,0 +1,416 @@
+use crate::{GpuMaterial, GpuNodeInstruction, MaterialId, Scene, NO_REGISTER};
+use cgmath::{ElementWise, InnerSpace};
+
+/// The most instructions a compiled [`NodeGraph`] can have, each node is one instruction and
+/// the shader keeps a register for every one of them
+pub const MAX_NODE_INSTRUCTIONS: usize = 32;
+
+/// What a [`Node`] computes, every value is a `vec4` and numbers are repeated across it.
+/// The order must match the cases of `run_node_program` in `nodes.wgsl`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NodeKind {
+    /// The properties of the material that the graph replaces, any that aren't connected are
+    /// left as they are
+    Output,
+    /// The first component of the node's value
+    Value,
+    /// The first three components of the node's value
+    Color,
+    /// Where the surface was hit, the same position emission patterns use
+    Position,
+    Normal,
+    /// Smooth 4D value noise between 0 and 1 of the position, scaled by the node's value
+    Noise,
+    /// 0 or 1 in alternating cells of the position scaled by the node's value, a zero
+    /// component ignores that axis
+    Checker,
+    /// How much light reflects off the surface at the angle it was hit from, for the index of
+    /// refraction in the node's value
+    Fresnel,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Minimum,
+    Maximum,
+    Power,
+    /// From `A` to `B` by the factor
+    Mix,
+}
+
+impl NodeKind {
+    pub const ALL: [NodeKind; 16] = [
+        NodeKind::Output,
+        NodeKind::Value,
+        NodeKind::Color,
+        NodeKind::Position,
+        NodeKind::Normal,
+        NodeKind::Noise,
+        NodeKind::Checker,
+        NodeKind::Fresnel,
+        NodeKind::Add,
+        NodeKind::Subtract,
+        NodeKind::Multiply,
+        NodeKind::Divide,
+        NodeKind::Minimum,
+        NodeKind::Maximum,
+        NodeKind::Power,
+        NodeKind::Mix,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NodeKind::Output => "Output",
+            NodeKind::Value => "Value",
+            NodeKind::Color => "Color",
+            NodeKind::Position => "Position",
+            NodeKind::Normal => "Normal",
+            NodeKind::Noise => "Noise",
+            NodeKind::Checker => "Checker",
+            NodeKind::Fresnel => "Fresnel",
+            NodeKind::Add => "Add",
+            NodeKind::Subtract => "Subtract",
+            NodeKind::Multiply => "Multiply",
+            NodeKind::Divide => "Divide",
+            NodeKind::Minimum => "Minimum",
+            NodeKind::Maximum => "Maximum",
+            NodeKind::Power => "Power",
+            NodeKind::Mix => "Mix",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The names of the inputs, at most 3
+    pub fn inputs(self) -> &'static [&'static str] {
+        match self {
+            NodeKind::Output => &["Base Color", "Emissive Color"],
+            NodeKind::Value | NodeKind::Color | NodeKind::Position | NodeKind::Normal => &[],
+            NodeKind::Noise | NodeKind::Checker => &["Position"],
+            NodeKind::Fresnel => &[],
+            NodeKind::Add
+            | NodeKind::Subtract
+            | NodeKind::Multiply
+            | NodeKind::Divide
+            | NodeKind::Minimum
+            | NodeKind::Maximum
+            | NodeKind::Power => &["A", "B"],
+            NodeKind::Mix => &["A", "B", "Factor"],
+        }
+    }
+
+    /// What the node's value is used for, unconnected inputs also use it
+    pub fn value_label(self) -> Option<&'static str> {
+        match self {
+            NodeKind::Value => Some("Value"),
+            NodeKind::Color => Some("Color"),
+            NodeKind::Noise | NodeKind::Checker => Some("Scale"),
+            NodeKind::Fresnel => Some("IOR"),
+            NodeKind::Add
+            | NodeKind::Subtract
+            | NodeKind::Multiply
+            | NodeKind::Divide
+            | NodeKind::Minimum
+            | NodeKind::Maximum
+            | NodeKind::Power
+            | NodeKind::Mix => Some("Unconnected"),
+            NodeKind::Output | NodeKind::Position | NodeKind::Normal => None,
+        }
+    }
+
+    /// The value new nodes start with
+    pub fn default_value(self) -> cgmath::Vector4<f32> {
+        match self {
+            NodeKind::Value | NodeKind::Multiply | NodeKind::Divide | NodeKind::Power => {
+                cgmath::vec4(1.0, 1.0, 1.0, 1.0)
+            }
+            NodeKind::Color => cgmath::vec4(0.9, 0.9, 0.9, 1.0),
+            NodeKind::Noise | NodeKind::Checker => cgmath::vec4(4.0, 4.0, 4.0, 4.0),
+            NodeKind::Fresnel => cgmath::vec4(1.5, 0.0, 0.0, 0.0),
+            NodeKind::Mix => cgmath::vec4(0.5, 0.5, 0.5, 0.5),
+            _ => cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        }
+    }
+}
+
+/// A stable handle to a node of a [`NodeGraph`]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct NodeId(pub u32);
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Node {
+    pub id: NodeId,
+    pub kind: NodeKind,
+    /// The parameter of the node, and the value of inputs that aren't connected
+    pub value: cgmath::Vector4<f32>,
+    /// Which node each input is connected to, only the first `kind.inputs().len()` are used
+    pub inputs: [Option<NodeId>; 3],
+    /// Where the node is drawn in the editor
+    pub position: [f32; 2],
+}
+
+/// Why a [`NodeGraph`] can't be compiled
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NodeGraphError {
+    /// A node's inputs lead back to itself
+    Cycle,
+    /// More than [`MAX_NODE_INSTRUCTIONS`] nodes lead into the output
+    TooManyNodes,
+}
+
+impl NodeGraphError {
+    pub fn message(self) -> &'static str {
+        match self {
+            NodeGraphError::Cycle => "The nodes are connected in a loop, they are ignored",
+            NodeGraphError::TooManyNodes => {
+                "More than 32 nodes lead into the output, they are ignored"
+            }
+        }
+    }
+}
+
+/// A material built from nodes, it replaces some properties of the [`GpuMaterial`] it
+/// belongs to for every hit. It is compiled into a list of [`GpuNodeInstruction`]s that
+/// the shader runs, and the first node is always the [`NodeKind::Output`]
+#[derive(Clone, PartialEq, Debug)]
+pub struct NodeGraph {
+    pub nodes: Vec<Node>,
+    next_node_id: u32,
+}
+
+impl Default for NodeGraph {
+    fn default() -> Self {
+        Self {
+            nodes: vec![Node {
+                id: NodeId(0),
+                kind: NodeKind::Output,
+                value: NodeKind::Output.default_value(),
+                inputs: [None; 3],
+                position: [400.0, 100.0],
+            }],
+            next_node_id: 1,
+        }
+    }
+}
+
+impl NodeGraph {
+    /// Adding an [`NodeKind::Output`] returns the existing one instead, moved to `position`
+    pub fn add_node(&mut self, kind: NodeKind, position: [f32; 2]) -> NodeId {
+        if kind == NodeKind::Output {
+            self.nodes[0].position = position;
+            return self.nodes[0].id;
+        }
+        let id = NodeId(self.next_node_id);
+        self.next_node_id += 1;
+        self.nodes.push(Node {
+            id,
+            kind,
+            value: kind.default_value(),
+            inputs: [None; 3],
+            position,
+        });
+        id
+    }
+
+    /// Removes the node and every connection to it, the output can't be removed
+    pub fn remove_node(&mut self, id: NodeId) {
+        if id == self.nodes[0].id {
+            return;
+        }
+        self.nodes.retain(|node| node.id != id);
+        for node in &mut self.nodes {
+            for input in &mut node.inputs {
+                if *input == Some(id) {
+                    *input = None;
+                }
+            }
+        }
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        self.nodes.iter_mut().find(|node| node.id == id)
+    }
+
+    /// Connects the result of `from` to input `input` of `to`, or disconnects it with `None`
+    pub fn connect(&mut self, from: Option<NodeId>, to: NodeId, input: usize) {
+        if let Some(node) = self
+            .node_mut(to)
+            .filter(|node| input < node.kind.inputs().len())
+        {
+            node.inputs[input] = from;
+        }
+    }
+
+    /// The program the shader runs for the graph, only the nodes that lead into the output
+    /// are included and each one comes after the nodes it reads
+    pub fn compile(&self) -> Result<Vec<GpuNodeInstruction>, NodeGraphError> {
+        let mut order = vec![];
+        let mut visiting = vec![];
+        self.visit(self.nodes[0].id, &mut order, &mut visiting)?;
+        if order.len() > MAX_NODE_INSTRUCTIONS {
+            return Err(NodeGraphError::TooManyNodes);
+        }
+        let register = |id: Option<NodeId>| {
+            id.and_then(|id| order.iter().position(|&node| node == id))
+                .map_or(NO_REGISTER, |register| register as u32)
+        };
+        Ok(order
+            .iter()
+            .map(|&id| {
+                let node = self.node(id).unwrap();
+                GpuNodeInstruction {
+                    kind: node.kind as u32,
+                    a: register(node.inputs[0]),
+                    b: register(node.inputs[1]),
+                    c: register(node.inputs[2]),
+                    value: node.value,
+                }
+            })
+            .collect())
+    }
+
+    fn visit(
+        &self,
+        id: NodeId,
+        order: &mut Vec<NodeId>,
+        visiting: &mut Vec<NodeId>,
+    ) -> Result<(), NodeGraphError> {
+        if order.contains(&id) {
+            return Ok(());
+        }
+        if visiting.contains(&id) {
+            return Err(NodeGraphError::Cycle);
+        }
+        let Some(node) = self.node(id) else {
+            return Ok(());
+        };
+        visiting.push(id);
+        for input in node.inputs.iter().take(node.kind.inputs().len()).flatten() {
+            self.visit(*input, order, visiting)?;
+        }
+        visiting.pop();
+        if self.node(id).is_some() {
+            order.push(id);
+        }
+        Ok(())
+    }
+}
+
+impl GpuNodeInstruction {
+    /// Runs a program from [`NodeGraph::compile`] on the CPU like `run_node_program` in
+    /// `nodes.wgsl` does. `position` is the hit's pattern position, the normal faces the ray and
+    /// `incoming` is the direction the ray arrived in. The colors are clamped between 0 and 1
+    pub fn run(
+        program: &[GpuNodeInstruction],
+        material: &mut GpuMaterial,
+        position: cgmath::Vector4<f32>,
+        normal: cgmath::Vector4<f32>,
+        incoming: cgmath::Vector4<f32>,
+    ) {
+        let mut registers = [cgmath::vec4(0.0, 0.0, 0.0, 0.0); MAX_NODE_INSTRUCTIONS];
+        for (i, instruction) in program.iter().take(MAX_NODE_INSTRUCTIONS).enumerate() {
+            let input = |register: u32| {
+                registers
+                    .get(register as usize)
+                    .copied()
+                    .filter(|_| (register as usize) < i)
+                    .unwrap_or(instruction.value)
+            };
+            let (a, b, c) = (
+                input(instruction.a),
+                input(instruction.b),
+                input(instruction.c),
+            );
+            let splat = |x: f32| cgmath::vec4(x, x, x, x);
+            registers[i] = match NodeKind::ALL.get(instruction.kind as usize) {
+                Some(NodeKind::Output) => {
+                    if instruction.a != NO_REGISTER {
+                        material.base_color = a.truncate();
+                    }
+                    if instruction.b != NO_REGISTER {
+                        material.emissive_color = b.truncate();
+                    }
+                    splat(0.0)
+                }
+                Some(NodeKind::Value) => splat(instruction.value.x),
+                Some(NodeKind::Color) => instruction.value.truncate().extend(1.0),
+                Some(NodeKind::Position) => position,
+                Some(NodeKind::Normal) => normal,
+                Some(NodeKind::Noise) => {
+                    let position = if instruction.a == NO_REGISTER {
+                        position
+                    } else {
+                        a
+                    };
+                    splat(
+                        crate::BlendMask::Noise.value(position.mul_element_wise(instruction.value)),
+                    )
+                }
+                Some(NodeKind::Checker) => {
+                    let position = if instruction.a == NO_REGISTER {
+                        position
+                    } else {
+                        a
+                    };
+                    splat(
+                        crate::BlendMask::Checker
+                            .value(position.mul_element_wise(instruction.value)),
+                    )
+                }
+                Some(NodeKind::Fresnel) => {
+                    let r0 = ((1.0 - instruction.value.x) / (1.0 + instruction.value.x)).powi(2);
+                    let cos = normal.dot(-incoming).abs().min(1.0);
+                    splat(r0 + (1.0 - r0) * (1.0 - cos).powi(5))
+                }
+                Some(NodeKind::Add) => a + b,
+                Some(NodeKind::Subtract) => a - b,
+                Some(NodeKind::Multiply) => a.mul_element_wise(b),
+                Some(NodeKind::Divide) => a.div_element_wise(b),
+                Some(NodeKind::Minimum) => a.zip(b, f32::min),
+                Some(NodeKind::Maximum) => a.zip(b, f32::max),
+                Some(NodeKind::Power) => a.zip(b, f32::powf),
+                Some(NodeKind::Mix) => a + (b - a).mul_element_wise(c),
+                None => splat(0.0),
+            };
+        }
+        // a division by zero or a negative power can't reach the rest of the path
+        let unit = |x: f32| if x > 0.0 { x.min(1.0) } else { 0.0 };
+        material.base_color = material.base_color.map(unit);
+        material.emissive_color = material.emissive_color.map(unit);
+    }
+}
+
+impl Scene {
+    /// The materials as they are uploaded, [sanitized](Scene::sanitized_materials) and pointing
+    /// at their compiled node graphs, along with every program one after the other. Graphs
+    /// that don't compile are left out
+    pub fn compiled_materials(&self) -> (Vec<GpuMaterial>, Vec<GpuNodeInstruction>) {
+        let mut materials = self.sanitized_materials();
+        let mut programs = vec![];
+        for (material, id) in materials.iter_mut().zip(&self.material_ids) {
+            let program = self
+                .node_graphs
+                .get(id)
+                .and_then(|graph| graph.compile().ok())
+                .unwrap_or_default();
+            material.node_program = programs.len() as u32;
+            material.node_program_length = program.len() as u32;
+            programs.extend(program);
+        }
+        (materials, programs)
+    }
+
+    /// The graph of `id`, adding an empty one if it doesn't have one
+    pub fn node_graph_mut(&mut self, id: MaterialId) -> &mut NodeGraph {
+        self.changes.materials = true;
+        self.node_graphs.entry(id).or_default()
+    }
+}
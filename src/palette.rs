@@ -0,0 +1,260 @@
+use crate::{
+    json::Json, BiVector4, BlendMask, Camera, EmissionPattern, Environment, GpuError,
+    GpuHyperPlane, GpuHyperSphere, GpuMaterial, RenderPath, Renderer, Scene,
+};
+
+/// Named materials that can be shared between scenes as a JSON list
+#[derive(Clone, Default)]
+pub struct Palette {
+    pub materials: Vec<(String, GpuMaterial)>,
+}
+
+impl Palette {
+    pub fn from_scene(scene: &Scene) -> Self {
+        Self {
+            materials: scene
+                .material_names
+                .iter()
+                .cloned()
+                .zip(scene.materials.iter().map(|&material| GpuMaterial {
+                    // light groups belong to the scene, not the material, and so do the
+                    // materials a blend refers to
+                    light_group: 0,
+                    blend_mask: BlendMask::None as _,
+                    ..material
+                }))
+                .collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let vec3 = |v: cgmath::Vector3<f32>| {
+            Json::Array([v.x, v.y, v.z].map(|x| Json::Number(x as _)).to_vec())
+        };
+        let vec4 = |v: cgmath::Vector4<f32>| {
+            Json::Array([v.x, v.y, v.z, v.w].map(|x| Json::Number(x as _)).to_vec())
+        };
+        Json::Array(
+            self.materials
+                .iter()
+                .map(|(name, material)| {
+                    let pattern = EmissionPattern::from_u32(material.emission_pattern)
+                        .unwrap_or(EmissionPattern::Solid);
+                    Json::Object(vec![
+                        ("name".into(), Json::String(name.clone())),
+                        ("base_color".into(), vec3(material.base_color)),
+                        ("emissive_color".into(), vec3(material.emissive_color)),
+                        (
+                            "emission_strength".into(),
+                            Json::Number(material.emission_strength as _),
+                        ),
+                        (
+                            "bump_strength".into(),
+                            Json::Number(material.bump_strength as _),
+                        ),
+                        ("bump_scale".into(), Json::Number(material.bump_scale as _)),
+                        (
+                            "emission_pattern".into(),
+                            Json::String(pattern.name().into()),
+                        ),
+                        (
+                            "emission_pattern_scale".into(),
+                            vec4(material.emission_pattern_scale),
+                        ),
+                        (
+                            "transmission".into(),
+                            Json::Number(material.transmission as _),
+                        ),
+                        ("ior".into(), Json::Number(material.ior as _)),
+                        ("dispersion".into(), Json::Number(material.dispersion as _)),
+                        ("priority".into(), Json::Number(material.priority as _)),
+                        ("absorption_color".into(), vec3(material.absorption_color)),
+                        (
+                            "absorption_density".into(),
+                            Json::Number(material.absorption_density as _),
+                        ),
+                        ("clearcoat".into(), Json::Number(material.clearcoat as _)),
+                        (
+                            "clearcoat_roughness".into(),
+                            Json::Number(material.clearcoat_roughness as _),
+                        ),
+                        ("sheen".into(), Json::Number(material.sheen as _)),
+                        ("sheen_color".into(), vec3(material.sheen_color)),
+                    ])
+                })
+                .collect(),
+        )
+        .to_string_pretty()
+    }
+
+    /// Fields that are missing are left at their defaults, anything that is there has to be valid
+    pub fn from_json(source: &str) -> Result<Self, String> {
+        let json = Json::parse(source)?;
+        let entries = json
+            .as_array()
+            .ok_or("a palette should be a list of materials")?;
+
+        let mut materials = vec![];
+        for (i, entry) in entries.iter().enumerate() {
+            let error = |message: &str| format!("material {i}: {message}");
+            let Json::Object(_) = entry else {
+                return Err(error("expected an object"));
+            };
+            let number = |key: &str, value: &mut f32| {
+                if let Some(json) = entry.get(key) {
+                    *value = json
+                        .as_f32()
+                        .ok_or_else(|| error(&format!("'{key}' should be a number")))?;
+                }
+                Ok::<_, String>(())
+            };
+            let numbers = |key: &str, values: &mut [f32]| {
+                if let Some(json) = entry.get(key) {
+                    let array = json
+                        .as_array()
+                        .filter(|array| array.len() == values.len())
+                        .ok_or_else(|| {
+                            error(&format!("'{key}' should be {} numbers", values.len()))
+                        })?;
+                    for (value, json) in values.iter_mut().zip(array) {
+                        *value = json
+                            .as_f32()
+                            .ok_or_else(|| error(&format!("'{key}' should be numbers")))?;
+                    }
+                }
+                Ok::<_, String>(())
+            };
+
+            let name = match entry.get("name") {
+                Some(name) => name
+                    .as_str()
+                    .ok_or_else(|| error("'name' should be a string"))?
+                    .to_string(),
+                None => format!("Material {i}"),
+            };
+            let mut material = GpuMaterial::default();
+            numbers(
+                "base_color",
+                AsMut::<[f32; 3]>::as_mut(&mut material.base_color),
+            )?;
+            numbers(
+                "emissive_color",
+                AsMut::<[f32; 3]>::as_mut(&mut material.emissive_color),
+            )?;
+            number("emission_strength", &mut material.emission_strength)?;
+            number("bump_strength", &mut material.bump_strength)?;
+            number("bump_scale", &mut material.bump_scale)?;
+            if let Some(pattern) = entry.get("emission_pattern") {
+                let pattern = pattern
+                    .as_str()
+                    .and_then(|name| EmissionPattern::ALL.into_iter().find(|p| p.name() == name))
+                    .ok_or_else(|| error("unknown 'emission_pattern'"))?;
+                material.emission_pattern = pattern as _;
+            }
+            numbers(
+                "emission_pattern_scale",
+                AsMut::<[f32; 4]>::as_mut(&mut material.emission_pattern_scale),
+            )?;
+            number("transmission", &mut material.transmission)?;
+            number("ior", &mut material.ior)?;
+            number("dispersion", &mut material.dispersion)?;
+            let mut priority = material.priority as f32;
+            number("priority", &mut priority)?;
+            material.priority = priority.max(0.0) as _;
+            numbers(
+                "absorption_color",
+                AsMut::<[f32; 3]>::as_mut(&mut material.absorption_color),
+            )?;
+            number("absorption_density", &mut material.absorption_density)?;
+            number("clearcoat", &mut material.clearcoat)?;
+            number("clearcoat_roughness", &mut material.clearcoat_roughness)?;
+            number("sheen", &mut material.sheen)?;
+            numbers(
+                "sheen_color",
+                AsMut::<[f32; 3]>::as_mut(&mut material.sheen_color),
+            )?;
+            materials.push((name, material));
+        }
+        Ok(Self { materials })
+    }
+}
+
+/// Renders small previews of materials on a hyper sphere over a grey floor
+pub struct SwatchRenderer {
+    renderer: Renderer,
+    camera: Camera,
+}
+
+impl SwatchRenderer {
+    /// The width and height of a swatch in pixels
+    pub const SIZE: usize = 48;
+
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let mut renderer = Renderer::new(device, queue, RenderPath::for_device(device));
+        renderer.resize(device, queue, Self::SIZE, Self::SIZE);
+        Self {
+            renderer,
+            camera: Camera {
+                position: cgmath::vec4(0.0, 1.5, -2.5, 0.0),
+                pitch: -10.0f32.to_radians(),
+                fov: 60.0f32.to_radians(),
+                bounce_count: 4,
+                sample_count: 32,
+                deterministic_seed: Some(0),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Tightly packed RGBA8 rows, blocking until the GPU is done
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        material: &GpuMaterial,
+    ) -> Result<Vec<u8>, GpuError> {
+        let floor = GpuMaterial {
+            base_color: cgmath::vec3(0.5, 0.5, 0.5),
+            ..Default::default()
+        };
+        // the materials a blend uses aren't uploaded with it, so it shows its own properties
+        let material = &GpuMaterial {
+            blend_mask: BlendMask::None as _,
+            ..*material
+        };
+        self.renderer.upload_objects(
+            device,
+            queue,
+            &[GpuHyperSphere {
+                center: cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+                velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                radius: 1.0,
+                material: 0,
+                inner_radius: 0.0,
+            }],
+            &[GpuHyperPlane {
+                point: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                normal: cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+                velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                angular_velocity: BiVector4::ZERO,
+                material: 1,
+                extents: cgmath::vec3(0.0, 0.0, 0.0),
+                tangent: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                bitangent: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            }],
+        )?;
+        self.renderer
+            .upload_environment(queue, &Environment::default().to_gpu())?;
+        self.renderer
+            .upload_materials(device, queue, &[*material, floor])?;
+        self.renderer.reset_accumulation();
+        self.renderer.upload_camera(queue, &self.camera.to_gpu(0))?;
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Swatch Command Encoder"),
+        });
+        self.renderer
+            .render(device, &mut encoder, self.camera.render_options());
+        queue.submit([encoder.finish()]);
+        self.renderer.read_pixels(device, queue)
+    }
+}
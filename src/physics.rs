@@ -0,0 +1,270 @@
+use crate::{GpuHyperSphere, MaterialId, ObjectRef, Scene};
+use cgmath::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PhysicsMode {
+    /// Uniform gravity with bounces off each other and the hyper planes
+    Sandbox,
+    /// Hyper spheres with an emissive material are fixed suns that pull on the others, with
+    /// the inverse cube law of 4D gravity, and there are no collisions
+    Orbital,
+}
+
+impl PhysicsMode {
+    pub const ALL: [PhysicsMode; 2] = [PhysicsMode::Sandbox, PhysicsMode::Orbital];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PhysicsMode::Sandbox => "Sandbox",
+            PhysicsMode::Orbital => "Orbital",
+        }
+    }
+}
+
+/// Moves the hyper spheres of a [`Scene`], the hyper planes never move. A hyper sphere's
+/// `velocity` is its physics velocity so motion blur keeps working, and its mass is its
+/// 4D volume, proportional to `radius⁴`
+///
+/// Trails are small hyper spheres added at the end of the scene's hyper spheres, they are
+/// never simulated and are removed again by [`Physics::reset`]
+#[derive(Clone)]
+pub struct Physics {
+    pub mode: PhysicsMode,
+    pub gravity: cgmath::Vector4<f32>,
+    /// How much of the speed along the contact normal is kept in a bounce, 1 is perfectly elastic
+    pub restitution: f32,
+    /// How strongly suns pull in [`PhysicsMode::Orbital`]
+    pub gravitational_constant: f32,
+    /// Seconds between the trail hyper spheres dropped behind each moving hyper sphere,
+    /// 0 for no trails
+    pub trail_interval: f32,
+    /// How many trail hyper spheres each moving hyper sphere keeps
+    pub trail_length: usize,
+    pub running: bool,
+    /// The hyper spheres from when the simulation was first started, for [`Physics::reset`]
+    initial: Option<Vec<GpuHyperSphere>>,
+    trail_count: usize,
+    trail_timer: f32,
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Self {
+            mode: PhysicsMode::Sandbox,
+            gravity: cgmath::vec4(0.0, -9.81, 0.0, 0.0),
+            restitution: 1.0,
+            gravitational_constant: 10.0,
+            trail_interval: 0.0,
+            trail_length: 64,
+            running: false,
+            initial: None,
+            trail_count: 0,
+            trail_timer: 0.0,
+        }
+    }
+}
+
+impl Physics {
+    /// Starts or continues the simulation, remembering the hyper spheres if it wasn't started before
+    pub fn play(&mut self, scene: &Scene) {
+        if self.initial.is_none() {
+            self.initial = Some(scene.hyper_spheres().copied().collect());
+        }
+        self.running = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.running = false;
+    }
+
+    /// Stops the simulation, removes the trails and puts the hyper spheres back to how they
+    /// were when it started, unless hyper spheres have been added or removed since
+    pub fn reset(&mut self, scene: &mut Scene) {
+        self.running = false;
+        self.trail_timer = 0.0;
+        let trail_count = std::mem::take(&mut self.trail_count);
+        if let Some(initial) = self.initial.take() {
+            let hyper_sphere_count = scene.hyper_sphere_count();
+            if initial.len() + trail_count == hyper_sphere_count {
+                let trails = (initial.len()..hyper_sphere_count)
+                    .map(ObjectRef::HyperSphere)
+                    .collect::<Vec<_>>();
+                scene.remove_objects(&trails);
+                for (hyper_sphere, initial) in scene.hyper_spheres_mut().zip(initial) {
+                    *hyper_sphere = initial;
+                }
+            }
+        }
+    }
+
+    /// How many hyper spheres at the start of the scene are simulated, the rest are trails
+    fn body_count(&self, scene: &Scene) -> usize {
+        scene.hyper_sphere_count().saturating_sub(self.trail_count)
+    }
+
+    /// Advances the simulation by `ts` seconds if it is running
+    pub fn step(&mut self, scene: &mut Scene, ts: f32) {
+        if !self.running || ts <= 0.0 {
+            return;
+        }
+
+        // smaller steps keep fast spheres from tunneling through each other
+        const SUBSTEPS: usize = 8;
+        let dt = ts.min(0.1) / SUBSTEPS as f32;
+        for _ in 0..SUBSTEPS {
+            match self.mode {
+                PhysicsMode::Sandbox => self.sandbox_substep(scene, dt),
+                PhysicsMode::Orbital => self.orbital_substep(scene, dt),
+            }
+        }
+        scene.changes.hyper_spheres = true;
+
+        if self.trail_interval > 0.0 {
+            self.trail_timer += ts;
+            if self.trail_timer >= self.trail_interval {
+                self.trail_timer %= self.trail_interval;
+                self.drop_trails(scene);
+            }
+        }
+    }
+
+    fn sandbox_substep(&self, scene: &mut Scene, dt: f32) {
+        let body_count = self.body_count(scene);
+        let hyper_planes = scene.hyper_planes().copied().collect::<Vec<_>>();
+        let mut hyper_spheres = scene
+            .hyper_spheres_mut()
+            .take(body_count)
+            .collect::<Vec<_>>();
+        for hyper_sphere in hyper_spheres.iter_mut() {
+            hyper_sphere.velocity += self.gravity * dt;
+            hyper_sphere.center += hyper_sphere.velocity * dt;
+
+            for hyper_plane in &hyper_planes {
+                if !hyper_plane.contains(hyper_sphere.center, hyper_sphere.radius) {
+                    continue;
+                }
+                let height = hyper_plane
+                    .normal
+                    .dot(hyper_sphere.center - hyper_plane.point);
+                let normal = if height >= 0.0 {
+                    hyper_plane.normal
+                } else {
+                    -hyper_plane.normal
+                };
+                let penetration = hyper_sphere.radius - height.abs();
+                if penetration > 0.0 {
+                    hyper_sphere.center += normal * penetration;
+                    let speed = hyper_sphere.velocity.dot(normal);
+                    if speed < 0.0 {
+                        hyper_sphere.velocity -= normal * ((1.0 + self.restitution) * speed);
+                    }
+                }
+            }
+        }
+
+        for i in 0..hyper_spheres.len() {
+            for j in i + 1..hyper_spheres.len() {
+                let (before, after) = hyper_spheres.split_at_mut(j);
+                let (a, b) = (&mut *before[i], &mut *after[0]);
+                let offset = b.center - a.center;
+                let distance = offset.magnitude();
+                let penetration = a.radius + b.radius - distance;
+                if penetration <= 0.0 || distance == 0.0 {
+                    continue;
+                }
+
+                let normal = offset / distance;
+                let a_mass = a.radius.powi(4);
+                let b_mass = b.radius.powi(4);
+                let total_mass = a_mass + b_mass;
+                if total_mass == 0.0 {
+                    continue;
+                }
+                // push them apart in proportion to the other's mass, so the heavier one moves less
+                a.center -= normal * (penetration * b_mass / total_mass);
+                b.center += normal * (penetration * a_mass / total_mass);
+
+                let approach_speed = (a.velocity - b.velocity).dot(normal);
+                if approach_speed > 0.0 {
+                    let impulse = (1.0 + self.restitution) * approach_speed / total_mass;
+                    a.velocity -= normal * (impulse * b_mass);
+                    b.velocity += normal * (impulse * a_mass);
+                }
+            }
+        }
+    }
+
+    fn is_sun(scene: &Scene, hyper_sphere: &GpuHyperSphere) -> bool {
+        scene
+            .material(MaterialId(hyper_sphere.material))
+            .is_some_and(|material| {
+                material.emission_strength > 0.0
+                    && material.emissive_color != cgmath::Vector3::zero()
+            })
+    }
+
+    fn orbital_substep(&self, scene: &mut Scene, dt: f32) {
+        let body_count = self.body_count(scene);
+        let is_sun = scene
+            .hyper_spheres()
+            .take(body_count)
+            .map(|hyper_sphere| Self::is_sun(scene, hyper_sphere))
+            .collect::<Vec<_>>();
+        let suns = scene
+            .hyper_spheres()
+            .zip(&is_sun)
+            .filter(|(_, &is_sun)| is_sun)
+            .map(|(sun, _)| (sun.center, sun.radius))
+            .collect::<Vec<_>>();
+        for (hyper_sphere, is_sun) in scene.hyper_spheres_mut().zip(is_sun) {
+            if is_sun {
+                continue;
+            }
+            for &(center, radius) in &suns {
+                let offset = center - hyper_sphere.center;
+                // the force stops growing inside the sun so passing through it doesn't fling things away
+                let distance = offset.magnitude().max(radius);
+                if distance == 0.0 {
+                    continue;
+                }
+                let mass = radius.powi(4);
+                hyper_sphere.velocity += offset / distance
+                    * (self.gravitational_constant * mass / distance.powi(3) * dt);
+            }
+            hyper_sphere.center += hyper_sphere.velocity * dt;
+        }
+    }
+
+    /// Leaves a small hyper sphere behind every moving hyper sphere, removing the oldest ones
+    /// once there are more than `trail_length` each
+    fn drop_trails(&mut self, scene: &mut Scene) {
+        let body_count = self.body_count(scene);
+        let trails = scene
+            .hyper_spheres()
+            .take(body_count)
+            .filter(|hyper_sphere| hyper_sphere.velocity != cgmath::Vector4::zero())
+            .map(|hyper_sphere| GpuHyperSphere {
+                center: hyper_sphere.center,
+                velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                radius: (hyper_sphere.radius * 0.1).max(0.02),
+                material: hyper_sphere.material,
+                inner_radius: 0.0,
+            })
+            .collect::<Vec<_>>();
+        let moving_count = trails.len();
+        for trail in trails {
+            scene.add_hyper_sphere("Trail", trail);
+            self.trail_count += 1;
+        }
+
+        let max_trails = moving_count * self.trail_length;
+        if self.trail_count > max_trails {
+            let excess = self.trail_count - max_trails;
+            let oldest = (body_count..body_count + excess)
+                .map(ObjectRef::HyperSphere)
+                .collect::<Vec<_>>();
+            scene.remove_objects(&oldest);
+            self.trail_count = max_trails;
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{GpuCamera, ObjectRef, Scene};
+use cgmath::prelude::*;
+
+/// The first surface along a ray cast by [`pick`]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PickHit {
+    pub object: ObjectRef,
+    pub distance: f32,
+    pub position: cgmath::Vector4<f32>,
+    /// Faces back towards the ray origin
+    pub normal: cgmath::Vector4<f32>,
+}
+
+/// The ray the shader would trace through `normalized_uv`, which goes from -1 to 1 with +y up,
+/// `aspect` is the image's and is ignored when the camera overrides it
+pub fn camera_ray(
+    camera: &GpuCamera,
+    normalized_uv: cgmath::Vector2<f32>,
+    aspect: f32,
+) -> (cgmath::Vector4<f32>, cgmath::Vector4<f32>) {
+    let theta = (camera.fov / 2.0).tan();
+    let aspect = if camera.aspect_override > 0.0 {
+        camera.aspect_override
+    } else {
+        aspect
+    };
+    let normalized_uv = normalized_uv + camera.lens_shift;
+    let direction = (camera.right * (normalized_uv.x * aspect * theta)
+        + camera.up * (normalized_uv.y * theta)
+        + camera.forward)
+        .normalize();
+    (camera.position, direction)
+}
+
+/// [`camera_ray`] for a side-by-side stereo image, `normalized_uv` and `aspect` are for the
+/// whole image and the ray starts from the eye whose half it lands in
+pub fn stereo_camera_ray(
+    camera: &GpuCamera,
+    normalized_uv: cgmath::Vector2<f32>,
+    aspect: f32,
+) -> (cgmath::Vector4<f32>, cgmath::Vector4<f32>) {
+    let (eye_x, side) = if normalized_uv.x < 0.0 {
+        (normalized_uv.x * 2.0 + 1.0, -1.0)
+    } else {
+        (normalized_uv.x * 2.0 - 1.0, 1.0)
+    };
+    let (origin, direction) =
+        camera_ray(camera, cgmath::vec2(eye_x, normalized_uv.y), aspect * 0.5);
+    (
+        origin + camera.right * (side * camera.eye_offset),
+        direction,
+    )
+}
+
+/// Casts a single ray against the objects at time 0, ignoring the camera's cross section
+/// and projection so it only matches what is seen with the perspective projection, the
+/// panoramic projections would need their rays from [`crate::Projection::ray_direction`]
+pub fn pick(
+    scene: &Scene,
+    origin: cgmath::Vector4<f32>,
+    direction: cgmath::Vector4<f32>,
+) -> Option<PickHit> {
+    let mut closest: Option<PickHit> = None;
+    for (object, data) in scene.object_refs().zip(&scene.objects) {
+        let Some((distance, mut normal)) = data.data.intersect(origin, direction) else {
+            continue;
+        };
+        if closest.is_some_and(|hit| hit.distance <= distance) {
+            continue;
+        }
+
+        let position = origin + direction * distance;
+        if normal.dot(direction) > 0.0 {
+            normal = -normal;
+        }
+        closest = Some(PickHit {
+            object,
+            distance,
+            position,
+            normal,
+        });
+    }
+    closest
+}
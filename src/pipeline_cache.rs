@@ -0,0 +1,395 @@
+use crate::{
+    preprocess_shader, GpuError, Projection, RayTracingPipeline, RenderPath, WavefrontPipelines,
+    ENTRY_SHADER_MODULE,
+};
+use std::collections::HashMap;
+
+/// Options that are compiled into the shader instead of being branched on at runtime,
+/// each combination gets its own pipeline
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct RenderOptions {
+    pub motion_blur: bool,
+    pub sampler: SamplerKind,
+    pub render_mode: RenderMode,
+    pub cross_section: bool,
+    pub projection: Projection,
+    /// Renders [`crate::Camera::stereo`]'s two eyes side by side
+    pub stereo: bool,
+    /// Each sample traces a single wavelength, for dispersion
+    pub spectral: bool,
+    /// Accumulates each light group apart so their weights can change without starting over,
+    /// only on the compute path when path tracing
+    pub light_groups: bool,
+    /// Writes the AOVs of the first hit to [`crate::Renderer::read_aovs`], only on the compute
+    /// path when path tracing
+    pub aovs: bool,
+    pub color_management: ColorManagement,
+    /// Builds the single pixel debug dispatch of [`crate::Renderer::inspect_pixel`] instead,
+    /// only on the compute path
+    pub debug_path: bool,
+    /// Reads the objects as [`crate::ObjectFormat::Packed`], the [`crate::Renderer`] sets this
+    /// to match how it last uploaded them
+    pub packed_objects: bool,
+    /// Follows the blends of blended materials, the [`crate::Renderer`] sets this when the
+    /// materials it last uploaded have any so other scenes don't pay for it
+    pub material_blends: bool,
+    /// Runs the node programs of materials built from nodes, set by the [`crate::Renderer`]
+    /// like `material_blends`
+    pub material_nodes: bool,
+    /// Finds the hyper spheres rays hit through the [`crate::Bvh`] the [`crate::Renderer`]
+    /// last built, it sets this when it built one
+    pub bvh: bool,
+    /// Traces in separate kernels that generate, compact, intersect and shade a whole wave of
+    /// paths at a time instead of one kernel following every path to the end, the
+    /// [`crate::Renderer`] sets this when [`crate::Renderer::set_wavefront`] is on and nothing
+    /// the kernels leave out is needed
+    pub wavefront: bool,
+}
+
+impl RenderOptions {
+    pub fn shader_defines(self) -> Vec<&'static str> {
+        let mut defines = vec![self.sampler.shader_define()];
+        if self.motion_blur {
+            defines.push("MOTION_BLUR");
+        }
+        match self.render_mode {
+            RenderMode::PathTrace => {}
+            RenderMode::AmbientOcclusion => defines.push("AMBIENT_OCCLUSION"),
+            RenderMode::WCoordinate => defines.push("W_COORDINATE"),
+        }
+        if self.cross_section {
+            defines.push("CROSS_SECTION");
+        }
+        if let Some(define) = self.projection.shader_define() {
+            defines.push(define);
+        }
+        if self.stereo {
+            defines.push("STEREO");
+        }
+        if self.spectral {
+            defines.push("SPECTRAL");
+        }
+        if self.light_groups {
+            defines.push("LIGHT_GROUPS");
+        }
+        if self.aovs {
+            defines.push("AOVS");
+        }
+        if self.packed_objects {
+            defines.push("PACKED_OBJECTS");
+        }
+        if self.material_blends {
+            defines.push("MATERIAL_BLENDS");
+        }
+        if self.material_nodes {
+            defines.push("MATERIAL_NODES");
+        }
+        if self.bvh {
+            defines.push("BVH");
+        }
+        if self.wavefront {
+            defines.push("WAVEFRONT");
+        }
+        if self.debug_path {
+            defines.push("DEBUG_PATH");
+        }
+        if let Some(define) = self.color_management.shader_define() {
+            defines.push(define);
+        }
+        defines
+    }
+}
+
+/// What is computed for each pixel
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RenderMode {
+    PathTrace,
+    /// How much of the hemisphere above the first hit is unblocked within
+    /// [`crate::Camera::ao_distance`], fast and low noise for inspecting geometry
+    AmbientOcclusion,
+    /// Colors the first hit by how far ana or kata of the camera it is, using [`crate::Camera::w_ramp`]
+    WCoordinate,
+}
+
+impl RenderMode {
+    pub const ALL: [RenderMode; 3] = [
+        RenderMode::PathTrace,
+        RenderMode::AmbientOcclusion,
+        RenderMode::WCoordinate,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RenderMode::PathTrace => "Path Trace",
+            RenderMode::AmbientOcclusion => "Ambient Occlusion",
+            RenderMode::WCoordinate => "W Coordinate",
+        }
+    }
+}
+
+/// How the linear colors that are traced are encoded into the output texture
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ColorManagement {
+    /// The output holds sRGB encoded colors, so it looks the same as the color pickers and
+    /// screenshots aren't too dark. 8 bits are also spread more evenly over the dark colors
+    Srgb,
+    /// The linear colors are stored as they are and shown as if they were already sRGB encoded,
+    /// for looking at the raw values
+    Linear,
+}
+
+impl ColorManagement {
+    pub const ALL: [ColorManagement; 2] = [ColorManagement::Srgb, ColorManagement::Linear];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorManagement::Srgb => "sRGB",
+            ColorManagement::Linear => "Linear",
+        }
+    }
+
+    pub fn shader_define(self) -> Option<&'static str> {
+        match self {
+            ColorManagement::Srgb => Some("SRGB_OUTPUT"),
+            ColorManagement::Linear => None,
+        }
+    }
+
+    /// Encodes a linear color in `[0, 1]` the way the output texture stores it
+    pub fn encode(self, color: cgmath::Vector3<f32>) -> cgmath::Vector3<f32> {
+        match self {
+            ColorManagement::Srgb => color.map(srgb_from_linear),
+            ColorManagement::Linear => color,
+        }
+    }
+}
+
+/// The sRGB transfer function, mirrors `srgb_from_linear` in the shaders
+pub fn srgb_from_linear(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Where the random numbers for anti-aliasing, motion blur and bounce directions come from
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SamplerKind {
+    Pcg,
+    Halton,
+    Sobol,
+    /// Blue noise for the first bounce, PCG for the rest
+    BlueNoise,
+}
+
+impl SamplerKind {
+    pub const ALL: [SamplerKind; 4] = [
+        SamplerKind::Pcg,
+        SamplerKind::Halton,
+        SamplerKind::Sobol,
+        SamplerKind::BlueNoise,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SamplerKind::Pcg => "PCG",
+            SamplerKind::Halton => "Halton",
+            SamplerKind::Sobol => "Sobol",
+            SamplerKind::BlueNoise => "Blue Noise",
+        }
+    }
+
+    pub fn shader_define(self) -> &'static str {
+        match self {
+            SamplerKind::Pcg => "SAMPLER_PCG",
+            SamplerKind::Halton => "SAMPLER_HALTON",
+            SamplerKind::Sobol => "SAMPLER_SOBOL",
+            SamplerKind::BlueNoise => "SAMPLER_BLUE_NOISE",
+        }
+    }
+}
+
+pub(crate) struct PipelineCache {
+    render_path: RenderPath,
+    layout: wgpu::PipelineLayout,
+    /// binds the wavefront paths in place of the debug path and AOVs, only on the compute path
+    wavefront_layout: Option<wgpu::PipelineLayout>,
+    /// bumped whenever the shader source changes, pipelines from older generations get rebuilt
+    generation: u64,
+    pipelines: HashMap<RenderOptions, (u64, RayTracingPipeline)>,
+}
+
+impl PipelineCache {
+    pub fn new(
+        render_path: RenderPath,
+        layout: wgpu::PipelineLayout,
+        wavefront_layout: Option<wgpu::PipelineLayout>,
+    ) -> Self {
+        Self {
+            render_path,
+            layout,
+            wavefront_layout,
+            generation: 0,
+            pipelines: HashMap::new(),
+        }
+    }
+
+    pub fn render_path(&self) -> RenderPath {
+        self.render_path
+    }
+
+    /// Marks every cached pipeline as out of date, they are rebuilt the next time they are used
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    pub fn invalidate(&mut self) {
+        self.generation += 1;
+    }
+
+    /// Gets the pipeline for `options`, building it if needed. If a rebuild fails the
+    /// previous pipeline is kept, `None` is only returned if there has never been a working one
+    pub fn get(
+        &mut self,
+        device: &wgpu::Device,
+        options: RenderOptions,
+    ) -> Option<&RayTracingPipeline> {
+        let up_to_date = self
+            .pipelines
+            .get(&options)
+            .is_some_and(|&(generation, _)| generation == self.generation);
+        if !up_to_date {
+            match self.build(device, options) {
+                Some(pipeline) => {
+                    self.pipelines.insert(options, (self.generation, pipeline));
+                }
+                None => {
+                    // don't retry until the source changes again
+                    if let Some((generation, _)) = self.pipelines.get_mut(&options) {
+                        *generation = self.generation;
+                    }
+                }
+            }
+        }
+        self.pipelines.get(&options).map(|(_, pipeline)| pipeline)
+    }
+
+    fn defines(&self, options: RenderOptions) -> Vec<&'static str> {
+        let mut defines = self.render_path.shader_defines().to_vec();
+        defines.extend(options.shader_defines());
+        if Self::culls(self.render_path, options) {
+            defines.push("CULLING");
+        }
+        defines
+    }
+
+    /// Whether camera rays only check the hyper spheres a culling pass found to be visible,
+    /// only perspective rays stay inside a cone, the others can go in any direction so nothing
+    /// can be culled for them. The debug dispatch only traces one pixel so it isn't worth a
+    /// culling pass
+    fn culls(render_path: RenderPath, options: RenderOptions) -> bool {
+        render_path == RenderPath::Compute
+            && options.projection == Projection::Perspective
+            && !options.debug_path
+    }
+
+    #[cfg(not(all(feature = "hot-reload", not(target_arch = "wasm32"))))]
+    fn build(
+        &mut self,
+        device: &wgpu::Device,
+        options: RenderOptions,
+    ) -> Option<RayTracingPipeline> {
+        let source = preprocess_shader(
+            ENTRY_SHADER_MODULE,
+            &self.defines(options),
+            crate::embedded_shader_module,
+        )
+        .expect("the embedded shader modules should always preprocess");
+        self.create_pipeline(device, options, source)
+    }
+
+    /// Builds from the shader sources on disk, logging errors instead of panicking
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    fn build(
+        &mut self,
+        device: &wgpu::Device,
+        options: RenderOptions,
+    ) -> Option<RayTracingPipeline> {
+        let shader_dir = crate::hot_reload::shader_dir();
+        let source = match preprocess_shader(ENTRY_SHADER_MODULE, &self.defines(options), |name| {
+            if name == crate::PRIMITIVES_SHADER_MODULE {
+                return crate::embedded_shader_module(name);
+            }
+            let path = shader_dir.join(name);
+            std::fs::read_to_string(&path)
+                .map(std::borrow::Cow::Owned)
+                .map_err(|error| format!("Failed to read {}: {error}", path.display()))
+        }) {
+            Ok(source) => source,
+            Err(error) => {
+                log::error!("{error}");
+                return None;
+            }
+        };
+        self.create_pipeline(device, options, source)
+    }
+
+    /// Logs validation errors and returns `None` instead of letting them reach the device's error handler
+    #[cfg(not(target_arch = "wasm32"))]
+    fn create_pipeline(
+        &self,
+        device: &wgpu::Device,
+        options: RenderOptions,
+        source: String,
+    ) -> Option<RayTracingPipeline> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Ray Tracing Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let pipeline = self.create(device, options, &module);
+        match pollster::block_on(device.pop_error_scope()) {
+            Some(error) => {
+                log::error!(
+                    "Shader compile error ({options:?}): {}",
+                    GpuError::from(error)
+                );
+                None
+            }
+            None => {
+                log::info!("Built shader ({options:?})");
+                Some(pipeline)
+            }
+        }
+    }
+
+    /// Error scopes can't be waited on here, errors go to the device's error handler
+    #[cfg(target_arch = "wasm32")]
+    fn create_pipeline(
+        &self,
+        device: &wgpu::Device,
+        options: RenderOptions,
+        source: String,
+    ) -> Option<RayTracingPipeline> {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Ray Tracing Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        Some(self.create(device, options, &module))
+    }
+
+    fn create(
+        &self,
+        device: &wgpu::Device,
+        options: RenderOptions,
+        module: &wgpu::ShaderModule,
+    ) -> RayTracingPipeline {
+        let culls = Self::culls(self.render_path, options);
+        match &self.wavefront_layout {
+            Some(layout) if options.wavefront => RayTracingPipeline::Wavefront(
+                WavefrontPipelines::new(device, layout, module, culls),
+            ),
+            _ => self
+                .render_path
+                .create_pipeline(device, &self.layout, module, culls),
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use crate::{ColorManagement, GpuPostEffect, RenderPath};
+use encase::{DynamicUniformBuffer, ShaderType};
+
+/// One step of the post processing stack, applied to the finished image in order
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PostEffect {
+    /// Darkens towards the corners, `radius` is how far out it starts with 1 being the corners
+    Vignette { strength: f32, radius: f32 },
+    /// Splits red outwards and blue inwards, `strength` is how far in pixels per pixel from the center
+    ChromaticAberration { strength: f32 },
+    /// Random per-pixel brightness noise that changes every frame
+    FilmGrain { strength: f32 },
+}
+
+impl PostEffect {
+    /// Every kind of effect with reasonable settings
+    pub const DEFAULTS: [PostEffect; 3] = [
+        PostEffect::Vignette {
+            strength: 0.5,
+            radius: 0.4,
+        },
+        PostEffect::ChromaticAberration { strength: 0.005 },
+        PostEffect::FilmGrain { strength: 0.05 },
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PostEffect::Vignette { .. } => "Vignette",
+            PostEffect::ChromaticAberration { .. } => "Chromatic Aberration",
+            PostEffect::FilmGrain { .. } => "Film Grain",
+        }
+    }
+
+    fn to_gpu(self, frame_index: u32, color_management: ColorManagement) -> GpuPostEffect {
+        let (kind, strength, radius) = match self {
+            PostEffect::Vignette { strength, radius } => (0, strength, radius),
+            PostEffect::ChromaticAberration { strength } => (1, strength, 0.0),
+            PostEffect::FilmGrain { strength } => (2, strength, 0.0),
+        };
+        GpuPostEffect {
+            kind,
+            strength,
+            radius,
+            seed: frame_index,
+            srgb: (color_management == ColorManagement::Srgb) as _,
+        }
+    }
+}
+
+/// Runs a list of [`PostEffect`]s over a ray tracer's output, one compute pass per effect.
+/// The passes ping-pong between two textures, ordered so the last one always writes
+/// [`PostProcessor::texture_view`]
+pub struct PostProcessor {
+    texture_width: usize,
+    texture_height: usize,
+    texture: wgpu::Texture,
+    texture_view: wgpu::TextureView,
+    scratch_texture_view: wgpu::TextureView,
+    bind_group_layout: wgpu::BindGroupLayout,
+    /// from the input to the output, the input to the scratch texture, the output to the
+    /// scratch texture and the scratch texture to the output
+    bind_groups: [wgpu::BindGroup; 4],
+    effect_uniform_buffer: wgpu::Buffer,
+    effect_uniform_stride: u64,
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl PostProcessor {
+    /// Any more effects than this are ignored
+    pub const MAX_EFFECTS: usize = 8;
+
+    /// Post processing needs compute shaders, so it's only available on the compute path
+    pub fn is_supported(device: &wgpu::Device) -> bool {
+        RenderPath::for_device(device) == RenderPath::Compute
+    }
+
+    /// `input` is the ray tracer's `Rgba8Unorm` output texture, of `width` by `height` pixels
+    pub fn new(
+        device: &wgpu::Device,
+        input: &wgpu::TextureView,
+        width: usize,
+        height: usize,
+    ) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post Processing Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::Rgba8Unorm,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: Some(GpuPostEffect::min_size()),
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post Processing Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Post Processing Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("./shaders/post.wgsl").into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Post Processing Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &module,
+            entry_point: "post_process",
+        });
+
+        let effect_uniform_stride = GpuPostEffect::min_size()
+            .get()
+            .next_multiple_of(device.limits().min_uniform_buffer_offset_alignment as u64);
+        let effect_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Post Effect Uniform Buffer"),
+            size: effect_uniform_stride * Self::MAX_EFFECTS as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+            mapped_at_creation: false,
+        });
+
+        let texture = Self::create_texture(device, width, height);
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let scratch_texture_view = Self::create_texture(device, width, height)
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_groups = Self::create_bind_groups(
+            device,
+            &bind_group_layout,
+            input,
+            &texture_view,
+            &scratch_texture_view,
+            &effect_uniform_buffer,
+        );
+        Self {
+            texture_width: width,
+            texture_height: height,
+            texture,
+            texture_view,
+            scratch_texture_view,
+            bind_group_layout,
+            bind_groups,
+            effect_uniform_buffer,
+            effect_uniform_stride,
+            pipeline,
+        }
+    }
+
+    fn create_texture(device: &wgpu::Device, width: usize, height: usize) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Post Processing Texture"),
+            size: wgpu::Extent3d {
+                width: width as _,
+                height: height as _,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: RenderPath::Compute.texture_usages(),
+            view_formats: &[],
+        })
+    }
+
+    fn create_bind_groups(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        input: &wgpu::TextureView,
+        output: &wgpu::TextureView,
+        scratch: &wgpu::TextureView,
+        effect_uniform_buffer: &wgpu::Buffer,
+    ) -> [wgpu::BindGroup; 4] {
+        [
+            (input, output),
+            (input, scratch),
+            (output, scratch),
+            (scratch, output),
+        ]
+        .map(|(from, to)| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Post Processing Bind Group"),
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(from),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(to),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                            buffer: effect_uniform_buffer,
+                            offset: 0,
+                            size: Some(GpuPostEffect::min_size()),
+                        }),
+                    },
+                ],
+            })
+        })
+    }
+
+    /// Reads from `input` from now on, call it whenever the ray tracer's texture is recreated.
+    /// The textures are only recreated if the size changed, returns whether they were
+    pub fn set_input(
+        &mut self,
+        device: &wgpu::Device,
+        input: &wgpu::TextureView,
+        width: usize,
+        height: usize,
+    ) -> bool {
+        let resized = (width, height) != (self.texture_width, self.texture_height);
+        if resized {
+            (self.texture_width, self.texture_height) = (width, height);
+            self.texture = Self::create_texture(device, width, height);
+            self.texture_view = self
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default());
+            self.scratch_texture_view = Self::create_texture(device, width, height)
+                .create_view(&wgpu::TextureViewDescriptor::default());
+        }
+        self.bind_groups = Self::create_bind_groups(
+            device,
+            &self.bind_group_layout,
+            input,
+            &self.texture_view,
+            &self.scratch_texture_view,
+            &self.effect_uniform_buffer,
+        );
+        resized
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    /// The result of the last effect, it can be sampled once the commands from
+    /// [`PostProcessor::render`] are submitted. Unchanged if there were no effects
+    pub fn texture_view(&self) -> &wgpu::TextureView {
+        &self.texture_view
+    }
+
+    /// Records every effect into `encoder`, `color_management` has to match how the input was
+    /// rendered and `frame_index` picks the film grain
+    pub fn render(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        effects: &[PostEffect],
+        color_management: ColorManagement,
+        frame_index: u32,
+    ) {
+        let effects = &effects[..effects.len().min(Self::MAX_EFFECTS)];
+        if effects.is_empty() {
+            return;
+        }
+
+        let mut effects_buffer =
+            DynamicUniformBuffer::new_with_alignment(vec![], self.effect_uniform_stride);
+        for effect in effects {
+            effects_buffer
+                .write(&effect.to_gpu(frame_index, color_management))
+                .expect("dynamic buffers grow to fit");
+        }
+        queue.write_buffer(&self.effect_uniform_buffer, 0, &effects_buffer.into_inner());
+
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Post Processing Pass"),
+        });
+        compute_pass.set_pipeline(&self.pipeline);
+        let workgroup_size = (16, 16);
+        for i in 0..effects.len() {
+            let to_output = (effects.len() - 1 - i).is_multiple_of(2);
+            let bind_group = match (i, to_output) {
+                (0, true) => &self.bind_groups[0],
+                (0, false) => &self.bind_groups[1],
+                (_, false) => &self.bind_groups[2],
+                (_, true) => &self.bind_groups[3],
+            };
+            compute_pass.set_bind_group(
+                0,
+                bind_group,
+                &[(i as u64 * self.effect_uniform_stride) as _],
+            );
+            compute_pass.dispatch_workgroups(
+                self.texture_width.div_ceil(workgroup_size.0) as _,
+                self.texture_height.div_ceil(workgroup_size.1) as _,
+                1,
+            );
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use crate::{
+    scene_script, wedge, BiVector4, GpuMaterial, MaterialId, ObjectId, ObjectRef, Rotor4, Scene,
+    Script,
+};
+use std::collections::HashMap;
+
+/// Where a [`Prefab`] is put into a scene, its objects are scaled and rotated about the origin
+/// and then moved to `position`
+#[derive(Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub position: cgmath::Vector4<f32>,
+    pub angle: f32,
+    pub plane: BiVector4,
+    pub scale: f32,
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Self {
+            position: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            angle: 0.0,
+            plane: BiVector4::XZ,
+            scale: 1.0,
+        }
+    }
+}
+
+impl Placement {
+    fn rotor(&self) -> Rotor4 {
+        if self.angle == 0.0 {
+            Rotor4::IDENTITY
+        } else {
+            Rotor4::from_angle_plane(self.angle, self.plane)
+        }
+    }
+
+    /// Where a point of the prefab ends up
+    pub fn point(&self, point: cgmath::Vector4<f32>) -> cgmath::Vector4<f32> {
+        self.position + self.rotor().rotate_vec(point * self.scale)
+    }
+
+    /// Rotated but not scaled, for normals and tangents
+    pub fn direction(&self, direction: cgmath::Vector4<f32>) -> cgmath::Vector4<f32> {
+        self.rotor().rotate_vec(direction)
+    }
+
+    /// Rotated like the planes it is made of, for angular velocities
+    pub fn bivector(&self, bivector: BiVector4) -> BiVector4 {
+        let axes = [
+            cgmath::vec4(1.0, 0.0, 0.0, 0.0),
+            cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+            cgmath::vec4(0.0, 0.0, 1.0, 0.0),
+            cgmath::vec4(0.0, 0.0, 0.0, 1.0),
+        ]
+        .map(|axis| self.direction(axis));
+        let components = [
+            (bivector.xy, 0, 1),
+            (bivector.xz, 0, 2),
+            (bivector.xw, 0, 3),
+            (bivector.yz, 1, 2),
+            (bivector.yw, 1, 3),
+            (bivector.zw, 2, 3),
+        ];
+        let mut rotated = BiVector4::ZERO;
+        for (amount, a, b) in components {
+            let plane = wedge(axes[a], axes[b]);
+            rotated.xy += plane.xy * amount;
+            rotated.xz += plane.xz * amount;
+            rotated.xw += plane.xw * amount;
+            rotated.yz += plane.yz * amount;
+            rotated.yw += plane.yw * amount;
+            rotated.zw += plane.zw * amount;
+        }
+        rotated
+    }
+}
+
+/// A group of objects and the materials they use, saved on its own as a script so it can be
+/// placed into other scenes
+#[derive(Clone)]
+pub struct Prefab {
+    pub name: String,
+    /// Only the objects and their materials, positioned around the origin
+    pub scene: Scene,
+}
+
+impl Prefab {
+    /// The extension of prefab files, they are scripts like saved scenes
+    pub const EXTENSION: &'static str = "prefab";
+
+    /// Copies `objects` and their materials, centered on the average of their positions.
+    /// Objects that have been removed are skipped
+    pub fn from_objects(name: impl Into<String>, scene: &Scene, objects: &[ObjectRef]) -> Self {
+        let objects = objects
+            .iter()
+            .filter_map(|&object| scene.object(object))
+            .collect::<Vec<_>>();
+        let center = objects
+            .iter()
+            .map(|object| object.position())
+            .fold(cgmath::vec4(0.0, 0.0, 0.0, 0.0), |sum, position| {
+                sum + position
+            })
+            / objects.len().max(1) as f32;
+
+        // the materials that blends use come before the blends, so placing the prefab can
+        // compare a blend with existing ones after the materials it uses have been placed
+        let mut used = vec![];
+        for object in &objects {
+            used_materials(scene, object.material(), &mut used, &mut vec![]);
+        }
+        let mut prefab = Scene::empty();
+        let mut materials = HashMap::new();
+        for &id in &used {
+            let (name, material) = scene
+                .material_index(id)
+                .map_or(("Default Material", GpuMaterial::default()), |index| {
+                    (&scene.material_names[index], scene.materials[index])
+                });
+            // light groups belong to the scene, not the material
+            let material = GpuMaterial {
+                light_group: 0,
+                ..material
+            };
+            let added = prefab.add_material(name, material);
+            if let Some(graph) = scene.node_graphs.get(&id) {
+                prefab.node_graphs.insert(added, graph.clone());
+            }
+            materials.insert(id, added);
+        }
+        for material in prefab.materials_mut() {
+            if material.blend_mask().is_some() {
+                material.blend_a = materials[&MaterialId(material.blend_a)].0;
+                material.blend_b = materials[&MaterialId(material.blend_b)].0;
+            }
+        }
+        for object in objects {
+            let material = materials[&object.material()];
+            let mut data = object.data;
+            *data.position_mut() -= center;
+            data.set_material(material);
+            prefab.add_object(object.name.clone(), data);
+            prefab.objects.last_mut().unwrap().notes = object.notes.clone();
+        }
+        Self {
+            name: name.into(),
+            scene: prefab,
+        }
+    }
+
+    pub fn to_script(&self) -> String {
+        scene_script(&self.scene)
+    }
+
+    pub fn from_script(name: impl Into<String>, source: &str) -> Result<Self, String> {
+        let mut scene = Scene::empty();
+        Script::compile(source)?.run(&mut scene, &mut vec![])?;
+        Ok(Self {
+            name: name.into(),
+            scene,
+        })
+    }
+
+    /// Named after the file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &std::path::Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|error| format!("Failed to read {}: {error}", path.display()))?;
+        let name = path
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        Self::from_script(name, &source)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: &std::path::Path) -> Result<(), String> {
+        std::fs::write(path, self.to_script())
+            .map_err(|error| format!("Failed to write {}: {error}", path.display()))
+    }
+
+    /// Adds a copy of the objects to `scene`, returns their ids. Materials are reused when the
+    /// scene already has one with the same name, properties and nodes, otherwise they are added.
+    /// Blends that use themselves are always added
+    pub fn instantiate(&self, scene: &mut Scene, placement: &Placement) -> Vec<ObjectId> {
+        let mut materials = HashMap::<MaterialId, MaterialId>::new();
+        let mut unresolved = vec![];
+        for ((&id, name), material) in self
+            .scene
+            .material_ids
+            .iter()
+            .zip(&self.scene.material_names)
+            .zip(&self.scene.materials)
+        {
+            let mut material = *material;
+            let mut resolved = true;
+            if let Some(blended) = material.blended() {
+                match blended.map(|id| materials.get(&id).copied()) {
+                    [Some(a), Some(b)] => {
+                        material.blend_a = a.0;
+                        material.blend_b = b.0;
+                    }
+                    // fixed up once every material has been placed
+                    _ => resolved = false,
+                }
+            }
+            let graph = self.scene.node_graphs.get(&id);
+            let existing = scene
+                .material_names
+                .iter()
+                .zip(&scene.materials)
+                .zip(&scene.material_ids)
+                .position(|(other, other_id)| {
+                    resolved
+                        && other == (name, &material)
+                        && scene.node_graphs.get(other_id) == graph
+                })
+                .map(|index| scene.material_ids[index]);
+            let placed = existing.unwrap_or_else(|| {
+                let placed = scene.add_material(name.clone(), material);
+                if let Some(graph) = graph {
+                    scene.node_graphs.insert(placed, graph.clone());
+                }
+                placed
+            });
+            if !resolved {
+                unresolved.push(placed);
+            }
+            materials.insert(id, placed);
+        }
+        for id in unresolved {
+            let index = scene.material_index(id).unwrap();
+            let material = &mut scene.materials_mut()[index];
+            material.blend_a = materials[&MaterialId(material.blend_a)].0;
+            material.blend_b = materials[&MaterialId(material.blend_b)].0;
+        }
+
+        let mut ids = vec![];
+        for object in &self.scene.objects {
+            let mut data = object.data.placed(placement);
+            if let Some(&material) = materials.get(&object.material()) {
+                data.set_material(material);
+            }
+            ids.push(scene.add_object(object.name.clone(), data));
+            scene.objects.last_mut().unwrap().notes = object.notes.clone();
+        }
+        ids
+    }
+}
+
+/// Adds `id` to `used` after the materials it blends, `blending` are the blends being added
+/// so that blends that use themselves stop
+fn used_materials(
+    scene: &Scene,
+    id: MaterialId,
+    used: &mut Vec<MaterialId>,
+    blending: &mut Vec<MaterialId>,
+) {
+    if used.contains(&id) || blending.contains(&id) {
+        return;
+    }
+    if let Some(blended) = scene.material(id).and_then(GpuMaterial::blended) {
+        blending.push(id);
+        for blended in blended {
+            used_materials(scene, blended, used, blending);
+        }
+        blending.pop();
+    }
+    used.push(id);
+}
+
+/// The prefab files in `directory`, sorted by name, empty if it can't be read
+#[cfg(not(target_arch = "wasm32"))]
+pub fn list_prefabs(directory: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return vec![];
+    };
+    let mut paths = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == Prefab::EXTENSION)
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
@@ -0,0 +1,61 @@
+use crate::Camera;
+
+/// Lower settings rendered while the camera is moving so the editor stays responsive, the
+/// camera's own settings come back once it has been still for `settle_time`
+#[derive(Clone, Copy)]
+pub struct MotionPreview {
+    pub enabled: bool,
+    pub sample_count: u32,
+    pub bounce_count: u32,
+    /// Seconds the camera has to be still before the full settings are used again
+    pub settle_time: f32,
+    still_time: f32,
+    previous: Option<Camera>,
+}
+
+impl Default for MotionPreview {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sample_count: 1,
+            bounce_count: 3,
+            settle_time: 0.2,
+            still_time: f32::INFINITY,
+            previous: None,
+        }
+    }
+}
+
+impl MotionPreview {
+    /// Tracks whether `camera` has moved since the last update, `ts` is the time since then
+    pub fn update(&mut self, camera: &Camera, ts: f32) {
+        let moved = self
+            .previous
+            .is_some_and(|previous| !previous.same_view(camera));
+        self.previous = Some(*camera);
+        if moved {
+            self.still_time = 0.0;
+        } else {
+            self.still_time += ts;
+        }
+    }
+
+    /// Whether the preview settings are being used
+    pub fn active(&self) -> bool {
+        self.enabled && self.still_time < self.settle_time
+    }
+
+    /// The camera to render with, `camera` with the preview's counts while it is moving. The
+    /// counts are only ever lowered, and accumulation restarts when they change back because
+    /// the camera is different
+    pub fn apply(&self, camera: &Camera) -> Camera {
+        if !self.active() {
+            return *camera;
+        }
+        Camera {
+            sample_count: camera.sample_count.min(self.sample_count.max(1)),
+            bounce_count: camera.bounce_count.min(self.bounce_count),
+            ..*camera
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use crate::{GpuHyperPlane, GpuHyperSphere, MaterialId, ObjectProblem, Placement};
+use std::{any::Any, fmt::Write};
+
+/// A shape that objects of a [`Scene`](crate::Scene) can be, implemented by the struct that
+/// is uploaded for it
+///
+/// A new shape implements this in its own module and is added to the `primitives!` list below,
+/// which generates its [`PrimitiveKind`], [`ObjectData`] and [`ObjectRef`] variants
+pub trait Primitive: Copy + 'static {
+    const KIND: PrimitiveKind;
+    /// Shown in the editor
+    const NAME: &'static str;
+    /// The heading of the editor's list of them
+    const PLURAL_NAME: &'static str;
+    /// The name of the shape in the shaders, in snake case. Its shader module is this followed
+    /// by `.wgsl` and defines `_hit(ray: Ray, index: u32) -> Hit` after this, which
+    /// [`primitives_shader_module`] calls for every index below `_count()` after this, the
+    /// length of the buffer named this followed by `s`
+    const SHADER_NAME: &'static str;
+    /// Whether camera rays only check the ones a culling pass found to be visible, listed in the
+    /// buffer named `visible_` followed by the shader name and `s`
+    const CULLED: bool = false;
+    /// Whether rays find the ones that don't move through a [`crate::Bvh`] when `BVH` is defined,
+    /// the shader module then defines `closest_bvh_` followed by the shader name and
+    /// `_hit(ray: Ray, closest_hit: Hit) -> Hit`, which replaces the loop over all of them
+    const BVH: bool = false;
+
+    /// A new one at the origin, for the editor's add buttons
+    fn new(material: MaterialId) -> Self;
+
+    fn material(&self) -> MaterialId;
+    fn set_material(&mut self, material: MaterialId);
+
+    /// The point the shape is placed at, moved by the editor and animations
+    fn position(&self) -> cgmath::Vector4<f32>;
+    fn position_mut(&mut self) -> &mut cgmath::Vector4<f32>;
+
+    /// A copy moved, rotated and scaled by `placement`, for putting a [`crate::Prefab`] into a scene
+    fn placed(self, placement: &Placement) -> Self;
+
+    /// Everything [`Primitive::sanitized`] would change, `material_ids` are the materials of
+    /// the scene
+    fn problems(&self, material_ids: &[MaterialId]) -> Vec<ObjectProblem>;
+    /// A copy the GPU can draw
+    fn sanitized(self, material_ids: &[MaterialId]) -> Self;
+
+    /// The distance along the ray to the closest surface in front of `origin` and the normal
+    /// there at time 0, used for picking
+    fn intersect(
+        &self,
+        origin: cgmath::Vector4<f32>,
+        direction: cgmath::Vector4<f32>,
+    ) -> Option<(f32, cgmath::Vector4<f32>)>;
+
+    /// Shows the shape's properties, returns whether any of them changed
+    #[cfg(feature = "editor")]
+    fn edit(&mut self, ui: &mut eframe::egui::Ui, context: &crate::EditContext) -> bool;
+}
+
+macro_rules! primitives {
+    ($($kind:ident($primitive:ty)),* $(,)?) => {
+        /// Every [`Primitive`], in the order the editor lists them
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+        pub enum PrimitiveKind {
+            $($kind,)*
+        }
+
+        impl PrimitiveKind {
+            pub const ALL: &'static [Self] = &[$(Self::$kind),*];
+
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Self::$kind => <$primitive as Primitive>::NAME,)*
+                }
+            }
+
+            pub fn plural_name(self) -> &'static str {
+                match self {
+                    $(Self::$kind => <$primitive as Primitive>::PLURAL_NAME,)*
+                }
+            }
+
+            pub fn shader_name(self) -> &'static str {
+                match self {
+                    $(Self::$kind => <$primitive as Primitive>::SHADER_NAME,)*
+                }
+            }
+
+            pub fn culled(self) -> bool {
+                match self {
+                    $(Self::$kind => <$primitive as Primitive>::CULLED,)*
+                }
+            }
+
+            pub fn bvh(self) -> bool {
+                match self {
+                    $(Self::$kind => <$primitive as Primitive>::BVH,)*
+                }
+            }
+        }
+
+        /// What an object is, along with what is uploaded for it
+        #[derive(Clone, Copy, PartialEq)]
+        pub enum ObjectData {
+            $($kind($primitive),)*
+        }
+
+        $(impl From<$primitive> for ObjectData {
+            fn from(primitive: $primitive) -> Self {
+                Self::$kind(primitive)
+            }
+        })*
+
+        /// Identifies an object in a [`Scene`](crate::Scene) by its kind and its index among
+        /// the objects of that kind
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+        pub enum ObjectRef {
+            $($kind(usize),)*
+        }
+
+        impl ObjectRef {
+            pub fn new(kind: PrimitiveKind, index: usize) -> Self {
+                match kind {
+                    $(PrimitiveKind::$kind => Self::$kind(index),)*
+                }
+            }
+
+            pub fn kind(self) -> PrimitiveKind {
+                match self {
+                    $(Self::$kind(_) => PrimitiveKind::$kind,)*
+                }
+            }
+
+            pub fn index(self) -> usize {
+                match self {
+                    $(Self::$kind(index) => index,)*
+                }
+            }
+        }
+
+        /// Calls `$f` with the primitive inside `$data`, whatever its type
+        macro_rules! with_primitive {
+            ($data:expr, $primitive_name:ident => $f:expr) => {
+                match $data {
+                    $(ObjectData::$kind($primitive_name) => $f,)*
+                }
+            };
+        }
+    };
+}
+
+primitives! {
+    HyperSphere(GpuHyperSphere),
+    HyperPlane(GpuHyperPlane),
+}
+
+impl ObjectData {
+    /// A new object of `kind` at the origin
+    pub fn new(kind: PrimitiveKind, material: MaterialId) -> Self {
+        match kind {
+            PrimitiveKind::HyperSphere => GpuHyperSphere::new(material).into(),
+            PrimitiveKind::HyperPlane => GpuHyperPlane::new(material).into(),
+        }
+    }
+
+    pub fn kind(&self) -> PrimitiveKind {
+        with_primitive!(self, primitive => kind_of(primitive))
+    }
+
+    /// The primitive if it is a `T`
+    pub fn get<T: Primitive>(&self) -> Option<&T> {
+        with_primitive!(self, primitive => (primitive as &dyn Any).downcast_ref())
+    }
+
+    pub fn get_mut<T: Primitive>(&mut self) -> Option<&mut T> {
+        with_primitive!(self, primitive => (primitive as &mut dyn Any).downcast_mut())
+    }
+
+    pub fn material(&self) -> MaterialId {
+        with_primitive!(self, primitive => primitive.material())
+    }
+
+    pub fn set_material(&mut self, material: MaterialId) {
+        with_primitive!(self, primitive => primitive.set_material(material))
+    }
+
+    pub fn position(&self) -> cgmath::Vector4<f32> {
+        with_primitive!(self, primitive => primitive.position())
+    }
+
+    pub fn position_mut(&mut self) -> &mut cgmath::Vector4<f32> {
+        with_primitive!(self, primitive => primitive.position_mut())
+    }
+
+    pub fn placed(self, placement: &Placement) -> Self {
+        with_primitive!(self, primitive => primitive.placed(placement).into())
+    }
+
+    pub fn problems(&self, material_ids: &[MaterialId]) -> Vec<ObjectProblem> {
+        with_primitive!(self, primitive => primitive.problems(material_ids))
+    }
+
+    pub fn sanitized(self, material_ids: &[MaterialId]) -> Self {
+        with_primitive!(self, primitive => primitive.sanitized(material_ids).into())
+    }
+
+    pub fn intersect(
+        &self,
+        origin: cgmath::Vector4<f32>,
+        direction: cgmath::Vector4<f32>,
+    ) -> Option<(f32, cgmath::Vector4<f32>)> {
+        with_primitive!(self, primitive => primitive.intersect(origin, direction))
+    }
+
+    #[cfg(feature = "editor")]
+    pub fn edit(&mut self, ui: &mut eframe::egui::Ui, context: &crate::EditContext) -> bool {
+        with_primitive!(self, primitive => primitive.edit(ui, context))
+    }
+}
+
+fn kind_of<T: Primitive>(_: &T) -> PrimitiveKind {
+    T::KIND
+}
+
+/// The `primitives.wgsl` shader module, generated so that the closest hit is searched for
+/// among every [`Primitive`]. It imports their shader modules and defines
+/// `closest_primitive_hit(ray: Ray, closest_hit: Hit) -> Hit`, along with
+/// `closest_visible_primitive_hit` when `CULLING` is defined. Camera rays that are culled don't
+/// use the BVH, the visible ones are usually few
+pub fn primitives_shader_module() -> String {
+    let mut source = String::from("// generated by `primitives_shader_module`\n");
+    for kind in PrimitiveKind::ALL {
+        writeln!(source, "#import \"{}.wgsl\"", kind.shader_name()).unwrap();
+    }
+
+    source.push_str("\nfn closest_primitive_hit(ray: Ray, closest_hit: Hit) -> Hit {\n");
+    source.push_str("    var closest_hit = closest_hit;\n");
+    for kind in PrimitiveKind::ALL {
+        let name = kind.shader_name();
+        if kind.bvh() {
+            source.push_str("#ifdef BVH\n");
+            writeln!(
+                source,
+                "    closest_hit = closest_bvh_{name}_hit(ray, closest_hit);"
+            )
+            .unwrap();
+            source.push_str("#else\n");
+            write_hit_loop(&mut source, name, &format!("{name}_count()"), "i");
+            source.push_str("#endif\n");
+        } else {
+            write_hit_loop(&mut source, name, &format!("{name}_count()"), "i");
+        }
+    }
+    source.push_str("    return closest_hit;\n}\n");
+
+    source.push_str("\n#ifdef CULLING\n");
+    source.push_str("fn closest_visible_primitive_hit(ray: Ray, closest_hit: Hit) -> Hit {\n");
+    source.push_str("    var closest_hit = closest_hit;\n");
+    for kind in PrimitiveKind::ALL {
+        let name = kind.shader_name();
+        if kind.culled() {
+            writeln!(
+                source,
+                "    let visible_{name}_count = atomicLoad(&visible_{name}s.count);"
+            )
+            .unwrap();
+            write_hit_loop(
+                &mut source,
+                name,
+                &format!("visible_{name}_count"),
+                &format!("visible_{name}s.indices[i]"),
+            );
+        } else {
+            write_hit_loop(&mut source, name, &format!("{name}_count()"), "i");
+        }
+    }
+    source.push_str("    return closest_hit;\n}\n#endif\n");
+    source
+}
+
+fn write_hit_loop(source: &mut String, shader_name: &str, count: &str, index: &str) {
+    writeln!(
+        source,
+        "    for (var i = 0u; i < {count}; i += 1u) {{
+        let hit = {shader_name}_hit(ray, {index});
+        if hit.hit && hit.distance < closest_hit.distance {{
+            closest_hit = hit;
+        }}
+    }}"
+    )
+    .unwrap();
+}
@@ -0,0 +1,246 @@
+use crate::{
+    Aovs, BvhMode, BvhStats, GpuCamera, GpuError, HdrImage, ObjectFormat, PixelPath, RenderOptions,
+    RenderPath, Renderer, Scene, Tile,
+};
+
+/// A backend that ray traces a [`Scene`] into an `Rgba8Unorm` texture, so the app can swap
+/// between them at runtime. The texture holds colors encoded by [`crate::ColorManagement`],
+/// see [`crate::DisplayTexture`] for sampling it
+pub trait RayTracer {
+    fn size(&self) -> (usize, usize);
+    fn texture_view(&self) -> &wgpu::TextureView;
+    /// Recreates the output texture if it is the wrong size, returns whether it was recreated
+    fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: usize,
+        height: usize,
+    ) -> bool;
+    fn update_camera(&mut self, queue: &wgpu::Queue, camera: &GpuCamera) -> Result<(), GpuError>;
+    /// Only needs to update the parts of `scene` marked in `scene.changes`
+    fn update_scene(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scene: &Scene,
+    ) -> Result<(), GpuError>;
+    fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        options: RenderOptions,
+    );
+    /// How many renders are averaged together in the output, for backends that accumulate
+    fn accumulated_frames(&self) -> u32 {
+        0
+    }
+    /// Makes the next render start averaging from scratch
+    fn reset_accumulation(&mut self) {}
+    /// Splits rendering into tiles of at most `tile_size` pixels on each side, for backends that can
+    fn set_tile_size(&mut self, _device: &wgpu::Device, _tile_size: Option<usize>) {}
+    /// Only renders the part of the output inside `region`, for backends that can
+    fn set_region(&mut self, _device: &wgpu::Device, _region: Option<Tile>) {}
+    /// How objects are stored from the next scene update on, for backends that can pack them.
+    /// Returns whether it changed, the objects have to be updated again for it to apply
+    fn set_object_format(&mut self, _object_format: ObjectFormat) -> bool {
+        false
+    }
+    /// Whether a BVH is built over the hyper spheres from the next scene update on, for
+    /// backends that can. Returns whether it changed, like [`RayTracer::set_object_format`]
+    fn set_bvh_mode(&mut self, _bvh_mode: BvhMode) -> bool {
+        false
+    }
+    /// Of the BVH built by the last scene update, if there is one
+    fn bvh_stats(&self) -> Option<BvhStats> {
+        None
+    }
+    /// Path traces in wavefront kernels when the render options allow it, for backends that can
+    fn set_wavefront(&mut self, _wavefront: bool) {}
+    /// How many tiles of the current pass have been rendered, and how many there are
+    fn tile_progress(&self) -> (usize, usize) {
+        (0, 1)
+    }
+    /// Rebuilds any shaders from their source on the next render
+    fn reload_shaders(&mut self) {}
+    /// Traces a single ray through `pixel` and returns every bounce it took, for backends that can
+    fn inspect_pixel(
+        &mut self,
+        _device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        _options: RenderOptions,
+        _pixel: (usize, usize),
+    ) -> Result<Option<PixelPath>, GpuError> {
+        Ok(None)
+    }
+    /// The unclamped linear average of every frame so far, for backends that accumulate
+    fn read_hdr(
+        &self,
+        _device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+    ) -> Result<Option<HdrImage>, GpuError> {
+        Ok(None)
+    }
+    /// The AOVs averaged so far, for backends that can write them
+    fn read_aovs(
+        &self,
+        _device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+    ) -> Result<Option<Aovs>, GpuError> {
+        Ok(None)
+    }
+}
+
+impl RayTracer for Renderer {
+    fn size(&self) -> (usize, usize) {
+        Renderer::size(self)
+    }
+
+    fn texture_view(&self) -> &wgpu::TextureView {
+        Renderer::texture_view(self)
+    }
+
+    fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: usize,
+        height: usize,
+    ) -> bool {
+        Renderer::resize(self, device, queue, width, height)
+    }
+
+    fn update_camera(&mut self, queue: &wgpu::Queue, camera: &GpuCamera) -> Result<(), GpuError> {
+        self.upload_camera(queue, camera)
+    }
+
+    fn update_scene(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scene: &Scene,
+    ) -> Result<(), GpuError> {
+        self.upload_scene(device, queue, scene)
+    }
+
+    fn render(
+        &mut self,
+        device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        options: RenderOptions,
+    ) {
+        Renderer::render(self, device, encoder, options);
+    }
+
+    fn accumulated_frames(&self) -> u32 {
+        Renderer::accumulated_frames(self)
+    }
+
+    fn reset_accumulation(&mut self) {
+        Renderer::reset_accumulation(self);
+    }
+
+    fn set_tile_size(&mut self, device: &wgpu::Device, tile_size: Option<usize>) {
+        Renderer::set_tile_size(self, device, tile_size);
+    }
+
+    fn set_region(&mut self, device: &wgpu::Device, region: Option<Tile>) {
+        Renderer::set_region(self, device, region);
+    }
+
+    fn set_object_format(&mut self, object_format: ObjectFormat) -> bool {
+        let changed = object_format != self.object_format();
+        Renderer::set_object_format(self, object_format);
+        changed
+    }
+
+    fn set_bvh_mode(&mut self, bvh_mode: BvhMode) -> bool {
+        let changed = bvh_mode != self.bvh_mode();
+        Renderer::set_bvh_mode(self, bvh_mode);
+        changed
+    }
+
+    fn bvh_stats(&self) -> Option<BvhStats> {
+        Renderer::bvh_stats(self)
+    }
+
+    fn set_wavefront(&mut self, wavefront: bool) {
+        Renderer::set_wavefront(self, wavefront);
+    }
+
+    fn tile_progress(&self) -> (usize, usize) {
+        Renderer::tile_progress(self)
+    }
+
+    fn inspect_pixel(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        options: RenderOptions,
+        pixel: (usize, usize),
+    ) -> Result<Option<PixelPath>, GpuError> {
+        Renderer::inspect_pixel(self, device, queue, options, pixel)
+    }
+
+    fn read_hdr(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Option<HdrImage>, GpuError> {
+        Renderer::read_hdr(self, device, queue)
+    }
+
+    fn read_aovs(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Option<Aovs>, GpuError> {
+        Renderer::read_aovs(self, device, queue)
+    }
+
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    fn reload_shaders(&mut self) {
+        self.pipeline_cache.invalidate();
+        Renderer::reset_accumulation(self);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Backend {
+    Gpu(RenderPath),
+    #[cfg(feature = "cpu-reference")]
+    Cpu,
+}
+
+impl Backend {
+    /// Every backend that can be used with `device`
+    pub fn available(device: &wgpu::Device) -> Vec<Self> {
+        let mut backends = vec![];
+        if RenderPath::for_device(device) == RenderPath::Compute {
+            backends.push(Backend::Gpu(RenderPath::Compute));
+        }
+        backends.push(Backend::Gpu(RenderPath::Fragment));
+        #[cfg(feature = "cpu-reference")]
+        backends.push(Backend::Cpu);
+        backends
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Gpu(RenderPath::Compute) => "GPU Compute",
+            Backend::Gpu(RenderPath::Fragment) => "GPU Fragment",
+            #[cfg(feature = "cpu-reference")]
+            Backend::Cpu => "CPU Reference",
+        }
+    }
+
+    pub fn create(self, device: &wgpu::Device, queue: &wgpu::Queue) -> Box<dyn RayTracer> {
+        match self {
+            Backend::Gpu(render_path) => Box::new(Renderer::new(device, queue, render_path)),
+            #[cfg(feature = "cpu-reference")]
+            Backend::Cpu => Box::new(crate::cpu_reference::CpuRayTracer::new(device)),
+        }
+    }
+}
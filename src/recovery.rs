@@ -0,0 +1,152 @@
+//! Writes the scene and camera to a recovery file when the editor panics, so the next launch
+//! can offer to bring them back
+
+use crate::{json::Json, scene_script, Camera, Config, Scene};
+use std::{path::PathBuf, sync::Mutex};
+
+static SNAPSHOT: Mutex<Option<(Scene, Camera)>> = Mutex::new(None);
+
+/// The scene and camera from a session that crashed
+#[derive(Clone)]
+pub struct Recovery {
+    /// A script that rebuilds the scene, like [`scene_script`] makes
+    pub scene_source: String,
+    /// Only the position, rotation and field of view are kept, the rest are defaults
+    pub camera: Camera,
+}
+
+impl Recovery {
+    /// Next to the config file
+    pub fn path() -> Option<PathBuf> {
+        Some(Config::path()?.with_file_name("recovery.json"))
+    }
+
+    /// Makes [`Recovery::snapshot`] what gets written to [`Recovery::path`] if anything panics,
+    /// the hook that was there before still runs afterwards
+    pub fn install_panic_hook() {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            Self::save_snapshot();
+            previous(info);
+        }));
+    }
+
+    /// Writes the last [`Recovery::snapshot`] to [`Recovery::path`], for when the session can't
+    /// go on. Does nothing if there is no snapshot or it is being updated
+    pub fn save_snapshot() {
+        // the session could have ended while the snapshot was being updated
+        if let Ok(snapshot) = SNAPSHOT.try_lock() {
+            if let Some((scene, camera)) = &*snapshot {
+                _ = Self::from_scene(scene, camera).save();
+            }
+        }
+    }
+
+    /// Replaces the state the panic hook writes out
+    pub fn snapshot(scene: &Scene, camera: &Camera) {
+        *SNAPSHOT.lock().unwrap_or_else(|error| error.into_inner()) =
+            Some((scene.clone(), *camera));
+    }
+
+    pub fn from_scene(scene: &Scene, camera: &Camera) -> Self {
+        Self {
+            scene_source: scene_script(scene),
+            camera: *camera,
+        }
+    }
+
+    /// The recovery file left by the last session, if it crashed
+    pub fn load() -> Option<Result<Self, String>> {
+        let source = std::fs::read_to_string(Self::path()?).ok()?;
+        Some(Self::from_json(&source))
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(path) = Self::path() else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "could not find a config directory",
+            ));
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_json())
+    }
+
+    /// Deletes the recovery file, once it has been restored or turned down
+    pub fn discard() {
+        if let Some(path) = Self::path() {
+            _ = std::fs::remove_file(path);
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let camera = &self.camera;
+        let position = camera.position;
+        Json::Object(vec![
+            ("scene".into(), Json::String(self.scene_source.clone())),
+            (
+                "camera".into(),
+                Json::Object(vec![
+                    (
+                        "position".into(),
+                        Json::Array(
+                            [position.x, position.y, position.z, position.w]
+                                .map(|x| Json::Number(x as _))
+                                .to_vec(),
+                        ),
+                    ),
+                    ("pitch".into(), Json::Number(camera.pitch as _)),
+                    ("yaw".into(), Json::Number(camera.yaw as _)),
+                    ("weird_pitch".into(), Json::Number(camera.weird_pitch as _)),
+                    ("weird_yaw".into(), Json::Number(camera.weird_yaw as _)),
+                    ("fov".into(), Json::Number(camera.fov as _)),
+                ]),
+            ),
+        ])
+        .to_string_pretty()
+    }
+
+    pub fn from_json(source: &str) -> Result<Self, String> {
+        let json = Json::parse(source)?;
+        let scene_source = json
+            .get("scene")
+            .and_then(Json::as_str)
+            .ok_or("'scene' should be a string")?
+            .to_string();
+
+        let mut camera = Camera::default();
+        if let Some(json) = json.get("camera") {
+            let number = |key: &str, value: &mut f32| {
+                if let Some(json) = json.get(key) {
+                    *value = json
+                        .as_f32()
+                        .ok_or_else(|| format!("camera '{key}' should be a number"))?;
+                }
+                Ok::<_, String>(())
+            };
+            if let Some(position) = json.get("position") {
+                let array = position
+                    .as_array()
+                    .filter(|array| array.len() == 4)
+                    .ok_or("camera 'position' should be 4 numbers")?;
+                for (value, json) in AsMut::<[f32; 4]>::as_mut(&mut camera.position)
+                    .iter_mut()
+                    .zip(array)
+                {
+                    *value = json.as_f32().ok_or("camera 'position' should be numbers")?;
+                }
+            }
+            number("pitch", &mut camera.pitch)?;
+            number("yaw", &mut camera.yaw)?;
+            number("weird_pitch", &mut camera.weird_pitch)?;
+            number("weird_yaw", &mut camera.weird_yaw)?;
+            number("fov", &mut camera.fov)?;
+        }
+        Ok(Self {
+            scene_source,
+            camera,
+        })
+    }
+}
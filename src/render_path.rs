@@ -0,0 +1,136 @@
+use crate::WavefrontPipelines;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RenderPath {
+    /// Ray traces in a compute shader writing to a storage texture
+    Compute,
+    /// Ray traces in a full-screen fragment shader, for devices without compute shaders or storage textures.
+    /// It reads the scene from textures, so it fits in WebGL2's limits
+    Fragment,
+}
+
+impl RenderPath {
+    pub fn for_device(device: &wgpu::Device) -> Self {
+        let limits = device.limits();
+        if limits.max_storage_textures_per_shader_stage == 0
+            || limits.max_compute_workgroups_per_dimension == 0
+        {
+            RenderPath::Fragment
+        } else {
+            RenderPath::Compute
+        }
+    }
+
+    pub fn shader_stages(self) -> wgpu::ShaderStages {
+        match self {
+            RenderPath::Compute => wgpu::ShaderStages::COMPUTE,
+            RenderPath::Fragment => wgpu::ShaderStages::FRAGMENT,
+        }
+    }
+
+    pub fn texture_usages(self) -> wgpu::TextureUsages {
+        match self {
+            RenderPath::Compute => {
+                wgpu::TextureUsages::STORAGE_BINDING
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC
+            }
+            RenderPath::Fragment => {
+                wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC
+            }
+        }
+    }
+
+    pub fn shader_defines(self) -> &'static [&'static str] {
+        match self {
+            RenderPath::Compute => &["COMPUTE"],
+            RenderPath::Fragment => &["FRAGMENT"],
+        }
+    }
+
+    /// `culling` is whether `module` was built with `CULLING`, only the compute path can cull
+    pub(crate) fn create_pipeline(
+        self,
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        module: &wgpu::ShaderModule,
+        culling: bool,
+    ) -> RayTracingPipeline {
+        match self {
+            RenderPath::Compute => RayTracingPipeline::Compute {
+                trace: device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some("Ray Tracing Pipeline"),
+                    layout: Some(layout),
+                    module,
+                    entry_point: "ray_trace",
+                }),
+                cull: culling.then(|| {
+                    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                        label: Some("Culling Pipeline"),
+                        layout: Some(layout),
+                        module,
+                        entry_point: "cull_hyper_spheres",
+                    })
+                }),
+            },
+            RenderPath::Fragment => RayTracingPipeline::Fragment(device.create_render_pipeline(
+                &wgpu::RenderPipelineDescriptor {
+                    label: Some("Ray Tracing Pipeline"),
+                    layout: Some(layout),
+                    vertex: wgpu::VertexState {
+                        module,
+                        entry_point: "vertex",
+                        buffers: &[],
+                    },
+                    primitive: wgpu::PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState::default(),
+                    fragment: Some(wgpu::FragmentState {
+                        module,
+                        entry_point: "ray_trace",
+                        targets: &[Some(wgpu::TextureFormat::Rgba8Unorm.into())],
+                    }),
+                    multiview: None,
+                },
+            )),
+        }
+    }
+}
+
+pub(crate) enum RayTracingPipeline {
+    Compute {
+        trace: wgpu::ComputePipeline,
+        /// Fills the visible hyper spheres list before `trace` runs
+        cull: Option<wgpu::ComputePipeline>,
+    },
+    Fragment(wgpu::RenderPipeline),
+    Wavefront(WavefrontPipelines),
+}
+
+/// The limits to request for `adapter`, dropping the compute and storage texture requirements
+/// if the adapter can't provide them so that the fragment shader path can be used instead
+pub fn device_limits(adapter: &wgpu::Adapter) -> wgpu::Limits {
+    let adapter_limits = adapter.limits();
+    let supports_compute = adapter
+        .get_downlevel_capabilities()
+        .flags
+        .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS)
+        && adapter_limits.max_storage_textures_per_shader_stage > 0;
+    if supports_compute {
+        wgpu::Limits::default()
+    } else {
+        wgpu::Limits {
+            max_storage_textures_per_shader_stage: 0,
+            max_compute_workgroup_storage_size: 0,
+            max_compute_invocations_per_workgroup: 0,
+            max_compute_workgroup_size_x: 0,
+            max_compute_workgroup_size_y: 0,
+            max_compute_workgroup_size_z: 0,
+            max_compute_workgroups_per_dimension: 0,
+            ..wgpu::Limits::downlevel_defaults()
+        }
+        .using_resolution(adapter_limits)
+    }
+}
@@ -0,0 +1,287 @@
+use crate::{Camera, Caption, GpuCamera, RenderPath, Renderer, Scene, SceneChanges, Script};
+use std::path::{Path, PathBuf};
+
+/// Where a queued render gets its scene from
+#[derive(Clone)]
+pub enum JobScene {
+    /// A copy of a scene taken when the job was queued, later edits don't affect it
+    Snapshot(Box<Scene>),
+    /// A `.script` scene file, read when the job starts
+    File(PathBuf),
+}
+
+/// A still image for [`RenderQueue`] to render and write to a PNG file, or a linear HDR EXR
+/// file if `output` ends in `.exr`
+#[derive(Clone)]
+pub struct RenderJob {
+    pub name: String,
+    pub scene: JobScene,
+    pub camera: Camera,
+    pub width: usize,
+    pub height: usize,
+    /// Samples per pixel in the finished image, rounded up to a whole number of frames of
+    /// [`Camera::sample_count`] samples each
+    pub samples: u32,
+    pub output: PathBuf,
+    /// Burned into PNG output, EXR output is left as the raw render
+    pub caption: Option<Caption>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum JobStatus {
+    Queued,
+    /// How far through its frames the job is, from 0 to 1
+    Rendering(f32),
+    Done,
+    Failed(String),
+}
+
+struct ActiveJob {
+    index: usize,
+    renderer: Renderer,
+    camera: Camera,
+    light_group_weights: cgmath::Vector4<f32>,
+    frames_rendered: u32,
+    frames: u32,
+}
+
+/// Renders queued jobs one after another, a frame at a time so the editor keeps running
+/// while they are processed
+#[derive(Default)]
+pub struct RenderQueue {
+    jobs: Vec<(RenderJob, JobStatus)>,
+    active: Option<ActiveJob>,
+    pub paused: bool,
+}
+
+impl RenderQueue {
+    pub fn push(&mut self, job: RenderJob) {
+        self.jobs.push((job, JobStatus::Queued));
+    }
+
+    pub fn jobs(&self) -> &[(RenderJob, JobStatus)] {
+        &self.jobs
+    }
+
+    /// Removes a job that isn't being rendered, returns whether it was removed
+    pub fn remove(&mut self, index: usize) -> bool {
+        if index >= self.jobs.len() || self.active_index() == Some(index) {
+            return false;
+        }
+        self.jobs.remove(index);
+        if let Some(active) = &mut self.active {
+            if active.index > index {
+                active.index -= 1;
+            }
+        }
+        true
+    }
+
+    /// Drops the job being rendered so it starts over, for when the GPU device is replaced
+    pub fn restart(&mut self) {
+        if let Some(active) = self.active.take() {
+            self.jobs[active.index].1 = JobStatus::Queued;
+        }
+    }
+
+    /// Removes every job that is done or failed
+    pub fn clear_finished(&mut self) {
+        let mut index = 0;
+        while index < self.jobs.len() {
+            if matches!(self.jobs[index].1, JobStatus::Done | JobStatus::Failed(_)) {
+                self.remove(index);
+            } else {
+                index += 1;
+            }
+        }
+    }
+
+    /// The index of the job being rendered
+    pub fn active_index(&self) -> Option<usize> {
+        self.active.as_ref().map(|active| active.index)
+    }
+
+    /// Whether there is a job being rendered or waiting to be
+    pub fn is_busy(&self) -> bool {
+        self.active.is_some()
+            || self
+                .jobs
+                .iter()
+                .any(|(_, status)| *status == JobStatus::Queued)
+    }
+
+    /// Renders the next frame of the current job, starting the next queued job if there isn't
+    /// one. Finished images are read back and written out, which blocks until the GPU is done
+    pub fn step(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if self.paused {
+            return;
+        }
+        if self.active.is_none() {
+            let Some(index) = self
+                .jobs
+                .iter()
+                .position(|(_, status)| *status == JobStatus::Queued)
+            else {
+                return;
+            };
+            match Self::start(&self.jobs[index].0, index, device, queue) {
+                Ok(active) => {
+                    self.jobs[index].1 = JobStatus::Rendering(0.0);
+                    self.active = Some(active);
+                }
+                Err(error) => {
+                    log::error!("Failed to start {}: {error}", self.jobs[index].0.name);
+                    self.jobs[index].1 = JobStatus::Failed(error);
+                }
+            }
+            return;
+        }
+
+        let Some(active) = &mut self.active else {
+            return;
+        };
+        let result = active
+            .renderer
+            .upload_camera(
+                queue,
+                &GpuCamera {
+                    light_group_weights: active.light_group_weights,
+                    ..active.camera.to_gpu(active.frames_rendered)
+                },
+            )
+            .map_err(|error| error.to_string())
+            .map(|()| {
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Queue Command Encoder"),
+                });
+                active
+                    .renderer
+                    .render(device, &mut encoder, active.camera.render_options());
+                queue.submit([encoder.finish()]);
+                active.frames_rendered += 1;
+            });
+        let (job, status) = &mut self.jobs[active.index];
+        *status = match result {
+            Ok(()) if active.frames_rendered < active.frames => {
+                JobStatus::Rendering(active.frames_rendered as f32 / active.frames as f32)
+            }
+            Ok(()) => match write_output(job, active, device, queue) {
+                Ok(()) => {
+                    log::info!("Rendered {} to {}", job.name, job.output.display());
+                    JobStatus::Done
+                }
+                Err(error) => JobStatus::Failed(error),
+            },
+            Err(error) => JobStatus::Failed(error),
+        };
+        if let JobStatus::Failed(error) = status {
+            log::error!("Failed to render {}: {error}", job.name);
+        }
+        if !matches!(status, JobStatus::Rendering(_)) {
+            self.active = None;
+        }
+    }
+
+    fn start(
+        job: &RenderJob,
+        index: usize,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<ActiveJob, String> {
+        let mut scene = match &job.scene {
+            JobScene::Snapshot(scene) => (**scene).clone(),
+            JobScene::File(path) => load_scene_file(path)?,
+        };
+        scene.changes = SceneChanges::ALL;
+
+        let render_path = RenderPath::for_device(device);
+        let mut renderer = Renderer::new(device, queue, render_path);
+        renderer.resize(device, queue, job.width.max(1), job.height.max(1));
+        renderer
+            .upload_scene(device, queue, &scene)
+            .map_err(|error| error.to_string())?;
+
+        let mut camera = job.camera;
+        let samples = job.samples.max(1);
+        // only the compute path averages frames together
+        let frames = if render_path == RenderPath::Compute {
+            samples.div_ceil(camera.sample_count.max(1))
+        } else {
+            camera.sample_count = samples;
+            1
+        };
+        Ok(ActiveJob {
+            index,
+            renderer,
+            camera,
+            light_group_weights: scene.light_group_weights(),
+            frames_rendered: 0,
+            frames,
+        })
+    }
+}
+
+/// Runs a `.script` scene file on the default scene, like dropping it on the editor does
+pub fn load_scene_file(path: &Path) -> Result<Scene, String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read {}: {error}", path.display()))?;
+    let mut scene = Scene::default();
+    Script::compile(&source)?.run(&mut scene, &mut vec![])?;
+    Ok(scene)
+}
+
+/// Writes what the job's renderer has accumulated to its output, as HDR if it ends in `.exr`
+fn write_output(
+    job: &RenderJob,
+    active: &ActiveJob,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+) -> Result<(), String> {
+    let (renderer, path) = (&active.renderer, job.output.as_path());
+    let is_exr = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("exr"));
+    if is_exr {
+        renderer
+            .read_hdr(device, queue)
+            .map_err(|error| error.to_string())?
+            .ok_or("EXR output needs the compute render path")?
+            .write_exr(path)
+    } else {
+        let (width, height) = renderer.size();
+        let mut pixels = renderer
+            .read_pixels(device, queue)
+            .map_err(|error| error.to_string())?;
+        if let Some(caption) = &job.caption {
+            caption.burn(
+                &mut pixels,
+                width,
+                height,
+                &active.camera,
+                active.frames_rendered * active.camera.sample_count,
+                std::time::SystemTime::now(),
+            );
+        }
+        write_png(path, width, height, &pixels)
+    }
+}
+
+/// Writes tightly packed RGBA8 rows like [`Renderer::read_pixels`] returns to a PNG file
+pub fn write_png(path: &Path, width: usize, height: usize, pixels: &[u8]) -> Result<(), String> {
+    let png = encode_png(width, height, pixels)?;
+    std::fs::write(path, png)
+        .map_err(|error| format!("Failed to write {}: {error}", path.display()))
+}
+
+/// Encodes tightly packed RGBA8 rows as a PNG in memory
+pub fn encode_png(width: usize, height: usize, pixels: &[u8]) -> Result<Vec<u8>, String> {
+    let mut png = vec![];
+    let mut encoder = png::Encoder::new(&mut png, width as _, height as _);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(pixels))
+        .map_err(|error| format!("Failed to encode the PNG: {error}"))?;
+    Ok(png)
+}
@@ -0,0 +1,1694 @@
+use crate::{
+    blue_noise, split_into_tiles, Aovs, Bvh, BvhMode, BvhStats, BvhUpdate, Environment, GpuBvh,
+    GpuCamera, GpuDebugPath, GpuEnvironment, GpuError, GpuHyperPlane, GpuHyperPlanes,
+    GpuHyperSphere, GpuHyperSpheres, GpuMaterial, GpuMaterials, GpuNodeInstruction,
+    GpuNodePrograms, GpuPackedHyperPlanes, GpuPackedHyperSpheres, GpuTile, HdrImage, LightGroup,
+    ObjectFormat, PipelineCache, PixelPath, Projection, RayTracingPipeline, RenderMode,
+    RenderOptions, RenderPath, Scene, SceneBuffer, Tile, Wavefront, BLUE_NOISE_SIZE, WAVE_SIZE,
+};
+use encase::{
+    ArrayLength, DynamicStorageBuffer, DynamicUniformBuffer, ShaderSize, ShaderType, StorageBuffer,
+    UniformBuffer,
+};
+use std::ops::Range;
+use wgpu::util::DeviceExt;
+
+/// How many frames can be recorded before the GPU has to have finished the oldest one
+const FRAMES_IN_FLIGHT: usize = 2;
+
+/// Owns the GPU resources for ray tracing the scene into an `Rgba8Unorm` texture, the colors
+/// are averaged in linear and encoded by [`crate::ColorManagement`] when they are stored
+///
+/// Nothing is drawn until [`Renderer::render`] is recorded, the camera and scene are only
+/// read by the GPU so they can be uploaded whenever they change
+pub struct Renderer {
+    texture_width: usize,
+    texture_height: usize,
+    texture: wgpu::Texture,
+    texture_view: wgpu::TextureView,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    texture_bind_group: wgpu::BindGroup,
+    accumulation_buffer: wgpu::Buffer,
+    /// if `accumulation_buffer` has room for every light group, see [`RenderOptions::light_groups`]
+    accumulating_light_groups: bool,
+    /// if `accumulation_buffer` has room for the AOVs, and `aov_texture` is the size of the
+    /// output, see [`RenderOptions::aovs`]
+    accumulating_aovs: bool,
+    /// from the last uploaded camera, to weigh the light groups of `accumulation_buffer` for [`Renderer::read_hdr`]
+    light_group_weights: cgmath::Vector4<f32>,
+    /// [`Aovs::LAYER_COUNT`] `Rgba32Float` layers, only used on the compute path
+    aov_texture: wgpu::Texture,
+    aov_texture_view: wgpu::TextureView,
+    accumulated_frames: u32,
+    tile_size: Option<usize>,
+    /// only this part of the texture is rendered, the rest keeps what it had
+    region: Option<Tile>,
+    tiles: Vec<Tile>,
+    /// the next tile to render
+    tile_index: usize,
+    /// how many tiles of the current accumulation pass have been rendered
+    tiles_rendered: usize,
+    /// what the wavefront path tracer splits the tiles into, each tile's are in `tile_waves`
+    waves: Vec<Tile>,
+    tile_waves: Vec<Range<usize>>,
+    /// every tile's rect followed by every wave's, one per `tile_uniform_stride` bytes
+    tile_uniform_buffer: wgpu::Buffer,
+    tile_uniform_stride: u64,
+    /// written by the single ray dispatch of [`Renderer::inspect_pixel`], only on the compute path
+    debug_path_buffer: wgpu::Buffer,
+    /// one per frame in flight so a frame's camera isn't overwritten while the GPU may still be reading it
+    camera_uniform_buffers: Vec<wgpu::Buffer>,
+    camera_bind_groups: Vec<wgpu::BindGroup>,
+    frame: usize,
+    /// from the last uploaded camera, the wavefront path tracer records a pass for each
+    /// sample and bounce
+    camera_sample_count: u32,
+    camera_bounce_count: u32,
+    /// shared by every frame, it only changes when the scene does
+    environment_uniform_buffer: wgpu::Buffer,
+    hyper_spheres_buffer: SceneBuffer,
+    hyper_planes_buffer: SceneBuffer,
+    /// what was last written to the object buffers, so unchanged objects aren't written again
+    uploaded_hyper_spheres: Vec<u8>,
+    uploaded_hyper_planes: Vec<u8>,
+    object_upload_size: usize,
+    hyper_sphere_count: usize,
+    /// a count and an index per hyper sphere written by the culling pass, only on the compute path
+    visible_hyper_spheres_buffer: Option<wgpu::Buffer>,
+    visible_hyper_spheres_buffer_size: usize,
+    /// the [`GpuBvh`] over the hyper spheres, only on the compute path
+    bvh_buffer: Option<SceneBuffer>,
+    uploaded_bvh: Vec<u8>,
+    bvh_mode: BvhMode,
+    /// the BVH in `bvh_buffer`, `None` if the last upload didn't build one
+    bvh: Option<Bvh>,
+    object_format: ObjectFormat,
+    /// whether the last upload was packed, the shader has to match
+    objects_packed: bool,
+    objects_bind_group_layout: wgpu::BindGroupLayout,
+    objects_bind_group: wgpu::BindGroup,
+    materials_buffer: SceneBuffer,
+    /// whether the last uploaded materials have blends, the shader only follows them then
+    materials_blended: bool,
+    node_programs_buffer: SceneBuffer,
+    /// whether the last uploaded materials run node programs, the shader only does then
+    materials_have_nodes: bool,
+    materials_bind_group_layout: wgpu::BindGroupLayout,
+    materials_bind_group: wgpu::BindGroup,
+    output_size_uniform_buffer: wgpu::Buffer,
+    /// the paths of [`RenderOptions::wavefront`], only on the compute path
+    wavefront: Option<Wavefront>,
+    use_wavefront: bool,
+    pub(crate) pipeline_cache: PipelineCache,
+}
+
+impl Renderer {
+    /// The output texture starts out 1x1, call [`Renderer::resize`] before rendering
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, render_path: RenderPath) -> Self {
+        let texture_width = 1;
+        let texture_height = 1;
+        let texture = Self::create_texture(device, render_path, texture_width, texture_height);
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // the fragment path renders into the texture directly, so it only needs to know the size
+        let output_size_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Output Size Uniform Buffer"),
+            size: <cgmath::Vector2<u32> as ShaderSize>::SHADER_SIZE.get(),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+            mapped_at_creation: false,
+        });
+
+        let accumulation_buffer =
+            Self::create_accumulation_buffer(device, texture_width, texture_height, false, false);
+        let aov_texture = Self::create_aov_texture(device, render_path, 1, 1);
+        let aov_texture_view = aov_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let tiles = split_into_tiles(texture_width, texture_height, None);
+        let (waves, tile_waves) = Self::split_into_waves(&tiles);
+        let tile_uniform_stride = <GpuTile as ShaderSize>::SHADER_SIZE
+            .get()
+            .next_multiple_of(device.limits().min_uniform_buffer_offset_alignment as u64);
+        let tile_uniform_buffer =
+            Self::create_tile_uniform_buffer(device, tile_uniform_stride, &tiles, &waves);
+
+        let debug_path_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Debug Path Buffer"),
+            size: <GpuDebugPath as ShaderSize>::SHADER_SIZE.get(),
+            usage: wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let output_entry = wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: render_path.shader_stages(),
+            ty: match render_path {
+                RenderPath::Compute => wgpu::BindingType::StorageTexture {
+                    access: wgpu::StorageTextureAccess::WriteOnly,
+                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+                RenderPath::Fragment => wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: Some(<cgmath::Vector2<u32> as ShaderSize>::SHADER_SIZE),
+                },
+            },
+            count: None,
+        };
+        // only the compute path accumulates, fragment shaders can't write to storage buffers on downlevel devices
+        let accumulation_entry = wgpu::BindGroupLayoutEntry {
+            binding: 1,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        // the fragment path uses a scissor rect instead
+        let tile_entry = wgpu::BindGroupLayoutEntry {
+            binding: 2,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: Some(<GpuTile as ShaderSize>::SHADER_SIZE),
+            },
+            count: None,
+        };
+        let debug_path_entry = wgpu::BindGroupLayoutEntry {
+            binding: 3,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: Some(<GpuDebugPath as ShaderSize>::SHADER_SIZE),
+            },
+            count: None,
+        };
+        let aov_entry = wgpu::BindGroupLayoutEntry {
+            binding: 4,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::StorageTexture {
+                access: wgpu::StorageTextureAccess::WriteOnly,
+                format: wgpu::TextureFormat::Rgba32Float,
+                view_dimension: wgpu::TextureViewDimension::D2Array,
+            },
+            count: None,
+        };
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Texture Bind Group Layout"),
+                entries: &match render_path {
+                    RenderPath::Compute => vec![
+                        output_entry,
+                        accumulation_entry,
+                        tile_entry,
+                        debug_path_entry,
+                        aov_entry,
+                    ],
+                    RenderPath::Fragment => vec![output_entry],
+                },
+            });
+
+        let texture_bind_group = Self::create_texture_bind_group(
+            device,
+            render_path,
+            &texture_bind_group_layout,
+            &texture_view,
+            &accumulation_buffer,
+            &tile_uniform_buffer,
+            &debug_path_buffer,
+            &aov_texture_view,
+            &output_size_uniform_buffer,
+        );
+
+        let camera_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Camera Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: render_path.shader_stages(),
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: Some(<GpuCamera as ShaderSize>::SHADER_SIZE),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: render_path.shader_stages(),
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: render_path.shader_stages(),
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: Some(<GpuEnvironment as ShaderSize>::SHADER_SIZE),
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let blue_noise_texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Blue Noise Texture"),
+                size: wgpu::Extent3d {
+                    width: BLUE_NOISE_SIZE as _,
+                    height: BLUE_NOISE_SIZE as _,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R32Float,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            &blue_noise()
+                .iter()
+                .flat_map(|value| value.to_ne_bytes())
+                .collect::<Vec<_>>(),
+        );
+        let blue_noise_texture_view =
+            blue_noise_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let camera_uniform_buffers = (0..FRAMES_IN_FLIGHT)
+            .map(|_| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Camera Uniform Buffer"),
+                    size: <GpuCamera as ShaderSize>::SHADER_SIZE.get(),
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+                    mapped_at_creation: false,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut environment_buffer =
+            UniformBuffer::new([0; <GpuEnvironment as ShaderSize>::SHADER_SIZE.get() as _]);
+        environment_buffer
+            .write(&Environment::default().to_gpu())
+            .expect("the buffer is sized for the environment");
+        let environment_uniform_buffer =
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Environment Uniform Buffer"),
+                contents: &environment_buffer.into_inner(),
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+            });
+
+        let camera_bind_groups = camera_uniform_buffers
+            .iter()
+            .map(|camera_uniform_buffer| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Camera Bind Group"),
+                    layout: &camera_bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                                buffer: camera_uniform_buffer,
+                                offset: 0,
+                                size: Some(<GpuCamera as ShaderSize>::SHADER_SIZE),
+                            }),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::TextureView(&blue_noise_texture_view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: environment_uniform_buffer.as_entire_binding(),
+                        },
+                    ],
+                })
+            })
+            .collect();
+
+        let hyper_spheres_buffer = SceneBuffer::new(
+            device,
+            render_path,
+            "Hyper Spheres Buffer",
+            <GpuHyperSpheres as ShaderType>::min_size().get() as _,
+        );
+        let hyper_planes_buffer = SceneBuffer::new(
+            device,
+            render_path,
+            "Hyper Planes Buffer",
+            <GpuHyperPlanes as ShaderType>::min_size().get() as _,
+        );
+
+        let visible_hyper_spheres_buffer_size = 2 * std::mem::size_of::<u32>();
+        let visible_hyper_spheres_buffer = (render_path == RenderPath::Compute).then(|| {
+            Self::create_visible_hyper_spheres_buffer(device, visible_hyper_spheres_buffer_size)
+        });
+
+        let bvh_buffer = (render_path == RenderPath::Compute).then(|| {
+            SceneBuffer::new(
+                device,
+                render_path,
+                "BVH Buffer",
+                <GpuBvh as ShaderType>::min_size().get() as _,
+            )
+        });
+
+        let hyper_spheres_entry =
+            SceneBuffer::layout_entry(render_path, 0, <GpuHyperSpheres as ShaderType>::min_size());
+        let hyper_planes_entry =
+            SceneBuffer::layout_entry(render_path, 1, <GpuHyperPlanes as ShaderType>::min_size());
+        // written by the culling pass, which needs compute shaders
+        let visible_hyper_spheres_entry = wgpu::BindGroupLayoutEntry {
+            binding: 2,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        // only the compute path builds a BVH, the fragment path checks every hyper sphere
+        let bvh_entry = wgpu::BindGroupLayoutEntry {
+            binding: 3,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: Some(<GpuBvh as ShaderType>::min_size()),
+            },
+            count: None,
+        };
+        let objects_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Objects Bind Group Layout"),
+                entries: &match render_path {
+                    RenderPath::Compute => vec![
+                        hyper_spheres_entry,
+                        hyper_planes_entry,
+                        visible_hyper_spheres_entry,
+                        bvh_entry,
+                    ],
+                    RenderPath::Fragment => vec![hyper_spheres_entry, hyper_planes_entry],
+                },
+            });
+
+        let objects_bind_group = Self::create_objects_bind_group(
+            device,
+            &objects_bind_group_layout,
+            &hyper_spheres_buffer,
+            &hyper_planes_buffer,
+            visible_hyper_spheres_buffer.as_ref(),
+            bvh_buffer.as_ref(),
+        );
+
+        let materials_buffer = SceneBuffer::new(
+            device,
+            render_path,
+            "Materials Buffer",
+            <GpuMaterials as ShaderType>::min_size().get() as _,
+        );
+        let node_programs_buffer = SceneBuffer::new(
+            device,
+            render_path,
+            "Node Programs Buffer",
+            <GpuNodePrograms as ShaderType>::min_size().get() as _,
+        );
+
+        let materials_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Materials Bind Group Layout"),
+                entries: &[
+                    SceneBuffer::layout_entry(
+                        render_path,
+                        0,
+                        <GpuMaterials as ShaderType>::min_size(),
+                    ),
+                    SceneBuffer::layout_entry(
+                        render_path,
+                        1,
+                        <GpuNodePrograms as ShaderType>::min_size(),
+                    ),
+                ],
+            });
+
+        let materials_bind_group = Self::create_materials_bind_group(
+            device,
+            &materials_bind_group_layout,
+            &materials_buffer,
+            &node_programs_buffer,
+        );
+
+        let ray_tracing_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Ray Tracing Pipeline Layout"),
+                bind_group_layouts: &[
+                    &texture_bind_group_layout,
+                    &camera_bind_group_layout,
+                    &objects_bind_group_layout,
+                    &materials_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+
+        let wavefront = (render_path == RenderPath::Compute)
+            .then(|| Wavefront::new(device, &[output_entry, accumulation_entry, tile_entry]));
+        let wavefront_pipeline_layout = wavefront.as_ref().map(|wavefront| {
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Wavefront Pipeline Layout"),
+                bind_group_layouts: &[
+                    wavefront.bind_group_layout(),
+                    &camera_bind_group_layout,
+                    &objects_bind_group_layout,
+                    &materials_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            })
+        });
+
+        Self {
+            texture_width,
+            texture_height,
+            texture,
+            texture_view,
+            texture_bind_group_layout,
+            texture_bind_group,
+            accumulation_buffer,
+            accumulating_light_groups: false,
+            accumulating_aovs: false,
+            light_group_weights: cgmath::vec4(1.0, 1.0, 1.0, 1.0),
+            aov_texture,
+            aov_texture_view,
+            accumulated_frames: 0,
+            tile_size: None,
+            region: None,
+            tiles,
+            tile_index: 0,
+            tiles_rendered: 0,
+            waves,
+            tile_waves,
+            tile_uniform_buffer,
+            tile_uniform_stride,
+            debug_path_buffer,
+            camera_uniform_buffers,
+            camera_bind_groups,
+            frame: 0,
+            camera_sample_count: 0,
+            camera_bounce_count: 0,
+            environment_uniform_buffer,
+            hyper_spheres_buffer,
+            hyper_planes_buffer,
+            uploaded_hyper_spheres: vec![],
+            uploaded_hyper_planes: vec![],
+            object_upload_size: 0,
+            hyper_sphere_count: 0,
+            visible_hyper_spheres_buffer,
+            visible_hyper_spheres_buffer_size,
+            bvh_buffer,
+            bvh_mode: BvhMode::Auto,
+            uploaded_bvh: vec![],
+            bvh: None,
+            object_format: ObjectFormat::Auto,
+            objects_packed: false,
+            objects_bind_group_layout,
+            objects_bind_group,
+            materials_buffer,
+            materials_blended: false,
+            node_programs_buffer,
+            materials_have_nodes: false,
+            materials_bind_group_layout,
+            materials_bind_group,
+            output_size_uniform_buffer,
+            wavefront,
+            use_wavefront: false,
+            pipeline_cache: PipelineCache::new(
+                render_path,
+                ray_tracing_pipeline_layout,
+                wavefront_pipeline_layout,
+            ),
+        }
+    }
+
+    fn create_texture(
+        device: &wgpu::Device,
+        render_path: RenderPath,
+        width: usize,
+        height: usize,
+    ) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Texture"),
+            size: wgpu::Extent3d {
+                width: width as _,
+                height: height as _,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: render_path.texture_usages(),
+            view_formats: &[],
+        })
+    }
+
+    /// Light groups need a sum for each group and one for everything else, the sums of the AOVs
+    /// come after every pixel's colors
+    fn create_accumulation_buffer(
+        device: &wgpu::Device,
+        width: usize,
+        height: usize,
+        light_groups: bool,
+        aovs: bool,
+    ) -> wgpu::Buffer {
+        let sums = if light_groups { LightGroup::MAX + 1 } else { 1 };
+        let aov_floats = if aovs { Aovs::LAYER_COUNT * 4 } else { 0 };
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Accumulation Storage Buffer"),
+            size: (width * height * (sums * 3 + aov_floats) * std::mem::size_of::<f32>()) as _,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_aov_texture(
+        device: &wgpu::Device,
+        render_path: RenderPath,
+        width: usize,
+        height: usize,
+    ) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("AOV Texture"),
+            size: wgpu::Extent3d {
+                width: width as _,
+                height: height as _,
+                depth_or_array_layers: Aovs::LAYER_COUNT as _,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba32Float,
+            // downlevel devices can't store to it, and only the compute path does
+            usage: match render_path {
+                RenderPath::Compute => {
+                    wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::COPY_SRC
+                }
+                RenderPath::Fragment => wgpu::TextureUsages::COPY_SRC,
+            },
+            view_formats: &[],
+        })
+    }
+
+    /// Recreates the accumulation buffer and AOV texture for the current size, they are only
+    /// as large as what is being accumulated needs
+    fn recreate_accumulation(&mut self, device: &wgpu::Device) {
+        self.accumulation_buffer = Self::create_accumulation_buffer(
+            device,
+            self.texture_width,
+            self.texture_height,
+            self.accumulating_light_groups,
+            self.accumulating_aovs,
+        );
+        let (width, height) = if self.accumulating_aovs {
+            (self.texture_width, self.texture_height)
+        } else {
+            (1, 1)
+        };
+        self.aov_texture = Self::create_aov_texture(device, self.render_path(), width, height);
+        self.aov_texture_view = self
+            .aov_texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+    }
+
+    /// Every tile's waves one after another, and which of them belong to each tile
+    fn split_into_waves(tiles: &[Tile]) -> (Vec<Tile>, Vec<Range<usize>>) {
+        let mut waves = vec![];
+        let tile_waves = tiles
+            .iter()
+            .map(|tile| {
+                let start = waves.len();
+                waves.extend(tile.split(Some(WAVE_SIZE)));
+                start..waves.len()
+            })
+            .collect();
+        (waves, tile_waves)
+    }
+
+    fn create_tile_uniform_buffer(
+        device: &wgpu::Device,
+        stride: u64,
+        tiles: &[Tile],
+        waves: &[Tile],
+    ) -> wgpu::Buffer {
+        let mut tiles_buffer = DynamicUniformBuffer::new_with_alignment(vec![], stride);
+        for tile in tiles.iter().chain(waves) {
+            tiles_buffer
+                .write(&GpuTile {
+                    offset: cgmath::vec2(tile.x as _, tile.y as _),
+                    size: cgmath::vec2(tile.width as _, tile.height as _),
+                })
+                .expect("dynamic buffers grow to fit");
+        }
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Tile Uniform Buffer"),
+            contents: &tiles_buffer.into_inner(),
+            usage: wgpu::BufferUsages::UNIFORM,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_texture_bind_group(
+        device: &wgpu::Device,
+        render_path: RenderPath,
+        layout: &wgpu::BindGroupLayout,
+        texture_view: &wgpu::TextureView,
+        accumulation_buffer: &wgpu::Buffer,
+        tile_uniform_buffer: &wgpu::Buffer,
+        debug_path_buffer: &wgpu::Buffer,
+        aov_texture_view: &wgpu::TextureView,
+        output_size_uniform_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Texture Bind Group"),
+            layout,
+            entries: &match render_path {
+                RenderPath::Compute => vec![
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(texture_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: accumulation_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                            buffer: tile_uniform_buffer,
+                            offset: 0,
+                            size: Some(<GpuTile as ShaderSize>::SHADER_SIZE),
+                        }),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: debug_path_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: wgpu::BindingResource::TextureView(aov_texture_view),
+                    },
+                ],
+                RenderPath::Fragment => vec![wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: output_size_uniform_buffer.as_entire_binding(),
+                }],
+            },
+        })
+    }
+
+    fn create_objects_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        hyper_spheres_buffer: &SceneBuffer,
+        hyper_planes_buffer: &SceneBuffer,
+        visible_hyper_spheres_buffer: Option<&wgpu::Buffer>,
+        bvh_buffer: Option<&SceneBuffer>,
+    ) -> wgpu::BindGroup {
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: hyper_spheres_buffer.binding_resource(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: hyper_planes_buffer.binding_resource(),
+            },
+        ];
+        if let Some(visible_hyper_spheres_buffer) = visible_hyper_spheres_buffer {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 2,
+                resource: visible_hyper_spheres_buffer.as_entire_binding(),
+            });
+        }
+        if let Some(bvh_buffer) = bvh_buffer {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 3,
+                resource: bvh_buffer.binding_resource(),
+            });
+        }
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Objects Bind Group"),
+            layout,
+            entries: &entries,
+        })
+    }
+
+    fn create_visible_hyper_spheres_buffer(device: &wgpu::Device, size: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Visible Hyper Spheres Storage Buffer"),
+            size: size as _,
+            // the count is cleared before every culling pass
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_materials_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        materials_buffer: &SceneBuffer,
+        node_programs_buffer: &SceneBuffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Materials Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: materials_buffer.binding_resource(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: node_programs_buffer.binding_resource(),
+                },
+            ],
+        })
+    }
+
+    pub fn render_path(&self) -> RenderPath {
+        self.pipeline_cache.render_path()
+    }
+
+    /// The width and height of the output texture
+    pub fn size(&self) -> (usize, usize) {
+        (self.texture_width, self.texture_height)
+    }
+
+    /// The output texture, it can be sampled once the commands from [`Renderer::render`] are submitted
+    pub fn texture_view(&self) -> &wgpu::TextureView {
+        &self.texture_view
+    }
+
+    /// Recreates the output texture if it is the wrong size, returns whether it was recreated
+    pub fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: usize,
+        height: usize,
+    ) -> bool {
+        if (width, height) == (self.texture_width, self.texture_height) {
+            return false;
+        }
+        (self.texture_width, self.texture_height) = (width, height);
+
+        self.texture = Self::create_texture(device, self.render_path(), width, height);
+        self.texture_view = self
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        match self.render_path() {
+            RenderPath::Compute => self.recreate_accumulation(device),
+            RenderPath::Fragment => {
+                let mut output_size_buffer = UniformBuffer::new(
+                    [0; <cgmath::Vector2<u32> as ShaderSize>::SHADER_SIZE.get() as _],
+                );
+                output_size_buffer
+                    .write(&cgmath::vec2(width as u32, height as u32))
+                    .expect("the buffer is sized for the output size");
+                queue.write_buffer(
+                    &self.output_size_uniform_buffer,
+                    0,
+                    &output_size_buffer.into_inner(),
+                );
+            }
+        }
+        self.update_tiles(device);
+        true
+    }
+
+    /// Splits each render into tiles of at most `tile_size` pixels on each side, rendering one
+    /// tile per [`Renderer::render`] so that a single submission can't run long enough to hit
+    /// a GPU timeout. `None` renders the whole texture at once
+    pub fn set_tile_size(&mut self, device: &wgpu::Device, tile_size: Option<usize>) {
+        if tile_size != self.tile_size {
+            self.tile_size = tile_size;
+            self.update_tiles(device);
+        }
+    }
+
+    pub fn tile_size(&self) -> Option<usize> {
+        self.tile_size
+    }
+
+    /// Only renders the part of the output texture inside `region`, the rest keeps its last
+    /// accumulated value. Accumulation starts again whenever it changes. `None`, or a region
+    /// outside the texture, renders everything
+    pub fn set_region(&mut self, device: &wgpu::Device, region: Option<Tile>) {
+        if region != self.region {
+            self.region = region;
+            self.update_tiles(device);
+        }
+    }
+
+    pub fn region(&self) -> Option<Tile> {
+        self.region
+    }
+
+    /// How many tiles of the current accumulation pass have been rendered, and how many there are
+    pub fn tile_progress(&self) -> (usize, usize) {
+        (self.tiles_rendered, self.tiles.len())
+    }
+
+    /// Recomputes the tiles and starts accumulating again from the first one
+    fn update_tiles(&mut self, device: &wgpu::Device) {
+        let whole = Tile {
+            x: 0,
+            y: 0,
+            width: self.texture_width,
+            height: self.texture_height,
+        };
+        self.tiles = self
+            .region
+            .and_then(|region| region.intersection(whole))
+            .unwrap_or(whole)
+            .split(self.tile_size);
+        (self.waves, self.tile_waves) = Self::split_into_waves(&self.tiles);
+        self.tile_index = 0;
+        self.reset_accumulation();
+        self.tile_uniform_buffer = Self::create_tile_uniform_buffer(
+            device,
+            self.tile_uniform_stride,
+            &self.tiles,
+            &self.waves,
+        );
+        if let Some(wavefront) = &mut self.wavefront {
+            wavefront.invalidate();
+        }
+        self.texture_bind_group = Self::create_texture_bind_group(
+            device,
+            self.render_path(),
+            &self.texture_bind_group_layout,
+            &self.texture_view,
+            &self.accumulation_buffer,
+            &self.tile_uniform_buffer,
+            &self.debug_path_buffer,
+            &self.aov_texture_view,
+            &self.output_size_uniform_buffer,
+        );
+    }
+
+    /// How many renders have been averaged together in the output texture
+    pub fn accumulated_frames(&self) -> u32 {
+        self.accumulated_frames
+    }
+
+    /// Makes the next render start averaging from scratch, call it whenever the camera or scene changes
+    pub fn reset_accumulation(&mut self) {
+        self.accumulated_frames = 0;
+        self.tiles_rendered = 0;
+    }
+
+    /// Starts a new frame, the camera is written to that frame's uniform buffer.
+    /// `camera.accumulated_frames` is ignored, the renderer keeps track of it
+    pub fn upload_camera(
+        &mut self,
+        queue: &wgpu::Queue,
+        camera: &GpuCamera,
+    ) -> Result<(), GpuError> {
+        self.frame = (self.frame + 1) % FRAMES_IN_FLIGHT;
+        self.light_group_weights = camera.light_group_weights;
+        self.camera_sample_count = camera.sample_count;
+        self.camera_bounce_count = camera.bounce_count;
+        let mut camera_buffer =
+            UniformBuffer::new([0; <GpuCamera as ShaderSize>::SHADER_SIZE.get() as _]);
+        camera_buffer.write(&GpuCamera {
+            accumulated_frames: self.accumulated_frames,
+            ..*camera
+        })?;
+        queue.write_buffer(
+            &self.camera_uniform_buffers[self.frame],
+            0,
+            &camera_buffer.into_inner(),
+        );
+        Ok(())
+    }
+
+    /// Uploads the parts of `scene` marked in `scene.changes`, clearing the flags afterwards is up to the caller.
+    /// The objects are [sanitized](Scene::sanitized_hyper_spheres) so invalid values can't reach the GPU
+    pub fn upload_scene(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scene: &Scene,
+    ) -> Result<(), GpuError> {
+        if scene.changes.hyper_spheres || scene.changes.hyper_planes {
+            self.upload_objects(
+                device,
+                queue,
+                &scene.sanitized_hyper_spheres(),
+                &scene.sanitized_hyper_planes(),
+            )?;
+        }
+        if scene.changes.materials {
+            let (materials, node_programs) = scene.compiled_materials();
+            self.upload_materials(device, queue, &materials)?;
+            self.upload_node_programs(device, queue, &node_programs)?;
+        }
+        if scene.changes.environment {
+            self.upload_environment(queue, &scene.environment.to_gpu())?;
+        }
+        Ok(())
+    }
+
+    pub fn upload_environment(
+        &self,
+        queue: &wgpu::Queue,
+        environment: &GpuEnvironment,
+    ) -> Result<(), GpuError> {
+        let mut environment_buffer =
+            UniformBuffer::new([0; <GpuEnvironment as ShaderSize>::SHADER_SIZE.get() as _]);
+        environment_buffer.write(environment)?;
+        queue.write_buffer(
+            &self.environment_uniform_buffer,
+            0,
+            &environment_buffer.into_inner(),
+        );
+        Ok(())
+    }
+
+    /// Used from the next [`Renderer::upload_objects`] on
+    pub fn set_object_format(&mut self, object_format: ObjectFormat) {
+        self.object_format = object_format;
+    }
+
+    pub fn object_format(&self) -> ObjectFormat {
+        self.object_format
+    }
+
+    /// Used from the next [`Renderer::upload_objects`] on, only the compute path has a BVH
+    pub fn set_bvh_mode(&mut self, bvh_mode: BvhMode) {
+        self.bvh_mode = bvh_mode;
+    }
+
+    pub fn bvh_mode(&self) -> BvhMode {
+        self.bvh_mode
+    }
+
+    /// Of the BVH the last [`Renderer::upload_objects`] built, `None` if it didn't build one
+    pub fn bvh_stats(&self) -> Option<BvhStats> {
+        self.bvh.as_ref().map(|bvh| bvh.stats)
+    }
+
+    /// Path traces in wavefront kernels from the next [`Renderer::render`] on when the options
+    /// allow it, see [`RenderOptions::wavefront`]. Only on the compute path
+    pub fn set_wavefront(&mut self, wavefront: bool) {
+        self.use_wavefront = wavefront;
+    }
+
+    pub fn wavefront(&self) -> bool {
+        self.use_wavefront
+    }
+
+    /// Whether the objects were packed when they were last uploaded
+    pub fn objects_packed(&self) -> bool {
+        self.objects_packed
+    }
+
+    /// How many bytes of objects and BVH nodes the last [`Renderer::upload_objects`] wrote, when
+    /// the number of objects stays the same only the parts of the buffers that changed are written
+    pub fn object_upload_size(&self) -> usize {
+        self.object_upload_size
+    }
+
+    /// Buffers that are too small are recreated with at least double the size, shrinking reuses the old one.
+    /// Objects that are the same as in the last upload aren't written again
+    pub fn upload_objects(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        hyper_spheres: &[GpuHyperSphere],
+        hyper_planes: &[GpuHyperPlane],
+    ) -> Result<(), GpuError> {
+        let mut bind_group_invalidated = false;
+        self.hyper_sphere_count = hyper_spheres.len();
+        let visible_hyper_spheres_size = (hyper_spheres.len() + 1) * std::mem::size_of::<u32>();
+        if let Some(buffer) = &mut self.visible_hyper_spheres_buffer {
+            if visible_hyper_spheres_size > self.visible_hyper_spheres_buffer_size {
+                self.visible_hyper_spheres_buffer_size =
+                    visible_hyper_spheres_size.max(self.visible_hyper_spheres_buffer_size * 2);
+                *buffer = Self::create_visible_hyper_spheres_buffer(
+                    device,
+                    self.visible_hyper_spheres_buffer_size,
+                );
+                bind_group_invalidated = true;
+            }
+        }
+        self.objects_packed = self
+            .object_format
+            .packs(hyper_spheres.len() + hyper_planes.len());
+
+        // Upload hyper spheres
+        {
+            let mut hyper_spheres_buffer = DynamicStorageBuffer::new(vec![]);
+            if self.objects_packed {
+                let packed = hyper_spheres
+                    .iter()
+                    .map(GpuHyperSphere::pack)
+                    .collect::<Vec<_>>();
+                hyper_spheres_buffer.write(&GpuPackedHyperSpheres {
+                    count: ArrayLength,
+                    data: &packed,
+                })?;
+            } else {
+                hyper_spheres_buffer.write(&GpuHyperSpheres {
+                    count: ArrayLength,
+                    data: hyper_spheres,
+                })?;
+            }
+            let hyper_spheres_buffer = hyper_spheres_buffer.into_inner();
+
+            let (recreated, written) = self.hyper_spheres_buffer.write_changed(
+                device,
+                queue,
+                &mut self.uploaded_hyper_spheres,
+                &hyper_spheres_buffer,
+            );
+            bind_group_invalidated |= recreated;
+            self.object_upload_size = written;
+        }
+
+        // Build the BVH, see `Bvh::update` for how edits and animations avoid rebuilding it
+        let builder = self.bvh_mode.builder(hyper_spheres.len());
+        if let (Some(buffer), Some(builder)) = (&mut self.bvh_buffer, builder) {
+            // packed hyper spheres are intersected where their rounded centers are
+            let unpacked;
+            let hyper_spheres = if self.objects_packed {
+                unpacked = hyper_spheres
+                    .iter()
+                    .map(|hyper_sphere| hyper_sphere.pack().unpack())
+                    .collect::<Vec<_>>();
+                &unpacked
+            } else {
+                hyper_spheres
+            };
+            let update = match &mut self.bvh {
+                Some(bvh) if bvh.stats.builder == builder => bvh.update(hyper_spheres),
+                bvh => {
+                    *bvh = Some(Bvh::build(hyper_spheres, builder));
+                    BvhUpdate::Rebuilt
+                }
+            };
+            if update != BvhUpdate::Unchanged {
+                let bvh = self.bvh.as_ref().unwrap();
+                let mut bvh_buffer = DynamicStorageBuffer::new(vec![]);
+                bvh_buffer.write(&GpuBvh {
+                    tree_nodes: bvh.tree_nodes as _,
+                    count: ArrayLength,
+                    data: &bvh.nodes,
+                })?;
+                // a refit only writes the nodes on the paths to the hyper spheres that moved
+                let (recreated, written) = buffer.write_changed(
+                    device,
+                    queue,
+                    &mut self.uploaded_bvh,
+                    &bvh_buffer.into_inner(),
+                );
+                bind_group_invalidated |= recreated;
+                self.object_upload_size += written;
+            }
+        } else {
+            self.bvh = None;
+        }
+
+        // Upload Hyper Planes
+        {
+            let mut hyper_planes_buffer = DynamicStorageBuffer::new(vec![]);
+            if self.objects_packed {
+                let packed = hyper_planes
+                    .iter()
+                    .map(GpuHyperPlane::pack)
+                    .collect::<Vec<_>>();
+                hyper_planes_buffer.write(&GpuPackedHyperPlanes {
+                    count: ArrayLength,
+                    data: &packed,
+                })?;
+            } else {
+                hyper_planes_buffer.write(&GpuHyperPlanes {
+                    count: ArrayLength,
+                    data: hyper_planes,
+                })?;
+            }
+            let hyper_planes_buffer = hyper_planes_buffer.into_inner();
+
+            let (recreated, written) = self.hyper_planes_buffer.write_changed(
+                device,
+                queue,
+                &mut self.uploaded_hyper_planes,
+                &hyper_planes_buffer,
+            );
+            bind_group_invalidated |= recreated;
+            self.object_upload_size += written;
+        }
+
+        if bind_group_invalidated {
+            self.objects_bind_group = Self::create_objects_bind_group(
+                device,
+                &self.objects_bind_group_layout,
+                &self.hyper_spheres_buffer,
+                &self.hyper_planes_buffer,
+                self.visible_hyper_spheres_buffer.as_ref(),
+                self.bvh_buffer.as_ref(),
+            );
+        }
+        Ok(())
+    }
+
+    pub fn upload_materials(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        materials: &[GpuMaterial],
+    ) -> Result<(), GpuError> {
+        self.materials_blended = materials
+            .iter()
+            .any(|material| material.blend_mask().is_some());
+        self.materials_have_nodes = materials
+            .iter()
+            .any(|material| material.node_program_length > 0);
+        let mut materials_buffer = DynamicStorageBuffer::new(vec![]);
+        materials_buffer.write(&GpuMaterials {
+            count: ArrayLength,
+            data: materials,
+        })?;
+        let materials_buffer = materials_buffer.into_inner();
+
+        if self
+            .materials_buffer
+            .write(device, queue, &materials_buffer)
+        {
+            self.materials_bind_group = Self::create_materials_bind_group(
+                device,
+                &self.materials_bind_group_layout,
+                &self.materials_buffer,
+                &self.node_programs_buffer,
+            );
+        }
+        Ok(())
+    }
+
+    /// The instructions that the `node_program` of the materials point into, from
+    /// [`Scene::compiled_materials`]
+    pub fn upload_node_programs(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        node_programs: &[GpuNodeInstruction],
+    ) -> Result<(), GpuError> {
+        let mut node_programs_buffer = DynamicStorageBuffer::new(vec![]);
+        node_programs_buffer.write(&GpuNodePrograms {
+            count: ArrayLength,
+            data: node_programs,
+        })?;
+        let node_programs_buffer = node_programs_buffer.into_inner();
+
+        if self
+            .node_programs_buffer
+            .write(device, queue, &node_programs_buffer)
+        {
+            self.materials_bind_group = Self::create_materials_bind_group(
+                device,
+                &self.materials_bind_group_layout,
+                &self.materials_buffer,
+                &self.node_programs_buffer,
+            );
+        }
+        Ok(())
+    }
+
+    /// Records the ray tracing of the next tile into `encoder`, does nothing if the shader failed
+    /// to build so the texture keeps the last frame. On the compute path each pass over every
+    /// tile is averaged with the previous ones until [`Renderer::reset_accumulation`], the camera
+    /// has to be uploaded before every render for that
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        options: RenderOptions,
+    ) {
+        let options = RenderOptions {
+            packed_objects: self.objects_packed,
+            material_blends: self.materials_blended,
+            material_nodes: self.materials_have_nodes,
+            bvh: self.bvh.is_some(),
+            light_groups: options.light_groups && self.render_path() == RenderPath::Compute,
+            aovs: options.aovs && self.render_path() == RenderPath::Compute,
+            // the kernels only path trace, and leave out what only the megakernel needs
+            wavefront: self.use_wavefront
+                && self.wavefront.is_some()
+                && options.render_mode == RenderMode::PathTrace
+                && !options.light_groups
+                && !options.aovs
+                && !options.debug_path
+                && options.projection != Projection::Stereographic,
+            ..options
+        };
+        if (options.light_groups, options.aovs)
+            != (self.accumulating_light_groups, self.accumulating_aovs)
+        {
+            self.accumulating_light_groups = options.light_groups;
+            self.accumulating_aovs = options.aovs;
+            self.recreate_accumulation(device);
+            // the sums are laid out differently, so the average starts over
+            self.update_tiles(device);
+        }
+        let tile = self.tiles[self.tile_index];
+        let rendered = match self.pipeline_cache.get(device, options) {
+            Some(RayTracingPipeline::Compute { trace, cull }) => {
+                if let (Some(cull), Some(visible_hyper_spheres_buffer)) =
+                    (cull, &self.visible_hyper_spheres_buffer)
+                {
+                    Self::record_cull_pass(
+                        encoder,
+                        cull,
+                        [
+                            &self.texture_bind_group,
+                            &self.camera_bind_groups[self.frame],
+                            &self.objects_bind_group,
+                            &self.materials_bind_group,
+                        ],
+                        (self.tile_index as u64 * self.tile_uniform_stride) as _,
+                        visible_hyper_spheres_buffer,
+                        self.hyper_sphere_count,
+                    );
+                }
+
+                let workgroup_size = (16, 16);
+                let (dispatch_width, dispatch_height) = (
+                    tile.width.div_ceil(workgroup_size.0),
+                    tile.height.div_ceil(workgroup_size.1),
+                );
+
+                let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("Compute Pass"),
+                });
+                compute_pass.set_pipeline(trace);
+                compute_pass.set_bind_group(
+                    0,
+                    &self.texture_bind_group,
+                    &[(self.tile_index as u64 * self.tile_uniform_stride) as _],
+                );
+                compute_pass.set_bind_group(1, &self.camera_bind_groups[self.frame], &[]);
+                compute_pass.set_bind_group(2, &self.objects_bind_group, &[]);
+                compute_pass.set_bind_group(3, &self.materials_bind_group, &[]);
+                compute_pass.dispatch_workgroups(dispatch_width as _, dispatch_height as _, 1);
+                true
+            }
+            Some(RayTracingPipeline::Wavefront(pipelines)) => match &mut self.wavefront {
+                Some(wavefront) => {
+                    let bind_group = wavefront.bind_group(
+                        device,
+                        &self.texture_view,
+                        &self.accumulation_buffer,
+                        &self.tile_uniform_buffer,
+                    );
+                    if let (Some(cull), Some(visible_hyper_spheres_buffer)) =
+                        (&pipelines.cull, &self.visible_hyper_spheres_buffer)
+                    {
+                        Self::record_cull_pass(
+                            encoder,
+                            cull,
+                            [
+                                bind_group,
+                                &self.camera_bind_groups[self.frame],
+                                &self.objects_bind_group,
+                                &self.materials_bind_group,
+                            ],
+                            0,
+                            visible_hyper_spheres_buffer,
+                            self.hyper_sphere_count,
+                        );
+                    }
+                    // the waves come after the tiles in the tile uniform buffer
+                    for index in self.tile_waves[self.tile_index].clone() {
+                        wavefront.record_wave(
+                            encoder,
+                            pipelines,
+                            [
+                                &self.camera_bind_groups[self.frame],
+                                &self.objects_bind_group,
+                                &self.materials_bind_group,
+                            ],
+                            ((self.tiles.len() + index) as u64 * self.tile_uniform_stride) as _,
+                            self.waves[index],
+                            self.camera_sample_count,
+                            self.camera_bounce_count,
+                        );
+                    }
+                    true
+                }
+                None => false,
+            },
+            Some(RayTracingPipeline::Fragment(pipeline)) => {
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &self.texture_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            // the other tiles have to be kept
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                });
+                render_pass.set_pipeline(pipeline);
+                render_pass.set_bind_group(0, &self.texture_bind_group, &[]);
+                render_pass.set_bind_group(1, &self.camera_bind_groups[self.frame], &[]);
+                render_pass.set_bind_group(2, &self.objects_bind_group, &[]);
+                render_pass.set_bind_group(3, &self.materials_bind_group, &[]);
+                render_pass.set_scissor_rect(
+                    tile.x as _,
+                    tile.y as _,
+                    tile.width as _,
+                    tile.height as _,
+                );
+                render_pass.draw(0..3, 0..1);
+                true
+            }
+            None => false,
+        };
+
+        if rendered {
+            self.tile_index = (self.tile_index + 1) % self.tiles.len();
+            // a pass can start at any tile, it's finished once every tile has been rendered since
+            self.tiles_rendered += 1;
+            if self.tiles_rendered == self.tiles.len() {
+                self.tiles_rendered = 0;
+                if self.render_path() == RenderPath::Compute {
+                    self.accumulated_frames += 1;
+                }
+            }
+        }
+    }
+
+    /// Clears the visible hyper spheres list and fills it again, `bind_groups` are the four bind
+    /// groups `cull` was built with
+    fn record_cull_pass(
+        encoder: &mut wgpu::CommandEncoder,
+        cull: &wgpu::ComputePipeline,
+        bind_groups: [&wgpu::BindGroup; 4],
+        tile_offset: u32,
+        visible_hyper_spheres_buffer: &wgpu::Buffer,
+        hyper_sphere_count: usize,
+    ) {
+        encoder.clear_buffer(
+            visible_hyper_spheres_buffer,
+            0,
+            wgpu::BufferSize::new(std::mem::size_of::<u32>() as _),
+        );
+        let mut cull_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Culling Pass"),
+        });
+        cull_pass.set_pipeline(cull);
+        for (i, bind_group) in bind_groups.into_iter().enumerate() {
+            let offsets: &[u32] = if i == 0 { &[tile_offset] } else { &[] };
+            cull_pass.set_bind_group(i as _, bind_group, offsets);
+        }
+        cull_pass.dispatch_workgroups(hyper_sphere_count.div_ceil(64) as _, 1, 1);
+    }
+
+    /// Copies the output texture back to the CPU as tightly packed RGBA8 rows, blocking until it's done
+    pub fn read_pixels(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Vec<u8>, GpuError> {
+        self.read_texture(device, queue, &self.texture, 4, 1)
+    }
+
+    /// Copies the average of every frame accumulated so far back to the CPU as linear colors
+    /// that haven't been clamped, blocking until it's done. `None` on the fragment path, which
+    /// doesn't accumulate, or before anything is rendered. Pixels outside the region are black
+    pub fn read_hdr(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Option<HdrImage>, GpuError> {
+        if self.render_path() != RenderPath::Compute
+            || (self.accumulated_frames == 0 && self.tiles_rendered == 0)
+        {
+            return Ok(None);
+        }
+        let sums_per_pixel = if self.accumulating_light_groups {
+            LightGroup::MAX + 1
+        } else {
+            1
+        };
+        let pixel_count = self.texture_width * self.texture_height;
+        let size = (pixel_count * sums_per_pixel * 3 * std::mem::size_of::<f32>()) as u64;
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("HDR Readback Buffer"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("HDR Readback Command Encoder"),
+        });
+        encoder.copy_buffer_to_buffer(&self.accumulation_buffer, 0, &buffer, 0, size);
+        queue.submit([encoder.finish()]);
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            _ = sender.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError))?;
+        let data = slice.get_mapped_range();
+        let sums = data
+            .chunks_exact(3 * std::mem::size_of::<f32>())
+            .map(|sum| {
+                cgmath::Vector3::from(std::array::from_fn(|i| {
+                    f32::from_le_bytes(sum[i * 4..][..4].try_into().unwrap())
+                }))
+            })
+            .collect::<Vec<_>>();
+
+        // the tiles rendered so far in the current pass have one more frame in them
+        let mut frames = vec![0; pixel_count];
+        for (i, tile) in self.tiles.iter().enumerate() {
+            let since_rendered = (self.tile_index + self.tiles.len() - i) % self.tiles.len();
+            let tile_frames = self.accumulated_frames
+                + u32::from(since_rendered != 0 && since_rendered <= self.tiles_rendered);
+            for y in tile.y..tile.y + tile.height {
+                frames[y * self.texture_width + tile.x..][..tile.width].fill(tile_frames);
+            }
+        }
+
+        let pixels = sums
+            .chunks_exact(sums_per_pixel)
+            .zip(frames)
+            .map(|(sums, frames)| {
+                if frames == 0 {
+                    return cgmath::vec3(0.0, 0.0, 0.0);
+                }
+                // the same weights as `light_group_weight` in the shader
+                let sum = sums
+                    .iter()
+                    .enumerate()
+                    .map(|(group, &sum)| {
+                        sum * if group == 0 {
+                            1.0
+                        } else {
+                            self.light_group_weights[group - 1]
+                        }
+                    })
+                    .sum::<cgmath::Vector3<f32>>();
+                sum / frames as f32
+            })
+            .collect();
+        Ok(Some(HdrImage {
+            width: self.texture_width,
+            height: self.texture_height,
+            pixels,
+        }))
+    }
+
+    /// Copies the AOVs averaged so far back to the CPU, blocking until it's done. `None` unless
+    /// the last render had [`RenderOptions::aovs`] on the compute path
+    pub fn read_aovs(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Option<Aovs>, GpuError> {
+        if !self.accumulating_aovs {
+            return Ok(None);
+        }
+        let bytes = self.read_texture(
+            device,
+            queue,
+            &self.aov_texture,
+            4 * std::mem::size_of::<f32>(),
+            Aovs::LAYER_COUNT,
+        )?;
+        let layers = bytes
+            .chunks_exact(4 * std::mem::size_of::<f32>())
+            .map(|pixel| {
+                std::array::from_fn(|i| f32::from_le_bytes(pixel[i * 4..][..4].try_into().unwrap()))
+            })
+            .collect();
+        Ok(Some(Aovs::from_layers(
+            self.texture_width,
+            self.texture_height,
+            layers,
+        )))
+    }
+
+    /// Copies every layer of a texture the size of the output back as tightly packed rows
+    fn read_texture(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        bytes_per_pixel: usize,
+        layers: usize,
+    ) -> Result<Vec<u8>, GpuError> {
+        let unpadded_bytes_per_row = self.texture_width * bytes_per_pixel;
+        let padded_bytes_per_row =
+            unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize);
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback Buffer"),
+            size: (padded_bytes_per_row * self.texture_height * layers) as _,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Readback Command Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(
+                        std::num::NonZeroU32::new(padded_bytes_per_row as _).unwrap(),
+                    ),
+                    rows_per_image: std::num::NonZeroU32::new(self.texture_height as _),
+                },
+            },
+            wgpu::Extent3d {
+                width: self.texture_width as _,
+                height: self.texture_height as _,
+                depth_or_array_layers: layers as _,
+            },
+        );
+        queue.submit([encoder.finish()]);
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            _ = sender.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError))?;
+
+        let data = slice.get_mapped_range();
+        Ok(data
+            .chunks(padded_bytes_per_row)
+            .flat_map(|row| &row[..unpadded_bytes_per_row])
+            .copied()
+            .collect())
+    }
+
+    /// Path traces a single ray through `pixel` with the last uploaded camera and scene and reads
+    /// back every bounce of its first sample, blocking until it's done. Returns `None` on the
+    /// fragment path, if `pixel` is outside the texture, or if the shader failed to build
+    pub fn inspect_pixel(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        options: RenderOptions,
+        pixel: (usize, usize),
+    ) -> Result<Option<PixelPath>, GpuError> {
+        if pixel.0 >= self.texture_width || pixel.1 >= self.texture_height {
+            return Ok(None);
+        }
+        let options = RenderOptions {
+            render_mode: RenderMode::PathTrace,
+            packed_objects: self.objects_packed,
+            material_blends: self.materials_blended,
+            material_nodes: self.materials_have_nodes,
+            bvh: self.bvh.is_some(),
+            light_groups: false,
+            aovs: false,
+            debug_path: true,
+            wavefront: false,
+            ..options
+        };
+        let Some(RayTracingPipeline::Compute { trace, .. }) =
+            self.pipeline_cache.get(device, options)
+        else {
+            return Ok(None);
+        };
+
+        let size = <GpuDebugPath as ShaderSize>::SHADER_SIZE.get();
+        let mut request = vec![0; size as usize];
+        request[0..4].copy_from_slice(&(pixel.0 as u32).to_le_bytes());
+        request[4..8].copy_from_slice(&(pixel.1 as u32).to_le_bytes());
+        queue.write_buffer(&self.debug_path_buffer, 0, &request);
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Debug Path Readback Buffer"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Debug Path Command Encoder"),
+        });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Debug Path Pass"),
+            });
+            compute_pass.set_pipeline(trace);
+            compute_pass.set_bind_group(0, &self.texture_bind_group, &[0]);
+            compute_pass.set_bind_group(1, &self.camera_bind_groups[self.frame], &[]);
+            compute_pass.set_bind_group(2, &self.objects_bind_group, &[]);
+            compute_pass.set_bind_group(3, &self.materials_bind_group, &[]);
+            compute_pass.dispatch_workgroups(1, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&self.debug_path_buffer, 0, &buffer, 0, size);
+        queue.submit([encoder.finish()]);
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            _ = sender.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError))?;
+
+        let data = slice.get_mapped_range();
+        let path: GpuDebugPath = StorageBuffer::new(&*data).create()?;
+        Ok(Some(path.into()))
+    }
+}
@@ -0,0 +1,596 @@
+use crate::{
+    BiVector4, Environment, GpuHyperPlane, GpuHyperSphere, GpuMaterial, LightGroup, NodeGraph,
+    ObjectData, ObjectRef, PrimitiveKind,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Which parts of a [`Scene`] changed since they were last uploaded, so that unchanged
+/// buffers aren't re-encoded every frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SceneChanges {
+    pub hyper_spheres: bool,
+    pub hyper_planes: bool,
+    pub materials: bool,
+    pub environment: bool,
+}
+
+impl SceneChanges {
+    pub const NONE: Self = Self {
+        hyper_spheres: false,
+        hyper_planes: false,
+        materials: false,
+        environment: false,
+    };
+    pub const ALL: Self = Self {
+        hyper_spheres: true,
+        hyper_planes: true,
+        materials: true,
+        environment: true,
+    };
+}
+
+impl SceneChanges {
+    /// Marks the objects of `kind` as changed
+    pub fn mark(&mut self, kind: PrimitiveKind) {
+        match kind {
+            PrimitiveKind::HyperSphere => self.hyper_spheres = true,
+            PrimitiveKind::HyperPlane => self.hyper_planes = true,
+        }
+    }
+}
+
+impl ObjectRef {
+    /// Where this object ends up after `removed` are removed, `None` if it is one of them
+    pub fn after_removal(self, removed: &[ObjectRef]) -> Option<ObjectRef> {
+        if removed.contains(&self) {
+            return None;
+        }
+        let mut removed = removed.to_vec();
+        removed.sort_unstable();
+        removed.dedup();
+        let shift = removed
+            .iter()
+            .filter(|object| object.kind() == self.kind() && object.index() < self.index())
+            .count();
+        Some(ObjectRef::new(self.kind(), self.index() - shift))
+    }
+}
+
+/// A stable handle to a material of a [`Scene`], it keeps referring to the same material when
+/// others are removed or reordered. The `material` of an object holds one on the CPU, and it is
+/// replaced with the material's index when the objects are uploaded
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct MaterialId(pub u32);
+
+/// Something still using a material, which stops [`Scene::remove_material`] from removing it
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MaterialUser {
+    Object(ObjectRef),
+    /// A material that blends it as its `blend_a` or `blend_b`
+    Blend(MaterialId),
+}
+
+/// A stable handle to an object of a [`Scene`], unlike an [`ObjectRef`] it keeps referring to
+/// the same object when others are removed, and it is never reused for a different object
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ObjectId(pub u32);
+
+/// An object of a [`Scene`]
+///
+/// Its material is the `material` of its data, which holds a [`MaterialId`] on the CPU and is
+/// replaced with the material's index when the objects are uploaded
+#[derive(Clone)]
+pub struct SceneObject {
+    pub id: ObjectId,
+    pub name: String,
+    /// Free text about what the object is there to show, saved with the scene
+    pub notes: String,
+    pub data: ObjectData,
+}
+
+impl SceneObject {
+    pub fn hyper_sphere(&self) -> Option<&GpuHyperSphere> {
+        self.data.get()
+    }
+
+    pub fn hyper_sphere_mut(&mut self) -> Option<&mut GpuHyperSphere> {
+        self.data.get_mut()
+    }
+
+    pub fn hyper_plane(&self) -> Option<&GpuHyperPlane> {
+        self.data.get()
+    }
+
+    pub fn hyper_plane_mut(&mut self) -> Option<&mut GpuHyperPlane> {
+        self.data.get_mut()
+    }
+
+    pub fn material(&self) -> MaterialId {
+        self.data.material()
+    }
+
+    pub fn set_material(&mut self, material: MaterialId) {
+        self.data.set_material(material);
+    }
+
+    /// The center of a hyper sphere or the point of a hyper plane
+    pub fn position(&self) -> cgmath::Vector4<f32> {
+        self.data.position()
+    }
+
+    pub fn position_mut(&mut self) -> &mut cgmath::Vector4<f32> {
+        self.data.position_mut()
+    }
+}
+
+/// The objects and materials being rendered, each with a name shown in the UI
+///
+/// Hyper spheres and hyper planes share `objects`, and are split into a buffer per kind when
+/// they are uploaded. An [`ObjectRef`] counts objects of its own kind, so
+/// `ObjectRef::HyperPlane(1)` is the second hyper plane in `objects`. Anything that modifies
+/// `objects` or `materials` directly has to set the matching flags in `changes`, the `_mut`
+/// accessors do it automatically. Objects and materials have to be added through the `Scene`
+/// so that they get unique ids, and materials also have to be removed through it so that
+/// `material_ids` stays in sync. Objects can be edited in place but not reordered or changed
+/// to another kind, and have to be removed through it too so that their lookups stay in sync
+#[derive(Clone)]
+pub struct Scene {
+    pub objects: Vec<SceneObject>,
+    next_object_id: u32,
+    object_lookup: ObjectLookup,
+    pub materials: Vec<GpuMaterial>,
+    pub material_names: Vec<String>,
+    pub material_ids: Vec<MaterialId>,
+    next_material_id: u32,
+    /// The materials that are built from nodes, anything that modifies them directly has to
+    /// set `changes.materials`
+    pub node_graphs: HashMap<MaterialId, NodeGraph>,
+    pub environment: Environment,
+    /// Changing these doesn't set any flags in `changes`, because with
+    /// [`crate::Camera::light_group_aovs`] they don't need anything uploaded again
+    pub light_groups: Vec<LightGroup>,
+    pub changes: SceneChanges,
+}
+
+/// Where the objects of a [`Scene`] are in `objects`, so that finding one by its [`ObjectRef`]
+/// or [`ObjectId`] doesn't search through all of them
+#[derive(Clone, Default)]
+struct ObjectLookup {
+    /// The indices of the objects of each kind, in the order of their [`ObjectRef`]s
+    by_kind: [Vec<usize>; PrimitiveKind::ALL.len()],
+    refs: HashMap<ObjectId, ObjectRef>,
+}
+
+impl ObjectLookup {
+    fn new(objects: &[SceneObject]) -> Self {
+        let mut lookup = Self::default();
+        for (index, object) in objects.iter().enumerate() {
+            lookup.push(index, object);
+        }
+        lookup
+    }
+
+    /// Adds the object at `index`, which comes after every other object
+    fn push(&mut self, index: usize, object: &SceneObject) {
+        let kind = object.data.kind();
+        let indices = &mut self.by_kind[kind as usize];
+        self.refs
+            .insert(object.id, ObjectRef::new(kind, indices.len()));
+        indices.push(index);
+    }
+
+    fn index(&self, object: ObjectRef) -> Option<usize> {
+        self.by_kind[object.kind() as usize]
+            .get(object.index())
+            .copied()
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        let objects = vec![
+            SceneObject {
+                id: ObjectId(0),
+                name: "Hyper Sphere".into(),
+                notes: String::new(),
+                data: ObjectData::HyperSphere(GpuHyperSphere {
+                    center: cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+                    velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                    radius: 1.0,
+                    material: 0,
+                    inner_radius: 0.0,
+                }),
+            },
+            SceneObject {
+                id: ObjectId(1),
+                name: "Ground".into(),
+                notes: String::new(),
+                data: ObjectData::HyperPlane(GpuHyperPlane {
+                    point: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                    normal: cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+                    velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                    angular_velocity: BiVector4::ZERO,
+                    material: 1,
+                    extents: cgmath::vec3(0.0, 0.0, 0.0),
+                    tangent: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                    bitangent: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                }),
+            },
+        ];
+        Self {
+            object_lookup: ObjectLookup::new(&objects),
+            objects,
+            next_object_id: 2,
+            materials: vec![
+                GpuMaterial {
+                    base_color: cgmath::vec3(0.8, 0.4, 0.1),
+                    emissive_color: cgmath::vec3(0.0, 0.0, 0.0),
+                    ..Default::default()
+                },
+                GpuMaterial {
+                    base_color: cgmath::vec3(0.1, 0.8, 0.3),
+                    emissive_color: cgmath::vec3(0.0, 0.0, 0.0),
+                    ..Default::default()
+                },
+            ],
+            material_names: vec!["Orange".into(), "Green".into()],
+            material_ids: vec![MaterialId(0), MaterialId(1)],
+            next_material_id: 2,
+            node_graphs: HashMap::new(),
+            environment: Environment::default(),
+            light_groups: vec![],
+            changes: SceneChanges::ALL,
+        }
+    }
+}
+
+impl Scene {
+    /// A scene with no objects or materials
+    pub fn empty() -> Self {
+        Self {
+            objects: vec![],
+            next_object_id: 0,
+            object_lookup: ObjectLookup::default(),
+            materials: vec![],
+            material_names: vec![],
+            material_ids: vec![],
+            next_material_id: 0,
+            node_graphs: HashMap::new(),
+            environment: Environment::default(),
+            light_groups: vec![],
+            changes: SceneChanges::ALL,
+        }
+    }
+
+    /// Removes every object but keeps the materials, the ids of the removed objects aren't reused
+    pub fn clear_objects(&mut self) {
+        self.objects.clear();
+        self.object_lookup = ObjectLookup::default();
+        self.changes.hyper_spheres = true;
+        self.changes.hyper_planes = true;
+    }
+
+    /// Every object, marking both kinds of objects as changed
+    pub fn objects_mut(&mut self) -> &mut [SceneObject] {
+        self.changes.hyper_spheres = true;
+        self.changes.hyper_planes = true;
+        &mut self.objects
+    }
+
+    pub fn hyper_spheres(&self) -> impl Iterator<Item = &GpuHyperSphere> {
+        self.objects.iter().filter_map(SceneObject::hyper_sphere)
+    }
+
+    pub fn hyper_spheres_mut(&mut self) -> impl Iterator<Item = &mut GpuHyperSphere> {
+        self.changes.hyper_spheres = true;
+        self.objects
+            .iter_mut()
+            .filter_map(SceneObject::hyper_sphere_mut)
+    }
+
+    pub fn hyper_planes(&self) -> impl Iterator<Item = &GpuHyperPlane> {
+        self.objects.iter().filter_map(SceneObject::hyper_plane)
+    }
+
+    pub fn hyper_planes_mut(&mut self) -> impl Iterator<Item = &mut GpuHyperPlane> {
+        self.changes.hyper_planes = true;
+        self.objects
+            .iter_mut()
+            .filter_map(SceneObject::hyper_plane_mut)
+    }
+
+    pub fn hyper_sphere_count(&self) -> usize {
+        self.object_lookup.by_kind[PrimitiveKind::HyperSphere as usize].len()
+    }
+
+    pub fn hyper_plane_count(&self) -> usize {
+        self.object_lookup.by_kind[PrimitiveKind::HyperPlane as usize].len()
+    }
+
+    /// The `i`th hyper sphere, `None` if there aren't that many
+    pub fn hyper_sphere(&self, i: usize) -> Option<&GpuHyperSphere> {
+        self.object(ObjectRef::HyperSphere(i))?.hyper_sphere()
+    }
+
+    pub fn hyper_sphere_mut(&mut self, i: usize) -> Option<&mut GpuHyperSphere> {
+        self.object_mut(ObjectRef::HyperSphere(i))?
+            .hyper_sphere_mut()
+    }
+
+    /// The `i`th hyper plane, `None` if there aren't that many
+    pub fn hyper_plane(&self, i: usize) -> Option<&GpuHyperPlane> {
+        self.object(ObjectRef::HyperPlane(i))?.hyper_plane()
+    }
+
+    pub fn hyper_plane_mut(&mut self, i: usize) -> Option<&mut GpuHyperPlane> {
+        self.object_mut(ObjectRef::HyperPlane(i))?.hyper_plane_mut()
+    }
+
+    pub fn materials_mut(&mut self) -> &mut Vec<GpuMaterial> {
+        self.changes.materials = true;
+        &mut self.materials
+    }
+
+    /// Returns the id to use as the `material` of objects
+    pub fn add_material(&mut self, name: impl Into<String>, material: GpuMaterial) -> MaterialId {
+        let id = MaterialId(self.next_material_id);
+        self.next_material_id += 1;
+        self.materials_mut().push(material);
+        self.material_names.push(name.into());
+        self.material_ids.push(id);
+        // objects that were using the id before it existed have to be uploaded again
+        if !self.material_users(id).is_empty() {
+            self.changes.hyper_spheres = true;
+            self.changes.hyper_planes = true;
+        }
+        id
+    }
+
+    /// Removes `id` unless some objects or blends are still using it, in which case they are
+    /// returned
+    pub fn remove_material(&mut self, id: MaterialId) -> Result<(), Vec<MaterialUser>> {
+        let users = self
+            .material_users(id)
+            .into_iter()
+            .map(MaterialUser::Object)
+            .chain(self.blend_users(id).into_iter().map(MaterialUser::Blend))
+            .collect::<Vec<_>>();
+        if !users.is_empty() {
+            return Err(users);
+        }
+        if let Some(index) = self.material_index(id) {
+            self.materials_mut().remove(index);
+            self.material_names.remove(index);
+            self.material_ids.remove(index);
+            self.node_graphs.remove(&id);
+            // the indices of the materials after it have moved
+            self.changes.hyper_spheres = true;
+            self.changes.hyper_planes = true;
+        }
+        Ok(())
+    }
+
+    /// Removes every material and light group, the ids start again from 0 so scripts can refer
+    /// to materials by the order they add them in
+    pub fn clear_materials(&mut self) {
+        self.light_groups.clear();
+        self.materials_mut().clear();
+        self.material_names.clear();
+        self.material_ids.clear();
+        self.node_graphs.clear();
+        self.next_material_id = 0;
+        self.changes.hyper_spheres = true;
+        self.changes.hyper_planes = true;
+    }
+
+    /// Swaps the materials at indices `a` and `b`, objects keep their materials
+    pub fn swap_materials(&mut self, a: usize, b: usize) {
+        self.materials_mut().swap(a, b);
+        self.material_names.swap(a, b);
+        self.material_ids.swap(a, b);
+        self.changes.hyper_spheres = true;
+        self.changes.hyper_planes = true;
+    }
+
+    /// Adds a light group and returns the number materials use to join it, `None` if there are
+    /// already [`LightGroup::MAX`]
+    pub fn add_light_group(&mut self, light_group: LightGroup) -> Option<u32> {
+        if self.light_groups.len() >= LightGroup::MAX {
+            return None;
+        }
+        self.light_groups.push(light_group);
+        Some(self.light_groups.len() as u32)
+    }
+
+    /// Removes the light group at `index`, its materials are left without a group
+    pub fn remove_light_group(&mut self, index: usize) {
+        if index >= self.light_groups.len() {
+            return;
+        }
+        self.light_groups.remove(index);
+        let removed = index as u32 + 1;
+        for material in self.materials_mut() {
+            if material.light_group == removed {
+                material.light_group = 0;
+            } else if material.light_group > removed {
+                material.light_group -= 1;
+            }
+        }
+    }
+
+    /// For [`crate::GpuCamera::light_group_weights`], groups that don't exist are left at 1
+    pub fn light_group_weights(&self) -> cgmath::Vector4<f32> {
+        let mut weights = cgmath::vec4(1.0, 1.0, 1.0, 1.0);
+        for (i, light_group) in self.light_groups.iter().take(LightGroup::MAX).enumerate() {
+            weights[i] = light_group.weight();
+        }
+        weights
+    }
+
+    /// Where `id` is in `materials`, `None` if it doesn't exist
+    pub fn material_index(&self, id: MaterialId) -> Option<usize> {
+        self.material_ids
+            .iter()
+            .position(|&material_id| material_id == id)
+    }
+
+    pub fn material(&self, id: MaterialId) -> Option<&GpuMaterial> {
+        self.material_index(id).map(|index| &self.materials[index])
+    }
+
+    pub fn material_name(&self, id: MaterialId) -> Option<&str> {
+        self.material_index(id)
+            .map(|index| self.material_names[index].as_str())
+    }
+
+    /// The index in `materials` of every material, built when the objects are uploaded
+    pub fn material_indices(&self) -> HashMap<MaterialId, u32> {
+        self.material_ids
+            .iter()
+            .enumerate()
+            .map(|(index, &id)| (id, index as u32))
+            .collect()
+    }
+
+    /// Every object using `id`
+    pub fn material_users(&self, id: MaterialId) -> Vec<ObjectRef> {
+        self.object_refs()
+            .zip(&self.objects)
+            .filter(|(_, object)| object.material() == id)
+            .map(|(object, _)| object)
+            .collect()
+    }
+
+    /// Every other material blending `id`
+    pub fn blend_users(&self, id: MaterialId) -> Vec<MaterialId> {
+        self.material_ids
+            .iter()
+            .zip(&self.materials)
+            .filter(|&(&other, material)| {
+                other != id
+                    && material
+                        .blended()
+                        .is_some_and(|blended| blended.contains(&id))
+            })
+            .map(|(&other, _)| other)
+            .collect()
+    }
+
+    /// Returns the id of the new object
+    pub fn add_object(&mut self, name: impl Into<String>, data: ObjectData) -> ObjectId {
+        let id = ObjectId(self.next_object_id);
+        self.next_object_id += 1;
+        self.changes.mark(data.kind());
+        let object = SceneObject {
+            id,
+            name: name.into(),
+            notes: String::new(),
+            data,
+        };
+        self.object_lookup.push(self.objects.len(), &object);
+        self.objects.push(object);
+        id
+    }
+
+    pub fn add_hyper_sphere(
+        &mut self,
+        name: impl Into<String>,
+        hyper_sphere: GpuHyperSphere,
+    ) -> ObjectId {
+        self.add_object(name, hyper_sphere.into())
+    }
+
+    pub fn add_hyper_plane(
+        &mut self,
+        name: impl Into<String>,
+        hyper_plane: GpuHyperPlane,
+    ) -> ObjectId {
+        self.add_object(name, hyper_plane.into())
+    }
+
+    /// The [`ObjectRef`] of every object, in the order of `objects`
+    pub fn object_refs(&self) -> impl Iterator<Item = ObjectRef> + '_ {
+        let mut counts = [0; PrimitiveKind::ALL.len()];
+        self.objects.iter().map(move |object| {
+            let kind = object.data.kind();
+            counts[kind as usize] += 1;
+            ObjectRef::new(kind, counts[kind as usize] - 1)
+        })
+    }
+
+    /// Where the object is in `objects`, `None` if it has been removed
+    pub fn object_index(&self, object: ObjectRef) -> Option<usize> {
+        self.object_lookup.index(object)
+    }
+
+    pub fn object(&self, object: ObjectRef) -> Option<&SceneObject> {
+        self.object_index(object).map(|index| &self.objects[index])
+    }
+
+    /// Marks the object's kind as changed
+    pub fn object_mut(&mut self, object: ObjectRef) -> Option<&mut SceneObject> {
+        let index = self.object_index(object)?;
+        self.changes.mark(object.kind());
+        Some(&mut self.objects[index])
+    }
+
+    /// `None` if the object has been removed
+    pub fn object_id(&self, object: ObjectRef) -> Option<ObjectId> {
+        self.object(object).map(|object| object.id)
+    }
+
+    /// Where the object currently is, `None` if it has been removed
+    pub fn object_ref(&self, id: ObjectId) -> Option<ObjectRef> {
+        self.object_lookup.refs.get(&id).copied()
+    }
+
+    /// `None` if the object has been removed
+    pub fn object_name(&self, object: ObjectRef) -> Option<&str> {
+        self.object(object).map(|object| object.name.as_str())
+    }
+
+    /// The center of a hyper sphere or the point of a hyper plane, `None` if the object has been removed
+    pub fn object_position(&self, object: ObjectRef) -> Option<cgmath::Vector4<f32>> {
+        self.object(object).map(SceneObject::position)
+    }
+
+    /// Moves every listed object by `delta`, skipping ones that have been removed
+    pub fn translate_objects(&mut self, objects: &[ObjectRef], delta: cgmath::Vector4<f32>) {
+        for &object in objects {
+            if let Some(object) = self.object_mut(object) {
+                *object.position_mut() += delta;
+            }
+        }
+    }
+
+    pub fn set_objects_material(&mut self, objects: &[ObjectRef], material: MaterialId) {
+        for &object in objects {
+            if let Some(object) = self.object_mut(object) {
+                object.set_material(material);
+            }
+        }
+    }
+
+    /// Removes every listed object at once, so the references don't shift part way through
+    pub fn remove_objects(&mut self, objects: &[ObjectRef]) {
+        if objects.is_empty() {
+            return;
+        }
+        for object in objects {
+            self.changes.mark(object.kind());
+        }
+        let objects = objects.iter().collect::<HashSet<_>>();
+        let removed = self
+            .object_refs()
+            .map(|object| objects.contains(&object))
+            .collect::<Vec<_>>();
+        let mut removed = removed.into_iter();
+        self.objects.retain(|_| !removed.next().unwrap_or(false));
+        self.object_lookup = ObjectLookup::new(&self.objects);
+    }
+
+    /// `None` if the object has been removed
+    pub fn object_material(&self, object: ObjectRef) -> Option<MaterialId> {
+        self.object(object).map(SceneObject::material)
+    }
+}
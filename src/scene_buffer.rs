@@ -0,0 +1,217 @@
+use crate::RenderPath;
+
+/// How many texels are in each row of a [`SceneBuffer`]'s texture. Has to match
+/// `DATA_TEXTURE_WIDTH` in scene.wgsl
+pub const DATA_TEXTURE_WIDTH: usize = 1024;
+
+/// The bytes in an `Rgba32Uint` texel
+const TEXEL_SIZE: usize = 16;
+const ROW_SIZE: usize = DATA_TEXTURE_WIDTH * TEXEL_SIZE;
+
+/// How finely [`SceneBuffer::write_changed`] compares uploads, a multiple of
+/// [`wgpu::COPY_BUFFER_ALIGNMENT`] and of a texel
+const CHANGE_CHUNK_SIZE: usize = 256;
+
+/// Where the shaders read one of the scene's lists from, with the count in the first 16 bytes
+/// and the structs after them, laid out by encase as if for a storage buffer
+///
+/// That is a storage buffer on the compute path. Fragment shaders can't read storage buffers on
+/// downlevel devices like WebGL2, so on the fragment path the same bytes are in an `Rgba32Uint`
+/// texture instead, [`DATA_TEXTURE_WIDTH`] texels to a row, which scene.wgsl reads a struct at a
+/// time from
+pub(crate) struct SceneBuffer {
+    label: &'static str,
+    storage: Storage,
+    /// how many bytes fit
+    size: usize,
+}
+
+enum Storage {
+    Buffer(wgpu::Buffer),
+    Texture(wgpu::Texture, wgpu::TextureView),
+}
+
+impl SceneBuffer {
+    /// Large enough for `size` bytes
+    pub fn new(
+        device: &wgpu::Device,
+        render_path: RenderPath,
+        label: &'static str,
+        size: usize,
+    ) -> Self {
+        let storage = match render_path {
+            RenderPath::Compute => Storage::Buffer(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: size as _,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            })),
+            RenderPath::Fragment => {
+                let texture = device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width: DATA_TEXTURE_WIDTH as _,
+                        height: size.div_ceil(ROW_SIZE).max(1) as _,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: wgpu::TextureFormat::Rgba32Uint,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                    view_formats: &[],
+                });
+                let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+                Storage::Texture(texture, view)
+            }
+        };
+        let size = match storage {
+            Storage::Buffer(_) => size,
+            Storage::Texture(..) => size.div_ceil(ROW_SIZE).max(1) * ROW_SIZE,
+        };
+        Self {
+            label,
+            storage,
+            size,
+        }
+    }
+
+    /// The entry for a [`SceneBuffer`] of `render_path` read by its shader stage,
+    /// `min_binding_size` is only checked for storage buffers
+    pub fn layout_entry(
+        render_path: RenderPath,
+        binding: u32,
+        min_binding_size: wgpu::BufferSize,
+    ) -> wgpu::BindGroupLayoutEntry {
+        wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: render_path.shader_stages(),
+            ty: match render_path {
+                RenderPath::Compute => wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: Some(min_binding_size),
+                },
+                RenderPath::Fragment => wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Uint,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+            },
+            count: None,
+        }
+    }
+
+    pub fn binding_resource(&self) -> wgpu::BindingResource<'_> {
+        match &self.storage {
+            Storage::Buffer(buffer) => buffer.as_entire_binding(),
+            Storage::Texture(_, view) => wgpu::BindingResource::TextureView(view),
+        }
+    }
+
+    /// Writes `data` to the start, growing it geometrically if it doesn't fit so that slowly
+    /// growing scenes don't recreate it every frame. Returns whether it was recreated, in which
+    /// case the bind groups using it have to be recreated too
+    pub fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[u8]) -> bool {
+        let recreated = data.len() > self.size;
+        if recreated {
+            let render_path = match self.storage {
+                Storage::Buffer(_) => RenderPath::Compute,
+                Storage::Texture(..) => RenderPath::Fragment,
+            };
+            *self = Self::new(
+                device,
+                render_path,
+                self.label,
+                data.len().max(self.size * 2),
+            );
+        }
+        self.write_at(queue, 0, data);
+        recreated
+    }
+
+    /// Like [`SceneBuffer::write`], but when `data` is the same size as `uploaded`, the data
+    /// written last time, only the chunks that differ are written. Objects that are being moved
+    /// or animated are the only ones that get uploaded again, while the rest of a large scene
+    /// stays as it is on the GPU. Returns whether it was recreated and how many bytes were written
+    pub fn write_changed(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        uploaded: &mut Vec<u8>,
+        data: &[u8],
+    ) -> (bool, usize) {
+        if data.len() != uploaded.len() || data.len() > self.size {
+            let recreated = self.write(device, queue, data);
+            uploaded.clear();
+            uploaded.extend_from_slice(data);
+            return (recreated, data.len());
+        }
+
+        let mut written = 0;
+        let mut start = 0;
+        while start < data.len() {
+            let chunk = |start: usize| start..(start + CHANGE_CHUNK_SIZE).min(data.len());
+            if data[chunk(start)] == uploaded[chunk(start)] {
+                start += CHANGE_CHUNK_SIZE;
+                continue;
+            }
+            // neighbouring chunks that changed are written together
+            let mut end = chunk(start).end;
+            while end < data.len() && data[chunk(end)] != uploaded[chunk(end)] {
+                end = chunk(end).end;
+            }
+            self.write_at(queue, start, &data[start..end]);
+            uploaded[start..end].copy_from_slice(&data[start..end]);
+            written += end - start;
+            start = end;
+        }
+        (false, written)
+    }
+
+    /// `offset` and the length of `data` are multiples of a texel, which every struct the
+    /// scene is uploaded as is
+    fn write_at(&self, queue: &wgpu::Queue, offset: usize, data: &[u8]) {
+        let texture = match &self.storage {
+            Storage::Buffer(buffer) => {
+                queue.write_buffer(buffer, offset as _, data);
+                return;
+            }
+            Storage::Texture(texture, _) => texture,
+        };
+        let end = offset + data.len();
+        let mut start = offset;
+        while start < end {
+            // whole rows are written at once, the rest a row at a time
+            let (width, height) = if start.is_multiple_of(ROW_SIZE) && end - start >= ROW_SIZE {
+                (ROW_SIZE, (end - start) / ROW_SIZE)
+            } else {
+                ((end - start).min(ROW_SIZE - start % ROW_SIZE), 1)
+            };
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: (start % ROW_SIZE / TEXEL_SIZE) as _,
+                        y: (start / ROW_SIZE) as _,
+                        z: 0,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &data[start - offset..start - offset + width * height],
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(width as _),
+                    rows_per_image: None,
+                },
+                wgpu::Extent3d {
+                    width: (width / TEXEL_SIZE) as _,
+                    height: height as _,
+                    depth_or_array_layers: 1,
+                },
+            );
+            start += width * height;
+        }
+    }
+}
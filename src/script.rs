@@ -0,0 +1,1435 @@
+//! A small scripting language for building and animating scenes.
+//!
+//! ```text
+//! let red = material("Red", 0.8, 0.1, 0.1, 0, 0, 0, 0)
+//! for x in 0..3 {
+//!     sphere("Sphere", x * 2, 1, 0, 0, 0.5, red)
+//! }
+//! fn update(time, dt) {
+//!     set_sphere_center(0, 0, 1, 0, sin(time))
+//! }
+//! ```
+//!
+//! Values are numbers, strings and booleans. Top level statements run once, if an `update(time, dt)`
+//! function is defined it can be called every frame. Variables declared at the top level stay
+//! alive between calls.
+
+use crate::{
+    BiVector4, BlendMask, EmissionPattern, GpuHyperPlane, GpuHyperSphere, GpuMaterial, LightGroup,
+    MaterialId, NodeId, NodeKind, ObjectRef, Scene,
+};
+use cgmath::InnerSpace;
+use std::collections::HashMap;
+
+/// Statements executed per run before the script is assumed to be stuck in a loop
+const MAX_STEPS: usize = 10_000_000;
+/// How deeply statements and expressions can be nested while running, counting those in the
+/// functions being called, before the script is assumed to recurse forever. The stack of the
+/// scene loading thread would overflow and abort long before the steps ran out. A function
+/// calling itself from a `return` nests two deeper each time
+const MAX_DEPTH: usize = 256;
+/// How deeply blocks and expressions can be nested in the source, for the same reason
+const MAX_NESTING: usize = 64;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Nil,
+    Number(f64),
+    Bool(bool),
+    String(String),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Nil => write!(f, "nil"),
+            Value::Number(n) => write!(f, "{n}"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::String(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    String(String),
+    Ident(String),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: &[&str] = &[
+    "..", "==", "!=", "<=", ">=", "&&", "||", "(", ")", "{", "}", ",", ";", "=", "<", ">", "+",
+    "-", "*", "/", "%", "!",
+];
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+    let mut tokens = vec![];
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let mut chars = line.char_indices().peekable();
+        while let Some(&(start, c)) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+            } else if line[start..].starts_with("//") {
+                break;
+            } else if c.is_ascii_digit() {
+                let mut end = start;
+                while let Some(&(i, c)) = chars.peek() {
+                    // stop before `..` so ranges like `0..3` work
+                    if c.is_ascii_digit() || (c == '.' && !line[i..].starts_with("..")) {
+                        end = i + c.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let number = line[start..end]
+                    .parse()
+                    .map_err(|_| format!("line {line_number}: invalid number"))?;
+                tokens.push((Token::Number(number), line_number));
+            } else if c.is_alphabetic() || c == '_' {
+                let mut end = start;
+                while let Some(&(i, c)) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' {
+                        end = i + c.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push((Token::Ident(line[start..end].to_string()), line_number));
+            } else if c == '"' {
+                chars.next();
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, 'n')) => string.push('\n'),
+                            Some((_, c @ ('"' | '\\'))) => string.push(c),
+                            Some((_, c)) => {
+                                string.push('\\');
+                                string.push(c);
+                            }
+                            None => return Err(format!("line {line_number}: unterminated string")),
+                        },
+                        Some((_, c)) => string.push(c),
+                        None => return Err(format!("line {line_number}: unterminated string")),
+                    }
+                }
+                tokens.push((Token::String(string), line_number));
+            } else if let Some(&symbol) = SYMBOLS.iter().find(|s| line[start..].starts_with(**s)) {
+                for _ in 0..symbol.len() {
+                    chars.next();
+                }
+                tokens.push((Token::Symbol(symbol), line_number));
+            } else {
+                return Err(format!("line {line_number}: unexpected character '{c}'"));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug)]
+enum Expr {
+    Literal(Value),
+    Variable(String, usize),
+    Call(String, Vec<Expr>, usize),
+    Unary(&'static str, Box<Expr>, usize),
+    Binary(&'static str, Box<Expr>, Box<Expr>, usize),
+}
+
+#[derive(Debug)]
+enum Stmt {
+    Let(String, Expr),
+    Assign(String, Expr, usize),
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    For(String, Expr, Expr, Vec<Stmt>),
+    While(Expr, Vec<Stmt>),
+    Return(Option<Expr>),
+    Expr(Expr),
+}
+
+struct Function {
+    parameters: Vec<String>,
+    body: Vec<Stmt>,
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    /// how many blocks and expressions the current position is inside of
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map_or(1, |&(_, line)| line)
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("line {}: {message}", self.line())
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), String> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{symbol}'")))
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(ident)) if ident == keyword)
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Ident(ident)) => Ok(ident),
+            _ => {
+                self.position -= 1;
+                Err(self.error("expected a name"))
+            }
+        }
+    }
+
+    /// Parses with `parse` one level deeper, failing past [`MAX_NESTING`]
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, String>,
+    ) -> Result<T, String> {
+        if self.depth >= MAX_NESTING {
+            return Err(self.error("nested too deeply"));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>, String> {
+        self.nested(Self::parse_block)
+    }
+
+    fn parse_block(&mut self) -> Result<Vec<Stmt>, String> {
+        self.expect("{")?;
+        let mut statements = vec![];
+        while !self.eat("}") {
+            if self.peek().is_none() {
+                return Err(self.error("expected '}'"));
+            }
+            statements.push(self.statement()?);
+        }
+        Ok(statements)
+    }
+
+    fn statement(&mut self) -> Result<Stmt, String> {
+        let line = self.line();
+        let statement = if self.is_keyword("let") {
+            self.position += 1;
+            let name = self.ident()?;
+            self.expect("=")?;
+            Stmt::Let(name, self.expression()?)
+        } else if self.is_keyword("if") {
+            self.position += 1;
+            let condition = self.expression()?;
+            let then = self.block()?;
+            let otherwise = if self.is_keyword("else") {
+                self.position += 1;
+                if self.is_keyword("if") {
+                    vec![self.statement()?]
+                } else {
+                    self.block()?
+                }
+            } else {
+                vec![]
+            };
+            Stmt::If(condition, then, otherwise)
+        } else if self.is_keyword("for") {
+            self.position += 1;
+            let name = self.ident()?;
+            if !self.is_keyword("in") {
+                return Err(self.error("expected 'in'"));
+            }
+            self.position += 1;
+            let start = self.expression()?;
+            self.expect("..")?;
+            let end = self.expression()?;
+            Stmt::For(name, start, end, self.block()?)
+        } else if self.is_keyword("while") {
+            self.position += 1;
+            let condition = self.expression()?;
+            Stmt::While(condition, self.block()?)
+        } else if self.is_keyword("return") {
+            self.position += 1;
+            if matches!(self.peek(), None | Some(Token::Symbol(";" | "}"))) {
+                Stmt::Return(None)
+            } else {
+                Stmt::Return(Some(self.expression()?))
+            }
+        } else if matches!(self.peek(), Some(Token::Ident(_)))
+            && matches!(
+                self.tokens.get(self.position + 1),
+                Some((Token::Symbol("="), _))
+            )
+        {
+            let name = self.ident()?;
+            self.expect("=")?;
+            Stmt::Assign(name, self.expression()?, line)
+        } else {
+            Stmt::Expr(self.expression()?)
+        };
+        self.eat(";");
+        Ok(statement)
+    }
+
+    fn expression(&mut self) -> Result<Expr, String> {
+        self.nested(|parser| parser.binary(0))
+    }
+
+    fn binary(&mut self, precedence: usize) -> Result<Expr, String> {
+        const LEVELS: &[&[&str]] = &[
+            &["||"],
+            &["&&"],
+            &["==", "!="],
+            &["<", "<=", ">", ">="],
+            &["+", "-"],
+            &["*", "/", "%"],
+        ];
+        let Some(operators) = LEVELS.get(precedence) else {
+            return self.unary();
+        };
+        let mut left = self.binary(precedence + 1)?;
+        // every operator in a chain nests the ones before it a level deeper
+        let depth = self.depth;
+        let result = loop {
+            let line = self.line();
+            let Some(&operator) = operators.iter().find(|&&operator| self.eat(operator)) else {
+                break Ok(left);
+            };
+            if self.depth >= MAX_NESTING {
+                break Err(self.error("nested too deeply"));
+            }
+            self.depth += 1;
+            let right = match self.binary(precedence + 1) {
+                Ok(right) => right,
+                Err(error) => break Err(error),
+            };
+            left = Expr::Binary(operator, Box::new(left), Box::new(right), line);
+        };
+        self.depth = depth;
+        result
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        let line = self.line();
+        for operator in ["-", "!"] {
+            if self.eat(operator) {
+                let operand = self.nested(Self::unary)?;
+                return Ok(Expr::Unary(operator, Box::new(operand), line));
+            }
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let line = self.line();
+        match self.next() {
+            Some(Token::Number(number)) => Ok(Expr::Literal(Value::Number(number))),
+            Some(Token::String(string)) => Ok(Expr::Literal(Value::String(string))),
+            Some(Token::Ident(ident)) if ident == "true" => Ok(Expr::Literal(Value::Bool(true))),
+            Some(Token::Ident(ident)) if ident == "false" => Ok(Expr::Literal(Value::Bool(false))),
+            Some(Token::Ident(ident)) if ident == "nil" => Ok(Expr::Literal(Value::Nil)),
+            Some(Token::Ident(ident)) => {
+                if self.eat("(") {
+                    let mut arguments = vec![];
+                    while !self.eat(")") {
+                        arguments.push(self.expression()?);
+                        if !self.eat(",") {
+                            self.expect(")")?;
+                            break;
+                        }
+                    }
+                    Ok(Expr::Call(ident, arguments, line))
+                } else {
+                    Ok(Expr::Variable(ident, line))
+                }
+            }
+            Some(Token::Symbol("(")) => {
+                let expression = self.expression()?;
+                self.expect(")")?;
+                Ok(expression)
+            }
+            _ => {
+                self.position -= 1;
+                Err(self.error("expected an expression"))
+            }
+        }
+    }
+}
+
+enum Flow {
+    Normal,
+    Return(Value),
+}
+
+/// A compiled script and its global variables
+pub struct Script {
+    functions: HashMap<String, Function>,
+    main: Vec<Stmt>,
+    globals: HashMap<String, Value>,
+}
+
+struct Context<'a> {
+    functions: &'a HashMap<String, Function>,
+    globals: &'a mut HashMap<String, Value>,
+    /// local scopes of the current function, empty at the top level
+    locals: Vec<HashMap<String, Value>>,
+    scene: &'a mut Scene,
+    output: &'a mut Vec<String>,
+    steps: usize,
+    /// how many statements and expressions are being run inside each other
+    depth: usize,
+}
+
+impl Script {
+    pub fn compile(source: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+            depth: 0,
+        };
+        let mut functions = HashMap::new();
+        let mut main = vec![];
+        while parser.peek().is_some() {
+            if parser.is_keyword("fn") {
+                parser.position += 1;
+                let name = parser.ident()?;
+                parser.expect("(")?;
+                let mut parameters = vec![];
+                while !parser.eat(")") {
+                    parameters.push(parser.ident()?);
+                    if !parser.eat(",") {
+                        parser.expect(")")?;
+                        break;
+                    }
+                }
+                let body = parser.block()?;
+                functions.insert(name, Function { parameters, body });
+            } else {
+                main.push(parser.statement()?);
+            }
+        }
+        Ok(Self {
+            functions,
+            main,
+            globals: HashMap::new(),
+        })
+    }
+
+    pub fn has_function(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+
+    /// Runs the top level statements, `print` output is appended to `output`
+    pub fn run(&mut self, scene: &mut Scene, output: &mut Vec<String>) -> Result<(), String> {
+        let mut context = Context {
+            functions: &self.functions,
+            globals: &mut self.globals,
+            locals: vec![],
+            scene,
+            output,
+            steps: 0,
+            depth: 0,
+        };
+        context.block(&self.main).map(|_| ())
+    }
+
+    pub fn call(
+        &mut self,
+        name: &str,
+        arguments: &[Value],
+        scene: &mut Scene,
+        output: &mut Vec<String>,
+    ) -> Result<Value, String> {
+        let mut context = Context {
+            functions: &self.functions,
+            globals: &mut self.globals,
+            locals: vec![],
+            scene,
+            output,
+            steps: 0,
+            depth: 0,
+        };
+        context.call_function(name, arguments.to_vec(), 0)
+    }
+}
+
+/// A single expression of the time `t`, like `sin(t) * 2`, for animating values without a whole script
+#[derive(Debug)]
+pub struct Expression {
+    expression: Expr,
+}
+
+impl Expression {
+    pub fn compile(source: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+            depth: 0,
+        };
+        let expression = parser.expression()?;
+        if parser.peek().is_some() {
+            return Err(parser.error("expected the end of the expression"));
+        }
+        Ok(Self { expression })
+    }
+
+    /// Builtins that edit the scene run against an empty scene that is thrown away
+    pub fn evaluate(&self, t: f32) -> Result<f32, String> {
+        let functions = HashMap::new();
+        let mut globals = HashMap::from([("t".to_string(), Value::Number(t as _))]);
+        let mut scene = Scene::empty();
+        let mut output = vec![];
+        let mut context = Context {
+            functions: &functions,
+            globals: &mut globals,
+            locals: vec![],
+            scene: &mut scene,
+            output: &mut output,
+            steps: 0,
+            depth: 0,
+        };
+        match context.expression(&self.expression)? {
+            Value::Number(n) => Ok(n as f32),
+            value => Err(format!("expected a number, got '{value}'")),
+        }
+    }
+}
+
+/// A script that recreates the objects and materials of `scene` when it is run,
+/// the environment isn't included
+pub fn scene_script(scene: &Scene) -> String {
+    use std::fmt::Write;
+
+    // quotes, backslashes and newlines are escaped with a backslash
+    let name = |name: &str| {
+        name.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    };
+    let default = GpuMaterial::default();
+    // the materials are cleared first, so their ids when the script runs are their indices
+    let material_index = |id| scene.material_index(MaterialId(id)).unwrap_or(0);
+    let mut script = String::from("clear()\nclear_materials()\n");
+    // clearing the materials clears the light groups too, so they are numbered from 1 again
+    for (i, light_group) in scene.light_groups.iter().enumerate() {
+        _ = writeln!(
+            script,
+            "light_group(\"{}\", {})",
+            name(&light_group.name),
+            light_group.intensity,
+        );
+        if !light_group.enabled {
+            _ = writeln!(script, "set_light_group_enabled({}, false)", i + 1);
+        }
+    }
+    for (i, (material, material_name)) in scene
+        .materials
+        .iter()
+        .zip(&scene.material_names)
+        .enumerate()
+    {
+        let base = material.base_color;
+        let emissive = material.emissive_color;
+        _ = writeln!(
+            script,
+            "material(\"{}\", {}, {}, {}, {}, {}, {}, {})",
+            name(material_name),
+            base.x,
+            base.y,
+            base.z,
+            emissive.x,
+            emissive.y,
+            emissive.z,
+            material.emission_strength,
+        );
+        if material.emission_pattern != default.emission_pattern
+            || material.emission_pattern_scale != default.emission_pattern_scale
+        {
+            let pattern = EmissionPattern::ALL
+                .get(material.emission_pattern as usize)
+                .unwrap_or(&EmissionPattern::Solid);
+            let scale = material.emission_pattern_scale;
+            _ = writeln!(
+                script,
+                "set_material_emission_pattern({i}, \"{}\", {}, {}, {}, {})",
+                pattern.name(),
+                scale.x,
+                scale.y,
+                scale.z,
+                scale.w,
+            );
+        }
+        if material.bump_strength != default.bump_strength
+            || material.bump_scale != default.bump_scale
+        {
+            _ = writeln!(
+                script,
+                "set_material_bump({i}, {}, {})",
+                material.bump_strength, material.bump_scale,
+            );
+        }
+        if material.transmission != default.transmission
+            || material.ior != default.ior
+            || material.dispersion != default.dispersion
+        {
+            _ = writeln!(
+                script,
+                "set_material_glass({i}, {}, {}, {})",
+                material.transmission, material.ior, material.dispersion,
+            );
+        }
+        if material.absorption_color != default.absorption_color
+            || material.absorption_density != default.absorption_density
+        {
+            let color = material.absorption_color;
+            _ = writeln!(
+                script,
+                "set_material_absorption({i}, {}, {}, {}, {})",
+                color.x, color.y, color.z, material.absorption_density,
+            );
+        }
+        if material.clearcoat != default.clearcoat
+            || material.clearcoat_roughness != default.clearcoat_roughness
+        {
+            _ = writeln!(
+                script,
+                "set_material_clearcoat({i}, {}, {})",
+                material.clearcoat, material.clearcoat_roughness,
+            );
+        }
+        if material.sheen != default.sheen || material.sheen_color != default.sheen_color {
+            let color = material.sheen_color;
+            _ = writeln!(
+                script,
+                "set_material_sheen({i}, {}, {}, {}, {})",
+                material.sheen, color.x, color.y, color.z,
+            );
+        }
+        if material.priority != default.priority {
+            _ = writeln!(script, "set_material_priority({i}, {})", material.priority);
+        }
+        if material.light_group != default.light_group {
+            _ = writeln!(
+                script,
+                "set_material_light_group({i}, {})",
+                material.light_group
+            );
+        }
+    }
+    // after every material, so blends can refer to materials that come later
+    for (i, material) in scene.materials.iter().enumerate() {
+        let Some(mask) = material.blend_mask() else {
+            continue;
+        };
+        let scale = material.blend_scale;
+        _ = writeln!(
+            script,
+            "set_material_blend({i}, \"{}\", {}, {}, {}, {}, {}, {})",
+            mask.name(),
+            material_index(material.blend_a),
+            material_index(material.blend_b),
+            scale.x,
+            scale.y,
+            scale.z,
+            scale.w,
+        );
+    }
+    // a new graph numbers its nodes from the output up, so the nodes are referred to by position
+    for (i, id) in scene.material_ids.iter().enumerate() {
+        let Some(graph) = scene.node_graphs.get(id) else {
+            continue;
+        };
+        for node in &graph.nodes {
+            let value = node.value;
+            _ = writeln!(
+                script,
+                "material_node({i}, \"{}\", {}, {}, {}, {}, {}, {})",
+                node.kind.name(),
+                node.position[0],
+                node.position[1],
+                value.x,
+                value.y,
+                value.z,
+                value.w,
+            );
+        }
+        let position = |id| graph.nodes.iter().position(|node| node.id == id);
+        for (to, node) in graph.nodes.iter().enumerate() {
+            for (input, from) in node.inputs.iter().enumerate() {
+                if let Some(from) = from.and_then(position) {
+                    _ = writeln!(script, "connect_material_nodes({i}, {from}, {to}, {input})");
+                }
+            }
+        }
+    }
+    let spheres = scene
+        .objects
+        .iter()
+        .filter_map(|object| Some((object.hyper_sphere()?, &object.name, &object.notes)));
+    for (i, (sphere, sphere_name, notes)) in spheres.enumerate() {
+        let center = sphere.center;
+        _ = writeln!(
+            script,
+            "sphere(\"{}\", {}, {}, {}, {}, {}, {})",
+            name(sphere_name),
+            center.x,
+            center.y,
+            center.z,
+            center.w,
+            sphere.radius,
+            material_index(sphere.material),
+        );
+        let velocity = sphere.velocity;
+        if velocity != cgmath::vec4(0.0, 0.0, 0.0, 0.0) {
+            _ = writeln!(
+                script,
+                "set_sphere_velocity({i}, {}, {}, {}, {})",
+                velocity.x, velocity.y, velocity.z, velocity.w,
+            );
+        }
+        if sphere.inner_radius > 0.0 {
+            _ = writeln!(
+                script,
+                "set_sphere_inner_radius({i}, {})",
+                sphere.inner_radius
+            );
+        }
+        if !notes.is_empty() {
+            _ = writeln!(script, "set_sphere_notes({i}, \"{}\")", name(notes));
+        }
+    }
+    let planes = scene
+        .objects
+        .iter()
+        .filter_map(|object| Some((object.hyper_plane()?, &object.name, &object.notes)));
+    for (i, (plane, plane_name, notes)) in planes.enumerate() {
+        let (point, normal) = (plane.point, plane.normal);
+        _ = writeln!(
+            script,
+            "plane(\"{}\", {}, {}, {}, {}, {}, {}, {}, {}, {})",
+            name(plane_name),
+            point.x,
+            point.y,
+            point.z,
+            point.w,
+            normal.x,
+            normal.y,
+            normal.z,
+            normal.w,
+            material_index(plane.material),
+        );
+        let velocity = plane.velocity;
+        if velocity != cgmath::vec4(0.0, 0.0, 0.0, 0.0) {
+            _ = writeln!(
+                script,
+                "set_plane_velocity({i}, {}, {}, {}, {})",
+                velocity.x, velocity.y, velocity.z, velocity.w,
+            );
+        }
+        let angular_velocity = plane.angular_velocity;
+        if angular_velocity != BiVector4::ZERO {
+            _ = writeln!(
+                script,
+                "set_plane_angular_velocity({i}, {}, {}, {}, {}, {}, {})",
+                angular_velocity.xy,
+                angular_velocity.xz,
+                angular_velocity.xw,
+                angular_velocity.yz,
+                angular_velocity.yw,
+                angular_velocity.zw,
+            );
+        }
+        if plane.custom_tangents().is_some() {
+            let (tangent, bitangent) = (plane.tangent, plane.bitangent);
+            _ = writeln!(
+                script,
+                "set_plane_tangents({i}, {}, {}, {}, {}, {}, {}, {}, {})",
+                tangent.x,
+                tangent.y,
+                tangent.z,
+                tangent.w,
+                bitangent.x,
+                bitangent.y,
+                bitangent.z,
+                bitangent.w,
+            );
+        }
+        let extents = plane.extents;
+        if extents != cgmath::vec3(0.0, 0.0, 0.0) {
+            _ = writeln!(
+                script,
+                "set_plane_extents({i}, {}, {}, {})",
+                extents.x, extents.y, extents.z,
+            );
+        }
+        if !notes.is_empty() {
+            _ = writeln!(script, "set_plane_notes({i}, \"{}\")", name(notes));
+        }
+    }
+    script
+}
+
+impl Context<'_> {
+    fn block(&mut self, statements: &[Stmt]) -> Result<Flow, String> {
+        for statement in statements {
+            if let Flow::Return(value) = self.statement(statement)? {
+                return Ok(Flow::Return(value));
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn scoped_block(&mut self, statements: &[Stmt]) -> Result<Flow, String> {
+        if self.locals.is_empty() {
+            return self.block(statements);
+        }
+        self.locals.push(HashMap::new());
+        let flow = self.block(statements);
+        self.locals.pop();
+        flow
+    }
+
+    fn declare(&mut self, name: &str, value: Value) {
+        match self.locals.last_mut() {
+            Some(scope) => scope.insert(name.to_string(), value),
+            None => self.globals.insert(name.to_string(), value),
+        };
+    }
+
+    fn variable(&mut self, name: &str) -> Option<&mut Value> {
+        match self
+            .locals
+            .iter_mut()
+            .rev()
+            .find(|scope| scope.contains_key(name))
+        {
+            Some(scope) => scope.get_mut(name),
+            None => self.globals.get_mut(name),
+        }
+    }
+
+    fn step(&mut self) -> Result<(), String> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            return Err("the script took too long, is there an infinite loop?".into());
+        }
+        Ok(())
+    }
+
+    /// Runs `run` one level deeper, failing past [`MAX_DEPTH`]
+    fn nested<T>(&mut self, run: impl FnOnce(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err("recursion too deep, does a function keep calling itself?".into());
+        }
+        self.depth += 1;
+        let result = run(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn statement(&mut self, statement: &Stmt) -> Result<Flow, String> {
+        self.nested(|context| context.run_statement(statement))
+    }
+
+    fn run_statement(&mut self, statement: &Stmt) -> Result<Flow, String> {
+        self.step()?;
+        match statement {
+            Stmt::Let(name, value) => {
+                let value = self.expression(value)?;
+                self.declare(name, value);
+            }
+            Stmt::Assign(name, value, line) => {
+                let value = self.expression(value)?;
+                *self
+                    .variable(name)
+                    .ok_or_else(|| format!("line {line}: unknown variable '{name}'"))? = value;
+            }
+            Stmt::If(condition, then, otherwise) => {
+                let branch = if self.truthy(condition)? {
+                    then
+                } else {
+                    otherwise
+                };
+                return self.scoped_block(branch);
+            }
+            Stmt::For(name, start, end, body) => {
+                let start = self.number(start)?;
+                let end = self.number(end)?;
+                let mut i = start;
+                while i < end {
+                    self.step()?;
+                    self.declare(name, Value::Number(i));
+                    if let Flow::Return(value) = self.scoped_block(body)? {
+                        return Ok(Flow::Return(value));
+                    }
+                    i += 1.0;
+                }
+            }
+            Stmt::While(condition, body) => {
+                while self.truthy(condition)? {
+                    self.step()?;
+                    if let Flow::Return(value) = self.scoped_block(body)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+            }
+            Stmt::Return(value) => {
+                let value = match value {
+                    Some(value) => self.expression(value)?,
+                    None => Value::Nil,
+                };
+                return Ok(Flow::Return(value));
+            }
+            Stmt::Expr(expression) => {
+                self.expression(expression)?;
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn truthy(&mut self, expression: &Expr) -> Result<bool, String> {
+        Ok(match self.expression(expression)? {
+            Value::Nil | Value::Bool(false) => false,
+            Value::Number(n) => n != 0.0,
+            _ => true,
+        })
+    }
+
+    fn number(&mut self, expression: &Expr) -> Result<f64, String> {
+        match self.expression(expression)? {
+            Value::Number(n) => Ok(n),
+            value => Err(format!("expected a number but got '{value}'")),
+        }
+    }
+
+    fn expression(&mut self, expression: &Expr) -> Result<Value, String> {
+        self.nested(|context| context.evaluate(expression))
+    }
+
+    fn evaluate(&mut self, expression: &Expr) -> Result<Value, String> {
+        Ok(match expression {
+            Expr::Literal(value) => value.clone(),
+            Expr::Variable(name, line) => self
+                .variable(name)
+                .cloned()
+                .ok_or_else(|| format!("line {line}: unknown variable '{name}'"))?,
+            Expr::Call(name, arguments, line) => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| self.expression(argument))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.call_function(name, arguments, *line)?
+            }
+            Expr::Unary(operator, operand, line) => match (*operator, self.expression(operand)?) {
+                ("-", Value::Number(n)) => Value::Number(-n),
+                ("!", Value::Bool(b)) => Value::Bool(!b),
+                (operator, value) => {
+                    return Err(format!(
+                        "line {line}: can't apply '{operator}' to '{value}'"
+                    ))
+                }
+            },
+            Expr::Binary("&&", left, right, _) => {
+                Value::Bool(self.truthy(left)? && self.truthy(right)?)
+            }
+            Expr::Binary("||", left, right, _) => {
+                Value::Bool(self.truthy(left)? || self.truthy(right)?)
+            }
+            Expr::Binary(operator, left, right, line) => {
+                let left = self.expression(left)?;
+                let right = self.expression(right)?;
+                match (*operator, left, right) {
+                    ("==", a, b) => Value::Bool(a == b),
+                    ("!=", a, b) => Value::Bool(a != b),
+                    ("+", Value::String(a), b) => Value::String(format!("{a}{b}")),
+                    ("+", Value::Number(a), Value::Number(b)) => Value::Number(a + b),
+                    ("-", Value::Number(a), Value::Number(b)) => Value::Number(a - b),
+                    ("*", Value::Number(a), Value::Number(b)) => Value::Number(a * b),
+                    ("/", Value::Number(a), Value::Number(b)) => Value::Number(a / b),
+                    ("%", Value::Number(a), Value::Number(b)) => Value::Number(a.rem_euclid(b)),
+                    ("<", Value::Number(a), Value::Number(b)) => Value::Bool(a < b),
+                    ("<=", Value::Number(a), Value::Number(b)) => Value::Bool(a <= b),
+                    (">", Value::Number(a), Value::Number(b)) => Value::Bool(a > b),
+                    (">=", Value::Number(a), Value::Number(b)) => Value::Bool(a >= b),
+                    (operator, a, b) => {
+                        return Err(format!(
+                            "line {line}: can't apply '{operator}' to '{a}' and '{b}'"
+                        ))
+                    }
+                }
+            }
+        })
+    }
+
+    fn call_function(
+        &mut self,
+        name: &str,
+        arguments: Vec<Value>,
+        line: usize,
+    ) -> Result<Value, String> {
+        let functions = self.functions;
+        let Some(function) = functions.get(name) else {
+            return self
+                .builtin(name, &arguments)
+                .map_err(|error| format!("line {line}: {error}"));
+        };
+        if arguments.len() != function.parameters.len() {
+            return Err(format!(
+                "line {line}: '{name}' takes {} arguments but got {}",
+                function.parameters.len(),
+                arguments.len()
+            ));
+        }
+        let scope = function.parameters.iter().cloned().zip(arguments).collect();
+        let caller_locals = std::mem::replace(&mut self.locals, vec![scope]);
+        let flow = self.block(&function.body);
+        self.locals = caller_locals;
+        Ok(match flow? {
+            Flow::Normal => Value::Nil,
+            Flow::Return(value) => value,
+        })
+    }
+
+    fn builtin(&mut self, name: &str, arguments: &[Value]) -> Result<Value, String> {
+        let number = |index: usize| match arguments.get(index) {
+            Some(Value::Number(n)) => Ok(*n),
+            Some(value) => Err(format!(
+                "argument {} of '{name}' should be a number, got '{value}'",
+                index + 1
+            )),
+            None => Err(format!("'{name}' is missing argument {}", index + 1)),
+        };
+        let vec4 = |index: usize| -> Result<cgmath::Vector4<f32>, String> {
+            Ok(cgmath::vec4(
+                number(index)? as f32,
+                number(index + 1)? as f32,
+                number(index + 2)? as f32,
+                number(index + 3)? as f32,
+            ))
+        };
+        let string = |index: usize| match arguments.get(index) {
+            Some(Value::String(s)) => Ok(s.clone()),
+            Some(value) => Err(format!(
+                "argument {} of '{name}' should be a string, got '{value}'",
+                index + 1
+            )),
+            None => Err(format!("'{name}' is missing argument {}", index + 1)),
+        };
+        let index = |index: usize, len: usize| {
+            let i = number(index)?;
+            if i >= 0.0 && (i as usize) < len {
+                Ok(i as usize)
+            } else {
+                Err(format!("index {i} is out of range, there are {len}"))
+            }
+        };
+
+        // materials are referred to by id, see `MaterialId`
+        let material_index = |scene: &Scene, index: usize| {
+            let id = number(index)?;
+            scene
+                .material_index(MaterialId(id as _))
+                .filter(|_| id >= 0.0)
+                .ok_or_else(|| format!("there is no material {id}"))
+        };
+
+        let scene = &mut *self.scene;
+        let sphere_count = scene.hyper_sphere_count();
+        let plane_count = scene.hyper_plane_count();
+        Ok(match name {
+            "print" => {
+                self.output.push(
+                    arguments
+                        .iter()
+                        .map(|argument| argument.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                );
+                Value::Nil
+            }
+            "pi" => Value::Number(std::f64::consts::PI),
+            "sin" => Value::Number(number(0)?.sin()),
+            "cos" => Value::Number(number(0)?.cos()),
+            "tan" => Value::Number(number(0)?.tan()),
+            "sqrt" => Value::Number(number(0)?.sqrt()),
+            "abs" => Value::Number(number(0)?.abs()),
+            "floor" => Value::Number(number(0)?.floor()),
+            "min" => Value::Number(number(0)?.min(number(1)?)),
+            "max" => Value::Number(number(0)?.max(number(1)?)),
+            "random" => Value::Number(rand::random()),
+            "clear" => {
+                scene.clear_objects();
+                Value::Nil
+            }
+            "clear_materials" => {
+                scene.clear_materials();
+                Value::Nil
+            }
+            "material" => Value::Number(
+                scene
+                    .add_material(
+                        string(0)?,
+                        GpuMaterial {
+                            base_color: cgmath::vec3(
+                                number(1)? as _,
+                                number(2)? as _,
+                                number(3)? as _,
+                            ),
+                            emissive_color: cgmath::vec3(
+                                number(4)? as _,
+                                number(5)? as _,
+                                number(6)? as _,
+                            ),
+                            emission_strength: number(7)? as _,
+                            ..Default::default()
+                        },
+                    )
+                    .0 as _,
+            ),
+            "sphere" => {
+                scene.add_hyper_sphere(
+                    string(0)?,
+                    GpuHyperSphere {
+                        center: vec4(1)?,
+                        velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                        radius: number(5)? as _,
+                        material: number(6)? as _,
+                        inner_radius: 0.0,
+                    },
+                );
+                Value::Number(sphere_count as _)
+            }
+            "plane" => {
+                scene.add_hyper_plane(
+                    string(0)?,
+                    GpuHyperPlane {
+                        point: vec4(1)?,
+                        normal: vec4(5)?.normalize(),
+                        velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                        angular_velocity: BiVector4::ZERO,
+                        material: number(9)? as _,
+                        extents: cgmath::vec3(0.0, 0.0, 0.0),
+                        tangent: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                        bitangent: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                    },
+                );
+                Value::Number(plane_count as _)
+            }
+            "sphere_count" => Value::Number(sphere_count as _),
+            "plane_count" => Value::Number(plane_count as _),
+            "material_count" => Value::Number(scene.materials.len() as _),
+            "remove_sphere" => {
+                let i = index(0, sphere_count)?;
+                scene.remove_objects(&[ObjectRef::HyperSphere(i)]);
+                Value::Nil
+            }
+            "remove_plane" => {
+                let i = index(0, plane_count)?;
+                scene.remove_objects(&[ObjectRef::HyperPlane(i)]);
+                Value::Nil
+            }
+            "set_sphere_center" => {
+                scene
+                    .hyper_sphere_mut(index(0, sphere_count)?)
+                    .unwrap()
+                    .center = vec4(1)?;
+                Value::Nil
+            }
+            "set_sphere_velocity" => {
+                scene
+                    .hyper_sphere_mut(index(0, sphere_count)?)
+                    .unwrap()
+                    .velocity = vec4(1)?;
+                Value::Nil
+            }
+            "set_sphere_radius" => {
+                scene
+                    .hyper_sphere_mut(index(0, sphere_count)?)
+                    .unwrap()
+                    .radius = number(1)? as _;
+                Value::Nil
+            }
+            "set_sphere_inner_radius" => {
+                let hyper_sphere = scene.hyper_sphere_mut(index(0, sphere_count)?).unwrap();
+                hyper_sphere.inner_radius = (number(1)? as f32).clamp(0.0, hyper_sphere.radius);
+                Value::Nil
+            }
+            "set_sphere_material" => {
+                scene
+                    .hyper_sphere_mut(index(0, sphere_count)?)
+                    .unwrap()
+                    .material = number(1)? as _;
+                Value::Nil
+            }
+            "set_plane_point" => {
+                scene.hyper_plane_mut(index(0, plane_count)?).unwrap().point = vec4(1)?;
+                Value::Nil
+            }
+            "set_plane_normal" => {
+                scene
+                    .hyper_plane_mut(index(0, plane_count)?)
+                    .unwrap()
+                    .normal = vec4(1)?.normalize();
+                Value::Nil
+            }
+            "set_plane_velocity" => {
+                scene
+                    .hyper_plane_mut(index(0, plane_count)?)
+                    .unwrap()
+                    .velocity = vec4(1)?;
+                Value::Nil
+            }
+            "set_plane_angular_velocity" => {
+                scene
+                    .hyper_plane_mut(index(0, plane_count)?)
+                    .unwrap()
+                    .angular_velocity = BiVector4 {
+                    xy: number(1)? as _,
+                    xz: number(2)? as _,
+                    xw: number(3)? as _,
+                    yz: number(4)? as _,
+                    yw: number(5)? as _,
+                    zw: number(6)? as _,
+                };
+                Value::Nil
+            }
+            "set_plane_tangents" => {
+                let hyper_plane = scene.hyper_plane_mut(index(0, plane_count)?).unwrap();
+                hyper_plane.tangent = vec4(1)?;
+                hyper_plane.bitangent = vec4(5)?;
+                hyper_plane.orthonormalize_tangents();
+                Value::Nil
+            }
+            "set_plane_extents" => {
+                scene
+                    .hyper_plane_mut(index(0, plane_count)?)
+                    .unwrap()
+                    .extents = cgmath::vec3(
+                    (number(1)? as f32).max(0.0),
+                    (number(2)? as f32).max(0.0),
+                    (number(3)? as f32).max(0.0),
+                );
+                Value::Nil
+            }
+            "set_sphere_notes" => {
+                let i = index(0, sphere_count)?;
+                scene.object_mut(ObjectRef::HyperSphere(i)).unwrap().notes = string(1)?;
+                Value::Nil
+            }
+            "set_plane_notes" => {
+                let i = index(0, plane_count)?;
+                scene.object_mut(ObjectRef::HyperPlane(i)).unwrap().notes = string(1)?;
+                Value::Nil
+            }
+            "set_material_color" => {
+                let index = material_index(scene, 0)?;
+                scene.materials_mut()[index].base_color =
+                    cgmath::vec3(number(1)? as _, number(2)? as _, number(3)? as _);
+                Value::Nil
+            }
+            "set_material_emission" => {
+                let index = material_index(scene, 0)?;
+                let material = &mut scene.materials_mut()[index];
+                material.emissive_color =
+                    cgmath::vec3(number(1)? as _, number(2)? as _, number(3)? as _);
+                material.emission_strength = number(4)? as _;
+                Value::Nil
+            }
+            "set_material_emission_pattern" => {
+                let pattern = string(1)?;
+                let pattern = EmissionPattern::ALL
+                    .into_iter()
+                    .find(|p| p.name().eq_ignore_ascii_case(&pattern))
+                    .ok_or_else(|| format!("unknown emission pattern '{pattern}'"))?;
+                let index = material_index(scene, 0)?;
+                let material = &mut scene.materials_mut()[index];
+                material.emission_pattern = pattern as _;
+                material.emission_pattern_scale = vec4(2)?;
+                Value::Nil
+            }
+            "set_material_blend" => {
+                let mask = string(1)?;
+                let mask = BlendMask::ALL
+                    .into_iter()
+                    .find(|m| m.name().eq_ignore_ascii_case(&mask))
+                    .ok_or_else(|| format!("unknown blend mask '{mask}'"))?;
+                let index = material_index(scene, 0)?;
+                let a = scene.material_ids[material_index(scene, 2)?];
+                let b = scene.material_ids[material_index(scene, 3)?];
+                let material = &mut scene.materials_mut()[index];
+                material.blend_mask = mask as _;
+                material.blend_a = a.0;
+                material.blend_b = b.0;
+                material.blend_scale = vec4(4)?;
+                Value::Nil
+            }
+            "material_node" => {
+                let kind = string(1)?;
+                let kind = NodeKind::from_name(&kind)
+                    .ok_or_else(|| format!("unknown material node '{kind}'"))?;
+                let material = scene.material_ids[material_index(scene, 0)?];
+                let position = [number(2)? as f32, number(3)? as f32];
+                let value = vec4(4)?;
+                let graph = scene.node_graph_mut(material);
+                let id = graph.add_node(kind, position);
+                graph.node_mut(id).unwrap().value = value;
+                Value::Number(id.0 as _)
+            }
+            "connect_material_nodes" => {
+                let material = scene.material_ids[material_index(scene, 0)?];
+                let graph = scene.node_graph_mut(material);
+                let node = |index: usize| {
+                    let id = NodeId(number(index)? as _);
+                    graph
+                        .node(id)
+                        .map(|node| (id, node.kind))
+                        .ok_or_else(|| format!("there is no material node {}", id.0))
+                };
+                let (from, _) = node(1)?;
+                let (to, kind) = node(2)?;
+                let input = number(3)?;
+                if input < 0.0 || input as usize >= kind.inputs().len() {
+                    return Err(format!("{} nodes don't have input {input}", kind.name()));
+                }
+                graph.connect(Some(from), to, input as usize);
+                Value::Nil
+            }
+            "set_material_bump" => {
+                let index = material_index(scene, 0)?;
+                let material = &mut scene.materials_mut()[index];
+                material.bump_strength = number(1)? as _;
+                material.bump_scale = number(2)? as _;
+                Value::Nil
+            }
+            "set_material_glass" => {
+                let index = material_index(scene, 0)?;
+                let material = &mut scene.materials_mut()[index];
+                material.transmission = number(1)? as _;
+                material.ior = number(2)? as _;
+                material.dispersion = number(3)? as _;
+                Value::Nil
+            }
+            "set_material_absorption" => {
+                let index = material_index(scene, 0)?;
+                let material = &mut scene.materials_mut()[index];
+                material.absorption_color =
+                    cgmath::vec3(number(1)? as _, number(2)? as _, number(3)? as _);
+                material.absorption_density = number(4)?.max(0.0) as _;
+                Value::Nil
+            }
+            "set_material_clearcoat" => {
+                let index = material_index(scene, 0)?;
+                let material = &mut scene.materials_mut()[index];
+                material.clearcoat = number(1)?.clamp(0.0, 1.0) as _;
+                material.clearcoat_roughness = number(2)?.clamp(0.0, 1.0) as _;
+                Value::Nil
+            }
+            "set_material_sheen" => {
+                let index = material_index(scene, 0)?;
+                let material = &mut scene.materials_mut()[index];
+                material.sheen = number(1)?.max(0.0) as _;
+                material.sheen_color =
+                    cgmath::vec3(number(2)? as _, number(3)? as _, number(4)? as _);
+                Value::Nil
+            }
+            "set_material_priority" => {
+                let index = material_index(scene, 0)?;
+                let material = &mut scene.materials_mut()[index];
+                material.priority = number(1)?.max(0.0) as _;
+                Value::Nil
+            }
+            "light_group" => {
+                let mut light_group = LightGroup::new(string(0)?);
+                light_group.intensity = number(1)?.max(0.0) as _;
+                let group = scene
+                    .add_light_group(light_group)
+                    .ok_or_else(|| format!("there can only be {} light groups", LightGroup::MAX))?;
+                Value::Number(group as _)
+            }
+            "set_light_group_enabled" => {
+                let group = index(0, scene.light_groups.len() + 1)?;
+                if group == 0 {
+                    return Err("light group 0 is for materials without a group".into());
+                }
+                scene.light_groups[group - 1].enabled = match arguments.get(1) {
+                    Some(Value::Bool(enabled)) => *enabled,
+                    Some(value) => {
+                        return Err(format!(
+                            "argument 2 of '{name}' should be a boolean, got '{value}'"
+                        ))
+                    }
+                    None => return Err(format!("'{name}' is missing argument 2")),
+                };
+                Value::Nil
+            }
+            "set_material_light_group" => {
+                let material = material_index(scene, 0)?;
+                let group = index(1, scene.light_groups.len() + 1)?;
+                scene.materials_mut()[material].light_group = group as _;
+                Value::Nil
+            }
+            _ => return Err(format!("unknown function '{name}'")),
+        })
+    }
+}
+
+pub const EXAMPLE_SCRIPT: &str = r#"// a grid of spheres spread through x and w that bob up and down
+clear()
+clear_materials()
+let ground = material("Ground", 0.6, 0.6, 0.6, 0, 0, 0, 0)
+let light = material("Light", 1, 1, 1, 1, 0.9, 0.7, 3)
+plane("Ground", 0, 0, 0, 0, 0, 1, 0, 0, ground)
+
+let size = 4
+for x in 0..size {
+    for w in 0..size {
+        let color = material("Sphere", x / size, 0.5, w / size, 0, 0, 0, 0)
+        sphere("Sphere", x * 1.5 - 2.25, 0.5, 3, w * 1.5 - 2.25, 0.5, color)
+    }
+}
+sphere("Light", 0, 6, 3, 0, 2, light)
+
+fn update(time, dt) {
+    for i in 0..size * size {
+        let x = floor(i / size)
+        let w = i % size
+        set_sphere_center(i, x * 1.5 - 2.25, 0.5 + abs(sin(time * 2 + i)), 3, w * 1.5 - 2.25)
+    }
+}
+"#;
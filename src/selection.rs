@@ -0,0 +1,82 @@
+use crate::{ObjectId, ObjectRef, Scene};
+
+/// The selected objects, in the order they were selected
+///
+/// Objects are kept by their [`ObjectId`] so that the selection stays correct when other
+/// objects are added or removed, removed objects are skipped by [`Selection::objects`]
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Selection {
+    ids: Vec<ObjectId>,
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ids(&self) -> &[ObjectId] {
+        &self.ids
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn contains(&self, id: ObjectId) -> bool {
+        self.ids.contains(&id)
+    }
+
+    /// The most recently selected object, the one that single object tools act on
+    pub fn primary(&self) -> Option<ObjectId> {
+        self.ids.last().copied()
+    }
+
+    /// Replaces the selection with just `id`
+    pub fn select(&mut self, id: ObjectId) {
+        self.ids.clear();
+        self.ids.push(id);
+    }
+
+    /// Adds `id` to the selection, making it the primary object
+    pub fn add(&mut self, id: ObjectId) {
+        self.remove(id);
+        self.ids.push(id);
+    }
+
+    pub fn remove(&mut self, id: ObjectId) {
+        self.ids.retain(|&selected| selected != id);
+    }
+
+    pub fn toggle(&mut self, id: ObjectId) {
+        if self.contains(id) {
+            self.remove(id);
+        } else {
+            self.ids.push(id);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.ids.clear();
+    }
+
+    pub fn select_all(&mut self, scene: &Scene) {
+        self.ids = scene.objects.iter().map(|object| object.id).collect();
+    }
+
+    /// Where the selected objects currently are, skipping ones that have been removed
+    pub fn objects(&self, scene: &Scene) -> Vec<ObjectRef> {
+        self.ids
+            .iter()
+            .filter_map(|&id| scene.object_ref(id))
+            .collect()
+    }
+
+    /// Forgets the objects that have been removed from `scene`
+    pub fn retain_existing(&mut self, scene: &Scene) {
+        self.ids.retain(|&id| scene.object_ref(id).is_some());
+    }
+}
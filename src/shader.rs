@@ -0,0 +1,119 @@
+use crate::primitives_shader_module;
+use std::{borrow::Cow, collections::HashSet};
+
+/// Every shader module, embedded so the binary doesn't depend on the source tree
+pub const SHADER_MODULES: &[(&str, &str)] = &[
+    (
+        "ray_tracing.wgsl",
+        include_str!("./shaders/ray_tracing.wgsl"),
+    ),
+    ("trace.wgsl", include_str!("./shaders/trace.wgsl")),
+    ("scene.wgsl", include_str!("./shaders/scene.wgsl")),
+    ("rotor.wgsl", include_str!("./shaders/rotor.wgsl")),
+    ("intersect.wgsl", include_str!("./shaders/intersect.wgsl")),
+    (
+        "hyper_sphere.wgsl",
+        include_str!("./shaders/hyper_sphere.wgsl"),
+    ),
+    (
+        "hyper_plane.wgsl",
+        include_str!("./shaders/hyper_plane.wgsl"),
+    ),
+    ("random.wgsl", include_str!("./shaders/random.wgsl")),
+    ("sampler.wgsl", include_str!("./shaders/sampler.wgsl")),
+    ("brdf.wgsl", include_str!("./shaders/brdf.wgsl")),
+    ("sky.wgsl", include_str!("./shaders/sky.wgsl")),
+    ("noise.wgsl", include_str!("./shaders/noise.wgsl")),
+    ("emission.wgsl", include_str!("./shaders/emission.wgsl")),
+    ("blend.wgsl", include_str!("./shaders/blend.wgsl")),
+    ("nodes.wgsl", include_str!("./shaders/nodes.wgsl")),
+    ("dielectric.wgsl", include_str!("./shaders/dielectric.wgsl")),
+    ("spectral.wgsl", include_str!("./shaders/spectral.wgsl")),
+    ("compute.wgsl", include_str!("./shaders/compute.wgsl")),
+    ("wavefront.wgsl", include_str!("./shaders/wavefront.wgsl")),
+    ("fragment.wgsl", include_str!("./shaders/fragment.wgsl")),
+];
+
+pub const ENTRY_SHADER_MODULE: &str = "ray_tracing.wgsl";
+
+/// Generated by [`primitives_shader_module`] instead of being read from a file
+pub const PRIMITIVES_SHADER_MODULE: &str = "primitives.wgsl";
+
+pub fn embedded_shader_module(name: &str) -> Result<Cow<'static, str>, String> {
+    if name == PRIMITIVES_SHADER_MODULE {
+        return Ok(Cow::Owned(primitives_shader_module()));
+    }
+    SHADER_MODULES
+        .iter()
+        .find(|&&(module_name, _)| module_name == name)
+        .map(|&(_, source)| Cow::Borrowed(source))
+        .ok_or_else(|| format!("unknown shader module \"{name}\""))
+}
+
+/// Resolves `#import "module.wgsl"` (each module is only included once) and
+/// `#ifdef`/`#ifndef`/`#else`/`#endif` blocks against `defines`
+pub fn preprocess_shader<'a>(
+    entry: &str,
+    defines: &[&str],
+    mut load: impl FnMut(&str) -> Result<Cow<'a, str>, String>,
+) -> Result<String, String> {
+    let mut output = String::new();
+    let mut imported = HashSet::new();
+    preprocess_module(entry, defines, &mut load, &mut imported, &mut output)?;
+    Ok(output)
+}
+
+fn preprocess_module<'a>(
+    name: &str,
+    defines: &[&str],
+    load: &mut impl FnMut(&str) -> Result<Cow<'a, str>, String>,
+    imported: &mut HashSet<String>,
+    output: &mut String,
+) -> Result<(), String> {
+    if !imported.insert(name.to_string()) {
+        return Ok(());
+    }
+
+    let source = load(name)?;
+    // each entry is whether the lines in that block are active
+    let mut conditions: Vec<bool> = vec![];
+    for (line_number, line) in source.lines().enumerate() {
+        let error = |message: &str| format!("{name}:{}: {message}", line_number + 1);
+        let active = conditions.iter().all(|&active| active);
+
+        let trimmed = line.trim();
+        if let Some(define) = trimmed.strip_prefix("#ifdef ") {
+            conditions.push(defines.contains(&define.trim()));
+        } else if let Some(define) = trimmed.strip_prefix("#ifndef ") {
+            conditions.push(!defines.contains(&define.trim()));
+        } else if trimmed == "#else" {
+            let condition = conditions
+                .last_mut()
+                .ok_or_else(|| error("#else without #if"))?;
+            *condition = !*condition;
+        } else if trimmed == "#endif" {
+            conditions
+                .pop()
+                .ok_or_else(|| error("#endif without #if"))?;
+        } else if let Some(import) = trimmed.strip_prefix("#import ") {
+            if active {
+                let import = import
+                    .trim()
+                    .strip_prefix('"')
+                    .and_then(|import| import.strip_suffix('"'))
+                    .ok_or_else(|| error("expected a quoted module name after #import"))?;
+                preprocess_module(import, defines, load, imported, output)?;
+            }
+        } else if trimmed.starts_with('#') {
+            return Err(error(&format!("unknown directive \"{trimmed}\"")));
+        } else if active {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+
+    if !conditions.is_empty() {
+        return Err(format!("{name}: unterminated #ifdef"));
+    }
+    Ok(())
+}
@@ -0,0 +1,114 @@
+// exposure analysis of the finished image, a luminance histogram and a false color view
+
+@group(0)
+@binding(0)
+var input_texture: texture_2d<f32>;
+
+// `BIN_COUNT` luma bins, then how many pixels are clipped and how many are crushed
+@group(0)
+@binding(1)
+var<storage, read_write> counts: array<atomic<u32>, 66>;
+
+@group(0)
+@binding(2)
+var false_color_texture: texture_storage_2d<rgba8unorm, write>;
+
+struct Settings {
+    // whether the input is sRGB encoded
+    srgb: u32,
+}
+
+@group(0)
+@binding(3)
+var<uniform> settings: Settings;
+
+const BIN_COUNT: u32 = 64u;
+const CLIPPED: u32 = 64u;
+const CRUSHED: u32 = 65u;
+
+var<workgroup> workgroup_counts: array<atomic<u32>, 66>;
+
+fn luma(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+fn is_clipped(color: vec3<f32>) -> bool {
+    return max(color.r, max(color.g, color.b)) >= 1.0;
+}
+
+fn is_crushed(color: vec3<f32>) -> bool {
+    return max(color.r, max(color.g, color.b)) <= 0.0;
+}
+
+// each workgroup counts its pixels in shared memory first, so only the bins it hit are added
+// to the global counts instead of every pixel contending for the same few atomics
+@compute
+@workgroup_size(16, 16)
+fn histogram(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    if local_index < 66u {
+        atomicStore(&workgroup_counts[local_index], 0u);
+    }
+    workgroupBarrier();
+
+    let size = textureDimensions(input_texture);
+    let coords = vec2<i32>(global_id.xy);
+    if coords.x < size.x && coords.y < size.y {
+        let color = textureLoad(input_texture, coords, 0).rgb;
+        // binned by the stored values, so the bins are perceptually even when they are sRGB
+        let bin = min(u32(luma(color) * f32(BIN_COUNT)), BIN_COUNT - 1u);
+        atomicAdd(&workgroup_counts[bin], 1u);
+        if is_clipped(color) {
+            atomicAdd(&workgroup_counts[CLIPPED], 1u);
+        }
+        if is_crushed(color) {
+            atomicAdd(&workgroup_counts[CRUSHED], 1u);
+        }
+    }
+    workgroupBarrier();
+
+    if local_index < 66u {
+        let count = atomicLoad(&workgroup_counts[local_index]);
+        if count > 0u {
+            atomicAdd(&counts[local_index], count);
+        }
+    }
+}
+
+fn linear_from_srgb(value: f32) -> f32 {
+    if value <= 0.04045 {
+        return value / 12.92;
+    }
+    return pow((value + 0.055) / 1.055, 2.4);
+}
+
+// clipped pixels are red, crushed ones blue, ones around middle grey green and the rest grey
+@compute
+@workgroup_size(16, 16)
+fn false_color(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+) {
+    let size = textureDimensions(input_texture);
+    let coords = vec2<i32>(global_id.xy);
+    if coords.x >= size.x || coords.y >= size.y {
+        return;
+    }
+
+    let color = textureLoad(input_texture, coords, 0).rgb;
+    var linear_luma = luma(color);
+    if settings.srgb != 0u {
+        linear_luma = luma(vec3<f32>(linear_from_srgb(color.r), linear_from_srgb(color.g), linear_from_srgb(color.b)));
+    }
+
+    var result = vec3<f32>(luma(color) * 0.8);
+    if is_clipped(color) {
+        result = vec3<f32>(1.0, 0.0, 0.0);
+    } else if is_crushed(color) {
+        result = vec3<f32>(0.0, 0.0, 1.0);
+    } else if abs(linear_luma - 0.18) < 0.02 {
+        result = vec3<f32>(0.0, 1.0, 0.0);
+    }
+    textureStore(false_color_texture, coords, vec4<f32>(result, 1.0));
+}
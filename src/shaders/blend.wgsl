@@ -0,0 +1,65 @@
+#import "scene.wgsl"
+#import "noise.wgsl"
+
+// must match `MAX_BLEND_DEPTH`, past it the material that was reached is used as it is
+const MAX_BLEND_DEPTH: u32 = 8u;
+
+// 4D value noise in [-1, 1], smoothstep interpolated between the 16 corners of each cell
+fn value_noise(p: vec4<f32>) -> f32 {
+    let cell = floor(p);
+    let f = p - cell;
+    let t = f * f * (3.0 - 2.0 * f);
+
+    var value = 0.0;
+    for (var i = 0u; i < 16u; i += 1u) {
+        let corner = vec4<f32>(vec4<u32>(i, i >> 1u, i >> 2u, i >> 3u) & vec4<u32>(1u));
+        let w = mix(1.0 - t, t, corner);
+        value += lattice_value(vec4<i32>(cell) + vec4<i32>(corner)) * w.x * w.y * w.z * w.w;
+    }
+    return value;
+}
+
+// the chance of a blended material using `blend_b` at the already scaled position,
+// the cases must match the order of `BlendMask`
+fn blend_mask(mask: u32, scaled: vec4<f32>) -> f32 {
+    switch mask {
+        // noise
+        case 1u: {
+            return 0.5 + 0.5 * value_noise(scaled);
+        }
+        // gradient
+        case 2u: {
+            return clamp(0.5 + scaled.w, 0.0, 1.0);
+        }
+        // checker
+        case 3u: {
+            let cell = vec4<i32>(floor(scaled));
+            return f32((cell.x + cell.y + cell.z + cell.w) & 1);
+        }
+        default: {
+            return 0.0;
+        }
+    }
+}
+
+// follows the blends starting at `material_index` to the material a hit at `position` uses,
+// `sample` is rescaled at every blend so one random number is enough for the whole tree, see `MaterialNode::resolve`
+fn resolve_material(material_index: u32, position: vec4<f32>, sample: f32) -> u32 {
+    var index = material_index;
+    var remaining = sample;
+    for (var depth = 0u; depth < MAX_BLEND_DEPTH; depth += 1u) {
+        let material = get_material(index);
+        if material.blend_mask == 0u {
+            break;
+        }
+        let mask = blend_mask(material.blend_mask, position * material.blend_scale);
+        if remaining < mask {
+            remaining /= mask;
+            index = material.blend_b;
+        } else {
+            remaining = (remaining - mask) / (1.0 - mask);
+            index = material.blend_a;
+        }
+    }
+    return index;
+}
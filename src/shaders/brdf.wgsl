@@ -0,0 +1,29 @@
+#import "scene.wgsl"
+#import "sampler.wgsl"
+
+fn diffuse_direction(normal: vec4<f32>, sample_state: ptr<function, SampleState>) -> vec4<f32> {
+    return normalize(normal + sample_direction(sample_state));
+}
+
+// the Schlick fresnel term of a clear coat with an index of refraction of 1.5
+fn clearcoat_reflectance(direction: vec4<f32>, normal: vec4<f32>) -> f32 {
+    let cos_theta = clamp(-dot(direction, normal), 0.0, 1.0);
+    return 0.04 + 0.96 * pow(1.0 - cos_theta, 5.0);
+}
+
+// a mirror reflection blurred by `roughness`, kept above the surface
+fn glossy_direction(direction: vec4<f32>, normal: vec4<f32>, roughness: f32, sample_state: ptr<function, SampleState>) -> vec4<f32> {
+    var scattered = normalize(reflect(direction, normal) + sample_direction(sample_state) * roughness);
+    let below = dot(scattered, normal);
+    if below < 0.0 {
+        scattered -= 2.0 * below * normal;
+    }
+    return scattered;
+}
+
+// the Disney sheen lobe, strongest when light comes in and leaves at grazing angles
+fn sheen_color(material: Material, incoming: vec4<f32>, outgoing: vec4<f32>) -> vec3<f32> {
+    let half_vector = normalize(outgoing - incoming);
+    let cos_d = clamp(dot(outgoing, half_vector), 0.0, 1.0);
+    return material.sheen_color * (material.sheen * pow(1.0 - cos_d, 5.0));
+}
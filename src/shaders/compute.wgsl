@@ -0,0 +1,161 @@
+#import "trace.wgsl"
+
+@group(0)
+@binding(0)
+var output_texture: texture_storage_2d<rgba8unorm, write>;
+
+// the sum of every accumulated frame, 3 floats per pixel
+@group(0)
+@binding(1)
+var<storage, read_write> accumulation: array<f32>;
+
+struct Tile {
+    offset: vec2<u32>,
+    size: vec2<u32>,
+}
+
+// the part of the output this dispatch renders
+@group(0)
+@binding(2)
+var<uniform> tile: Tile;
+
+#ifdef AOVS
+// albedo and depth, the normal, direct light and w depth, then indirect light
+@group(0)
+@binding(4)
+var aov_texture: texture_storage_2d_array<rgba32float, write>;
+
+// the AOVs of every pixel are summed after all of the colors
+#ifdef LIGHT_GROUPS
+const COLOR_FLOATS: u32 = 15u;
+#else
+const COLOR_FLOATS: u32 = 3u;
+#endif
+
+// adds `aov_average` to the pixel's sums and stores the averages in each layer of `aov_texture`
+fn accumulate_aovs(coords: vec2<i32>, size: vec2<i32>) {
+    let pixel = u32(coords.x + coords.y * size.x);
+    let start = u32(size.x * size.y) * COLOR_FLOATS + pixel * 16u;
+    var layers = array<vec4<f32>, 4>(
+        vec4<f32>(aov_average.albedo, aov_average.depth),
+        aov_average.normal,
+        vec4<f32>(aov_average.direct, aov_average.w_depth),
+        vec4<f32>(aov_average.indirect, 0.0),
+    );
+    for (var layer = 0u; layer < 4u; layer += 1u) {
+        let index = start + layer * 4u;
+        var sum = layers[layer];
+        if camera.accumulated_frames > 0u {
+            sum += vec4<f32>(accumulation[index], accumulation[index + 1u], accumulation[index + 2u], accumulation[index + 3u]);
+        }
+        accumulation[index] = sum.x;
+        accumulation[index + 1u] = sum.y;
+        accumulation[index + 2u] = sum.z;
+        accumulation[index + 3u] = sum.w;
+        textureStore(aov_texture, coords, i32(layer), sum / f32(camera.accumulated_frames + 1u));
+    }
+}
+#endif
+
+#ifdef CULLING
+// finds the hyper spheres that are at least partly inside the cone around `camera.forward`
+// holding every camera ray and within `camera.max_distance`
+@compute
+@workgroup_size(64)
+fn cull_hyper_spheres(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+) {
+    let index = global_id.x;
+    if index >= hyper_spheres.count {
+        return;
+    }
+
+    let size = vec2<f32>(textureDimensions(output_texture));
+    let theta = tan(camera.fov / 2.0);
+    var aspect = size.x / size.y;
+    if camera.aspect_override > 0.0 {
+        aspect = camera.aspect_override;
+    }
+    // the jitter can move a sample up to a pixel past the edge, and the lens shift moves the
+    // whole image off center
+    let extent = vec2<f32>(1.0) + abs(camera.lens_shift) + 2.0 / size;
+    let corner = vec2<f32>(aspect * theta * extent.x, theta * extent.y);
+    let cone_angle = atan(length(corner));
+
+    // grown to cover everywhere it moves to while the shutter is open, and by how far the
+    // stereo eyes are from the camera so it is conservative for both of them
+    let hyper_sphere = get_hyper_sphere(index);
+    let center = hyper_sphere.center + hyper_sphere.velocity * (camera.shutter_time * 0.5);
+    let radius = hyper_sphere.radius + length(hyper_sphere.velocity) * camera.shutter_time * 0.5 + abs(camera.eye_offset);
+
+    let offset = center - camera.position;
+    let distance = length(offset);
+    var visible = distance <= radius;
+    if !visible && distance - radius <= camera.max_distance {
+        let angle = acos(clamp(dot(offset, camera.forward) / distance, -1.0, 1.0));
+        visible = angle - asin(radius / distance) <= cone_angle;
+    }
+    if visible {
+        let slot = atomicAdd(&visible_hyper_spheres.count, 1u);
+        visible_hyper_spheres.indices[slot] = index;
+    }
+}
+#endif
+
+#ifdef DEBUG_PATH
+// traces the single pixel in `debug_path`, recording the path of its first sample
+@compute
+@workgroup_size(1)
+fn ray_trace() {
+    let size = textureDimensions(output_texture);
+    debug_path.color = clamp(ray_trace_pixel(vec2<i32>(debug_path.pixel), size), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+#else
+@compute
+@workgroup_size(16, 16)
+fn ray_trace(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+) {
+    let size = textureDimensions(output_texture);
+    let coords = vec2<i32>(global_id.xy + tile.offset);
+
+    if global_id.x >= tile.size.x || global_id.y >= tile.size.y || coords.x >= size.x || coords.y >= size.y {
+        return;
+    }
+
+#ifdef LIGHT_GROUPS
+    // each light group is summed apart so changing their weights reuses what was accumulated,
+    // the weighted sum is only clamped once it is shown
+    ray_trace_pixel(coords, size);
+    let pixel = u32(coords.x + coords.y * size.x);
+    var color = vec3<f32>(0.0);
+    for (var group = 0u; group < 5u; group += 1u) {
+        let index = (pixel * 5u + group) * 3u;
+        var sum = light_group_light[group];
+        if camera.accumulated_frames > 0u {
+            sum += vec3<f32>(accumulation[index], accumulation[index + 1u], accumulation[index + 2u]);
+        }
+        accumulation[index] = sum.r;
+        accumulation[index + 1u] = sum.g;
+        accumulation[index + 2u] = sum.b;
+        color += sum * light_group_weight(group);
+    }
+    textureStore(output_texture, coords.xy, encode_output(color / f32(camera.accumulated_frames + 1u)));
+#else
+    let index = u32(coords.x + coords.y * size.x) * 3u;
+    var total = ray_trace_pixel(coords, size);
+    if camera.accumulated_frames > 0u {
+        total += vec3<f32>(accumulation[index], accumulation[index + 1u], accumulation[index + 2u]);
+    }
+    accumulation[index] = total.r;
+    accumulation[index + 1u] = total.g;
+    accumulation[index + 2u] = total.b;
+
+    // the average stays linear and unclamped, only what is shown gets encoded
+    textureStore(output_texture, coords.xy, encode_output(total / f32(camera.accumulated_frames + 1u)));
+#endif
+#ifdef AOVS
+    accumulate_aovs(coords, size);
+#endif
+}
+#endif
@@ -0,0 +1,130 @@
+#import "scene.wgsl"
+#import "sampler.wgsl"
+
+// Cauchy's equation, `material.ior` is the index at 589.3nm
+fn index_of_refraction(material: Material, wavelength: f32) -> f32 {
+    let micrometers = wavelength * 0.001;
+    return material.ior + material.dispersion * (1.0 / (micrometers * micrometers) - 1.0 / (0.5893 * 0.5893));
+}
+
+// the transmissive materials a ray is inside of, innermost last, so that overlapping objects
+// refract with the media on both sides of a boundary. When they overlap the one with the highest
+// `priority` fills the overlap, the boundaries of the others inside of it are false hits
+struct MediumStack {
+    materials: array<u32, 4>,
+    count: u32,
+}
+
+// where the highest priority medium in the stack is, skipping the one at `excluded`, the innermost
+// wins ties. 4 if there isn't one and the ray is in air
+fn medium_stack_top(stack: ptr<function, MediumStack>, excluded: u32) -> u32 {
+    var top = 4u;
+    var top_priority = 0u;
+    for (var i = 0u; i < (*stack).count; i += 1u) {
+        let priority = get_material((*stack).materials[i]).priority;
+        if i != excluded {
+            if top == 4u || priority >= top_priority {
+                top = i;
+                top_priority = priority;
+            }
+        }
+    }
+    return top;
+}
+
+fn medium_ior(stack: ptr<function, MediumStack>, index: u32, wavelength: f32) -> f32 {
+    if index == 4u {
+        return 1.0;
+    }
+    return index_of_refraction(get_material((*stack).materials[index]), wavelength);
+}
+
+// how much light is left after travelling `distance` through the medium the ray is in
+fn medium_transmittance(stack: ptr<function, MediumStack>, distance: f32) -> vec3<f32> {
+    let top = medium_stack_top(stack, 4u);
+    if top == 4u {
+        return vec3<f32>(1.0);
+    }
+    let material = get_material((*stack).materials[top]);
+    let depth = material.absorption_density * distance;
+    if depth <= 0.0 {
+        return vec3<f32>(1.0);
+    }
+    return pow(material.absorption_color, vec3<f32>(depth));
+}
+
+struct Boundary {
+    false_hit: bool,
+    from_ior: f32,
+    to_ior: f32,
+    // where the hit material is in the stack, 4 if it isn't
+    inside: u32,
+}
+
+fn medium_boundary(stack: ptr<function, MediumStack>, hit: Hit, material: Material, wavelength: f32) -> Boundary {
+    var boundary: Boundary;
+    boundary.inside = 4u;
+    for (var i = 0u; i < (*stack).count; i += 1u) {
+        if (*stack).materials[i] == hit.material {
+            boundary.inside = i;
+        }
+    }
+
+    // entering goes from the current medium into the material, leaving goes back out into
+    // whatever is left once the material is taken off the stack
+    let outside = medium_stack_top(stack, boundary.inside);
+    let ior = index_of_refraction(material, wavelength);
+    if hit.front_face {
+        boundary.from_ior = medium_ior(stack, outside, wavelength);
+        boundary.to_ior = ior;
+    } else {
+        boundary.from_ior = ior;
+        boundary.to_ior = medium_ior(stack, outside, wavelength);
+    }
+    boundary.false_hit = false;
+    if outside != 4u {
+        boundary.false_hit = get_material((*stack).materials[outside]).priority > material.priority;
+    }
+    return boundary;
+}
+
+// updates the stack after the ray refracted through the boundary
+fn medium_stack_cross(stack: ptr<function, MediumStack>, hit: Hit, boundary: Boundary) {
+    if hit.front_face {
+        if (*stack).count < 4u {
+            (*stack).materials[(*stack).count] = hit.material;
+            (*stack).count += 1u;
+        }
+    } else if boundary.inside != 4u {
+        for (var i = boundary.inside; i + 1u < (*stack).count; i += 1u) {
+            (*stack).materials[i] = (*stack).materials[i + 1u];
+        }
+        (*stack).count -= 1u;
+    }
+}
+
+// either reflects or refracts through the surface between media with indices of refraction
+// `from_ior` and `to_ior`, picked with the Schlick approximation of the fresnel term
+fn dielectric_scatter(ray: Ray, hit: Hit, from_ior: f32, to_ior: f32, sample_state: ptr<function, SampleState>) -> Ray {
+    let eta = from_ior / to_ior;
+    let cos_theta = min(-dot(ray.direction, hit.normal), 1.0);
+    var r0 = (from_ior - to_ior) / (from_ior + to_ior);
+    r0 *= r0;
+    let reflectance = r0 + (1.0 - r0) * pow(1.0 - cos_theta, 5.0);
+
+    var scattered = ray;
+    let refracted = refract(ray.direction, hit.normal, eta);
+    // `refract` returns zero for total internal reflection
+    var reflected = all(refracted == vec4<f32>(0.0));
+    if !reflected {
+        reflected = sample_1d(sample_state) < reflectance;
+    }
+    if reflected {
+        scattered.origin = hit.position + hit.normal * camera.min_distance;
+        scattered.direction = reflect(ray.direction, hit.normal);
+    } else {
+        scattered.origin = hit.position - hit.normal * camera.min_distance;
+        scattered.direction = normalize(refracted);
+    }
+    return scattered;
+}
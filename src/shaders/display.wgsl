@@ -0,0 +1,35 @@
+// copies the output texture into an sRGB texture without changing the stored bytes, so that
+// sampling it gives back the linear colors the output encodes
+
+@group(0)
+@binding(0)
+var output_texture: texture_2d<f32>;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+}
+
+@vertex
+fn vertex(
+    @builtin(vertex_index) vertex_index: u32,
+) -> VertexOutput {
+    // a single triangle that covers the whole screen
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    return out;
+}
+
+fn linear_from_srgb(value: f32) -> f32 {
+    if value <= 0.04045 {
+        return value / 12.92;
+    }
+    return pow((value + 0.055) / 1.055, 2.4);
+}
+
+@fragment
+fn display(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureLoad(output_texture, vec2<i32>(floor(in.position.xy)), 0);
+    // the target encodes it again on write
+    return vec4<f32>(linear_from_srgb(color.r), linear_from_srgb(color.g), linear_from_srgb(color.b), color.a);
+}
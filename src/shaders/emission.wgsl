@@ -0,0 +1,39 @@
+#import "scene.wgsl"
+
+// how much of the material's emission strength is used at `position`,
+// the cases must match the order of `EmissionPattern`
+fn emission_pattern(material: Material, position: vec4<f32>) -> f32 {
+    let scaled = position * material.emission_pattern_scale;
+    switch material.emission_pattern {
+        // gradient
+        case 1u: {
+            return clamp(0.5 + dot(scaled, vec4<f32>(1.0)), 0.0, 1.0);
+        }
+        // stripes
+        case 2u: {
+            return step(0.5, fract(dot(scaled, vec4<f32>(1.0))));
+        }
+        // checker
+        case 3u: {
+            let cell = vec4<i32>(floor(scaled));
+            return f32((cell.x + cell.y + cell.z + cell.w) & 1);
+        }
+        default: {
+            return 1.0;
+        }
+    }
+}
+
+// what the emission of `light_group` is scaled by, materials without a group aren't scaled
+fn light_group_weight(light_group: u32) -> f32 {
+    if light_group == 0u || light_group > 4u {
+        return 1.0;
+    }
+    return camera.light_group_weights[light_group - 1u];
+}
+
+#ifdef LIGHT_GROUPS
+// the light each light group added to the current sample, the first is everything that isn't
+// in a group including the sky, left unweighted so `compute.wgsl` can accumulate them apart
+var<private> light_group_sample: array<vec3<f32>, 5>;
+#endif
@@ -0,0 +1,26 @@
+#import "trace.wgsl"
+
+@group(0)
+@binding(0)
+var<uniform> output_size: vec2<u32>;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+}
+
+@vertex
+fn vertex(
+    @builtin(vertex_index) vertex_index: u32,
+) -> VertexOutput {
+    // a single triangle that covers the whole screen
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    return out;
+}
+
+@fragment
+fn ray_trace(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coords = vec2<i32>(floor(in.position.xy));
+    return encode_output(ray_trace_pixel(coords, vec2<i32>(output_size)));
+}
@@ -0,0 +1,63 @@
+#import "intersect.wgsl"
+
+fn intersect_hyper_plane(ray: Ray, hyper_plane: HyperPlane) -> Hit {
+    var hit: Hit;
+    hit.hit = false;
+    hit.material = hyper_plane.material;
+
+    let point = hyper_plane.point + hyper_plane.velocity * ray.time;
+    let normal = rotate_by_angular_velocity(hyper_plane.normal, hyper_plane.angular_velocity, ray.time);
+
+    let d = dot(normal, ray.direction);
+    if d == 0.0 {
+        return hit;
+    }
+
+    let p = point - ray.origin;
+    hit.distance = dot(p, normal) / d;
+
+    if hit.distance < camera.min_distance || camera.max_distance < hit.distance {
+        return hit;
+    }
+
+    hit.position = ray.origin + ray.direction * hit.distance;
+    hit.pattern_position = hit.position;
+
+    let bounded = hyper_plane.extents > vec3<f32>(0.0);
+    let has_own_tangents = dot(hyper_plane.tangent, hyper_plane.tangent) + dot(hyper_plane.bitangent, hyper_plane.bitangent) > 0.0;
+    if any(bounded) || has_own_tangents {
+        let basis = plane_basis(hyper_plane);
+        let offset = hit.position - point;
+        let coordinates = vec3<f32>(
+            dot(offset, rotate_by_angular_velocity(basis.tangent, hyper_plane.angular_velocity, ray.time)),
+            dot(offset, rotate_by_angular_velocity(basis.bitangent, hyper_plane.angular_velocity, ray.time)),
+            dot(offset, rotate_by_angular_velocity(basis.third, hyper_plane.angular_velocity, ray.time)),
+        );
+        if any(bounded & (abs(coordinates) > hyper_plane.extents)) {
+            return hit;
+        }
+        if basis.custom {
+            hit.pattern_position = vec4<f32>(coordinates, 0.0);
+        }
+    }
+
+    hit.normal = normal;
+    hit.front_face = true;
+    if dot(hit.normal, ray.origin - hit.position) < 0.0 {
+        hit.normal *= -1.0;
+        hit.front_face = false;
+    }
+
+    hit.hit = true;
+    return hit;
+}
+
+fn hyper_plane_hit(ray: Ray, index: u32) -> Hit {
+    var hit = intersect_hyper_plane(ray, get_hyper_plane(index));
+#ifdef CROSS_SECTION
+    if hit.hit && is_cut_away(hit.position) {
+        hit.hit = false;
+    }
+#endif
+    return hit;
+}
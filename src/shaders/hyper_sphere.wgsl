@@ -0,0 +1,172 @@
+#import "intersect.wgsl"
+
+// the first distance past `min_distance` where the ray crosses a sphere of `radius`, or -1
+fn sphere_crossing(oc: vec4<f32>, direction: vec4<f32>, radius: f32, min_distance: f32) -> f32 {
+    let a = dot(direction, direction);
+    let half_b = dot(oc, direction);
+    let c = dot(oc, oc) - radius * radius;
+    let discriminant = half_b * half_b - a * c;
+
+    if discriminant < 0.0 {
+        return -1.0;
+    }
+
+    let sqrt_discriminant = sqrt(discriminant);
+    let t0 = (-half_b - sqrt_discriminant) / a;
+    let t1 = (-half_b + sqrt_discriminant) / a;
+
+    if t0 > min_distance {
+        return t0;
+    }
+    if t1 > min_distance {
+        return t1;
+    }
+    return -1.0;
+}
+
+fn intersect_hyper_sphere(ray: Ray, hyper_sphere: HyperSphere) -> Hit {
+    return intersect_hyper_sphere_after(ray, hyper_sphere, camera.min_distance);
+}
+
+// hollow hyper spheres are hit on whichever of their two surfaces comes first, the normal
+// of the inner one points into the hollow
+fn intersect_hyper_sphere_after(ray: Ray, hyper_sphere: HyperSphere, min_distance: f32) -> Hit {
+    var hit: Hit;
+    hit.hit = false;
+    hit.material = hyper_sphere.material;
+
+    let center = hyper_sphere.center + hyper_sphere.velocity * ray.time;
+    let oc = ray.origin - center;
+
+    hit.distance = sphere_crossing(oc, ray.direction, hyper_sphere.radius, min_distance);
+    var inner = false;
+    if hyper_sphere.inner_radius > 0.0 {
+        let inner_distance = sphere_crossing(oc, ray.direction, hyper_sphere.inner_radius, min_distance);
+        if inner_distance >= 0.0 && (hit.distance < 0.0 || inner_distance < hit.distance) {
+            hit.distance = inner_distance;
+            inner = true;
+        }
+    }
+
+    if hit.distance < 0.0 || camera.max_distance < hit.distance {
+        return hit;
+    }
+
+    hit.position = ray.origin + ray.direction * hit.distance;
+    hit.pattern_position = hit.position;
+    hit.normal = normalize(hit.position - center);
+    if inner {
+        hit.normal *= -1.0;
+    }
+    hit.front_face = true;
+    if dot(hit.normal, ray.origin - hit.position) < 0.0 {
+        hit.normal *= -1.0;
+        hit.front_face = false;
+    }
+
+    hit.hit = true;
+    return hit;
+}
+
+#ifdef CROSS_SECTION
+// a hit on the cut away side becomes the point where the ray crosses the cut inside the hyper sphere, if it does
+fn cut_hyper_sphere(ray: Ray, hyper_sphere: HyperSphere, hit: Hit) -> Hit {
+    if !hit.hit || !is_cut_away(hit.position) {
+        return hit;
+    }
+
+    var cut_hit = hit;
+    cut_hit.hit = false;
+
+    let d = dot(camera.cut_normal, ray.direction);
+    if d == 0.0 {
+        return cut_hit;
+    }
+
+    let t = dot(camera.cut_point - ray.origin, camera.cut_normal) / d;
+    if t < camera.min_distance || camera.max_distance < t {
+        return cut_hit;
+    }
+
+    let position = ray.origin + ray.direction * t;
+    let center = hyper_sphere.center + hyper_sphere.velocity * ray.time;
+    let from_center = distance(position, center);
+    if from_center > hyper_sphere.radius {
+        return cut_hit;
+    }
+    // the cut goes through the hollow, so the ray carries on to the far inner surface
+    if from_center < hyper_sphere.inner_radius {
+        let next_hit = intersect_hyper_sphere_after(ray, hyper_sphere, t);
+        if next_hit.hit && !is_cut_away(next_hit.position) {
+            return next_hit;
+        }
+        return cut_hit;
+    }
+
+    cut_hit.hit = true;
+    cut_hit.distance = t;
+    cut_hit.position = position;
+    cut_hit.pattern_position = position;
+    cut_hit.normal = camera.cut_normal * -sign(d);
+    cut_hit.cut = true;
+    cut_hit.front_face = true;
+    return cut_hit;
+}
+#endif
+
+fn hyper_sphere_hit(ray: Ray, index: u32) -> Hit {
+    let hyper_sphere = get_hyper_sphere(index);
+    var hit = intersect_hyper_sphere(ray, hyper_sphere);
+#ifdef CROSS_SECTION
+    hit = cut_hyper_sphere(ray, hyper_sphere, hit);
+#endif
+    return hit;
+}
+
+#ifdef BVH
+// whether the ray passes through the box before `max_distance`, `inverse_direction` is one over
+// the ray's direction with zeros replaced so they don't divide by zero
+fn ray_hits_box(origin: vec4<f32>, inverse_direction: vec4<f32>, box_min: vec4<f32>, box_max: vec4<f32>, max_distance: f32) -> bool {
+    let t0 = (box_min - origin) * inverse_direction;
+    let t1 = (box_max - origin) * inverse_direction;
+    let near = min(t0, t1);
+    let far = max(t0, t1);
+    let enter = max(max(near.x, near.y), max(near.z, near.w));
+    let exit = min(min(far.x, far.y), min(far.z, far.w));
+    return enter <= exit && exit >= 0.0 && enter <= max_distance;
+}
+
+// checks the leaf's hyper spheres
+fn closest_bvh_leaf_hit(ray: Ray, node: u32, closest_hit: Hit) -> Hit {
+    var closest_hit = closest_hit;
+    for (var i = 0u; i < bvh.data[node].count; i += 1u) {
+        let hit = hyper_sphere_hit(ray, bvh.data[node].hyper_spheres[i]);
+        if hit.hit && hit.distance < closest_hit.distance {
+            closest_hit = hit;
+        }
+    }
+    return closest_hit;
+}
+
+// the tree is walked in depth first order without a stack, nodes the ray misses are skipped
+// along with everything below them
+fn closest_bvh_hyper_sphere_hit(ray: Ray, closest_hit: Hit) -> Hit {
+    var closest_hit = closest_hit;
+    let inverse_direction = 1.0 / select(ray.direction, vec4<f32>(1e-20), abs(ray.direction) < vec4<f32>(1e-20));
+    var node = 0u;
+    while node < bvh.tree_nodes {
+        if !ray_hits_box(ray.origin, inverse_direction, bvh.data[node].min, bvh.data[node].max, closest_hit.distance) {
+            node = bvh.data[node].skip;
+        } else if bvh.data[node].count > 0u {
+            closest_hit = closest_bvh_leaf_hit(ray, node, closest_hit);
+            node = bvh.data[node].skip;
+        } else {
+            node += 1u;
+        }
+    }
+    for (var leaf = bvh.tree_nodes; leaf < bvh.count; leaf += 1u) {
+        closest_hit = closest_bvh_leaf_hit(ray, leaf, closest_hit);
+    }
+    return closest_hit;
+}
+#endif
@@ -0,0 +1,28 @@
+#import "scene.wgsl"
+#import "rotor.wgsl"
+#import "primitives.wgsl"
+
+#ifdef CROSS_SECTION
+fn is_cut_away(position: vec4<f32>) -> bool {
+    return dot(position - camera.cut_point, camera.cut_normal) > 0.0;
+}
+#endif
+
+fn get_closest_hit(ray: Ray) -> Hit {
+    var closest_hit: Hit;
+    closest_hit.hit = false;
+    closest_hit.distance = camera.max_distance;
+    return closest_primitive_hit(ray, closest_hit);
+}
+
+// `get_closest_hit` for rays starting at the camera, only the visible culled primitives are checked
+fn get_closest_camera_hit(ray: Ray) -> Hit {
+#ifdef CULLING
+    var closest_hit: Hit;
+    closest_hit.hit = false;
+    closest_hit.distance = camera.max_distance;
+    return closest_visible_primitive_hit(ray, closest_hit);
+#else
+    return get_closest_hit(ray);
+#endif
+}
@@ -0,0 +1,156 @@
+#import "scene.wgsl"
+#import "blend.wgsl"
+
+// must match `MAX_NODE_INSTRUCTIONS`, there is a register for each instruction
+const MAX_NODE_INSTRUCTIONS: u32 = 32u;
+// must match `NO_REGISTER`, inputs that aren't connected read the instruction's value instead
+const NO_REGISTER: u32 = 0xffffffffu;
+
+struct NodeInstruction {
+    // a `NodeKind`
+    kind: u32,
+    a: u32,
+    b: u32,
+    c: u32,
+    value: vec4<f32>,
+}
+
+struct NodePrograms {
+    count: u32,
+    data: array<NodeInstruction>,
+}
+
+#ifdef FRAGMENT
+@group(3)
+@binding(1)
+var node_programs: texture_2d<u32>;
+
+fn node_instruction_count() -> u32 {
+    return textureLoad(node_programs, data_texel(0u), 0).x;
+}
+
+fn get_node_instruction(index: u32) -> NodeInstruction {
+    let first = 1u + index * 2u;
+    let registers = textureLoad(node_programs, data_texel(first), 0);
+    let value = textureLoad(node_programs, data_texel(first + 1u), 0);
+    return NodeInstruction(registers.x, registers.y, registers.z, registers.w, bitcast<vec4<f32>>(value));
+}
+#else
+@group(3)
+@binding(1)
+var<storage, read> node_programs: NodePrograms;
+
+fn node_instruction_count() -> u32 {
+    return node_programs.count;
+}
+
+fn get_node_instruction(index: u32) -> NodeInstruction {
+    return node_programs.data[index];
+}
+#endif
+
+// replaces the properties of `material` that its node program sets, for a hit the ray arrived at
+// travelling in `incoming`, see `GpuNodeInstruction::run`
+fn run_node_program(material: ptr<function, Material>, hit: Hit, incoming: vec4<f32>) {
+    var registers: array<vec4<f32>, MAX_NODE_INSTRUCTIONS>;
+    let start = (*material).node_program;
+    let length = min((*material).node_program_length, MAX_NODE_INSTRUCTIONS);
+    for (var i = 0u; i < length && start + i < node_instruction_count(); i += 1u) {
+        let instruction = get_node_instruction(start + i);
+        var a = instruction.value;
+        if instruction.a < i {
+            a = registers[instruction.a];
+        }
+        var b = instruction.value;
+        if instruction.b < i {
+            b = registers[instruction.b];
+        }
+        var c = instruction.value;
+        if instruction.c < i {
+            c = registers[instruction.c];
+        }
+
+        // the cases must match the order of `NodeKind`
+        var result = vec4<f32>(0.0);
+        switch instruction.kind {
+            // output
+            case 0u: {
+                if instruction.a != NO_REGISTER {
+                    (*material).base_color = a.xyz;
+                }
+                if instruction.b != NO_REGISTER {
+                    (*material).emissive_color = b.xyz;
+                }
+            }
+            // value
+            case 1u: {
+                result = vec4<f32>(instruction.value.x);
+            }
+            // color
+            case 2u: {
+                result = vec4<f32>(instruction.value.xyz, 1.0);
+            }
+            // position
+            case 3u: {
+                result = hit.pattern_position;
+            }
+            // normal
+            case 4u: {
+                result = hit.normal;
+            }
+            // noise
+            case 5u: {
+                let position = select(a, hit.pattern_position, instruction.a == NO_REGISTER);
+                result = vec4<f32>(blend_mask(1u, position * instruction.value));
+            }
+            // checker
+            case 6u: {
+                let position = select(a, hit.pattern_position, instruction.a == NO_REGISTER);
+                result = vec4<f32>(blend_mask(3u, position * instruction.value));
+            }
+            // fresnel
+            case 7u: {
+                let r0 = pow((1.0 - instruction.value.x) / (1.0 + instruction.value.x), 2.0);
+                let cos_theta = min(abs(dot(hit.normal, -incoming)), 1.0);
+                result = vec4<f32>(r0 + (1.0 - r0) * pow(1.0 - cos_theta, 5.0));
+            }
+            // add
+            case 8u: {
+                result = a + b;
+            }
+            // subtract
+            case 9u: {
+                result = a - b;
+            }
+            // multiply
+            case 10u: {
+                result = a * b;
+            }
+            // divide
+            case 11u: {
+                result = a / b;
+            }
+            // minimum
+            case 12u: {
+                result = min(a, b);
+            }
+            // maximum
+            case 13u: {
+                result = max(a, b);
+            }
+            // power
+            case 14u: {
+                result = pow(a, b);
+            }
+            // mix
+            case 15u: {
+                result = a + (b - a) * c;
+            }
+            default: {}
+        }
+        registers[i] = result;
+    }
+    // a division by zero or a negative power can't reach the rest of the path
+    (*material).base_color = min(max((*material).base_color, vec3<f32>(0.0)), vec3<f32>(1.0));
+    (*material).emissive_color = min(max((*material).emissive_color, vec3<f32>(0.0)), vec3<f32>(1.0));
+}
@@ -0,0 +1,40 @@
+fn lattice_hash(x: u32) -> u32 {
+    let state = x * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// a value in [-1, 1] for each integer lattice point
+fn lattice_value(point: vec4<i32>) -> f32 {
+    let h = lattice_hash(u32(point.x) ^ lattice_hash(u32(point.y) ^ lattice_hash(u32(point.z) ^ lattice_hash(u32(point.w)))));
+    return f32(h >> 8u) / 8388607.5 - 1.0;
+}
+
+// the analytic gradient of 4D value noise, smoothstep interpolated between the 16 corners of each cell
+fn value_noise_gradient(p: vec4<f32>) -> vec4<f32> {
+    let cell = floor(p);
+    let f = p - cell;
+    let t = f * f * (3.0 - 2.0 * f);
+    let dt = 6.0 * f * (1.0 - f);
+
+    var gradient = vec4<f32>(0.0);
+    for (var i = 0u; i < 16u; i += 1u) {
+        let corner = vec4<f32>(vec4<u32>(i, i >> 1u, i >> 2u, i >> 3u) & vec4<u32>(1u));
+        let w = mix(1.0 - t, t, corner);
+        let dw = mix(-dt, dt, corner);
+        let value = lattice_value(vec4<i32>(cell) + vec4<i32>(corner));
+        gradient += value * vec4<f32>(
+            dw.x * w.y * w.z * w.w,
+            w.x * dw.y * w.z * w.w,
+            w.x * w.y * dw.z * w.w,
+            w.x * w.y * w.z * dw.w,
+        );
+    }
+    return gradient;
+}
+
+// tilts `normal` against the noise gradient at `position`, without changing the geometry
+fn bump_normal(normal: vec4<f32>, position: vec4<f32>, strength: f32, scale: f32) -> vec4<f32> {
+    let gradient = value_noise_gradient(position * scale);
+    return normalize(normal - strength * (gradient - dot(gradient, normal) * normal));
+}
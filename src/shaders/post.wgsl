@@ -0,0 +1,104 @@
+// one effect of the post processing stack, reading the previous effect's result
+
+struct Effect {
+    kind: u32,
+    strength: f32,
+    radius: f32,
+    seed: u32,
+    // whether the colors are sRGB encoded, the effects work on linear colors
+    srgb: u32,
+}
+
+@group(0)
+@binding(0)
+var input_texture: texture_2d<f32>;
+
+@group(0)
+@binding(1)
+var output_texture: texture_storage_2d<rgba8unorm, write>;
+
+@group(0)
+@binding(2)
+var<uniform> effect: Effect;
+
+fn linear_from_srgb(value: f32) -> f32 {
+    if value <= 0.04045 {
+        return value / 12.92;
+    }
+    return pow((value + 0.055) / 1.055, 2.4);
+}
+
+fn srgb_from_linear(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        return value * 12.92;
+    }
+    return 1.055 * pow(value, 1.0 / 2.4) - 0.055;
+}
+
+fn load(coords: vec2<i32>) -> vec3<f32> {
+    let size = vec2<i32>(textureDimensions(input_texture));
+    let color = textureLoad(input_texture, clamp(coords, vec2<i32>(0), size - 1), 0).rgb;
+    if effect.srgb != 0u {
+        return vec3<f32>(linear_from_srgb(color.r), linear_from_srgb(color.g), linear_from_srgb(color.b));
+    }
+    return color;
+}
+
+fn load_bilinear(position: vec2<f32>) -> vec3<f32> {
+    let corner = floor(position - 0.5);
+    let t = position - 0.5 - corner;
+    let coords = vec2<i32>(corner);
+    let top = mix(load(coords), load(coords + vec2<i32>(1, 0)), t.x);
+    let bottom = mix(load(coords + vec2<i32>(0, 1)), load(coords + vec2<i32>(1, 1)), t.x);
+    return mix(top, bottom, t.y);
+}
+
+fn hash(value: u32) -> f32 {
+    let state = value * 747796405u + 2891336453u;
+    var result = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    result = (result >> 22u) ^ result;
+    return f32(result) / 4294967295.0;
+}
+
+@compute
+@workgroup_size(16, 16)
+fn post_process(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+) {
+    let size = textureDimensions(output_texture);
+    let coords = vec2<i32>(global_id.xy);
+    if coords.x >= size.x || coords.y >= size.y {
+        return;
+    }
+
+    let position = vec2<f32>(coords) + 0.5;
+    let center = vec2<f32>(size) * 0.5;
+    // 0 in the middle of the image and 1 in the corners
+    let offset = (position - center) / length(center);
+
+    var color = load(coords);
+    switch effect.kind {
+        // vignette
+        case 0u: {
+            color *= 1.0 - effect.strength * smoothstep(effect.radius, 1.0, length(offset));
+        }
+        // chromatic aberration, red is pushed outwards and blue inwards, more towards the edges
+        case 1u: {
+            let shift = (position - center) * effect.strength * length(offset);
+            color.r = load_bilinear(position + shift).r;
+            color.b = load_bilinear(position - shift).b;
+        }
+        // film grain
+        case 2u: {
+            let noise = hash(u32(coords.x) + u32(coords.y) * u32(size.x) + effect.seed * 1664525u) - 0.5;
+            color *= max(1.0 + effect.strength * noise * 2.0, 0.0);
+        }
+        default: {}
+    }
+
+    color = clamp(color, vec3<f32>(0.0), vec3<f32>(1.0));
+    if effect.srgb != 0u {
+        color = vec3<f32>(srgb_from_linear(color.r), srgb_from_linear(color.g), srgb_from_linear(color.b));
+    }
+    textureStore(output_texture, coords, vec4<f32>(color, 1.0));
+}
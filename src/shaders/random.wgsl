@@ -0,0 +1,6 @@
+fn random_value(state: ptr<function, u32>) -> f32 {
+    *state = *state * 747796405u + 2891336453u;
+    var result = ((*state >> ((*state >> 28u) + 4u)) ^ *state) * 277803737u;
+    result = (result >> 22u) ^ result;
+    return f32(result) / 4294967295.0;
+}
@@ -0,0 +1,12 @@
+#import "trace.wgsl"
+
+#ifdef COMPUTE
+#import "compute.wgsl"
+#ifdef WAVEFRONT
+#import "wavefront.wgsl"
+#endif
+#endif
+
+#ifdef FRAGMENT
+#import "fragment.wgsl"
+#endif
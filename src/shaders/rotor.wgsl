@@ -0,0 +1,69 @@
+struct BiVector4 {
+    xy: f32,
+    xz: f32,
+    xw: f32,
+    yz: f32,
+    yw: f32,
+    zw: f32,
+}
+
+struct Rotor4 {
+    s: f32,
+    bv: BiVector4,
+}
+
+fn bivector_length(bv: BiVector4) -> f32 {
+    return sqrt(bv.xy * bv.xy + bv.xz * bv.xz + bv.xw * bv.xw + bv.yz * bv.yz + bv.yw * bv.yw + bv.zw * bv.zw);
+}
+
+fn rotor_from_angle_plane(angle: f32, plane: BiVector4) -> Rotor4 {
+    let half_angle = angle * 0.5;
+    let sin = sin(half_angle);
+    var r: Rotor4;
+    r.s = cos(half_angle);
+    r.bv.xy = plane.xy * -sin;
+    r.bv.xz = plane.xz * -sin;
+    r.bv.xw = plane.xw * -sin;
+    r.bv.yz = plane.yz * -sin;
+    r.bv.yw = plane.yw * -sin;
+    r.bv.zw = plane.zw * -sin;
+    return r;
+}
+
+fn rotor_rotate_vec(r: Rotor4, v: vec4<f32>) -> vec4<f32> {
+    let x = r.s * v.x + r.bv.xy * v.y + r.bv.xz * v.z + r.bv.xw * v.w;
+    let y = r.s * v.y - r.bv.xy * v.x + r.bv.yz * v.z + r.bv.yw * v.w;
+    let z = r.s * v.z - r.bv.xz * v.x - r.bv.yz * v.y + r.bv.zw * v.w;
+    let w = r.s * v.w - r.bv.xw * v.x - r.bv.yw * v.y - r.bv.zw * v.z;
+
+    let xyz = r.bv.xy * v.z - r.bv.xz * v.y + r.bv.yz * v.x;
+    let yzw = r.bv.yz * v.w - r.bv.yw * v.z + r.bv.zw * v.y;
+    let zwx = r.bv.xz * v.w - r.bv.xw * v.z + r.bv.zw * v.x;
+    let wxy = r.bv.xy * v.w - r.bv.xw * v.y + r.bv.yw * v.x;
+
+    // the reverse of the rotor, `-self` on the rust side
+    let p_s = r.s;
+    let p = BiVector4(-r.bv.xy, -r.bv.xz, -r.bv.xw, -r.bv.yz, -r.bv.yw, -r.bv.zw);
+    return vec4<f32>(
+        x * p_s - y * p.xy - z * p.xz - w * p.xw - xyz * p.yz - wxy * p.yw - zwx * p.zw,
+        y * p_s + x * p.xy - z * p.yz - w * p.yw + xyz * p.xz + wxy * p.xw - yzw * p.zw,
+        z * p_s + x * p.xz + y * p.yz - w * p.zw - xyz * p.xy + zwx * p.xw + yzw * p.yw,
+        w * p_s + x * p.xw + y * p.yw + z * p.zw - wxy * p.xy - zwx * p.xz - yzw * p.yz,
+    );
+}
+
+fn rotate_by_angular_velocity(v: vec4<f32>, angular_velocity: BiVector4, time: f32) -> vec4<f32> {
+    let speed = bivector_length(angular_velocity);
+    if speed == 0.0 {
+        return v;
+    }
+    let plane = BiVector4(
+        angular_velocity.xy / speed,
+        angular_velocity.xz / speed,
+        angular_velocity.xw / speed,
+        angular_velocity.yz / speed,
+        angular_velocity.yw / speed,
+        angular_velocity.zw / speed,
+    );
+    return rotor_rotate_vec(rotor_from_angle_plane(speed * time, plane), v);
+}
@@ -0,0 +1,132 @@
+#import "random.wgsl"
+
+@group(1)
+@binding(1)
+var blue_noise: texture_2d<f32>;
+
+// source of the "random" numbers used for anti-aliasing, motion blur and bounce directions,
+// which sequence is used is picked with the `SAMPLER_*` defines
+struct SampleState {
+    rng: u32,
+    pixel: vec2<u32>,
+    index: u32,
+    dimension: u32,
+    first_bounce: bool,
+}
+
+fn sample_state_new(pixel: vec2<u32>, rng: u32) -> SampleState {
+    var sample_state: SampleState;
+    sample_state.rng = rng;
+    sample_state.pixel = pixel;
+    sample_state.index = 0u;
+    sample_state.dimension = 0u;
+    sample_state.first_bounce = true;
+    return sample_state;
+}
+
+fn start_sample(sample_state: ptr<function, SampleState>, index: u32) {
+    (*sample_state).index = index;
+    (*sample_state).dimension = 0u;
+    (*sample_state).first_bounce = true;
+}
+
+fn hash(x: u32) -> u32 {
+    var state = x;
+    return u32(random_value(&state) * 4294967295.0);
+}
+
+fn pixel_hash(sample_state: ptr<function, SampleState>, salt: u32) -> u32 {
+    return hash((*sample_state).pixel.x ^ hash((*sample_state).pixel.y ^ hash(salt)));
+}
+
+fn radical_inverse(index: u32, base: u32) -> f32 {
+    var i = index;
+    var result = 0.0;
+    var inverse_base = 1.0 / f32(base);
+    var factor = inverse_base;
+    while i > 0u {
+        result += f32(i % base) * factor;
+        i /= base;
+        factor *= inverse_base;
+    }
+    return result;
+}
+
+fn halton_prime(dimension: u32) -> u32 {
+    var primes = array<u32, 16>(2u, 3u, 5u, 7u, 11u, 13u, 17u, 19u, 23u, 29u, 31u, 37u, 41u, 43u, 47u, 53u);
+    return primes[dimension];
+}
+
+fn sobol_2d(index: u32) -> vec2<f32> {
+    // the first dimension is the van der corput sequence, the second uses the
+    // direction numbers generated by the primitive polynomial x + 1
+    var v = 1u << 31u;
+    var y = 0u;
+    for (var i = index; i != 0u; i >>= 1u) {
+        if (i & 1u) != 0u {
+            y ^= v;
+        }
+        v ^= v >> 1u;
+    }
+    return vec2<f32>(f32(reverseBits(index)), f32(y)) / 4294967296.0;
+}
+
+fn sample_1d(sample_state: ptr<function, SampleState>) -> f32 {
+    let dimension = (*sample_state).dimension;
+    (*sample_state).dimension += 1u;
+
+#ifdef SAMPLER_HALTON
+    if dimension < 16u {
+        let offset = f32(pixel_hash(sample_state, dimension)) / 4294967296.0;
+        return fract(radical_inverse((*sample_state).index, halton_prime(dimension)) + offset);
+    }
+#endif
+
+#ifdef SAMPLER_SOBOL
+    if dimension < 64u {
+        // pad the 2d sequence out to higher dimensions by shuffling the index and
+        // scrambling the digits differently for every pair of dimensions
+        let pair = dimension / 2u;
+        let index = (*sample_state).index ^ (pixel_hash(sample_state, pair) & 0xFFu);
+        let scramble = pixel_hash(sample_state, pair + 1024u);
+        let point = sobol_2d(index);
+        let value = select(point.y, point.x, dimension % 2u == 0u);
+        return f32(u32(value * 4294967296.0) ^ scramble) / 4294967296.0;
+    }
+#endif
+
+#ifdef SAMPLER_BLUE_NOISE
+    if (*sample_state).first_bounce {
+        // each dimension reads a different part of the tile, each sample is offset by the golden ratio
+        let coords = ((*sample_state).pixel + vec2<u32>(dimension * 17u, dimension * 31u)) % vec2<u32>(textureDimensions(blue_noise));
+        let value = textureLoad(blue_noise, vec2<i32>(coords), 0).r;
+        return fract(value + f32((*sample_state).index) * 0.61803398875);
+    }
+#endif
+
+    return random_value(&(*sample_state).rng);
+}
+
+fn sample_normal_distribution(sample_state: ptr<function, SampleState>) -> f32 {
+    let theta = 2.0 * 3.1415926 * sample_1d(sample_state);
+    // low discrepancy sequences can return exactly 0
+    let rho = sqrt(-2.0 * log(max(sample_1d(sample_state), 1.0e-7)));
+    return rho * cos(theta);
+}
+
+fn sample_direction(sample_state: ptr<function, SampleState>) -> vec4<f32> {
+    return normalize(vec4<f32>(
+        sample_normal_distribution(sample_state),
+        sample_normal_distribution(sample_state),
+        sample_normal_distribution(sample_state),
+        sample_normal_distribution(sample_state),
+    ));
+}
+
+fn sample_direction_in_hemisphere(sample_state: ptr<function, SampleState>, normal: vec4<f32>) -> vec4<f32> {
+    var direction = sample_direction(sample_state);
+    if dot(direction, normal) < 0.0 {
+        direction *= -1.0;
+    }
+    return direction;
+}
@@ -0,0 +1,475 @@
+#import "rotor.wgsl"
+
+struct Camera {
+    position: vec4<f32>,
+    forward: vec4<f32>,
+    right: vec4<f32>,
+    up: vec4<f32>,
+    fov: f32,
+    aspect_override: f32,
+    lens_shift: vec2<f32>,
+    min_distance: f32,
+    max_distance: f32,
+    bounce_count: u32,
+    sample_count: u32,
+    seed_offset: u32,
+    shutter_time: f32,
+    sample_index: u32,
+    accumulated_frames: u32,
+    ao_distance: f32,
+    w_range: f32,
+    w_kata_color: vec3<f32>,
+    w_center_color: vec3<f32>,
+    w_ana_color: vec3<f32>,
+    cut_point: vec4<f32>,
+    cut_normal: vec4<f32>,
+    cut_color: vec3<f32>,
+    stereographic_pole: vec4<f32>,
+    eye_offset: f32,
+    fisheye_angle: f32,
+    light_group_weights: vec4<f32>,
+}
+
+@group(1)
+@binding(0)
+var<uniform> camera: Camera;
+
+struct Environment {
+    sun_direction: vec4<f32>,
+    sky: u32,
+    sun_cos_radius: f32,
+    exposure: f32,
+    perez_a: vec3<f32>,
+    perez_b: vec3<f32>,
+    perez_c: vec3<f32>,
+    perez_d: vec3<f32>,
+    perez_e: vec3<f32>,
+    zenith: vec3<f32>,
+    sun_radiance: vec3<f32>,
+}
+
+@group(1)
+@binding(2)
+var<uniform> environment: Environment;
+
+struct HyperSphere {
+    center: vec4<f32>,
+    velocity: vec4<f32>,
+    radius: f32,
+    material: u32,
+    // 0 is solid
+    inner_radius: f32,
+}
+
+#ifdef PACKED_OBJECTS
+// the vectors are pairs of halves, see `GpuPackedHyperSphere`
+struct PackedHyperSphere {
+    center: vec2<u32>,
+    velocity: vec2<u32>,
+    radius: f32,
+    material: u32,
+    inner_radius: f32,
+}
+
+struct HyperSpheres {
+    count: u32,
+    // so `data` starts a texel in, see `SceneBuffer`. GLSL ignores `@align` there
+    padding: vec2<u32>,
+    data: array<PackedHyperSphere>,
+}
+#else
+struct HyperSpheres {
+    count: u32,
+    data: array<HyperSphere>,
+}
+#endif
+
+#ifdef FRAGMENT
+// fragment shaders can't read storage buffers on downlevel devices, so there each buffer's bytes
+// are in a texture instead, see `SceneBuffer`. The count is in the first texel and the structs
+// start at the second, each taking a whole number of texels
+
+// must match `DATA_TEXTURE_WIDTH`
+const DATA_TEXTURE_WIDTH: u32 = 1024u;
+
+// where the `index`th texel of a buffer is in its texture
+fn data_texel(index: u32) -> vec2<i32> {
+    return vec2<i32>(i32(index % DATA_TEXTURE_WIDTH), i32(index / DATA_TEXTURE_WIDTH));
+}
+
+@group(2)
+@binding(0)
+var hyper_spheres: texture_2d<u32>;
+
+fn hyper_sphere_count() -> u32 {
+    return textureLoad(hyper_spheres, data_texel(0u), 0).x;
+}
+#else
+@group(2)
+@binding(0)
+var<storage, read> hyper_spheres: HyperSpheres;
+
+fn hyper_sphere_count() -> u32 {
+    return hyper_spheres.count;
+}
+#endif
+
+#ifdef CULLING
+// the hyper spheres camera rays can hit, refilled by `cull_hyper_spheres` before every render
+struct VisibleHyperSpheres {
+    count: atomic<u32>,
+    indices: array<u32>,
+}
+
+@group(2)
+@binding(2)
+var<storage, read_write> visible_hyper_spheres: VisibleHyperSpheres;
+#endif
+
+#ifdef BVH
+// see `GpuBvhNode`
+struct BvhNode {
+    min: vec4<f32>,
+    max: vec4<f32>,
+    hyper_spheres: vec4<u32>,
+    // 0 for interior nodes, whose first child comes right after them
+    count: u32,
+    // the node after everything below this one
+    skip: u32,
+}
+
+struct Bvh {
+    // the nodes after these are leaves with the dynamic hyper spheres
+    tree_nodes: u32,
+    count: u32,
+    data: array<BvhNode>,
+}
+
+@group(2)
+@binding(3)
+var<storage, read> bvh: Bvh;
+#endif
+
+fn unpack_vec4(halves: vec2<u32>) -> vec4<f32> {
+    return vec4<f32>(unpack2x16float(halves.x), unpack2x16float(halves.y));
+}
+
+fn get_hyper_sphere(index: u32) -> HyperSphere {
+#ifdef FRAGMENT
+#ifdef PACKED_OBJECTS
+    let first = 1u + index * 2u;
+    let a = textureLoad(hyper_spheres, data_texel(first), 0);
+    let b = textureLoad(hyper_spheres, data_texel(first + 1u), 0);
+    return HyperSphere(unpack_vec4(a.xy), unpack_vec4(a.zw), bitcast<f32>(b.x), b.y, bitcast<f32>(b.z));
+#else
+    let first = 1u + index * 3u;
+    let a = textureLoad(hyper_spheres, data_texel(first), 0);
+    let b = textureLoad(hyper_spheres, data_texel(first + 1u), 0);
+    let c = textureLoad(hyper_spheres, data_texel(first + 2u), 0);
+    return HyperSphere(bitcast<vec4<f32>>(a), bitcast<vec4<f32>>(b), bitcast<f32>(c.x), c.y, bitcast<f32>(c.z));
+#endif
+#else
+#ifdef PACKED_OBJECTS
+    let stored = hyper_spheres.data[index];
+    return HyperSphere(unpack_vec4(stored.center), unpack_vec4(stored.velocity), stored.radius, stored.material, stored.inner_radius);
+#else
+    return hyper_spheres.data[index];
+#endif
+#endif
+}
+
+struct HyperPlane {
+    point: vec4<f32>,
+    normal: vec4<f32>,
+    velocity: vec4<f32>,
+    angular_velocity: BiVector4,
+    material: u32,
+    // half sizes along `plane_basis`, 0 is unbounded
+    extents: vec3<f32>,
+    // both zero picks the basis automatically
+    tangent: vec4<f32>,
+    bitangent: vec4<f32>,
+}
+
+#ifdef PACKED_OBJECTS
+// the vectors and angular velocity are pairs of stored, see `GpuPackedHyperPlane`
+struct PackedHyperPlane {
+    point: vec2<u32>,
+    normal: vec2<u32>,
+    velocity: vec2<u32>,
+    angular_velocity: array<u32, 3>,
+    material: u32,
+    extents: array<u32, 2>,
+    tangent: vec2<u32>,
+    bitangent: vec2<u32>,
+}
+
+struct HyperPlanes {
+    count: u32,
+    // so `data` starts a texel in, see `SceneBuffer`. GLSL ignores `@align` there
+    padding: vec2<u32>,
+    data: array<PackedHyperPlane>,
+}
+#else
+struct HyperPlanes {
+    count: u32,
+    data: array<HyperPlane>,
+}
+#endif
+
+#ifdef FRAGMENT
+@group(2)
+@binding(1)
+var hyper_planes: texture_2d<u32>;
+
+fn hyper_plane_count() -> u32 {
+    return textureLoad(hyper_planes, data_texel(0u), 0).x;
+}
+#else
+@group(2)
+@binding(1)
+var<storage, read> hyper_planes: HyperPlanes;
+
+fn hyper_plane_count() -> u32 {
+    return hyper_planes.count;
+}
+#endif
+
+fn get_hyper_plane(index: u32) -> HyperPlane {
+#ifdef FRAGMENT
+#ifdef PACKED_OBJECTS
+    let first = 1u + index * 4u;
+    let a = textureLoad(hyper_planes, data_texel(first), 0);
+    let b = textureLoad(hyper_planes, data_texel(first + 1u), 0);
+    let c = textureLoad(hyper_planes, data_texel(first + 2u), 0);
+    let d = textureLoad(hyper_planes, data_texel(first + 3u), 0);
+    var stored: PackedHyperPlane;
+    stored.point = a.xy;
+    stored.normal = a.zw;
+    stored.velocity = b.xy;
+    stored.angular_velocity = array<u32, 3>(b.z, b.w, c.x);
+    stored.material = c.y;
+    stored.extents = array<u32, 2>(c.z, c.w);
+    stored.tangent = d.xy;
+    stored.bitangent = d.zw;
+#else
+    let first = 1u + index * 8u;
+    let angular_velocity = bitcast<vec4<f32>>(textureLoad(hyper_planes, data_texel(first + 3u), 0));
+    let rest = textureLoad(hyper_planes, data_texel(first + 4u), 0);
+    return HyperPlane(
+        bitcast<vec4<f32>>(textureLoad(hyper_planes, data_texel(first), 0)),
+        bitcast<vec4<f32>>(textureLoad(hyper_planes, data_texel(first + 1u), 0)),
+        bitcast<vec4<f32>>(textureLoad(hyper_planes, data_texel(first + 2u), 0)),
+        BiVector4(angular_velocity.x, angular_velocity.y, angular_velocity.z, angular_velocity.w, bitcast<f32>(rest.x), bitcast<f32>(rest.y)),
+        rest.z,
+        bitcast<vec4<f32>>(textureLoad(hyper_planes, data_texel(first + 5u), 0)).xyz,
+        bitcast<vec4<f32>>(textureLoad(hyper_planes, data_texel(first + 6u), 0)),
+        bitcast<vec4<f32>>(textureLoad(hyper_planes, data_texel(first + 7u), 0))
+    );
+#endif
+#else
+#ifdef PACKED_OBJECTS
+    let stored = hyper_planes.data[index];
+#else
+    return hyper_planes.data[index];
+#endif
+#endif
+#ifdef PACKED_OBJECTS
+    let xy_xz = unpack2x16float(stored.angular_velocity[0]);
+    let xw_yz = unpack2x16float(stored.angular_velocity[1]);
+    let yw_zw = unpack2x16float(stored.angular_velocity[2]);
+    let extents_xy = unpack2x16float(stored.extents[0]);
+    let extents_z = unpack2x16float(stored.extents[1]);
+    return HyperPlane(
+        unpack_vec4(stored.point),
+        // rounding leaves it slightly off unit length
+        normalize(unpack_vec4(stored.normal)),
+        unpack_vec4(stored.velocity),
+        BiVector4(xy_xz.x, xy_xz.y, xw_yz.x, xw_yz.y, yw_zw.x, yw_zw.y),
+        stored.material,
+        vec3<f32>(extents_xy, extents_z.x),
+        unpack_vec4(stored.tangent),
+        unpack_vec4(stored.bitangent)
+    );
+#endif
+}
+
+// `GpuHyperPlane::tangents`
+struct PlaneBasis {
+    tangent: vec4<f32>,
+    bitangent: vec4<f32>,
+    third: vec4<f32>,
+    // whether the plane's own tangents are used, then the plane has coordinates for patterns
+    custom: bool,
+}
+
+// the vector perpendicular to `a`, `b` and `c`
+fn cross4(a: vec4<f32>, b: vec4<f32>, c: vec4<f32>) -> vec4<f32> {
+    return vec4<f32>(
+        determinant(mat3x3<f32>(a.yzw, b.yzw, c.yzw)),
+        -determinant(mat3x3<f32>(a.xzw, b.xzw, c.xzw)),
+        determinant(mat3x3<f32>(a.xyw, b.xyw, c.xyw)),
+        -determinant(mat3x3<f32>(a.xyz, b.xyz, c.xyz)),
+    );
+}
+
+// without local arrays because llvmpipe miscompiles shaders that index them dynamically
+fn plane_basis(hyper_plane: HyperPlane) -> PlaneBasis {
+    let normal = hyper_plane.normal;
+    var basis: PlaneBasis;
+
+    let tangent = hyper_plane.tangent - normal * dot(hyper_plane.tangent, normal);
+    if dot(tangent, tangent) > 1e-6 {
+        basis.tangent = normalize(tangent);
+        let bitangent = hyper_plane.bitangent - normal * dot(hyper_plane.bitangent, normal) - basis.tangent * dot(hyper_plane.bitangent, basis.tangent);
+        if dot(bitangent, bitangent) > 1e-6 {
+            basis.bitangent = normalize(bitangent);
+            basis.third = normalize(cross4(normal, basis.tangent, basis.bitangent));
+            basis.custom = true;
+            return basis;
+        }
+    }
+
+    var largest = 0u;
+    for (var axis = 1u; axis < 4u; axis += 1u) {
+        if abs(normal[axis]) > abs(normal[largest]) {
+            largest = axis;
+        }
+    }
+    // the tangents before the one being made are zero until they are
+    basis.tangent = vec4<f32>(0.0);
+    basis.bitangent = vec4<f32>(0.0);
+    var count = 0u;
+    for (var axis = 0u; axis < 4u; axis += 1u) {
+        if axis != largest {
+            var tangent = select(vec4<f32>(0.0), vec4<f32>(1.0), vec4<u32>(0u, 1u, 2u, 3u) == vec4<u32>(axis));
+            tangent -= normal * dot(tangent, normal);
+            tangent -= basis.tangent * dot(tangent, basis.tangent);
+            tangent -= basis.bitangent * dot(tangent, basis.bitangent);
+            tangent = normalize(tangent);
+            if count == 0u {
+                basis.tangent = tangent;
+            } else if count == 1u {
+                basis.bitangent = tangent;
+            } else {
+                basis.third = tangent;
+            }
+            count += 1u;
+        }
+    }
+    basis.custom = false;
+    return basis;
+}
+
+struct Material {
+    base_color: vec3<f32>,
+    emissive_color: vec3<f32>,
+    emission_strength: f32,
+    bump_strength: f32,
+    bump_scale: f32,
+    emission_pattern: u32,
+    emission_pattern_scale: vec4<f32>,
+    transmission: f32,
+    ior: f32,
+    dispersion: f32,
+    priority: u32,
+    absorption_color: vec3<f32>,
+    absorption_density: f32,
+    clearcoat: f32,
+    clearcoat_roughness: f32,
+    sheen: f32,
+    sheen_color: vec3<f32>,
+    // 0 for none, otherwise 1 plus the index of its weight in `camera.light_group_weights`
+    light_group: u32,
+    // a `BlendMask`, anything but 0 means hits use `blend_a` or `blend_b` instead
+    blend_mask: u32,
+    blend_a: u32,
+    blend_b: u32,
+    blend_scale: vec4<f32>,
+    // the instructions in `node_programs` that replace some of the properties for every hit
+    node_program: u32,
+    node_program_length: u32,
+}
+
+struct Materials {
+    count: u32,
+    data: array<Material>,
+}
+
+#ifdef FRAGMENT
+@group(3)
+@binding(0)
+var materials: texture_2d<u32>;
+
+// the `texel`th of the 11 texels of the material at `index`
+fn material_texel(index: u32, texel: u32) -> vec4<u32> {
+    return textureLoad(materials, data_texel(1u + index * 11u + texel), 0);
+}
+
+fn get_material(index: u32) -> Material {
+    var material: Material;
+    material.base_color = bitcast<vec4<f32>>(material_texel(index, 0u)).xyz;
+    let emission = bitcast<vec4<f32>>(material_texel(index, 1u));
+    material.emissive_color = emission.xyz;
+    material.emission_strength = emission.w;
+    let bump = material_texel(index, 2u);
+    material.bump_strength = bitcast<f32>(bump.x);
+    material.bump_scale = bitcast<f32>(bump.y);
+    material.emission_pattern = bump.z;
+    material.emission_pattern_scale = bitcast<vec4<f32>>(material_texel(index, 3u));
+    let transmission = material_texel(index, 4u);
+    material.transmission = bitcast<f32>(transmission.x);
+    material.ior = bitcast<f32>(transmission.y);
+    material.dispersion = bitcast<f32>(transmission.z);
+    material.priority = transmission.w;
+    let absorption = bitcast<vec4<f32>>(material_texel(index, 5u));
+    material.absorption_color = absorption.xyz;
+    material.absorption_density = absorption.w;
+    let coat = bitcast<vec4<f32>>(material_texel(index, 6u));
+    material.clearcoat = coat.x;
+    material.clearcoat_roughness = coat.y;
+    material.sheen = coat.z;
+    let sheen = material_texel(index, 7u);
+    material.sheen_color = bitcast<vec4<f32>>(sheen).xyz;
+    material.light_group = sheen.w;
+    let blend = material_texel(index, 8u);
+    material.blend_mask = blend.x;
+    material.blend_a = blend.y;
+    material.blend_b = blend.z;
+    material.blend_scale = bitcast<vec4<f32>>(material_texel(index, 9u));
+    let node_program = material_texel(index, 10u);
+    material.node_program = node_program.x;
+    material.node_program_length = node_program.y;
+    return material;
+}
+#else
+@group(3)
+@binding(0)
+var<storage, read> materials: Materials;
+
+fn get_material(index: u32) -> Material {
+    return materials.data[index];
+}
+#endif
+
+struct Ray {
+    origin: vec4<f32>,
+    direction: vec4<f32>,
+    time: f32,
+    // in nanometers, only sampled in `SPECTRAL` mode
+    wavelength: f32,
+}
+
+struct Hit {
+    hit: bool,
+    distance: f32,
+    position: vec4<f32>,
+    normal: vec4<f32>,
+    material: u32,
+    // where emission patterns are evaluated, the plane coordinates of hyper planes with their own tangents
+    pattern_position: vec4<f32>,
+    // the surface left by the cross section, inside an object
+    cut: bool,
+    // if the ray hit the outside of the object, the normal is flipped to face the ray when it didn't
+    front_face: bool,
+}
@@ -0,0 +1,40 @@
+#import "scene.wgsl"
+
+fn gradient_sky(direction: vec4<f32>) -> vec3<f32> {
+    let up_color = vec3<f32>(0.3, 0.4, 0.8);
+    let down_color = vec3<f32>(0.2, 0.2, 0.2);
+    return mix(down_color, up_color, direction.y * 0.5 + 0.5);
+}
+
+fn perez(cos_theta: f32, cos_gamma: f32) -> vec3<f32> {
+    let gamma = acos(clamp(cos_gamma, -1.0, 1.0));
+    return (1.0 + environment.perez_a * exp(environment.perez_b / cos_theta))
+        * (1.0 + environment.perez_c * exp(environment.perez_d * gamma) + environment.perez_e * (cos_gamma * cos_gamma));
+}
+
+// the Preetham daylight model without the sun disk, in linear sRGB
+fn physical_sky(direction: vec4<f32>) -> vec3<f32> {
+    let cos_theta = max(direction.y, 0.01);
+    let cos_gamma = dot(direction, environment.sun_direction);
+    let yxy = environment.zenith * perez(cos_theta, cos_gamma);
+    let luminance = yxy.x;
+    let xyz = vec3<f32>(yxy.y / yxy.z * luminance, luminance, (1.0 - yxy.y - yxy.z) / yxy.z * luminance);
+    let rgb = vec3<f32>(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    );
+    return max(rgb * environment.exposure, vec3<f32>(0.0));
+}
+
+fn sky_color(direction: vec4<f32>) -> vec3<f32> {
+    // must match the order of `SkyKind`
+    if environment.sky == 1u {
+        var color = physical_sky(direction);
+        if dot(direction, environment.sun_direction) > environment.sun_cos_radius {
+            color += environment.sun_radiance;
+        }
+        return color;
+    }
+    return gradient_sky(direction);
+}
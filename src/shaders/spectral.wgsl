@@ -0,0 +1,32 @@
+// one lobe of the fit below, a gaussian with a different width on each side of the peak
+fn cie_lobe(wavelength: f32, peak: f32, width_below: f32, width_above: f32) -> f32 {
+    var width = width_above;
+    if wavelength < peak {
+        width = width_below;
+    }
+    let t = (wavelength - peak) / width;
+    return exp(-0.5 * t * t);
+}
+
+// the CIE 1931 color matching functions, using the multi-lobe fit from
+// "Simple Analytic Approximations to the CIE XYZ Color Matching Functions" by Wyman et al.
+fn cie_xyz(wavelength: f32) -> vec3<f32> {
+    return vec3<f32>(
+        1.056 * cie_lobe(wavelength, 599.8, 37.9, 31.0) + 0.362 * cie_lobe(wavelength, 442.0, 16.0, 26.7) - 0.065 * cie_lobe(wavelength, 501.1, 20.4, 26.2),
+        0.821 * cie_lobe(wavelength, 568.8, 46.9, 40.5) + 0.286 * cie_lobe(wavelength, 530.9, 16.3, 31.1),
+        1.217 * cie_lobe(wavelength, 437.0, 11.8, 36.0) + 0.681 * cie_lobe(wavelength, 459.0, 26.0, 13.8),
+    );
+}
+
+// the linear sRGB weight of a sample at `wavelength`. Colors outside of sRGB are clipped, and each
+// channel is scaled so the average over wavelengths between 380nm and 780nm is 1, so a scene
+// without dispersion looks the same as without `SPECTRAL`
+fn wavelength_to_rgb(wavelength: f32) -> vec3<f32> {
+    let xyz = cie_xyz(wavelength);
+    let rgb = vec3<f32>(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    );
+    return max(rgb, vec3<f32>(0.0)) / vec3<f32>(0.4404567, 0.2884662, 0.2732404);
+}
@@ -0,0 +1,472 @@
+#import "scene.wgsl"
+#import "sampler.wgsl"
+#import "intersect.wgsl"
+#import "brdf.wgsl"
+#import "sky.wgsl"
+#import "noise.wgsl"
+#import "emission.wgsl"
+#import "blend.wgsl"
+#import "nodes.wgsl"
+#import "dielectric.wgsl"
+#import "spectral.wgsl"
+
+#ifdef DEBUG_PATH
+struct DebugBounce {
+    position: vec4<f32>,
+    normal: vec4<f32>,
+    // the direction the ray arrived from
+    direction: vec4<f32>,
+    // what the light from further along the path is multiplied by after this bounce
+    throughput: vec3<f32>,
+    material: u32,
+    // the light this bounce adds to the pixel
+    emitted: vec3<f32>,
+    // 1 transmitted, 2 clearcoat reflection, 4 cross section cut, 8 front face
+    flags: u32,
+}
+
+// the path of the first sample through `pixel`, filled in by the debug `ray_trace` entry point
+struct DebugPath {
+    pixel: vec2<u32>,
+    traced_samples: u32,
+    bounce_count: u32,
+    color: vec3<f32>,
+    escaped: u32,
+    // the light from the sky when the path escaped
+    sky: vec3<f32>,
+    origin: vec4<f32>,
+    // the direction the path left the scene in when it escaped
+    escape_direction: vec4<f32>,
+    bounces: array<DebugBounce, 16>,
+}
+
+@group(0)
+@binding(3)
+var<storage, read_write> debug_path: DebugPath;
+#endif
+
+#ifdef AOVS
+// what a single sample saw, the light is split by whether it reached the camera after at most
+// one bounce. Nothing is filled in by the ambient occlusion and w coordinate modes
+struct Aovs {
+    albedo: vec3<f32>,
+    normal: vec4<f32>,
+    // along the camera ray
+    depth: f32,
+    // how far the hit is from the camera in w
+    w_depth: f32,
+    direct: vec3<f32>,
+    indirect: vec3<f32>,
+}
+
+var<private> aov_sample: Aovs;
+
+// records `light` in the direct or indirect light of `aov_sample`
+fn add_aov_light(light: vec3<f32>, bounces: u32) {
+    if bounces <= 1u {
+        aov_sample.direct += light;
+    } else {
+        aov_sample.indirect += light;
+    }
+}
+#endif
+
+#ifdef AMBIENT_OCCLUSION
+// white where nothing is within `camera.ao_distance` of the first hit, escaping rays count as unoccluded
+fn trace(ray: Ray, sample_state: ptr<function, SampleState>) -> vec3<f32> {
+    let hit = get_closest_camera_hit(ray);
+    if !hit.hit {
+        return vec3<f32>(1.0);
+    }
+
+    var occlusion_ray: Ray;
+    occlusion_ray.origin = hit.position + hit.normal * camera.min_distance;
+    occlusion_ray.direction = diffuse_direction(hit.normal, sample_state);
+    occlusion_ray.time = ray.time;
+    let occluder = get_closest_hit(occlusion_ray);
+    if occluder.hit && occluder.distance < camera.ao_distance {
+        return vec3<f32>(0.0);
+    }
+    return vec3<f32>(1.0);
+}
+#else
+#ifdef W_COORDINATE
+// the ramp color for how far along w the first hit is from the camera, shaded so shapes stay readable
+fn trace(ray: Ray, sample_state: ptr<function, SampleState>) -> vec3<f32> {
+    let hit = get_closest_camera_hit(ray);
+    if !hit.hit {
+        return sky_color(ray.direction);
+    }
+
+    let t = clamp((hit.position.w - camera.position.w) / camera.w_range, -1.0, 1.0);
+    var color = mix(camera.w_center_color, camera.w_ana_color, t);
+    if t < 0.0 {
+        color = mix(camera.w_center_color, camera.w_kata_color, -t);
+    }
+    return color * (0.25 + 0.75 * abs(dot(hit.normal, ray.direction)));
+}
+#else
+fn trace(ray: Ray, sample_state: ptr<function, SampleState>) -> vec3<f32> {
+#ifdef DEBUG_PATH
+    let recording = debug_path.traced_samples == 0u;
+    debug_path.traced_samples += 1u;
+    if recording {
+        debug_path.origin = ray.origin;
+    }
+#endif
+    var ray = ray;
+    var incoming_light = vec3<f32>(0.0);
+    var ray_color = vec3<f32>(1.0);
+    var media: MediumStack;
+    media.count = 0u;
+
+    // false hits on the boundaries of overlapping media don't count as bounces, but there can only be a few of them
+    var bounces = 0u;
+    for (var i = 0u; bounces < camera.bounce_count && i < camera.bounce_count + 8u; i += 1u) {
+        var hit: Hit;
+        if i == 0u {
+            hit = get_closest_camera_hit(ray);
+        } else {
+            hit = get_closest_hit(ray);
+        }
+        if hit.hit {
+            ray_color *= medium_transmittance(&media, hit.distance);
+#ifdef MATERIAL_BLENDS
+            // the rest of the bounce, including the media, only sees the material that was picked
+            if get_material(hit.material).blend_mask != 0u {
+                hit.material = resolve_material(hit.material, hit.pattern_position, sample_1d(sample_state));
+            }
+#endif
+            var material = get_material(hit.material);
+#ifdef MATERIAL_NODES
+            if material.node_program_length > 0u {
+                run_node_program(&material, hit, ray.direction);
+            }
+#endif
+#ifdef CROSS_SECTION
+            if hit.cut {
+                material.base_color = camera.cut_color;
+                material.emission_strength = 0.0;
+                material.bump_strength = 0.0;
+                material.clearcoat = 0.0;
+                material.sheen = 0.0;
+            }
+#endif
+
+#ifdef DEBUG_PATH
+            let incoming_direction = ray.direction;
+            var debug_flags = select(0u, 4u, hit.cut) | select(0u, 8u, hit.front_face);
+#endif
+#ifdef AOVS
+            if bounces == 0u {
+                aov_sample.albedo = material.base_color;
+                aov_sample.normal = hit.normal;
+                aov_sample.depth = hit.distance;
+                aov_sample.w_depth = hit.position.w - camera.position.w;
+            }
+#endif
+            var surface_color = material.base_color;
+            var transmitted = false;
+            var boundary: Boundary;
+            if material.transmission > 0.0 {
+                boundary = medium_boundary(&media, hit, material, ray.wavelength);
+                if boundary.false_hit {
+                    ray.origin = hit.position - hit.normal * camera.min_distance;
+                    continue;
+                }
+                transmitted = sample_1d(sample_state) < material.transmission;
+            }
+            if transmitted {
+                ray = dielectric_scatter(ray, hit, boundary.from_ior, boundary.to_ior, sample_state);
+                if dot(ray.direction, hit.normal) < 0.0 {
+                    medium_stack_cross(&media, hit, boundary);
+                }
+            } else {
+                ray.origin = hit.position + hit.normal * camera.min_distance;
+                var coated = false;
+                if material.clearcoat > 0.0 {
+                    coated = sample_1d(sample_state) < material.clearcoat * clearcoat_reflectance(ray.direction, hit.normal);
+                }
+                let incoming = ray.direction;
+                if coated {
+#ifdef DEBUG_PATH
+                    debug_flags |= 2u;
+#endif
+                    ray.direction = glossy_direction(ray.direction, hit.normal, material.clearcoat_roughness, sample_state);
+                    surface_color = vec3<f32>(1.0);
+                } else if material.bump_strength > 0.0 {
+                    let normal = bump_normal(hit.normal, hit.position, material.bump_strength, material.bump_scale);
+                    ray.direction = diffuse_direction(normal, sample_state);
+                    // the tilted normal can send the ray into the surface, mirror it back out
+                    let below = dot(ray.direction, hit.normal);
+                    if below < 0.0 {
+                        ray.direction -= 2.0 * below * hit.normal;
+                    }
+                } else {
+                    ray.direction = diffuse_direction(hit.normal, sample_state);
+                }
+                if !coated && material.sheen > 0.0 {
+                    surface_color += sheen_color(material, incoming, ray.direction);
+                }
+            }
+            (*sample_state).first_bounce = false;
+
+            let emission = material.emission_strength * emission_pattern(material, hit.pattern_position);
+#ifdef LIGHT_GROUPS
+            light_group_sample[min(material.light_group, 4u)] += (material.emissive_color * emission) * ray_color;
+#else
+            incoming_light += (material.emissive_color * emission * light_group_weight(material.light_group)) * ray_color;
+#endif
+#ifdef AOVS
+            add_aov_light((material.emissive_color * emission * light_group_weight(material.light_group)) * ray_color, bounces);
+#endif
+#ifdef DEBUG_PATH
+            if recording && bounces < 16u {
+                debug_path.bounces[bounces].position = hit.position;
+                debug_path.bounces[bounces].normal = hit.normal;
+                debug_path.bounces[bounces].direction = incoming_direction;
+                debug_path.bounces[bounces].throughput = ray_color * surface_color;
+                debug_path.bounces[bounces].material = hit.material;
+                debug_path.bounces[bounces].emitted = (material.emissive_color * emission) * ray_color;
+                debug_path.bounces[bounces].flags = debug_flags | select(0u, 1u, transmitted);
+                debug_path.bounce_count = bounces + 1u;
+            }
+#endif
+            ray_color *= surface_color;
+            bounces += 1u;
+        } else {
+            incoming_light += sky_color(ray.direction) * ray_color;
+#ifdef LIGHT_GROUPS
+            light_group_sample[0] += sky_color(ray.direction) * ray_color;
+#endif
+#ifdef AOVS
+            add_aov_light(sky_color(ray.direction) * ray_color, bounces);
+#endif
+#ifdef DEBUG_PATH
+            if recording {
+                debug_path.escaped = 1u;
+                debug_path.sky = sky_color(ray.direction) * ray_color;
+                debug_path.escape_direction = ray.direction;
+            }
+#endif
+            break;
+        }
+    }
+
+    return incoming_light;
+}
+#endif
+#endif
+
+#ifdef STEREOGRAPHIC
+// the point on the 3-sphere that projects to `point`, which has to be perpendicular to the pole
+fn inverse_stereographic(point: vec4<f32>) -> vec4<f32> {
+    let radius_squared = dot(camera.stereographic_pole, camera.stereographic_pole);
+    let distance_squared = dot(point, point);
+    return (2.0 * radius_squared * point + (distance_squared - radius_squared) * camera.stereographic_pole) / (distance_squared + radius_squared);
+}
+
+// follows the circle on the 3-sphere that the projected ray maps to in short straight steps,
+// the path continues as a normal 4D ray from the first hit
+fn trace_stereographic(ray: Ray, sample_state: ptr<function, SampleState>) -> vec3<f32> {
+    // 64 steps, spaced to be even along circles through the center of the projection
+    let radius = length(camera.stereographic_pole);
+    var previous = inverse_stereographic(ray.origin);
+    for (var i = 1u; i <= 64u; i += 1u) {
+        var point = camera.stereographic_pole;
+        if i < 64u {
+            let s = radius * tan(f32(i) / 64.0 * 3.1415926 * 0.5);
+            point = inverse_stereographic(ray.origin + ray.direction * s);
+        }
+
+        var step: Ray;
+        step.origin = previous;
+        step.direction = normalize(point - previous);
+        step.time = ray.time;
+        step.wavelength = ray.wavelength;
+        let hit = get_closest_hit(step);
+        if hit.hit && hit.distance <= distance(point, previous) {
+            return trace(step, sample_state);
+        }
+        previous = point;
+    }
+#ifdef LIGHT_GROUPS
+    light_group_sample[0] += sky_color(ray.direction);
+#endif
+#ifdef AOVS
+    add_aov_light(sky_color(ray.direction), 0u);
+#endif
+    return sky_color(ray.direction);
+}
+#endif
+
+// the direction the camera ray through `normalized_uv` leaves in, zero outside the fisheye circle
+fn camera_direction(normalized_uv: vec2<f32>, aspect: f32) -> vec4<f32> {
+#ifdef FISHEYE
+    // the circle touches the shorter sides of the image
+    var point = vec2<f32>(normalized_uv.x * aspect, normalized_uv.y);
+    if aspect < 1.0 {
+        point = vec2<f32>(normalized_uv.x, normalized_uv.y / aspect);
+    }
+    let radius = length(point);
+    if radius > 1.0 {
+        return vec4<f32>(0.0);
+    }
+    let angle = radius * camera.fisheye_angle * 0.5;
+    var sideways = vec4<f32>(0.0);
+    if radius > 0.0 {
+        sideways = (camera.right * point.x + camera.up * point.y) / radius;
+    }
+    return camera.forward * cos(angle) + sideways * sin(angle);
+#else
+#ifdef EQUIRECTANGULAR
+    let longitude = normalized_uv.x * 3.1415926;
+    let latitude = normalized_uv.y * 3.1415926 * 0.5;
+    return (camera.forward * cos(longitude) + camera.right * sin(longitude)) * cos(latitude) + camera.up * sin(latitude);
+#else
+    let theta = tan(camera.fov / 2.0);
+    return normalize(camera.right * (normalized_uv.x * aspect * theta) + camera.up * (normalized_uv.y * theta) + camera.forward);
+#endif
+#endif
+}
+
+#ifdef AOVS
+// the average of `aov_sample` over the samples of the last `ray_trace_pixel`
+var<private> aov_average: Aovs;
+#endif
+
+#ifdef LIGHT_GROUPS
+// the average of `light_group_sample` over the samples of the last `ray_trace_pixel`
+var<private> light_group_light: array<vec3<f32>, 5>;
+#endif
+
+// the ray through a random point of the pixel at `coords` for the sample `sample_state` is on,
+// its direction is zero outside the fisheye circle
+fn camera_ray(coords: vec2<i32>, size: vec2<i32>, sample_state: ptr<function, SampleState>) -> Ray {
+    var eye_coords = coords;
+    var eye_size = size;
+    var origin = camera.position;
+#ifdef STEREO
+    // each eye gets its own half of the image
+    eye_size.x = size.x / 2;
+    if coords.x < eye_size.x {
+        origin -= camera.right * camera.eye_offset;
+    } else {
+        eye_coords.x -= eye_size.x;
+        origin += camera.right * camera.eye_offset;
+    }
+#endif
+
+    var aspect = f32(eye_size.x) / f32(eye_size.y);
+    if camera.aspect_override > 0.0 {
+        aspect = camera.aspect_override;
+    }
+
+    let uv = (vec2<f32>(eye_coords) + vec2<f32>(sample_1d(sample_state), sample_1d(sample_state)) * 2.0 - 1.0) / vec2<f32>(eye_size);
+    let normalized_uv = vec2<f32>(uv.x, 1.0 - uv.y) * 2.0 - 1.0 + camera.lens_shift;
+
+    var ray: Ray;
+    ray.origin = origin;
+#ifdef MOTION_BLUR
+    ray.time = sample_1d(sample_state) * camera.shutter_time;
+#else
+    ray.time = 0.0;
+#endif
+#ifdef SPECTRAL
+    ray.wavelength = 380.0 + 400.0 * sample_1d(sample_state);
+#else
+    ray.wavelength = 589.3;
+#endif
+    ray.direction = camera_direction(normalized_uv, aspect);
+    return ray;
+}
+
+fn ray_trace_pixel(coords: vec2<i32>, size: vec2<i32>) -> vec3<f32> {
+    var sample_state = sample_state_new(vec2<u32>(coords), hash(u32(coords.x + coords.y * size.x) ^ hash(camera.seed_offset)));
+
+    var color = vec3<f32>(0.0);
+#ifdef LIGHT_GROUPS
+    for (var group = 0u; group < 5u; group += 1u) {
+        light_group_light[group] = vec3<f32>(0.0);
+    }
+#endif
+#ifdef AOVS
+    aov_average = Aovs();
+#endif
+    for (var i = 0u; i < camera.sample_count; i += 1u) {
+        start_sample(&sample_state, camera.sample_index + i);
+        let ray = camera_ray(coords, size, &sample_state);
+        // outside the fisheye circle is left black
+        if all(ray.direction == vec4<f32>(0.0)) {
+            continue;
+        }
+
+#ifdef LIGHT_GROUPS
+        for (var group = 0u; group < 5u; group += 1u) {
+            light_group_sample[group] = vec3<f32>(0.0);
+        }
+#endif
+#ifdef AOVS
+        // rays that escape are as deep as the camera sees
+        aov_sample = Aovs();
+        aov_sample.depth = camera.max_distance;
+#endif
+#ifdef STEREOGRAPHIC
+        var sample_color = trace_stereographic(ray, &sample_state);
+#else
+        var sample_color = trace(ray, &sample_state);
+#endif
+        var tint = vec3<f32>(1.0);
+#ifdef SPECTRAL
+        tint = wavelength_to_rgb(ray.wavelength);
+#endif
+        color += sample_color * tint;
+#ifdef LIGHT_GROUPS
+        for (var group = 0u; group < 5u; group += 1u) {
+            light_group_light[group] += light_group_sample[group] * tint;
+        }
+#endif
+#ifdef AOVS
+        aov_average.albedo += aov_sample.albedo;
+        aov_average.normal += aov_sample.normal;
+        aov_average.depth += aov_sample.depth;
+        aov_average.w_depth += aov_sample.w_depth;
+        aov_average.direct += aov_sample.direct * tint;
+        aov_average.indirect += aov_sample.indirect * tint;
+#endif
+    }
+    color /= f32(camera.sample_count);
+#ifdef LIGHT_GROUPS
+    for (var group = 0u; group < 5u; group += 1u) {
+        light_group_light[group] /= f32(camera.sample_count);
+    }
+#endif
+#ifdef AOVS
+    let samples = f32(camera.sample_count);
+    aov_average.albedo /= samples;
+    aov_average.normal /= samples;
+    aov_average.depth /= samples;
+    aov_average.w_depth /= samples;
+    aov_average.direct /= samples;
+    aov_average.indirect /= samples;
+#endif
+    // left unclamped so the accumulated average stays HDR, it is clamped when it is shown
+    return color;
+}
+
+fn srgb_from_linear(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        return value * 12.92;
+    }
+    return 1.055 * pow(value, 1.0 / 2.4) - 0.055;
+}
+
+// how a linear color is stored in the output texture, it is clamped to [0, 1] first
+fn encode_output(color: vec3<f32>) -> vec4<f32> {
+    let clamped = clamp(color, vec3<f32>(0.0), vec3<f32>(1.0));
+#ifdef SRGB_OUTPUT
+    return vec4<f32>(srgb_from_linear(clamped.r), srgb_from_linear(clamped.g), srgb_from_linear(clamped.b), 1.0);
+#else
+    return vec4<f32>(clamped, 1.0);
+#endif
+}
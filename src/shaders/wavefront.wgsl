@@ -0,0 +1,321 @@
+#import "trace.wgsl"
+#import "compute.wgsl"
+
+// splits the megakernel's `trace` into kernels that each do one step of every path in a wave,
+// so paths that take different branches through the materials don't hold each other up.
+// Only path tracing without light groups, AOVs or the debug path can be split up
+
+// a path through one pixel of the wave, kept between the kernels
+struct Path {
+    origin: vec4<f32>,
+    direction: vec4<f32>,
+    time: f32,
+    wavelength: f32,
+    // what `intersect_paths` found, 1 hit, 2 cross section cut, 4 front face
+    hit_flags: u32,
+    hit_material: u32,
+    hit_position: vec4<f32>,
+    hit_normal: vec4<f32>,
+    hit_pattern_position: vec4<f32>,
+    hit_distance: f32,
+    // what the light from further along the path is multiplied by, starts as the wavelength's tint
+    throughput: vec3<f32>,
+    // the sum of every sample of the pixel traced so far
+    light: vec3<f32>,
+    rng: u32,
+    media: MediumStack,
+    dimension: u32,
+    // how many samples of the pixel have been started
+    samples: u32,
+    bounces: u32,
+    // bounces and false hits on the boundaries of overlapping media
+    iteration: u32,
+    first_bounce: u32,
+    alive: u32,
+}
+
+// `WAVE_SIZE` squared in wavefront.rs
+const WAVE_PATHS: u32 = 65536u;
+
+struct Wavefront {
+    // how many paths `compact_paths` has queued
+    queue_count: atomic<u32>,
+    // the workgroups `intersect_paths` and `shade_paths` are dispatched with, copied into
+    // the indirect buffer before they run
+    workgroups: atomic<u32>,
+    // the paths that are still alive
+    queue: array<u32, WAVE_PATHS>,
+    // one per pixel of the wave, row by row
+    paths: array<Path>,
+}
+
+@group(0)
+@binding(5)
+var<storage, read_write> wavefront: Wavefront;
+
+// the pixel of the wave the path at `index` is traced through
+fn path_coords(index: u32) -> vec2<u32> {
+    return tile.offset + vec2<u32>(index % tile.size.x, index / tile.size.x);
+}
+
+fn in_wave(global_id: vec3<u32>) -> bool {
+    let coords = vec2<i32>(global_id.xy + tile.offset);
+    return global_id.x < tile.size.x && global_id.y < tile.size.y && all(coords < textureDimensions(output_texture));
+}
+
+fn path_sample_state(path: Path, coords: vec2<u32>) -> SampleState {
+    var sample_state = sample_state_new(coords, path.rng);
+    sample_state.index = camera.sample_index + path.samples - 1u;
+    sample_state.dimension = path.dimension;
+    sample_state.first_bounce = path.first_bounce != 0u;
+    return sample_state;
+}
+
+fn store_sample_state(path: ptr<function, Path>, sample_state: SampleState) {
+    (*path).rng = sample_state.rng;
+    (*path).dimension = sample_state.dimension;
+    (*path).first_bounce = select(0u, 1u, sample_state.first_bounce);
+}
+
+fn path_ray(path: Path) -> Ray {
+    var ray: Ray;
+    ray.origin = path.origin;
+    ray.direction = path.direction;
+    ray.time = path.time;
+    ray.wavelength = path.wavelength;
+    return ray;
+}
+
+fn store_ray(path: ptr<function, Path>, ray: Ray) {
+    (*path).origin = ray.origin;
+    (*path).direction = ray.direction;
+    (*path).time = ray.time;
+    (*path).wavelength = ray.wavelength;
+}
+
+fn path_hit(path: Path) -> Hit {
+    var hit: Hit;
+    hit.hit = (path.hit_flags & 1u) != 0u;
+    hit.cut = (path.hit_flags & 2u) != 0u;
+    hit.front_face = (path.hit_flags & 4u) != 0u;
+    hit.distance = path.hit_distance;
+    hit.position = path.hit_position;
+    hit.normal = path.hit_normal;
+    hit.material = path.hit_material;
+    hit.pattern_position = path.hit_pattern_position;
+    return hit;
+}
+
+// starts the next sample of every pixel in the wave with its camera ray
+@compute
+@workgroup_size(16, 16)
+fn generate_paths(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+) {
+    if !in_wave(global_id) {
+        return;
+    }
+    let size = textureDimensions(output_texture);
+    let coords = vec2<i32>(global_id.xy + tile.offset);
+    let index = global_id.x + global_id.y * tile.size.x;
+
+    var path = wavefront.paths[index];
+    var sample_state: SampleState;
+    if path.samples == 0u {
+        // the same sequence as `ray_trace_pixel`
+        sample_state = sample_state_new(vec2<u32>(coords), hash(u32(coords.x + coords.y * size.x) ^ hash(camera.seed_offset)));
+        path.light = vec3<f32>(0.0);
+    } else {
+        sample_state = path_sample_state(path, vec2<u32>(coords));
+    }
+    start_sample(&sample_state, camera.sample_index + path.samples);
+    path.samples += 1u;
+
+    let ray = camera_ray(coords, size, &sample_state);
+    store_ray(&path, ray);
+    store_sample_state(&path, sample_state);
+    path.throughput = vec3<f32>(1.0);
+#ifdef SPECTRAL
+    path.throughput = wavelength_to_rgb(ray.wavelength);
+#endif
+    path.media.count = 0u;
+    path.bounces = 0u;
+    path.iteration = 0u;
+    // outside the fisheye circle is left black
+    path.alive = select(0u, 1u, camera.bounce_count > 0u && any(ray.direction != vec4<f32>(0.0)));
+    wavefront.paths[index] = path;
+}
+
+// queues the paths of the wave that are still alive, the queue has to be cleared first
+@compute
+@workgroup_size(16, 16)
+fn compact_paths(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+) {
+    if !in_wave(global_id) {
+        return;
+    }
+    let index = global_id.x + global_id.y * tile.size.x;
+    if wavefront.paths[index].alive == 0u {
+        return;
+    }
+    let slot = atomicAdd(&wavefront.queue_count, 1u);
+    wavefront.queue[slot] = index;
+    atomicMax(&wavefront.workgroups, slot / 64u + 1u);
+}
+
+// finds what the ray of every queued path hits
+@compute
+@workgroup_size(64)
+fn intersect_paths(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+) {
+    if global_id.x >= atomicLoad(&wavefront.queue_count) {
+        return;
+    }
+    let index = wavefront.queue[global_id.x];
+    let ray = path_ray(wavefront.paths[index]);
+
+    var hit: Hit;
+    if wavefront.paths[index].iteration == 0u {
+        hit = get_closest_camera_hit(ray);
+    } else {
+        hit = get_closest_hit(ray);
+    }
+    wavefront.paths[index].hit_flags = select(0u, 1u, hit.hit) | select(0u, 2u, hit.cut) | select(0u, 4u, hit.front_face);
+    wavefront.paths[index].hit_distance = hit.distance;
+    wavefront.paths[index].hit_position = hit.position;
+    wavefront.paths[index].hit_normal = hit.normal;
+    wavefront.paths[index].hit_material = hit.material;
+    wavefront.paths[index].hit_pattern_position = hit.pattern_position;
+}
+
+// one iteration of the loop in `trace`
+fn shade_path(path: ptr<function, Path>, sample_state: ptr<function, SampleState>) {
+    var ray = path_ray(*path);
+    var hit = path_hit(*path);
+    if !hit.hit {
+        (*path).light += sky_color(ray.direction) * (*path).throughput;
+        (*path).alive = 0u;
+        return;
+    }
+
+    (*path).throughput *= medium_transmittance(&(*path).media, hit.distance);
+#ifdef MATERIAL_BLENDS
+    if get_material(hit.material).blend_mask != 0u {
+        hit.material = resolve_material(hit.material, hit.pattern_position, sample_1d(sample_state));
+    }
+#endif
+    var material = get_material(hit.material);
+#ifdef MATERIAL_NODES
+    if material.node_program_length > 0u {
+        run_node_program(&material, hit, ray.direction);
+    }
+#endif
+#ifdef CROSS_SECTION
+    if hit.cut {
+        material.base_color = camera.cut_color;
+        material.emission_strength = 0.0;
+        material.bump_strength = 0.0;
+        material.clearcoat = 0.0;
+        material.sheen = 0.0;
+    }
+#endif
+
+    var surface_color = material.base_color;
+    var transmitted = false;
+    var boundary: Boundary;
+    if material.transmission > 0.0 {
+        boundary = medium_boundary(&(*path).media, hit, material, ray.wavelength);
+        if boundary.false_hit {
+            (*path).origin = hit.position - hit.normal * camera.min_distance;
+            return;
+        }
+        transmitted = sample_1d(sample_state) < material.transmission;
+    }
+    if transmitted {
+        ray = dielectric_scatter(ray, hit, boundary.from_ior, boundary.to_ior, sample_state);
+        if dot(ray.direction, hit.normal) < 0.0 {
+            medium_stack_cross(&(*path).media, hit, boundary);
+        }
+    } else {
+        ray.origin = hit.position + hit.normal * camera.min_distance;
+        var coated = false;
+        if material.clearcoat > 0.0 {
+            coated = sample_1d(sample_state) < material.clearcoat * clearcoat_reflectance(ray.direction, hit.normal);
+        }
+        let incoming = ray.direction;
+        if coated {
+            ray.direction = glossy_direction(ray.direction, hit.normal, material.clearcoat_roughness, sample_state);
+            surface_color = vec3<f32>(1.0);
+        } else if material.bump_strength > 0.0 {
+            let normal = bump_normal(hit.normal, hit.position, material.bump_strength, material.bump_scale);
+            ray.direction = diffuse_direction(normal, sample_state);
+            let below = dot(ray.direction, hit.normal);
+            if below < 0.0 {
+                ray.direction -= 2.0 * below * hit.normal;
+            }
+        } else {
+            ray.direction = diffuse_direction(hit.normal, sample_state);
+        }
+        if !coated && material.sheen > 0.0 {
+            surface_color += sheen_color(material, incoming, ray.direction);
+        }
+    }
+    (*sample_state).first_bounce = false;
+
+    let emission = material.emission_strength * emission_pattern(material, hit.pattern_position);
+    (*path).light += (material.emissive_color * emission * light_group_weight(material.light_group)) * (*path).throughput;
+    (*path).throughput *= surface_color;
+    (*path).bounces += 1u;
+    store_ray(path, ray);
+}
+
+// bounces every queued path off what it hit, or adds the sky if it escaped
+@compute
+@workgroup_size(64)
+fn shade_paths(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+) {
+    if global_id.x >= atomicLoad(&wavefront.queue_count) {
+        return;
+    }
+    let index = wavefront.queue[global_id.x];
+    var path = wavefront.paths[index];
+    var sample_state = path_sample_state(path, path_coords(index));
+
+    shade_path(&path, &sample_state);
+    store_sample_state(&path, sample_state);
+    path.iteration += 1u;
+    if path.bounces >= camera.bounce_count || path.iteration >= camera.bounce_count + 8u {
+        path.alive = 0u;
+    }
+    wavefront.paths[index] = path;
+}
+
+// averages the samples of every pixel in the wave into the output like `ray_trace`
+@compute
+@workgroup_size(16, 16)
+fn resolve_paths(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+) {
+    if !in_wave(global_id) {
+        return;
+    }
+    let size = textureDimensions(output_texture);
+    let coords = vec2<i32>(global_id.xy + tile.offset);
+    let path_index = global_id.x + global_id.y * tile.size.x;
+
+    let index = u32(coords.x + coords.y * size.x) * 3u;
+    var total = wavefront.paths[path_index].light / f32(camera.sample_count);
+    if camera.accumulated_frames > 0u {
+        total += vec3<f32>(accumulation[index], accumulation[index + 1u], accumulation[index + 2u]);
+    }
+    accumulation[index] = total.r;
+    accumulation[index + 1u] = total.g;
+    accumulation[index + 2u] = total.b;
+    textureStore(output_texture, coords.xy, encode_output(total / f32(camera.accumulated_frames + 1u)));
+
+    // the next wave starts its pixels from scratch
+    wavefront.paths[path_index].samples = 0u;
+}
@@ -0,0 +1,58 @@
+/// Grid snapping and axis locks for editing object positions
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Snapping {
+    pub enabled: bool,
+    /// The grid spacing along x, y, z and w, axes with a step of 0 aren't snapped
+    pub step: cgmath::Vector4<f32>,
+    /// Edits can't change locked axes, whether or not snapping is enabled
+    pub locked: [bool; 4],
+}
+
+impl Default for Snapping {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            step: cgmath::vec4(0.25, 0.25, 0.25, 0.25),
+            locked: [false; 4],
+        }
+    }
+}
+
+impl Snapping {
+    /// Applies the locks and the grid to a position that was edited from `previous` to `edited`
+    pub fn apply(
+        &self,
+        previous: cgmath::Vector4<f32>,
+        edited: cgmath::Vector4<f32>,
+    ) -> cgmath::Vector4<f32> {
+        let mut result = edited;
+        for axis in 0..4 {
+            if self.locked[axis] {
+                result[axis] = previous[axis];
+            } else {
+                result[axis] = self.snap(axis, edited[axis]);
+            }
+        }
+        result
+    }
+
+    /// Rounds `value` to the grid along `axis` if snapping is enabled
+    pub fn snap(&self, axis: usize, value: f32) -> f32 {
+        let step = self.step[axis];
+        if self.enabled && step > 0.0 {
+            (value / step).round() * step
+        } else {
+            value
+        }
+    }
+
+    /// Rounds every axis to the grid, ignoring the locks
+    pub fn snap_position(&self, position: cgmath::Vector4<f32>) -> cgmath::Vector4<f32> {
+        cgmath::vec4(
+            self.snap(0, position.x),
+            self.snap(1, position.y),
+            self.snap(2, position.z),
+            self.snap(3, position.w),
+        )
+    }
+}
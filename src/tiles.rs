@@ -0,0 +1,56 @@
+/// A rectangle of the output texture in pixels
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Tile {
+    /// The part of both rectangles, `None` if they don't overlap
+    pub fn intersection(self, other: Tile) -> Option<Tile> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+        (right > x && bottom > y).then(|| Tile {
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
+        })
+    }
+
+    /// Splits this rectangle into row-major tiles of at most `tile_size` pixels on each side,
+    /// or just itself if `tile_size` is `None`
+    pub fn split(self, tile_size: Option<usize>) -> Vec<Tile> {
+        let Some(tile_size) = tile_size else {
+            return vec![self];
+        };
+        let tile_size = tile_size.max(1);
+        (0..self.height)
+            .step_by(tile_size)
+            .flat_map(|y| {
+                (0..self.width).step_by(tile_size).map(move |x| Tile {
+                    x: self.x + x,
+                    y: self.y + y,
+                    width: tile_size.min(self.width - x),
+                    height: tile_size.min(self.height - y),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Splits a `width` by `height` image into row-major tiles of at most `tile_size` pixels
+/// on each side, or a single tile covering everything if `tile_size` is `None`
+pub fn split_into_tiles(width: usize, height: usize, tile_size: Option<usize>) -> Vec<Tile> {
+    Tile {
+        x: 0,
+        y: 0,
+        width,
+        height,
+    }
+    .split(tile_size)
+}
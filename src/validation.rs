@@ -0,0 +1,188 @@
+use crate::{
+    BiVector4, GpuHyperPlane, GpuHyperSphere, GpuMaterial, MaterialId, MaterialNode, NodeGraph,
+    NodeGraphError, ObjectRef, Primitive, Scene, SceneObject,
+};
+
+/// Something about an object that would send garbage to the GPU, objects are
+/// [sanitized](Primitive::sanitized) before they are uploaded so it's only a warning
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ObjectProblem {
+    /// A position, direction, or size is NaN or infinite
+    NonFinite,
+    NegativeRadius,
+    ZeroNormal,
+    NegativeExtents,
+    MaterialOutOfRange,
+}
+
+impl ObjectProblem {
+    /// What is wrong and what is rendered instead
+    pub fn message(self) -> &'static str {
+        match self {
+            ObjectProblem::NonFinite => "Has NaN or infinite values, they are rendered as 0",
+            ObjectProblem::NegativeRadius => "The radius is negative, its absolute value is used",
+            ObjectProblem::ZeroNormal => "The normal has no length, +Y is used instead",
+            ObjectProblem::NegativeExtents => {
+                "Some extents are negative, their absolute values are used"
+            }
+            ObjectProblem::MaterialOutOfRange => {
+                "The material doesn't exist, the first one is used"
+            }
+        }
+    }
+}
+
+/// Something about a blended or node material that the shader can't follow as it is
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MaterialProblem {
+    BlendMaterialMissing,
+    /// Usually because the blend contains itself
+    BlendTooDeep,
+    /// The node graph doesn't compile, so the material is used without it
+    Nodes(NodeGraphError),
+}
+
+impl MaterialProblem {
+    /// What is wrong and what is rendered instead
+    pub fn message(self) -> &'static str {
+        match self {
+            MaterialProblem::BlendMaterialMissing => {
+                "A blended material doesn't exist, the first one is used"
+            }
+            MaterialProblem::BlendTooDeep => {
+                "The blends go deeper than 8 materials, the blend itself is used past that"
+            }
+            MaterialProblem::Nodes(error) => error.message(),
+        }
+    }
+}
+
+pub(crate) fn all_finite(values: &[f32]) -> bool {
+    values.iter().all(|value| value.is_finite())
+}
+
+pub(crate) fn finite_or_zero(value: f32) -> f32 {
+    if value.is_finite() {
+        value
+    } else {
+        0.0
+    }
+}
+
+pub(crate) fn bivector_components(bivector: BiVector4) -> [f32; 6] {
+    let BiVector4 {
+        xy,
+        xz,
+        xw,
+        yz,
+        yw,
+        zw,
+    } = bivector;
+    [xy, xz, xw, yz, yw, zw]
+}
+
+/// `material` if it exists, otherwise the first material
+pub(crate) fn existing_material(material: u32, material_ids: &[MaterialId]) -> u32 {
+    if material_ids.contains(&MaterialId(material)) {
+        material
+    } else {
+        material_ids.first().map_or(0, |id| id.0)
+    }
+}
+
+impl SceneObject {
+    pub fn problems(&self, material_ids: &[MaterialId]) -> Vec<ObjectProblem> {
+        self.data.problems(material_ids)
+    }
+}
+
+impl Scene {
+    /// The problems of every object that has some
+    pub fn problems(&self) -> Vec<(ObjectRef, Vec<ObjectProblem>)> {
+        self.object_refs()
+            .zip(&self.objects)
+            .map(|(object_ref, object)| (object_ref, object.problems(&self.material_ids)))
+            .filter(|(_, problems)| !problems.is_empty())
+            .collect()
+    }
+
+    /// The problems of every material that has some
+    pub fn material_problems(&self) -> Vec<(MaterialId, Vec<MaterialProblem>)> {
+        self.material_ids
+            .iter()
+            .zip(&self.materials)
+            .map(|(&id, material)| {
+                let mut problems = vec![];
+                if let Some(blended) = material.blended() {
+                    if blended.iter().any(|&id| self.material_index(id).is_none()) {
+                        problems.push(MaterialProblem::BlendMaterialMissing);
+                    }
+                    if MaterialNode::new(self, id).is_truncated(self) {
+                        problems.push(MaterialProblem::BlendTooDeep);
+                    }
+                }
+                if let Some(Err(error)) = self.node_graphs.get(&id).map(NodeGraph::compile) {
+                    problems.push(MaterialProblem::Nodes(error));
+                }
+                (id, problems)
+            })
+            .filter(|(_, problems)| !problems.is_empty())
+            .collect()
+    }
+
+    /// The hyper spheres as they are uploaded, [sanitized](Primitive::sanitized) and with
+    /// their materials turned into indices into `materials`
+    pub fn sanitized_hyper_spheres(&self) -> Vec<GpuHyperSphere> {
+        let indices = self.material_indices();
+        self.hyper_spheres()
+            .map(|hyper_sphere| {
+                let hyper_sphere = hyper_sphere.sanitized(&self.material_ids);
+                GpuHyperSphere {
+                    material: indices
+                        .get(&MaterialId(hyper_sphere.material))
+                        .copied()
+                        .unwrap_or(0),
+                    ..hyper_sphere
+                }
+            })
+            .collect()
+    }
+
+    /// The materials as they are uploaded, with the materials they blend turned into indices into
+    /// `materials`. Blends of materials that don't exist use the first material instead
+    pub fn sanitized_materials(&self) -> Vec<GpuMaterial> {
+        let indices = self.material_indices();
+        let index = |id: u32| {
+            indices
+                .get(&MaterialId(existing_material(id, &self.material_ids)))
+                .copied()
+                .unwrap_or(0)
+        };
+        self.materials
+            .iter()
+            .map(|material| GpuMaterial {
+                blend_a: index(material.blend_a),
+                blend_b: index(material.blend_b),
+                ..*material
+            })
+            .collect()
+    }
+
+    /// The hyper planes as they are uploaded, [sanitized](Primitive::sanitized) and with
+    /// their materials turned into indices into `materials`
+    pub fn sanitized_hyper_planes(&self) -> Vec<GpuHyperPlane> {
+        let indices = self.material_indices();
+        self.hyper_planes()
+            .map(|hyper_plane| {
+                let hyper_plane = hyper_plane.sanitized(&self.material_ids);
+                GpuHyperPlane {
+                    material: indices
+                        .get(&MaterialId(hyper_plane.material))
+                        .copied()
+                        .unwrap_or(0),
+                    ..hyper_plane
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{GpuTile, Tile};
+use encase::ShaderSize;
+use wgpu::util::DeviceExt;
+
+/// Tiles are traced in waves of at most this many pixels on each side, so the paths of a whole
+/// wave fit in one buffer. Has to match `WAVE_PATHS` in wavefront.wgsl
+pub const WAVE_SIZE: usize = 256;
+
+/// The size of a `Path` in wavefront.wgsl
+const PATH_SIZE: u64 = 192;
+
+/// The queue count, the workgroup count and the queue, padded to where the paths start
+const HEADER_SIZE: u64 = (2 + WAVE_SIZE * WAVE_SIZE) as u64 * 4;
+const PATHS_OFFSET: u64 = HEADER_SIZE.next_multiple_of(16);
+
+/// The kernels of a shader built with `WAVEFRONT`, see [`crate::RenderOptions::wavefront`]
+pub(crate) struct WavefrontPipelines {
+    /// Starts the next sample of every pixel of a wave
+    generate: wgpu::ComputePipeline,
+    /// Queues the paths that are still alive and counts the workgroups needed for them
+    compact: wgpu::ComputePipeline,
+    intersect: wgpu::ComputePipeline,
+    /// Bounces the queued paths off what they hit
+    shade: wgpu::ComputePipeline,
+    /// Averages the samples of a wave into the output
+    resolve: wgpu::ComputePipeline,
+    /// Fills the visible hyper spheres list before anything is traced
+    pub cull: Option<wgpu::ComputePipeline>,
+}
+
+impl WavefrontPipelines {
+    /// `culling` is whether `module` was built with `CULLING`
+    pub fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        module: &wgpu::ShaderModule,
+        culling: bool,
+    ) -> Self {
+        let create = |label, entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(layout),
+                module,
+                entry_point,
+            })
+        };
+        Self {
+            generate: create("Generate Paths Pipeline", "generate_paths"),
+            compact: create("Compact Paths Pipeline", "compact_paths"),
+            intersect: create("Intersect Paths Pipeline", "intersect_paths"),
+            shade: create("Shade Paths Pipeline", "shade_paths"),
+            resolve: create("Resolve Paths Pipeline", "resolve_paths"),
+            cull: culling.then(|| create("Culling Pipeline", "cull_hyper_spheres")),
+        }
+    }
+}
+
+/// The paths of the wave being traced and the queue of the ones still alive, the buffers are
+/// only created the first time they are used
+pub(crate) struct Wavefront {
+    bind_group_layout: wgpu::BindGroupLayout,
+    paths_buffer: Option<wgpu::Buffer>,
+    /// the workgroups `intersect_paths` and `shade_paths` are dispatched with
+    dispatch_buffer: Option<wgpu::Buffer>,
+    bind_group: Option<wgpu::BindGroup>,
+}
+
+impl Wavefront {
+    /// `texture_entries` are the output, accumulation and tile entries of the texture bind group,
+    /// the paths are bound after them
+    pub fn new(device: &wgpu::Device, texture_entries: &[wgpu::BindGroupLayoutEntry]) -> Self {
+        let mut entries = texture_entries.to_vec();
+        entries.push(wgpu::BindGroupLayoutEntry {
+            binding: 5,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Wavefront Bind Group Layout"),
+            entries: &entries,
+        });
+        Self {
+            bind_group_layout,
+            paths_buffer: None,
+            dispatch_buffer: None,
+            bind_group: None,
+        }
+    }
+
+    /// Takes the place of the texture bind group layout
+    pub fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.bind_group_layout
+    }
+
+    /// Call whenever the output texture, accumulation buffer or tile uniform buffer is recreated
+    pub fn invalidate(&mut self) {
+        self.bind_group = None;
+    }
+
+    /// Creates the buffers and bind group if they don't exist yet, returns the bind group
+    pub fn bind_group(
+        &mut self,
+        device: &wgpu::Device,
+        texture_view: &wgpu::TextureView,
+        accumulation_buffer: &wgpu::Buffer,
+        tile_uniform_buffer: &wgpu::Buffer,
+    ) -> &wgpu::BindGroup {
+        let paths_buffer = self.paths_buffer.get_or_insert_with(|| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Wavefront Paths Storage Buffer"),
+                size: PATHS_OFFSET + (WAVE_SIZE * WAVE_SIZE) as u64 * PATH_SIZE,
+                // the counts are cleared before every compaction and copied into `dispatch_buffer`
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        });
+        self.dispatch_buffer.get_or_insert_with(|| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Wavefront Dispatch Buffer"),
+                contents: &[0u32, 1, 1]
+                    .iter()
+                    .flat_map(|value| value.to_le_bytes())
+                    .collect::<Vec<_>>(),
+                usage: wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
+            })
+        });
+        self.bind_group.get_or_insert_with(|| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Wavefront Bind Group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(texture_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: accumulation_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                            buffer: tile_uniform_buffer,
+                            offset: 0,
+                            size: Some(<GpuTile as ShaderSize>::SHADER_SIZE),
+                        }),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 5,
+                        resource: paths_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        })
+    }
+
+    /// Traces `sample_count` samples of every pixel in `wave` and averages them into the output.
+    /// `bind_groups` are the camera, objects and materials bind groups, `tile_offset` is where
+    /// the wave is in the tile uniform buffer. [`Wavefront::bind_group`] has to be called first
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_wave(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        pipelines: &WavefrontPipelines,
+        bind_groups: [&wgpu::BindGroup; 3],
+        tile_offset: u32,
+        wave: Tile,
+        sample_count: u32,
+        bounce_count: u32,
+    ) {
+        let (Some(paths_buffer), Some(dispatch_buffer), Some(bind_group)) =
+            (&self.paths_buffer, &self.dispatch_buffer, &self.bind_group)
+        else {
+            return;
+        };
+        let bind_groups = [bind_group, bind_groups[0], bind_groups[1], bind_groups[2]];
+        let workgroups = (
+            wave.width.div_ceil(16) as u32,
+            wave.height.div_ceil(16) as u32,
+        );
+
+        for _ in 0..sample_count {
+            // like `trace`, false hits on the boundaries of overlapping media get 8 extra iterations
+            for iteration in 0..bounce_count + 8 {
+                encoder.clear_buffer(paths_buffer, 0, wgpu::BufferSize::new(8));
+                {
+                    let mut compact_pass =
+                        encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                            label: Some("Compact Paths Pass"),
+                        });
+                    set_bind_groups(&mut compact_pass, bind_groups, tile_offset);
+                    if iteration == 0 {
+                        compact_pass.set_pipeline(&pipelines.generate);
+                        compact_pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+                    }
+                    compact_pass.set_pipeline(&pipelines.compact);
+                    compact_pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+                }
+                // a buffer can't be bound for writing and read as the indirect arguments at once
+                encoder.copy_buffer_to_buffer(paths_buffer, 4, dispatch_buffer, 0, 4);
+                {
+                    let mut trace_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                        label: Some("Trace Paths Pass"),
+                    });
+                    set_bind_groups(&mut trace_pass, bind_groups, tile_offset);
+                    trace_pass.set_pipeline(&pipelines.intersect);
+                    trace_pass.dispatch_workgroups_indirect(dispatch_buffer, 0);
+                    trace_pass.set_pipeline(&pipelines.shade);
+                    trace_pass.dispatch_workgroups_indirect(dispatch_buffer, 0);
+                }
+            }
+        }
+
+        let mut resolve_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Resolve Paths Pass"),
+        });
+        set_bind_groups(&mut resolve_pass, bind_groups, tile_offset);
+        resolve_pass.set_pipeline(&pipelines.resolve);
+        resolve_pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+    }
+}
+
+/// Only the wavefront bind group has a dynamic offset, the wave's in the tile uniform buffer
+fn set_bind_groups<'a>(
+    pass: &mut wgpu::ComputePass<'a>,
+    bind_groups: [&'a wgpu::BindGroup; 4],
+    tile_offset: u32,
+) {
+    for (i, bind_group) in bind_groups.into_iter().enumerate() {
+        let offsets: &[u32] = if i == 0 { &[tile_offset] } else { &[] };
+        pass.set_bind_group(i as u32, bind_group, offsets);
+    }
+}
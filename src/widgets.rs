@@ -0,0 +1,174 @@
+use crate::{BiVector4, MaterialId, Rotor4, Snapping};
+use cgmath::prelude::*;
+use eframe::egui;
+
+pub(crate) const ROTATION_PLANES: [(&str, BiVector4); 6] = [
+    ("XY", BiVector4::XY),
+    ("XZ", BiVector4::XZ),
+    ("XW", BiVector4::XW),
+    ("YZ", BiVector4::YZ),
+    ("YW", BiVector4::YW),
+    ("ZW", BiVector4::ZW),
+];
+
+/// What the editors of [`crate::Primitive`]s need from the rest of the scene
+pub struct EditContext<'a> {
+    pub snapping: &'a Snapping,
+    pub material_ids: &'a [MaterialId],
+    pub material_names: &'a [String],
+}
+
+#[inline(always)]
+pub(crate) fn edit_value(
+    ui: &mut egui::Ui,
+    label: impl Into<egui::WidgetText>,
+    value: &mut impl egui::emath::Numeric,
+    speed: impl Into<f64>,
+) -> bool {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.add(egui::DragValue::new(value).speed(speed)).changed()
+    })
+    .inner
+}
+
+#[inline(always)]
+pub(crate) fn edit_vec4(
+    ui: &mut egui::Ui,
+    label: impl Into<egui::WidgetText>,
+    vec: &mut cgmath::Vector4<impl egui::emath::Numeric>,
+) -> bool {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.add(egui::DragValue::new(&mut vec.x).prefix("x: ").speed(0.01))
+            .changed()
+            | ui.add(egui::DragValue::new(&mut vec.y).prefix("y: ").speed(0.01))
+                .changed()
+            | ui.add(egui::DragValue::new(&mut vec.z).prefix("z: ").speed(0.01))
+                .changed()
+            | ui.add(egui::DragValue::new(&mut vec.w).prefix("w: ").speed(0.01))
+                .changed()
+    })
+    .inner
+}
+
+/// Like `edit_vec4` but locked axes can't be dragged and edits are snapped to the grid
+pub(crate) fn edit_position(
+    ui: &mut egui::Ui,
+    label: impl Into<egui::WidgetText>,
+    position: &mut cgmath::Vector4<f32>,
+    snapping: &Snapping,
+) -> bool {
+    let previous = *position;
+    let mut edited = *position;
+    let changed = ui
+        .horizontal(|ui| {
+            ui.label(label);
+            let mut changed = false;
+            for (axis, prefix) in ["x: ", "y: ", "z: ", "w: "].into_iter().enumerate() {
+                changed |= ui
+                    .add_enabled(
+                        !snapping.locked[axis],
+                        egui::DragValue::new(&mut edited[axis])
+                            .prefix(prefix)
+                            .speed(0.01),
+                    )
+                    .changed();
+            }
+            changed
+        })
+        .inner;
+    if changed {
+        *position = snapping.apply(previous, edited);
+    }
+    changed
+}
+
+/// Rotates `direction` by a chosen angle in a chosen plane each time "Rotate" is
+/// clicked, returns whether it changed
+pub(crate) fn edit_orientation(
+    ui: &mut egui::Ui,
+    direction: &mut cgmath::Vector4<f32>,
+    default: cgmath::Vector4<f32>,
+) -> bool {
+    let id = ui.id().with("Orientation");
+    let (mut plane_index, mut angle) = ui
+        .data_mut(|data| *data.get_temp_mut_or_insert_with(id, || (0usize, 15.0f32.to_radians())));
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label("Rotate: ");
+        egui::ComboBox::from_id_source(id)
+            .selected_text(ROTATION_PLANES[plane_index].0)
+            .show_ui(ui, |ui| {
+                for (i, (name, _)) in ROTATION_PLANES.iter().enumerate() {
+                    ui.selectable_value(&mut plane_index, i, *name);
+                }
+            });
+        ui.drag_angle(&mut angle);
+        if ui.button("Rotate").clicked() {
+            *direction = Rotor4::from_angle_plane(angle, ROTATION_PLANES[plane_index].1)
+                .rotate_vec(*direction)
+                .normalize();
+            changed = true;
+        }
+        if ui.button("Reset Orientation").clicked() {
+            *direction = default;
+            changed = true;
+        }
+    });
+    ui.data_mut(|data| data.insert_temp(id, (plane_index, angle)));
+    changed
+}
+
+#[inline(always)]
+pub(crate) fn edit_bivector4(
+    ui: &mut egui::Ui,
+    label: impl Into<egui::WidgetText>,
+    bv: &mut BiVector4,
+) -> bool {
+    let first = ui.horizontal(|ui| {
+        ui.label(label);
+        ui.add(egui::DragValue::new(&mut bv.xy).prefix("xy: ").speed(0.01))
+            .changed()
+            | ui.add(egui::DragValue::new(&mut bv.xz).prefix("xz: ").speed(0.01))
+                .changed()
+            | ui.add(egui::DragValue::new(&mut bv.xw).prefix("xw: ").speed(0.01))
+                .changed()
+    });
+    let second = ui.horizontal(|ui| {
+        ui.add(egui::DragValue::new(&mut bv.yz).prefix("yz: ").speed(0.01))
+            .changed()
+            | ui.add(egui::DragValue::new(&mut bv.yw).prefix("yw: ").speed(0.01))
+                .changed()
+            | ui.add(egui::DragValue::new(&mut bv.zw).prefix("zw: ").speed(0.01))
+                .changed()
+    });
+    first.inner | second.inner
+}
+
+#[inline(always)]
+pub(crate) fn edit_material(
+    ui: &mut egui::Ui,
+    label: impl Into<egui::WidgetText>,
+    material_id: &mut u32,
+    material_ids: &[MaterialId],
+    material_names: &[String],
+) -> bool {
+    let previous = *material_id;
+    ui.horizontal(|ui| {
+        ui.label(label);
+        egui::ComboBox::from_label("")
+            .selected_text(
+                material_ids
+                    .iter()
+                    .position(|&id| id == MaterialId(*material_id))
+                    .map_or("Invalid", |index| material_names[index].as_str()),
+            )
+            .show_ui(ui, |ui| {
+                for (id, material_name) in material_ids.iter().zip(material_names) {
+                    ui.selectable_value(material_id, id.0, material_name.as_str());
+                }
+            });
+    });
+    *material_id != previous
+}
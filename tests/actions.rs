@@ -0,0 +1,36 @@
+#![cfg(feature = "editor")]
+
+use four_dimentions::{fuzzy_score, Action};
+
+#[test]
+fn fuzzy_matches_need_the_characters_in_order() {
+    assert!(fuzzy_score("ahs", "Add Hyper Sphere").is_some());
+    assert!(fuzzy_score("ADD sphere", "Add Hyper Sphere").is_some());
+    assert!(fuzzy_score("sha", "Add Hyper Sphere").is_none());
+    assert_eq!(fuzzy_score("", "Anything"), Some(0));
+}
+
+#[test]
+fn word_starts_and_runs_score_higher() {
+    let score = |query| fuzzy_score(query, "Toggle Minimap").unwrap();
+    assert!(score("tm") > score("ti"));
+    assert!(score("mini") > score("mnmp"));
+}
+
+#[test]
+fn actions_have_unique_names_and_shortcuts() {
+    let actions = Action::all();
+    for (i, action) in actions.iter().enumerate() {
+        for other in &actions[i + 1..] {
+            assert_ne!(action.name(), other.name());
+            if let (Some(a), Some(b)) = (action.shortcut(), other.shortcut()) {
+                assert!(
+                    a.key != b.key || a.modifiers != b.modifiers,
+                    "{} and {} have the same shortcut",
+                    action.name(),
+                    other.name()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! The GPU tests pass without doing anything when there is no GPU adapter or it can't run compute shaders.
+
+mod common;
+
+use common::*;
+use four_dimentions::{ExposureAnalysis, Histogram, RenderPath, Renderer};
+
+/// Renders `scene` and analyses it, returning the pixels, the histogram and the false color pixels
+fn analyse(scene: &Scene) -> Option<(Vec<u8>, Histogram, Vec<u8>)> {
+    let Some((device, queue)) = request_device() else {
+        eprintln!("skipping, no GPU adapter is available");
+        return None;
+    };
+    if !ExposureAnalysis::is_supported(&device) {
+        eprintln!("skipping, the adapter doesn't support compute shaders");
+        return None;
+    }
+
+    let mut renderer = Renderer::new(&device, &queue, RenderPath::for_device(&device));
+    renderer.resize(&device, &queue, WIDTH, HEIGHT);
+    renderer
+        .upload_objects(&device, &queue, &scene.hyper_spheres, &scene.hyper_planes)
+        .unwrap();
+    renderer
+        .upload_materials(&device, &queue, &scene.materials)
+        .unwrap();
+    renderer
+        .upload_environment(&queue, &scene.environment.to_gpu())
+        .unwrap();
+    renderer
+        .upload_camera(&queue, &scene.camera.to_gpu(0))
+        .unwrap();
+
+    let mut exposure_analysis = ExposureAnalysis::new(&device, renderer.texture_view(), 1, 1);
+    assert!(exposure_analysis.set_input(&device, renderer.texture_view(), WIDTH, HEIGHT));
+    let mut encoder = device.create_command_encoder(&Default::default());
+    renderer.render(&device, &mut encoder, scene.camera.render_options());
+    assert!(exposure_analysis.record_histogram(&mut encoder));
+    exposure_analysis
+        .record_false_color(&queue, &mut encoder, scene.camera.color_management)
+        .unwrap();
+    queue.submit([encoder.finish()]);
+    exposure_analysis.start_readback();
+    // a second histogram can't start until the first one has been read back
+    assert!(!exposure_analysis
+        .record_histogram(&mut device.create_command_encoder(&Default::default())));
+    device.poll(wgpu::Maintain::Wait);
+    exposure_analysis.poll_readback().unwrap();
+
+    Some((
+        renderer.read_pixels(&device, &queue).unwrap(),
+        exposure_analysis.histogram().unwrap().clone(),
+        read_texture(&device, &queue, exposure_analysis.false_color_texture()),
+    ))
+}
+
+#[test]
+fn histogram_matches_the_pixels() {
+    let Some((pixels, histogram, _)) = analyse(&default_scene()) else {
+        return;
+    };
+    assert_eq!(histogram.bins.len(), Histogram::BIN_COUNT);
+    assert_eq!(histogram.pixel_count() as usize, WIDTH * HEIGHT);
+
+    let mut expected = vec![0u32; Histogram::BIN_COUNT];
+    for pixel in pixels.chunks_exact(4) {
+        let [r, g, b] = [0, 1, 2].map(|i| pixel[i] as f32 / 255.0);
+        let luma = r * 0.2126 + g * 0.7152 + b * 0.0722;
+        expected[((luma * Histogram::BIN_COUNT as f32) as usize).min(Histogram::BIN_COUNT - 1)] +=
+            1;
+    }
+    // luma right on a bin edge can round either way
+    let misplaced: u32 = expected
+        .iter()
+        .zip(&histogram.bins)
+        .map(|(&a, &b)| a.abs_diff(b))
+        .sum();
+    assert!(
+        misplaced as usize <= WIDTH * HEIGHT / 100,
+        "{misplaced} pixels are in other bins"
+    );
+}
+
+#[test]
+fn overexposure_is_clipped_and_shown_in_red() {
+    let mut scene = default_scene();
+    scene.materials[0].emissive_color = cgmath::vec3(1.0, 1.0, 1.0);
+    scene.materials[0].emission_strength = 10.0;
+    let Some((pixels, histogram, false_color)) = analyse(&scene) else {
+        return;
+    };
+
+    let clipped = pixels
+        .chunks_exact(4)
+        .map(|pixel| pixel[..3].contains(&255))
+        .collect::<Vec<_>>();
+    assert!(histogram.clipped > 0);
+    assert_eq!(
+        histogram.clipped as usize,
+        clipped.iter().filter(|&&clipped| clipped).count()
+    );
+    for (&clipped, pixel) in clipped.iter().zip(false_color.chunks_exact(4)) {
+        assert_eq!(clipped, pixel[..3] == [255, 0, 0]);
+    }
+}
@@ -0,0 +1,79 @@
+use cgmath::InnerSpace;
+use four_dimentions::{AnimatedValue, Animation, Animator, BiVector4, ObjectRef, Scene};
+
+#[test]
+fn expressions_move_objects_over_time() {
+    let mut scene = Scene::default();
+    let mut animation = Animation::new(&scene, ObjectRef::HyperSphere(0));
+    animation.position[0] = AnimatedValue::new("2 * t");
+    let mut animator = Animator {
+        animations: vec![animation],
+        playing: true,
+        ..Default::default()
+    };
+
+    animator.update(&mut scene, 0.5).unwrap();
+    let center = scene.hyper_sphere(0).unwrap().center;
+    assert!((center.x - 1.0).abs() < 1e-5, "{center:?}");
+    // empty expressions leave the other coordinates alone
+    assert_eq!(center.y, 1.0);
+    assert!(scene.changes.hyper_spheres);
+}
+
+#[test]
+fn hyper_planes_rotate_from_their_normal() {
+    let mut scene = Scene::default();
+    let mut animation = Animation::new(&scene, ObjectRef::HyperPlane(0));
+    animation.angle = AnimatedValue::new("t");
+    animation.plane = BiVector4::YZ;
+    animation
+        .apply(&mut scene, std::f32::consts::FRAC_PI_2)
+        .unwrap();
+    let normal = scene.hyper_plane(0).unwrap().normal;
+    assert!((normal.magnitude() - 1.0).abs() < 1e-5);
+    assert!(normal.y.abs() < 1e-5, "{normal:?}");
+    assert!((normal.z.abs() - 1.0).abs() < 1e-5, "{normal:?}");
+}
+
+#[test]
+fn errors_stop_playing() {
+    let mut scene = Scene::default();
+    let mut animation = Animation::new(&scene, ObjectRef::HyperSphere(0));
+    animation.position[1] = AnimatedValue::new("sin(");
+    assert!(animation.position[1].error().is_some());
+    let mut animator = Animator {
+        animations: vec![animation],
+        playing: true,
+        ..Default::default()
+    };
+    assert!(animator.update(&mut scene, 0.1).is_err());
+    assert!(!animator.playing);
+}
+
+#[test]
+fn removing_objects_keeps_animations_on_the_same_objects() {
+    let scene = Scene::default();
+    let mut animator = Animator::default();
+    for object in [
+        ObjectRef::HyperSphere(0),
+        ObjectRef::HyperSphere(2),
+        ObjectRef::HyperSphere(3),
+        ObjectRef::HyperPlane(1),
+    ] {
+        animator.animations.push(Animation::new(&scene, object));
+    }
+    animator.remove_objects(&[ObjectRef::HyperSphere(0), ObjectRef::HyperSphere(1)]);
+    let objects = animator
+        .animations
+        .iter()
+        .map(|a| a.object)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        objects,
+        [
+            ObjectRef::HyperSphere(0),
+            ObjectRef::HyperSphere(1),
+            ObjectRef::HyperPlane(1),
+        ]
+    );
+}
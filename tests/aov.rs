@@ -0,0 +1,96 @@
+mod common;
+
+use cgmath::InnerSpace;
+use four_dimentions::{Aovs, RenderPath, Renderer};
+
+#[test]
+fn exr_files_have_a_channel_for_every_layer() {
+    let (width, height) = (3, 2);
+    let layers = (0..width * height * Aovs::LAYER_COUNT)
+        .map(|i| [i as f32; 4])
+        .collect();
+    let exr = Aovs::from_layers(width, height, layers).to_exr();
+    assert_eq!(exr[..4], [0x76, 0x2f, 0x31, 0x01]);
+    for name in [
+        "R",
+        "G",
+        "B",
+        "albedo.R",
+        "normal_xyz.B",
+        "normal_w.R",
+        "direct.G",
+        "indirect.B",
+        "depth.Z",
+        "w_depth.Z",
+    ] {
+        let name = format!("{name}\0");
+        assert!(
+            exr.windows(name.len())
+                .any(|window| window == name.as_bytes()),
+            "{name:?} is missing"
+        );
+    }
+
+    // 20 float channels per scanline, after the y and size of each block, and the offsets of
+    // each block are just before the first one
+    let block_size = 8 + 20 * width * 4;
+    let last_offset = u64::from_le_bytes(
+        exr[exr.len() - height * block_size - 8..][..8]
+            .try_into()
+            .unwrap(),
+    );
+    assert_eq!(last_offset as usize, exr.len() - block_size);
+}
+
+#[test]
+fn aovs_describe_the_first_hit() {
+    let Some((device, queue)) = common::request_device() else {
+        eprintln!("skipping, no GPU adapter is available");
+        return;
+    };
+    if RenderPath::for_device(&device) != RenderPath::Compute {
+        eprintln!("skipping, AOVs need compute shaders");
+        return;
+    }
+    let mut scene = common::default_scene();
+    scene.camera.aovs = true;
+
+    let mut renderer = Renderer::new(&device, &queue, RenderPath::Compute);
+    renderer.resize(&device, &queue, common::WIDTH, common::HEIGHT);
+    renderer
+        .upload_objects(&device, &queue, &scene.hyper_spheres, &scene.hyper_planes)
+        .unwrap();
+    renderer
+        .upload_materials(&device, &queue, &scene.materials)
+        .unwrap();
+    assert!(renderer.read_aovs(&device, &queue).unwrap().is_none());
+    renderer
+        .upload_camera(&queue, &scene.camera.to_gpu(0))
+        .unwrap();
+    let mut encoder = device.create_command_encoder(&Default::default());
+    renderer.render(&device, &mut encoder, scene.camera.render_options());
+    queue.submit([encoder.finish()]);
+    let aovs = renderer.read_aovs(&device, &queue).unwrap().unwrap();
+
+    let mut hits = [0; 2];
+    for y in 0..common::HEIGHT {
+        for x in 0..common::WIDTH {
+            let albedo = aovs.albedo(x, y);
+            if albedo == cgmath::vec3(0.0, 0.0, 0.0) {
+                assert_eq!(aovs.depth(x, y), scene.camera.max_distance);
+                continue;
+            }
+            for (hits, material) in hits.iter_mut().zip(&scene.materials) {
+                // every sample hit the same material
+                if (albedo - material.base_color).magnitude() < 1e-4 {
+                    *hits += 1;
+                    assert!(aovs.normal(x, y).magnitude() > 0.9);
+                    assert!(aovs.depth(x, y) < scene.camera.max_distance);
+                }
+            }
+            let beauty = aovs.beauty(x, y);
+            assert!(beauty.x >= 0.0 && beauty.y >= 0.0 && beauty.z >= 0.0);
+        }
+    }
+    assert!(hits.iter().all(|&hits| hits > 0), "{hits:?}");
+}
@@ -0,0 +1,133 @@
+use cgmath::InnerSpace;
+use four_dimentions::{
+    ArrayKind, ArrayModifier, BiVector4, GpuHyperPlane, GpuHyperSphere, ObjectId, ObjectRef, Scene,
+};
+
+fn scene_with_sphere(center: cgmath::Vector4<f32>) -> (Scene, ObjectId) {
+    let mut scene = Scene::empty();
+    let material = scene.add_material("Material", Default::default());
+    let sphere = scene.add_hyper_sphere(
+        "Ball",
+        GpuHyperSphere {
+            center,
+            velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            radius: 0.5,
+            material: material.0,
+            inner_radius: 0.0,
+        },
+    );
+    (scene, sphere)
+}
+
+fn centers(scene: &Scene) -> Vec<cgmath::Vector4<f32>> {
+    scene.hyper_spheres().map(|sphere| sphere.center).collect()
+}
+
+#[test]
+fn linear_arrays_follow_their_source() {
+    let (mut scene, sphere) = scene_with_sphere(cgmath::vec4(0.0, 1.0, 0.0, 0.0));
+    let mut modifier = ArrayModifier::new(sphere);
+    modifier.settings.count = 3;
+    modifier.settings.kind = ArrayKind::Linear {
+        offset: cgmath::vec4(0.0, 0.0, 0.0, 1.5),
+    };
+    assert!(modifier.update(&mut scene).is_empty());
+    assert_eq!(
+        centers(&scene),
+        [0.0, 1.5, 3.0, 4.5].map(|w| cgmath::vec4(0.0, 1.0, 0.0, w))
+    );
+    assert_eq!(scene.objects[2].name, "Ball 2");
+    let clones = modifier.clones().to_vec();
+
+    scene.hyper_sphere_mut(0).unwrap().center.x = 2.0;
+    scene.hyper_sphere_mut(0).unwrap().radius = 0.25;
+    modifier.update(&mut scene);
+    assert_eq!(modifier.clones(), clones);
+    assert!(scene.hyper_spheres().all(|sphere| sphere.center.x == 2.0));
+    assert!(scene.hyper_spheres().all(|sphere| sphere.radius == 0.25));
+
+    modifier.settings.count = 1;
+    let surplus = modifier.update(&mut scene);
+    assert_eq!(
+        surplus,
+        [ObjectRef::HyperSphere(2), ObjectRef::HyperSphere(3)]
+    );
+    scene.remove_objects(&surplus);
+    assert_eq!(modifier.clones(), &clones[..1]);
+
+    scene.remove_objects(&[ObjectRef::HyperSphere(0)]);
+    assert_eq!(modifier.update(&mut scene), [ObjectRef::HyperSphere(0)]);
+    assert!(modifier.clones().is_empty());
+}
+
+#[test]
+fn removed_clones_are_regenerated() {
+    let (mut scene, sphere) = scene_with_sphere(cgmath::vec4(0.0, 1.0, 0.0, 0.0));
+    let mut modifier = ArrayModifier::new(sphere);
+    modifier.update(&mut scene);
+    scene.remove_objects(&[ObjectRef::HyperSphere(1)]);
+    modifier.update(&mut scene);
+    assert_eq!(scene.hyper_sphere_count(), 4);
+    assert_eq!(modifier.clones().len(), 3);
+}
+
+#[test]
+fn radial_arrays_circle_through_w() {
+    let (mut scene, sphere) = scene_with_sphere(cgmath::vec4(2.0, 1.0, 0.0, 0.0));
+    let mut modifier = ArrayModifier::new(sphere);
+    modifier.settings.count = 3;
+    modifier.settings.kind = ArrayKind::Radial {
+        center: cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+        plane: BiVector4::XW,
+        angle: std::f32::consts::TAU,
+    };
+    modifier.update(&mut scene);
+
+    let centers = centers(&scene);
+    assert_eq!(centers.len(), 4);
+    for center in &centers {
+        assert!((center.y - 1.0).abs() < 1e-5 && center.z.abs() < 1e-5);
+        assert!((cgmath::vec2(center.x, center.w).magnitude() - 2.0).abs() < 1e-5);
+    }
+    // a quarter turn apart, so the opposite copy is on the other side of the center
+    assert!((centers[2] - cgmath::vec4(-2.0, 1.0, 0.0, 0.0)).magnitude() < 1e-5);
+    assert!(centers[1].w.abs() > 1.99 && centers[3].w.abs() > 1.99);
+    assert!((centers[1].w + centers[3].w).abs() < 1e-5);
+}
+
+#[test]
+fn copies_can_turn_further_each_time() {
+    let mut scene = Scene::empty();
+    let material = scene.add_material("Material", Default::default());
+    let plane = scene.add_hyper_plane(
+        "Panel",
+        GpuHyperPlane {
+            point: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            normal: cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+            velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            angular_velocity: BiVector4::ZERO,
+            material: material.0,
+            extents: cgmath::vec3(1.0, 1.0, 1.0),
+            tangent: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            bitangent: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        },
+    );
+    let mut modifier = ArrayModifier::new(plane);
+    modifier.settings.count = 2;
+    modifier.settings.rotation = std::f32::consts::FRAC_PI_2;
+    modifier.settings.rotation_plane = BiVector4::YW;
+    modifier.update(&mut scene);
+
+    let normals = scene
+        .hyper_planes()
+        .map(|plane| plane.normal)
+        .collect::<Vec<_>>();
+    assert!(normals[1].y.abs() < 1e-5 && (normals[1].w.abs() - 1.0).abs() < 1e-5);
+    assert!((normals[2] + normals[0]).magnitude() < 1e-5);
+    let points = scene
+        .hyper_planes()
+        .map(|plane| plane.point)
+        .collect::<Vec<_>>();
+    assert_eq!(points[1], cgmath::vec4(2.0, 0.0, 0.0, 0.0));
+    assert_eq!(points[2], cgmath::vec4(4.0, 0.0, 0.0, 0.0));
+}
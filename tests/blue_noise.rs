@@ -0,0 +1,8 @@
+use four_dimentions::{blue_noise, generate_blue_noise, BLUE_NOISE_SIZE};
+
+#[test]
+fn blue_noise_is_generated_once_and_shared() {
+    let first = blue_noise();
+    assert!(std::ptr::eq(first, blue_noise()));
+    assert_eq!(first, generate_blue_noise(BLUE_NOISE_SIZE));
+}
@@ -0,0 +1,348 @@
+mod common;
+
+use four_dimentions::{
+    Aabb4, Bvh, BvhBuilder, BvhMode, BvhUpdate, GpuHyperSphere, ObjectFormat, Primitive,
+    RenderPath, Renderer, MAX_BVH_LEAF_SIZE, MAX_DYNAMIC_HYPER_SPHERES,
+};
+
+/// A small generator so the scenes are the same every run
+struct Random(u32);
+
+impl Random {
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32
+    }
+
+    fn vec4(&mut self, scale: f32) -> cgmath::Vector4<f32> {
+        cgmath::vec4(self.next(), self.next(), self.next(), self.next()) * scale
+    }
+}
+
+/// Clumps of hyper spheres, every seventh one moves
+fn clustered_hyper_spheres(count: usize) -> Vec<GpuHyperSphere> {
+    let mut random = Random(0x1234_5678);
+    let clusters = (0..5).map(|_| random.vec4(60.0)).collect::<Vec<_>>();
+    (0..count)
+        .map(|i| GpuHyperSphere {
+            center: clusters[i % clusters.len()] + random.vec4(4.0),
+            velocity: if i % 7 == 0 {
+                cgmath::vec4(0.0, 1.0, 0.0, 0.0)
+            } else {
+                cgmath::vec4(0.0, 0.0, 0.0, 0.0)
+            },
+            radius: 0.2 + random.next() * 0.5,
+            material: 0,
+            inner_radius: 0.0,
+        })
+        .collect()
+}
+
+fn bounds(node: &four_dimentions::GpuBvhNode) -> Aabb4 {
+    Aabb4 {
+        min: node.min,
+        max: node.max,
+    }
+}
+
+/// Every hyper sphere is in exactly one leaf, inside the bounds of it and its parents
+fn assert_valid(bvh: &Bvh, hyper_spheres: &[GpuHyperSphere]) {
+    let builder = bvh.stats.builder;
+    assert_eq!(bvh.nodes[0].skip as usize, bvh.tree_nodes);
+    assert_eq!(bvh.stats.nodes, bvh.tree_nodes);
+    assert_eq!(bvh.stats.leaves * 2 - 1, bvh.tree_nodes);
+
+    let mut seen = vec![0; hyper_spheres.len()];
+    for (i, node) in bvh.nodes.iter().enumerate() {
+        assert!(node.count as usize <= MAX_BVH_LEAF_SIZE);
+        for &index in &[
+            node.hyper_spheres.x,
+            node.hyper_spheres.y,
+            node.hyper_spheres.z,
+            node.hyper_spheres.w,
+        ][..node.count as usize]
+        {
+            seen[index as usize] += 1;
+            let moves = hyper_spheres[index as usize].velocity.y != 0.0;
+            assert!(!moves || i >= bvh.tree_nodes);
+            if i < bvh.tree_nodes {
+                let hyper_sphere = &hyper_spheres[index as usize];
+                assert!(bounds(node).contains(Aabb4::around_hyper_sphere(hyper_sphere)));
+            }
+        }
+        if i < bvh.tree_nodes && node.count == 0 {
+            let first = &bvh.nodes[i + 1];
+            let second = &bvh.nodes[first.skip as usize];
+            assert!(bounds(node).contains(bounds(first)));
+            assert!(bounds(node).contains(bounds(second)));
+            assert_eq!(second.skip, node.skip);
+        }
+    }
+    assert!(seen.iter().all(|&count| count == 1), "{builder:?}");
+    let dynamic = bvh.nodes[bvh.tree_nodes..]
+        .iter()
+        .map(|node| node.count as usize)
+        .sum::<usize>();
+    assert_eq!(dynamic, bvh.stats.dynamic);
+}
+
+/// Every hyper sphere a ray could hit is one of the BVH's candidates for it
+fn assert_rays_find_hits(bvh: &Bvh, hyper_spheres: &[GpuHyperSphere], random: &mut Random) {
+    for _ in 0..100 {
+        let origin = random.vec4(60.0);
+        let target = hyper_spheres[(random.next() * 299.0) as usize].center;
+        let direction = target - origin;
+        let candidates = bvh.candidates(origin, direction);
+        for (i, hyper_sphere) in hyper_spheres.iter().enumerate() {
+            if hyper_sphere.intersect(origin, direction).is_some() {
+                assert!(candidates.contains(&(i as u32)), "{i}");
+            }
+        }
+    }
+}
+
+#[test]
+fn every_hyper_sphere_is_in_one_leaf_inside_its_parents() {
+    let hyper_spheres = clustered_hyper_spheres(500);
+    for builder in [BvhBuilder::Median, BvhBuilder::BinnedSah] {
+        assert_valid(&Bvh::build(&hyper_spheres, builder), &hyper_spheres);
+    }
+}
+
+#[test]
+fn animated_hyper_spheres_refit_the_bvh() {
+    let mut hyper_spheres = clustered_hyper_spheres(500);
+    let mut random = Random(99);
+    for builder in [BvhBuilder::Median, BvhBuilder::BinnedSah] {
+        let mut bvh = Bvh::build(&hyper_spheres, builder);
+        assert_eq!(bvh.update(&hyper_spheres), BvhUpdate::Unchanged);
+        let skips = |bvh: &Bvh| bvh.nodes.iter().map(|node| node.skip).collect::<Vec<_>>();
+        let shape = skips(&bvh);
+
+        // a small step of an animation keeps the tree
+        for hyper_sphere in &mut hyper_spheres {
+            hyper_sphere.center += random.vec4(0.5);
+        }
+        assert_eq!(bvh.update(&hyper_spheres), BvhUpdate::Refit);
+        assert_eq!(bvh.stats.refits, 1);
+        assert_valid(&bvh, &hyper_spheres);
+        assert_eq!(skips(&bvh), shape);
+        let refit = bvh.nodes.clone();
+
+        // scattering them makes the boxes overlap too much
+        for hyper_sphere in &mut hyper_spheres {
+            hyper_sphere.center = random.vec4(60.0);
+        }
+        assert_eq!(bvh.update(&hyper_spheres), BvhUpdate::Rebuilt);
+        assert_eq!(bvh.stats.refits, 0);
+        assert_ne!(bvh.nodes, refit);
+        assert_valid(&bvh, &hyper_spheres);
+
+        // a hyper sphere that starts moving leaves the tree
+        assert_eq!(bvh.stats.dynamic, hyper_spheres.len().div_ceil(7));
+        hyper_spheres[1].velocity.y = 1.0;
+        assert_eq!(bvh.update(&hyper_spheres), BvhUpdate::Rebuilt);
+        assert_valid(&bvh, &hyper_spheres);
+        hyper_spheres = clustered_hyper_spheres(500);
+    }
+}
+
+#[test]
+fn edited_hyper_spheres_become_dynamic() {
+    let mut hyper_spheres = clustered_hyper_spheres(300);
+    let moving = hyper_spheres.len().div_ceil(7);
+    let mut random = Random(5);
+    let mut bvh = Bvh::build(&hyper_spheres, BvhBuilder::BinnedSah);
+    assert_eq!(bvh.stats.dynamic, moving);
+
+    // dragging one far away makes the refit tree too costly, so it is left out of the rebuild
+    hyper_spheres[3].center.x += 200.0;
+    assert_eq!(bvh.update(&hyper_spheres), BvhUpdate::Rebuilt);
+    assert_eq!(bvh.stats.dynamic, moving + 1);
+    assert_valid(&bvh, &hyper_spheres);
+
+    // after which moving it again doesn't touch the tree
+    let nodes = bvh.nodes.clone();
+    for _ in 0..10 {
+        hyper_spheres[3].center = random.vec4(60.0);
+        assert_eq!(bvh.update(&hyper_spheres), BvhUpdate::Unchanged);
+        assert_eq!(bvh.nodes, nodes);
+        assert_rays_find_hits(&bvh, &hyper_spheres, &mut random);
+    }
+
+    // too many edits at once are refit instead
+    for hyper_sphere in &mut hyper_spheres[..MAX_DYNAMIC_HYPER_SPHERES * 2] {
+        hyper_sphere.center.x += 200.0;
+    }
+    assert_eq!(bvh.update(&hyper_spheres), BvhUpdate::Rebuilt);
+    assert_eq!(bvh.stats.dynamic, moving);
+    assert_valid(&bvh, &hyper_spheres);
+    assert_rays_find_hits(&bvh, &hyper_spheres, &mut random);
+}
+
+#[test]
+fn binned_sah_is_cheaper_than_the_median() {
+    let hyper_spheres = clustered_hyper_spheres(2000);
+    let median = Bvh::build(&hyper_spheres, BvhBuilder::Median).stats;
+    let sah = Bvh::build(&hyper_spheres, BvhBuilder::BinnedSah).stats;
+    assert!(sah.sah_cost < median.sah_cost, "{sah:?} {median:?}");
+    assert!(sah.sah_cost > 0.0);
+}
+
+#[test]
+fn rays_find_every_hyper_sphere_they_hit() {
+    let hyper_spheres = clustered_hyper_spheres(300);
+    let mut random = Random(42);
+    for builder in [BvhBuilder::Median, BvhBuilder::BinnedSah] {
+        let bvh = Bvh::build(&hyper_spheres, builder);
+        let mut checked = 0;
+        for _ in 0..200 {
+            let origin = random.vec4(60.0);
+            let target = hyper_spheres[(random.next() * 299.0) as usize].center;
+            let direction = target - origin;
+            let candidates = bvh.candidates(origin, direction);
+            checked += candidates.len();
+            for (i, hyper_sphere) in hyper_spheres.iter().enumerate() {
+                if hyper_sphere.intersect(origin, direction).is_some() {
+                    assert!(candidates.contains(&(i as u32)), "{builder:?} {i}");
+                }
+            }
+        }
+        // most of them are skipped
+        assert!(checked < 200 * hyper_spheres.len() / 4, "{checked}");
+    }
+}
+
+#[test]
+fn auto_builds_a_bvh_for_large_scenes() {
+    assert_eq!(
+        BvhMode::Auto.builder(BvhMode::AUTO_BVH_HYPER_SPHERES - 1),
+        None
+    );
+    assert_eq!(
+        BvhMode::Auto.builder(BvhMode::AUTO_BVH_HYPER_SPHERES),
+        Some(BvhBuilder::Median)
+    );
+    assert_eq!(BvhMode::Off.builder(usize::MAX), None);
+    assert_eq!(BvhMode::BinnedSah.builder(0), Some(BvhBuilder::BinnedSah));
+
+    let empty = Bvh::build(&[], BvhBuilder::BinnedSah);
+    assert!(empty.nodes.is_empty());
+    assert_eq!(empty.stats.sah_cost, 0.0);
+}
+
+#[test]
+fn bvh_renders_like_checking_every_hyper_sphere() {
+    let Some((device, queue)) = common::request_device() else {
+        eprintln!("skipping, no GPU adapter is available");
+        return;
+    };
+    let mut scene = common::default_scene();
+    let mut random = Random(7);
+    for i in 0..40 {
+        scene.hyper_spheres.push(GpuHyperSphere {
+            center: cgmath::vec4(
+                random.next() * 8.0 - 4.0,
+                random.next() * 2.0,
+                random.next() * 8.0 - 2.0,
+                random.next() - 0.5,
+            ),
+            velocity: if i % 5 == 0 {
+                cgmath::vec4(0.1, 0.0, 0.0, 0.0)
+            } else {
+                cgmath::vec4(0.0, 0.0, 0.0, 0.0)
+            },
+            radius: 0.2 + random.next() * 0.3,
+            ..scene.hyper_spheres[0]
+        });
+    }
+    let render_path = RenderPath::for_device(&device);
+
+    let mut renderer = Renderer::new(&device, &queue, render_path);
+    renderer.set_bvh_mode(BvhMode::BinnedSah);
+    renderer
+        .upload_objects(&device, &queue, &scene.hyper_spheres, &scene.hyper_planes)
+        .unwrap();
+    // the fragment path has no room for the BVH's buffer
+    assert_eq!(
+        renderer.bvh_stats().is_some(),
+        render_path == RenderPath::Compute
+    );
+
+    // packed hyper spheres are intersected at their rounded centers
+    for object_format in [ObjectFormat::Full, ObjectFormat::Packed] {
+        scene.object_format = object_format;
+        scene.bvh_mode = BvhMode::Off;
+        let expected = common::render(&device, &queue, render_path, &scene);
+        for bvh_mode in [BvhMode::Median, BvhMode::BinnedSah] {
+            scene.bvh_mode = bvh_mode;
+            let actual = common::render(&device, &queue, render_path, &scene);
+            let (mean_difference, outlier_fraction) = common::image_difference(&expected, &actual);
+            assert!(
+                common::images_match(mean_difference, outlier_fraction),
+                "{object_format:?} {bvh_mode:?}: mean difference {mean_difference}, outliers {outlier_fraction}"
+            );
+        }
+    }
+}
+
+#[test]
+fn uploads_only_rebuild_the_bvh_when_they_have_to() {
+    let Some((device, queue)) = common::request_device() else {
+        eprintln!("skipping, no GPU adapter is available");
+        return;
+    };
+    let render_path = RenderPath::for_device(&device);
+    if render_path != RenderPath::Compute {
+        eprintln!("skipping, only the compute path has a BVH");
+        return;
+    }
+    let mut scene = common::default_scene();
+    scene.hyper_spheres = clustered_hyper_spheres(300);
+    scene.bvh_mode = BvhMode::Median;
+    let mut renderer = Renderer::new(&device, &queue, render_path);
+    renderer.resize(&device, &queue, common::WIDTH, common::HEIGHT);
+    renderer.set_bvh_mode(scene.bvh_mode);
+    renderer
+        .upload_objects(&device, &queue, &scene.hyper_spheres, &scene.hyper_planes)
+        .unwrap();
+    renderer
+        .upload_materials(&device, &queue, &scene.materials)
+        .unwrap();
+    let stats = renderer.bvh_stats().unwrap();
+
+    // only the plane is written again
+    scene.hyper_planes[0].point.y -= 1.0;
+    renderer
+        .upload_objects(&device, &queue, &scene.hyper_spheres, &scene.hyper_planes)
+        .unwrap();
+    assert!(renderer.object_upload_size() <= 256);
+    assert_eq!(renderer.bvh_stats(), Some(stats));
+
+    // moving one hyper sphere a little only refits the nodes above it
+    scene.hyper_spheres[1].center.x += 0.25;
+    renderer
+        .upload_objects(&device, &queue, &scene.hyper_spheres, &scene.hyper_planes)
+        .unwrap();
+    assert_eq!(renderer.bvh_stats().unwrap().refits, 1);
+    let full = stats.nodes * std::mem::size_of::<four_dimentions::GpuBvhNode>();
+    assert!(renderer.object_upload_size() * 4 < full, "{full}");
+    renderer
+        .upload_camera(&queue, &scene.camera.to_gpu(0))
+        .unwrap();
+    let mut encoder = device.create_command_encoder(&Default::default());
+    renderer.render(&device, &mut encoder, scene.camera.render_options());
+    queue.submit([encoder.finish()]);
+    assert_eq!(
+        renderer.read_pixels(&device, &queue).unwrap(),
+        common::render(&device, &queue, render_path, &scene)
+    );
+
+    renderer.set_bvh_mode(BvhMode::BinnedSah);
+    renderer
+        .upload_objects(&device, &queue, &scene.hyper_spheres, &scene.hyper_planes)
+        .unwrap();
+    assert_eq!(renderer.bvh_stats().unwrap().builder, BvhBuilder::BinnedSah);
+}
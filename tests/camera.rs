@@ -0,0 +1,121 @@
+use cgmath::InnerSpace;
+use four_dimentions::{
+    camera_ray, stereo_camera_ray, BiVector4, Camera, Orbit, Projection, Stereo, WRamp,
+};
+
+fn assert_close(a: cgmath::Vector4<f32>, b: cgmath::Vector4<f32>) {
+    assert!((a - b).magnitude() < 1e-4, "{a:?} != {b:?}");
+}
+
+#[test]
+fn orbit_keeps_the_distance_to_the_target() {
+    for plane in [BiVector4::ZX, BiVector4::XW, BiVector4::ZW] {
+        let mut orbit = Orbit {
+            target: cgmath::vec4(1.0, 0.0, 2.0, -1.0),
+            plane,
+            ..Default::default()
+        };
+        let position = cgmath::vec4(0.0, 1.0, -3.0, 0.5);
+        let distance = (position - orbit.target).magnitude();
+        for _ in 0..10 {
+            orbit.advance(0.37);
+            let orbited = orbit.apply_to_point(position);
+            assert!(((orbited - orbit.target).magnitude() - distance).abs() < 1e-4);
+        }
+    }
+}
+
+#[test]
+fn orbit_moves_the_view_with_the_camera() {
+    let target = cgmath::vec4(0.0, 1.0, 0.0, 0.0);
+    let camera = Camera {
+        orbit: Some(Orbit {
+            target,
+            plane: BiVector4::ZW,
+            angle: 90.0f32.to_radians(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    // the default camera looks straight at the target, so it still does a quarter turn later
+    let gpu_camera = camera.to_gpu(0);
+    assert_close(
+        (target - gpu_camera.position).normalize(),
+        gpu_camera.forward,
+    );
+    assert!(gpu_camera.position.w.abs() > 1.0);
+}
+
+#[test]
+fn w_ramp_blends_towards_ana_and_kata() {
+    let ramp = WRamp::default();
+    let assert_color = |a: cgmath::Vector3<f32>, b: cgmath::Vector3<f32>| {
+        assert!((a - b).magnitude() < 1e-6, "{a:?} != {b:?}");
+    };
+    assert_color(ramp.color(0.0), ramp.center);
+    assert_color(ramp.color(ramp.range), ramp.ana);
+    assert_color(ramp.color(-ramp.range * 3.0), ramp.kata);
+    assert_color(ramp.color(ramp.range * 0.5), (ramp.center + ramp.ana) * 0.5);
+}
+
+#[test]
+fn stereo_eyes_sit_either_side_of_the_camera() {
+    let mut camera = Camera {
+        stereo: Some(Stereo {
+            eye_separation: 0.2,
+            cross_eyed: false,
+        }),
+        ..Default::default()
+    };
+    let gpu = camera.to_gpu(0);
+    let (left, left_direction) = stereo_camera_ray(&gpu, cgmath::vec2(-0.5, 0.0), 2.0);
+    let (right, right_direction) = stereo_camera_ray(&gpu, cgmath::vec2(0.5, 0.0), 2.0);
+    assert_close(left, camera.position - gpu.right * 0.1);
+    assert_close(right, camera.position + gpu.right * 0.1);
+    // the middle of each half looks straight ahead
+    assert_close(left_direction, gpu.forward);
+    assert_close(right_direction, gpu.forward);
+
+    camera.stereo.as_mut().unwrap().cross_eyed = true;
+    let gpu = camera.to_gpu(0);
+    let (left, _) = stereo_camera_ray(&gpu, cgmath::vec2(-0.5, 0.0), 2.0);
+    assert_close(left, camera.position + gpu.right * 0.1);
+}
+
+#[test]
+fn lens_shift_moves_the_view_without_turning() {
+    let camera = Camera {
+        lens_shift: cgmath::vec2(0.0, 1.0),
+        aspect_override: Some(3.0),
+        ..Default::default()
+    };
+    let gpu = camera.to_gpu(0);
+    // the middle of the image looks where the top edge would without the shift
+    let (_, shifted) = camera_ray(&gpu, cgmath::vec2(0.0, 0.0), 1.0);
+    let theta = (camera.fov / 2.0).tan();
+    assert_close(shifted, (gpu.forward + gpu.up * theta).normalize());
+    // and the image's own aspect ratio is ignored
+    let (_, edge) = camera_ray(&gpu, cgmath::vec2(1.0, -1.0), 1.0);
+    assert_close(edge, (gpu.forward + gpu.right * (3.0 * theta)).normalize());
+}
+
+#[test]
+fn panoramic_projections_cover_every_direction() {
+    let camera = Camera::default().to_gpu(0);
+    let direction = |projection: Projection, x: f32, y: f32| {
+        projection.ray_direction(&camera, cgmath::vec2(x, y), 2.0)
+    };
+
+    let equirectangular = |x, y| direction(Projection::Equirectangular, x, y).unwrap();
+    assert_close(equirectangular(0.0, 0.0), camera.forward);
+    assert_close(equirectangular(-1.0, 0.0), -camera.forward);
+    assert_close(equirectangular(0.5, 0.0), camera.right);
+    assert_close(equirectangular(0.3, 1.0), camera.up);
+
+    // the default fisheye sees 90 degrees to the side at the edge of its circle
+    let fisheye = |x, y| direction(Projection::Fisheye, x, y);
+    assert_close(fisheye(0.0, 0.0).unwrap(), camera.forward);
+    assert_close(fisheye(0.5, 0.0).unwrap(), camera.right);
+    assert_close(fisheye(0.0, -1.0).unwrap(), -camera.up);
+    assert_eq!(fisheye(0.9, 0.0), None);
+}
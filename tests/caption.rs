@@ -0,0 +1,63 @@
+use four_dimentions::{burn_text, format_utc, Camera, Caption};
+use std::time::{Duration, UNIX_EPOCH};
+
+#[test]
+fn text_includes_the_enabled_parts() {
+    let mut camera = Camera::default();
+    camera.position.w = 1.5;
+    let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let caption = Caption {
+        title: "Tesseract".into(),
+        w_coordinate: true,
+        sample_count: true,
+        timestamp: true,
+    };
+    assert_eq!(
+        caption.text(&camera, 64, time),
+        "Tesseract  |  w = 1.500  |  64 samples  |  2023-11-14 22:13:20 UTC"
+    );
+
+    let caption = Caption {
+        title: String::new(),
+        w_coordinate: false,
+        sample_count: true,
+        timestamp: false,
+    };
+    assert_eq!(caption.text(&camera, 8, time), "8 samples");
+}
+
+#[test]
+fn timestamps_handle_leap_days() {
+    assert_eq!(format_utc(UNIX_EPOCH), "1970-01-01 00:00:00 UTC");
+    assert_eq!(
+        format_utc(UNIX_EPOCH + Duration::from_secs(951_782_400)),
+        "2000-02-29 00:00:00 UTC"
+    );
+    assert_eq!(
+        format_utc(UNIX_EPOCH + Duration::from_secs(951_868_799)),
+        "2000-02-29 23:59:59 UTC"
+    );
+}
+
+#[test]
+fn text_is_burned_into_the_bottom_left() {
+    let (width, height) = (64, 48);
+    let mut pixels = vec![128; width * height * 4];
+    burn_text(&mut pixels, width, height, "W = 1", 1);
+
+    let pixel = |x: usize, y: usize| &pixels[(y * width + x) * 4..][..4];
+    // far from the text nothing changes
+    assert_eq!(pixel(width - 1, 0), [128; 4]);
+    assert_eq!(pixel(0, 0), [128; 4]);
+    // the background is darkened, the alpha is left alone
+    assert_eq!(pixel(0, height - 1), [51, 51, 51, 128]);
+    // the top left of the W
+    assert_eq!(pixel(2, height - 9), [255, 255, 255, 128]);
+}
+
+#[test]
+fn text_that_doesnt_fit_is_cut_off() {
+    let (width, height) = (8, 4);
+    let mut pixels = vec![0; width * height * 4];
+    burn_text(&mut pixels, width, height, "A very long caption", 3);
+}
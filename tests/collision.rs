@@ -0,0 +1,67 @@
+use cgmath::InnerSpace;
+use four_dimentions::{move_with_collisions, sphere_cast, Scene};
+
+#[test]
+fn moving_into_the_sphere_stops_at_its_surface() {
+    let scene = Scene::default();
+    let start = cgmath::vec4(0.0, 1.0, -3.0, 0.0);
+    let (distance, normal) = sphere_cast(&scene, start, cgmath::vec4(0.0, 0.0, 1.0, 0.0), 0.5)
+        .expect("the sphere is straight ahead");
+    assert!((distance - 1.5).abs() < 1e-4);
+    assert!((normal - cgmath::vec4(0.0, 0.0, -1.0, 0.0)).magnitude() < 1e-4);
+
+    let end = move_with_collisions(&scene, start, cgmath::vec4(0.0, 0.0, 5.0, 0.0), 0.5);
+    assert!((end.z - -1.5).abs() < 1e-2, "{end:?}");
+    assert!((end - scene.hyper_sphere(0).unwrap().center).magnitude() >= 1.5 - 1e-4);
+}
+
+#[test]
+fn blocked_moves_slide_along_the_ground() {
+    let scene = Scene::default();
+    let start = cgmath::vec4(5.0, 0.5, 0.0, 0.0);
+    let end = move_with_collisions(&scene, start, cgmath::vec4(1.0, -2.0, 0.0, 1.0), 0.25);
+    assert!((end.y - 0.25).abs() < 1e-2, "{end:?}");
+    assert!(
+        (end.x - 6.0).abs() < 1e-2 && (end.w - 1.0).abs() < 1e-2,
+        "{end:?}"
+    );
+}
+
+#[test]
+fn finite_planes_can_be_walked_off() {
+    let mut scene = Scene::default();
+    let start = cgmath::vec4(5.0, 0.5, 0.0, 0.0);
+    let delta = cgmath::vec4(0.0, -2.0, 0.0, 0.0);
+    assert!(sphere_cast(&scene, start, delta.normalize(), 0.25).is_some());
+
+    scene.hyper_plane_mut(0).unwrap().extents = cgmath::vec3(2.0, 0.0, 0.0);
+    assert!(scene
+        .hyper_plane(0)
+        .unwrap()
+        .contains(cgmath::vec4(-1.5, 0.0, 9.0, 9.0), 0.0));
+    assert!(!scene.hyper_plane(0).unwrap().contains(start, 0.25));
+    let end = move_with_collisions(&scene, start, delta, 0.25);
+    assert!((end - (start + delta)).magnitude() < 1e-4, "{end:?}");
+}
+
+#[test]
+fn hollow_spheres_hold_things_inside() {
+    let mut scene = Scene::default();
+    let center = scene.hyper_sphere(0).unwrap().center;
+    scene.hyper_sphere_mut(0).unwrap().radius = 4.0;
+    scene.hyper_sphere_mut(0).unwrap().inner_radius = 3.0;
+    let end = move_with_collisions(&scene, center, cgmath::vec4(10.0, 0.0, 0.0, 0.0), 0.5);
+    assert!(((end - center).magnitude() - 2.5).abs() < 1e-2, "{end:?}");
+}
+
+#[test]
+fn overlapping_objects_can_be_left() {
+    let scene = Scene::default();
+    // inside the sphere, moving out of it
+    let start = cgmath::vec4(0.0, 1.0, -0.5, 0.0);
+    let end = move_with_collisions(&scene, start, cgmath::vec4(0.0, 0.0, -2.0, 0.0), 0.1);
+    assert!(
+        (end - cgmath::vec4(0.0, 1.0, -2.5, 0.0)).magnitude() < 1e-4,
+        "{end:?}"
+    );
+}
@@ -0,0 +1,203 @@
+// not every test uses every helper
+#![allow(dead_code)]
+
+use four_dimentions::{
+    device_limits, BiVector4, BvhMode, Camera, Environment, GpuHyperPlane, GpuHyperSphere,
+    GpuMaterial, GpuNodeInstruction, ObjectFormat, RenderPath, Renderer,
+};
+
+pub const WIDTH: usize = 64;
+pub const HEIGHT: usize = 48;
+
+/// The largest allowed average per-channel difference
+pub const MAX_MEAN_DIFFERENCE: f64 = 1.0;
+/// The largest fraction of channels allowed to differ by more than `OUTLIER_DIFFERENCE`,
+/// different GPUs don't produce bit-identical floats so a few pixels take different bounces
+pub const MAX_OUTLIER_FRACTION: f64 = 0.01;
+pub const OUTLIER_DIFFERENCE: u8 = 16;
+
+pub struct Scene {
+    pub camera: Camera,
+    /// How many renders to accumulate
+    pub frames: u32,
+    pub hyper_spheres: Vec<GpuHyperSphere>,
+    pub hyper_planes: Vec<GpuHyperPlane>,
+    pub materials: Vec<GpuMaterial>,
+    /// What the `node_program` of the materials point into
+    pub node_programs: Vec<GpuNodeInstruction>,
+    pub environment: Environment,
+    pub object_format: ObjectFormat,
+    pub bvh_mode: BvhMode,
+    pub wavefront: bool,
+}
+
+pub fn default_scene() -> Scene {
+    Scene {
+        camera: Camera {
+            sample_count: 64,
+            deterministic_seed: Some(1234),
+            ..Default::default()
+        },
+        frames: 1,
+        hyper_spheres: vec![GpuHyperSphere {
+            center: cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+            velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            radius: 1.0,
+            material: 0,
+            inner_radius: 0.0,
+        }],
+        hyper_planes: vec![GpuHyperPlane {
+            point: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            normal: cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+            velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            angular_velocity: BiVector4::ZERO,
+            material: 1,
+            extents: cgmath::vec3(0.0, 0.0, 0.0),
+            tangent: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            bitangent: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        }],
+        materials: vec![
+            GpuMaterial {
+                base_color: cgmath::vec3(0.8, 0.4, 0.1),
+                emissive_color: cgmath::vec3(0.0, 0.0, 0.0),
+                ..Default::default()
+            },
+            GpuMaterial {
+                base_color: cgmath::vec3(0.1, 0.8, 0.3),
+                emissive_color: cgmath::vec3(0.0, 0.0, 0.0),
+                ..Default::default()
+            },
+        ],
+        node_programs: vec![],
+        environment: Environment::default(),
+        object_format: ObjectFormat::Auto,
+        bvh_mode: BvhMode::Auto,
+        wavefront: false,
+    }
+}
+
+pub fn request_device() -> Option<(wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+    let adapter = pollster::block_on(instance.request_adapter(&Default::default()))?;
+    pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            limits: device_limits(&adapter),
+            ..Default::default()
+        },
+        None,
+    ))
+    .ok()
+}
+
+/// A device with no more than WebGL2 allows, so only the fragment path works on it
+pub fn request_webgl2_device() -> Option<(wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+    let adapter = pollster::block_on(instance.request_adapter(&Default::default()))?;
+    pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            limits: wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits()),
+            ..Default::default()
+        },
+        None,
+    ))
+    .ok()
+}
+
+pub fn render(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    render_path: RenderPath,
+    scene: &Scene,
+) -> Vec<u8> {
+    let mut renderer = Renderer::new(device, queue, render_path);
+    renderer.resize(device, queue, WIDTH, HEIGHT);
+    renderer.set_object_format(scene.object_format);
+    renderer.set_bvh_mode(scene.bvh_mode);
+    renderer.set_wavefront(scene.wavefront);
+    renderer
+        .upload_objects(device, queue, &scene.hyper_spheres, &scene.hyper_planes)
+        .unwrap();
+    renderer
+        .upload_materials(device, queue, &scene.materials)
+        .unwrap();
+    renderer
+        .upload_node_programs(device, queue, &scene.node_programs)
+        .unwrap();
+    renderer
+        .upload_environment(queue, &scene.environment.to_gpu())
+        .unwrap();
+
+    for frame_index in 0..scene.frames {
+        renderer
+            .upload_camera(queue, &scene.camera.to_gpu(frame_index))
+            .unwrap();
+        let mut encoder = device.create_command_encoder(&Default::default());
+        renderer.render(device, &mut encoder, scene.camera.render_options());
+        queue.submit([encoder.finish()]);
+    }
+
+    renderer.read_pixels(device, queue).unwrap()
+}
+
+/// Copies a `WIDTH` by `HEIGHT` RGBA8 texture back to the CPU as tightly packed rows
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Vec<u8> {
+    let bytes_per_row = (WIDTH * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize);
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: (bytes_per_row * HEIGHT) as _,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&Default::default());
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: std::num::NonZeroU32::new(bytes_per_row as _),
+                rows_per_image: None,
+            },
+        },
+        wgpu::Extent3d {
+            width: WIDTH as _,
+            height: HEIGHT as _,
+            depth_or_array_layers: 1,
+        },
+    );
+    queue.submit([encoder.finish()]);
+    let slice = buffer.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+    device.poll(wgpu::Maintain::Wait);
+    let data = slice.get_mapped_range();
+    data.chunks(bytes_per_row)
+        .flat_map(|row| &row[..WIDTH * 4])
+        .copied()
+        .collect()
+}
+
+/// The mean per-channel difference and the fraction of channels that differ by more than `OUTLIER_DIFFERENCE`
+pub fn image_difference(expected: &[u8], actual: &[u8]) -> (f64, f64) {
+    assert_eq!(expected.len(), actual.len());
+    let differences = expected
+        .iter()
+        .zip(actual)
+        .map(|(&a, &b)| a.abs_diff(b))
+        .collect::<Vec<_>>();
+    let mean_difference =
+        differences.iter().map(|&d| d as f64).sum::<f64>() / differences.len() as f64;
+    let outlier_fraction = differences
+        .iter()
+        .filter(|&&d| d > OUTLIER_DIFFERENCE)
+        .count() as f64
+        / differences.len() as f64;
+    (mean_difference, outlier_fraction)
+}
+
+pub fn images_match(mean_difference: f64, outlier_fraction: f64) -> bool {
+    mean_difference <= MAX_MEAN_DIFFERENCE && outlier_fraction <= MAX_OUTLIER_FRACTION
+}
@@ -0,0 +1,97 @@
+use four_dimentions::{
+    adapter_power_preference, backend_to_backends, Config, Theme, PRESENT_MODES,
+};
+
+#[test]
+fn render_defaults_round_trip() {
+    let config = Config {
+        resolution_scale: 0.5,
+        sample_count: 4,
+        bounce_count: 3,
+        movement_speed: 7.5,
+        theme: Theme::Light,
+        startup_scene: Some("scenes/start.script".into()),
+        ..Default::default()
+    };
+    let loaded = Config::parse(&config.to_toml());
+    assert_eq!(loaded.resolution_scale, 0.5);
+    assert_eq!(loaded.sample_count, 4);
+    assert_eq!(loaded.bounce_count, 3);
+    assert_eq!(loaded.movement_speed, 7.5);
+    assert_eq!(loaded.theme, Theme::Light);
+    assert_eq!(loaded.startup_scene, config.startup_scene);
+}
+
+#[test]
+fn invalid_values_fall_back_to_the_defaults() {
+    let config = Config::parse(
+        r#"
+        resolution_scale = 100.0
+        sample_count = 0
+        bounce_count = -1
+        movement_speed = -2.0
+        theme = "purple"
+        startup_scene = ""
+        "#,
+    );
+    let default = Config::default();
+    assert_eq!(config.resolution_scale, default.resolution_scale);
+    assert_eq!(config.sample_count, default.sample_count);
+    assert_eq!(config.bounce_count, default.bounce_count);
+    assert_eq!(config.movement_speed, default.movement_speed);
+    assert_eq!(config.theme, default.theme);
+    assert_eq!(config.startup_scene, None);
+}
+
+#[test]
+fn present_modes_surfaces_may_not_support_fall_back_to_automatic_ones() {
+    for (saved, expected) in [
+        ("mailbox", wgpu::PresentMode::AutoNoVsync),
+        ("immediate", wgpu::PresentMode::AutoNoVsync),
+        ("fifo-relaxed", wgpu::PresentMode::AutoVsync),
+        ("fifo", wgpu::PresentMode::Fifo),
+    ] {
+        let config = Config::parse(&format!("present_mode = \"{saved}\""));
+        assert_eq!(config.present_mode, expected, "{saved}");
+        assert!(PRESENT_MODES.contains(&config.present_mode));
+    }
+}
+
+#[test]
+fn the_named_adapter_picks_its_backend_and_power_preference() {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        ..Default::default()
+    });
+    let Some(info) = instance
+        .enumerate_adapters(wgpu::Backends::all())
+        .map(|adapter| adapter.get_info())
+        .next()
+    else {
+        eprintln!("skipping, no GPU adapter is available");
+        return;
+    };
+
+    let mut config = Config {
+        adapter_name: Some(info.name.clone()),
+        ..Default::default()
+    };
+    let adapter = config.select_adapter(&instance).unwrap();
+    assert_eq!(adapter.get_info().name, info.name);
+    assert_eq!(
+        config.backends,
+        backend_to_backends(adapter.get_info().backend)
+    );
+    assert_eq!(
+        config.power_preference,
+        adapter_power_preference(info.device_type)
+    );
+
+    // a missing adapter leaves the choice to the power preference
+    let mut config = Config {
+        adapter_name: Some("Not A Real GPU".into()),
+        ..Default::default()
+    };
+    assert!(config.select_adapter(&instance).is_none());
+    assert_eq!(config.backends, Config::default().backends);
+}
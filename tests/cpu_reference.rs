@@ -0,0 +1,615 @@
+//! Cross-validates the shader against the CPU port in `cpu_reference`,
+//! the GPU comparisons pass without doing anything when there is no GPU adapter.
+#![cfg(feature = "cpu-reference")]
+
+mod common;
+
+use cgmath::InnerSpace;
+use common::*;
+use four_dimentions::{
+    cpu_reference, BlendMask, ColorManagement, CrossSection, EmissionPattern, GpuHyperSphere,
+    GpuMaterial, NodeGraph, NodeKind, ObjectFormat, Projection, RenderMode, RenderPath, SkyKind,
+    Stereo,
+};
+
+fn render_reference(scene: &Scene) -> Vec<u8> {
+    let camera = scene.camera.to_gpu(0);
+    cpu_reference::render(
+        &cpu_reference::Scene {
+            camera: &camera,
+            hyper_spheres: &scene.hyper_spheres,
+            hyper_planes: &scene.hyper_planes,
+            materials: &scene.materials,
+            node_programs: &scene.node_programs,
+            environment: &scene.environment.to_gpu(),
+        },
+        scene.camera.render_options(),
+        WIDTH,
+        HEIGHT,
+    )
+}
+
+fn compare_with_gpu(name: &str, scene: Scene) {
+    let Some((device, queue)) = request_device() else {
+        eprintln!("skipping {name}, no GPU adapter is available");
+        return;
+    };
+
+    let expected = render_reference(&scene);
+    let render_path = RenderPath::for_device(&device);
+    let actual = render(&device, &queue, render_path, &scene);
+    assert_matches_reference(name, &expected, &actual);
+    // GL contexts can't be current on the same thread twice
+    drop((device, queue));
+
+    // the fragment path reads the scene from textures instead of storage buffers there
+    if render_path == RenderPath::Compute {
+        let (device, queue) = request_webgl2_device().expect("the adapter was found before");
+        let actual = render(&device, &queue, RenderPath::Fragment, &scene);
+        assert_matches_reference(&format!("{name} on WebGL2"), &expected, &actual);
+    }
+}
+
+fn assert_matches_reference(name: &str, expected: &[u8], actual: &[u8]) {
+    let (mean_difference, outlier_fraction) = image_difference(expected, actual);
+    assert!(
+        images_match(mean_difference, outlier_fraction),
+        "{name} doesn't match the CPU reference (mean difference {mean_difference:.3}, \
+         {:.2}% outliers)",
+        outlier_fraction * 100.0,
+    );
+}
+
+#[test]
+fn default_scene_matches_gpu() {
+    compare_with_gpu("default_scene", default_scene());
+}
+
+#[test]
+fn moving_objects_match_gpu() {
+    let mut scene = default_scene();
+    scene.camera.shutter_time = 0.5;
+    scene.hyper_spheres[0].velocity = cgmath::vec4(1.0, 0.0, 0.0, 0.5);
+    scene.hyper_planes[0].angular_velocity.xy = 0.2;
+    compare_with_gpu("moving_objects", scene);
+}
+
+#[test]
+fn packed_objects_match_gpu() {
+    let mut scene = default_scene();
+    scene.object_format = ObjectFormat::Packed;
+    scene.camera.shutter_time = 0.5;
+    scene.hyper_spheres[0].velocity = cgmath::vec4(1.0, 0.0, 0.0, 0.5);
+    scene.hyper_planes[0].angular_velocity.xy = 0.25;
+    compare_with_gpu("packed_objects", scene);
+}
+
+fn finite_plane_scene(object_format: ObjectFormat) -> Scene {
+    let mut scene = default_scene();
+    scene.object_format = object_format;
+    scene.camera.shutter_time = 0.5;
+    scene.hyper_planes[0].extents = cgmath::vec3(1.5, 0.0, 2.0);
+    scene.hyper_planes[0].angular_velocity.xz = 0.3;
+    scene
+}
+
+#[test]
+fn finite_planes_match_gpu() {
+    compare_with_gpu("finite_planes", finite_plane_scene(ObjectFormat::Full));
+    compare_with_gpu(
+        "packed_finite_planes",
+        finite_plane_scene(ObjectFormat::Packed),
+    );
+}
+
+#[test]
+fn custom_plane_tangents_match_gpu() {
+    let mut scene = default_scene();
+    scene.materials[1].emissive_color = cgmath::vec3(1.0, 1.0, 1.0);
+    scene.materials[1].emission_strength = 1.0;
+    scene.materials[1].emission_pattern = EmissionPattern::Checker as _;
+    scene.materials[1].emission_pattern_scale = cgmath::vec4(2.0, 1.0, 1.0, 0.0);
+    let hyper_plane = &mut scene.hyper_planes[0];
+    hyper_plane.tangent = cgmath::vec4(1.0, 0.0, 1.0, 0.0);
+    hyper_plane.bitangent = cgmath::vec4(0.0, 0.0, 1.0, 0.5);
+    hyper_plane.extents = cgmath::vec3(2.0, 0.0, 0.0);
+    compare_with_gpu("custom_plane_tangents", scene);
+}
+
+#[test]
+fn culled_spheres_still_light_the_scene() {
+    let mut scene = default_scene();
+    // behind the camera, so only bounces can reach it
+    scene.hyper_spheres.push(GpuHyperSphere {
+        center: cgmath::vec4(0.0, 2.0, -6.0, 0.0),
+        velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        radius: 1.0,
+        material: 2,
+        inner_radius: 0.0,
+    });
+    // off to the side and past the max distance
+    for i in 0..8 {
+        scene.hyper_spheres.push(GpuHyperSphere {
+            center: cgmath::vec4(20.0 + i as f32, 0.0, 0.0, 0.0),
+            velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            radius: 0.5,
+            material: 0,
+            inner_radius: 0.0,
+        });
+    }
+    scene.camera.max_distance = 15.0;
+    scene.materials.push(GpuMaterial {
+        emissive_color: cgmath::vec3(1.0, 0.9, 0.6),
+        emission_strength: 4.0,
+        ..Default::default()
+    });
+    compare_with_gpu("culled_spheres", scene);
+}
+
+#[test]
+fn bump_mapping_matches_gpu() {
+    let mut scene = default_scene();
+    for material in &mut scene.materials {
+        material.bump_strength = 0.5;
+        material.bump_scale = 3.0;
+    }
+    compare_with_gpu("bump_mapping", scene);
+}
+
+#[test]
+fn emission_patterns_match_gpu() {
+    let mut scene = default_scene();
+    scene.materials[0].emissive_color = cgmath::vec3(1.0, 1.0, 1.0);
+    scene.materials[0].emission_strength = 2.0;
+    scene.materials[0].emission_pattern = EmissionPattern::Checker as _;
+    scene.materials[0].emission_pattern_scale = cgmath::vec4(3.0, 3.0, 3.0, 0.0);
+    scene.materials[1].emissive_color = cgmath::vec3(1.0, 0.5, 0.2);
+    scene.materials[1].emission_strength = 1.0;
+    scene.materials[1].emission_pattern = EmissionPattern::Stripes as _;
+    scene.materials[1].emission_pattern_scale = cgmath::vec4(1.0, 0.0, 0.5, 0.0);
+    compare_with_gpu("emission_patterns", scene);
+}
+
+#[test]
+fn blended_materials_match_gpu() {
+    let mut scene = default_scene();
+    let first = scene.materials.len() as u32;
+    scene.materials.push(GpuMaterial {
+        base_color: cgmath::vec3(0.9, 0.2, 0.1),
+        ..Default::default()
+    });
+    scene.materials.push(GpuMaterial {
+        emissive_color: cgmath::vec3(0.2, 0.4, 1.0),
+        emission_strength: 2.0,
+        ..Default::default()
+    });
+    scene.materials[0].blend_mask = BlendMask::Noise as _;
+    scene.materials[0].blend_a = first;
+    scene.materials[0].blend_b = first + 1;
+    scene.materials[0].blend_scale = cgmath::vec4(2.0, 2.0, 2.0, 2.0);
+    scene.materials[1].blend_mask = BlendMask::Checker as _;
+    scene.materials[1].blend_a = 0;
+    scene.materials[1].blend_b = first;
+    compare_with_gpu("blended_materials", scene);
+}
+
+#[test]
+fn node_materials_match_gpu() {
+    let mut graph = NodeGraph::default();
+    let output = graph.nodes[0].id;
+    let noise = graph.add_node(NodeKind::Noise, [0.0, 0.0]);
+    let color = graph.add_node(NodeKind::Color, [0.0, 0.0]);
+    graph.node_mut(color).unwrap().value = cgmath::vec4(0.2, 0.3, 0.9, 0.0);
+    let mix = graph.add_node(NodeKind::Mix, [0.0, 0.0]);
+    graph.connect(Some(color), mix, 1);
+    graph.connect(Some(noise), mix, 2);
+    graph.connect(Some(mix), output, 0);
+    let fresnel = graph.add_node(NodeKind::Fresnel, [0.0, 0.0]);
+    let checker = graph.add_node(NodeKind::Checker, [0.0, 0.0]);
+    let position = graph.add_node(NodeKind::Position, [0.0, 0.0]);
+    graph.connect(Some(position), checker, 0);
+    let emission = graph.add_node(NodeKind::Multiply, [0.0, 0.0]);
+    graph.connect(Some(fresnel), emission, 0);
+    graph.connect(Some(checker), emission, 1);
+    graph.connect(Some(emission), output, 1);
+
+    let mut scene = default_scene();
+    scene.node_programs = graph.compile().unwrap();
+    scene.materials[0].node_program_length = scene.node_programs.len() as u32;
+    scene.materials[0].emission_strength = 4.0;
+    compare_with_gpu("node_materials", scene);
+}
+
+#[test]
+fn physical_sky_matches_gpu() {
+    let mut scene = default_scene();
+    scene.environment.sky = SkyKind::Physical;
+    scene.environment.sun_elevation = 20.0f32.to_radians();
+    scene.environment.sun_radius = 10.0f32.to_radians();
+    compare_with_gpu("physical_sky", scene);
+}
+
+#[test]
+fn ambient_occlusion_matches_gpu() {
+    let mut scene = default_scene();
+    scene.camera.render_mode = RenderMode::AmbientOcclusion;
+    scene.camera.ao_distance = 0.5;
+    compare_with_gpu("ambient_occlusion", scene);
+}
+
+#[test]
+fn w_coordinate_matches_gpu() {
+    let mut scene = default_scene();
+    scene.camera.render_mode = RenderMode::WCoordinate;
+    scene.hyper_spheres[0].center.w = 0.5;
+    scene.hyper_planes[0].normal = cgmath::vec4(0.0, 1.0, 0.0, 0.5).normalize();
+    compare_with_gpu("w_coordinate", scene);
+}
+
+#[test]
+fn glass_matches_gpu() {
+    let mut scene = default_scene();
+    scene.materials[0].transmission = 1.0;
+    scene.materials[0].base_color = cgmath::vec3(0.9, 0.9, 0.9);
+    compare_with_gpu("glass", scene);
+}
+
+#[test]
+fn spectral_dispersion_matches_gpu() {
+    let mut scene = default_scene();
+    scene.camera.spectral = true;
+    scene.materials[0].transmission = 1.0;
+    scene.materials[0].dispersion = 0.05;
+    compare_with_gpu("spectral_dispersion", scene);
+}
+
+fn nested_glass_scene(inner_priority: u32) -> Scene {
+    let mut scene = default_scene();
+    scene.materials[0].transmission = 1.0;
+    scene.materials[0].base_color = cgmath::vec3(0.9, 0.9, 0.9);
+    scene.materials[0].priority = 1;
+    // another glass material inside the sphere, as though it had something in it
+    scene.materials.push(four_dimentions::GpuMaterial {
+        base_color: cgmath::vec3(0.9, 0.9, 0.9),
+        transmission: 1.0,
+        ior: 1.3,
+        priority: inner_priority,
+        ..Default::default()
+    });
+    scene.hyper_spheres.push(four_dimentions::GpuHyperSphere {
+        radius: 0.6,
+        material: 2,
+        ..scene.hyper_spheres[0]
+    });
+    scene
+}
+
+#[test]
+fn nested_glass_matches_gpu() {
+    compare_with_gpu("nested_glass", nested_glass_scene(2));
+}
+
+#[test]
+fn lower_priority_media_are_ignored_inside_higher_ones() {
+    let mut scene = nested_glass_scene(0);
+    let nested = render_reference(&scene);
+    scene.hyper_spheres.pop();
+    let alone = render_reference(&scene);
+    let (mean_difference, outlier_fraction) = image_difference(&alone, &nested);
+    assert!(
+        images_match(mean_difference, outlier_fraction),
+        "mean difference {mean_difference:.3}, {:.2}% outliers",
+        outlier_fraction * 100.0
+    );
+
+    // the inner sphere fills its part of the outer one when it has the higher priority
+    let filled = render_reference(&nested_glass_scene(2));
+    let (mean_difference, _) = image_difference(&alone, &filled);
+    assert!(mean_difference > 1.0, "{mean_difference}");
+}
+
+#[test]
+fn absorbing_glass_matches_gpu() {
+    let mut scene = default_scene();
+    scene.materials[0].transmission = 1.0;
+    scene.materials[0].base_color = cgmath::vec3(1.0, 1.0, 1.0);
+    scene.materials[0].absorption_color = cgmath::vec3(0.2, 0.5, 0.9);
+    scene.materials[0].absorption_density = 1.0;
+    compare_with_gpu("absorbing_glass", scene);
+}
+
+#[test]
+fn absorption_darkens_thicker_glass() {
+    let brightness = |radius: f32, density: f32| {
+        let mut scene = default_scene();
+        scene.hyper_planes.clear();
+        scene.hyper_spheres[0].radius = radius;
+        scene.materials[0].transmission = 1.0;
+        scene.materials[0].base_color = cgmath::vec3(1.0, 1.0, 1.0);
+        scene.materials[0].absorption_color = cgmath::vec3(0.5, 0.5, 0.5);
+        scene.materials[0].absorption_density = density;
+        let pixels = render_reference(&scene);
+        let index = (HEIGHT / 2 * WIDTH + WIDTH / 2) * 4;
+        pixels[index..index + 3]
+            .iter()
+            .map(|&c| c as u32)
+            .sum::<u32>()
+    };
+    let clear = brightness(1.0, 0.0);
+    let thin = brightness(0.5, 1.0);
+    let thick = brightness(1.0, 1.0);
+    assert!(clear > thin && thin > thick, "{clear} {thin} {thick}");
+}
+
+#[test]
+fn clearcoat_and_sheen_match_gpu() {
+    let mut scene = default_scene();
+    scene.materials[0].clearcoat = 1.0;
+    scene.materials[0].clearcoat_roughness = 0.2;
+    scene.materials[1].sheen = 1.0;
+    scene.materials[1].sheen_color = cgmath::vec3(1.0, 0.5, 0.8);
+    compare_with_gpu("clearcoat_and_sheen", scene);
+}
+
+#[test]
+fn clearcoat_reflects_off_dark_bases() {
+    let brightness = |clearcoat: f32| {
+        let mut scene = default_scene();
+        scene.materials[0].base_color = cgmath::vec3(0.0, 0.0, 0.0);
+        scene.materials[0].clearcoat = clearcoat;
+        render_reference(&scene)
+            .iter()
+            .map(|&c| c as u32)
+            .sum::<u32>()
+    };
+    assert!(brightness(1.0) > brightness(0.0));
+}
+
+#[test]
+fn spectral_weights_average_to_white() {
+    let steps = 4000;
+    let mut total = cgmath::vec3(0.0, 0.0, 0.0);
+    for i in 0..steps {
+        let wavelength = 380.0 + 400.0 * (i as f32 + 0.5) / steps as f32;
+        total += cpu_reference::wavelength_to_rgb(wavelength);
+    }
+    let average = total / steps as f32;
+    assert!(
+        (average - cgmath::vec3(1.0, 1.0, 1.0)).magnitude() < 1e-3,
+        "{average:?}"
+    );
+}
+
+#[test]
+fn shorter_wavelengths_bend_more() {
+    let material = four_dimentions::GpuMaterial {
+        ior: 1.5,
+        dispersion: 0.01,
+        ..Default::default()
+    };
+    let ior = |wavelength| cpu_reference::index_of_refraction(&material, wavelength);
+    assert!((ior(cpu_reference::REFERENCE_WAVELENGTH) - 1.5).abs() < 1e-5);
+    assert!(ior(450.0) > ior(650.0));
+}
+
+#[test]
+fn spectral_without_dispersion_keeps_the_colors() {
+    let mut scene = default_scene();
+    let rgb = render_reference(&scene);
+    scene.camera.spectral = true;
+    let spectral = render_reference(&scene);
+    let channel_means = |pixels: &[u8]| {
+        let mut sums = [0.0f64; 3];
+        for pixel in pixels.chunks_exact(4) {
+            for channel in 0..3 {
+                sums[channel] += pixel[channel] as f64;
+            }
+        }
+        sums.map(|sum| sum / (WIDTH * HEIGHT) as f64)
+    };
+    let (rgb, spectral) = (channel_means(&rgb), channel_means(&spectral));
+    for channel in 0..3 {
+        assert!(
+            (rgb[channel] - spectral[channel]).abs() < 3.0,
+            "{rgb:?} {spectral:?}"
+        );
+    }
+}
+
+#[test]
+fn looking_at_the_sphere_hits_it() {
+    let scene = default_scene();
+    let camera = scene.camera.to_gpu(0);
+    let hit = cpu_reference::intersect_hyper_sphere(
+        &camera,
+        cpu_reference::Ray {
+            origin: camera.position,
+            direction: camera.forward,
+            time: 0.0,
+            wavelength: cpu_reference::REFERENCE_WAVELENGTH,
+        },
+        &scene.hyper_spheres[0],
+    )
+    .expect("the camera looks straight at the sphere");
+    assert!((hit.distance - 2.0).abs() < 1e-4, "{hit:?}");
+    assert!((hit.normal - cgmath::vec4(0.0, 0.0, -1.0, 0.0)).magnitude() < 1e-4);
+}
+
+#[test]
+fn cross_section_shows_the_inside_of_the_sphere() {
+    let mut scene = default_scene();
+    scene.camera.cross_section = Some(CrossSection::default());
+    let camera = scene.camera.to_gpu(0);
+    let hit = cpu_reference::get_closest_hit(
+        &cpu_reference::Scene {
+            camera: &camera,
+            hyper_spheres: &scene.hyper_spheres,
+            hyper_planes: &scene.hyper_planes,
+            materials: &scene.materials,
+            node_programs: &scene.node_programs,
+            environment: &scene.environment.to_gpu(),
+        },
+        cpu_reference::Ray {
+            origin: camera.position,
+            direction: camera.forward,
+            time: 0.0,
+            wavelength: cpu_reference::REFERENCE_WAVELENGTH,
+        },
+    )
+    .expect("the cut goes through the sphere");
+    // the front of the sphere is cut away at z = 0, leaving its middle facing the camera
+    assert!(hit.cut);
+    assert!((hit.distance - 3.0).abs() < 1e-4, "{hit:?}");
+    assert!((hit.normal - cgmath::vec4(0.0, 0.0, -1.0, 0.0)).magnitude() < 1e-4);
+}
+
+#[test]
+fn hollow_spheres_are_hit_inside_and_through_the_cut() {
+    let mut scene = default_scene();
+    scene.hyper_spheres[0].inner_radius = 0.5;
+    let camera = scene.camera.to_gpu(0);
+    let center = scene.hyper_spheres[0].center;
+    let from_the_center = cpu_reference::intersect_hyper_sphere(
+        &camera,
+        cpu_reference::Ray {
+            origin: center,
+            direction: cgmath::vec4(0.0, 0.0, 1.0, 0.0),
+            time: 0.0,
+            wavelength: cpu_reference::REFERENCE_WAVELENGTH,
+        },
+        &scene.hyper_spheres[0],
+    )
+    .expect("the hollow is enclosed by the inner surface");
+    assert!((from_the_center.distance - 0.5).abs() < 1e-4);
+    assert!(from_the_center.front_face);
+    assert!((from_the_center.normal - cgmath::vec4(0.0, 0.0, -1.0, 0.0)).magnitude() < 1e-4);
+
+    scene.camera.cross_section = Some(CrossSection::default());
+    let camera = scene.camera.to_gpu(0);
+    let hit = cpu_reference::get_closest_hit(
+        &cpu_reference::Scene {
+            camera: &camera,
+            hyper_spheres: &scene.hyper_spheres,
+            hyper_planes: &scene.hyper_planes,
+            materials: &scene.materials,
+            node_programs: &scene.node_programs,
+            environment: &scene.environment.to_gpu(),
+        },
+        cpu_reference::Ray {
+            origin: camera.position,
+            direction: camera.forward,
+            time: 0.0,
+            wavelength: cpu_reference::REFERENCE_WAVELENGTH,
+        },
+    )
+    .expect("the far side of the hollow is behind the cut");
+    // the cut at z = 0 goes through the hollow, so the far inner surface shows
+    assert!(!hit.cut);
+    assert!((hit.distance - 3.5).abs() < 1e-4, "{hit:?}");
+    assert!((hit.normal - cgmath::vec4(0.0, 0.0, -1.0, 0.0)).magnitude() < 1e-4);
+}
+
+fn hollow_sphere_scene(cross_section: bool) -> Scene {
+    let mut scene = default_scene();
+    scene.hyper_spheres[0].inner_radius = 0.7;
+    if cross_section {
+        scene.camera.cross_section = Some(CrossSection::default());
+    }
+    scene
+}
+
+#[test]
+fn hollow_spheres_match_gpu() {
+    compare_with_gpu("hollow_spheres", hollow_sphere_scene(false));
+    compare_with_gpu("hollow_spheres_cut", hollow_sphere_scene(true));
+}
+
+#[test]
+fn cross_section_matches_gpu() {
+    let mut scene = default_scene();
+    scene.camera.cross_section = Some(CrossSection::default());
+    compare_with_gpu("cross_section", scene);
+}
+
+#[test]
+fn stereographic_projection_matches_gpu() {
+    let mut scene = default_scene();
+    scene.camera.projection = Projection::Stereographic;
+    scene.camera.position = cgmath::vec4(0.0, 0.5, -2.0, 0.0);
+    compare_with_gpu("stereographic_projection", scene);
+}
+
+#[test]
+fn aspect_override_and_lens_shift_match_gpu() {
+    let mut scene = default_scene();
+    scene.camera.aspect_override = Some(2.39);
+    scene.camera.lens_shift = cgmath::vec2(0.3, -0.4);
+    compare_with_gpu("aspect_override_and_lens_shift", scene);
+}
+
+#[test]
+fn fisheye_matches_gpu() {
+    let mut scene = default_scene();
+    scene.camera.projection = Projection::Fisheye;
+    scene.camera.fisheye_angle = 200.0f32.to_radians();
+    compare_with_gpu("fisheye", scene);
+}
+
+#[test]
+fn equirectangular_matches_gpu() {
+    let mut scene = default_scene();
+    scene.camera.projection = Projection::Equirectangular;
+    compare_with_gpu("equirectangular", scene);
+}
+
+#[test]
+fn stereo_matches_gpu() {
+    let mut scene = default_scene();
+    scene.camera.stereo = Some(Stereo {
+        eye_separation: 0.5,
+        cross_eyed: false,
+    });
+    compare_with_gpu("stereo", scene);
+}
+
+#[test]
+fn stereographic_projection_sees_the_3_sphere() {
+    let mut scene = default_scene();
+    scene.hyper_planes.clear();
+    // a hyper sphere at the south pole of the unit 3-sphere, which projects to the origin
+    scene.hyper_spheres[0].center = cgmath::vec4(0.0, 0.0, 0.0, -1.0);
+    scene.hyper_spheres[0].radius = 0.5;
+    scene.camera.projection = Projection::Stereographic;
+    scene.camera.position = cgmath::vec4(0.0, 0.0, -3.0, 0.0);
+    let pixels = render_reference(&scene);
+    let pixel = |x: usize, y: usize| {
+        let index = (y * WIDTH + x) * 4;
+        [pixels[index], pixels[index + 1], pixels[index + 2]]
+    };
+    let [r, _, b] = pixel(WIDTH / 2, HEIGHT / 2);
+    assert!(r > b, "{:?}", [r, b]);
+    let [r, _, b] = pixel(0, 0);
+    assert!(b > r, "{:?}", [r, b]);
+}
+
+#[test]
+fn sphere_and_sky_are_visible() {
+    let scene = default_scene();
+    let pixels = render_reference(&scene);
+    let pixel = |x: usize, y: usize| {
+        let index = (y * WIDTH + x) * 4;
+        [pixels[index], pixels[index + 1], pixels[index + 2]]
+    };
+    // the sphere is orange, the sky above it is blue
+    let [r, _, b] = pixel(WIDTH / 2, HEIGHT / 2);
+    assert!(r > b, "{:?}", [r, b]);
+    let [r, _, b] = pixel(WIDTH / 2, 0);
+    assert!(b > r, "{:?}", [r, b]);
+}
+
+#[test]
+fn linear_color_management_matches_gpu() {
+    let mut scene = default_scene();
+    scene.camera.color_management = ColorManagement::Linear;
+    compare_with_gpu("linear_color_management", scene);
+}
@@ -0,0 +1,101 @@
+//! The GPU tests pass without doing anything when there is no GPU adapter.
+
+mod common;
+
+use common::*;
+use four_dimentions::{ColorManagement, DisplayTexture, RenderPath, Renderer};
+
+#[test]
+fn srgb_encoding_matches_the_transfer_function() {
+    let encode = |value: f32| {
+        ColorManagement::Srgb
+            .encode(cgmath::vec3(value, value, value))
+            .x
+    };
+    assert_eq!(encode(0.0), 0.0);
+    assert!((encode(1.0) - 1.0).abs() < 1e-6);
+    assert!((encode(0.5) - 0.7354).abs() < 1e-3);
+    assert!((encode(0.001) - 0.01292).abs() < 1e-6);
+    assert_eq!(
+        ColorManagement::Linear.encode(cgmath::vec3(0.25, 0.5, 0.75)),
+        cgmath::vec3(0.25, 0.5, 0.75)
+    );
+}
+
+#[test]
+fn srgb_output_is_brighter_than_linear() {
+    let Some((device, queue)) = request_device() else {
+        eprintln!("skipping, no GPU adapter is available");
+        return;
+    };
+    let render_path = RenderPath::for_device(&device);
+
+    let mut scene = default_scene();
+    scene.camera.color_management = ColorManagement::Linear;
+    let linear = render(&device, &queue, render_path, &scene);
+    scene.camera.color_management = ColorManagement::Srgb;
+    let srgb = render(&device, &queue, render_path, &scene);
+
+    let (mut brighter, mut total) = (0, 0);
+    for (linear, srgb) in linear.chunks_exact(4).zip(srgb.chunks_exact(4)) {
+        for channel in 0..3 {
+            if linear[channel] > 8 && linear[channel] < 247 {
+                total += 1;
+                let expected = ColorManagement::Srgb
+                    .encode(cgmath::vec3(linear[channel] as f32 / 255.0, 0.0, 0.0))
+                    .x;
+                brighter += usize::from((srgb[channel] as f32 / 255.0 - expected).abs() < 0.05);
+            }
+        }
+    }
+    assert!(total > 0);
+    assert!(
+        brighter as f32 > total as f32 * 0.95,
+        "only {brighter} of {total} channels are encoded"
+    );
+}
+
+#[test]
+fn display_texture_keeps_the_output_bytes() {
+    let Some((device, queue)) = request_device() else {
+        eprintln!("skipping, no GPU adapter is available");
+        return;
+    };
+    let scene = default_scene();
+    let mut renderer = Renderer::new(&device, &queue, RenderPath::for_device(&device));
+    renderer.resize(&device, &queue, WIDTH, HEIGHT);
+    renderer
+        .upload_objects(&device, &queue, &scene.hyper_spheres, &scene.hyper_planes)
+        .unwrap();
+    renderer
+        .upload_materials(&device, &queue, &scene.materials)
+        .unwrap();
+    renderer
+        .upload_environment(&queue, &scene.environment.to_gpu())
+        .unwrap();
+    renderer
+        .upload_camera(&queue, &scene.camera.to_gpu(0))
+        .unwrap();
+
+    let mut display = DisplayTexture::new(&device, renderer.texture_view(), 1, 1);
+    assert!(display.set_output(&device, renderer.texture_view(), WIDTH, HEIGHT));
+    let mut encoder = device.create_command_encoder(&Default::default());
+    renderer.render(&device, &mut encoder, scene.camera.render_options());
+    display.render(&mut encoder);
+    queue.submit([encoder.finish()]);
+
+    let output = renderer.read_pixels(&device, &queue).unwrap();
+    let displayed = read_texture(&device, &queue, display.texture());
+    assert_eq!(display.size(), (WIDTH, HEIGHT));
+    assert_eq!(display.read_pixels(&device, &queue).unwrap(), displayed);
+    let largest_difference = output
+        .iter()
+        .zip(&displayed)
+        .map(|(&a, &b)| a.abs_diff(b))
+        .max()
+        .unwrap();
+    assert!(
+        largest_difference <= 1,
+        "the display texture differs by up to {largest_difference}"
+    );
+}
@@ -0,0 +1,40 @@
+use cgmath::InnerSpace;
+use four_dimentions::{physical_sky_radiance, Environment, SkyKind};
+
+fn luminance(color: cgmath::Vector3<f32>) -> f32 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+#[test]
+fn physical_sky_is_brightest_around_the_sun() {
+    let environment = Environment {
+        sky: SkyKind::Physical,
+        sun_w_angle: 40.0f32.to_radians(),
+        ..Default::default()
+    };
+    let gpu_environment = environment.to_gpu();
+    let sun = environment.sun_direction();
+    assert!((sun.magnitude() - 1.0).abs() < 1e-5);
+    assert!(sun.w > 0.0);
+
+    let near_sun = physical_sky_radiance(&gpu_environment, sun);
+    let away_from_sun = cgmath::vec4(-sun.x, sun.y, -sun.z, -sun.w);
+    let far_from_sun = physical_sky_radiance(&gpu_environment, away_from_sun);
+    assert!(luminance(near_sun) > luminance(far_from_sun));
+    assert!(luminance(gpu_environment.sun_radiance) > luminance(near_sun));
+}
+
+#[test]
+fn sky_brightens_as_the_sun_rises() {
+    let zenith = cgmath::vec4(0.0, 1.0, 0.0, 0.0);
+    let brightness = |elevation: f32| {
+        let environment = Environment {
+            sky: SkyKind::Physical,
+            sun_elevation: elevation.to_radians(),
+            ..Default::default()
+        };
+        luminance(physical_sky_radiance(&environment.to_gpu(), zenith))
+    };
+    assert!(brightness(10.0) < brightness(40.0));
+    assert!(brightness(40.0) < brightness(80.0));
+}
@@ -0,0 +1,26 @@
+use four_dimentions::{wgpu, GpuError};
+
+#[derive(Debug)]
+struct Cause(&'static str);
+
+impl std::fmt::Display for Cause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl std::error::Error for Cause {}
+
+fn validation_error(source: impl std::error::Error + Send + Sync + 'static) -> GpuError {
+    GpuError::from(wgpu::Error::Validation {
+        source: Box::new(source),
+        description: "Validation Error".into(),
+    })
+}
+
+#[test]
+fn device_loss_is_found_in_the_source_chain() {
+    assert!(validation_error(wgpu_core::device::DeviceError::Lost).is_device_lost());
+    assert!(!validation_error(wgpu_core::device::DeviceError::OutOfMemory).is_device_lost());
+    assert!(!validation_error(Cause("buffer is too small")).is_device_lost());
+}
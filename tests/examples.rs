@@ -0,0 +1,33 @@
+use four_dimentions::{Scene, Script, Value, EXAMPLES};
+
+#[test]
+fn examples_run_and_animate() {
+    for example in EXAMPLES {
+        let mut script = Script::compile(example.source)
+            .unwrap_or_else(|error| panic!("{}: {error}", example.name));
+        let mut scene = Scene::default();
+        let mut output = vec![];
+        script
+            .run(&mut scene, &mut output)
+            .unwrap_or_else(|error| panic!("{}: {error}", example.name));
+        assert!(scene.hyper_sphere_count() > 0, "{} is empty", example.name);
+        assert!(scene
+            .hyper_spheres()
+            .all(|hyper_sphere| (hyper_sphere.material as usize) < scene.materials.len()));
+
+        if script.has_function("update") {
+            let sphere_count = scene.hyper_sphere_count();
+            for frame in 0..3 {
+                script
+                    .call(
+                        "update",
+                        &[Value::Number(frame as f64 * 0.1), Value::Number(0.1)],
+                        &mut scene,
+                        &mut output,
+                    )
+                    .unwrap_or_else(|error| panic!("{}: {error}", example.name));
+            }
+            assert_eq!(scene.hyper_sphere_count(), sphere_count);
+        }
+    }
+}
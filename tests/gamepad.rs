@@ -0,0 +1,55 @@
+use four_dimentions::{
+    GamepadAxis, GamepadBinding, GamepadBindings, GamepadButton, GamepadControl, GamepadInput,
+    GamepadState,
+};
+
+#[test]
+fn default_bindings_drive_the_camera() {
+    let bindings = GamepadBindings::default();
+    let mut state = GamepadState::default();
+    state.set_axis(GamepadAxis::LeftStickY, 1.0);
+    state.set_axis(GamepadAxis::LeftTrigger, 1.0);
+    state.set_axis(GamepadAxis::RightStickX, -1.0);
+    state.set_button(GamepadButton::RightBumper, true);
+    let controls = bindings.controls(&state);
+    assert_eq!(controls.movement, cgmath::vec4(0.0, 0.0, 1.0, -1.0));
+    assert_eq!(controls.yaw, -1.0);
+    assert_eq!(controls.pitch, 0.0);
+    assert_eq!(controls.weird_yaw, 1.0);
+}
+
+#[test]
+fn dead_zone_only_applies_to_sticks() {
+    let mut state = GamepadState::default();
+    state.set_axis(GamepadAxis::LeftStickX, 0.1);
+    state.set_axis(GamepadAxis::RightStickX, 0.6);
+    state.set_axis(GamepadAxis::RightTrigger, 0.1);
+    let stick = |axis| state.value(GamepadInput::Axis(axis), 0.2);
+    assert_eq!(stick(GamepadAxis::LeftStickX), 0.0);
+    assert!((stick(GamepadAxis::RightStickX) - 0.5).abs() < 1e-6);
+    assert_eq!(stick(GamepadAxis::RightTrigger), 0.1);
+}
+
+#[test]
+fn bindings_add_up_with_their_scale() {
+    let bindings = GamepadBindings {
+        bindings: vec![
+            GamepadBinding {
+                input: GamepadInput::Button(GamepadButton::South),
+                control: GamepadControl::Pitch,
+                scale: 0.5,
+            },
+            GamepadBinding {
+                input: GamepadInput::Button(GamepadButton::East),
+                control: GamepadControl::Pitch,
+                scale: -2.0,
+            },
+        ],
+        dead_zone: 0.0,
+    };
+    let mut state = GamepadState::default();
+    state.set_button(GamepadButton::South, true);
+    assert_eq!(bindings.controls(&state).pitch, 0.5);
+    state.set_button(GamepadButton::East, true);
+    assert_eq!(bindings.controls(&state).pitch, -1.5);
+}
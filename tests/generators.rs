@@ -0,0 +1,96 @@
+use cgmath::InnerSpace;
+use four_dimentions::{CornellBox, RandomSphereField, Room, Scene, SphereLattice, Spiral};
+
+fn assert_consistent(scene: &Scene) {
+    let ids = scene
+        .objects
+        .iter()
+        .map(|object| object.id)
+        .collect::<std::collections::HashSet<_>>();
+    assert_eq!(ids.len(), scene.objects.len());
+    assert_eq!(scene.materials.len(), scene.material_names.len());
+    let material_count = scene.materials.len() as u32;
+    assert!(scene
+        .hyper_spheres()
+        .all(|hyper_sphere| hyper_sphere.material < material_count));
+    assert!(scene
+        .hyper_planes()
+        .all(|hyper_plane| hyper_plane.material < material_count));
+}
+
+#[test]
+fn sphere_lattice_fills_every_cell() {
+    let scene = SphereLattice {
+        size: 3,
+        ..Default::default()
+    }
+    .generate();
+    assert_consistent(&scene);
+    assert_eq!(scene.hyper_sphere_count(), 81);
+    assert_eq!(scene.hyper_plane_count(), 1);
+}
+
+#[test]
+fn random_sphere_field_is_reproducible() {
+    let field = RandomSphereField {
+        count: 20,
+        seed: 7,
+        ..Default::default()
+    };
+    let a = field.generate();
+    let b = field.generate();
+    assert_consistent(&a);
+    assert_eq!(a.hyper_sphere_count(), 20);
+    for (a, b) in a.hyper_spheres().zip(b.hyper_spheres()) {
+        assert_eq!(a.center, b.center);
+        assert_eq!(a.radius, b.radius);
+        assert_eq!(a.material, b.material);
+    }
+
+    let other = RandomSphereField { seed: 8, ..field }.generate();
+    assert_ne!(
+        a.hyper_sphere(0).unwrap().center,
+        other.hyper_sphere(0).unwrap().center
+    );
+}
+
+#[test]
+fn spiral_moves_through_w() {
+    let scene = Spiral::default().generate();
+    assert_consistent(&scene);
+    let first = scene.hyper_spheres().next().unwrap().center.w;
+    let last = scene.hyper_spheres().last().unwrap().center.w;
+    assert!(first < last);
+}
+
+#[test]
+fn cornell_box_encloses_the_camera() {
+    let scene = CornellBox::default().generate();
+    assert_consistent(&scene);
+    assert_eq!(scene.hyper_plane_count(), 8);
+    let camera = four_dimentions::Camera::default().position;
+    // every wall must face the camera
+    for hyper_plane in scene.hyper_planes() {
+        assert!((camera - hyper_plane.point).dot(hyper_plane.normal) > 0.0);
+    }
+}
+
+#[test]
+fn room_walls_close_around_the_camera() {
+    let scene = Room::default().generate();
+    assert_consistent(&scene);
+    let camera = four_dimentions::Camera::default().position;
+    let hyper_planes = scene.hyper_planes().copied().collect::<Vec<_>>();
+    let (walls, light) = hyper_planes.split_at(8);
+    for hyper_plane in walls {
+        assert!((camera - hyper_plane.point).dot(hyper_plane.normal) > 0.0);
+        // the walls reach over the camera but stop at the edges of the room
+        let projected =
+            camera - hyper_plane.normal * (camera - hyper_plane.point).dot(hyper_plane.normal);
+        assert!(hyper_plane.contains(projected, 0.0));
+        assert!(!hyper_plane.contains(projected + hyper_plane.tangents()[0] * 10.0, 0.0));
+    }
+    let ceiling = &walls[1];
+    assert!(ceiling.contains(light[0].point, 0.0));
+    assert!(light[0].extents.x < ceiling.extents.x);
+}
@@ -0,0 +1,145 @@
+//! Renders small fixed scenes headlessly and compares them against the reference images in
+//! `tests/golden`. Missing references are written instead of compared, set `UPDATE_GOLDEN=1`
+//! to overwrite all of them after an intentional rendering change.
+//! The tests pass without doing anything when there is no GPU adapter.
+
+mod common;
+
+use common::*;
+use four_dimentions::{RenderPath, SamplerKind};
+use std::path::{Path, PathBuf};
+
+fn read_png(path: &Path) -> Option<Vec<u8>> {
+    let decoder = png::Decoder::new(std::fs::File::open(path).ok()?);
+    let mut reader = decoder.read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    assert_eq!(
+        (info.width as usize, info.height as usize, info.color_type),
+        (WIDTH, HEIGHT, png::ColorType::Rgba),
+        "{} has the wrong format",
+        path.display(),
+    );
+    pixels.truncate(info.buffer_size());
+    Some(pixels)
+}
+
+fn write_png(path: &Path, pixels: &[u8]) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let mut encoder = png::Encoder::new(
+        std::fs::File::create(path).unwrap(),
+        WIDTH as _,
+        HEIGHT as _,
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .unwrap()
+        .write_image_data(pixels)
+        .unwrap();
+}
+
+fn golden_test(name: &str, render_path: RenderPath, scene: Scene) {
+    let Some((device, queue)) = request_device() else {
+        eprintln!("skipping golden image test {name}, no GPU adapter is available");
+        return;
+    };
+    if render_path == RenderPath::Compute && RenderPath::for_device(&device) != RenderPath::Compute
+    {
+        eprintln!("skipping golden image test {name}, the adapter doesn't support compute shaders");
+        return;
+    }
+
+    let actual = render(&device, &queue, render_path, &scene);
+
+    let reference_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{name}.png"));
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some_and(|value| value != "0");
+    let expected = match read_png(&reference_path) {
+        Some(expected) if !update => expected,
+        _ => {
+            write_png(&reference_path, &actual);
+            eprintln!("wrote reference image {}", reference_path.display());
+            return;
+        }
+    };
+
+    let (mean_difference, outlier_fraction) = image_difference(&expected, &actual);
+    if !images_match(mean_difference, outlier_fraction) {
+        let actual_path = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+            .join("golden")
+            .join(format!("{name}.actual.png"));
+        write_png(&actual_path, &actual);
+        panic!(
+            "{name} doesn't match its reference image (mean difference {mean_difference:.3}, \
+             {:.2}% outliers), the render was written to {}",
+            outlier_fraction * 100.0,
+            actual_path.display(),
+        );
+    }
+}
+
+#[test]
+fn default_scene_compute() {
+    golden_test(
+        "default_scene_compute",
+        RenderPath::Compute,
+        default_scene(),
+    );
+}
+
+#[test]
+fn default_scene_fragment() {
+    golden_test(
+        "default_scene_fragment",
+        RenderPath::Fragment,
+        default_scene(),
+    );
+}
+
+#[test]
+fn emissive_sphere() {
+    let mut scene = default_scene();
+    scene.materials[0].emissive_color = cgmath::vec3(1.0, 0.9, 0.7);
+    scene.materials[0].emission_strength = 4.0;
+    golden_test("emissive_sphere", RenderPath::Compute, scene);
+}
+
+#[test]
+fn motion_blur() {
+    let mut scene = default_scene();
+    scene.camera.shutter_time = 0.5;
+    scene.hyper_spheres[0].velocity = cgmath::vec4(1.0, 0.0, 0.0, 0.0);
+    golden_test("motion_blur", RenderPath::Compute, scene);
+}
+
+#[test]
+fn accumulation() {
+    let mut scene = default_scene();
+    scene.camera.sample_count = 8;
+    scene.frames = 8;
+    golden_test("accumulation", RenderPath::Compute, scene);
+}
+
+#[test]
+fn samplers() {
+    for sampler in [
+        SamplerKind::Halton,
+        SamplerKind::Sobol,
+        SamplerKind::BlueNoise,
+    ] {
+        let mut scene = default_scene();
+        scene.camera.sampler = sampler;
+        golden_test(
+            &format!(
+                "sampler_{}",
+                sampler.name().to_lowercase().replace(' ', "_")
+            ),
+            RenderPath::Compute,
+            scene,
+        );
+    }
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use four_dimentions::{HdrImage, RenderPath, Renderer};
+
+#[test]
+fn exr_files_end_with_the_last_row() {
+    let image = HdrImage {
+        width: 2,
+        height: 2,
+        pixels: vec![
+            cgmath::vec3(0.0, 0.0, 0.0),
+            cgmath::vec3(1.0, 1.0, 1.0),
+            cgmath::vec3(0.5, 2.0, 4.0),
+            cgmath::vec3(8.0, 16.0, 32.0),
+        ],
+    };
+    let exr = image.to_exr();
+    assert_eq!(exr[..4], [0x76, 0x2f, 0x31, 0x01]);
+    // the channels are in name order, each a whole row
+    let last_row = exr[exr.len() - 6 * 4..]
+        .chunks_exact(4)
+        .map(|value| f32::from_le_bytes(value.try_into().unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(last_row, [4.0, 32.0, 2.0, 16.0, 0.5, 8.0]);
+}
+
+#[test]
+fn hdr_pixels_keep_light_brighter_than_white() {
+    let Some((device, queue)) = common::request_device() else {
+        eprintln!("skipping, no GPU adapter is available");
+        return;
+    };
+    if RenderPath::for_device(&device) != RenderPath::Compute {
+        eprintln!("skipping, only the compute path accumulates");
+        return;
+    }
+    let mut scene = common::default_scene();
+    scene.frames = 2;
+    scene.materials[0].emissive_color = cgmath::vec3(1.0, 1.0, 1.0);
+    scene.materials[0].emission_strength = 8.0;
+
+    let mut renderer = Renderer::new(&device, &queue, RenderPath::Compute);
+    renderer.resize(&device, &queue, common::WIDTH, common::HEIGHT);
+    // tiles so the second frame can be stopped halfway through
+    renderer.set_tile_size(&device, Some(common::WIDTH / 2));
+    renderer
+        .upload_objects(&device, &queue, &scene.hyper_spheres, &scene.hyper_planes)
+        .unwrap();
+    renderer
+        .upload_materials(&device, &queue, &scene.materials)
+        .unwrap();
+    assert!(renderer.read_hdr(&device, &queue).unwrap().is_none());
+    let (_, tile_count) = renderer.tile_progress();
+    for dispatch in 0..tile_count + tile_count / 2 {
+        renderer
+            .upload_camera(&queue, &scene.camera.to_gpu((dispatch / tile_count) as _))
+            .unwrap();
+        let mut encoder = device.create_command_encoder(&Default::default());
+        renderer.render(&device, &mut encoder, scene.camera.render_options());
+        queue.submit([encoder.finish()]);
+    }
+    let image = renderer.read_hdr(&device, &queue).unwrap().unwrap();
+    let pixels = renderer.read_pixels(&device, &queue).unwrap();
+
+    assert!(image
+        .pixels
+        .iter()
+        .any(|pixel| pixel.x > 1.0 && pixel.y > 1.0 && pixel.z > 1.0));
+    for (hdr, pixel) in image.pixels.iter().zip(pixels.chunks_exact(4)) {
+        let expected = scene
+            .camera
+            .color_management
+            .encode(hdr.map(|value| value.clamp(0.0, 1.0)));
+        for (expected, &actual) in [expected.x, expected.y, expected.z].into_iter().zip(pixel) {
+            assert!(((expected * 255.0).round() - actual as f32).abs() <= 1.0);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! The GPU tests pass without doing anything when there is no GPU adapter or it can't run compute shaders.
+
+mod common;
+
+use cgmath::InnerSpace;
+use common::*;
+use four_dimentions::{MaterialId, PathBounce, PathEscape, PixelPath, RenderPath, Renderer};
+
+/// Uploads the default scene and inspects `pixel`
+fn inspect(pixel: (usize, usize)) -> Option<(Scene, PixelPath)> {
+    let Some((device, queue)) = request_device() else {
+        eprintln!("skipping, no GPU adapter is available");
+        return None;
+    };
+    if RenderPath::for_device(&device) != RenderPath::Compute {
+        eprintln!("skipping, the adapter doesn't support compute shaders");
+        return None;
+    }
+
+    let scene = default_scene();
+    let mut renderer = Renderer::new(&device, &queue, RenderPath::Compute);
+    renderer.resize(&device, &queue, WIDTH, HEIGHT);
+    renderer
+        .upload_objects(&device, &queue, &scene.hyper_spheres, &scene.hyper_planes)
+        .unwrap();
+    renderer
+        .upload_materials(&device, &queue, &scene.materials)
+        .unwrap();
+    renderer
+        .upload_environment(&queue, &scene.environment.to_gpu())
+        .unwrap();
+    renderer
+        .upload_camera(&queue, &scene.camera.to_gpu(0))
+        .unwrap();
+    let path = renderer
+        .inspect_pixel(&device, &queue, scene.camera.render_options(), pixel)
+        .unwrap()
+        .expect("the compute path can inspect pixels");
+    Some((scene, path))
+}
+
+#[test]
+fn center_pixel_hits_the_sphere() {
+    let Some((scene, path)) = inspect((WIDTH / 2, HEIGHT / 2)) else {
+        return;
+    };
+    assert_eq!(path.pixel, (WIDTH / 2, HEIGHT / 2));
+    assert!((path.origin - scene.camera.position).magnitude() < 1e-3);
+    assert!(!path.bounces.is_empty());
+    assert!(path.bounces.len() <= scene.camera.bounce_count as usize);
+
+    let first = path.bounces[0];
+    assert_eq!(first.material, 0);
+    assert!(first.front_face);
+    assert!((first.normal.magnitude() - 1.0).abs() < 1e-3);
+    let center = scene.hyper_spheres[0].center;
+    assert!(((first.position - center).magnitude() - 1.0).abs() < 1e-3);
+    // the camera looks straight at the sphere
+    assert!(first.direction.dot(first.normal) < 0.0);
+    for bounce in &path.bounces {
+        assert!(bounce.material < scene.materials.len() as u32);
+    }
+}
+
+#[test]
+fn sky_pixel_escapes_without_bouncing() {
+    let Some((_, path)) = inspect((WIDTH / 2, 0)) else {
+        return;
+    };
+    assert!(path.bounces.is_empty());
+    let escape = path.escape.expect("the path escaped");
+    // the top of the image looks up
+    assert!(escape.direction.y > 0.0);
+}
+
+#[test]
+fn pixels_outside_the_texture_are_not_inspected() {
+    let Some((device, queue)) = request_device() else {
+        eprintln!("skipping, no GPU adapter is available");
+        return;
+    };
+    let mut renderer = Renderer::new(&device, &queue, RenderPath::for_device(&device));
+    renderer.resize(&device, &queue, WIDTH, HEIGHT);
+    let options = default_scene().camera.render_options();
+    assert_eq!(
+        renderer
+            .inspect_pixel(&device, &queue, options, (WIDTH, 0))
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn markers_follow_the_path() {
+    let bounce = |position| PathBounce {
+        position,
+        normal: cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+        direction: cgmath::vec4(1.0, 0.0, 0.0, 0.0),
+        material: 0,
+        throughput: cgmath::vec3(1.0, 1.0, 1.0),
+        emitted: cgmath::vec3(0.0, 0.0, 0.0),
+        transmitted: false,
+        coated: false,
+        cut: false,
+        front_face: true,
+    };
+    let mut path = PixelPath {
+        pixel: (0, 0),
+        color: cgmath::vec3(0.0, 0.0, 0.0),
+        origin: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        bounces: vec![
+            bounce(cgmath::vec4(3.0, 0.0, 0.0, 0.0)),
+            bounce(cgmath::vec4(3.0, 0.0, 0.0, 3.0)),
+        ],
+        escape: None,
+    };
+
+    let markers = path.markers(MaterialId(5), MaterialId(6), 0.1);
+    let hits = markers.iter().filter(|marker| marker.material == 5);
+    assert_eq!(
+        hits.map(|marker| marker.center).collect::<Vec<_>>(),
+        [path.bounces[0].position, path.bounces[1].position]
+    );
+    let segments = markers.iter().filter(|marker| marker.material == 6);
+    // 10 markers are spaced over each of the 2 segments, the ends are left to the hits
+    assert_eq!(segments.clone().count(), 9 * 2);
+    for marker in segments {
+        assert!(marker.radius < 0.1);
+        let on_first = marker.center.y == 0.0 && marker.center.w == 0.0;
+        let on_second = marker.center.x == 3.0 && marker.center.y == 0.0;
+        assert!(on_first || on_second);
+    }
+
+    path.escape = Some(PathEscape {
+        direction: cgmath::vec4(0.0, 2.0, 0.0, 0.0),
+        light: cgmath::vec3(1.0, 1.0, 1.0),
+    });
+    let escaping = path.markers(MaterialId(5), MaterialId(6), 0.1);
+    assert!(escaping.len() > markers.len());
+    let furthest = escaping
+        .iter()
+        .map(|marker| marker.center.y)
+        .fold(0.0, f32::max);
+    assert!(furthest > 0.0 && furthest < PixelPath::ESCAPE_LENGTH);
+}
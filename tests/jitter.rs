@@ -0,0 +1,105 @@
+use cgmath::InnerSpace;
+use four_dimentions::{rotate_hue, GpuHyperSphere, GpuMaterial, Jitter, ObjectRef, Scene};
+
+fn objects(scene: &Scene) -> Vec<ObjectRef> {
+    scene.object_refs().collect()
+}
+
+#[test]
+fn jitter_stays_within_its_ranges() {
+    let mut scene = Scene::default();
+    let original = scene.clone();
+    let jitter = Jitter {
+        position: cgmath::vec4(0.5, 0.0, 0.25, 1.0),
+        radius: 0.5,
+        hue: 0.0,
+        seed: 3,
+    };
+    jitter.apply(&mut scene, &objects(&original));
+
+    for (jittered, object) in scene.objects.iter().zip(&original.objects) {
+        let offset = jittered.position() - object.position();
+        assert!(offset.x.abs() <= 0.5 && offset.z.abs() <= 0.25 && offset.w.abs() <= 1.0);
+        assert_eq!(offset.y, 0.0);
+        assert_ne!(offset.w, 0.0);
+        assert_eq!(jittered.material(), object.material());
+    }
+    let (jittered, sphere) = (
+        scene.hyper_sphere(0).unwrap(),
+        original.hyper_sphere(0).unwrap(),
+    );
+    assert!(jittered.radius >= sphere.radius * 0.5 && jittered.radius <= sphere.radius * 1.5);
+    assert_eq!(scene.materials.len(), original.materials.len());
+
+    let mut again = original.clone();
+    jitter.apply(&mut again, &objects(&original));
+    assert_eq!(
+        again.hyper_sphere(0).unwrap().center,
+        scene.hyper_sphere(0).unwrap().center
+    );
+}
+
+#[test]
+fn only_listed_objects_change() {
+    let mut scene = Scene::default();
+    let original = scene.clone();
+    Jitter::default().apply(
+        &mut scene,
+        &[ObjectRef::HyperPlane(0), ObjectRef::HyperPlane(5)],
+    );
+    assert_eq!(
+        scene.hyper_sphere(0).unwrap().center,
+        original.hyper_sphere(0).unwrap().center
+    );
+    assert_ne!(
+        scene.hyper_plane(0).unwrap().point,
+        original.hyper_plane(0).unwrap().point
+    );
+}
+
+#[test]
+fn hue_jitter_copies_materials() {
+    let mut scene = Scene::empty();
+    let red = scene.add_material(
+        "Red",
+        GpuMaterial {
+            base_color: cgmath::vec3(0.8, 0.1, 0.1),
+            ..Default::default()
+        },
+    );
+    for x in 0..3 {
+        let sphere = GpuHyperSphere {
+            center: cgmath::vec4(x as f32, 1.0, 0.0, 0.0),
+            velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            radius: 0.5,
+            material: red.0,
+            inner_radius: 0.0,
+        };
+        scene.add_hyper_sphere("Ball", sphere);
+    }
+    let jitter = Jitter {
+        position: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        radius: 0.0,
+        hue: 0.25,
+        seed: 1,
+    };
+    let balls = objects(&scene);
+    jitter.apply(&mut scene, &balls);
+
+    assert_eq!(scene.materials.len(), 4);
+    assert_eq!(scene.materials[0].base_color, cgmath::vec3(0.8, 0.1, 0.1));
+    for object in &scene.objects {
+        assert_ne!(object.material(), red);
+        assert_eq!(scene.material_name(object.material()), Some("Red"));
+    }
+}
+
+#[test]
+fn hue_rotation_keeps_grays_and_wraps_around() {
+    let gray = cgmath::vec3(0.3, 0.3, 0.3);
+    assert!((rotate_hue(gray, 0.2) - gray).magnitude() < 1e-5);
+    let color = cgmath::vec3(0.8, 0.4, 0.1);
+    assert!((rotate_hue(color, 1.0) - color).magnitude() < 1e-5);
+    // a third of a turn swaps the channels around
+    assert!((rotate_hue(color, 1.0 / 3.0) - cgmath::vec3(0.1, 0.8, 0.4)).magnitude() < 1e-5);
+}
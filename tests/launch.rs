@@ -0,0 +1,43 @@
+use four_dimentions::{Config, LaunchOptions};
+
+fn parse(args: &[&str]) -> Result<LaunchOptions, String> {
+    LaunchOptions::parse(args.iter().map(|arg| arg.to_string()))
+}
+
+#[test]
+fn arguments_override_the_config() {
+    let options = parse(&[
+        "--scene",
+        "start.script",
+        "--samples",
+        "4",
+        "--bounces",
+        "2",
+        "--size",
+        "640x480",
+        "--screenshot",
+        "out.png",
+        "--exit",
+    ])
+    .unwrap();
+    assert_eq!(options.size, Some((640, 480)));
+    assert_eq!(options.screenshot, Some("out.png".into()));
+    assert!(options.exit);
+
+    let mut config = Config::default();
+    options.apply(&mut config);
+    assert_eq!(config.startup_scene, Some("start.script".into()));
+    assert_eq!(config.sample_count, 4);
+    assert_eq!(config.bounce_count, 2);
+}
+
+#[test]
+fn bad_arguments_are_errors() {
+    assert!(parse(&["--samples"]).is_err());
+    assert!(parse(&["--samples", "0"]).is_err());
+    assert!(parse(&["--size", "640"]).is_err());
+    assert!(parse(&["--size", "0x480"]).is_err());
+    assert!(parse(&["--exit"]).is_err());
+    assert!(parse(&["--frobnicate"]).is_err());
+    assert_eq!(parse(&[]).unwrap(), LaunchOptions::default());
+}
@@ -0,0 +1,41 @@
+use four_dimentions::{Config, Dock, Layout, Panel};
+
+#[test]
+fn panels_only_move_within_their_dock() {
+    let mut layout = Layout::default();
+    assert_eq!(
+        layout.docked(Dock::Left),
+        [Panel::Camera, Panel::RenderSettings, Panel::Scene]
+    );
+    assert!(layout.move_down(Panel::Camera));
+    assert_eq!(
+        layout.docked(Dock::Left),
+        [Panel::RenderSettings, Panel::Camera, Panel::Scene]
+    );
+    assert!(!layout.move_up(Panel::RenderSettings));
+    assert!(!layout.move_down(Panel::Console));
+
+    layout.set_dock(Panel::Scene, Dock::Floating);
+    assert_eq!(layout.docked(Dock::Floating), [Panel::Scene]);
+    assert!(!layout.move_up(Panel::Scene));
+}
+
+#[test]
+fn layouts_have_every_panel_once() {
+    let layout = Layout::new([(Panel::Console, Dock::Hidden), (Panel::Console, Dock::Left)]);
+    assert_eq!(layout.panels().len(), Panel::ALL.len());
+    assert_eq!(layout.dock(Panel::Console), Some(Dock::Hidden));
+    assert_eq!(layout.panels()[0].0, Panel::Console);
+    for panel in Panel::ALL.into_iter().filter(|&p| p != Panel::Console) {
+        assert_eq!(layout.dock(panel), Layout::default().dock(panel));
+    }
+}
+
+#[test]
+fn layout_is_saved_in_the_config() {
+    let mut config = Config::default();
+    config.layout.set_dock(Panel::Materials, Dock::Floating);
+    config.layout.move_up(Panel::Scene);
+    let loaded = Config::parse(&config.to_toml());
+    assert_eq!(loaded.layout, config.layout);
+}
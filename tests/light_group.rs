@@ -0,0 +1,134 @@
+mod common;
+
+use four_dimentions::{GpuMaterial, LightGroup, RenderPath, Renderer, Scene};
+
+#[test]
+fn scenes_hold_at_most_max_light_groups() {
+    let mut scene = Scene::empty();
+    for i in 0..LightGroup::MAX {
+        assert_eq!(
+            scene.add_light_group(LightGroup::new(format!("{i}"))),
+            Some(i as u32 + 1)
+        );
+    }
+    assert_eq!(scene.add_light_group(LightGroup::new("Extra")), None);
+}
+
+#[test]
+fn weights_come_from_enabled_groups() {
+    let mut scene = Scene::empty();
+    scene.add_light_group(LightGroup {
+        intensity: 3.0,
+        ..LightGroup::new("Bright")
+    });
+    scene.add_light_group(LightGroup {
+        enabled: false,
+        ..LightGroup::new("Off")
+    });
+    assert_eq!(
+        scene.light_group_weights(),
+        cgmath::vec4(3.0, 0.0, 1.0, 1.0)
+    );
+}
+
+#[test]
+fn removing_a_light_group_renumbers_materials() {
+    let mut scene = Scene::empty();
+    scene.clear_materials();
+    for name in ["A", "B", "C"] {
+        scene.add_light_group(LightGroup::new(name));
+    }
+    for light_group in 0..=3 {
+        scene.add_material(
+            format!("{light_group}"),
+            GpuMaterial {
+                light_group,
+                ..Default::default()
+            },
+        );
+    }
+    scene.remove_light_group(1);
+    let names = scene
+        .light_groups
+        .iter()
+        .map(|light_group| light_group.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["A", "C"]);
+    let groups = scene
+        .materials
+        .iter()
+        .map(|material| material.light_group)
+        .collect::<Vec<_>>();
+    assert_eq!(groups, [0, 1, 0, 2]);
+}
+
+/// Renders `frames` with the first light group weighted by `weight`, using `aovs` or not
+fn render_weighted(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    scene: &common::Scene,
+    weight: f32,
+    aovs: bool,
+) -> Vec<u8> {
+    let camera = four_dimentions::Camera {
+        light_group_aovs: aovs,
+        ..scene.camera
+    };
+    let mut renderer = Renderer::new(device, queue, RenderPath::Compute);
+    renderer.resize(device, queue, common::WIDTH, common::HEIGHT);
+    renderer
+        .upload_objects(device, queue, &scene.hyper_spheres, &scene.hyper_planes)
+        .unwrap();
+    renderer
+        .upload_materials(device, queue, &scene.materials)
+        .unwrap();
+    renderer
+        .upload_environment(queue, &scene.environment.to_gpu())
+        .unwrap();
+    for frame_index in 0..scene.frames {
+        // with aovs the weight only changes on the last frame, and should still apply to every frame
+        let weight = if aovs && frame_index + 1 < scene.frames {
+            1.0
+        } else {
+            weight
+        };
+        renderer
+            .upload_camera(
+                queue,
+                &four_dimentions::GpuCamera {
+                    light_group_weights: cgmath::vec4(weight, 1.0, 1.0, 1.0),
+                    ..camera.to_gpu(frame_index)
+                },
+            )
+            .unwrap();
+        let mut encoder = device.create_command_encoder(&Default::default());
+        renderer.render(device, &mut encoder, camera.render_options());
+        queue.submit([encoder.finish()]);
+    }
+    renderer.read_pixels(device, queue).unwrap()
+}
+
+#[test]
+fn reweighting_aovs_matches_rendering_with_the_weight() {
+    let Some((device, queue)) = common::request_device() else {
+        eprintln!("skipping, no GPU adapter is available");
+        return;
+    };
+    let mut scene = common::default_scene();
+    scene.frames = 4;
+    scene.materials[0].emissive_color = cgmath::vec3(1.0, 0.6, 0.2);
+    scene.materials[0].emission_strength = 0.5;
+    scene.materials[0].light_group = 1;
+
+    let expected = render_weighted(&device, &queue, &scene, 0.25, false);
+    let reweighted = render_weighted(&device, &queue, &scene, 0.25, true);
+    let (mean_difference, outlier_fraction) = common::image_difference(&expected, &reweighted);
+    assert!(
+        common::images_match(mean_difference, outlier_fraction),
+        "mean difference {mean_difference}, outlier fraction {outlier_fraction}"
+    );
+
+    let unweighted = render_weighted(&device, &queue, &scene, 1.0, false);
+    let (mean_difference, _) = common::image_difference(&expected, &unweighted);
+    assert!(mean_difference > common::MAX_MEAN_DIFFERENCE);
+}
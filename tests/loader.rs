@@ -0,0 +1,32 @@
+use four_dimentions::{Scene, SceneChanges, SceneLoad, EXAMPLE_SCRIPT};
+
+#[test]
+fn loads_run_the_script_on_the_given_scene() {
+    let mut scene = Scene::empty();
+    scene.changes = SceneChanges::NONE;
+    let loaded = SceneLoad::start(EXAMPLE_SCRIPT.into(), scene).wait();
+    let scene = loaded.scene.unwrap();
+    assert!(loaded.script.is_ok());
+    assert_eq!(scene.hyper_sphere_count(), 17);
+    assert_eq!(scene.changes, SceneChanges::ALL);
+}
+
+#[test]
+fn compile_errors_give_the_scene_back_unchanged() {
+    let loaded = SceneLoad::start("let = ;".into(), Scene::empty()).wait();
+    assert!(loaded.script.is_err());
+    assert_eq!(loaded.scene.unwrap().hyper_sphere_count(), 0);
+}
+
+#[test]
+fn polling_eventually_finishes() {
+    let mut load = SceneLoad::start(EXAMPLE_SCRIPT.into(), Scene::empty());
+    let loaded = loop {
+        if let Some(loaded) = load.poll() {
+            break loaded;
+        }
+        std::thread::yield_now();
+    };
+    assert!(loaded.script.is_ok());
+    assert!(loaded.scene.is_some());
+}
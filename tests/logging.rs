@@ -0,0 +1,40 @@
+use four_dimentions::ConsoleLogger;
+use log::Log;
+
+fn log(logger: &ConsoleLogger, level: log::Level, message: &str) {
+    logger.log(
+        &log::Record::builder()
+            .level(level)
+            .target("test")
+            .args(format_args!("{message}"))
+            .build(),
+    );
+}
+
+#[test]
+fn records_are_kept_in_order() {
+    let logger = ConsoleLogger::new();
+    log(&logger, log::Level::Warn, "first");
+    log(&logger, log::Level::Error, "second");
+    let entries = logger.entries();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].level, log::Level::Warn);
+    assert_eq!(entries[0].message, "first");
+    assert_eq!(entries[1].target, "test");
+    assert_eq!(entries[1].message, "second");
+}
+
+#[test]
+fn the_oldest_records_are_dropped() {
+    let logger = ConsoleLogger::new();
+    for i in 0..ConsoleLogger::CAPACITY + 5 {
+        log(&logger, log::Level::Info, &i.to_string());
+    }
+    let entries = logger.entries();
+    assert_eq!(entries.len(), ConsoleLogger::CAPACITY);
+    assert_eq!(entries[0].message, "5");
+    drop(entries);
+
+    logger.clear();
+    assert!(logger.entries().is_empty());
+}
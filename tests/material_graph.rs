@@ -0,0 +1,181 @@
+use four_dimentions::{
+    BlendMask, GpuHyperSphere, GpuMaterial, MaterialId, MaterialNode, MaterialProblem,
+    MaterialUser, ObjectRef, Prefab, Scene, Script, MAX_BLEND_DEPTH,
+};
+
+fn blend(mask: BlendMask, a: MaterialId, b: MaterialId) -> GpuMaterial {
+    GpuMaterial {
+        blend_mask: mask as _,
+        blend_a: a.0,
+        blend_b: b.0,
+        blend_scale: cgmath::vec4(1.0, 0.0, 0.0, 0.0),
+        ..Default::default()
+    }
+}
+
+/// Red and blue alternating along x, blended by noise with green
+fn scene() -> (Scene, [MaterialId; 5]) {
+    let mut scene = Scene::empty();
+    let red = scene.add_material("Red", GpuMaterial::default());
+    let blue = scene.add_material("Blue", GpuMaterial::default());
+    let green = scene.add_material("Green", GpuMaterial::default());
+    let checker = scene.add_material("Checker", blend(BlendMask::Checker, red, blue));
+    let noisy = scene.add_material("Noisy", blend(BlendMask::Noise, checker, green));
+    (scene, [red, blue, green, checker, noisy])
+}
+
+#[test]
+fn blends_form_a_tree_of_materials() {
+    let (scene, [red, blue, green, checker, noisy]) = scene();
+    assert_eq!(MaterialNode::new(&scene, red), MaterialNode::Material(red));
+    let node = MaterialNode::new(&scene, noisy);
+    assert_eq!(node.id(), noisy);
+    assert_eq!(node.leaves(), [red, blue, green]);
+    assert!(!node.is_truncated(&scene));
+
+    let checker = MaterialNode::new(&scene, checker);
+    for (x, expected) in [(0.5, red), (1.5, blue), (-0.5, blue), (2.5, red)] {
+        let position = cgmath::vec4(x, 0.0, 0.0, 0.0);
+        for sample in [0.0, 0.5, 0.99] {
+            assert_eq!(checker.resolve(position, sample), expected);
+        }
+    }
+}
+
+#[test]
+fn masks_are_chances_between_zero_and_one() {
+    for i in 0..100 {
+        let position = cgmath::vec4(i as f32 * 0.37, -1.3, i as f32 * 0.11, 2.0);
+        let noise = BlendMask::Noise.value(position);
+        assert!((0.0..=1.0).contains(&noise));
+    }
+    assert_eq!(BlendMask::None.value(cgmath::vec4(1.0, 2.0, 3.0, 4.0)), 0.0);
+    let gradient = |w| BlendMask::Gradient.value(cgmath::vec4(5.0, 0.0, 0.0, w));
+    assert_eq!(gradient(0.0), 0.5);
+    assert_eq!(gradient(-1.0), 0.0);
+    assert_eq!(gradient(0.25), 0.75);
+    assert_eq!(gradient(3.0), 1.0);
+}
+
+#[test]
+fn resolving_a_gradient_picks_materials_by_chance() {
+    let mut scene = Scene::empty();
+    let a = scene.add_material("A", GpuMaterial::default());
+    let b = scene.add_material("B", GpuMaterial::default());
+    let mut gradient = blend(BlendMask::Gradient, a, b);
+    gradient.blend_scale = cgmath::vec4(0.0, 0.0, 0.0, 1.0);
+    let gradient = scene.add_material("Gradient", gradient);
+    let node = MaterialNode::new(&scene, gradient);
+
+    let position = cgmath::vec4(0.0, 0.0, 0.0, 0.25);
+    let picked_b = (0..100)
+        .filter(|&i| node.resolve(position, i as f32 / 100.0) == b)
+        .count();
+    assert_eq!(picked_b, 75);
+}
+
+#[test]
+fn materials_in_blends_are_not_removed() {
+    let (mut scene, [red, blue, green, checker, noisy]) = scene();
+    assert_eq!(
+        scene.remove_material(red),
+        Err(vec![MaterialUser::Blend(checker)])
+    );
+    assert_eq!(
+        scene.remove_material(checker),
+        Err(vec![MaterialUser::Blend(noisy)])
+    );
+    assert_eq!(scene.material_name(red), Some("Red"));
+
+    // blending itself doesn't stop a material from being removed
+    let index = scene.material_index(green).unwrap();
+    scene.materials_mut()[index] = blend(BlendMask::Noise, green, blue);
+    scene.remove_material(noisy).unwrap();
+    scene.remove_material(green).unwrap();
+    scene.remove_material(checker).unwrap();
+    scene.remove_material(red).unwrap();
+}
+
+#[test]
+fn blends_are_uploaded_as_indices() {
+    let (mut scene, [red, blue, _, checker, _]) = scene();
+    let index = scene.material_index(checker).unwrap();
+    scene.materials_mut()[index].blend_a = 100;
+    let materials = scene.sanitized_materials();
+    // the missing material is replaced with the first one
+    assert_eq!(materials[index].blend_a, 0);
+    assert_eq!(scene.material_index(red), Some(0));
+    assert_eq!(
+        materials[index].blend_b,
+        scene.material_index(blue).unwrap() as u32
+    );
+    assert_eq!(
+        scene.material_problems(),
+        [(checker, vec![MaterialProblem::BlendMaterialMissing])]
+    );
+}
+
+fn depth(node: &MaterialNode) -> usize {
+    match node {
+        MaterialNode::Material(_) => 0,
+        MaterialNode::Blend { a, b, .. } => 1 + depth(a).max(depth(b)),
+    }
+}
+
+#[test]
+fn blends_that_contain_themselves_are_cut_off() {
+    let (mut scene, [red, _, _, checker, noisy]) = scene();
+    let index = scene.material_index(checker).unwrap();
+    scene.materials_mut()[index].blend_b = noisy.0;
+    let node = MaterialNode::new(&scene, noisy);
+    assert!(node.is_truncated(&scene));
+    assert!(node.leaves().contains(&red));
+
+    assert_eq!(depth(&node), MAX_BLEND_DEPTH);
+    assert!(scene
+        .material_problems()
+        .iter()
+        .all(|(_, problems)| problems == &[MaterialProblem::BlendTooDeep]));
+}
+
+#[test]
+fn saved_scenes_and_prefabs_keep_blends() {
+    let (mut scene, [.., noisy]) = scene();
+    scene.add_hyper_sphere(
+        "Sphere",
+        GpuHyperSphere {
+            center: cgmath::vec4(2.0, 1.0, 0.0, 0.0),
+            velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            radius: 0.5,
+            material: noisy.0,
+            inner_radius: 0.0,
+        },
+    );
+
+    let mut loaded = Scene::empty();
+    Script::compile(&four_dimentions::scene_script(&scene))
+        .unwrap()
+        .run(&mut loaded, &mut vec![])
+        .unwrap();
+    assert!(loaded.materials == scene.materials);
+
+    let prefab = Prefab::from_objects("Sphere", &scene, &[ObjectRef::HyperSphere(0)]);
+    assert_eq!(
+        prefab.scene.material_names,
+        ["Red", "Blue", "Checker", "Green", "Noisy"]
+    );
+    let mut target = Scene::default();
+    let materials = target.materials.len();
+    prefab.instantiate(&mut target, &Default::default());
+    prefab.instantiate(&mut target, &Default::default());
+    assert_eq!(target.materials.len(), materials + 5);
+    let placed = target.object_material(ObjectRef::HyperSphere(1)).unwrap();
+    assert_eq!(
+        MaterialNode::new(&target, placed)
+            .leaves()
+            .into_iter()
+            .map(|id| target.material_name(id).unwrap())
+            .collect::<Vec<_>>(),
+        ["Red", "Blue", "Green"]
+    );
+}
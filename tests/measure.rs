@@ -0,0 +1,54 @@
+use four_dimentions::{
+    angle_between, camera_ray, pick, Camera, MeasurePoint, Measurement, ObjectRef, Scene,
+};
+
+#[test]
+fn picking_through_the_center_hits_the_sphere() {
+    let scene = Scene::default();
+    let camera = Camera::default().to_gpu(0);
+    let (origin, direction) = camera_ray(&camera, cgmath::vec2(0.0, 0.0), 1.0);
+    let hit = pick(&scene, origin, direction).expect("the camera looks at the sphere");
+    assert_eq!(hit.object, ObjectRef::HyperSphere(0));
+    assert!((hit.distance - 2.0).abs() < 1e-4, "{hit:?}");
+
+    // straight down from the camera is the ground
+    let (origin, direction) = camera_ray(&camera, cgmath::vec2(0.0, -1.0e4), 1.0);
+    let hit = pick(&scene, origin, direction).expect("the ground is below the camera");
+    assert_eq!(hit.object, ObjectRef::HyperPlane(0));
+}
+
+#[test]
+fn measurements_follow_the_objects() {
+    let mut scene = Scene::default();
+    let sphere = MeasurePoint::Object(ObjectRef::HyperSphere(0));
+    let point = MeasurePoint::Point {
+        position: cgmath::vec4(0.0, 1.0, 3.0, 4.0),
+        normal: cgmath::vec4(0.0, 0.0, 1.0, 0.0),
+    };
+    let measurement =
+        Measurement::between(&scene, sphere, point, cgmath::vec4(0.0, 1.0, 0.0, 0.0)).unwrap();
+    assert_eq!(measurement.delta, cgmath::vec4(0.0, 0.0, 3.0, 4.0));
+    assert!((measurement.distance - 5.0).abs() < 1e-6);
+    assert_eq!(measurement.direction_angle, None);
+
+    let ground = MeasurePoint::Object(ObjectRef::HyperPlane(0));
+    let measurement =
+        Measurement::between(&scene, ground, point, scene.hyper_sphere(0).unwrap().center);
+    let angle = measurement.unwrap().direction_angle.unwrap();
+    assert!((angle - 90.0f32.to_radians()).abs() < 1e-5);
+
+    scene.remove_objects(&[ObjectRef::HyperSphere(0)]);
+    assert_eq!(
+        Measurement::between(&scene, sphere, point, cgmath::vec4(0.0, 0.0, 0.0, 0.0)),
+        None
+    );
+}
+
+#[test]
+fn angles_between_vectors() {
+    let x = cgmath::vec4(1.0, 0.0, 0.0, 0.0);
+    let w = cgmath::vec4(0.0, 0.0, 0.0, 2.0);
+    assert!((angle_between(x, w) - 90.0f32.to_radians()).abs() < 1e-6);
+    assert!((angle_between(x, -x) - 180.0f32.to_radians()).abs() < 1e-6);
+    assert_eq!(angle_between(x, cgmath::vec4(0.0, 0.0, 0.0, 0.0)), 0.0);
+}
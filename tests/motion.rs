@@ -0,0 +1,54 @@
+use cgmath::InnerSpace;
+use four_dimentions::{Camera, CameraMotion};
+
+#[test]
+fn distance_and_turning_dont_depend_on_the_frame_rate() {
+    let target = cgmath::vec4(3.0, 0.0, 0.0, 0.0);
+    let run = |steps: usize| {
+        let mut motion = CameraMotion::default();
+        motion.damping = 0.3;
+        let mut camera = Camera::default();
+        motion.add_rotation(0.0, 1.0, 0.0, 0.0);
+        let mut distance = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+        for _ in 0..steps {
+            distance += motion.movement(target, 3.0, 1.0 / steps as f32);
+            motion.rotate(&mut camera, 1.0 / steps as f32);
+        }
+        (distance, camera.yaw)
+    };
+    let (slow_distance, slow_yaw) = run(20);
+    let (fast_distance, fast_yaw) = run(240);
+    assert!((slow_distance - fast_distance).magnitude() < 1e-3);
+    assert!((slow_yaw - fast_yaw).abs() < 1e-4);
+}
+
+#[test]
+fn speeds_up_and_slows_down_over_the_acceleration_time() {
+    let mut motion = CameraMotion::default();
+    motion.acceleration_time = 0.5;
+    let target = cgmath::vec4(0.0, 0.0, 2.0, 0.0);
+    motion.movement(target, 2.0, 0.25);
+    assert!((motion.velocity().magnitude() - 1.0).abs() < 1e-5);
+    motion.movement(target, 2.0, 0.25);
+    assert_eq!(motion.velocity(), target);
+
+    let zero = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+    motion.movement(zero, 2.0, 0.25);
+    assert!(motion.velocity().magnitude() > 0.0);
+    motion.movement(zero, 2.0, 0.25);
+    assert_eq!(motion.velocity(), zero);
+}
+
+#[test]
+fn no_smoothing_moves_and_turns_instantly() {
+    let mut motion = CameraMotion::default();
+    motion.acceleration_time = 0.0;
+    motion.damping = 0.0;
+    let mut camera = Camera::default();
+    let pitch = camera.pitch;
+    motion.add_rotation(0.5, 0.0, 0.0, 0.0);
+    motion.rotate(&mut camera, 0.016);
+    assert_eq!(camera.pitch, pitch + 0.5);
+    let movement = motion.movement(cgmath::vec4(1.0, 0.0, 0.0, 0.0), 1.0, 0.5);
+    assert_eq!(movement, cgmath::vec4(0.5, 0.0, 0.0, 0.0));
+}
@@ -0,0 +1,198 @@
+use four_dimentions::{
+    GpuHyperSphere, GpuMaterial, GpuNodeInstruction, MaterialProblem, NodeGraph, NodeGraphError,
+    NodeKind, ObjectRef, Prefab, Scene, Script, MAX_NODE_INSTRUCTIONS, NO_REGISTER,
+};
+
+/// Base color from a color multiplied by a checker, emission from a value
+fn graph() -> NodeGraph {
+    let mut graph = NodeGraph::default();
+    let output = graph.nodes[0].id;
+    let color = graph.add_node(NodeKind::Color, [0.0, 0.0]);
+    graph.node_mut(color).unwrap().value = cgmath::vec4(1.0, 0.5, 0.25, 0.0);
+    let checker = graph.add_node(NodeKind::Checker, [0.0, 100.0]);
+    graph.node_mut(checker).unwrap().value = cgmath::vec4(1.0, 0.0, 0.0, 0.0);
+    let multiply = graph.add_node(NodeKind::Multiply, [200.0, 50.0]);
+    graph.connect(Some(color), multiply, 0);
+    graph.connect(Some(checker), multiply, 1);
+    graph.connect(Some(multiply), output, 0);
+    let value = graph.add_node(NodeKind::Value, [200.0, 150.0]);
+    graph.node_mut(value).unwrap().value = cgmath::vec4(0.75, 0.0, 0.0, 0.0);
+    graph.connect(Some(value), output, 1);
+    // not connected to the output, so it isn't compiled
+    graph.add_node(NodeKind::Noise, [0.0, 200.0]);
+    graph
+}
+
+fn run(program: &[GpuNodeInstruction], x: f32) -> GpuMaterial {
+    let mut material = GpuMaterial::default();
+    GpuNodeInstruction::run(
+        program,
+        &mut material,
+        cgmath::vec4(x, 0.0, 0.0, 0.0),
+        cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+        cgmath::vec4(0.0, -1.0, 0.0, 0.0),
+    );
+    material
+}
+
+#[test]
+fn graphs_compile_with_inputs_before_the_nodes_that_read_them() {
+    let program = graph().compile().unwrap();
+    assert_eq!(program.len(), 5);
+    let output = program.last().unwrap();
+    assert_eq!(output.kind, NodeKind::Output as u32);
+    for (i, instruction) in program.iter().enumerate() {
+        for register in [instruction.a, instruction.b, instruction.c] {
+            assert!(register == NO_REGISTER || (register as usize) < i);
+        }
+    }
+
+    let lit = run(&program, 0.5);
+    assert_eq!(lit.base_color, cgmath::vec3(0.0, 0.0, 0.0));
+    assert_eq!(lit.emissive_color, cgmath::vec3(0.75, 0.75, 0.75));
+    let dark = run(&program, 1.5);
+    assert_eq!(dark.base_color, cgmath::vec3(1.0, 0.5, 0.25));
+}
+
+#[test]
+fn unconnected_outputs_keep_the_material_and_results_are_clamped() {
+    let mut graph = NodeGraph::default();
+    let output = graph.nodes[0].id;
+    let divide = graph.add_node(NodeKind::Divide, [0.0, 0.0]);
+    graph.node_mut(divide).unwrap().value = cgmath::vec4(0.0, 1.0, -1.0, 0.0);
+    graph.connect(Some(divide), output, 0);
+    let material = run(&graph.compile().unwrap(), 0.0);
+    // 0 / 0, 1 / 1 and -1 / -1 are each applied to both sides of the division
+    assert_eq!(material.base_color, cgmath::vec3(0.0, 1.0, 1.0));
+    assert_eq!(
+        material.emissive_color,
+        GpuMaterial::default().emissive_color
+    );
+
+    let fresnel = graph.add_node(NodeKind::Fresnel, [0.0, 0.0]);
+    graph.connect(Some(fresnel), output, 0);
+    let head_on = run(&graph.compile().unwrap(), 0.0).base_color.x;
+    assert!((head_on - 0.04).abs() < 1e-4, "{head_on}");
+}
+
+#[test]
+fn loops_and_large_graphs_are_rejected() {
+    let mut graph = NodeGraph::default();
+    let output = graph.nodes[0].id;
+    let a = graph.add_node(NodeKind::Add, [0.0, 0.0]);
+    let b = graph.add_node(NodeKind::Add, [0.0, 0.0]);
+    graph.connect(Some(a), b, 0);
+    graph.connect(Some(b), a, 0);
+    graph.connect(Some(a), output, 0);
+    assert_eq!(graph.compile(), Err(NodeGraphError::Cycle));
+    graph.remove_node(b);
+    assert_eq!(graph.compile().unwrap().len(), 2);
+
+    let mut previous = a;
+    for _ in 0..MAX_NODE_INSTRUCTIONS {
+        let next = graph.add_node(NodeKind::Add, [0.0, 0.0]);
+        graph.connect(Some(previous), next, 0);
+        previous = next;
+    }
+    graph.connect(Some(previous), output, 0);
+    assert_eq!(graph.compile(), Err(NodeGraphError::TooManyNodes));
+
+    let mut scene = Scene::empty();
+    let material = scene.add_material("Nodes", GpuMaterial::default());
+    *scene.node_graph_mut(material) = graph;
+    assert_eq!(
+        scene.material_problems(),
+        [(
+            material,
+            vec![MaterialProblem::Nodes(NodeGraphError::TooManyNodes)]
+        )]
+    );
+    let (materials, programs) = scene.compiled_materials();
+    assert_eq!(materials[0].node_program_length, 0);
+    assert!(programs.is_empty());
+}
+
+#[test]
+fn the_output_node_stays() {
+    let mut graph = graph();
+    let output = graph.nodes[0].id;
+    graph.remove_node(output);
+    assert_eq!(graph.nodes[0].kind, NodeKind::Output);
+    assert_eq!(graph.add_node(NodeKind::Output, [5.0, 5.0]), output);
+    assert_eq!(graph.nodes[0].position, [5.0, 5.0]);
+    assert_eq!(
+        graph
+            .nodes
+            .iter()
+            .filter(|node| node.kind == NodeKind::Output)
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn compiled_programs_are_placed_one_after_another() {
+    let mut scene = Scene::empty();
+    let plain = scene.add_material("Plain", GpuMaterial::default());
+    let first = scene.add_material("First", GpuMaterial::default());
+    let second = scene.add_material("Second", GpuMaterial::default());
+    *scene.node_graph_mut(first) = graph();
+    *scene.node_graph_mut(second) = graph();
+    let (materials, programs) = scene.compiled_materials();
+    assert_eq!(programs.len(), 10);
+    let index = |id| scene.material_index(id).unwrap();
+    assert_eq!(materials[index(plain)].node_program_length, 0);
+    assert_eq!(materials[index(first)].node_program, 0);
+    assert_eq!(materials[index(second)].node_program, 5);
+    assert_eq!(materials[index(second)].node_program_length, 5);
+
+    scene.remove_material(first).unwrap();
+    assert!(!scene.node_graphs.contains_key(&first));
+}
+
+#[test]
+fn graphs_survive_scripts_and_prefabs() {
+    let mut scene = Scene::empty();
+    scene.add_material("Plain", GpuMaterial::default());
+    let material = scene.add_material("Nodes", GpuMaterial::default());
+    let mut graph = graph();
+    // leave a gap in the ids, the script refers to nodes by position
+    let removed = graph.add_node(NodeKind::Value, [0.0, 0.0]);
+    graph.remove_node(removed);
+    let normal = graph.add_node(NodeKind::Normal, [10.0, 20.0]);
+    let output = graph.nodes[0].id;
+    graph.connect(Some(normal), output, 1);
+    *scene.node_graph_mut(material) = graph;
+    scene.add_hyper_sphere(
+        "Sphere",
+        GpuHyperSphere {
+            center: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            radius: 1.0,
+            material: material.0,
+            inner_radius: 0.0,
+        },
+    );
+
+    let mut loaded = Scene::empty();
+    Script::compile(&four_dimentions::scene_script(&scene))
+        .unwrap()
+        .run(&mut loaded, &mut vec![])
+        .unwrap();
+    let loaded_id = loaded.material_ids[1];
+    assert_eq!(
+        loaded.node_graphs[&loaded_id].compile(),
+        scene.node_graphs[&material].compile()
+    );
+    assert_eq!(loaded.node_graphs.len(), 1);
+
+    let prefab = Prefab::from_objects("Sphere", &scene, &[ObjectRef::HyperSphere(0)]);
+    let mut target = Scene::empty();
+    prefab.instantiate(&mut target, &Default::default());
+    prefab.instantiate(&mut target, &Default::default());
+    assert_eq!(target.materials.len(), 1);
+    assert_eq!(
+        target.node_graphs[&target.material_ids[0]],
+        scene.node_graphs[&material]
+    );
+}
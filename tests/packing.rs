@@ -0,0 +1,87 @@
+mod common;
+
+use four_dimentions::{
+    pack_halves, unpack_halves, GpuHyperSphere, ObjectFormat, RenderPath, Renderer,
+};
+
+#[test]
+fn halves_pack_like_wgsl() {
+    assert_eq!(pack_halves(1.0, -2.0), 0xc000_3c00);
+    assert_eq!(pack_halves(0.0, 65504.0), 0x7bff_0000);
+    // too large for a half
+    assert_eq!(pack_halves(1e6, 0.0), 0x7c00);
+    // the smallest subnormal half
+    assert_eq!(pack_halves(2.0f32.powi(-24), 0.0), 1);
+    // 1 + 2^-11 is halfway between two halves and rounds up
+    assert_eq!(pack_halves(1.0 + 2.0f32.powi(-11), 0.0), 0x3c01);
+}
+
+#[test]
+fn halves_unpack_like_wgsl() {
+    assert_eq!(unpack_halves(0xc000_3c00), (1.0, -2.0));
+    assert_eq!(unpack_halves(0x7bff_0000), (0.0, 65504.0));
+    assert_eq!(unpack_halves(0x7c00), (f32::INFINITY, 0.0));
+    assert_eq!(unpack_halves(1), (2.0f32.powi(-24), 0.0));
+    // rounded to the nearest half on the way
+    assert_eq!(
+        unpack_halves(pack_halves(1000.3, -0.1)),
+        (1000.5, -0.099975586)
+    );
+}
+
+#[test]
+fn auto_packs_large_scenes() {
+    assert!(!ObjectFormat::Auto.packs(ObjectFormat::AUTO_PACK_OBJECTS - 1));
+    assert!(ObjectFormat::Auto.packs(ObjectFormat::AUTO_PACK_OBJECTS));
+    assert!(!ObjectFormat::Full.packs(usize::MAX));
+    assert!(ObjectFormat::Packed.packs(0));
+}
+
+#[test]
+fn moving_one_object_only_uploads_it_again() {
+    let Some((device, queue)) = common::request_device() else {
+        eprintln!("skipping, no GPU adapter is available");
+        return;
+    };
+    let mut scene = common::default_scene();
+    for i in 0..64 {
+        scene.hyper_spheres.push(GpuHyperSphere {
+            center: cgmath::vec4(i as f32 - 32.0, 0.5, 6.0, 0.0),
+            radius: 0.4,
+            ..scene.hyper_spheres[0]
+        });
+    }
+    let render_path = RenderPath::for_device(&device);
+    let mut renderer = Renderer::new(&device, &queue, render_path);
+    renderer.resize(&device, &queue, common::WIDTH, common::HEIGHT);
+    renderer
+        .upload_objects(&device, &queue, &scene.hyper_spheres, &scene.hyper_planes)
+        .unwrap();
+    let full = renderer.object_upload_size();
+    renderer
+        .upload_materials(&device, &queue, &scene.materials)
+        .unwrap();
+    renderer
+        .upload_camera(&queue, &scene.camera.to_gpu(0))
+        .unwrap();
+
+    scene.hyper_spheres[0].center.y += 0.5;
+    renderer
+        .upload_objects(&device, &queue, &scene.hyper_spheres, &scene.hyper_planes)
+        .unwrap();
+    assert!(renderer.object_upload_size() > 0);
+    assert!(renderer.object_upload_size() * 4 < full, "{full}");
+    renderer
+        .upload_objects(&device, &queue, &scene.hyper_spheres, &scene.hyper_planes)
+        .unwrap();
+    assert_eq!(renderer.object_upload_size(), 0);
+
+    let mut encoder = device.create_command_encoder(&Default::default());
+    renderer.render(&device, &mut encoder, scene.camera.render_options());
+    queue.submit([encoder.finish()]);
+    // the same as uploading everything to a new renderer
+    assert_eq!(
+        renderer.read_pixels(&device, &queue).unwrap(),
+        common::render(&device, &queue, render_path, &scene)
+    );
+}
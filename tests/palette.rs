@@ -0,0 +1,122 @@
+mod common;
+
+use four_dimentions::{BlendMask, EmissionPattern, GpuMaterial, Palette, Scene, SwatchRenderer};
+
+#[test]
+fn palettes_round_trip_through_json() {
+    let mut scene = Scene::default();
+    scene.add_material(
+        "Glowing \"Checker\"",
+        GpuMaterial {
+            base_color: cgmath::vec3(0.25, 0.5, 0.75),
+            emissive_color: cgmath::vec3(1.0, 0.5, 0.0),
+            emission_strength: 2.5,
+            bump_strength: 0.1,
+            bump_scale: 8.0,
+            emission_pattern: EmissionPattern::Checker as _,
+            emission_pattern_scale: cgmath::vec4(1.0, 2.0, 0.0, 3.0),
+            transmission: 0.75,
+            ior: 1.33,
+            dispersion: 0.01,
+            priority: 2,
+            absorption_color: cgmath::vec3(0.2, 0.6, 0.9),
+            absorption_density: 1.5,
+            clearcoat: 0.8,
+            clearcoat_roughness: 0.05,
+            sheen: 0.5,
+            sheen_color: cgmath::vec3(0.9, 0.7, 0.8),
+            light_group: 0,
+            // blends aren't kept in palettes
+            blend_mask: BlendMask::Noise as _,
+            ..Default::default()
+        },
+    );
+    let palette = Palette::from_scene(&scene);
+    assert!(palette
+        .materials
+        .iter()
+        .all(|(_, material)| material.blend_mask().is_none()));
+    let imported = Palette::from_json(&palette.to_json()).unwrap();
+
+    assert_eq!(imported.materials.len(), scene.materials.len());
+    for ((name, material), (expected_name, expected)) in
+        imported.materials.iter().zip(&palette.materials)
+    {
+        assert_eq!(name, expected_name);
+        assert_eq!(material.base_color, expected.base_color);
+        assert_eq!(material.emissive_color, expected.emissive_color);
+        assert_eq!(material.emission_strength, expected.emission_strength);
+        assert_eq!(material.bump_strength, expected.bump_strength);
+        assert_eq!(material.bump_scale, expected.bump_scale);
+        assert_eq!(material.emission_pattern, expected.emission_pattern);
+        assert_eq!(
+            material.emission_pattern_scale,
+            expected.emission_pattern_scale
+        );
+        assert_eq!(material.transmission, expected.transmission);
+        assert_eq!(material.ior, expected.ior);
+        assert_eq!(material.dispersion, expected.dispersion);
+        assert_eq!(material.priority, expected.priority);
+        assert_eq!(material.absorption_color, expected.absorption_color);
+        assert_eq!(material.absorption_density, expected.absorption_density);
+        assert_eq!(material.clearcoat, expected.clearcoat);
+        assert_eq!(material.clearcoat_roughness, expected.clearcoat_roughness);
+        assert_eq!(material.sheen, expected.sheen);
+        assert_eq!(material.sheen_color, expected.sheen_color);
+    }
+}
+
+#[test]
+fn missing_fields_use_the_defaults() {
+    let palette = Palette::from_json(r#"[{"name": "Red", "base_color": [1, 0, 0]}, {}]"#).unwrap();
+    let default = GpuMaterial::default();
+    assert_eq!(palette.materials[0].0, "Red");
+    assert_eq!(
+        palette.materials[0].1.base_color,
+        cgmath::vec3(1.0, 0.0, 0.0)
+    );
+    assert_eq!(palette.materials[0].1.bump_scale, default.bump_scale);
+    assert_eq!(palette.materials[1].0, "Material 1");
+    assert_eq!(palette.materials[1].1.base_color, default.base_color);
+}
+
+#[test]
+fn invalid_palettes_are_errors() {
+    assert!(Palette::from_json(r#"{"name": "Red"}"#).is_err());
+    assert!(Palette::from_json(r#"[{"base_color": [1, 0]}]"#).is_err());
+    assert!(Palette::from_json(r#"[{"emission_pattern": "Spots"}]"#).is_err());
+    assert!(Palette::from_json(r#"[{"name": "Red",}]"#).is_err());
+    let Err(error) = Palette::from_json("[\n{},\n{\"bump_scale\": \"big\"}]") else {
+        panic!("a string isn't a number");
+    };
+    assert!(error.contains("material 1"), "{error}");
+}
+
+#[test]
+fn swatches_show_the_material() {
+    let Some((device, queue)) = common::request_device() else {
+        eprintln!("skipping swatches_show_the_material, no GPU adapter is available");
+        return;
+    };
+    let mut swatch_renderer = SwatchRenderer::new(&device, &queue);
+    let pixels = swatch_renderer
+        .render(
+            &device,
+            &queue,
+            &GpuMaterial {
+                base_color: cgmath::vec3(0.9, 0.1, 0.1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        pixels.len(),
+        SwatchRenderer::SIZE * SwatchRenderer::SIZE * 4
+    );
+    let center = (SwatchRenderer::SIZE / 2 * SwatchRenderer::SIZE + SwatchRenderer::SIZE / 2) * 4;
+    assert!(
+        pixels[center] > pixels[center + 2],
+        "{:?}",
+        &pixels[center..center + 4]
+    );
+}
@@ -0,0 +1,171 @@
+use cgmath::InnerSpace;
+use four_dimentions::{GpuHyperSphere, GpuMaterial, Physics, PhysicsMode, Scene};
+
+fn ball(center: cgmath::Vector4<f32>, velocity: cgmath::Vector4<f32>) -> GpuHyperSphere {
+    GpuHyperSphere {
+        center,
+        velocity,
+        radius: 0.5,
+        material: 0,
+        inner_radius: 0.0,
+    }
+}
+
+#[test]
+fn balls_bounce_on_the_ground() {
+    let mut scene = Scene::default();
+    scene.hyper_sphere_mut(0).unwrap().center.y = 3.0;
+    let mut physics = Physics::default();
+    physics.play(&scene);
+    let mut lowest = f32::INFINITY;
+    let mut highest_after_bounce = f32::NEG_INFINITY;
+    for frame in 0..240 {
+        physics.step(&mut scene, 1.0 / 60.0);
+        let y = scene.hyper_sphere(0).unwrap().center.y;
+        lowest = lowest.min(y);
+        if frame > 120 {
+            highest_after_bounce = highest_after_bounce.max(y);
+        }
+    }
+    assert!(lowest >= 1.0 - 1e-3, "{lowest}");
+    // elastic, so it comes most of the way back up
+    assert!(highest_after_bounce > 2.5, "{highest_after_bounce}");
+}
+
+#[test]
+fn equal_balls_swap_velocities_head_on() {
+    let mut scene = Scene::empty();
+    scene.add_hyper_sphere(
+        "A",
+        ball(
+            cgmath::vec4(-1.0, 0.0, 0.0, 0.0),
+            cgmath::vec4(0.0, 0.0, 0.0, 1.0),
+        ),
+    );
+    scene.add_hyper_sphere(
+        "B",
+        ball(
+            cgmath::vec4(-1.0, 0.0, 0.0, 2.0),
+            cgmath::vec4(0.0, 0.0, 0.0, -1.0),
+        ),
+    );
+    let mut physics = Physics::default();
+    physics.gravity = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+    physics.play(&scene);
+    for _ in 0..60 {
+        physics.step(&mut scene, 1.0 / 60.0);
+    }
+    let [a, b] = [
+        scene.hyper_sphere(0).unwrap(),
+        scene.hyper_sphere(1).unwrap(),
+    ];
+    assert!((a.velocity - cgmath::vec4(0.0, 0.0, 0.0, -1.0)).magnitude() < 1e-4);
+    assert!((b.velocity - cgmath::vec4(0.0, 0.0, 0.0, 1.0)).magnitude() < 1e-4);
+    assert!((b.center - a.center).magnitude() >= 1.0 - 1e-4);
+}
+
+#[test]
+fn reset_restores_the_starting_scene() {
+    let mut scene = Scene::default();
+    let start = *scene.hyper_sphere(0).unwrap();
+    scene.hyper_sphere_mut(0).unwrap().center.y = 3.0;
+    let mut physics = Physics::default();
+    physics.play(&scene);
+    physics.step(&mut scene, 0.5);
+    physics.pause();
+    physics.step(&mut scene, 0.5);
+    assert!(scene.hyper_sphere(0).unwrap().center.y < 3.0);
+
+    physics.reset(&mut scene);
+    assert!(!physics.running);
+    assert_eq!(scene.hyper_sphere(0).unwrap().center.y, 3.0);
+    assert_eq!(scene.hyper_sphere(0).unwrap().velocity, start.velocity);
+}
+
+fn orbital_scene() -> (Scene, Physics) {
+    let mut scene = Scene::empty();
+    let sun = scene.add_material(
+        "Sun",
+        GpuMaterial {
+            emissive_color: cgmath::vec3(1.0, 0.9, 0.6),
+            emission_strength: 5.0,
+            ..Default::default()
+        },
+    );
+    let planet = scene.add_material("Planet", GpuMaterial::default());
+    scene.add_hyper_sphere(
+        "Sun",
+        GpuHyperSphere {
+            radius: 1.0,
+            material: sun.0,
+            ..ball(
+                cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            )
+        },
+    );
+    // the speed for a circular orbit, where G M / r³ = v² / r
+    let radius = 4.0f32;
+    let speed = (10.0 / (radius * radius)).sqrt();
+    scene.add_hyper_sphere(
+        "Planet",
+        GpuHyperSphere {
+            radius: 0.2,
+            material: planet.0,
+            ..ball(
+                cgmath::vec4(radius, 0.0, 0.0, 0.0),
+                cgmath::vec4(0.0, 0.0, 0.0, speed),
+            )
+        },
+    );
+    let mut physics = Physics::default();
+    physics.mode = PhysicsMode::Orbital;
+    physics.gravitational_constant = 10.0;
+    (scene, physics)
+}
+
+#[test]
+fn suns_pull_planets_around_them() {
+    let (mut scene, mut physics) = orbital_scene();
+    physics.play(&scene);
+    for _ in 0..120 {
+        physics.step(&mut scene, 1.0 / 60.0);
+        let distance = scene.hyper_sphere(1).unwrap().center.magnitude();
+        assert!((distance - 4.0).abs() < 0.1, "{distance}");
+    }
+    // the sun doesn't move and the planet has gone a good way around through w
+    assert_eq!(
+        scene.hyper_sphere(0).unwrap().center,
+        cgmath::vec4(0.0, 0.0, 0.0, 0.0)
+    );
+    assert!(scene.hyper_sphere(1).unwrap().center.w > 1.0);
+}
+
+#[test]
+fn trails_are_limited_and_removed_on_reset() {
+    let (mut scene, mut physics) = orbital_scene();
+    physics.trail_interval = 0.1;
+    physics.trail_length = 5;
+    physics.play(&scene);
+    for _ in 0..120 {
+        physics.step(&mut scene, 1.0 / 60.0);
+    }
+    // only the planet moves, so it is the only one with a trail
+    let names = |scene: &Scene| {
+        scene
+            .objects
+            .iter()
+            .filter(|object| object.hyper_sphere().is_some())
+            .map(|object| object.name.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(scene.hyper_sphere_count(), 2 + 5);
+    assert!(names(&scene)[2..].iter().all(|name| name == "Trail"));
+
+    physics.reset(&mut scene);
+    assert_eq!(names(&scene), ["Sun", "Planet"]);
+    assert_eq!(
+        scene.hyper_sphere(1).unwrap().center,
+        cgmath::vec4(4.0, 0.0, 0.0, 0.0)
+    );
+}
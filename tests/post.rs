@@ -0,0 +1,126 @@
+//! The GPU tests pass without doing anything when there is no GPU adapter or it can't run compute shaders.
+
+mod common;
+
+use common::*;
+use four_dimentions::{PostEffect, PostProcessor, RenderPath, Renderer};
+
+/// Renders the default scene and runs `effects` over it, returning the input and output pixels
+fn post_process(effects: &[PostEffect]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let Some((device, queue)) = request_device() else {
+        eprintln!("skipping, no GPU adapter is available");
+        return None;
+    };
+    if !PostProcessor::is_supported(&device) {
+        eprintln!("skipping, the adapter doesn't support compute shaders");
+        return None;
+    }
+
+    let scene = default_scene();
+    let mut renderer = Renderer::new(&device, &queue, RenderPath::for_device(&device));
+    renderer.resize(&device, &queue, WIDTH, HEIGHT);
+    renderer
+        .upload_objects(&device, &queue, &scene.hyper_spheres, &scene.hyper_planes)
+        .unwrap();
+    renderer
+        .upload_materials(&device, &queue, &scene.materials)
+        .unwrap();
+    renderer
+        .upload_environment(&queue, &scene.environment.to_gpu())
+        .unwrap();
+    renderer
+        .upload_camera(&queue, &scene.camera.to_gpu(0))
+        .unwrap();
+
+    let mut post_processor = PostProcessor::new(&device, renderer.texture_view(), 1, 1);
+    assert!(post_processor.set_input(&device, renderer.texture_view(), WIDTH, HEIGHT));
+    let mut encoder = device.create_command_encoder(&Default::default());
+    renderer.render(&device, &mut encoder, scene.camera.render_options());
+    post_processor.render(
+        &queue,
+        &mut encoder,
+        effects,
+        scene.camera.color_management,
+        0,
+    );
+    queue.submit([encoder.finish()]);
+
+    Some((
+        renderer.read_pixels(&device, &queue).unwrap(),
+        read_texture(&device, &queue, post_processor.texture()),
+    ))
+}
+
+fn brightness(pixels: &[u8], x: usize, y: usize) -> u32 {
+    let pixel = &pixels[(x + y * WIDTH) * 4..][..3];
+    pixel.iter().map(|&c| c as u32).sum()
+}
+
+#[test]
+fn effects_without_strength_keep_the_image() {
+    // both an odd and an even number of passes have to end up in the output texture
+    for count in [1, 2, 3] {
+        let effects = vec![PostEffect::FilmGrain { strength: 0.0 }; count];
+        let Some((input, output)) = post_process(&effects) else {
+            return;
+        };
+        let largest_difference = input
+            .iter()
+            .zip(&output)
+            .map(|(&a, &b)| a.abs_diff(b))
+            .max()
+            .unwrap();
+        assert!(
+            largest_difference <= 1,
+            "{count} passes changed the image by up to {largest_difference}"
+        );
+    }
+}
+
+#[test]
+fn vignette_darkens_the_corners() {
+    let Some((input, output)) = post_process(&[PostEffect::Vignette {
+        strength: 1.0,
+        radius: 0.3,
+    }]) else {
+        return;
+    };
+    let center = (WIDTH / 2, HEIGHT / 2);
+    assert!(
+        brightness(&output, center.0, center.1).abs_diff(brightness(&input, center.0, center.1))
+            <= 3
+    );
+    for (x, y) in [
+        (0, 0),
+        (WIDTH - 1, 0),
+        (0, HEIGHT - 1),
+        (WIDTH - 1, HEIGHT - 1),
+    ] {
+        assert!(brightness(&output, x, y) < brightness(&input, x, y) / 4);
+    }
+}
+
+#[test]
+fn film_grain_adds_noise() {
+    let Some((input, output)) = post_process(&[PostEffect::FilmGrain { strength: 0.5 }]) else {
+        return;
+    };
+    let changed = input
+        .chunks_exact(4)
+        .zip(output.chunks_exact(4))
+        .filter(|(a, b)| a[..3] != b[..3])
+        .count();
+    assert!(changed > WIDTH * HEIGHT / 2);
+}
+
+#[test]
+fn chromatic_aberration_only_moves_red_and_blue() {
+    let Some((input, output)) = post_process(&[PostEffect::ChromaticAberration { strength: 0.05 }])
+    else {
+        return;
+    };
+    for (a, b) in input.chunks_exact(4).zip(output.chunks_exact(4)) {
+        assert!(a[1].abs_diff(b[1]) <= 1);
+    }
+    assert_ne!(input, output);
+}
@@ -0,0 +1,174 @@
+use cgmath::InnerSpace;
+use four_dimentions::{
+    wedge, BiVector4, GpuHyperPlane, GpuHyperSphere, GpuMaterial, LightGroup, ObjectRef, Placement,
+    Prefab, Scene,
+};
+
+fn sphere(center: cgmath::Vector4<f32>, material: u32) -> GpuHyperSphere {
+    GpuHyperSphere {
+        center,
+        velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        radius: 0.5,
+        material,
+        inner_radius: 0.0,
+    }
+}
+
+fn close(a: cgmath::Vector4<f32>, b: cgmath::Vector4<f32>) -> bool {
+    (a - b).magnitude() < 1e-5
+}
+
+fn bivector_close(a: BiVector4, b: BiVector4) -> bool {
+    let difference = BiVector4 {
+        xy: a.xy - b.xy,
+        xz: a.xz - b.xz,
+        xw: a.xw - b.xw,
+        yz: a.yz - b.yz,
+        yw: a.yw - b.yw,
+        zw: a.zw - b.zw,
+    };
+    difference.length() < 1e-5
+}
+
+/// Two spheres around (2, 1, 0, 0) sharing a red material that is in a light group, and an
+/// unused blue material
+fn scene() -> Scene {
+    let mut scene = Scene::empty();
+    scene.add_light_group(LightGroup::new("Lamps"));
+    let red = GpuMaterial {
+        base_color: cgmath::vec3(1.0, 0.0, 0.0),
+        light_group: 1,
+        ..Default::default()
+    };
+    scene.add_material("Blue", GpuMaterial::default());
+    let red = scene.add_material("Red", red);
+    scene.add_hyper_sphere("A", sphere(cgmath::vec4(1.0, 1.0, 0.0, 0.0), red.0));
+    scene.add_hyper_sphere("B", sphere(cgmath::vec4(3.0, 1.0, 0.0, 0.0), red.0));
+    scene.objects[1].notes = "The second one".into();
+    scene
+}
+
+#[test]
+fn prefabs_are_centered_and_only_keep_used_materials() {
+    let scene = scene();
+    let prefab = Prefab::from_objects(
+        "Pair",
+        &scene,
+        &[ObjectRef::HyperSphere(0), ObjectRef::HyperSphere(1)],
+    );
+    assert_eq!(prefab.name, "Pair");
+    assert_eq!(prefab.scene.material_names, ["Red"]);
+    assert_eq!(prefab.scene.materials[0].light_group, 0);
+    let centers = prefab
+        .scene
+        .hyper_spheres()
+        .map(|sphere| sphere.center)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        centers,
+        [
+            cgmath::vec4(-1.0, 0.0, 0.0, 0.0),
+            cgmath::vec4(1.0, 0.0, 0.0, 0.0)
+        ]
+    );
+
+    let loaded = Prefab::from_script("Loaded", &prefab.to_script()).unwrap();
+    assert_eq!(loaded.scene.material_names, prefab.scene.material_names);
+    assert!(loaded.scene.materials == prefab.scene.materials);
+    assert_eq!(loaded.scene.objects.len(), 2);
+    assert_eq!(loaded.scene.objects[1].name, "B");
+    assert_eq!(loaded.scene.objects[1].notes, "The second one");
+}
+
+#[test]
+fn placing_moves_rotates_and_scales_and_reuses_materials() {
+    let scene = scene();
+    let prefab = Prefab::from_objects(
+        "Pair",
+        &scene,
+        &[ObjectRef::HyperSphere(0), ObjectRef::HyperSphere(1)],
+    );
+    let mut target = Scene::default();
+    let materials = target.materials.len();
+    let placement = Placement {
+        position: cgmath::vec4(0.0, 2.0, 0.0, 5.0),
+        angle: std::f32::consts::FRAC_PI_2,
+        plane: BiVector4::XW,
+        scale: 2.0,
+    };
+    let ids = prefab.instantiate(&mut target, &placement);
+    assert_eq!(ids.len(), 2);
+    assert_eq!(target.materials.len(), materials + 1);
+
+    let placed = target.object_ref(ids[1]).unwrap();
+    let sphere = target.object(placed).unwrap().hyper_sphere().unwrap();
+    assert_eq!(sphere.radius, 1.0);
+    assert!(((sphere.center - placement.position).magnitude() - 2.0).abs() < 1e-5);
+    assert!(close(
+        sphere.center,
+        placement.point(cgmath::vec4(1.0, 0.0, 0.0, 0.0))
+    ));
+    // a quarter turn in the xw plane moves x onto w
+    assert!(sphere.center.x.abs() < 1e-5 && (sphere.center.w - 5.0).abs() > 1.9);
+    assert_eq!(target.object_name(placed), Some("B"));
+    assert_eq!(
+        target.material_name(target.object_material(placed).unwrap()),
+        Some("Red")
+    );
+
+    prefab.instantiate(&mut target, &Placement::default());
+    assert_eq!(target.materials.len(), materials + 1);
+}
+
+#[test]
+fn placed_planes_keep_their_shape() {
+    let placement = Placement {
+        position: cgmath::vec4(1.0, 0.0, -1.0, 0.5),
+        angle: 0.7,
+        plane: BiVector4::YZ,
+        scale: 1.5,
+    };
+    let a = cgmath::vec4(1.0, 0.5, 0.0, -0.25);
+    let b = cgmath::vec4(0.0, 1.0, 2.0, 0.5);
+    assert!(bivector_close(
+        placement.bivector(wedge(a, b)),
+        wedge(placement.direction(a), placement.direction(b))
+    ));
+
+    let plane = GpuHyperPlane {
+        point: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        normal: cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+        velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        angular_velocity: wedge(a, b),
+        material: 0,
+        extents: cgmath::vec3(1.0, 2.0, 0.5),
+        tangent: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        bitangent: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+    };
+    let mut scene = Scene::empty();
+    let material = scene.add_material("Material", GpuMaterial::default());
+    scene.add_hyper_plane(
+        "Panel",
+        GpuHyperPlane {
+            material: material.0,
+            ..plane
+        },
+    );
+    let prefab = Prefab::from_objects("Panel", &scene, &[ObjectRef::HyperPlane(0)]);
+    let mut target = Scene::empty();
+    prefab.instantiate(&mut target, &placement);
+    let placed = target.hyper_plane(0).unwrap();
+
+    assert!(close(placed.normal, placement.direction(plane.normal)));
+    assert_eq!(placed.extents, plane.extents * 1.5);
+    assert!(bivector_close(
+        placed.angular_velocity,
+        placement.bivector(plane.angular_velocity)
+    ));
+    for (inside, corner) in [(true, 0.95), (false, 1.05)] {
+        let point = plane.tangents()[0] * plane.extents.x * corner
+            + plane.tangents()[1] * plane.extents.y * corner;
+        assert_eq!(plane.contains(point, 0.0), inside);
+        assert_eq!(placed.contains(placement.point(point), 0.0), inside);
+    }
+}
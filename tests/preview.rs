@@ -0,0 +1,40 @@
+use four_dimentions::{Camera, MotionPreview};
+
+#[test]
+fn moving_lowers_the_settings_until_the_camera_settles() {
+    let mut preview = MotionPreview::default();
+    let mut camera = Camera::default();
+    preview.update(&camera, 0.016);
+    assert!(!preview.active());
+    assert_eq!(preview.apply(&camera).sample_count, camera.sample_count);
+
+    camera.position.x += 1.0;
+    preview.update(&camera, 0.016);
+    assert!(preview.active());
+    let moving = preview.apply(&camera);
+    assert_eq!(moving.sample_count, preview.sample_count);
+    assert_eq!(moving.bounce_count, preview.bounce_count);
+    assert!(moving != camera);
+
+    // changing how it samples isn't motion
+    camera.sample_count = 20;
+    preview.update(&camera, preview.settle_time);
+    assert!(!preview.active());
+    assert!(preview.apply(&camera) == camera);
+}
+
+#[test]
+fn preview_never_raises_the_settings() {
+    let mut preview = MotionPreview::default();
+    let mut camera = Camera {
+        bounce_count: 1,
+        ..Camera::default()
+    };
+    preview.update(&camera, 0.0);
+    camera.yaw += 0.1;
+    preview.update(&camera, 0.0);
+    assert_eq!(preview.apply(&camera).bounce_count, 1);
+
+    preview.enabled = false;
+    assert!(preview.apply(&camera) == camera);
+}
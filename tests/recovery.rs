@@ -0,0 +1,34 @@
+use four_dimentions::{Camera, Recovery, Scene, Script};
+
+#[test]
+fn recovery_files_round_trip() {
+    let scene = Scene::default();
+    let camera = Camera {
+        position: cgmath::vec4(1.0, 2.0, -3.0, 0.5),
+        pitch: 0.25,
+        weird_yaw: -1.0,
+        fov: 1.2,
+        ..Default::default()
+    };
+    let recovery = Recovery::from_scene(&scene, &camera);
+    let loaded = Recovery::from_json(&recovery.to_json()).unwrap();
+    assert_eq!(loaded.scene_source, recovery.scene_source);
+    assert_eq!(loaded.camera.position, camera.position);
+    assert_eq!(loaded.camera.pitch, camera.pitch);
+    assert_eq!(loaded.camera.weird_yaw, camera.weird_yaw);
+    assert_eq!(loaded.camera.fov, camera.fov);
+
+    let mut restored = Scene::empty();
+    Script::compile(&loaded.scene_source)
+        .unwrap()
+        .run(&mut restored, &mut vec![])
+        .unwrap();
+    assert_eq!(restored.hyper_sphere_count(), scene.hyper_sphere_count());
+    assert_eq!(restored.hyper_plane_count(), scene.hyper_plane_count());
+}
+
+#[test]
+fn broken_recovery_files_are_errors() {
+    assert!(Recovery::from_json("{}").is_err());
+    assert!(Recovery::from_json(r#"{"scene": "", "camera": {"position": [1, 2]}}"#).is_err());
+}
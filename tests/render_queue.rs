@@ -0,0 +1,110 @@
+mod common;
+
+use common::request_device;
+use four_dimentions::{encode_png, Camera, JobScene, JobStatus, RenderJob, RenderQueue};
+
+fn job(name: &str, scene: JobScene, output: std::path::PathBuf) -> RenderJob {
+    RenderJob {
+        name: name.into(),
+        scene,
+        camera: Camera {
+            sample_count: 2,
+            ..Default::default()
+        },
+        width: 24,
+        height: 16,
+        samples: 5,
+        output,
+        caption: None,
+    }
+}
+
+#[test]
+fn jobs_render_in_order_and_write_pngs() {
+    let Some((device, queue)) = request_device() else {
+        eprintln!("skipping, no GPU adapter is available");
+        return;
+    };
+    let directory = std::env::temp_dir().join(format!("render_queue_{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let mut render_queue = RenderQueue::default();
+    let first = directory.join("first.png");
+    let second = directory.join("second.png");
+    render_queue.push(job(
+        "first",
+        JobScene::Snapshot(Box::default()),
+        first.clone(),
+    ));
+    render_queue.push(job(
+        "missing",
+        JobScene::File(directory.join("missing.script")),
+        directory.join("missing.png"),
+    ));
+    render_queue.push(job(
+        "second",
+        JobScene::Snapshot(Box::default()),
+        second.clone(),
+    ));
+
+    let mut steps = 0;
+    while render_queue.is_busy() {
+        render_queue.step(&device, &queue);
+        steps += 1;
+        assert!(steps < 100, "the queue never finished");
+        if let Some(index) = render_queue.active_index() {
+            // nothing after the job being rendered has started yet
+            for (_, status) in &render_queue.jobs()[index + 1..] {
+                assert_eq!(*status, JobStatus::Queued);
+            }
+        }
+    }
+
+    let statuses = render_queue
+        .jobs()
+        .iter()
+        .map(|(_, status)| status.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(statuses[0], JobStatus::Done);
+    assert!(matches!(statuses[1], JobStatus::Failed(_)));
+    assert_eq!(statuses[2], JobStatus::Done);
+    for path in [first, second] {
+        let decoder = png::Decoder::new(std::fs::File::open(&path).unwrap());
+        let info = decoder.read_info().unwrap().info().clone();
+        assert_eq!((info.width, info.height), (24, 16));
+    }
+
+    render_queue.clear_finished();
+    assert!(render_queue.jobs().is_empty());
+    _ = std::fs::remove_dir_all(directory);
+}
+
+#[test]
+fn paused_queues_dont_start_jobs() {
+    let Some((device, queue)) = request_device() else {
+        eprintln!("skipping, no GPU adapter is available");
+        return;
+    };
+    let mut render_queue = RenderQueue::default();
+    render_queue.paused = true;
+    render_queue.push(job(
+        "paused",
+        JobScene::Snapshot(Box::default()),
+        std::env::temp_dir().join("paused.png"),
+    ));
+    render_queue.step(&device, &queue);
+    assert_eq!(render_queue.active_index(), None);
+    assert_eq!(render_queue.jobs()[0].1, JobStatus::Queued);
+    assert!(render_queue.remove(0));
+}
+
+#[test]
+fn encoded_pngs_decode_to_the_same_pixels() {
+    let pixels = (0..4 * 3 * 4).map(|i| i as u8 * 5).collect::<Vec<_>>();
+    let png = encode_png(4, 3, &pixels).unwrap();
+    let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+    let mut decoded = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut decoded).unwrap();
+    assert_eq!((info.width, info.height), (4, 3));
+    assert_eq!(decoded, pixels);
+}
@@ -0,0 +1,250 @@
+use cgmath::InnerSpace;
+use four_dimentions::{
+    GpuHyperPlane, GpuHyperSphere, GpuMaterial, MaterialId, MaterialUser, ObjectData, ObjectRef,
+    PrimitiveKind, Scene, SceneChanges,
+};
+
+fn scene_with_spheres(count: usize) -> Scene {
+    let mut scene = Scene::default();
+    for i in 0..count {
+        scene.add_hyper_sphere(
+            format!("Sphere {i}"),
+            GpuHyperSphere {
+                center: cgmath::vec4(i as f32, 0.0, 0.0, 0.0),
+                velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                radius: 0.5,
+                material: 0,
+                inner_radius: 0.0,
+            },
+        );
+    }
+    scene.changes = SceneChanges::NONE;
+    scene
+}
+
+#[test]
+fn batch_edits_apply_to_every_selected_object() {
+    let mut scene = scene_with_spheres(2);
+    let selection = [ObjectRef::HyperSphere(1), ObjectRef::HyperPlane(0)];
+    scene.translate_objects(&selection, cgmath::vec4(0.0, 0.0, 0.0, 2.0));
+    assert_eq!(
+        scene.hyper_sphere(1).unwrap().center,
+        cgmath::vec4(0.0, 0.0, 0.0, 2.0)
+    );
+    assert_eq!(
+        scene.hyper_plane(0).unwrap().point,
+        cgmath::vec4(0.0, 0.0, 0.0, 2.0)
+    );
+    assert_eq!(
+        scene.hyper_sphere(2).unwrap().center,
+        cgmath::vec4(1.0, 0.0, 0.0, 0.0)
+    );
+    assert!(scene.changes.hyper_spheres && scene.changes.hyper_planes);
+
+    scene.set_objects_material(&selection, MaterialId(1));
+    assert_eq!(
+        scene.object_material(ObjectRef::HyperSphere(1)),
+        Some(MaterialId(1))
+    );
+    assert_eq!(
+        scene.object_material(ObjectRef::HyperPlane(0)),
+        Some(MaterialId(1))
+    );
+    assert_eq!(
+        scene.object_material(ObjectRef::HyperSphere(0)),
+        Some(MaterialId(0))
+    );
+}
+
+#[test]
+fn removing_several_objects_keeps_the_names_in_sync() {
+    let mut scene = scene_with_spheres(3);
+    scene.remove_objects(&[
+        ObjectRef::HyperSphere(1),
+        ObjectRef::HyperSphere(3),
+        ObjectRef::HyperSphere(1),
+        ObjectRef::HyperPlane(0),
+    ]);
+    let names = scene
+        .objects
+        .iter()
+        .map(|object| object.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["Hyper Sphere", "Sphere 1"]);
+    assert_eq!(scene.hyper_sphere_count(), 2);
+    assert_eq!(scene.hyper_plane_count(), 0);
+    assert_eq!(scene.object_refs().count(), 2);
+}
+
+#[test]
+fn custom_tangents_are_an_orthonormal_basis() {
+    let mut scene = Scene::default();
+    let hyper_plane = scene.hyper_plane_mut(0).unwrap();
+    assert!(hyper_plane.custom_tangents().is_none());
+
+    hyper_plane.tangent = cgmath::vec4(1.0, 1.0, 1.0, 0.0);
+    hyper_plane.bitangent = cgmath::vec4(0.0, 0.5, 0.0, 2.0);
+    hyper_plane.orthonormalize_tangents();
+    let [tangent, bitangent, third] = hyper_plane.tangents();
+    assert!((tangent - cgmath::vec4(1.0, 0.0, 1.0, 0.0).normalize()).magnitude() < 1e-5);
+    assert!((bitangent - cgmath::vec4(0.0, 0.0, 0.0, 1.0)).magnitude() < 1e-5);
+    for (a, b) in [
+        (tangent, bitangent),
+        (tangent, third),
+        (bitangent, third),
+        (third, hyper_plane.normal),
+    ] {
+        assert!(a.dot(b).abs() < 1e-5);
+    }
+    assert!((third.magnitude() - 1.0).abs() < 1e-5);
+    assert!((hyper_plane.tangent - tangent).magnitude() < 1e-6);
+
+    // parallel to the normal doesn't pick out a direction, so it falls back to the automatic ones
+    hyper_plane.tangent = hyper_plane.normal;
+    assert!(hyper_plane.custom_tangents().is_none());
+}
+
+#[test]
+fn materials_in_use_are_not_removed() {
+    let mut scene = scene_with_spheres(2);
+    let orange = MaterialId(0);
+    assert_eq!(
+        scene.remove_material(orange),
+        Err(vec![
+            MaterialUser::Object(ObjectRef::HyperSphere(0)),
+            MaterialUser::Object(ObjectRef::HyperSphere(1)),
+            MaterialUser::Object(ObjectRef::HyperSphere(2)),
+        ])
+    );
+    assert_eq!(scene.materials.len(), 2);
+    assert_eq!(scene.material_name(orange), Some("Orange"));
+}
+
+#[test]
+fn material_ids_survive_removal_and_reordering() {
+    let mut scene = scene_with_spheres(0);
+    let unused = scene.add_material("Unused", GpuMaterial::default());
+    let blue = scene.add_material(
+        "Blue",
+        GpuMaterial {
+            base_color: cgmath::vec3(0.1, 0.1, 0.9),
+            ..Default::default()
+        },
+    );
+    scene.set_objects_material(&[ObjectRef::HyperSphere(0)], blue);
+    scene.changes = SceneChanges::NONE;
+
+    assert_eq!(scene.remove_material(unused), Ok(()));
+    assert_eq!(scene.material_index(unused), None);
+    assert_eq!(scene.object_material(ObjectRef::HyperSphere(0)), Some(blue));
+    assert_eq!(scene.material_name(blue), Some("Blue"));
+    // the objects have to be uploaded again since the indices moved
+    assert!(scene.changes.hyper_spheres && scene.changes.hyper_planes);
+    assert_eq!(scene.sanitized_hyper_spheres()[0].material, 2);
+
+    scene.swap_materials(0, 2);
+    assert_eq!(scene.object_material(ObjectRef::HyperSphere(0)), Some(blue));
+    assert_eq!(scene.sanitized_hyper_spheres()[0].material, 0);
+    assert_eq!(scene.materials[0].base_color, cgmath::vec3(0.1, 0.1, 0.9));
+    // the ground was green and still is
+    let ground = scene.sanitized_hyper_planes()[0].material;
+    assert_eq!(scene.material_names[ground as usize], "Green");
+
+    // new materials never reuse an id
+    let next = scene.add_material("Next", GpuMaterial::default());
+    assert!(next != unused && next != blue);
+}
+
+#[test]
+fn object_refs_count_each_kind_separately() {
+    let mut scene = scene_with_spheres(1);
+    let plane = *scene.hyper_plane(0).unwrap();
+    let second_plane = scene.add_hyper_plane("Second Plane", plane);
+    let last = scene.add_hyper_sphere("Last", *scene.hyper_sphere(0).unwrap());
+    assert_eq!(
+        scene.object_refs().collect::<Vec<_>>(),
+        [
+            ObjectRef::HyperSphere(0),
+            ObjectRef::HyperPlane(0),
+            ObjectRef::HyperSphere(1),
+            ObjectRef::HyperPlane(1),
+            ObjectRef::HyperSphere(2),
+        ]
+    );
+    assert_eq!(
+        scene.object_ref(second_plane),
+        Some(ObjectRef::HyperPlane(1))
+    );
+    assert_eq!(scene.object_name(ObjectRef::HyperSphere(2)), Some("Last"));
+    assert_eq!(scene.object_id(ObjectRef::HyperSphere(2)), Some(last));
+    assert_eq!(scene.sanitized_hyper_spheres().len(), 3);
+    assert_eq!(scene.sanitized_hyper_planes().len(), 2);
+
+    scene.changes = SceneChanges::NONE;
+    scene.remove_objects(&[ObjectRef::HyperPlane(0)]);
+    assert_eq!(
+        scene.object_ref(second_plane),
+        Some(ObjectRef::HyperPlane(0))
+    );
+    assert!(scene.changes.hyper_planes && !scene.changes.hyper_spheres);
+}
+
+fn assert_lookups_match(scene: &Scene) {
+    for (index, (object, data)) in scene.object_refs().zip(&scene.objects).enumerate() {
+        assert_eq!(scene.object_index(object), Some(index));
+        assert_eq!(scene.object_ref(data.id), Some(object));
+    }
+    assert_eq!(scene.hyper_sphere_count(), scene.hyper_spheres().count());
+    assert_eq!(scene.hyper_plane_count(), scene.hyper_planes().count());
+}
+
+#[test]
+fn lookups_stay_in_sync_with_the_objects() {
+    let mut scene = scene_with_spheres(4);
+    let plane = *scene.hyper_plane(0).unwrap();
+    let planes = [
+        scene.add_hyper_plane("Plane A", plane),
+        scene.add_hyper_plane("Plane B", plane),
+    ];
+    assert_lookups_match(&scene);
+
+    let removed = scene.object_id(ObjectRef::HyperSphere(1)).unwrap();
+    scene.remove_objects(&[ObjectRef::HyperSphere(1), ObjectRef::HyperPlane(1)]);
+    assert_lookups_match(&scene);
+    assert_eq!(scene.object_ref(removed), None);
+    assert_eq!(scene.object_ref(planes[0]), None);
+    assert_eq!(scene.object_ref(planes[1]), Some(ObjectRef::HyperPlane(1)));
+    assert!(scene.hyper_sphere(4).is_none());
+    assert_eq!(
+        scene.hyper_sphere(3).map(|sphere| sphere.center),
+        scene.hyper_spheres().nth(3).map(|sphere| sphere.center)
+    );
+
+    scene.add_hyper_sphere("After Removal", *scene.hyper_sphere(0).unwrap());
+    assert_lookups_match(&scene);
+    scene.clear_objects();
+    assert_lookups_match(&scene);
+    assert_eq!(scene.object_ref(planes[1]), None);
+    assert!(scene.hyper_sphere(0).is_none());
+}
+
+#[test]
+fn new_objects_of_every_kind_are_valid() {
+    let mut scene = Scene::empty();
+    let material = scene.add_material("Material", GpuMaterial::default());
+    for &kind in PrimitiveKind::ALL {
+        let data = ObjectData::new(kind, material);
+        assert_eq!(data.kind(), kind);
+        assert_eq!(data.material(), material);
+        assert!(data.problems(&scene.material_ids).is_empty());
+        scene.add_object(format!("New {}", kind.name()), data);
+    }
+    assert_eq!(scene.hyper_sphere_count(), 1);
+    assert_eq!(scene.hyper_plane_count(), 1);
+    assert!(scene.objects[0].data.get::<GpuHyperSphere>().is_some());
+    assert!(scene.objects[0].data.get::<GpuHyperPlane>().is_none());
+    assert_eq!(
+        ObjectRef::new(PrimitiveKind::HyperPlane, 3),
+        ObjectRef::HyperPlane(3)
+    );
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::*;
+use four_dimentions::{GpuHyperSphere, RenderPath, Renderer, DATA_TEXTURE_WIDTH};
+
+/// A wall of small hyper spheres in front of the camera, more than fit in a row of a data texture
+fn wall_scene() -> Scene {
+    let mut scene = default_scene();
+    scene.camera.position = cgmath::vec4(0.0, 1.5, -5.0, 0.0);
+    scene.camera.sample_count = 8;
+    scene.hyper_spheres = (0..24 * 24)
+        .map(|i| GpuHyperSphere {
+            center: cgmath::vec4(
+                (i % 24) as f32 * 0.12 - 1.4,
+                (i / 24) as f32 * 0.12 + 0.1,
+                0.0,
+                0.0,
+            ),
+            radius: 0.05,
+            material: (i % 2) as _,
+            ..scene.hyper_spheres[0]
+        })
+        .collect();
+    let texels = 1 + scene.hyper_spheres.len() * 3;
+    assert!(texels > DATA_TEXTURE_WIDTH);
+    scene
+}
+
+/// Moves the hyper spheres in the last row of the data texture, and one before it
+fn move_hyper_spheres(scene: &mut Scene) {
+    let last = scene.hyper_spheres.len() - 1;
+    for index in [100, last - 1, last] {
+        scene.hyper_spheres[index].center.y += 0.3;
+        scene.hyper_spheres[index].radius = 0.1;
+    }
+}
+
+#[test]
+fn data_textures_hold_scenes_larger_than_a_row() {
+    let Some((device, queue)) = request_device() else {
+        eprintln!("skipping, no GPU adapter is available");
+        return;
+    };
+    if RenderPath::for_device(&device) != RenderPath::Compute {
+        eprintln!("skipping, the storage buffers of the compute path are the reference");
+        return;
+    }
+    let mut scene = wall_scene();
+    let expected = render(&device, &queue, RenderPath::Compute, &scene);
+    move_hyper_spheres(&mut scene);
+    let expected_moved = render(&device, &queue, RenderPath::Compute, &scene);
+    // GL contexts can't be current on the same thread twice
+    drop((device, queue));
+
+    let (device, queue) = request_webgl2_device().expect("the adapter was found before");
+    let mut scene = wall_scene();
+    let mut renderer = Renderer::new(&device, &queue, RenderPath::Fragment);
+    renderer.resize(&device, &queue, WIDTH, HEIGHT);
+    renderer
+        .upload_materials(&device, &queue, &scene.materials)
+        .unwrap();
+    renderer
+        .upload_node_programs(&device, &queue, &scene.node_programs)
+        .unwrap();
+    let mut render_fragment = |scene: &Scene| {
+        renderer
+            .upload_objects(&device, &queue, &scene.hyper_spheres, &scene.hyper_planes)
+            .unwrap();
+        renderer
+            .upload_camera(&queue, &scene.camera.to_gpu(0))
+            .unwrap();
+        let mut encoder = device.create_command_encoder(&Default::default());
+        renderer.render(&device, &mut encoder, scene.camera.render_options());
+        queue.submit([encoder.finish()]);
+        let pixels = renderer.read_pixels(&device, &queue).unwrap();
+        (pixels, renderer.object_upload_size())
+    };
+
+    for (name, expected) in [("wall", expected), ("moved", expected_moved)] {
+        let (actual, upload_size) = render_fragment(&scene);
+        if name == "moved" {
+            // only the texels of the hyper spheres that moved are written again
+            assert!(upload_size < 1024, "{upload_size} bytes were written");
+        }
+        let (mean_difference, outlier_fraction) = image_difference(&expected, &actual);
+        assert!(
+            images_match(mean_difference, outlier_fraction),
+            "{name}: mean difference {mean_difference}, outliers {outlier_fraction}"
+        );
+        move_hyper_spheres(&mut scene);
+    }
+}
@@ -0,0 +1,249 @@
+use cgmath::InnerSpace;
+use four_dimentions::{
+    scene_script, EmissionPattern, Expression, MaterialId, ObjectRef, Scene, SceneChanges, Script,
+    Value, EXAMPLE_SCRIPT,
+};
+
+fn run(source: &str) -> Result<(Scene, Vec<String>), String> {
+    let mut scene = Scene::empty();
+    let mut output = vec![];
+    Script::compile(source)?.run(&mut scene, &mut output)?;
+    Ok((scene, output))
+}
+
+#[test]
+fn example_script_runs_and_animates() {
+    let mut scene = Scene::empty();
+    let mut output = vec![];
+    let mut script = Script::compile(EXAMPLE_SCRIPT).unwrap();
+    script.run(&mut scene, &mut output).unwrap();
+    assert_eq!(scene.hyper_sphere_count(), 17);
+    assert_eq!(scene.hyper_plane_count(), 1);
+
+    let before = scene.hyper_sphere(1).unwrap().center;
+    scene.changes = SceneChanges::NONE;
+    script
+        .call(
+            "update",
+            &[Value::Number(1.0), Value::Number(0.1)],
+            &mut scene,
+            &mut output,
+        )
+        .unwrap();
+    assert_ne!(scene.hyper_sphere(1).unwrap().center, before);
+    // only moving spheres shouldn't cause the planes or materials to be uploaded again
+    assert_eq!(
+        scene.changes,
+        SceneChanges {
+            hyper_spheres: true,
+            ..SceneChanges::NONE
+        }
+    );
+}
+
+#[test]
+fn arithmetic_control_flow_and_functions() {
+    let (_, output) = run(r#"
+        fn fib(n) {
+            if n < 2 { return n }
+            return fib(n - 1) + fib(n - 2)
+        }
+        let total = 0
+        for i in 0..5 { total = total + i }
+        let i = 0
+        while i < 3 { i = i + 1; }
+        print("fib", fib(10), total, i, 7 % 3, -2 * 3, !(1 > 2) && true)
+    "#)
+    .unwrap();
+    assert_eq!(output, ["fib 55 10 3 1 -6 true"]);
+}
+
+#[test]
+fn errors_report_the_line() {
+    let error = run("let a = 1\nprint(b)").err().unwrap();
+    assert_eq!(error, "line 2: unknown variable 'b'");
+    let error = run("\n\nsphere(\"a\", 0, 0, 0)").err().unwrap();
+    assert_eq!(error, "line 3: 'sphere' is missing argument 5");
+    let error = run("for i in 0..3 {").err().unwrap();
+    assert!(error.starts_with("line 1:"), "{error}");
+    let error = run("while true { }").err().unwrap();
+    assert!(error.contains("too long"), "{error}");
+}
+
+#[test]
+fn runaway_recursion_is_an_error() {
+    let error = run("fn f(n) { return f(n + 1); }\nf(0)").err().unwrap();
+    assert!(error.contains("recursion too deep"), "{error}");
+
+    // as deep as the parser allows inside every call
+    for nested in [
+        format!("fn f(n) {{ return {}f(n + 1); }}\nf(0)", "-".repeat(60)),
+        format!(
+            "fn f(n) {{ {}f(n + 1){} }}\nf(0)",
+            "if true { ".repeat(60),
+            " }".repeat(60)
+        ),
+    ] {
+        let error = run(&nested).err().unwrap();
+        assert!(error.contains("recursion too deep"), "{error}");
+    }
+
+    // calls that do return can still go fairly deep
+    let (_, output) =
+        run("fn f(n) { if n > 0 { return f(n - 1) + 1; } return 0; }\nprint(f(50))").unwrap();
+    assert_eq!(output, ["50"]);
+}
+
+#[test]
+fn deeply_nested_source_is_an_error() {
+    for source in [
+        format!("let a = {}1{}", "(".repeat(100_000), ")".repeat(100_000)),
+        format!("let a = {}1", "-".repeat(100_000)),
+        format!("let a = 1{}", " + 1".repeat(100_000)),
+        format!("{}{}", "if true { ".repeat(100_000), "}".repeat(100_000)),
+    ] {
+        let error = Script::compile(&source).err().unwrap();
+        assert!(error.contains("nested too deeply"), "{error}");
+    }
+}
+
+#[test]
+fn emission_patterns_are_set_by_name() {
+    let (scene, _) = run(r#"
+        let light = material("Light", 1, 1, 1, 1, 1, 1, 3)
+        set_material_emission_pattern(light, "stripes", 2, 0, 0, 0)
+    "#)
+    .unwrap();
+    assert_eq!(
+        scene.materials[0].emission_pattern,
+        EmissionPattern::Stripes as u32
+    );
+    assert_eq!(
+        scene.materials[0].emission_pattern_scale,
+        cgmath::vec4(2.0, 0.0, 0.0, 0.0)
+    );
+
+    let error = run(r#"set_material_emission_pattern(material("Light", 1, 1, 1, 1, 1, 1, 3), "plaid", 1, 0, 0, 0)"#)
+        .err()
+        .unwrap();
+    assert!(error.contains("plaid"), "{error}");
+}
+
+#[test]
+fn expressions_of_time() {
+    let expression = Expression::compile("2 * sin(t) + t").unwrap();
+    let value = expression.evaluate(1.5).unwrap();
+    assert!((value - (2.0 * 1.5f32.sin() + 1.5)).abs() < 1e-5, "{value}");
+
+    assert!(Expression::compile("t +").is_err());
+    assert!(Expression::compile("t t").is_err());
+    assert!(Expression::compile("x").unwrap().evaluate(0.0).is_err());
+}
+
+#[test]
+fn saved_scenes_run_back_into_the_same_scene() {
+    let (mut scene, _) = run(EXAMPLE_SCRIPT).unwrap();
+    let material = &mut scene.materials_mut()[0];
+    material.transmission = 0.5;
+    material.emission_pattern = EmissionPattern::Stripes as _;
+    material.sheen = 0.25;
+    material.priority = 3;
+    scene.hyper_plane_mut(0).unwrap().velocity = cgmath::vec4(0.5, 0.0, -1.0, 0.0);
+    scene.hyper_plane_mut(0).unwrap().angular_velocity.zw = 0.75;
+    scene.hyper_plane_mut(0).unwrap().extents = cgmath::vec3(4.0, 0.0, 2.5);
+    scene.hyper_plane_mut(0).unwrap().tangent = cgmath::vec4(1.0, 0.0, 1.0, 0.0);
+    scene.hyper_plane_mut(0).unwrap().bitangent = cgmath::vec4(0.0, 0.0, 0.0, 1.0);
+    scene.hyper_plane_mut(0).unwrap().orthonormalize_tangents();
+    scene.object_mut(ObjectRef::HyperSphere(0)).unwrap().name = "Quoted \"Name\"".into();
+    scene.hyper_sphere_mut(0).unwrap().inner_radius = scene.hyper_sphere(0).unwrap().radius * 0.5;
+
+    let (loaded, _) = run(&scene_script(&scene)).unwrap();
+    assert!(loaded.materials == scene.materials);
+    assert_eq!(loaded.material_names, scene.material_names);
+    assert_eq!(
+        loaded.object_name(ObjectRef::HyperSphere(0)),
+        Some("Quoted \"Name\"")
+    );
+    assert_eq!(loaded.hyper_sphere_count(), scene.hyper_sphere_count());
+    for (loaded, sphere) in loaded.hyper_spheres().zip(scene.hyper_spheres()) {
+        assert_eq!(loaded.center, sphere.center);
+        assert_eq!(loaded.velocity, sphere.velocity);
+        assert_eq!(loaded.radius, sphere.radius);
+        assert_eq!(loaded.inner_radius, sphere.inner_radius);
+        assert_eq!(loaded.material, sphere.material);
+    }
+    assert_eq!(loaded.hyper_plane_count(), scene.hyper_plane_count());
+    for (loaded, plane) in loaded.hyper_planes().zip(scene.hyper_planes()) {
+        assert_eq!(loaded.point, plane.point);
+        assert_eq!(loaded.velocity, plane.velocity);
+        assert!(loaded.angular_velocity == plane.angular_velocity);
+        assert_eq!(loaded.material, plane.material);
+        assert_eq!(loaded.extents, plane.extents);
+        for (loaded, tangent) in loaded.tangents().into_iter().zip(plane.tangents()) {
+            assert!((loaded - tangent).magnitude() < 1e-5);
+        }
+    }
+}
+
+#[test]
+fn saved_scenes_keep_object_notes() {
+    let (mut scene, _) = run(r#"
+sphere("Ball", 0, 1, 0, 0, 1, 0)
+plane("Ground", 0, 0, 0, 0, 0, 1, 0, 0, 0)
+set_sphere_notes(0, "A slice of a \"hyper sphere\"\nis a sphere")
+"#)
+    .unwrap();
+    assert_eq!(
+        scene.object(ObjectRef::HyperSphere(0)).unwrap().notes,
+        "A slice of a \"hyper sphere\"\nis a sphere"
+    );
+    scene.object_mut(ObjectRef::HyperPlane(0)).unwrap().notes = r"C:\scenes\ground".into();
+
+    let (loaded, _) = run(&scene_script(&scene)).unwrap();
+    for (loaded, object) in loaded.objects.iter().zip(&scene.objects) {
+        assert_eq!(loaded.notes, object.notes);
+    }
+    assert!(run("set_plane_notes(5, \"Missing\")").is_err());
+}
+
+#[test]
+fn saved_scenes_keep_materials_after_removal() {
+    let (mut scene, _) = run(r#"
+clear()
+clear_materials()
+let unused = material("Unused", 1, 1, 1, 0, 0, 0, 0)
+let red = material("Red", 1, 0, 0, 0, 0, 0, 0)
+sphere("Ball", 0, 1, 0, 0, 1, red)
+set_material_color(red, 0.9, 0.1, 0.1)
+"#)
+    .unwrap();
+    assert_eq!(scene.materials[1].base_color, cgmath::vec3(0.9, 0.1, 0.1));
+    let unused = scene.material_ids[0];
+    scene.remove_material(unused).unwrap();
+
+    let (loaded, _) = run(&scene_script(&scene)).unwrap();
+    assert_eq!(loaded.material_names, ["Red"]);
+    let material = MaterialId(loaded.hyper_sphere(0).unwrap().material);
+    assert_eq!(loaded.material_name(material), Some("Red"));
+    assert!(run("set_material_color(5, 1, 1, 1)").is_err());
+}
+
+#[test]
+fn saved_scenes_keep_light_groups() {
+    let (scene, _) = run(r#"
+clear_materials()
+let lamp = material("Lamp", 1, 1, 1, 1, 1, 1, 4)
+let window = light_group("Window", 2.5)
+let lamps = light_group("Lamps", 1)
+set_light_group_enabled(window, false)
+set_material_light_group(lamp, lamps)
+"#)
+    .unwrap();
+    assert_eq!(scene.light_groups.len(), 2);
+    assert_eq!(scene.materials[0].light_group, 2);
+
+    let (loaded, _) = run(&scene_script(&scene)).unwrap();
+    assert_eq!(loaded.light_groups, scene.light_groups);
+    assert_eq!(loaded.materials[0].light_group, 2);
+    assert!(run("set_light_group_enabled(1, false)").is_err());
+}
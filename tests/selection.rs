@@ -0,0 +1,62 @@
+use four_dimentions::{GpuHyperSphere, ObjectRef, Scene, Selection};
+
+fn sphere(x: f32) -> GpuHyperSphere {
+    GpuHyperSphere {
+        center: cgmath::vec4(x, 0.0, 0.0, 0.0),
+        velocity: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        radius: 0.5,
+        material: 0,
+        inner_radius: 0.0,
+    }
+}
+
+#[test]
+fn selection_follows_objects_when_others_are_removed() {
+    let mut scene = Scene::default();
+    let a = scene.add_hyper_sphere("A", sphere(1.0));
+    let b = scene.add_hyper_sphere("B", sphere(2.0));
+    let mut selection = Selection::new();
+    selection.toggle(b);
+    assert_eq!(selection.objects(&scene), [ObjectRef::HyperSphere(2)]);
+
+    scene.remove_objects(&[ObjectRef::HyperSphere(0), ObjectRef::HyperSphere(1)]);
+    assert_eq!(scene.object_ref(a), None);
+    assert_eq!(selection.objects(&scene), [ObjectRef::HyperSphere(0)]);
+    assert_eq!(scene.object_name(selection.objects(&scene)[0]), Some("B"));
+}
+
+#[test]
+fn removed_objects_drop_out_of_the_selection() {
+    let mut scene = Scene::default();
+    let a = scene.add_hyper_sphere("A", sphere(1.0));
+    let b = scene.add_hyper_sphere("B", sphere(2.0));
+    let mut selection = Selection::new();
+    selection.select_all(&scene);
+    assert_eq!(selection.len(), 4);
+
+    scene.remove_objects(&[scene.object_ref(a).unwrap()]);
+    assert_eq!(selection.objects(&scene).len(), 3);
+    selection.retain_existing(&scene);
+    assert!(!selection.contains(a));
+    assert_eq!(selection.primary(), Some(b));
+
+    let ground = scene.object_id(ObjectRef::HyperPlane(0)).unwrap();
+    selection.add(ground);
+    assert_eq!(selection.primary(), Some(ground));
+    selection.toggle(b);
+    selection.add(b);
+    assert_eq!(selection.primary(), Some(b));
+    selection.select(b);
+    assert_eq!(selection.ids(), [b]);
+}
+
+#[test]
+fn object_ids_are_never_reused() {
+    let mut scene = Scene::default();
+    let first = scene.add_hyper_sphere("First", sphere(0.0));
+    scene.clear_objects();
+    let second = scene.add_hyper_sphere("Second", sphere(0.0));
+    assert_ne!(first, second);
+    assert_eq!(scene.object_id(ObjectRef::HyperSphere(0)), Some(second));
+    assert_eq!(scene.object_ref(first), None);
+}
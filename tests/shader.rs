@@ -0,0 +1,23 @@
+use four_dimentions::{
+    embedded_shader_module, preprocess_shader, PrimitiveKind, ENTRY_SHADER_MODULE,
+};
+
+#[test]
+fn every_primitive_has_an_embedded_shader_module() {
+    for kind in PrimitiveKind::ALL {
+        let module = format!("{}.wgsl", kind.shader_name());
+        let source = embedded_shader_module(&module).unwrap();
+        assert!(source.contains(&format!("fn {}_hit(", kind.shader_name())));
+    }
+}
+
+#[test]
+fn generated_intersect_loops_cover_every_primitive() {
+    for defines in [&["COMPUTE", "CULLING"][..], &["FRAGMENT"]] {
+        let source =
+            preprocess_shader(ENTRY_SHADER_MODULE, defines, embedded_shader_module).unwrap();
+        for kind in PrimitiveKind::ALL {
+            assert!(source.contains(&format!("{}_hit(ray, ", kind.shader_name())));
+        }
+    }
+}
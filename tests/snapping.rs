@@ -0,0 +1,44 @@
+use four_dimentions::Snapping;
+
+#[test]
+fn edits_snap_to_the_grid_per_axis() {
+    let snapping = Snapping {
+        enabled: true,
+        step: cgmath::vec4(0.5, 1.0, 0.0, 0.25),
+        ..Default::default()
+    };
+    let snapped = snapping.apply(
+        cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        cgmath::vec4(0.999999, -1.4, 0.123, 0.13),
+    );
+    assert_eq!(snapped, cgmath::vec4(1.0, -1.0, 0.123, 0.25));
+
+    let disabled = Snapping::default();
+    let edited = cgmath::vec4(0.999999, -1.4, 0.123, 0.13);
+    assert_eq!(
+        disabled.apply(cgmath::vec4(0.0, 0.0, 0.0, 0.0), edited),
+        edited
+    );
+}
+
+#[test]
+fn locked_axes_keep_their_value() {
+    let snapping = Snapping {
+        locked: [false, true, false, true],
+        ..Default::default()
+    };
+    let locked = snapping.apply(
+        cgmath::vec4(1.0, 2.0, 3.0, 4.0),
+        cgmath::vec4(5.0, 6.0, 7.0, 8.0),
+    );
+    assert_eq!(locked, cgmath::vec4(5.0, 2.0, 7.0, 4.0));
+    // snapping a whole position ignores the locks
+    let snapping = Snapping {
+        enabled: true,
+        ..snapping
+    };
+    assert_eq!(
+        snapping.snap_position(cgmath::vec4(0.3, 0.3, 0.3, 0.3)),
+        cgmath::vec4(0.25, 0.25, 0.25, 0.25)
+    );
+}
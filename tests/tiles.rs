@@ -0,0 +1,150 @@
+mod common;
+
+use common::*;
+use four_dimentions::{split_into_tiles, RenderPath, Renderer, Tile};
+
+#[test]
+fn tiles_cover_every_pixel_once() {
+    for (width, height, tile_size) in [(64, 48, 16), (100, 37, 32), (5, 5, 8), (1, 1, 1)] {
+        let mut covered = vec![0; width * height];
+        for tile in split_into_tiles(width, height, Some(tile_size)) {
+            assert!(tile.width > 0 && tile.width <= tile_size);
+            assert!(tile.height > 0 && tile.height <= tile_size);
+            for y in tile.y..tile.y + tile.height {
+                for x in tile.x..tile.x + tile.width {
+                    covered[x + y * width] += 1;
+                }
+            }
+        }
+        assert!(covered.iter().all(|&count| count == 1));
+    }
+}
+
+#[test]
+fn no_tile_size_is_one_tile() {
+    assert_eq!(
+        split_into_tiles(640, 480, None),
+        [Tile {
+            x: 0,
+            y: 0,
+            width: 640,
+            height: 480,
+        }]
+    );
+}
+
+#[test]
+fn tiles_of_a_region_stay_inside_it() {
+    let region = Tile {
+        x: 10,
+        y: 20,
+        width: 30,
+        height: 7,
+    };
+    let tiles = region.split(Some(8));
+    assert_eq!(tiles.len(), 4);
+    assert_eq!(
+        tiles
+            .iter()
+            .map(|tile| tile.width * tile.height)
+            .sum::<usize>(),
+        30 * 7
+    );
+    for tile in tiles {
+        assert_eq!(tile.intersection(region), Some(tile));
+    }
+    assert_eq!(region.split(None), [region]);
+}
+
+#[test]
+fn intersections() {
+    let a = Tile {
+        x: 0,
+        y: 0,
+        width: 10,
+        height: 10,
+    };
+    let b = Tile {
+        x: 5,
+        y: 8,
+        width: 10,
+        height: 10,
+    };
+    assert_eq!(
+        a.intersection(b),
+        Some(Tile {
+            x: 5,
+            y: 8,
+            width: 5,
+            height: 2,
+        })
+    );
+    assert_eq!(b.intersection(a), a.intersection(b));
+    let touching = Tile {
+        x: 10,
+        y: 0,
+        width: 5,
+        height: 5,
+    };
+    assert_eq!(a.intersection(touching), None);
+}
+
+#[test]
+fn only_the_region_is_rendered() {
+    let Some((device, queue)) = request_device() else {
+        eprintln!("skipping, no GPU adapter is available");
+        return;
+    };
+    let mut scene = default_scene();
+    let mut renderer = Renderer::new(&device, &queue, RenderPath::for_device(&device));
+    renderer.resize(&device, &queue, WIDTH, HEIGHT);
+    renderer
+        .upload_objects(&device, &queue, &scene.hyper_spheres, &scene.hyper_planes)
+        .unwrap();
+    renderer
+        .upload_environment(&queue, &scene.environment.to_gpu())
+        .unwrap();
+    let render_frame = |renderer: &mut Renderer, scene: &Scene| {
+        renderer
+            .upload_materials(&device, &queue, &scene.materials)
+            .unwrap();
+        renderer
+            .upload_camera(&queue, &scene.camera.to_gpu(0))
+            .unwrap();
+        let mut encoder = device.create_command_encoder(&Default::default());
+        renderer.render(&device, &mut encoder, scene.camera.render_options());
+        queue.submit([encoder.finish()]);
+        renderer.read_pixels(&device, &queue).unwrap()
+    };
+    let before = render_frame(&mut renderer, &scene);
+
+    // the floor turns red, but only inside the region
+    let region = Tile {
+        x: 8,
+        y: 30,
+        width: 20,
+        height: 10,
+    };
+    renderer.set_region(&device, Some(region));
+    scene.materials[1].base_color = cgmath::vec3(0.8, 0.1, 0.1);
+    let after = render_frame(&mut renderer, &scene);
+
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let index = (x + y * WIDTH) * 4;
+            let inside = region
+                .intersection(Tile {
+                    x,
+                    y,
+                    width: 1,
+                    height: 1,
+                })
+                .is_some();
+            assert_eq!(
+                before[index..index + 4] != after[index..index + 4],
+                inside,
+                "pixel ({x}, {y})"
+            );
+        }
+    }
+}
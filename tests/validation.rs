@@ -0,0 +1,73 @@
+use cgmath::InnerSpace;
+use four_dimentions::{ObjectProblem, ObjectRef, Primitive, Scene};
+
+#[test]
+fn default_scene_has_no_problems() {
+    assert!(Scene::default().problems().is_empty());
+}
+
+#[test]
+fn degenerate_hyper_spheres_are_sanitized() {
+    let mut scene = Scene::default();
+    scene.hyper_sphere_mut(0).unwrap().center.z = f32::NAN;
+    scene.hyper_sphere_mut(0).unwrap().radius = -2.0;
+    scene.hyper_sphere_mut(0).unwrap().inner_radius = 3.0;
+    scene.hyper_sphere_mut(0).unwrap().material = 7;
+    assert_eq!(
+        scene.problems(),
+        [(
+            ObjectRef::HyperSphere(0),
+            vec![
+                ObjectProblem::NonFinite,
+                ObjectProblem::NegativeRadius,
+                ObjectProblem::MaterialOutOfRange,
+            ]
+        )]
+    );
+
+    let [hyper_sphere] = scene.sanitized_hyper_spheres()[..] else {
+        panic!("the hyper sphere is kept");
+    };
+    assert_eq!(hyper_sphere.center, cgmath::vec4(0.0, 1.0, 0.0, 0.0));
+    assert_eq!(hyper_sphere.radius, 2.0);
+    assert_eq!(hyper_sphere.inner_radius, 2.0);
+    assert_eq!(hyper_sphere.material, 0);
+    assert!(hyper_sphere.problems(&scene.material_ids).is_empty());
+}
+
+#[test]
+fn degenerate_hyper_planes_are_sanitized() {
+    let mut scene = Scene::default();
+    scene.hyper_plane_mut(0).unwrap().normal = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+    scene.hyper_plane_mut(0).unwrap().extents = cgmath::vec3(-1.0, 2.0, 0.0);
+    scene.hyper_plane_mut(0).unwrap().angular_velocity.xw = f32::INFINITY;
+    assert_eq!(
+        scene.problems(),
+        [(
+            ObjectRef::HyperPlane(0),
+            vec![
+                ObjectProblem::NonFinite,
+                ObjectProblem::ZeroNormal,
+                ObjectProblem::NegativeExtents,
+            ]
+        )]
+    );
+
+    let [hyper_plane] = scene.sanitized_hyper_planes()[..] else {
+        panic!("the hyper plane is kept");
+    };
+    assert_eq!(hyper_plane.normal, cgmath::vec4(0.0, 1.0, 0.0, 0.0));
+    assert_eq!(hyper_plane.extents, cgmath::vec3(1.0, 2.0, 0.0));
+    assert_eq!(hyper_plane.angular_velocity.xw, 0.0);
+    assert!(hyper_plane.problems(&scene.material_ids).is_empty());
+}
+
+#[test]
+fn sanitizing_normalizes_plane_normals() {
+    let mut scene = Scene::default();
+    scene.hyper_plane_mut(0).unwrap().normal = cgmath::vec4(0.0, 3.0, 4.0, 0.0);
+    assert!(scene.problems().is_empty());
+    let normal = scene.sanitized_hyper_planes()[0].normal;
+    assert!((normal.magnitude() - 1.0).abs() < 1e-6);
+    assert!((normal - cgmath::vec4(0.0, 0.6, 0.8, 0.0)).magnitude() < 1e-6);
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use four_dimentions::{GpuHyperSphere, GpuMaterial, Projection, RenderPath, Renderer, WAVE_SIZE};
+
+/// Overlapping glass with false hits on its boundaries, a light and a clearcoat
+fn glass_scene() -> common::Scene {
+    let mut scene = common::default_scene();
+    scene.camera.position = cgmath::vec4(0.0, 1.0, -4.0, 0.0);
+    scene.materials.extend([
+        GpuMaterial {
+            base_color: cgmath::vec3(0.9, 0.9, 1.0),
+            emissive_color: cgmath::vec3(0.0, 0.0, 0.0),
+            transmission: 1.0,
+            ior: 1.5,
+            dispersion: 0.01,
+            ..Default::default()
+        },
+        GpuMaterial {
+            base_color: cgmath::vec3(0.0, 0.0, 0.0),
+            emissive_color: cgmath::vec3(1.0, 0.8, 0.5),
+            emission_strength: 4.0,
+            ..Default::default()
+        },
+    ]);
+    scene.materials[0].clearcoat = 0.5;
+    scene.hyper_spheres.extend([
+        GpuHyperSphere {
+            center: cgmath::vec4(-0.8, 0.8, -1.5, 0.0),
+            radius: 0.6,
+            material: 2,
+            ..scene.hyper_spheres[0]
+        },
+        GpuHyperSphere {
+            center: cgmath::vec4(-0.4, 0.8, -1.5, 0.0),
+            radius: 0.5,
+            material: 2,
+            ..scene.hyper_spheres[0]
+        },
+        GpuHyperSphere {
+            center: cgmath::vec4(1.5, 2.5, 0.0, 0.0),
+            radius: 0.5,
+            material: 3,
+            ..scene.hyper_spheres[0]
+        },
+    ]);
+    scene
+}
+
+fn assert_images_match(expected: &[u8], actual: &[u8], name: &str) {
+    let (mean_difference, outlier_fraction) = common::image_difference(expected, actual);
+    assert!(
+        common::images_match(mean_difference, outlier_fraction),
+        "{name}: mean difference {mean_difference}, outliers {outlier_fraction}"
+    );
+}
+
+#[test]
+fn wavefront_renders_like_the_megakernel() {
+    let Some((device, queue)) = common::request_device() else {
+        eprintln!("skipping, no GPU adapter is available");
+        return;
+    };
+    let render_path = RenderPath::for_device(&device);
+    for (name, spectral, projection) in [
+        ("perspective", false, Projection::Perspective),
+        ("spectral fisheye", true, Projection::Fisheye),
+    ] {
+        let mut scene = glass_scene();
+        scene.frames = 2;
+        scene.camera.spectral = spectral;
+        scene.camera.projection = projection;
+        let expected = common::render(&device, &queue, render_path, &scene);
+        scene.wavefront = true;
+        let actual = common::render(&device, &queue, render_path, &scene);
+        assert_images_match(&expected, &actual, name);
+    }
+}
+
+/// Renders one frame of `scene` at `size` in tiles of `tile_size` on the compute path
+fn render_tiled(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    scene: &common::Scene,
+    size: (usize, usize),
+    tile_size: Option<usize>,
+) -> Vec<u8> {
+    let mut renderer = Renderer::new(device, queue, RenderPath::Compute);
+    renderer.resize(device, queue, size.0, size.1);
+    renderer.set_tile_size(device, tile_size);
+    renderer.set_wavefront(scene.wavefront);
+    renderer
+        .upload_objects(device, queue, &scene.hyper_spheres, &scene.hyper_planes)
+        .unwrap();
+    renderer
+        .upload_materials(device, queue, &scene.materials)
+        .unwrap();
+    renderer
+        .upload_node_programs(device, queue, &scene.node_programs)
+        .unwrap();
+    let (_, tile_count) = renderer.tile_progress();
+    for _ in 0..tile_count {
+        renderer
+            .upload_camera(queue, &scene.camera.to_gpu(0))
+            .unwrap();
+        let mut encoder = device.create_command_encoder(&Default::default());
+        renderer.render(device, &mut encoder, scene.camera.render_options());
+        queue.submit([encoder.finish()]);
+    }
+    assert_eq!(renderer.accumulated_frames(), 1);
+    renderer.read_pixels(device, queue).unwrap()
+}
+
+#[test]
+fn wavefront_traces_every_tile_and_wave() {
+    let Some((device, queue)) = common::request_device() else {
+        eprintln!("skipping, no GPU adapter is available");
+        return;
+    };
+    if RenderPath::for_device(&device) != RenderPath::Compute {
+        eprintln!("skipping, only the compute path has wavefront kernels");
+        return;
+    }
+    let mut scene = glass_scene();
+    scene.camera.sample_count = 8;
+    for (name, size, tile_size) in [
+        ("tiled", (common::WIDTH, common::HEIGHT), Some(20)),
+        // wider than a wave, so the tiles are split into more than one
+        ("waves", (WAVE_SIZE + 40, 12), None),
+        ("tiled waves", (WAVE_SIZE + 40, 12), Some(WAVE_SIZE + 8)),
+    ] {
+        scene.wavefront = false;
+        let expected = render_tiled(&device, &queue, &scene, size, None);
+        scene.wavefront = true;
+        let actual = render_tiled(&device, &queue, &scene, size, tile_size);
+        assert_images_match(&expected, &actual, name);
+    }
+}